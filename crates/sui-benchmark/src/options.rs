@@ -6,6 +6,7 @@ use clap::*;
 use strum_macros::EnumString;
 
 use crate::drivers::Interval;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 #[derive(Parser)]
@@ -184,6 +185,10 @@ pub enum RunSpec {
         // relative weight of expected failure transactions in the benchmark workload
         #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [0])]
         expected_failure: Vec<u32>,
+        // relative weight of transactions from a workload file (see `move_package_workload`)
+        // in the benchmark workload
+        #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [0])]
+        move_package: Vec<u32>,
 
         // --- workload-specific options --- (TODO: use subcommands or similar)
         // 100 for max hotness i.e all requests target
@@ -217,6 +222,10 @@ pub enum RunSpec {
         // See `ExpectedFailureType` enum for `expected_failure_type`
         #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [0])]
         expected_failure_type: Vec<u32>,
+        // workload file describing the Move package, setup steps and transaction templates of the
+        // move package workload. See `workloads/data/move_package/workload.yaml` for an example.
+        #[clap(long, num_args(1..), value_delimiter = ',')]
+        move_package_workload: Option<Vec<PathBuf>>,
 
         // --- generic options ---
         // Target qps
@@ -0,0 +1,39 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/// An example package for the `move_package` benchmark workload, see `workload.yaml`.
+module move_package_bench::bench {
+    use sui::clock::Clock;
+
+    public struct Counter has key {
+        id: UID,
+        value: u64,
+        last_update_ms: u64,
+    }
+
+    public struct Item has key, store {
+        id: UID,
+        value: u64,
+    }
+
+    public fun create_counter(ctx: &mut TxContext) {
+        transfer::share_object(Counter { id: object::new(ctx), value: 0, last_update_ms: 0 })
+    }
+
+    public fun increment(counter: &mut Counter, by: u64, clock: &Clock) {
+        counter.value = counter.value + by;
+        counter.last_update_ms = clock.timestamp_ms();
+    }
+
+    public fun value(counter: &Counter): u64 {
+        counter.value
+    }
+
+    public fun new_item(ctx: &mut TxContext): Item {
+        Item { id: object::new(ctx), value: 0 }
+    }
+
+    public fun bump(item: &mut Item, counter: &Counter) {
+        item.value = item.value + counter.value;
+    }
+}
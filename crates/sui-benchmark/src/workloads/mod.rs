@@ -5,6 +5,7 @@ pub mod adversarial;
 pub mod batch_payment;
 pub mod delegation;
 pub mod expected_failure;
+pub mod move_package;
 pub mod payload;
 pub mod randomness;
 pub mod shared_counter;
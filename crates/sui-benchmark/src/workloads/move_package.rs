@@ -0,0 +1,666 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A workload running transactions against a user-provided Move package, described by a workload
+//! file (see `data/move_package/workload.yaml` for an example). The package is published, then the
+//! file's setup steps create pools of objects, and finally each payload repeatedly submits one of
+//! the file's transaction templates, picked at random according to their weights, with object
+//! arguments drawn from those pools.
+
+use crate::drivers::Interval;
+use crate::in_memory_wallet::InMemoryWallet;
+use crate::system_state_observer::SystemStateObserver;
+use crate::workloads::payload::Payload;
+use crate::workloads::workload::{
+    ExpectedFailureType, Workload, WorkloadBuilder, MAX_BUDGET, MAX_GAS_FOR_TESTING,
+};
+use crate::workloads::{Gas, GasCoinConfig, WorkloadBuilderInfo, WorkloadParams};
+use crate::{ExecutionEffects, ValidatorProxy};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures::future::join_all;
+use move_core_types::identifier::Identifier;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use sui_test_transaction_builder::TestTransactionBuilder;
+use sui_types::base_types::{ObjectID, ObjectRef, SuiAddress};
+use sui_types::crypto::get_key_pair;
+use sui_types::object::Owner;
+use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use sui_types::transaction::{Argument, ObjectArg, Transaction, TransactionData};
+use sui_types::utils::to_sender_signed_transaction;
+use sui_types::{parse_sui_type_tag, SUI_CLOCK_OBJECT_ID, SUI_CLOCK_OBJECT_SHARED_VERSION};
+use tracing::{error, info};
+
+/// Pool that the objects created by the package's `init` functions are added to.
+pub const INIT_POOL: &str = "init";
+
+/// Placeholder for the ID of the published package in type arguments.
+const PACKAGE_PLACEHOLDER: &str = "$package";
+
+/// A workload file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MovePackageWorkloadSpec {
+    /// The Move package to publish, relative to the workload file.
+    pub package: PathBuf,
+    /// Gas budget of each transaction.
+    #[serde(default)]
+    pub gas_budget: Option<u64>,
+    /// Steps run once, in order, before the benchmark starts.
+    #[serde(default)]
+    pub setup: Vec<SetupStep>,
+    /// The transactions submitted during the benchmark.
+    pub transactions: Vec<TransactionTemplate>,
+}
+
+/// A setup step, which runs a transaction and adds the objects it creates to a pool.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SetupStep {
+    /// The pool the created objects are added to.
+    pub pool: String,
+    /// The number of times the step is run, ignored if `per_account` is set.
+    #[serde(default = "default_count")]
+    pub count: u64,
+    /// Run the step once for each account submitting transactions, with that account as its
+    /// sender, so that owned objects it creates can be used by that account's transactions.
+    /// Otherwise, created objects are owned by the account running the setup, so only shared and
+    /// immutable ones are usable.
+    #[serde(default)]
+    pub per_account: bool,
+    /// Only add created objects whose type contains this string to the pool.
+    #[serde(default)]
+    pub object_type: Option<String>,
+    pub commands: Vec<CommandTemplate>,
+}
+
+/// A transaction submitted during the benchmark.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionTemplate {
+    /// Name of the transaction, used to label its metrics.
+    pub name: String,
+    /// Relative weight of the transaction among the workload's transactions.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    pub commands: Vec<CommandTemplate>,
+}
+
+/// A command of a programmable transaction.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandTemplate {
+    MoveCall {
+        /// `<module>::<function>` in the published package.
+        function: String,
+        /// Type arguments, where `$package` stands for the ID of the published package.
+        #[serde(default)]
+        type_args: Vec<String>,
+        #[serde(default)]
+        args: Vec<ArgTemplate>,
+    },
+    /// Transfer the given values to the sender.
+    TransferToSender(Vec<ArgTemplate>),
+}
+
+/// An argument to a command.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgTemplate {
+    /// A random object from `pool` that the sender can use: a shared or immutable object, or one
+    /// owned by the sender. An owned object is used at most once per transaction.
+    Object {
+        pool: String,
+        /// Whether a shared object is taken by mutable reference.
+        #[serde(default = "default_mutable")]
+        mutable: bool,
+    },
+    /// The result of an earlier command in the same transaction.
+    Result(u16),
+    /// One of the results of an earlier command in the same transaction.
+    NestedResult(u16, u16),
+    /// The address of the sender.
+    Sender,
+    /// The shared `Clock` object.
+    Clock,
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    Address(SuiAddress),
+    String(String),
+}
+
+fn default_count() -> u64 {
+    1
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn default_mutable() -> bool {
+    true
+}
+
+impl MovePackageWorkloadSpec {
+    /// Read a workload file, resolving the path of its package.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading workload file {}", path.display()))?;
+        let mut spec: Self = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Parsing workload file {}", path.display()))?;
+        if let Some(dir) = path.parent() {
+            spec.package = dir.join(&spec.package);
+        }
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.transactions.iter().all(|t| t.weight == 0) {
+            bail!("Workload file has no transactions with a non-zero weight");
+        }
+        let mut pools = BTreeSet::from([INIT_POOL]);
+        for step in &self.setup {
+            validate_commands(&step.commands, &pools)
+                .with_context(|| format!("In setup step for pool '{}'", step.pool))?;
+            pools.insert(&step.pool);
+        }
+        for transaction in &self.transactions {
+            validate_commands(&transaction.commands, &pools)
+                .with_context(|| format!("In transaction '{}'", transaction.name))?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_commands(commands: &[CommandTemplate], pools: &BTreeSet<&str>) -> Result<()> {
+    for (i, command) in commands.iter().enumerate() {
+        let args = match command {
+            CommandTemplate::MoveCall { function, args, .. } => {
+                if function.split("::").count() != 2 {
+                    bail!("Function '{function}' is not of the form <module>::<function>");
+                }
+                args
+            }
+            CommandTemplate::TransferToSender(args) => args,
+        };
+        for arg in args {
+            match arg {
+                ArgTemplate::Object { pool, .. } if !pools.contains(pool.as_str()) => {
+                    bail!("Pool '{pool}' is not created by an earlier setup step")
+                }
+                ArgTemplate::Result(j) | ArgTemplate::NestedResult(j, _) if *j as usize >= i => {
+                    bail!("Command {i} uses the result of command {j}, which does not precede it")
+                }
+                _ => (),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The objects created by the setup steps, by pool.
+type Pools = BTreeMap<String, Vec<(ObjectRef, Owner)>>;
+
+/// Build a transaction from `commands`, drawing owned objects and gas from `wallet`.
+fn make_transaction(
+    commands: &[CommandTemplate],
+    package_id: ObjectID,
+    sender: SuiAddress,
+    wallet: &InMemoryWallet,
+    pools: &Pools,
+    gas_budget: u64,
+    gas_price: u64,
+) -> Result<Transaction> {
+    let mut builder = ProgrammableTransactionBuilder::new();
+    let mut used_objects = BTreeSet::new();
+    let mut rng = rand::thread_rng();
+    for command in commands {
+        let arg_templates = match command {
+            CommandTemplate::MoveCall { args, .. } | CommandTemplate::TransferToSender(args) => {
+                args
+            }
+        };
+        let mut args = vec![];
+        for arg in arg_templates {
+            args.push(match arg {
+                ArgTemplate::Object { pool, mutable } => {
+                    let candidates: Vec<_> = pools
+                        .get(pool)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|(obj, owner)| match owner {
+                            Owner::Shared {
+                                initial_shared_version,
+                            } => Some(ObjectArg::SharedObject {
+                                id: obj.0,
+                                initial_shared_version: *initial_shared_version,
+                                mutable: *mutable,
+                            }),
+                            Owner::Immutable => Some(ObjectArg::ImmOrOwnedObject(*obj)),
+                            Owner::AddressOwner(_) if !used_objects.contains(&obj.0) => wallet
+                                .owned_object(&sender, &obj.0)
+                                .map(|obj| ObjectArg::ImmOrOwnedObject(*obj)),
+                            Owner::AddressOwner(_) | Owner::ObjectOwner(_) => None,
+                        })
+                        .collect();
+                    let obj_arg = candidates
+                        .choose(&mut rng)
+                        .ok_or_else(|| anyhow!("No object in pool '{pool}' usable by {sender}"))?;
+                    if let ObjectArg::ImmOrOwnedObject(obj) = obj_arg {
+                        used_objects.insert(obj.0);
+                    }
+                    builder.obj(*obj_arg)?
+                }
+                ArgTemplate::Result(i) => Argument::Result(*i),
+                ArgTemplate::NestedResult(i, j) => Argument::NestedResult(*i, *j),
+                ArgTemplate::Sender => builder.pure(sender)?,
+                ArgTemplate::Clock => builder.obj(ObjectArg::SharedObject {
+                    id: SUI_CLOCK_OBJECT_ID,
+                    initial_shared_version: SUI_CLOCK_OBJECT_SHARED_VERSION,
+                    mutable: false,
+                })?,
+                ArgTemplate::Bool(b) => builder.pure(b)?,
+                ArgTemplate::U8(n) => builder.pure(n)?,
+                ArgTemplate::U16(n) => builder.pure(n)?,
+                ArgTemplate::U32(n) => builder.pure(n)?,
+                ArgTemplate::U64(n) => builder.pure(n)?,
+                ArgTemplate::Address(a) => builder.pure(a)?,
+                ArgTemplate::String(s) => builder.pure(s)?,
+            });
+        }
+
+        match command {
+            CommandTemplate::MoveCall {
+                function,
+                type_args,
+                ..
+            } => {
+                let (module, function) = function.split_once("::").unwrap();
+                let type_args = type_args
+                    .iter()
+                    .map(|t| {
+                        parse_sui_type_tag(
+                            &t.replace(PACKAGE_PLACEHOLDER, &package_id.to_hex_uncompressed()),
+                        )
+                    })
+                    .collect::<Result<_>>()?;
+                builder.programmable_move_call(
+                    package_id,
+                    Identifier::new(module)?,
+                    Identifier::new(function)?,
+                    type_args,
+                    args,
+                );
+            }
+            CommandTemplate::TransferToSender(_) => builder.transfer_args(sender, args),
+        }
+    }
+
+    let account = wallet
+        .account(&sender)
+        .ok_or_else(|| anyhow!("Unknown sender {sender}"))?;
+    let data = TransactionData::new_programmable(
+        sender,
+        vec![account.gas],
+        builder.finish(),
+        gas_budget,
+        gas_price,
+    );
+    Ok(to_sender_signed_transaction(data, account.key()))
+}
+
+#[derive(Debug)]
+pub struct MovePackageTestPayload {
+    package_id: ObjectID,
+    sender: SuiAddress,
+    state: InMemoryWallet,
+    pools: Arc<Pools>,
+    transactions: Arc<Vec<TransactionTemplate>>,
+    weights: WeightedIndex<u32>,
+    /// The transaction template this payload submits next.
+    next: usize,
+    gas_budget: u64,
+    system_state_observer: Arc<SystemStateObserver>,
+}
+
+impl std::fmt::Display for MovePackageTestPayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "move_package:{}", self.transactions[self.next].name)
+    }
+}
+
+impl Payload for MovePackageTestPayload {
+    fn make_new_payload(&mut self, effects: &ExecutionEffects) {
+        if !effects.is_ok() {
+            effects.print_gas_summary();
+            error!(
+                "Move package tx '{}' failed... Status: {:?}",
+                self.transactions[self.next].name,
+                effects.status()
+            );
+        }
+        self.state.update(effects);
+        self.next = self.weights.sample(&mut rand::thread_rng());
+    }
+
+    fn make_transaction(&mut self) -> Transaction {
+        let gas_price = self
+            .system_state_observer
+            .state
+            .borrow()
+            .reference_gas_price;
+        let transaction = &self.transactions[self.next];
+        make_transaction(
+            &transaction.commands,
+            self.package_id,
+            self.sender,
+            &self.state,
+            &self.pools,
+            self.gas_budget,
+            gas_price,
+        )
+        .unwrap_or_else(|e| panic!("Failed to make transaction '{}': {e:?}", transaction.name))
+    }
+
+    fn get_failure_type(&self) -> Option<ExpectedFailureType> {
+        None
+    }
+}
+
+#[derive(Debug)]
+pub struct MovePackageWorkloadBuilder {
+    num_payloads: u64,
+    spec: MovePackageWorkloadSpec,
+}
+
+impl MovePackageWorkloadBuilder {
+    pub fn from(
+        workload_weight: f32,
+        target_qps: u64,
+        num_workers: u64,
+        in_flight_ratio: u64,
+        spec: Option<MovePackageWorkloadSpec>,
+        duration: Interval,
+        group: u32,
+    ) -> Option<WorkloadBuilderInfo> {
+        let target_qps = (workload_weight * target_qps as f32) as u64;
+        let num_workers = (workload_weight * num_workers as f32).ceil() as u64;
+        let max_ops = target_qps * in_flight_ratio;
+        let spec = spec?;
+        if max_ops == 0 || num_workers == 0 {
+            None
+        } else {
+            let workload_params = WorkloadParams {
+                group,
+                target_qps,
+                num_workers,
+                max_ops,
+                duration,
+            };
+            let workload_builder = Box::<dyn WorkloadBuilder<dyn Payload>>::from(Box::new(
+                MovePackageWorkloadBuilder {
+                    num_payloads: max_ops,
+                    spec,
+                },
+            ));
+            let builder_info = WorkloadBuilderInfo {
+                workload_params,
+                workload_builder,
+            };
+            Some(builder_info)
+        }
+    }
+}
+
+#[async_trait]
+impl WorkloadBuilder<dyn Payload> for MovePackageWorkloadBuilder {
+    async fn generate_coin_config_for_init(&self) -> Vec<GasCoinConfig> {
+        // Gas coin for publishing the package and running the setup steps that are not per account
+        let (address, keypair) = get_key_pair();
+        vec![GasCoinConfig {
+            amount: MAX_GAS_FOR_TESTING,
+            address,
+            keypair: Arc::new(keypair),
+        }]
+    }
+
+    async fn generate_coin_config_for_payloads(&self) -> Vec<GasCoinConfig> {
+        let mut configs = vec![];
+        // Gas coins for running workload
+        for _i in 0..self.num_payloads {
+            let (address, keypair) = get_key_pair();
+            configs.push(GasCoinConfig {
+                amount: MAX_GAS_FOR_TESTING,
+                address,
+                keypair: Arc::new(keypair),
+            });
+        }
+        configs
+    }
+
+    async fn build(
+        &self,
+        mut init_gas: Vec<Gas>,
+        payload_gas: Vec<Gas>,
+    ) -> Box<dyn Workload<dyn Payload>> {
+        Box::<dyn Workload<dyn Payload>>::from(Box::new(MovePackageWorkload {
+            package_id: None,
+            pools: Arc::new(Pools::new()),
+            init_gas: init_gas.pop().unwrap(),
+            payload_gas,
+            spec: self.spec.clone(),
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub struct MovePackageWorkload {
+    pub package_id: Option<ObjectID>,
+    pools: Arc<Pools>,
+    pub init_gas: Gas,
+    pub payload_gas: Vec<Gas>,
+    spec: MovePackageWorkloadSpec,
+}
+
+impl MovePackageWorkload {
+    fn gas_budget(&self) -> u64 {
+        self.spec.gas_budget.unwrap_or(MAX_BUDGET)
+    }
+
+    /// The objects created by `effects` that belong in the pool of `step`.
+    async fn created_for_step(
+        step: &SetupStep,
+        effects: &ExecutionEffects,
+        proxy: &Arc<dyn ValidatorProxy + Sync + Send>,
+    ) -> Vec<(ObjectRef, Owner)> {
+        let mut created = vec![];
+        for (obj, owner) in effects.created() {
+            if let Some(object_type) = &step.object_type {
+                let object = proxy.get_object(obj.0).await.unwrap();
+                let matches = object
+                    .data
+                    .struct_tag()
+                    .is_some_and(|tag| tag.to_string().contains(object_type.as_str()));
+                if !matches {
+                    continue;
+                }
+            }
+            created.push((obj, owner));
+        }
+        created
+    }
+}
+
+#[async_trait]
+impl Workload<dyn Payload> for MovePackageWorkload {
+    async fn init(
+        &mut self,
+        proxy: Arc<dyn ValidatorProxy + Sync + Send>,
+        system_state_observer: Arc<SystemStateObserver>,
+    ) {
+        if self.package_id.is_some() {
+            return;
+        }
+        let gas_price = system_state_observer.state.borrow().reference_gas_price;
+        let gas_budget = self.gas_budget();
+        let (gas, sender, keypair) = self.init_gas.clone();
+
+        info!("Publishing Move package {}", self.spec.package.display());
+        let transaction = TestTransactionBuilder::new(sender, gas, gas_price)
+            .publish(self.spec.package.clone())
+            .build_and_sign(keypair.as_ref());
+        let effects = proxy.execute_transaction_block(transaction).await.unwrap();
+        assert!(
+            effects.is_ok(),
+            "Failed to publish Move package: {}",
+            effects.status()
+        );
+        let package_id = effects
+            .created()
+            .iter()
+            .find(|(_, owner)| matches!(owner, Owner::Immutable))
+            .map(|(reference, _)| reference.0)
+            .unwrap();
+        info!("Move package id {:?}", package_id);
+
+        let mut pools = Pools::new();
+        pools.insert(
+            INIT_POOL.to_owned(),
+            effects
+                .created()
+                .into_iter()
+                .filter(|(obj, _)| obj.0 != package_id)
+                .collect(),
+        );
+
+        // Every account involved in the setup, so that their gas and owned objects are kept up to
+        // date as setup transactions are executed.
+        let mut wallet = InMemoryWallet::default();
+        wallet.add_account(sender, keypair, effects.gas_object().0, vec![]);
+        wallet.update(&effects);
+        for (gas, sender, keypair) in &self.payload_gas {
+            wallet.add_account(*sender, keypair.clone(), *gas, vec![]);
+        }
+
+        for step in &self.spec.setup {
+            info!("Running setup step for pool '{}'", step.pool);
+            // Transactions from the same sender have to be executed one after the other, as they
+            // use the same gas coin, so only transactions from different senders are batched.
+            let batches: Vec<Vec<SuiAddress>> = if step.per_account {
+                vec![self
+                    .payload_gas
+                    .iter()
+                    .map(|(_, sender, _)| *sender)
+                    .collect()]
+            } else {
+                (0..step.count).map(|_| vec![sender]).collect()
+            };
+            let mut created = vec![];
+            for batch in batches {
+                let transactions: Vec<_> = batch
+                    .iter()
+                    .map(|sender| {
+                        make_transaction(
+                            &step.commands,
+                            package_id,
+                            *sender,
+                            &wallet,
+                            &pools,
+                            gas_budget,
+                            gas_price,
+                        )
+                        .unwrap_or_else(|e| {
+                            panic!(
+                                "Failed to make setup transaction for '{}': {e:?}",
+                                step.pool
+                            )
+                        })
+                    })
+                    .collect();
+                let all_effects = join_all(transactions.into_iter().map(|transaction| {
+                    let proxy = proxy.clone();
+                    async move { proxy.execute_transaction_block(transaction).await.unwrap() }
+                }))
+                .await;
+                for effects in all_effects {
+                    assert!(
+                        effects.is_ok(),
+                        "Setup step for pool '{}' failed: {}",
+                        step.pool,
+                        effects.status()
+                    );
+                    wallet.update(&effects);
+                    created.extend(Self::created_for_step(step, &effects, &proxy).await);
+                }
+            }
+            pools.entry(step.pool.clone()).or_default().extend(created);
+        }
+
+        // The setup may have used the payloads' gas coins, and modified or deleted the owned
+        // objects it created.
+        for (gas, sender, _) in self.payload_gas.iter_mut() {
+            *gas = *wallet.gas(sender).unwrap();
+        }
+        self.init_gas.0 = *wallet.gas(&sender).unwrap();
+        for objects in pools.values_mut() {
+            objects.retain_mut(|(obj, owner)| match owner {
+                Owner::AddressOwner(address) => match wallet.owned_object(address, &obj.0) {
+                    Some(current) => {
+                        *obj = *current;
+                        true
+                    }
+                    None => false,
+                },
+                Owner::ObjectOwner(_) | Owner::Shared { .. } | Owner::Immutable => true,
+            });
+        }
+        self.pools = Arc::new(pools);
+        self.package_id = Some(package_id);
+    }
+
+    async fn make_test_payloads(
+        &self,
+        _proxy: Arc<dyn ValidatorProxy + Sync + Send>,
+        system_state_observer: Arc<SystemStateObserver>,
+    ) -> Vec<Box<dyn Payload>> {
+        info!("Creating Move package txn payloads, hang tight..");
+        let transactions = Arc::new(self.spec.transactions.clone());
+        let weights = WeightedIndex::new(transactions.iter().map(|t| t.weight)).unwrap();
+        let mut payloads = vec![];
+        for (gas, sender, keypair) in &self.payload_gas {
+            let owned = self
+                .pools
+                .values()
+                .flatten()
+                .filter(|(_, owner)| *owner == Owner::AddressOwner(*sender))
+                .map(|(obj, _)| *obj)
+                .collect();
+            let mut state = InMemoryWallet::default();
+            state.add_account(*sender, keypair.clone(), *gas, owned);
+            payloads.push(Box::new(MovePackageTestPayload {
+                package_id: self.package_id.unwrap(),
+                sender: *sender,
+                state,
+                pools: self.pools.clone(),
+                transactions: transactions.clone(),
+                next: weights.sample(&mut rand::thread_rng()),
+                weights: weights.clone(),
+                gas_budget: self.gas_budget(),
+                system_state_observer: system_state_observer.clone(),
+            }));
+        }
+        payloads
+            .into_iter()
+            .map(|b| Box::<dyn Payload>::from(b))
+            .collect()
+    }
+}
@@ -10,7 +10,7 @@ use crate::workloads::delegation::DelegationWorkloadBuilder;
 use crate::workloads::shared_counter::SharedCounterWorkloadBuilder;
 use crate::workloads::transfer_object::TransferObjectWorkloadBuilder;
 use crate::workloads::{ExpectedFailureType, GroupID, WorkloadBuilderInfo, WorkloadInfo};
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -18,6 +18,7 @@ use tracing::info;
 
 use super::adversarial::{AdversarialPayloadCfg, AdversarialWorkloadBuilder};
 use super::expected_failure::{ExpectedFailurePayloadCfg, ExpectedFailureWorkloadBuilder};
+use super::move_package::{MovePackageWorkloadBuilder, MovePackageWorkloadSpec};
 use super::randomness::RandomnessWorkloadBuilder;
 use super::shared_object_deletion::SharedCounterDeletionWorkloadBuilder;
 
@@ -30,6 +31,7 @@ pub struct WorkloadWeights {
     pub adversarial: u32,
     pub expected_failure: u32,
     pub randomness: u32,
+    pub move_package: u32,
 }
 
 pub struct WorkloadConfig {
@@ -39,6 +41,7 @@ pub struct WorkloadConfig {
     pub weights: WorkloadWeights,
     pub adversarial_cfg: AdversarialPayloadCfg,
     pub expected_failure_cfg: ExpectedFailurePayloadCfg,
+    pub move_package_workload: Option<MovePackageWorkloadSpec>,
     pub batch_payment_size: u32,
     pub shared_counter_hotness_factor: u32,
     pub num_shared_counters: Option<u64>,
@@ -69,12 +72,14 @@ impl WorkloadConfiguration {
                 adversarial,
                 expected_failure,
                 randomness,
+                move_package,
                 shared_counter_hotness_factor,
                 num_shared_counters,
                 shared_counter_max_tip,
                 batch_payment_size,
                 adversarial_cfg,
                 expected_failure_type,
+                move_package_workload,
                 target_qps,
                 num_workers,
                 in_flight_ratio,
@@ -89,6 +94,13 @@ impl WorkloadConfiguration {
                 // benchmark group will run in the same time for the same duration.
                 for workload_group in 0..num_of_benchmark_groups {
                     let i = workload_group as usize;
+                    let move_package_workload = move_package_workload
+                        .as_ref()
+                        .map(|paths| MovePackageWorkloadSpec::from_file(&paths[i]))
+                        .transpose()?;
+                    if move_package[i] > 0 && move_package_workload.is_none() {
+                        bail!("--move-package requires --move-package-workload");
+                    }
                     let config = WorkloadConfig {
                         group: workload_group,
                         num_workers: num_workers[i],
@@ -102,6 +114,7 @@ impl WorkloadConfiguration {
                             adversarial: adversarial[i],
                             expected_failure: expected_failure[i],
                             randomness: randomness[i],
+                            move_package: move_package[i],
                         },
                         adversarial_cfg: AdversarialPayloadCfg::from_str(&adversarial_cfg[i])
                             .unwrap(),
@@ -109,6 +122,7 @@ impl WorkloadConfiguration {
                             failure_type: ExpectedFailureType::try_from(expected_failure_type[i])
                                 .unwrap(),
                         },
+                        move_package_workload,
                         batch_payment_size: batch_payment_size[i],
                         shared_counter_hotness_factor: shared_counter_hotness_factor[i],
                         num_shared_counters: num_shared_counters.as_ref().map(|n| n[i]),
@@ -183,6 +197,7 @@ impl WorkloadConfiguration {
             weights,
             adversarial_cfg,
             expected_failure_cfg,
+            move_package_workload,
             batch_payment_size,
             shared_counter_hotness_factor,
             num_shared_counters,
@@ -200,7 +215,8 @@ impl WorkloadConfiguration {
             + weights.batch_payment
             + weights.adversarial
             + weights.randomness
-            + weights.expected_failure;
+            + weights.expected_failure
+            + weights.move_package;
         let reference_gas_price = system_state_observer.state.borrow().reference_gas_price;
         let mut workload_builders = vec![];
         let shared_workload = SharedCounterWorkloadBuilder::from(
@@ -288,6 +304,16 @@ impl WorkloadConfiguration {
             group,
         );
         workload_builders.push(expected_failure_workload);
+        let move_package_workload = MovePackageWorkloadBuilder::from(
+            weights.move_package as f32 / total_weight as f32,
+            target_qps,
+            num_workers,
+            in_flight_ratio,
+            move_package_workload,
+            duration,
+            group,
+        );
+        workload_builders.push(move_package_workload);
 
         workload_builders
     }
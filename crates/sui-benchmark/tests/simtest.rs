@@ -15,6 +15,7 @@ mod test {
     use sui_benchmark::system_state_observer::SystemStateObserver;
     use sui_benchmark::workloads::adversarial::AdversarialPayloadCfg;
     use sui_benchmark::workloads::expected_failure::ExpectedFailurePayloadCfg;
+    use sui_benchmark::workloads::move_package::MovePackageWorkloadSpec;
     use sui_benchmark::workloads::workload::ExpectedFailureType;
     use sui_benchmark::workloads::workload_configuration::{
         WorkloadConfig, WorkloadConfiguration, WorkloadWeights,
@@ -138,6 +139,20 @@ mod test {
         test_simulated_load(test_cluster, 15).await;
     }
 
+    #[sim_test(config = "test_config()")]
+    async fn test_simulated_load_move_package() {
+        let test_cluster = build_test_cluster(4, 0).await;
+        let mut workload_file = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        workload_file.extend(["src", "workloads", "data", "move_package", "workload.yaml"]);
+        let simulated_load_config = SimulatedLoadConfig {
+            move_package_weight: 1,
+            move_package_workload: Some(workload_file),
+            ..Default::default()
+        };
+        test_simulated_load_with_test_config(test_cluster, 30, simulated_load_config, None, None)
+            .await;
+    }
+
     #[sim_test(config = "test_config()")]
     async fn test_simulated_load_restarts() {
         sui_protocol_config::ProtocolConfig::poison_get_for_min_version();
@@ -942,6 +957,8 @@ mod test {
         shared_counter_max_tip: u64,
         expected_failure_weight: u32,
         expected_failure_config: ExpectedFailurePayloadCfg,
+        move_package_weight: u32,
+        move_package_workload: Option<PathBuf>,
     }
 
     impl Default for SimulatedLoadConfig {
@@ -962,6 +979,8 @@ mod test {
                 expected_failure_config: ExpectedFailurePayloadCfg {
                     failure_type: ExpectedFailureType::try_from(0).unwrap(),
                 },
+                move_package_weight: 0,
+                move_package_workload: None,
             }
         }
     }
@@ -1043,6 +1062,7 @@ mod test {
             randomness: config.randomness_weight,
             adversarial: adversarial_weight,
             expected_failure: config.expected_failure_weight,
+            move_package: config.move_package_weight,
         };
 
         let workload_config = WorkloadConfig {
@@ -1052,6 +1072,9 @@ mod test {
             weights,
             adversarial_cfg,
             expected_failure_cfg: config.expected_failure_config,
+            move_package_workload: config
+                .move_package_workload
+                .map(|path| MovePackageWorkloadSpec::from_file(&path).unwrap()),
             batch_payment_size,
             shared_counter_hotness_factor: config.shared_counter_hotness_factor,
             num_shared_counters: config.num_shared_counters,
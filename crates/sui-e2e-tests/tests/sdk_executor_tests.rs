@@ -0,0 +1,214 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use sui_json_rpc_types::{SuiObjectDataOptions, SuiTransactionBlockEffectsAPI};
+use sui_keys::keystore::{AccountKeystore, InMemKeystore, Keystore};
+use sui_macros::*;
+use sui_sdk::executor::{ExecutorConfig, ParallelExecutor};
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::{get_key_pair, AccountKeyPair, SuiKeyPair};
+use sui_types::object::Owner;
+use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use test_cluster::{TestCluster, TestClusterBuilder};
+
+async fn executor(cluster: &TestCluster, sender: SuiAddress) -> ParallelExecutor {
+    let key = cluster.wallet.config.keystore.get_key(&sender).unwrap();
+    let config = ExecutorConfig {
+        gas_coins_per_sender: 4,
+        ..Default::default()
+    };
+
+    let executor = executor_with_keys(cluster, vec![key.copy()], config).await;
+    executor.add_sender(sender).await.unwrap();
+    executor
+}
+
+/// An executor that can sign for `keys`, without any senders added.
+async fn executor_with_keys(
+    cluster: &TestCluster,
+    keys: Vec<SuiKeyPair>,
+    config: ExecutorConfig,
+) -> ParallelExecutor {
+    let mut keystore = Keystore::from(InMemKeystore::default());
+    for key in keys {
+        keystore.add_key(None, key).unwrap();
+    }
+
+    ParallelExecutor::new(cluster.sui_client().clone(), Arc::new(keystore), config)
+        .await
+        .unwrap()
+}
+
+#[sim_test]
+async fn test_parallel_transfers() {
+    let cluster = TestClusterBuilder::new().build().await;
+    let sender = cluster.get_address_0();
+    let recipient = SuiAddress::random_for_testing_only();
+    let executor = executor(&cluster, sender).await;
+
+    // More transactions than gas coins, so some have to wait for a coin to be returned.
+    let txs = (0..20).map(|_| {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.pay_sui(vec![recipient], vec![1000]).unwrap();
+        (sender, builder.finish())
+    });
+
+    for response in executor.execute_all(txs).await {
+        let response = response.unwrap();
+        assert!(response.effects.unwrap().status().is_ok());
+    }
+
+    let balance = cluster
+        .sui_client()
+        .coin_read_api()
+        .get_balance(recipient, None)
+        .await
+        .unwrap();
+
+    assert_eq!(balance.total_balance, 20_000);
+}
+
+#[sim_test]
+async fn test_stale_owned_object_refs() {
+    let cluster = TestClusterBuilder::new().build().await;
+    let sender = cluster.get_address_0();
+    let executor = executor(&cluster, sender).await;
+
+    // Create an object for the sender to pass around.
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder.pay_sui(vec![sender], vec![1000]).unwrap();
+    let response = executor.execute(sender, builder.finish()).await.unwrap();
+    let effects = response.effects.unwrap();
+    let object_ref = effects
+        .created()
+        .iter()
+        .find(|o| o.owner == Owner::AddressOwner(sender))
+        .unwrap()
+        .reference
+        .to_object_ref();
+
+    // Every transaction refers to the object at its original version, and they all touch the
+    // same object, so they must be queued up and have their inputs updated.
+    let txs = (0..5).map(|_| {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.transfer_object(sender, object_ref).unwrap();
+        (sender, builder.finish())
+    });
+
+    for response in executor.execute_all(txs).await {
+        let response = response.unwrap();
+        assert!(response.effects.unwrap().status().is_ok());
+    }
+
+    let latest = executor.latest_object_ref(&object_ref.0).unwrap();
+    assert!(latest.1 > object_ref.1);
+}
+
+#[sim_test]
+async fn test_contending_owned_objects() {
+    let cluster = TestClusterBuilder::new().build().await;
+    let sender = cluster.get_address_0();
+    let executor = executor(&cluster, sender).await;
+
+    // Create a few objects for the sender to pass around.
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder.pay_sui(vec![sender; 3], vec![1000; 3]).unwrap();
+    let response = executor.execute(sender, builder.finish()).await.unwrap();
+    let objects: Vec<_> = response
+        .effects
+        .unwrap()
+        .created()
+        .iter()
+        .filter(|o| o.owner == Owner::AddressOwner(sender))
+        .map(|o| o.reference.to_object_ref())
+        .collect();
+    assert_eq!(objects.len(), 3);
+
+    // Each transaction touches two of the objects, so every transaction contends with others in
+    // the batch, over different (and overlapping) sets of objects, listed in different orders.
+    let txs = (0..12).map(|i| {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.transfer_object(sender, objects[i % 3]).unwrap();
+        builder
+            .transfer_object(sender, objects[(i + 1) % 3])
+            .unwrap();
+        (sender, builder.finish())
+    });
+
+    for response in executor.execute_all(txs).await {
+        let response = response.unwrap();
+        assert!(response.effects.unwrap().status().is_ok());
+    }
+
+    // Every object was used by 8 of the transactions, one after the other.
+    for object in objects {
+        let latest = executor.latest_object_ref(&object.0).unwrap();
+        let read = cluster
+            .sui_client()
+            .read_api()
+            .get_object_with_options(object.0, SuiObjectDataOptions::new())
+            .await
+            .unwrap()
+            .object_ref_if_exists()
+            .unwrap();
+
+        assert_eq!(latest, read);
+        assert!(latest.1.value() >= object.1.value() + 8);
+    }
+}
+
+#[sim_test]
+async fn test_insufficient_gas() {
+    let cluster = TestClusterBuilder::new().build().await;
+    let recipient = SuiAddress::random_for_testing_only();
+
+    // A sender with only enough SUI for a handful of transactions.
+    let (sender, key): (SuiAddress, AccountKeyPair) = get_key_pair();
+    cluster
+        .transfer_sui_must_exceed(cluster.get_address_0(), sender, 40_000_000)
+        .await;
+
+    let config = ExecutorConfig {
+        gas_coins_per_sender: 2,
+        gas_coin_balance: 10_000_000,
+        min_gas_coin_balance: 5_000_000,
+        gas_budget: 5_000_000,
+        ..Default::default()
+    };
+
+    let executor = executor_with_keys(&cluster, vec![SuiKeyPair::Ed25519(key)], config).await;
+    executor.add_sender(sender).await.unwrap();
+
+    let txs = (0..30).map(|_| {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.pay_sui(vec![recipient], vec![1000]).unwrap();
+        (sender, builder.finish())
+    });
+
+    // Once the sender's gas runs out, the remaining transactions fail instead of waiting for a
+    // gas coin forever.
+    let responses = tokio::time::timeout(Duration::from_secs(120), executor.execute_all(txs))
+        .await
+        .expect("Executor hung after running out of gas");
+
+    let mut succeeded = 0;
+    let mut failed = 0;
+    for response in responses {
+        match response {
+            Ok(response) => {
+                assert!(response.effects.unwrap().status().is_ok());
+                succeeded += 1;
+            }
+            Err(e) => {
+                assert!(e.to_string().contains("Insufficient gas"), "{e:#}");
+                failed += 1;
+            }
+        }
+    }
+
+    assert!(succeeded > 0);
+    assert!(failed > 0);
+}
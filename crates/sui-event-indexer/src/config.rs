@@ -0,0 +1,349 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+use std::env;
+
+use anyhow::{bail, ensure, Context};
+use move_core_types::language_storage::StructTag;
+use serde::{Deserialize, Serialize};
+use sui_types::parse_sui_struct_tag;
+
+/// Postgres truncates identifiers longer than this many bytes.
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// Columns every table gets, identifying the event each row was extracted from.
+pub const METADATA_COLUMNS: [&str; 5] = [
+    "tx_digest",
+    "event_seq",
+    "checkpoint",
+    "timestamp_ms",
+    "sender",
+];
+
+/// config as loaded from `config.yaml`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IndexerConfig {
+    /// Name of the indexer, which prefixes the names of its tasks in the progress store.
+    pub name: String,
+    pub remote_store_url: String,
+    #[serde(default = "default_db_url")]
+    pub db_url: String,
+    /// Only provide this if you use a colocated FN
+    pub checkpoints_path: Option<String>,
+    /// Full node REST API, to fetch the latest checkpoint and the packages defining events.
+    pub rest_url: String,
+    /// Checkpoint to backfill from, e.g. the checkpoint the indexed packages were published in.
+    pub genesis_checkpoint: u64,
+    pub concurrency: u64,
+    pub metric_port: u16,
+    #[serde(default)]
+    pub mode: IndexerMode,
+    /// Split backfills into tasks of this many checkpoints, instead of a single task.
+    pub backfill_task_size: Option<u64>,
+    pub tables: Vec<TableConfig>,
+}
+
+/// Whether to tail the chain, backfill from `genesis_checkpoint`, or both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexerMode {
+    #[default]
+    Full,
+    Live,
+    Backfill,
+}
+
+/// A table filled with the events of one type.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TableConfig {
+    pub name: String,
+    /// Type of the events to index, e.g. `0x2::display::DisplayCreated`. If the type has no type
+    /// parameters, events of any instantiation of it are indexed.
+    pub event_type: String,
+    pub columns: Vec<ColumnConfig>,
+}
+
+/// A column filled with a field of the event.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ColumnConfig {
+    pub name: String,
+    /// Path of the field in the event, with fields of nested structs and indices of vectors
+    /// separated by `.`, e.g. `order.price` or `coins.0`. Missing fields are stored as NULL.
+    pub path: String,
+    #[serde(rename = "type")]
+    pub type_: ColumnType,
+    /// Create an index on this column.
+    #[serde(default)]
+    pub index: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    Text,
+    Bigint,
+    Numeric,
+    Boolean,
+    Jsonb,
+}
+
+impl sui_config::Config for IndexerConfig {}
+
+pub fn default_db_url() -> String {
+    env::var("DB_URL").expect("db_url must be set in config or via the $DB_URL env var")
+}
+
+impl IndexerConfig {
+    /// Checks that table and column names are valid and unique, as well as the names of the
+    /// indices derived from them, and that event types parse.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            !self.name.is_empty() && !self.name.contains(char::is_whitespace),
+            "Indexer name {:?} must be non-empty and contain no whitespace",
+            self.name,
+        );
+        ensure!(!self.tables.is_empty(), "No tables configured");
+        ensure!(
+            self.backfill_task_size != Some(0),
+            "backfill_task_size must be positive"
+        );
+
+        let mut tables = HashSet::new();
+        // Tables and indices share a namespace, and indices that already exist are not created.
+        let mut relations: HashSet<String> = HashSet::from([
+            "progress_store".to_string(),
+            "progress_store_pkey".to_string(),
+        ]);
+        for table in &self.tables {
+            validate_identifier(&table.name)?;
+            ensure!(
+                table.name != "progress_store",
+                "Table name progress_store is reserved"
+            );
+            ensure!(tables.insert(&table.name), "Duplicate table {}", table.name);
+            table.struct_tag()?;
+
+            let mut columns: HashSet<&str> = METADATA_COLUMNS.into_iter().collect();
+            for column in &table.columns {
+                validate_identifier(&column.name)
+                    .with_context(|| format!("In table {}", table.name))?;
+                ensure!(
+                    columns.insert(&column.name),
+                    "Duplicate or reserved column {} in table {}",
+                    column.name,
+                    table.name,
+                );
+                ensure!(
+                    !column.path.is_empty(),
+                    "Empty path for column {} in table {}",
+                    column.name,
+                    table.name,
+                );
+            }
+
+            let indices = ["pkey", "checkpoint"].into_iter().chain(
+                table
+                    .columns
+                    .iter()
+                    .filter(|c| c.index)
+                    .map(|c| c.name.as_str()),
+            );
+            for relation in std::iter::once(table.name.clone())
+                .chain(indices.map(|column| table.index_name(column)))
+            {
+                ensure!(
+                    relation.len() <= MAX_IDENTIFIER_LEN,
+                    "Index name {relation} of table {} is longer than {MAX_IDENTIFIER_LEN} bytes",
+                    table.name,
+                );
+                ensure!(
+                    relations.insert(relation.clone()),
+                    "Name {relation} of table {} clashes with another table or index",
+                    table.name,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TableConfig {
+    pub fn struct_tag(&self) -> anyhow::Result<StructTag> {
+        parse_sui_struct_tag(&self.event_type)
+            .with_context(|| format!("Invalid event type {}", self.event_type))
+    }
+
+    /// The name of the index on `column`, which is also how Postgres names the index of the
+    /// primary key, given `pkey`.
+    pub fn index_name(&self, column: &str) -> String {
+        format!("{}_{column}", self.name)
+    }
+}
+
+/// Whether events of type `event_type` go into a table configured with type `configured`.
+pub fn matches_event_type(configured: &StructTag, event_type: &StructTag) -> bool {
+    configured.address == event_type.address
+        && configured.module == event_type.module
+        && configured.name == event_type.name
+        && (configured.type_params.is_empty() || configured.type_params == event_type.type_params)
+}
+
+impl ColumnType {
+    pub fn sql_type(&self) -> &'static str {
+        match self {
+            ColumnType::Text => "TEXT",
+            ColumnType::Bigint => "BIGINT",
+            ColumnType::Numeric => "NUMERIC",
+            ColumnType::Boolean => "BOOLEAN",
+            ColumnType::Jsonb => "JSONB",
+        }
+    }
+}
+
+/// Names are interpolated into SQL, quoted so that keywords such as `order` can be used, and are
+/// restricted to lower-case identifiers so that they can also be queried unquoted.
+fn validate_identifier(name: &str) -> anyhow::Result<()> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some('a'..='z' | '_'))
+        && chars.all(|c| matches!(c, 'a'..='z' | '0'..='9' | '_'))
+        && name.len() <= MAX_IDENTIFIER_LEN;
+    if !valid {
+        bail!("Invalid name {name:?}: names must be lower-case SQL identifiers");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(tables: &str) -> IndexerConfig {
+        serde_yaml::from_str(&format!(
+            r#"
+name: TestIndexer
+remote_store_url: https://checkpoints.testnet.sui.io
+db_url: postgres://localhost/test
+rest_url: http://localhost:9000/rest
+genesis_checkpoint: 0
+concurrency: 1
+metric_port: 9184
+tables:
+{tables}
+"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let valid = config(
+            r#"
+  - name: display_created
+    event_type: 0x2::display::DisplayCreated
+    columns:
+      - { name: display_id, path: id, type: text, index: true }
+"#,
+        );
+        valid.validate().unwrap();
+        assert_eq!(valid.mode, IndexerMode::Full);
+
+        let reserved = config(
+            r#"
+  - name: display_created
+    event_type: 0x2::display::DisplayCreated
+    columns:
+      - { name: checkpoint, path: id, type: bigint }
+"#,
+        );
+        assert!(reserved.validate().is_err());
+
+        let bad_name = config(
+            r#"
+  - name: "displays; DROP TABLE progress_store"
+    event_type: 0x2::display::DisplayCreated
+    columns: []
+"#,
+        );
+        assert!(bad_name.validate().is_err());
+
+        let bad_type = config(
+            r#"
+  - name: displays
+    event_type: display::DisplayCreated
+    columns: []
+"#,
+        );
+        assert!(bad_type.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_index_names() {
+        // Names are quoted, so keywords are fine.
+        let keywords = config(
+            r#"
+  - name: order
+    event_type: 0x2::display::DisplayCreated
+    columns:
+      - { name: from, path: id, type: text, index: true }
+      - { name: user, path: id, type: text, index: true }
+      - { name: group, path: id, type: text }
+"#,
+        );
+        keywords.validate().unwrap();
+
+        let table = |name: &str, column: &str| {
+            format!(
+                r#"
+  - name: {name}
+    event_type: 0x2::display::DisplayCreated
+    columns:
+      - {{ name: {column}, path: id, type: text, index: true }}
+"#
+            )
+        };
+
+        // The index on the checkpoint column must fit in an identifier too.
+        config(&table(&"t".repeat(52), "c")).validate().unwrap();
+        assert!(config(&table(&"t".repeat(53), "c")).validate().is_err());
+        config(&table(&"t".repeat(30), &"c".repeat(32)))
+            .validate()
+            .unwrap();
+        assert!(config(&table(&"t".repeat(30), &"c".repeat(33)))
+            .validate()
+            .is_err());
+
+        // Derived index names must not clash with other tables or indices.
+        assert!(config(&(table("a", "b_c") + &table("a_b", "c")))
+            .validate()
+            .is_err());
+        assert!(config(&(table("a", "c") + &table("a_c", "d")))
+            .validate()
+            .is_err());
+        assert!(config(&table("a", "pkey")).validate().is_err());
+        assert!(config(&table("progress", "store")).validate().is_err());
+        config(&(table("a", "b") + &table("b", "a")))
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_matches() {
+        let tag = |t: &str| parse_sui_struct_tag(t).unwrap();
+        let event = tag("0x2::coin::CurrencyCreated<0x2::sui::SUI>");
+
+        assert!(matches_event_type(
+            &tag("0x2::coin::CurrencyCreated"),
+            &event
+        ));
+        assert!(matches_event_type(
+            &tag("0x2::coin::CurrencyCreated<0x2::sui::SUI>"),
+            &event
+        ));
+        assert!(!matches_event_type(
+            &tag("0x2::coin::CurrencyCreated<0x2::coin::Coin<0x2::sui::SUI>>"),
+            &event
+        ));
+        assert!(!matches_event_type(&tag("0x2::coin::CoinMetadata"), &event));
+    }
+}
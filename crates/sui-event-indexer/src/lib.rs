@@ -0,0 +1,13 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+pub mod config;
+pub mod mapper;
+pub mod metrics;
+pub mod models;
+pub mod package_store;
+pub mod postgres_manager;
+pub mod schema;
+
+pub mod sui_datasource;
+pub mod sui_event_indexer;
@@ -0,0 +1,107 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::*;
+use mysten_metrics::start_prometheus_server;
+use std::env;
+use std::net::IpAddr;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use sui_config::Config;
+use sui_data_ingestion_core::DataIngestionMetrics;
+use sui_event_indexer::config::{IndexerConfig, IndexerMode};
+use sui_event_indexer::mapper::EventDataMapper;
+use sui_event_indexer::metrics::EventIndexerMetrics;
+use sui_event_indexer::postgres_manager::{create_tables, get_connection_pool};
+use sui_event_indexer::sui_datasource::{EventDecoder, SuiCheckpointDatasource};
+use sui_event_indexer::sui_event_indexer::PgEventPersistent;
+use sui_indexer_builder::indexer_builder::{BackfillStrategy, IndexerBuilder};
+use sui_indexer_builder::progress::{OutOfOrderSaveAfterDurationPolicy, ProgressSavingPolicy};
+use tracing::info;
+
+#[derive(Parser, Clone, Debug)]
+struct Args {
+    /// Path to a yaml config
+    #[clap(long, short)]
+    config_path: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let _guard = telemetry_subscribers::TelemetryConfig::new()
+        .with_env()
+        .init();
+
+    let args = Args::parse();
+
+    // load config
+    let config_path = if let Some(path) = args.config_path {
+        path
+    } else {
+        env::current_dir()
+            .expect("Couldn't get current directory")
+            .join("config.yaml")
+    };
+    let config = IndexerConfig::load(&config_path)?;
+    config.validate()?;
+
+    // Init metrics server
+    let metrics_address =
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), config.metric_port);
+    let registry_service = start_prometheus_server(metrics_address);
+    let registry = registry_service.default_registry();
+    mysten_metrics::init_metrics(&registry);
+    info!("Metrics server started at port {}", config.metric_port);
+
+    let indexer_metrics = EventIndexerMetrics::new(&registry);
+    let ingestion_metrics = DataIngestionMetrics::new(&registry);
+
+    let pool = get_connection_pool(config.db_url.clone()).await;
+    create_tables(&pool, &config.tables).await?;
+
+    let tables = Arc::new(config.tables.clone());
+    let datastore = PgEventPersistent::new(
+        pool,
+        tables.clone(),
+        ProgressSavingPolicy::OutOfOrderSaveAfterDuration(OutOfOrderSaveAfterDurationPolicy::new(
+            tokio::time::Duration::from_secs(30),
+        )),
+    );
+
+    let sui_checkpoint_datasource = SuiCheckpointDatasource::new(
+        &config,
+        Arc::new(EventDecoder::new(&config)?),
+        config
+            .checkpoints_path
+            .clone()
+            .map(|p| p.into())
+            .unwrap_or(tempfile::tempdir()?.into_path()),
+        ingestion_metrics.clone(),
+        indexer_metrics.clone(),
+    );
+
+    let backfill_strategy = match (config.mode, config.backfill_task_size) {
+        (IndexerMode::Live, _) => BackfillStrategy::Disabled,
+        (_, Some(task_size)) => BackfillStrategy::Partitioned { task_size },
+        (_, None) => BackfillStrategy::Simple,
+    };
+
+    let mut builder = IndexerBuilder::new(
+        &config.name,
+        sui_checkpoint_datasource,
+        EventDataMapper {
+            tables,
+            metrics: indexer_metrics.clone(),
+        },
+        datastore,
+    )
+    .with_backfill_strategy(backfill_strategy);
+    if config.mode == IndexerMode::Backfill {
+        builder = builder.disable_live_task();
+    }
+    builder.build().start().await?;
+
+    Ok(())
+}
@@ -0,0 +1,236 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Error};
+use serde_json::Value;
+use sui_indexer_builder::indexer_builder::DataMapper;
+use tracing::warn;
+
+use crate::config::{ColumnType, TableConfig};
+use crate::metrics::EventIndexerMetrics;
+use crate::sui_datasource::IndexedEvent;
+
+/// A row to insert into one of the configured tables.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Row {
+    /// Index of the table in the config.
+    pub table: usize,
+    pub tx_digest: String,
+    pub event_seq: i64,
+    pub checkpoint: i64,
+    pub timestamp_ms: i64,
+    pub sender: String,
+    /// Values of the configured columns, in order, as text to be cast to the columns' types.
+    pub values: Vec<Option<String>>,
+}
+
+/// Extracts the configured columns from events.
+#[derive(Clone)]
+pub struct EventDataMapper {
+    pub tables: Arc<Vec<TableConfig>>,
+    pub metrics: EventIndexerMetrics,
+}
+
+impl DataMapper<IndexedEvent, Row> for EventDataMapper {
+    fn map(&self, event: IndexedEvent) -> Result<Vec<Row>, Error> {
+        event
+            .tables
+            .iter()
+            .map(|&table| {
+                let config = &self.tables[table];
+                // A field that does not fit its column (e.g. a `u64` above `i64::MAX` in a `bigint`
+                // column) is stored as NULL, rather than stopping ingestion at the event.
+                let values = config
+                    .columns
+                    .iter()
+                    .map(|column| {
+                        match column_value(field(&event.fields, &column.path), column.type_) {
+                            Ok(value) => value,
+                            Err(e) => {
+                                warn!(
+                                    "Storing {}.{} of event {}:{} as NULL: {e}",
+                                    config.name, column.name, event.tx_digest, event.event_seq,
+                                );
+                                self.metrics
+                                    .invalid_values
+                                    .with_label_values(&[&config.name, &column.name])
+                                    .inc();
+                                None
+                            }
+                        }
+                    })
+                    .collect();
+                self.metrics
+                    .indexed_events
+                    .with_label_values(&[&config.name])
+                    .inc();
+                Ok(Row {
+                    table,
+                    tx_digest: event.tx_digest.to_string(),
+                    event_seq: event.event_seq as i64,
+                    checkpoint: event.checkpoint as i64,
+                    timestamp_ms: event.timestamp_ms as i64,
+                    sender: event.sender.to_string(),
+                    values,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The field at `path` (field names and vector indices separated by `.`), if there is one.
+fn field<'v>(fields: &'v Value, path: &str) -> Option<&'v Value> {
+    path.split('.')
+        .try_fold(fields, |value, segment| match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(elements) => elements.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+/// The text representation of `value` as a `type_` column, or `None` for NULL.
+fn column_value(value: Option<&Value>, type_: ColumnType) -> anyhow::Result<Option<String>> {
+    let Some(value) = value.filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+
+    Ok(Some(match (type_, value) {
+        (ColumnType::Text, Value::String(s)) => s.clone(),
+        (ColumnType::Text, v) => v.to_string(),
+        (ColumnType::Jsonb, v) => v.to_string(),
+
+        // Integers wider than 32 bits are represented as strings in events.
+        (ColumnType::Bigint, Value::Number(n)) if n.is_i64() => n.to_string(),
+        (ColumnType::Bigint, Value::String(s)) => s
+            .parse::<i64>()
+            .map_err(|e| anyhow!("{s:?} is not a bigint: {e}"))?
+            .to_string(),
+        (ColumnType::Numeric, Value::Number(n)) => n.to_string(),
+        (ColumnType::Numeric, Value::String(s)) => {
+            if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+                bail!("{s:?} is not numeric");
+            }
+            s.clone()
+        }
+
+        (ColumnType::Boolean, Value::Bool(b)) => b.to_string(),
+
+        (type_, v) => bail!("{v} cannot be stored as {}", type_.sql_type()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ColumnConfig;
+    use serde_json::json;
+    use sui_types::base_types::SuiAddress;
+    use sui_types::digests::TransactionDigest;
+
+    #[test]
+    fn test_field() {
+        let fields = json!({
+            "pool_id": "0x1",
+            "order": { "price": "1000", "is_bid": true },
+            "fills": [{ "quantity": "5" }, { "quantity": "7" }],
+        });
+        assert_eq!(field(&fields, "pool_id"), Some(&json!("0x1")));
+        assert_eq!(field(&fields, "order.price"), Some(&json!("1000")));
+        assert_eq!(field(&fields, "fills.1.quantity"), Some(&json!("7")));
+        assert_eq!(field(&fields, "fills.2.quantity"), None);
+        assert_eq!(field(&fields, "fills.first"), None);
+        assert_eq!(field(&fields, "order.price.value"), None);
+        assert_eq!(field(&fields, "missing"), None);
+    }
+
+    #[test]
+    fn test_column_value() {
+        let value = |v: Value, t: ColumnType| column_value(Some(&v), t);
+
+        assert_eq!(
+            value(json!("abc"), ColumnType::Text).unwrap().unwrap(),
+            "abc"
+        );
+        assert_eq!(value(json!(12), ColumnType::Text).unwrap().unwrap(), "12");
+        assert_eq!(value(json!(12), ColumnType::Bigint).unwrap().unwrap(), "12");
+        assert_eq!(
+            value(json!("18446744073709551615"), ColumnType::Numeric)
+                .unwrap()
+                .unwrap(),
+            "18446744073709551615"
+        );
+        assert!(value(json!("18446744073709551615"), ColumnType::Bigint).is_err());
+        assert!(value(json!("0x1"), ColumnType::Numeric).is_err());
+        assert_eq!(
+            value(json!(true), ColumnType::Boolean).unwrap().unwrap(),
+            "true"
+        );
+        assert!(value(json!("true"), ColumnType::Boolean).is_err());
+        assert_eq!(
+            value(json!({ "a": [1] }), ColumnType::Jsonb)
+                .unwrap()
+                .unwrap(),
+            r#"{"a":[1]}"#
+        );
+        assert_eq!(column_value(None, ColumnType::Bigint).unwrap(), None);
+        assert_eq!(
+            column_value(Some(&Value::Null), ColumnType::Text).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_map_invalid_values_to_null() {
+        let column = |name: &str, type_: ColumnType| ColumnConfig {
+            name: name.to_string(),
+            path: name.to_string(),
+            type_,
+            index: false,
+        };
+
+        let mapper = EventDataMapper {
+            tables: Arc::new(vec![TableConfig {
+                name: "orders".to_string(),
+                event_type: "0x42::book::OrderPlaced".to_string(),
+                columns: vec![
+                    column("price", ColumnType::Bigint),
+                    column("quantity", ColumnType::Bigint),
+                    column("is_bid", ColumnType::Boolean),
+                ],
+            }]),
+            metrics: EventIndexerMetrics::new_for_testing(),
+        };
+
+        let rows = mapper
+            .map(IndexedEvent {
+                tables: vec![0],
+                tx_digest: TransactionDigest::random(),
+                event_seq: 0,
+                checkpoint: 1,
+                timestamp_ms: 2,
+                sender: SuiAddress::ZERO,
+                fields: json!({
+                    "price": "18446744073709551615",
+                    "quantity": "10",
+                    "is_bid": "yes",
+                }),
+            })
+            .unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values, vec![None, Some("10".to_string()), None]);
+
+        let invalid = |column: &str| {
+            mapper
+                .metrics
+                .invalid_values
+                .with_label_values(&["orders", column])
+                .get()
+        };
+        assert_eq!(invalid("price"), 1);
+        assert_eq!(invalid("quantity"), 0);
+        assert_eq!(invalid("is_bid"), 1);
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use prometheus::{
+    register_int_counter_vec_with_registry, register_int_gauge_vec_with_registry, IntCounterVec,
+    IntGaugeVec, Registry,
+};
+
+#[derive(Clone, Debug)]
+pub struct EventIndexerMetrics {
+    pub(crate) indexed_events: IntCounterVec,
+    pub(crate) invalid_values: IntCounterVec,
+    pub(crate) backfill_tasks_remaining_checkpoints: IntGaugeVec,
+    pub(crate) tasks_processed_checkpoints: IntCounterVec,
+    pub(crate) inflight_live_tasks: IntGaugeVec,
+}
+
+impl EventIndexerMetrics {
+    pub fn new(registry: &Registry) -> Self {
+        Self {
+            indexed_events: register_int_counter_vec_with_registry!(
+                "event_indexer_indexed_events",
+                "Total number of events extracted into each table",
+                &["table"],
+                registry,
+            )
+            .unwrap(),
+            invalid_values: register_int_counter_vec_with_registry!(
+                "event_indexer_invalid_values",
+                "Total number of event fields stored as NULL because they do not fit their column's type",
+                &["table", "column"],
+                registry,
+            )
+            .unwrap(),
+            backfill_tasks_remaining_checkpoints: register_int_gauge_vec_with_registry!(
+                "event_indexer_backfill_tasks_remaining_checkpoints",
+                "The remaining checkpoints for the currently running backfill task",
+                &["task_name"],
+                registry,
+            )
+            .unwrap(),
+            tasks_processed_checkpoints: register_int_counter_vec_with_registry!(
+                "event_indexer_tasks_processed_checkpoints",
+                "Total processed checkpoints for each task",
+                &["task_name", "task_type"],
+                registry,
+            )
+            .unwrap(),
+            inflight_live_tasks: register_int_gauge_vec_with_registry!(
+                "event_indexer_inflight_live_tasks",
+                "Number of inflight live tasks",
+                &["task_name"],
+                registry,
+            )
+            .unwrap(),
+        }
+    }
+
+    pub fn new_for_testing() -> Self {
+        let registry = Registry::new();
+        Self::new(&registry)
+    }
+}
@@ -0,0 +1,31 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use diesel::data_types::PgTimestamp;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+
+use sui_indexer_builder::{Task, LIVE_TASK_TARGET_CHECKPOINT};
+
+use crate::schema::progress_store;
+
+#[derive(Queryable, Selectable, Insertable, Identifiable, Debug)]
+#[diesel(table_name = progress_store, primary_key(task_name))]
+pub struct ProgressStore {
+    pub task_name: String,
+    pub checkpoint: i64,
+    pub target_checkpoint: i64,
+    pub timestamp: Option<PgTimestamp>,
+}
+
+impl From<ProgressStore> for Task {
+    fn from(value: ProgressStore) -> Self {
+        Self {
+            task_name: value.task_name,
+            start_checkpoint: value.checkpoint as u64,
+            target_checkpoint: value.target_checkpoint as u64,
+            // Ok to unwrap, timestamp is defaulted to now() in database
+            timestamp: value.timestamp.expect("Timestamp not set").0 as u64,
+            is_live_task: value.target_checkpoint == LIVE_TASK_TARGET_CHECKPOINT,
+        }
+    }
+}
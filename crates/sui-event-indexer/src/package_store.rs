@@ -0,0 +1,37 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use move_core_types::account_address::AccountAddress;
+use sui_package_resolver::{error::Error as PackageResolverError, Package, PackageStore, Result};
+use sui_rest_api::Client;
+use sui_types::base_types::ObjectID;
+
+/// Fetches packages from a full node. Event types are resolved through the package that first
+/// defined them, which never changes, so packages can be cached indefinitely (system packages,
+/// which are upgraded in place, only ever gain types).
+pub struct RestPackageStore {
+    client: Client,
+}
+
+impl RestPackageStore {
+    pub fn new(rest_url: &str) -> Self {
+        Self {
+            client: Client::new(rest_url),
+        }
+    }
+}
+
+#[async_trait]
+impl PackageStore for RestPackageStore {
+    async fn fetch(&self, id: AccountAddress) -> Result<Arc<Package>> {
+        let object = self
+            .client
+            .get_object(ObjectID::from(id))
+            .await
+            .map_err(|_| PackageResolverError::PackageNotFound(id))?;
+        Ok(Arc::new(Package::read_from_object(&object)?))
+    }
+}
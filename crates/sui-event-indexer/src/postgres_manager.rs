@@ -0,0 +1,229 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Write;
+
+use diesel::pg::Pg;
+use diesel::sql_types::{Int8, Nullable, Text};
+use diesel_async::pooled_connection::bb8::Pool;
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use tracing::info;
+
+use crate::config::{TableConfig, METADATA_COLUMNS};
+use crate::mapper::Row;
+
+pub(crate) type PgPool =
+    diesel_async::pooled_connection::bb8::Pool<diesel_async::AsyncPgConnection>;
+
+/// Postgres limits the number of bind parameters in a statement.
+const MAX_BINDS: usize = u16::MAX as usize;
+
+pub async fn get_connection_pool(database_url: String) -> PgPool {
+    let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(database_url);
+    Pool::builder()
+        .test_on_check_out(true)
+        .build(manager)
+        .await
+        .expect("Could not build Postgres DB connection pool")
+}
+
+/// Creates the progress store and the configured tables, or adds the columns and indices missing
+/// from existing tables, so that columns can be added to the config of a running indexer. Columns
+/// removed from the config are left in place.
+pub async fn create_tables(pool: &PgPool, tables: &[TableConfig]) -> anyhow::Result<()> {
+    let mut conn = pool.get().await?;
+    for statement in create_statements(tables) {
+        diesel::sql_query(statement).execute(&mut conn).await?;
+    }
+    info!("Created {} event tables", tables.len());
+    Ok(())
+}
+
+/// Inserts the rows for one table, in as few statements as the bind limit allows. Rows that are
+/// already there, e.g. because a task is re-processing checkpoints after a restart, are ignored.
+pub(crate) async fn insert_rows(
+    conn: &mut AsyncPgConnection,
+    table: &TableConfig,
+    rows: &[&Row],
+) -> anyhow::Result<()> {
+    let binds_per_row = METADATA_COLUMNS.len() + table.columns.len();
+    for chunk in rows.chunks(MAX_BINDS / binds_per_row) {
+        let mut query = diesel::sql_query(insert_statement(table, chunk.len())).into_boxed::<Pg>();
+        for row in chunk {
+            query = query
+                .bind::<Text, _>(row.tx_digest.clone())
+                .bind::<Int8, _>(row.event_seq)
+                .bind::<Int8, _>(row.checkpoint)
+                .bind::<Int8, _>(row.timestamp_ms)
+                .bind::<Text, _>(row.sender.clone());
+            for value in &row.values {
+                query = query.bind::<Nullable<Text>, _>(value.clone());
+            }
+        }
+        query.execute(conn).await?;
+    }
+    Ok(())
+}
+
+fn create_statements(tables: &[TableConfig]) -> Vec<String> {
+    let mut statements = vec![r#"
+        CREATE TABLE IF NOT EXISTS progress_store
+        (
+            task_name                   TEXT          PRIMARY KEY,
+            checkpoint                  BIGINT        NOT NULL,
+            target_checkpoint           BIGINT        DEFAULT 9223372036854775807 NOT NULL,
+            timestamp                   TIMESTAMP     DEFAULT now()
+        )"#
+    .to_string()];
+
+    for table in tables {
+        let name = &table.name;
+        statements.push(format!(
+            r#"
+        CREATE TABLE IF NOT EXISTS "{name}"
+        (
+            tx_digest                   TEXT          NOT NULL,
+            event_seq                   BIGINT        NOT NULL,
+            checkpoint                  BIGINT        NOT NULL,
+            timestamp_ms                BIGINT        NOT NULL,
+            sender                      TEXT          NOT NULL,
+            PRIMARY KEY (tx_digest, event_seq)
+        )"#
+        ));
+        statements.push(format!(
+            r#"CREATE INDEX IF NOT EXISTS "{}" ON "{name}" (checkpoint)"#,
+            table.index_name("checkpoint")
+        ));
+        for column in &table.columns {
+            let column_name = &column.name;
+            statements.push(format!(
+                r#"ALTER TABLE "{name}" ADD COLUMN IF NOT EXISTS "{column_name}" {}"#,
+                column.type_.sql_type()
+            ));
+            if column.index {
+                statements.push(format!(
+                    r#"CREATE INDEX IF NOT EXISTS "{}" ON "{name}" ("{column_name}")"#,
+                    table.index_name(column_name)
+                ));
+            }
+        }
+    }
+    statements
+}
+
+/// An insert of `rows` rows into `table`. Values of configured columns are bound as text and cast
+/// to the type of their column.
+fn insert_statement(table: &TableConfig, rows: usize) -> String {
+    let columns: Vec<_> = METADATA_COLUMNS
+        .into_iter()
+        .chain(table.columns.iter().map(|c| c.name.as_str()))
+        .map(|c| format!("\"{c}\""))
+        .collect();
+
+    let mut statement = format!(
+        "INSERT INTO \"{}\" ({}) VALUES ",
+        table.name,
+        columns.join(", ")
+    );
+    let mut bind = 0;
+    for row in 0..rows {
+        statement.push_str(if row == 0 { "(" } else { ", (" });
+        for i in 0..columns.len() {
+            bind += 1;
+            if i > 0 {
+                statement.push_str(", ");
+            }
+            write!(statement, "${bind}").unwrap();
+            if let Some(column) = i
+                .checked_sub(METADATA_COLUMNS.len())
+                .map(|c| &table.columns[c])
+            {
+                write!(statement, "::{}", column.type_.sql_type()).unwrap();
+            }
+        }
+        statement.push(')');
+    }
+    statement.push_str(" ON CONFLICT DO NOTHING");
+    statement
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ColumnConfig, ColumnType};
+
+    fn table() -> TableConfig {
+        TableConfig {
+            name: "swaps".to_string(),
+            event_type: "0x1::pool::Swapped".to_string(),
+            columns: vec![
+                ColumnConfig {
+                    name: "pool".to_string(),
+                    path: "pool_id".to_string(),
+                    type_: ColumnType::Text,
+                    index: true,
+                },
+                ColumnConfig {
+                    name: "amount".to_string(),
+                    path: "amount_in".to_string(),
+                    type_: ColumnType::Numeric,
+                    index: false,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_insert_statement() {
+        assert_eq!(
+            insert_statement(&table(), 2),
+            "INSERT INTO \"swaps\" (\"tx_digest\", \"event_seq\", \"checkpoint\", \"timestamp_ms\", \
+             \"sender\", \"pool\", \"amount\") VALUES ($1, $2, $3, $4, $5, $6::TEXT, $7::NUMERIC), \
+             ($8, $9, $10, $11, $12, $13::TEXT, $14::NUMERIC) \
+             ON CONFLICT DO NOTHING"
+        );
+    }
+
+    #[test]
+    fn test_create_statements() {
+        let statements = create_statements(&[table()]);
+        assert!(statements[0].contains("CREATE TABLE IF NOT EXISTS progress_store"));
+        assert!(statements[1].contains(r#"CREATE TABLE IF NOT EXISTS "swaps""#));
+        assert_eq!(
+            &statements[2..],
+            [
+                r#"CREATE INDEX IF NOT EXISTS "swaps_checkpoint" ON "swaps" (checkpoint)"#,
+                r#"ALTER TABLE "swaps" ADD COLUMN IF NOT EXISTS "pool" TEXT"#,
+                r#"CREATE INDEX IF NOT EXISTS "swaps_pool" ON "swaps" ("pool")"#,
+                r#"ALTER TABLE "swaps" ADD COLUMN IF NOT EXISTS "amount" NUMERIC"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keywords_are_quoted() {
+        let table = TableConfig {
+            name: "order".to_string(),
+            event_type: "0x1::book::Ordered".to_string(),
+            columns: vec![ColumnConfig {
+                name: "from".to_string(),
+                path: "sender".to_string(),
+                type_: ColumnType::Text,
+                index: true,
+            }],
+        };
+        assert_eq!(
+            insert_statement(&table, 1),
+            "INSERT INTO \"order\" (\"tx_digest\", \"event_seq\", \"checkpoint\", \"timestamp_ms\", \
+             \"sender\", \"from\") VALUES ($1, $2, $3, $4, $5, $6::TEXT) ON CONFLICT DO NOTHING"
+        );
+        assert_eq!(
+            &create_statements(&[table])[3..],
+            [
+                r#"ALTER TABLE "order" ADD COLUMN IF NOT EXISTS "from" TEXT"#,
+                r#"CREATE INDEX IF NOT EXISTS "order_from" ON "order" ("from")"#,
+            ]
+        );
+    }
+}
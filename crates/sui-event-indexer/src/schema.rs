@@ -0,0 +1,12 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Event tables are configured at runtime, so only the progress store has a static schema.
+diesel::table! {
+    progress_store (task_name) {
+        task_name -> Text,
+        checkpoint -> Int8,
+        target_checkpoint -> Int8,
+        timestamp -> Nullable<Timestamp>,
+    }
+}
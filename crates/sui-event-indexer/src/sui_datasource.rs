@@ -0,0 +1,263 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Error;
+use async_trait::async_trait;
+use move_core_types::annotated_value::MoveValue;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use mysten_metrics::{metered_channel, spawn_monitored_task};
+use prometheus::IntCounterVec;
+use prometheus::IntGaugeVec;
+use std::path::PathBuf;
+use std::sync::Arc;
+use sui_data_ingestion_core::{
+    DataIngestionMetrics, IndexerExecutor, ProgressStore, ReaderOptions, Worker, WorkerPool,
+};
+use sui_indexer_builder::indexer_builder::{DataSender, Datasource};
+use sui_indexer_builder::Task;
+use sui_json_rpc_types::type_and_fields_from_move_event_data;
+use sui_package_resolver::{PackageStoreWithLruCache, Resolver};
+use sui_rest_api::Client;
+use sui_types::base_types::{SuiAddress, TransactionDigest};
+use sui_types::full_checkpoint_content::CheckpointData as SuiCheckpointData;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use tokio::sync::oneshot;
+use tokio::sync::oneshot::Sender;
+use tokio::task::JoinHandle;
+
+use crate::config::{matches_event_type, IndexerConfig};
+use crate::metrics::EventIndexerMetrics;
+use crate::package_store::RestPackageStore;
+
+/// An event of a configured type, decoded, along with where it was emitted.
+#[derive(Clone, Debug)]
+pub struct IndexedEvent {
+    /// Indices of the tables the event goes into, in the config.
+    pub tables: Vec<usize>,
+    pub tx_digest: TransactionDigest,
+    pub event_seq: u64,
+    pub checkpoint: u64,
+    pub timestamp_ms: u64,
+    pub sender: SuiAddress,
+    /// The fields of the event, in the same format as the JSON-RPC API's `parsedJson`.
+    pub fields: serde_json::Value,
+}
+
+/// Picks the events of configured types out of transactions, and decodes them.
+pub struct EventDecoder {
+    event_types: Vec<StructTag>,
+    resolver: Resolver<PackageStoreWithLruCache<RestPackageStore>>,
+}
+
+impl EventDecoder {
+    pub fn new(config: &IndexerConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            event_types: config
+                .tables
+                .iter()
+                .map(|t| t.struct_tag())
+                .collect::<anyhow::Result<_>>()?,
+            resolver: Resolver::new(PackageStoreWithLruCache::new(RestPackageStore::new(
+                &config.rest_url,
+            ))),
+        })
+    }
+
+    async fn decode(&self, checkpoint: &SuiCheckpointData) -> anyhow::Result<Vec<IndexedEvent>> {
+        let summary = &checkpoint.checkpoint_summary;
+        let mut indexed = vec![];
+        for tx in &checkpoint.transactions {
+            let Some(events) = &tx.events else {
+                continue;
+            };
+            for (event_seq, event) in events.data.iter().enumerate() {
+                let tables: Vec<_> = self
+                    .event_types
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, configured)| matches_event_type(configured, &event.type_))
+                    .map(|(i, _)| i)
+                    .collect();
+                if tables.is_empty() {
+                    continue;
+                }
+                let layout = self
+                    .resolver
+                    .type_layout(TypeTag::Struct(Box::new(event.type_.clone())))
+                    .await?;
+                let move_value = MoveValue::simple_deserialize(&event.contents, &layout)?;
+                let (_, fields) = type_and_fields_from_move_event_data(move_value)?;
+                indexed.push(IndexedEvent {
+                    tables,
+                    tx_digest: *tx.transaction.digest(),
+                    event_seq: event_seq as u64,
+                    checkpoint: summary.sequence_number,
+                    timestamp_ms: summary.timestamp_ms,
+                    sender: event.sender,
+                    fields,
+                });
+            }
+        }
+        Ok(indexed)
+    }
+}
+
+pub struct SuiCheckpointDatasource {
+    remote_store_url: String,
+    rest_client: Client,
+    decoder: Arc<EventDecoder>,
+    concurrency: usize,
+    checkpoint_path: PathBuf,
+    genesis_checkpoint: u64,
+    metrics: DataIngestionMetrics,
+    indexer_metrics: EventIndexerMetrics,
+}
+
+impl SuiCheckpointDatasource {
+    pub fn new(
+        config: &IndexerConfig,
+        decoder: Arc<EventDecoder>,
+        checkpoint_path: PathBuf,
+        metrics: DataIngestionMetrics,
+        indexer_metrics: EventIndexerMetrics,
+    ) -> Self {
+        SuiCheckpointDatasource {
+            remote_store_url: config.remote_store_url.clone(),
+            rest_client: Client::new(&config.rest_url),
+            decoder,
+            concurrency: config.concurrency as usize,
+            checkpoint_path,
+            genesis_checkpoint: config.genesis_checkpoint,
+            metrics,
+            indexer_metrics,
+        }
+    }
+}
+
+#[async_trait]
+impl Datasource<IndexedEvent> for SuiCheckpointDatasource {
+    async fn start_data_retrieval(
+        &self,
+        task: Task,
+        data_sender: DataSender<IndexedEvent>,
+    ) -> Result<JoinHandle<Result<(), Error>>, Error> {
+        let (exit_sender, exit_receiver) = oneshot::channel();
+        let progress_store = PerTaskInMemProgressStore {
+            current_checkpoint: task.start_checkpoint,
+            exit_checkpoint: task.target_checkpoint,
+            exit_sender: Some(exit_sender),
+        };
+        let mut executor = IndexerExecutor::new(progress_store, 1, self.metrics.clone());
+        let worker = IndexerWorker::new(self.decoder.clone(), data_sender);
+        let worker_pool = WorkerPool::new(
+            worker,
+            TransactionDigest::random().to_string(),
+            self.concurrency,
+        );
+        executor.register(worker_pool).await?;
+        let checkpoint_path = self.checkpoint_path.clone();
+        let remote_store_url = self.remote_store_url.clone();
+        Ok(spawn_monitored_task!(async {
+            executor
+                .run(
+                    checkpoint_path,
+                    Some(remote_store_url),
+                    vec![], // optional remote store access options
+                    ReaderOptions::default(),
+                    exit_receiver,
+                )
+                .await?;
+            Ok(())
+        }))
+    }
+
+    async fn get_live_task_starting_checkpoint(&self) -> Result<u64, Error> {
+        Ok(self
+            .rest_client
+            .get_latest_checkpoint()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get latest checkpoint: {:?}", e))?
+            .sequence_number)
+    }
+
+    fn get_genesis_height(&self) -> u64 {
+        self.genesis_checkpoint
+    }
+
+    fn get_tasks_remaining_checkpoints_metric(&self) -> &IntGaugeVec {
+        &self.indexer_metrics.backfill_tasks_remaining_checkpoints
+    }
+
+    fn get_tasks_processed_checkpoints_metric(&self) -> &IntCounterVec {
+        &self.indexer_metrics.tasks_processed_checkpoints
+    }
+
+    fn get_inflight_live_tasks_metrics(&self) -> &IntGaugeVec {
+        &self.indexer_metrics.inflight_live_tasks
+    }
+}
+
+struct PerTaskInMemProgressStore {
+    pub current_checkpoint: u64,
+    pub exit_checkpoint: u64,
+    pub exit_sender: Option<Sender<()>>,
+}
+
+#[async_trait]
+impl ProgressStore for PerTaskInMemProgressStore {
+    async fn load(
+        &mut self,
+        _task_name: String,
+    ) -> Result<CheckpointSequenceNumber, anyhow::Error> {
+        Ok(self.current_checkpoint)
+    }
+
+    async fn save(
+        &mut self,
+        _task_name: String,
+        checkpoint_number: CheckpointSequenceNumber,
+    ) -> anyhow::Result<()> {
+        if checkpoint_number >= self.exit_checkpoint {
+            if let Some(sender) = self.exit_sender.take() {
+                let _ = sender.send(());
+            }
+        }
+        self.current_checkpoint = checkpoint_number;
+        Ok(())
+    }
+}
+
+pub struct IndexerWorker {
+    decoder: Arc<EventDecoder>,
+    data_sender: metered_channel::Sender<(u64, Vec<IndexedEvent>)>,
+}
+
+impl IndexerWorker {
+    pub fn new(
+        decoder: Arc<EventDecoder>,
+        data_sender: metered_channel::Sender<(u64, Vec<IndexedEvent>)>,
+    ) -> Self {
+        Self {
+            decoder,
+            data_sender,
+        }
+    }
+}
+
+#[async_trait]
+impl Worker for IndexerWorker {
+    type Result = ();
+
+    async fn process_checkpoint(&self, checkpoint: &SuiCheckpointData) -> anyhow::Result<()> {
+        tracing::trace!(
+            "Received checkpoint [{}] {}: {}",
+            checkpoint.checkpoint_summary.epoch,
+            checkpoint.checkpoint_summary.sequence_number,
+            checkpoint.transactions.len(),
+        );
+        let checkpoint_num = checkpoint.checkpoint_summary.sequence_number;
+        // Checkpoints without matching events are still sent, for progress to be saved.
+        let events = self.decoder.decode(checkpoint).await?;
+        Ok(self.data_sender.send((checkpoint_num, events)).await?)
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Error};
+use async_trait::async_trait;
+use diesel::dsl::now;
+use diesel::{ExpressionMethods, TextExpressionMethods};
+use diesel::{OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncConnection;
+use diesel_async::RunQueryDsl;
+use sui_indexer_builder::indexer_builder::{IndexerProgressStore, Persistent};
+use sui_indexer_builder::progress::ProgressSavingPolicy;
+use sui_indexer_builder::{Task, Tasks, LIVE_TASK_TARGET_CHECKPOINT};
+
+use crate::config::TableConfig;
+use crate::mapper::Row;
+use crate::postgres_manager::{insert_rows, PgPool};
+use crate::schema::progress_store::{columns, dsl};
+use crate::{models, schema};
+
+/// Persistent layer impl
+#[derive(Clone)]
+pub struct PgEventPersistent {
+    pub pool: PgPool,
+    tables: Arc<Vec<TableConfig>>,
+    save_progress_policy: ProgressSavingPolicy,
+}
+
+impl PgEventPersistent {
+    pub fn new(
+        pool: PgPool,
+        tables: Arc<Vec<TableConfig>>,
+        save_progress_policy: ProgressSavingPolicy,
+    ) -> Self {
+        Self {
+            pool,
+            tables,
+            save_progress_policy,
+        }
+    }
+}
+
+#[async_trait]
+impl Persistent<Row> for PgEventPersistent {
+    async fn write(&self, data: Vec<Row>) -> Result<(), Error> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let mut by_table: BTreeMap<usize, Vec<&Row>> = BTreeMap::new();
+        for row in &data {
+            by_table.entry(row.table).or_default().push(row);
+        }
+        let tables = &self.tables;
+        let connection = &mut self.pool.get().await?;
+        connection
+            .transaction(|conn| {
+                async move {
+                    for (table, rows) in by_table {
+                        insert_rows(conn, &tables[table], &rows).await?;
+                    }
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl IndexerProgressStore for PgEventPersistent {
+    async fn load_progress(&self, task_name: String) -> anyhow::Result<u64> {
+        let mut conn = self.pool.get().await?;
+        let cp: Option<models::ProgressStore> = dsl::progress_store
+            .find(&task_name)
+            .select(models::ProgressStore::as_select())
+            .first(&mut conn)
+            .await
+            .optional()?;
+        Ok(cp
+            .ok_or(anyhow!("Cannot found progress for task {task_name}"))?
+            .checkpoint as u64)
+    }
+
+    async fn save_progress(
+        &mut self,
+        task: &Task,
+        checkpoint_numbers: &[u64],
+    ) -> anyhow::Result<Option<u64>> {
+        if checkpoint_numbers.is_empty() {
+            return Ok(None);
+        }
+        let task_name = task.task_name.clone();
+        if let Some(checkpoint_to_save) = self
+            .save_progress_policy
+            .cache_progress(task, checkpoint_numbers)
+        {
+            let mut conn = self.pool.get().await?;
+            diesel::insert_into(schema::progress_store::table)
+                .values(&models::ProgressStore {
+                    task_name,
+                    checkpoint: checkpoint_to_save as i64,
+                    // Target checkpoint and timestamp will only be written for new entries
+                    target_checkpoint: i64::MAX,
+                    // Timestamp is defaulted to current time in DB if None
+                    timestamp: None,
+                })
+                .on_conflict(dsl::task_name)
+                .do_update()
+                .set((
+                    columns::checkpoint.eq(checkpoint_to_save as i64),
+                    columns::timestamp.eq(now),
+                ))
+                .execute(&mut conn)
+                .await?;
+            return Ok(Some(checkpoint_to_save));
+        }
+        Ok(None)
+    }
+
+    async fn get_ongoing_tasks(&self, prefix: &str) -> Result<Tasks, anyhow::Error> {
+        let mut conn = self.pool.get().await?;
+        // get all unfinished tasks
+        let cp: Vec<models::ProgressStore> = dsl::progress_store
+            .filter(columns::task_name.like(format!("{prefix} - %")))
+            .filter(columns::checkpoint.lt(columns::target_checkpoint))
+            .order_by(columns::target_checkpoint.desc())
+            .load(&mut conn)
+            .await?;
+        let tasks = cp.into_iter().map(|d| d.into()).collect();
+        Ok(Tasks::new(tasks)?)
+    }
+
+    async fn get_largest_backfill_task_target_checkpoint(
+        &self,
+        prefix: &str,
+    ) -> Result<Option<u64>, Error> {
+        let mut conn = self.pool.get().await?;
+        let cp: Option<i64> = dsl::progress_store
+            .select(columns::target_checkpoint)
+            .filter(columns::task_name.like(format!("{prefix} - %")))
+            .filter(columns::target_checkpoint.ne(i64::MAX))
+            .order_by(columns::target_checkpoint.desc())
+            .first::<i64>(&mut conn)
+            .await
+            .optional()?;
+        Ok(cp.map(|c| c as u64))
+    }
+
+    async fn register_task(
+        &mut self,
+        task_name: String,
+        checkpoint: u64,
+        target_checkpoint: u64,
+    ) -> Result<(), anyhow::Error> {
+        let mut conn = self.pool.get().await?;
+        diesel::insert_into(schema::progress_store::table)
+            .values(models::ProgressStore {
+                task_name,
+                checkpoint: checkpoint as i64,
+                target_checkpoint: target_checkpoint as i64,
+                // Timestamp is defaulted to current time in DB if None
+                timestamp: None,
+            })
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Register a live task to progress store with a start checkpoint.
+    async fn register_live_task(
+        &mut self,
+        task_name: String,
+        start_checkpoint: u64,
+    ) -> Result<(), anyhow::Error> {
+        let mut conn = self.pool.get().await?;
+        diesel::insert_into(schema::progress_store::table)
+            .values(models::ProgressStore {
+                task_name,
+                checkpoint: start_checkpoint as i64,
+                target_checkpoint: LIVE_TASK_TARGET_CHECKPOINT,
+                // Timestamp is defaulted to current time in DB if None
+                timestamp: None,
+            })
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_task(&mut self, task: Task) -> Result<(), anyhow::Error> {
+        let mut conn = self.pool.get().await?;
+        diesel::update(dsl::progress_store.filter(columns::task_name.eq(task.task_name)))
+            .set((
+                columns::checkpoint.eq(task.start_checkpoint as i64),
+                columns::target_checkpoint.eq(task.target_checkpoint as i64),
+                columns::timestamp.eq(now),
+            ))
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS hist_obj_types;
@@ -0,0 +1,36 @@
+-- Full history of `sum_obj_types`, to support point-in-time queries for the
+-- owner and type of objects at any checkpoint.
+--
+-- It contains the same columns as `wal_obj_types`, but unlike the
+-- write-ahead log, which only needs to cover the checkpoints between the
+-- summary table and the tip of the chain, it is never pruned. Each row records
+-- the state of an object as of the version it was updated to, deleted or
+-- wrapped at (in which case all the fields except the ID, version and
+-- checkpoint are NULL).
+--
+-- The state of an object at some checkpoint `S` is found in its row with the
+-- highest version from `cp_sequence_number <= S`, and the set of objects owned
+-- by an address at `S` is found by filtering the latest rows of the objects
+-- it owned at any point up to `S` to the ones it still owns.
+--
+-- See `up.sql` for the original `sum_obj_types` table for documentation on
+-- columns.
+CREATE TABLE IF NOT EXISTS hist_obj_types
+(
+    object_id                   BYTEA         NOT NULL,
+    object_version              BIGINT        NOT NULL,
+    owner_kind                  SMALLINT,
+    owner_id                    BYTEA,
+    package                     BYTEA,
+    module                      TEXT,
+    name                        TEXT,
+    instantiation               BYTEA,
+    cp_sequence_number          BIGINT        NOT NULL,
+    PRIMARY KEY (object_id, object_version)
+);
+
+CREATE INDEX IF NOT EXISTS hist_obj_types_owner
+ON hist_obj_types (owner_kind, owner_id, object_id, cp_sequence_number);
+
+CREATE INDEX IF NOT EXISTS hist_obj_types_inst
+ON hist_obj_types (package, module, name, instantiation, object_id, cp_sequence_number);
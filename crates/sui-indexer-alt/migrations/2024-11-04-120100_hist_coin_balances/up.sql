@@ -0,0 +1,21 @@
+-- Full history of `sum_coin_balances`, to support point-in-time queries for
+-- the balances of addresses at any checkpoint.
+--
+-- It contains the same columns as `wal_coin_balances`, but is never pruned
+-- (see `hist_obj_types` for how the history is queried).
+--
+-- See `up.sql` for the original `sum_coin_balances` table for documentation on
+-- columns.
+CREATE TABLE IF NOT EXISTS hist_coin_balances
+(
+    object_id                   BYTEA         NOT NULL,
+    object_version              BIGINT        NOT NULL,
+    owner_id                    BYTEA,
+    coin_type                   BYTEA,
+    coin_balance                BIGINT,
+    cp_sequence_number          BIGINT        NOT NULL,
+    PRIMARY KEY (object_id, object_version)
+);
+
+CREATE INDEX IF NOT EXISTS hist_coin_balances_owner_type
+ON hist_coin_balances (owner_id, coin_type, object_id, cp_sequence_number);
@@ -0,0 +1,10 @@
+-- Maps checkpoints to their timestamps, to translate points in time (such as
+-- the end of a day) into the checkpoint to run point-in-time queries at.
+CREATE TABLE IF NOT EXISTS cp_timestamps
+(
+    cp_sequence_number          BIGINT        PRIMARY KEY,
+    timestamp_ms                BIGINT        NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS cp_timestamps_timestamp_ms
+ON cp_timestamps (timestamp_ms);
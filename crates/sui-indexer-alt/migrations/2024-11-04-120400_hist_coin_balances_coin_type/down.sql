@@ -0,0 +1 @@
+DROP INDEX IF EXISTS hist_coin_balances_coin_type;
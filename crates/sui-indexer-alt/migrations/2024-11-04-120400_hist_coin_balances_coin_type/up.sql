@@ -0,0 +1,5 @@
+-- Supports finding the top holders of a coin type at a checkpoint, which
+-- collects the IDs of every coin of that type up to the checkpoint, before
+-- looking up the latest version of each coin by its primary key.
+CREATE INDEX IF NOT EXISTS hist_coin_balances_coin_type
+ON hist_coin_balances (coin_type, object_id, cp_sequence_number);
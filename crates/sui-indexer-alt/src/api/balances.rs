@@ -0,0 +1,952 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use anyhow::{anyhow, Context};
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::{Days, NaiveDate};
+use diesel::{
+    sql_types::{Bytea, Int8},
+    QueryableByName,
+};
+use diesel_async::RunQueryDsl;
+use serde::{Deserialize, Serialize};
+use sui_types::{
+    base_types::SuiAddress, digests::TransactionDigest, object::Owner, parse_sui_type_tag, TypeTag,
+};
+
+use crate::{
+    handlers::{
+        kv_transactions::KvTransactions, tx_affected_addresses::TxAffectedAddress,
+        tx_balance_changes::TxBalanceChanges, tx_digests::TxDigests,
+    },
+    history::HistoryReader,
+    models::{transactions::BalanceChange, watermarks::CommitterWatermark},
+    pipeline::Processor,
+};
+
+use super::{ApiError, ApiState};
+
+/// Transactions that touched an address, most recent first, with their balance changes.
+const BALANCE_CHANGES_QUERY: &str = r#"
+    SELECT
+        a.tx_sequence_number,
+        d.tx_digest,
+        k.cp_sequence_number,
+        k.timestamp_ms,
+        b.balance_changes
+    FROM tx_affected_addresses a
+    JOIN tx_balance_changes b ON b.tx_sequence_number = a.tx_sequence_number
+    JOIN tx_digests d ON d.tx_sequence_number = a.tx_sequence_number
+    JOIN kv_transactions k ON k.tx_digest = d.tx_digest
+    WHERE a.affected = $1 AND a.tx_sequence_number < $2
+    ORDER BY a.tx_sequence_number DESC
+    LIMIT $3
+"#;
+
+/// Transactions to fetch at a time while looking for balance changes.
+const SCAN_BATCH: usize = 200;
+
+/// Transactions to look through for a single page of balance changes, so that pages for
+/// addresses that rarely hold the requested coin type stay cheap.
+const MAX_SCAN: usize = 1000;
+
+const DEFAULT_CHANGES_LIMIT: usize = 50;
+const DEFAULT_HOLDERS_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 1000;
+
+/// Days that daily balances can be requested for at a time.
+const MAX_DAYS: u64 = 31;
+
+/// Routes of the balance API:
+/// - `GET /addresses/:address/balance-changes?coinType&before&limit`: changes to the address's
+///   balances, most recent first. Pages are fetched by passing the previous page's `nextCursor`
+///   as `before`.
+/// - `GET /addresses/:address/daily-balances?coinType&from&to`: the address's balances at the
+///   start and end of each (UTC) day between `from` and `to`, inclusive.
+/// - `GET /coins/:coin_type/top-holders?checkpoint&limit`: the addresses holding the most of a
+///   coin type at a checkpoint (the latest indexed checkpoint by default).
+pub(super) fn router() -> Router<ApiState> {
+    Router::new()
+        .route("/addresses/:address/balance-changes", get(balance_changes))
+        .route("/addresses/:address/daily-balances", get(daily_balances))
+        .route("/coins/:coin_type/top-holders", get(top_holders))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BalanceChangesParams {
+    coin_type: Option<String>,
+    before: Option<u64>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BalanceChangesResponse {
+    changes: Vec<BalanceChangeEntry>,
+    /// Pass as `before` to fetch the next page, absent if there are no more changes.
+    next_cursor: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BalanceChangeEntry {
+    tx_digest: String,
+    tx_sequence_number: u64,
+    checkpoint: u64,
+    timestamp_ms: u64,
+    coin_type: String,
+    /// Signed, as a string because it may not fit in a JSON number.
+    amount: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DailyBalancesParams {
+    coin_type: Option<String>,
+    from: NaiveDate,
+    to: NaiveDate,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DailyBalancesResponse {
+    /// Only includes days that have ended and been fully indexed.
+    days: Vec<DailyBalance>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DailyBalance {
+    date: NaiveDate,
+    /// Balances by coin type at the start of the day.
+    opening: BTreeMap<String, String>,
+    /// Balances by coin type at the end of the day.
+    closing: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TopHoldersParams {
+    checkpoint: Option<u64>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TopHoldersResponse {
+    checkpoint: u64,
+    holders: Vec<Holder>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Holder {
+    address: SuiAddress,
+    balance: String,
+}
+
+#[derive(QueryableByName)]
+struct StoredAddressTx {
+    #[diesel(sql_type = Int8)]
+    tx_sequence_number: i64,
+    #[diesel(sql_type = Bytea)]
+    tx_digest: Vec<u8>,
+    #[diesel(sql_type = Int8)]
+    cp_sequence_number: i64,
+    #[diesel(sql_type = Int8)]
+    timestamp_ms: i64,
+    #[diesel(sql_type = Bytea)]
+    balance_changes: Vec<u8>,
+}
+
+async fn balance_changes(
+    State(state): State<ApiState>,
+    Path(address): Path<String>,
+    Query(params): Query<BalanceChangesParams>,
+) -> Result<Json<BalanceChangesResponse>, ApiError> {
+    let address = parse_address(&address)?;
+    let coin_type = params
+        .coin_type
+        .as_deref()
+        .map(|t| parse_coin_type(t).map(|t| t.to_canonical_string(/* with_prefix */ true)))
+        .transpose()?;
+    let limit = parse_limit(params.limit, DEFAULT_CHANGES_LIMIT)?;
+
+    let mut conn = state.db.connect().await.context("Failed to connect")?;
+
+    // Only serve transactions that all the tables being joined have committed.
+    let mut tx_hi = u64::MAX;
+    for pipeline in [
+        TxAffectedAddress::NAME,
+        TxBalanceChanges::NAME,
+        TxDigests::NAME,
+        KvTransactions::NAME,
+    ] {
+        let watermark = CommitterWatermark::get(&mut conn, pipeline)
+            .await
+            .with_context(|| format!("Failed to get watermark for {pipeline}"))?;
+        tx_hi = tx_hi.min(watermark.map_or(0, |w| w.tx_hi as u64));
+    }
+
+    let mut before = params.before.unwrap_or(u64::MAX).min(tx_hi);
+    let mut changes: Vec<BalanceChangeEntry> = vec![];
+    let mut scanned = 0;
+
+    while scanned < MAX_SCAN {
+        let txs: Vec<StoredAddressTx> = diesel::sql_query(BALANCE_CHANGES_QUERY)
+            .bind::<Bytea, _>(address.to_vec())
+            .bind::<Int8, _>(before as i64)
+            .bind::<Int8, _>(SCAN_BATCH as i64)
+            .load(&mut conn)
+            .await
+            .context("Failed to fetch balance changes")?;
+
+        let exhausted = txs.len() < SCAN_BATCH;
+        for tx in txs {
+            let tx_sequence_number = tx.tx_sequence_number as u64;
+            let entries = address_changes(address, coin_type.as_deref(), tx)?;
+
+            // Pages end at transaction boundaries, so a transaction's changes are never split.
+            let full = changes.len() >= limit || changes.len() + entries.len() > limit;
+            if !changes.is_empty() && full {
+                return Ok(Json(BalanceChangesResponse {
+                    changes,
+                    next_cursor: Some(before),
+                }));
+            }
+
+            before = tx_sequence_number;
+            changes.extend(entries);
+            scanned += 1;
+        }
+
+        if exhausted {
+            return Ok(Json(BalanceChangesResponse {
+                changes,
+                next_cursor: None,
+            }));
+        }
+    }
+
+    Ok(Json(BalanceChangesResponse {
+        changes,
+        next_cursor: Some(before),
+    }))
+}
+
+async fn daily_balances(
+    State(state): State<ApiState>,
+    Path(address): Path<String>,
+    Query(params): Query<DailyBalancesParams>,
+) -> Result<Json<DailyBalancesResponse>, ApiError> {
+    let address = parse_address(&address)?;
+    let coin_type = params
+        .coin_type
+        .as_deref()
+        .map(parse_coin_type)
+        .transpose()?;
+
+    if params.from > params.to {
+        return Err(ApiError::BadRequest(anyhow!(
+            "from ({}) is after to ({})",
+            params.from,
+            params.to
+        )));
+    }
+
+    let num_days = (params.to - params.from).num_days() as u64 + 1;
+    if num_days > MAX_DAYS {
+        return Err(ApiError::BadRequest(anyhow!(
+            "Cannot request more than {MAX_DAYS} days at a time, got {num_days}"
+        )));
+    }
+
+    let history = &state.history;
+    let Some(timestamp_ms_hi) = history.timestamp_ms_hi_inclusive().await? else {
+        return Ok(Json(DailyBalancesResponse { days: vec![] }));
+    };
+
+    // Each day's closing balance is the next day's opening balance, so there is one snapshot per
+    // day boundary, taken just before it.
+    let mut days = vec![];
+    let mut opening = balances_before(history, address, params.from, timestamp_ms_hi).await?;
+    for date in params.from.iter_days().take(num_days as usize) {
+        let Some(start) = opening else {
+            break;
+        };
+
+        let next = date
+            .checked_add_days(Days::new(1))
+            .context("Date out of range")?;
+        let closing = balances_before(history, address, next, timestamp_ms_hi).await?;
+        let Some(end) = &closing else {
+            break;
+        };
+
+        days.push(DailyBalance {
+            date,
+            opening: balances_json(&start, coin_type.as_ref()),
+            closing: balances_json(end, coin_type.as_ref()),
+        });
+
+        opening = closing;
+    }
+
+    Ok(Json(DailyBalancesResponse { days }))
+}
+
+async fn top_holders(
+    State(state): State<ApiState>,
+    Path(coin_type): Path<String>,
+    Query(params): Query<TopHoldersParams>,
+) -> Result<Json<TopHoldersResponse>, ApiError> {
+    let coin_type = parse_coin_type(&coin_type)?;
+    let limit = parse_limit(params.limit, DEFAULT_HOLDERS_LIMIT)?;
+
+    let Some(checkpoint_hi) = state.history.checkpoint_hi_inclusive().await? else {
+        return Err(ApiError::BadRequest(anyhow!("No checkpoints indexed yet")));
+    };
+
+    let checkpoint = params.checkpoint.unwrap_or(checkpoint_hi);
+    if checkpoint > checkpoint_hi {
+        return Err(ApiError::BadRequest(anyhow!(
+            "Checkpoint {checkpoint} is not indexed yet, latest is {checkpoint_hi}"
+        )));
+    }
+
+    let holders = state
+        .history
+        .top_holders_at(&coin_type, checkpoint, limit)
+        .await?
+        .into_iter()
+        .map(|(address, balance)| Holder {
+            address,
+            balance: balance.to_string(),
+        })
+        .collect();
+
+    Ok(Json(TopHoldersResponse {
+        checkpoint,
+        holders,
+    }))
+}
+
+/// The changes to `address`'s balances in `tx`, optionally only of coin type `coin_type`.
+fn address_changes(
+    address: SuiAddress,
+    coin_type: Option<&str>,
+    tx: StoredAddressTx,
+) -> Result<Vec<BalanceChangeEntry>, ApiError> {
+    let balance_changes: Vec<BalanceChange> =
+        bcs::from_bytes(&tx.balance_changes).with_context(|| {
+            format!(
+                "Failed to deserialize balance changes for transaction {}",
+                tx.tx_sequence_number
+            )
+        })?;
+
+    let tx_digest = TransactionDigest::try_from(tx.tx_digest.as_slice())
+        .map_err(|e| anyhow!("Bad digest for transaction {}: {e}", tx.tx_sequence_number))?;
+
+    Ok(balance_changes
+        .into_iter()
+        .filter_map(|change| {
+            let BalanceChange::V1 {
+                owner,
+                coin_type: change_type,
+                amount,
+            } = change;
+
+            if owner != Owner::AddressOwner(address) {
+                return None;
+            }
+
+            if coin_type.is_some_and(|t| t != change_type) {
+                return None;
+            }
+
+            Some(BalanceChangeEntry {
+                tx_digest: tx_digest.to_string(),
+                tx_sequence_number: tx.tx_sequence_number as u64,
+                checkpoint: tx.cp_sequence_number as u64,
+                timestamp_ms: tx.timestamp_ms as u64,
+                coin_type: change_type,
+                amount: amount.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// `address`'s balances just before the start of `date`, or `None` if that point in time has not
+/// been indexed yet.
+async fn balances_before(
+    history: &HistoryReader,
+    address: SuiAddress,
+    date: NaiveDate,
+    timestamp_ms_hi: u64,
+) -> Result<Option<BTreeMap<TypeTag, u128>>, ApiError> {
+    let start_ms = date
+        .and_time(Default::default())
+        .and_utc()
+        .timestamp_millis();
+    let Ok(timestamp_ms) = u64::try_from(start_ms - 1) else {
+        return Ok(Some(BTreeMap::new()));
+    };
+
+    if timestamp_ms > timestamp_ms_hi {
+        return Ok(None);
+    }
+
+    let Some(checkpoint) = history.checkpoint_at(timestamp_ms).await? else {
+        return Ok(Some(BTreeMap::new()));
+    };
+
+    if history
+        .checkpoint_hi_inclusive()
+        .await?
+        .map_or(true, |hi| checkpoint > hi)
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(history.balances_at(address, checkpoint).await?))
+}
+
+fn balances_json(
+    balances: &BTreeMap<TypeTag, u128>,
+    coin_type: Option<&TypeTag>,
+) -> BTreeMap<String, String> {
+    let json =
+        |(t, b): (&TypeTag, &u128)| (t.to_canonical_string(/* with_prefix */ true), b.to_string());
+
+    match coin_type {
+        Some(t) => [(t, balances.get(t).unwrap_or(&0))]
+            .into_iter()
+            .map(json)
+            .collect(),
+        None => balances.iter().map(json).collect(),
+    }
+}
+
+fn parse_address(address: &str) -> Result<SuiAddress, ApiError> {
+    SuiAddress::from_str(address)
+        .map_err(|e| ApiError::BadRequest(anyhow!("Invalid address {address:?}: {e}")))
+}
+
+fn parse_coin_type(coin_type: &str) -> Result<TypeTag, ApiError> {
+    parse_sui_type_tag(coin_type)
+        .map_err(|e| ApiError::BadRequest(anyhow!("Invalid coin type {coin_type:?}: {e}")))
+}
+
+fn parse_limit(limit: Option<usize>, default: usize) -> Result<usize, ApiError> {
+    match limit {
+        None => Ok(default),
+        Some(limit @ 1..=MAX_LIMIT) => Ok(limit),
+        Some(limit) => Err(ApiError::BadRequest(anyhow!(
+            "limit must be between 1 and {MAX_LIMIT}, got {limit}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use sui_pg_temp_db::TempDb;
+    use sui_types::{base_types::ObjectID, gas_coin::GAS};
+
+    use crate::{
+        db::{Db, DbConfig},
+        handlers::{
+            cp_timestamps::CpTimestamps, hist_coin_balances::HistCoinBalances,
+            hist_obj_types::HistObjTypes,
+        },
+        models::{
+            checkpoints::StoredCpTimestamp,
+            objects::StoredHistCoinBalance,
+            transactions::{
+                StoredTransaction, StoredTxAffectedAddress, StoredTxBalanceChange, StoredTxDigest,
+            },
+            watermarks::CommitterWatermark,
+        },
+        schema::{
+            cp_timestamps, hist_coin_balances, kv_transactions, tx_affected_addresses,
+            tx_balance_changes, tx_digests,
+        },
+    };
+
+    use super::*;
+
+    /// Midnight (UTC) at the start of 2024-11-01.
+    const MIDNIGHT_MS: i64 = 1_730_419_200_000;
+    const HOUR_MS: i64 = 3_600_000;
+
+    fn addr(hex: &str) -> SuiAddress {
+        SuiAddress::from_str(hex).unwrap()
+    }
+
+    fn sui() -> String {
+        GAS::type_tag().to_canonical_string(/* with_prefix */ true)
+    }
+
+    fn usd() -> String {
+        parse_coin_type("0x42::usd::USD")
+            .unwrap()
+            .to_canonical_string(/* with_prefix */ true)
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 11, day).unwrap()
+    }
+
+    fn change(owner: SuiAddress, coin_type: String, amount: i128) -> BalanceChange {
+        BalanceChange::V1 {
+            owner: Owner::AddressOwner(owner),
+            coin_type,
+            amount,
+        }
+    }
+
+    fn ok<T>(response: Result<Json<T>, ApiError>) -> T {
+        match response {
+            Ok(Json(response)) => response,
+            Err(ApiError::BadRequest(e) | ApiError::Internal(e)) => panic!("{e:#}"),
+        }
+    }
+
+    async fn temp_db() -> (TempDb, ApiState) {
+        let temp_db = TempDb::new().unwrap();
+        let url = temp_db.database().url();
+        let db = Db::new(DbConfig::new(url.clone(), None, None))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+        let state = ApiState {
+            history: HistoryReader::new(db.clone()),
+            db,
+        };
+        (temp_db, state)
+    }
+
+    async fn set_watermark(
+        state: &ApiState,
+        pipeline: &str,
+        checkpoint_hi_inclusive: i64,
+        tx_hi: i64,
+        timestamp_ms_hi_inclusive: i64,
+    ) {
+        let mut conn = state.db.connect().await.unwrap();
+        CommitterWatermark {
+            checkpoint_hi_inclusive,
+            tx_hi,
+            timestamp_ms_hi_inclusive,
+            ..CommitterWatermark::initial(Cow::Borrowed(pipeline))
+        }
+        .update(&mut conn)
+        .await
+        .unwrap();
+    }
+
+    /// Transactions touching `0xa`, with their changes to its balances, in checkpoint `tx + 1`.
+    async fn insert_transactions(state: &ApiState, txs: &[Vec<BalanceChange>]) {
+        let digest = |tx: usize| TransactionDigest::new([tx as u8 + 1; 32]).inner().to_vec();
+        let mut conn = state.db.connect().await.unwrap();
+        for (tx, changes) in txs.iter().enumerate() {
+            diesel::insert_into(tx_affected_addresses::table)
+                .values(StoredTxAffectedAddress {
+                    tx_sequence_number: tx as i64,
+                    affected: addr("0xa").to_vec(),
+                    sender: addr("0xa").to_vec(),
+                })
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            diesel::insert_into(tx_balance_changes::table)
+                .values(StoredTxBalanceChange {
+                    tx_sequence_number: tx as i64,
+                    balance_changes: bcs::to_bytes(changes).unwrap(),
+                })
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            diesel::insert_into(tx_digests::table)
+                .values(StoredTxDigest {
+                    tx_sequence_number: tx as i64,
+                    tx_digest: digest(tx),
+                })
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            diesel::insert_into(kv_transactions::table)
+                .values(StoredTransaction {
+                    tx_digest: digest(tx),
+                    cp_sequence_number: tx as i64 + 1,
+                    timestamp_ms: MIDNIGHT_MS + tx as i64,
+                    raw_transaction: vec![],
+                    raw_effects: vec![],
+                    events: vec![],
+                })
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn changes(
+        state: &ApiState,
+        coin_type: Option<&str>,
+        before: Option<u64>,
+        limit: usize,
+    ) -> BalanceChangesResponse {
+        ok(balance_changes(
+            State(state.clone()),
+            Path("0xa".to_string()),
+            Query(BalanceChangesParams {
+                coin_type: coin_type.map(str::to_string),
+                before,
+                limit: Some(limit),
+            }),
+        )
+        .await)
+    }
+
+    fn amounts(response: &BalanceChangesResponse) -> Vec<(u64, &str)> {
+        response
+            .changes
+            .iter()
+            .map(|c| (c.tx_sequence_number, c.amount.as_str()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_balance_changes() {
+        let (_temp_db, state) = temp_db().await;
+        let (a, b) = (addr("0xa"), addr("0xb"));
+        insert_transactions(
+            &state,
+            &[
+                vec![change(a, sui(), 100)],
+                vec![change(a, sui(), -10), change(a, usd(), 5)],
+                vec![change(b, sui(), 1)],
+                vec![
+                    change(a, sui(), 7),
+                    change(b, sui(), -7),
+                    change(a, usd(), -3),
+                ],
+                vec![change(a, sui(), 1)],
+            ],
+        )
+        .await;
+
+        // Nothing is served until all the pipelines have committed the transactions.
+        assert!(changes(&state, None, None, 10).await.changes.is_empty());
+        for pipeline in [
+            TxAffectedAddress::NAME,
+            TxBalanceChanges::NAME,
+            TxDigests::NAME,
+        ] {
+            set_watermark(&state, pipeline, 5, 5, 0).await;
+        }
+        set_watermark(&state, KvTransactions::NAME, 4, 4, 0).await;
+
+        // Transaction 4 is not served yet, even if asked for explicitly.
+        let page = changes(&state, None, None, 10).await;
+        assert_eq!(
+            amounts(&page),
+            vec![(3, "7"), (3, "-3"), (1, "-10"), (1, "5"), (0, "100")]
+        );
+        assert_eq!(page.next_cursor, None);
+        assert_eq!(page.changes[0].checkpoint, 4);
+        assert_eq!(page.changes[0].timestamp_ms, MIDNIGHT_MS as u64 + 3);
+        assert_eq!(page.changes[0].coin_type, sui());
+        assert_eq!(
+            page.changes[0].tx_digest,
+            TransactionDigest::new([4; 32]).to_string()
+        );
+        assert_eq!(
+            amounts(&changes(&state, None, Some(100), 1).await)[0],
+            (3, "7")
+        );
+
+        // Pages end before a transaction that would not fit.
+        let page = changes(&state, None, None, 3).await;
+        assert_eq!(amounts(&page), vec![(3, "7"), (3, "-3")]);
+        assert_eq!(page.next_cursor, Some(2));
+        let page = changes(&state, None, page.next_cursor, 3).await;
+        assert_eq!(amounts(&page), vec![(1, "-10"), (1, "5"), (0, "100")]);
+        assert_eq!(page.next_cursor, None);
+
+        // A transaction with more changes than the limit is not split either.
+        let page = changes(&state, None, None, 1).await;
+        assert_eq!(amounts(&page), vec![(3, "7"), (3, "-3")]);
+        assert_eq!(page.next_cursor, Some(3));
+
+        let page = changes(&state, Some("0x42::usd::USD"), None, 10).await;
+        assert_eq!(amounts(&page), vec![(3, "-3"), (1, "5")]);
+
+        set_watermark(&state, KvTransactions::NAME, 5, 5, 0).await;
+        let page = changes(&state, Some("0x2::sui::SUI"), None, 2).await;
+        assert_eq!(amounts(&page), vec![(4, "1"), (3, "7")]);
+        assert_eq!(page.next_cursor, Some(3));
+    }
+
+    /// Balances of `0xa` in a SUI coin modified at checkpoint `cp` to `balance`, for each
+    /// `(cp, timestamp_ms, balance)`.
+    async fn insert_history(state: &ApiState, checkpoints: &[(i64, i64, Option<i64>)]) {
+        let mut conn = state.db.connect().await.unwrap();
+        for (version, (cp, timestamp_ms, balance)) in checkpoints.iter().enumerate() {
+            diesel::insert_into(cp_timestamps::table)
+                .values(StoredCpTimestamp {
+                    cp_sequence_number: *cp,
+                    timestamp_ms: *timestamp_ms,
+                })
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            let Some(balance) = balance else {
+                continue;
+            };
+            diesel::insert_into(hist_coin_balances::table)
+                .values(StoredHistCoinBalance {
+                    object_id: ObjectID::from_hex_literal("0x1").unwrap().to_vec(),
+                    object_version: version as i64 + 1,
+                    owner_id: Some(addr("0xa").to_vec()),
+                    coin_type: Some(bcs::to_bytes(&GAS::type_tag()).unwrap()),
+                    coin_balance: Some(*balance),
+                    cp_sequence_number: *cp,
+                })
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn daily(
+        state: &ApiState,
+        coin_type: Option<&str>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Json<DailyBalancesResponse>, ApiError> {
+        daily_balances(
+            State(state.clone()),
+            Path("0xa".to_string()),
+            Query(DailyBalancesParams {
+                coin_type: coin_type.map(str::to_string),
+                from,
+                to,
+            }),
+        )
+        .await
+    }
+
+    fn sui_balances(days: &[DailyBalance]) -> Vec<(NaiveDate, &str, &str)> {
+        days.iter()
+            .map(|d| {
+                (
+                    d.date,
+                    d.opening[&sui()].as_str(),
+                    d.closing[&sui()].as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_daily_balances() {
+        const DAY_MS: i64 = 24 * HOUR_MS;
+        let (_temp_db, state) = temp_db().await;
+        insert_history(
+            &state,
+            &[
+                (1, MIDNIGHT_MS - 1000, Some(100)),
+                // Changes at midnight are part of the day starting then.
+                (2, MIDNIGHT_MS, Some(200)),
+                (3, MIDNIGHT_MS + 12 * HOUR_MS, None),
+                (4, MIDNIGHT_MS + DAY_MS - 1, Some(300)),
+                (5, MIDNIGHT_MS + DAY_MS, Some(400)),
+                (6, MIDNIGHT_MS + DAY_MS + 12 * HOUR_MS, None),
+                (7, MIDNIGHT_MS + DAY_MS + 16 * HOUR_MS, None),
+            ],
+        )
+        .await;
+        let oct_31 = NaiveDate::from_ymd_opt(2024, 10, 31).unwrap();
+
+        // Nothing is indexed yet.
+        assert!(ok(daily(&state, None, date(1), date(2)).await)
+            .days
+            .is_empty());
+
+        let timestamp_ms_hi = MIDNIGHT_MS + DAY_MS + 12 * HOUR_MS;
+        set_watermark(&state, CpTimestamps::NAME, 6, 0, timestamp_ms_hi).await;
+        set_watermark(&state, HistObjTypes::NAME, 6, 0, 0).await;
+        set_watermark(&state, HistCoinBalances::NAME, 6, 0, 0).await;
+
+        // The 2nd has not ended yet, so only the 1st is returned.
+        let days = ok(daily(&state, None, date(1), date(2)).await).days;
+        assert_eq!(sui_balances(&days), vec![(date(1), "100", "300")]);
+
+        // Balances before the first indexed checkpoint are empty, but the requested coin type is
+        // reported even when there is none of it.
+        let days = ok(daily(&state, None, oct_31, oct_31).await).days;
+        assert!(days[0].opening.is_empty());
+        let days = ok(daily(&state, Some("0x2::sui::SUI"), oct_31, date(1)).await).days;
+        assert_eq!(
+            sui_balances(&days),
+            vec![(oct_31, "0", "100"), (date(1), "100", "300")]
+        );
+
+        // The end of the 2nd has a timestamp, but the history pipelines haven't caught up with it.
+        set_watermark(&state, CpTimestamps::NAME, 7, 0, MIDNIGHT_MS + 2 * DAY_MS).await;
+        let days = ok(daily(&state, None, date(1), date(2)).await).days;
+        assert_eq!(days.len(), 1);
+
+        set_watermark(&state, HistObjTypes::NAME, 7, 0, 0).await;
+        set_watermark(&state, HistCoinBalances::NAME, 7, 0, 0).await;
+        let days = ok(daily(&state, None, date(1), date(2)).await).days;
+        assert_eq!(
+            sui_balances(&days),
+            vec![(date(1), "100", "300"), (date(2), "300", "400")]
+        );
+
+        assert!(matches!(
+            daily(&state, None, date(2), date(1)).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(daily(&state, None, oct_31, date(30)).await.is_ok());
+        assert!(matches!(
+            daily(
+                &state,
+                None,
+                oct_31,
+                NaiveDate::from_ymd_opt(2024, 12, 1).unwrap()
+            )
+            .await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_not_indexed_is_bad_request() {
+        let (_temp_db, state) = temp_db().await;
+
+        // Reads ahead of what has been indexed are rejected, rather than failing.
+        let err = state.history.checkpoint_at(1000).await.unwrap_err();
+        assert!(matches!(ApiError::from(err), ApiError::BadRequest(_)));
+        let err = state.history.balances_at(addr("0xa"), 0).await.unwrap_err();
+        assert!(matches!(ApiError::from(err), ApiError::BadRequest(_)));
+
+        assert!(matches!(
+            ApiError::from(anyhow!("Connection refused")),
+            ApiError::Internal(_)
+        ));
+    }
+
+    fn stored_tx(changes: &[BalanceChange]) -> StoredAddressTx {
+        StoredAddressTx {
+            tx_sequence_number: 7,
+            tx_digest: TransactionDigest::new([1; 32]).inner().to_vec(),
+            cp_sequence_number: 3,
+            timestamp_ms: 1000,
+            balance_changes: bcs::to_bytes(changes).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_address_changes() {
+        let (a, b) = (addr("0xa"), addr("0xb"));
+        let tx = || {
+            stored_tx(&[
+                change(a, sui(), -(u64::MAX as i128) * 2),
+                change(b, sui(), 1),
+                BalanceChange::V1 {
+                    owner: Owner::ObjectOwner(a),
+                    coin_type: sui(),
+                    amount: 2,
+                },
+                change(a, usd(), 3),
+            ])
+        };
+
+        let Ok(entries) = address_changes(a, None, tx()) else {
+            panic!("Failed to read balance changes");
+        };
+        let amounts: Vec<_> = entries
+            .iter()
+            .map(|e| (e.coin_type.as_str(), e.amount.as_str()))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                (sui().as_str(), "-36893488147419103230"),
+                (usd().as_str(), "3")
+            ]
+        );
+        assert_eq!(entries[0].tx_sequence_number, 7);
+        assert_eq!(entries[0].checkpoint, 3);
+        assert_eq!(entries[0].timestamp_ms, 1000);
+        assert_eq!(
+            entries[0].tx_digest,
+            TransactionDigest::new([1; 32]).to_string()
+        );
+
+        let Ok(entries) = address_changes(a, Some(&usd()), tx()) else {
+            panic!("Failed to read balance changes");
+        };
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].amount, "3");
+
+        let mut bad = tx();
+        bad.balance_changes.pop();
+        assert!(address_changes(a, None, bad).is_err());
+        let mut bad = tx();
+        bad.tx_digest.pop();
+        assert!(address_changes(a, None, bad).is_err());
+    }
+
+    #[test]
+    fn test_balances_json() {
+        let sui_tag = GAS::type_tag();
+        let usd_tag = parse_coin_type("0x42::usd::USD").unwrap();
+        let balances = BTreeMap::from([(sui_tag.clone(), u64::MAX as u128 * 2), (usd_tag, 5)]);
+
+        assert_eq!(
+            balances_json(&balances, None),
+            BTreeMap::from([
+                (sui(), "36893488147419103230".to_string()),
+                (usd(), "5".to_string()),
+            ])
+        );
+        assert_eq!(
+            balances_json(&balances, Some(&sui_tag)),
+            BTreeMap::from([(sui(), "36893488147419103230".to_string())])
+        );
+        let other = parse_coin_type("0x42::eur::EUR").unwrap();
+        assert_eq!(
+            balances_json(&balances, Some(&other)),
+            BTreeMap::from([(other.to_canonical_string(true), "0".to_string())])
+        );
+        assert!(balances_json(&BTreeMap::new(), None).is_empty());
+    }
+
+    #[test]
+    fn test_parse_limit() {
+        assert!(matches!(parse_limit(None, 20), Ok(20)));
+        assert!(matches!(parse_limit(Some(1), 20), Ok(1)));
+        assert!(matches!(parse_limit(Some(MAX_LIMIT), 20), Ok(MAX_LIMIT)));
+        assert!(matches!(
+            parse_limit(Some(0), 20),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            parse_limit(Some(MAX_LIMIT + 1), 20),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+}
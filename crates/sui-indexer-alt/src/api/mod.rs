@@ -0,0 +1,107 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A JSON API over the indexer's tables, giving consumers a stable contract for reads that would
+//! otherwise need to know how the tables are laid out and encoded.
+//!
+//! Responses only include data that all the pipelines backing them have committed, so they are
+//! consistent with each other, even though pipelines commit independently.
+
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Router,
+};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::{
+    db::Db,
+    history::{HistoryError, HistoryReader},
+};
+
+mod balances;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ApiConfig {
+    /// Address to serve the API from.
+    #[arg(long, default_value = "0.0.0.0:8000")]
+    pub api_address: SocketAddr,
+}
+
+/// Service serving the API.
+pub struct ApiService {
+    addr: SocketAddr,
+    state: ApiState,
+    cancel: CancellationToken,
+}
+
+/// Shared by all the API's handlers.
+#[derive(Clone)]
+struct ApiState {
+    db: Db,
+    history: HistoryReader,
+}
+
+pub(crate) enum ApiError {
+    BadRequest(anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+impl ApiService {
+    pub fn new(config: ApiConfig, db: Db, cancel: CancellationToken) -> Self {
+        Self {
+            addr: config.api_address,
+            state: ApiState {
+                history: HistoryReader::new(db.clone()),
+                db,
+            },
+            cancel,
+        }
+    }
+
+    /// Start the service. The service will run until the cancellation token is triggered.
+    pub async fn run(self) -> Result<JoinHandle<()>> {
+        let listener = TcpListener::bind(&self.addr).await?;
+        let app = Router::new()
+            .merge(balances::router())
+            .with_state(self.state);
+
+        Ok(tokio::spawn(async move {
+            info!("Starting API service on {}", self.addr);
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    self.cancel.cancelled().await;
+                    info!("Shutdown received, stopping API service");
+                })
+                .await
+                .unwrap();
+        }))
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        // Reads ahead of the indexed data can be retried once it catches up.
+        if e.downcast_ref::<HistoryError>().is_some() {
+            ApiError::BadRequest(e)
+        } else {
+            ApiError::Internal(e)
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::BadRequest(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+            ApiError::Internal(e) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")).into_response()
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::api::ApiConfig;
 use crate::db::DbConfig;
 use crate::IndexerConfig;
 use clap::Subcommand;
@@ -27,6 +28,12 @@ pub enum Command {
         consistent_range: Option<u64>,
     },
 
+    /// Serve the balance history API over the indexed tables.
+    Api {
+        #[command(flatten)]
+        api_config: ApiConfig,
+    },
+
     /// Wipe the database of its contents
     ResetDatabase {
         /// If true, only drop all tables but do not run the migrations.
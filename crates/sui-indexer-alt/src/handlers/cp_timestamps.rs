@@ -0,0 +1,43 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use diesel_async::RunQueryDsl;
+use sui_types::full_checkpoint_content::CheckpointData;
+
+use crate::{
+    db, models::checkpoints::StoredCpTimestamp, pipeline::concurrent::Handler, pipeline::Processor,
+    schema::cp_timestamps,
+};
+
+pub struct CpTimestamps;
+
+impl Processor for CpTimestamps {
+    const NAME: &'static str = "cp_timestamps";
+
+    type Value = StoredCpTimestamp;
+
+    fn process(checkpoint: &Arc<CheckpointData>) -> Result<Vec<Self::Value>> {
+        Ok(vec![StoredCpTimestamp {
+            cp_sequence_number: checkpoint.checkpoint_summary.sequence_number as i64,
+            timestamp_ms: checkpoint.checkpoint_summary.timestamp_ms as i64,
+        }])
+    }
+}
+
+#[async_trait::async_trait]
+impl Handler for CpTimestamps {
+    const MIN_EAGER_ROWS: usize = 100;
+    const MAX_CHUNK_ROWS: usize = 1000;
+    const MAX_PENDING_ROWS: usize = 10000;
+
+    async fn commit(values: &[Self::Value], conn: &mut db::Connection<'_>) -> Result<usize> {
+        Ok(diesel::insert_into(cp_timestamps::table)
+            .values(values)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await?)
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use sui_types::full_checkpoint_content::CheckpointData;
+
+use crate::pipeline::{kafka::Handler, Processor};
+
+/// Publishes raw checkpoint data, BCS-encoded, keyed by checkpoint sequence number.
+pub struct KafkaCheckpoints;
+
+impl Processor for KafkaCheckpoints {
+    const NAME: &'static str = "kafka_checkpoints";
+
+    type Value = Arc<CheckpointData>;
+
+    fn process(checkpoint: &Arc<CheckpointData>) -> Result<Vec<Self::Value>> {
+        Ok(vec![checkpoint.clone()])
+    }
+}
+
+impl Handler for KafkaCheckpoints {
+    const TOPIC: &'static str = "sui_checkpoints";
+
+    // Checkpoints can be large, so publish them as soon as they are ready, a few at a time.
+    const MIN_EAGER_ROWS: usize = 1;
+    const MAX_BATCH_CHECKPOINTS: usize = 10;
+
+    fn key(value: &Self::Value) -> Vec<u8> {
+        value
+            .checkpoint_summary
+            .sequence_number
+            .to_be_bytes()
+            .to_vec()
+    }
+
+    fn payload(value: &Self::Value) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(value.as_ref())?)
+    }
+}
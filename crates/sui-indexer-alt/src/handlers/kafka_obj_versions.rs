@@ -0,0 +1,62 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::Serialize;
+use sui_types::{
+    base_types::ObjectID, digests::ObjectDigest, full_checkpoint_content::CheckpointData,
+};
+
+use crate::pipeline::{kafka::Handler, Processor};
+
+/// Publishes every new version of an object, as JSON, keyed by object ID, so that the versions of
+/// an object are consumed in order.
+pub struct KafkaObjVersions;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ObjVersion {
+    pub object_id: ObjectID,
+    pub object_version: u64,
+    pub object_digest: ObjectDigest,
+    pub cp_sequence_number: u64,
+}
+
+impl Processor for KafkaObjVersions {
+    const NAME: &'static str = "kafka_obj_versions";
+
+    type Value = ObjVersion;
+
+    fn process(checkpoint: &Arc<CheckpointData>) -> Result<Vec<Self::Value>> {
+        let CheckpointData {
+            transactions,
+            checkpoint_summary,
+            ..
+        } = checkpoint.as_ref();
+
+        let cp_sequence_number = checkpoint_summary.sequence_number;
+        Ok(transactions
+            .iter()
+            .flat_map(|txn| txn.output_objects.iter())
+            .map(|o| ObjVersion {
+                object_id: o.id(),
+                object_version: o.version().value(),
+                object_digest: o.digest(),
+                cp_sequence_number,
+            })
+            .collect())
+    }
+}
+
+impl Handler for KafkaObjVersions {
+    const TOPIC: &'static str = "sui_obj_versions";
+
+    fn key(value: &Self::Value) -> Vec<u8> {
+        value.object_id.to_vec()
+    }
+
+    fn payload(value: &Self::Value) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(value)?)
+    }
+}
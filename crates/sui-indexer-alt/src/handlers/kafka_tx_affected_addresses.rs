@@ -0,0 +1,82 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use itertools::Itertools;
+use serde::Serialize;
+use sui_types::{
+    base_types::SuiAddress, digests::TransactionDigest, full_checkpoint_content::CheckpointData,
+    object::Owner,
+};
+
+use crate::pipeline::{kafka::Handler, Processor};
+
+/// Publishes the transactions affecting each address (as sender, gas payer or recipient of
+/// objects), as JSON, keyed by the affected address, so that the transactions affecting an
+/// address are consumed in order.
+pub struct KafkaTxAffectedAddresses;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TxAffectedAddress {
+    pub affected: SuiAddress,
+    pub tx_digest: TransactionDigest,
+    pub tx_sequence_number: u64,
+    pub sender: SuiAddress,
+    pub cp_sequence_number: u64,
+}
+
+impl Processor for KafkaTxAffectedAddresses {
+    const NAME: &'static str = "kafka_tx_affected_addresses";
+
+    type Value = TxAffectedAddress;
+
+    fn process(checkpoint: &Arc<CheckpointData>) -> Result<Vec<Self::Value>> {
+        let CheckpointData {
+            transactions,
+            checkpoint_summary,
+            ..
+        } = checkpoint.as_ref();
+
+        let mut values = Vec::new();
+        let first_tx = checkpoint_summary.network_total_transactions - transactions.len() as u64;
+
+        for (i, tx) in transactions.iter().enumerate() {
+            let tx_sequence_number = first_tx + i as u64;
+            let tx_digest = *tx.transaction.digest();
+            let sender = tx.transaction.sender_address();
+            let payer = tx.transaction.gas_owner();
+            let recipients = tx.effects.all_changed_objects().into_iter().filter_map(
+                |(_object_ref, owner, _write_kind)| match owner {
+                    Owner::AddressOwner(address) => Some(address),
+                    _ => None,
+                },
+            );
+
+            values.extend(recipients.chain([sender, payer]).unique().map(|affected| {
+                TxAffectedAddress {
+                    affected,
+                    tx_digest,
+                    tx_sequence_number,
+                    sender,
+                    cp_sequence_number: checkpoint_summary.sequence_number,
+                }
+            }));
+        }
+
+        Ok(values)
+    }
+}
+
+impl Handler for KafkaTxAffectedAddresses {
+    const TOPIC: &'static str = "sui_tx_affected_addresses";
+
+    fn key(value: &Self::Value) -> Vec<u8> {
+        value.affected.to_vec()
+    }
+
+    fn payload(value: &Self::Value) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(value)?)
+    }
+}
@@ -1,8 +1,17 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod cp_timestamps;
 pub mod ev_emit_mod;
 pub mod ev_struct_inst;
+pub mod hist_coin_balances;
+pub mod hist_obj_types;
+#[cfg(feature = "kafka")]
+pub mod kafka_checkpoints;
+#[cfg(feature = "kafka")]
+pub mod kafka_obj_versions;
+#[cfg(feature = "kafka")]
+pub mod kafka_tx_affected_addresses;
 pub mod kv_checkpoints;
 pub mod kv_objects;
 pub mod kv_transactions;
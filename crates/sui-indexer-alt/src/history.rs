@@ -0,0 +1,744 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Point-in-time reads over the `hist_*` tables, which keep the full history of the owner, type
+//! and balance of every object (unlike the `sum_*` tables, which only hold the latest state, and
+//! the `wal_*` tables, which only cover the checkpoints between the summary and the tip of the
+//! chain).
+//!
+//! Reads are at a checkpoint, and fail if the pipelines backing them have not committed all the
+//! data up to that checkpoint yet. [HistoryReader::checkpoint_at] translates a point in time (e.g.
+//! the end of a day) into the last checkpoint at or before it.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use diesel::{
+    prelude::*,
+    sql_types::{Bytea, Int8, Text},
+};
+use diesel_async::RunQueryDsl;
+use move_core_types::language_storage::StructTag;
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    Identifier, TypeTag,
+};
+
+use crate::{
+    db::Db,
+    handlers::{
+        cp_timestamps::CpTimestamps, hist_coin_balances::HistCoinBalances,
+        hist_obj_types::HistObjTypes,
+    },
+    models::{
+        objects::{StoredHistCoinBalance, StoredHistObjType, StoredOwnerKind},
+        watermarks::CommitterWatermark,
+    },
+    pipeline::Processor,
+    schema::{cp_timestamps, hist_coin_balances, hist_obj_types},
+};
+
+/// The latest versions of the objects owned by an address at a checkpoint, out of all the objects
+/// the address owned at some point up to that checkpoint, paginated by object ID.
+///
+/// Candidate objects come from the owner index, and the latest version of each candidate is looked
+/// up by its primary key, so the query never scans the history of objects the address did not own.
+const OWNED_OBJECTS_QUERY: &str = r#"
+    SELECT latest.*
+    FROM (
+        SELECT DISTINCT object_id
+        FROM hist_obj_types
+        WHERE owner_kind = 1 AND owner_id = $1 AND cp_sequence_number <= $2 AND object_id > $3
+        ORDER BY object_id
+    ) candidates
+    CROSS JOIN LATERAL (
+        SELECT *
+        FROM hist_obj_types h
+        WHERE h.object_id = candidates.object_id AND h.cp_sequence_number <= $2
+        ORDER BY h.object_version DESC
+        LIMIT 1
+    ) latest
+    WHERE latest.owner_kind = 1 AND latest.owner_id = $1
+    ORDER BY latest.object_id
+    LIMIT $4
+"#;
+
+/// The latest versions of the coins owned by an address at a checkpoint, out of all the coins the
+/// address owned at some point up to that checkpoint (see [OWNED_OBJECTS_QUERY]).
+const OWNED_COINS_QUERY: &str = r#"
+    SELECT latest.*
+    FROM (
+        SELECT DISTINCT object_id
+        FROM hist_coin_balances
+        WHERE owner_id = $1 AND cp_sequence_number <= $2
+    ) candidates
+    CROSS JOIN LATERAL (
+        SELECT *
+        FROM hist_coin_balances h
+        WHERE h.object_id = candidates.object_id AND h.cp_sequence_number <= $2
+        ORDER BY h.object_version DESC
+        LIMIT 1
+    ) latest
+    WHERE latest.owner_id = $1
+"#;
+
+/// The addresses with the largest total balance of a coin type at a checkpoint, out of the latest
+/// versions of all the coins of that type that existed at some point up to that checkpoint (see
+/// [OWNED_OBJECTS_QUERY]).
+const TOP_HOLDERS_QUERY: &str = r#"
+    SELECT latest.owner_id, SUM(latest.coin_balance)::TEXT AS balance
+    FROM (
+        SELECT DISTINCT object_id
+        FROM hist_coin_balances
+        WHERE coin_type = $1 AND cp_sequence_number <= $2
+    ) candidates
+    CROSS JOIN LATERAL (
+        SELECT *
+        FROM hist_coin_balances h
+        WHERE h.object_id = candidates.object_id AND h.cp_sequence_number <= $2
+        ORDER BY h.object_version DESC
+        LIMIT 1
+    ) latest
+    WHERE latest.coin_type = $1 AND latest.owner_id IS NOT NULL
+    GROUP BY latest.owner_id
+    ORDER BY SUM(latest.coin_balance) DESC, latest.owner_id
+    LIMIT $3
+"#;
+
+/// Reads that fail because they are ahead of the data that has been indexed, rather than because
+/// the data could not be read.
+#[derive(thiserror::Error, Debug)]
+pub enum HistoryError {
+    #[error("Checkpoints up to {0}ms are not indexed yet")]
+    TimestampNotIndexed(u64),
+
+    #[error("Checkpoint {checkpoint} is not indexed yet (latest: {hi:?})")]
+    CheckpointNotIndexed { checkpoint: u64, hi: Option<u64> },
+}
+
+/// The state of an object at a checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectAt {
+    pub object_id: ObjectID,
+    /// The version the object was at, as of the checkpoint.
+    pub object_version: u64,
+    /// The checkpoint this version of the object was created in.
+    pub cp_sequence_number: u64,
+    pub owner_kind: StoredOwnerKind,
+    /// The owning address or object, for address- and object-owned objects.
+    pub owner_id: Option<SuiAddress>,
+    /// The type of the object, or `None` if it is a package.
+    pub type_: Option<StructTag>,
+    /// The balance of the object, if it is a coin owned by an address.
+    pub coin_balance: Option<u64>,
+}
+
+#[derive(QueryableByName)]
+struct StoredHolder {
+    #[diesel(sql_type = Bytea)]
+    owner_id: Vec<u8>,
+    #[diesel(sql_type = Text)]
+    balance: String,
+}
+
+/// Reads the state of objects and addresses as of arbitrary checkpoints.
+#[derive(Clone)]
+pub struct HistoryReader {
+    db: Db,
+}
+
+impl HistoryReader {
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+
+    /// The highest checkpoint that point-in-time reads can be served at, or `None` if the history
+    /// pipelines have not committed any checkpoints yet.
+    pub async fn checkpoint_hi_inclusive(&self) -> Result<Option<u64>> {
+        let mut conn = self.db.connect().await?;
+        let mut hi = u64::MAX;
+        for pipeline in [HistObjTypes::NAME, HistCoinBalances::NAME] {
+            let Some(watermark) = CommitterWatermark::get(&mut conn, pipeline).await? else {
+                return Ok(None);
+            };
+
+            hi = hi.min(watermark.checkpoint_hi_inclusive as u64);
+        }
+
+        Ok(Some(hi))
+    }
+
+    /// The latest point in time that [Self::checkpoint_at] can be called with, or `None` if no
+    /// checkpoints have been indexed yet.
+    pub async fn timestamp_ms_hi_inclusive(&self) -> Result<Option<u64>> {
+        let mut conn = self.db.connect().await?;
+        let watermark = CommitterWatermark::get(&mut conn, CpTimestamps::NAME).await?;
+        Ok(watermark.map(|w| w.timestamp_ms_hi_inclusive as u64))
+    }
+
+    /// The last checkpoint with a timestamp at or before `timestamp_ms`, or `None` if the first
+    /// indexed checkpoint is after it. Fails if checkpoints up to that point in time have not all
+    /// been indexed yet.
+    pub async fn checkpoint_at(&self, timestamp_ms: u64) -> Result<Option<u64>> {
+        let mut conn = self.db.connect().await?;
+        let watermark = CommitterWatermark::get(&mut conn, CpTimestamps::NAME)
+            .await?
+            .filter(|w| w.timestamp_ms_hi_inclusive as u64 >= timestamp_ms)
+            .ok_or(HistoryError::TimestampNotIndexed(timestamp_ms))?;
+
+        let cp_sequence_number: Option<i64> = cp_timestamps::table
+            .select(cp_timestamps::cp_sequence_number)
+            .filter(cp_timestamps::timestamp_ms.le(timestamp_ms as i64))
+            .filter(cp_timestamps::cp_sequence_number.le(watermark.checkpoint_hi_inclusive))
+            .order_by((
+                cp_timestamps::timestamp_ms.desc(),
+                cp_timestamps::cp_sequence_number.desc(),
+            ))
+            .first(&mut conn)
+            .await
+            .optional()?;
+
+        Ok(cp_sequence_number.map(|cp| cp as u64))
+    }
+
+    /// The owner, type and balance of an object as of `checkpoint`, or `None` if the object did not
+    /// exist, or was deleted or wrapped at that checkpoint.
+    pub async fn object_at(
+        &self,
+        object_id: ObjectID,
+        checkpoint: u64,
+    ) -> Result<Option<ObjectAt>> {
+        self.ensure_indexed(checkpoint).await?;
+        let mut conn = self.db.connect().await?;
+
+        let stored: Option<StoredHistObjType> = hist_obj_types::table
+            .select(StoredHistObjType::as_select())
+            .filter(hist_obj_types::object_id.eq(object_id.to_vec()))
+            .filter(hist_obj_types::cp_sequence_number.le(checkpoint as i64))
+            .order_by(hist_obj_types::object_version.desc())
+            .first(&mut conn)
+            .await
+            .optional()?;
+
+        let Some(stored) = stored else {
+            return Ok(None);
+        };
+
+        let coin: Option<StoredHistCoinBalance> = hist_coin_balances::table
+            .select(StoredHistCoinBalance::as_select())
+            .filter(hist_coin_balances::object_id.eq(object_id.to_vec()))
+            .filter(hist_coin_balances::object_version.eq(stored.object_version))
+            .first(&mut conn)
+            .await
+            .optional()?;
+
+        let Some(mut object) = ObjectAt::from_stored(stored)? else {
+            return Ok(None);
+        };
+
+        object.coin_balance = coin.and_then(|c| c.coin_balance).map(|b| b as u64);
+        Ok(Some(object))
+    }
+
+    /// Up to `limit` objects owned by `owner` as of `checkpoint`, in object ID order, starting
+    /// after `after` if provided. Their `coin_balance`s are not populated.
+    pub async fn owned_objects_at(
+        &self,
+        owner: SuiAddress,
+        checkpoint: u64,
+        after: Option<ObjectID>,
+        limit: usize,
+    ) -> Result<Vec<ObjectAt>> {
+        self.ensure_indexed(checkpoint).await?;
+        let mut conn = self.db.connect().await?;
+
+        let stored: Vec<StoredHistObjType> = diesel::sql_query(OWNED_OBJECTS_QUERY)
+            .bind::<Bytea, _>(owner.to_vec())
+            .bind::<Int8, _>(checkpoint as i64)
+            .bind::<Bytea, _>(after.map_or_else(Vec::new, |id| id.to_vec()))
+            .bind::<Int8, _>(limit as i64)
+            .load(&mut conn)
+            .await?;
+
+        let mut objects = Vec::with_capacity(stored.len());
+        for stored in stored {
+            objects.extend(ObjectAt::from_stored(stored)?);
+        }
+
+        Ok(objects)
+    }
+
+    /// The total balance of every coin type owned by `owner` as of `checkpoint`.
+    pub async fn balances_at(
+        &self,
+        owner: SuiAddress,
+        checkpoint: u64,
+    ) -> Result<BTreeMap<TypeTag, u128>> {
+        self.ensure_indexed(checkpoint).await?;
+        let mut conn = self.db.connect().await?;
+
+        let coins: Vec<StoredHistCoinBalance> = diesel::sql_query(OWNED_COINS_QUERY)
+            .bind::<Bytea, _>(owner.to_vec())
+            .bind::<Int8, _>(checkpoint as i64)
+            .load(&mut conn)
+            .await?;
+
+        let mut balances = BTreeMap::new();
+        for coin in coins {
+            let (Some(coin_type), Some(coin_balance)) = (coin.coin_type, coin.coin_balance) else {
+                continue;
+            };
+
+            let coin_type: TypeTag =
+                bcs::from_bytes(&coin_type).context("Failed to deserialize coin type")?;
+            *balances.entry(coin_type).or_insert(0u128) += coin_balance as u128;
+        }
+
+        Ok(balances)
+    }
+
+    /// Up to `limit` addresses with the largest total balance of coins of type `coin_type` (the
+    /// marker type, e.g. `0x2::sui::SUI`) as of `checkpoint`, largest first.
+    pub async fn top_holders_at(
+        &self,
+        coin_type: &TypeTag,
+        checkpoint: u64,
+        limit: usize,
+    ) -> Result<Vec<(SuiAddress, u128)>> {
+        self.ensure_indexed(checkpoint).await?;
+        let mut conn = self.db.connect().await?;
+
+        let holders: Vec<StoredHolder> = diesel::sql_query(TOP_HOLDERS_QUERY)
+            .bind::<Bytea, _>(bcs::to_bytes(coin_type)?)
+            .bind::<Int8, _>(checkpoint as i64)
+            .bind::<Int8, _>(limit as i64)
+            .load(&mut conn)
+            .await?;
+
+        holders
+            .into_iter()
+            .map(|h| {
+                let owner = SuiAddress::from_bytes(&h.owner_id)
+                    .map_err(|e| anyhow!("Bad owner in history: {e}"))?;
+                let balance = h
+                    .balance
+                    .parse()
+                    .with_context(|| format!("Bad balance for {owner}: {}", h.balance))?;
+                Ok((owner, balance))
+            })
+            .collect()
+    }
+
+    /// Point-in-time reads are only consistent at checkpoints that all history pipelines have
+    /// committed all the data for.
+    async fn ensure_indexed(&self, checkpoint: u64) -> Result<()> {
+        let hi = self.checkpoint_hi_inclusive().await?;
+        ensure!(
+            hi.is_some_and(|hi| checkpoint <= hi),
+            HistoryError::CheckpointNotIndexed { checkpoint, hi },
+        );
+        Ok(())
+    }
+}
+
+impl ObjectAt {
+    /// Interpret a row from `hist_obj_types`, returning `None` if it records a deletion or a
+    /// wrap.
+    fn from_stored(stored: StoredHistObjType) -> Result<Option<Self>> {
+        let object_id = ObjectID::from_bytes(&stored.object_id)
+            .map_err(|e| anyhow!("Bad object ID in history: {e}"))?;
+
+        let Some(owner_kind) = stored.owner_kind else {
+            return Ok(None);
+        };
+
+        let owner_id = stored
+            .owner_id
+            .map(SuiAddress::from_bytes)
+            .transpose()
+            .map_err(|e| anyhow!("Bad owner of {object_id} in history: {e}"))?;
+
+        let type_ = match (
+            stored.package,
+            stored.module,
+            stored.name,
+            stored.instantiation,
+        ) {
+            (None, None, None, None) => None,
+            (Some(package), Some(module), Some(name), Some(instantiation)) => Some(StructTag {
+                address: ObjectID::from_bytes(package)
+                    .map_err(|e| anyhow!("Bad package of {object_id} in history: {e}"))?
+                    .into(),
+                module: Identifier::new(module)?,
+                name: Identifier::new(name)?,
+                type_params: bcs::from_bytes(&instantiation)
+                    .with_context(|| format!("Bad type parameters of {object_id} in history"))?,
+            }),
+            _ => bail!("Partial type of {object_id} in history"),
+        };
+
+        Ok(Some(ObjectAt {
+            object_id,
+            object_version: stored.object_version as u64,
+            cp_sequence_number: stored.cp_sequence_number as u64,
+            owner_kind,
+            owner_id,
+            type_,
+            coin_balance: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use sui_pg_temp_db::TempDb;
+    use sui_types::{coin::Coin, gas_coin::GAS};
+
+    use crate::db::DbConfig;
+
+    use super::*;
+
+    fn id(hex: &str) -> ObjectID {
+        ObjectID::from_hex_literal(hex).unwrap()
+    }
+
+    fn addr(hex: &str) -> SuiAddress {
+        id(hex).into()
+    }
+
+    fn usd() -> TypeTag {
+        TypeTag::Struct(Box::new(StructTag {
+            address: id("0x42").into(),
+            module: Identifier::new("usd").unwrap(),
+            name: Identifier::new("USD").unwrap(),
+            type_params: vec![],
+        }))
+    }
+
+    /// The `hist_obj_types` and `hist_coin_balances` rows of a version of a coin of `coin_type`,
+    /// owned by `owner`, or deleted or wrapped if `owner` is `None`.
+    fn coin(
+        object_id: ObjectID,
+        object_version: i64,
+        cp_sequence_number: i64,
+        owner: Option<(SuiAddress, TypeTag, i64)>,
+    ) -> (StoredHistObjType, StoredHistCoinBalance) {
+        let coin_type = owner.as_ref().map(|(_, t, _)| Coin::type_(t.clone()));
+        (
+            StoredHistObjType {
+                object_id: object_id.to_vec(),
+                object_version,
+                owner_kind: owner.as_ref().map(|_| StoredOwnerKind::Address),
+                owner_id: owner.as_ref().map(|(o, _, _)| o.to_vec()),
+                package: coin_type.as_ref().map(|t| t.address.to_vec()),
+                module: coin_type.as_ref().map(|t| t.module.to_string()),
+                name: coin_type.as_ref().map(|t| t.name.to_string()),
+                instantiation: coin_type
+                    .as_ref()
+                    .map(|t| bcs::to_bytes(&t.type_params).unwrap()),
+                cp_sequence_number,
+            },
+            StoredHistCoinBalance {
+                object_id: object_id.to_vec(),
+                object_version,
+                owner_id: owner.as_ref().map(|(o, _, _)| o.to_vec()),
+                coin_type: owner.as_ref().map(|(_, t, _)| bcs::to_bytes(t).unwrap()),
+                coin_balance: owner.as_ref().map(|(_, _, b)| *b),
+                cp_sequence_number,
+            },
+        )
+    }
+
+    /// A version of an object that is not a coin, owned by `owner`, or deleted or wrapped if
+    /// `owner` is `None`.
+    fn object(
+        object_id: ObjectID,
+        object_version: i64,
+        cp_sequence_number: i64,
+        owner: Option<SuiAddress>,
+    ) -> StoredHistObjType {
+        StoredHistObjType {
+            object_id: object_id.to_vec(),
+            object_version,
+            owner_kind: owner.map(|_| StoredOwnerKind::Address),
+            owner_id: owner.map(|o| o.to_vec()),
+            package: owner.map(|_| id("0x42").to_vec()),
+            module: owner.map(|_| "m".to_string()),
+            name: owner.map(|_| "T".to_string()),
+            instantiation: owner.map(|_| bcs::to_bytes(&Vec::<TypeTag>::new()).unwrap()),
+            cp_sequence_number,
+        }
+    }
+
+    async fn set_watermark(db: &Db, pipeline: &str, checkpoint_hi_inclusive: i64) {
+        let mut conn = db.connect().await.unwrap();
+        CommitterWatermark {
+            checkpoint_hi_inclusive,
+            ..CommitterWatermark::initial(Cow::Borrowed(pipeline))
+        }
+        .update(&mut conn)
+        .await
+        .unwrap();
+    }
+
+    /// A database with the history of:
+    ///
+    /// - `0x1`, a SUI coin owned by `0xa` at checkpoint 1, modified at 3, transferred to `0xb` at
+    ///   5 and deleted at 7.
+    /// - `0x2`, an object owned by `0xa` at checkpoint 2, wrapped at 4 and unwrapped at 6.
+    /// - `0x3`, a SUI coin owned by `0xb` at checkpoint 1.
+    /// - `0x4`, a USD coin owned by `0xa` at checkpoint 2.
+    async fn history() -> (TempDb, Db) {
+        let temp_db = TempDb::new().unwrap();
+        let url = temp_db.database().url();
+        let db = Db::new(DbConfig::new(url.clone(), None, None))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+
+        let (a, b, sui) = (addr("0xa"), addr("0xb"), GAS::type_tag());
+        let (mut obj_types, coin_balances): (Vec<_>, Vec<_>) = [
+            coin(id("0x1"), 1, 1, Some((a, sui.clone(), 100))),
+            coin(id("0x1"), 2, 3, Some((a, sui.clone(), 150))),
+            coin(id("0x1"), 3, 5, Some((b, sui.clone(), 150))),
+            coin(id("0x1"), 4, 7, None),
+            coin(id("0x3"), 1, 1, Some((b, sui.clone(), 20))),
+            coin(id("0x4"), 1, 2, Some((a, usd(), 50))),
+        ]
+        .into_iter()
+        .unzip();
+        obj_types.extend([
+            object(id("0x2"), 1, 2, Some(a)),
+            object(id("0x2"), 2, 4, None),
+            object(id("0x2"), 3, 6, Some(a)),
+        ]);
+
+        let mut conn = db.connect().await.unwrap();
+        diesel::insert_into(hist_obj_types::table)
+            .values(&obj_types)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        diesel::insert_into(hist_coin_balances::table)
+            .values(&coin_balances)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        (temp_db, db)
+    }
+
+    fn versions(objects: &[ObjectAt]) -> Vec<(ObjectID, u64)> {
+        objects
+            .iter()
+            .map(|o| (o.object_id, o.object_version))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_object_at() {
+        let (_temp_db, db) = history().await;
+        set_watermark(&db, HistObjTypes::NAME, 7).await;
+        set_watermark(&db, HistCoinBalances::NAME, 7).await;
+        let history = HistoryReader::new(db);
+
+        assert_eq!(history.object_at(id("0x1"), 0).await.unwrap(), None);
+        assert_eq!(
+            history.object_at(id("0x1"), 4).await.unwrap(),
+            Some(ObjectAt {
+                object_id: id("0x1"),
+                object_version: 2,
+                cp_sequence_number: 3,
+                owner_kind: StoredOwnerKind::Address,
+                owner_id: Some(addr("0xa")),
+                type_: Some(Coin::type_(GAS::type_tag())),
+                coin_balance: Some(150),
+            })
+        );
+
+        let transferred = history.object_at(id("0x1"), 5).await.unwrap().unwrap();
+        assert_eq!(transferred.object_version, 3);
+        assert_eq!(transferred.owner_id, Some(addr("0xb")));
+        assert_eq!(history.object_at(id("0x1"), 7).await.unwrap(), None);
+
+        let object = history.object_at(id("0x2"), 2).await.unwrap().unwrap();
+        assert_eq!(object.object_version, 1);
+        assert_eq!(
+            object.type_,
+            Some(StructTag {
+                address: id("0x42").into(),
+                module: Identifier::new("m").unwrap(),
+                name: Identifier::new("T").unwrap(),
+                type_params: vec![],
+            })
+        );
+        assert_eq!(object.coin_balance, None);
+        assert_eq!(history.object_at(id("0x2"), 5).await.unwrap(), None);
+        let unwrapped = history.object_at(id("0x2"), 6).await.unwrap().unwrap();
+        assert_eq!(unwrapped.object_version, 3);
+    }
+
+    #[tokio::test]
+    async fn test_owned_objects_at() {
+        let (_temp_db, db) = history().await;
+        set_watermark(&db, HistObjTypes::NAME, 7).await;
+        set_watermark(&db, HistCoinBalances::NAME, 7).await;
+        let history = HistoryReader::new(db);
+        let (a, b) = (addr("0xa"), addr("0xb"));
+
+        let owned = |owner, checkpoint| history.owned_objects_at(owner, checkpoint, None, 10);
+        assert_eq!(versions(&owned(a, 0).await.unwrap()), vec![]);
+        assert_eq!(
+            versions(&owned(a, 2).await.unwrap()),
+            vec![(id("0x1"), 1), (id("0x2"), 1), (id("0x4"), 1)],
+        );
+        // The coin is modified, while the object is wrapped.
+        assert_eq!(
+            versions(&owned(a, 4).await.unwrap()),
+            vec![(id("0x1"), 2), (id("0x4"), 1)],
+        );
+        // The coin moves away, and the object is unwrapped.
+        assert_eq!(versions(&owned(a, 5).await.unwrap()), vec![(id("0x4"), 1)]);
+        assert_eq!(
+            versions(&owned(a, 6).await.unwrap()),
+            vec![(id("0x2"), 3), (id("0x4"), 1)],
+        );
+        assert_eq!(
+            versions(&owned(b, 5).await.unwrap()),
+            vec![(id("0x1"), 3), (id("0x3"), 1)],
+        );
+        assert_eq!(versions(&owned(b, 7).await.unwrap()), vec![(id("0x3"), 1)]);
+
+        // Pagination
+        let first = history.owned_objects_at(b, 5, None, 1).await.unwrap();
+        assert_eq!(versions(&first), vec![(id("0x1"), 3)]);
+        let rest = history
+            .owned_objects_at(b, 5, Some(id("0x1")), 1)
+            .await
+            .unwrap();
+        assert_eq!(versions(&rest), vec![(id("0x3"), 1)]);
+        let rest = history
+            .owned_objects_at(b, 5, Some(id("0x3")), 1)
+            .await
+            .unwrap();
+        assert_eq!(versions(&rest), vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_balances_at() {
+        let (_temp_db, db) = history().await;
+        set_watermark(&db, HistObjTypes::NAME, 7).await;
+        set_watermark(&db, HistCoinBalances::NAME, 7).await;
+        let history = HistoryReader::new(db);
+        let (a, b, sui) = (addr("0xa"), addr("0xb"), GAS::type_tag());
+
+        assert!(history.balances_at(a, 0).await.unwrap().is_empty());
+        assert_eq!(
+            history.balances_at(a, 2).await.unwrap(),
+            BTreeMap::from([(sui.clone(), 100), (usd(), 50)]),
+        );
+        assert_eq!(
+            history.balances_at(a, 3).await.unwrap(),
+            BTreeMap::from([(sui.clone(), 150), (usd(), 50)]),
+        );
+        assert_eq!(
+            history.balances_at(a, 5).await.unwrap(),
+            BTreeMap::from([(usd(), 50)]),
+        );
+        assert_eq!(
+            history.balances_at(b, 5).await.unwrap(),
+            BTreeMap::from([(sui.clone(), 170)]),
+        );
+        assert_eq!(
+            history.balances_at(b, 7).await.unwrap(),
+            BTreeMap::from([(sui, 20)]),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_top_holders_at() {
+        let (_temp_db, db) = history().await;
+        set_watermark(&db, HistObjTypes::NAME, 7).await;
+        set_watermark(&db, HistCoinBalances::NAME, 7).await;
+        let history = HistoryReader::new(db);
+        let (a, b, sui) = (addr("0xa"), addr("0xb"), GAS::type_tag());
+
+        assert!(history
+            .top_holders_at(&sui, 0, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            history.top_holders_at(&sui, 2, 10).await.unwrap(),
+            vec![(a, 100), (b, 20)],
+        );
+        assert_eq!(
+            history.top_holders_at(&sui, 2, 1).await.unwrap(),
+            vec![(a, 100)],
+        );
+        // Balances are summed over all of an owner's coins.
+        assert_eq!(
+            history.top_holders_at(&sui, 5, 10).await.unwrap(),
+            vec![(b, 170)],
+        );
+        // Deleted coins no longer count.
+        assert_eq!(
+            history.top_holders_at(&sui, 7, 10).await.unwrap(),
+            vec![(b, 20)],
+        );
+        assert_eq!(
+            history.top_holders_at(&usd(), 7, 10).await.unwrap(),
+            vec![(a, 50)],
+        );
+        assert!(history.top_holders_at(&sui, 8, 10).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reads_wait_for_all_pipelines() {
+        let (_temp_db, db) = history().await;
+        let history = HistoryReader::new(db.clone());
+        let a = addr("0xa");
+
+        // Nothing indexed yet.
+        assert_eq!(history.checkpoint_hi_inclusive().await.unwrap(), None);
+        assert!(history.object_at(id("0x1"), 0).await.is_err());
+
+        // Reads are bounded by the pipeline that is furthest behind.
+        set_watermark(&db, HistObjTypes::NAME, 7).await;
+        assert!(history.object_at(id("0x1"), 0).await.is_err());
+        set_watermark(&db, HistCoinBalances::NAME, 4).await;
+        assert_eq!(history.checkpoint_hi_inclusive().await.unwrap(), Some(4));
+
+        assert!(history.object_at(id("0x1"), 4).await.unwrap().is_some());
+        assert!(history.object_at(id("0x1"), 5).await.is_err());
+        assert_eq!(
+            history
+                .owned_objects_at(a, 4, None, 10)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(history.owned_objects_at(a, 5, None, 10).await.is_err());
+        assert_eq!(history.balances_at(a, 4).await.unwrap().len(), 2);
+        assert!(matches!(
+            history
+                .balances_at(a, 5)
+                .await
+                .unwrap_err()
+                .downcast::<HistoryError>(),
+            Ok(HistoryError::CheckpointNotIndexed {
+                checkpoint: 5,
+                hi: Some(4)
+            })
+        ));
+
+        set_watermark(&db, HistCoinBalances::NAME, 7).await;
+        assert!(history.balances_at(a, 5).await.is_ok());
+        assert!(history.owned_objects_at(a, 7, None, 10).await.is_ok());
+        assert!(history.balances_at(a, 8).await.is_err());
+    }
+}
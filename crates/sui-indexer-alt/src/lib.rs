@@ -8,16 +8,20 @@ use db::{Db, DbConfig};
 use ingestion::{client::IngestionClient, IngestionConfig, IngestionService};
 use metrics::{IndexerMetrics, MetricsService};
 use models::watermarks::CommitterWatermark;
-use pipeline::{concurrent, sequential, PipelineConfig, Processor};
+use pipeline::{concurrent, sequential, shard, PipelineConfig, Processor};
+#[cfg(feature = "kafka")]
+use pipeline::{kafka, KafkaConfig};
 use task::graceful_shutdown;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 use tracing::info;
 
+pub mod api;
 pub mod args;
 pub mod bootstrap;
 pub mod db;
 pub mod handlers;
+pub mod history;
 pub mod ingestion;
 pub mod metrics;
 pub mod models;
@@ -41,6 +45,10 @@ pub struct Indexer {
     /// Parameters for the committers of each pipeline.
     pipeline_config: PipelineConfig,
 
+    /// Connection to Kafka, for the Kafka sinks.
+    #[cfg(feature = "kafka")]
+    kafka_config: KafkaConfig,
+
     /// Optional override of the checkpoint lowerbound.
     first_checkpoint: Option<u64>,
 
@@ -70,6 +78,10 @@ pub struct IndexerConfig {
     #[command(flatten)]
     pub pipeline_config: PipelineConfig,
 
+    #[cfg(feature = "kafka")]
+    #[command(flatten)]
+    pub kafka_config: KafkaConfig,
+
     /// Override for the checkpoint to start ingestion from -- useful for backfills. By default,
     /// ingestion will start just after the lowest checkpoint watermark across all active
     /// pipelines.
@@ -100,6 +112,8 @@ impl Indexer {
         let IndexerConfig {
             ingestion_config,
             pipeline_config,
+            #[cfg(feature = "kafka")]
+            kafka_config,
             first_checkpoint,
             last_checkpoint,
             pipeline,
@@ -126,6 +140,8 @@ impl Indexer {
             metrics_service,
             ingestion_service,
             pipeline_config,
+            #[cfg(feature = "kafka")]
+            kafka_config,
             first_checkpoint,
             last_checkpoint,
             enabled_pipelines: pipeline.into_iter().collect(),
@@ -151,6 +167,9 @@ impl Indexer {
     /// Concurrent pipelines commit checkpoint data out-of-order to maximise throughput, and they
     /// keep the watermark table up-to-date with the highest point they can guarantee all data
     /// exists for, for their pipeline.
+    ///
+    /// If the indexer is configured with a shard, it only writes the checkpoints in that shard,
+    /// and the pipeline's watermark is advanced from the watermarks of all its shards.
     pub async fn concurrent_pipeline<H: concurrent::Handler + 'static>(&mut self) -> Result<()> {
         let Some(watermark) = self.add_pipeline::<H>().await? else {
             return Ok(());
@@ -198,6 +217,12 @@ impl Indexer {
             return Ok(());
         };
 
+        ensure!(
+            self.pipeline_config.shard.is_none(),
+            "Sequential pipeline {} cannot be sharded",
+            H::NAME,
+        );
+
         // For a sequential pipeline, data must be written in the order of checkpoints.
         // Hence, we do not allow the first_checkpoint override to be in arbitrary positions.
         self.check_first_checkpoint_consistency::<H>(&watermark)?;
@@ -221,6 +246,63 @@ impl Indexer {
         Ok(())
     }
 
+    /// Adds a new Kafka sink to this indexer and starts it up. Although its tasks have started,
+    /// they will be idle until the ingestion service starts, and serves it checkpoint data. Kafka
+    /// sinks are skipped if no Kafka brokers have been configured.
+    ///
+    /// Kafka sinks publish the values of their handler to a topic, in checkpoint order, with
+    /// exactly-once semantics: Checkpoints are published in Kafka transactions that also publish
+    /// the sink's watermark, and the sink resumes from the watermark it last published, which is
+    /// also written to the watermarks table.
+    ///
+    /// Kafka sinks are only available if the indexer is built with the `kafka` feature.
+    #[cfg(feature = "kafka")]
+    pub async fn kafka_sink<H: kafka::Handler + 'static>(&mut self) -> Result<()> {
+        if self.kafka_config.kafka_brokers.is_none() {
+            info!("Kafka brokers not configured, skipping sink {}", H::NAME);
+            return Ok(());
+        }
+
+        let Some(watermark) = self.add_pipeline::<H>().await? else {
+            return Ok(());
+        };
+
+        ensure!(
+            self.pipeline_config.shard.is_none(),
+            "Kafka sink {} cannot be sharded",
+            H::NAME,
+        );
+
+        // Creating the producer aborts any transaction left open by a previous run. After that, the
+        // watermark in the database may still lag behind the one published to Kafka, in which case
+        // ingestion will start earlier than necessary, and the sink will discard checkpoints that
+        // it has already published.
+        let (producer, watermark) =
+            kafka::start::<H>(&self.kafka_config, &self.db, watermark).await?;
+
+        // Like sequential pipelines, sinks publish data in checkpoint order.
+        self.check_first_checkpoint_consistency::<H>(&watermark)?;
+
+        let (checkpoint_rx, watermark_tx) = self.ingestion_service.subscribe();
+
+        let (processor, committer) = kafka::pipeline::<H>(
+            watermark,
+            self.pipeline_config.clone(),
+            self.kafka_config.clone(),
+            producer,
+            self.db.clone(),
+            checkpoint_rx,
+            watermark_tx,
+            self.metrics.clone(),
+            self.cancel.clone(),
+        );
+
+        self.handles.push(processor);
+        self.handles.push(committer);
+
+        Ok(())
+    }
+
     /// Checks that the first checkpoint override is consistent with the watermark for the pipeline.
     /// If the watermark does not exist, the override can be anything. If the watermark exists, the
     /// override must not leave any gap in the data: it can be in the past, or at the tip of the
@@ -231,7 +313,7 @@ impl Indexer {
     ) -> Result<()> {
         if let (Some(watermark), Some(first_checkpoint)) = (watermark, self.first_checkpoint) {
             ensure!(
-                first_checkpoint as i64 <= watermark.checkpoint_hi_inclusive + 1,
+                first_checkpoint <= self.next_checkpoint(Some(watermark)),
                 "For pipeline {}, first checkpoint override {} is too far ahead of watermark {}. This could create gaps in the data.",
                 P::NAME,
                 first_checkpoint,
@@ -259,13 +341,26 @@ impl Indexer {
             .first_checkpoint
             .unwrap_or(self.first_checkpoint_from_watermark);
 
-        let last_checkpoint = self.last_checkpoint.unwrap_or(u64::MAX);
+        let shard = self.pipeline_config.shard;
+        let last_checkpoint = self
+            .last_checkpoint
+            .unwrap_or(u64::MAX)
+            .min(shard.and_then(|s| s.last()).unwrap_or(u64::MAX));
+
+        info!(
+            first_checkpoint,
+            last_checkpoint = ?self.last_checkpoint,
+            shard = ?shard.map(|s| s.to_string()),
+            "Ingestion range",
+        );
 
-        info!(first_checkpoint, last_checkpoint = ?self.last_checkpoint, "Ingestion range");
+        // Shards only fetch the checkpoints they write.
+        let checkpoints = (first_checkpoint..=last_checkpoint)
+            .filter(move |cp| shard.map_or(true, |s| s.owns(*cp)));
 
         let (regulator_handle, broadcaster_handle) = self
             .ingestion_service
-            .run(first_checkpoint..=last_checkpoint)
+            .run(checkpoints)
             .await
             .context("Failed to start ingestion service")?;
 
@@ -306,12 +401,27 @@ impl Indexer {
             .await
             .with_context(|| format!("Failed to get watermark for {}", P::NAME))?;
 
+        // Shards track their progress separately from the pipeline.
+        let watermark = match self.pipeline_config.shard {
+            Some(shard) => shard::resume_watermark(&mut conn, P::NAME, shard, watermark).await?,
+            None => watermark,
+        };
+
         // TODO(amnn): Test this (depends on supporting migrations and tempdb).
-        self.first_checkpoint_from_watermark = watermark
-            .as_ref()
-            .map_or(0, |w| w.checkpoint_hi_inclusive as u64 + 1)
+        self.first_checkpoint_from_watermark = self
+            .next_checkpoint(watermark.as_ref())
             .min(self.first_checkpoint_from_watermark);
 
         Ok(Some(watermark))
     }
+
+    /// The first checkpoint that this indexer needs to write after `watermark`, taking into account
+    /// the shard it is writing, if any.
+    fn next_checkpoint(&self, watermark: Option<&CommitterWatermark>) -> u64 {
+        let hi = watermark.map(|w| w.checkpoint_hi_inclusive as u64);
+        match self.pipeline_config.shard {
+            Some(shard) => shard.next_after(hi),
+            None => hi.map_or(0, |hi| hi + 1),
+        }
+    }
 }
@@ -3,24 +3,33 @@
 
 use anyhow::{Context, Result};
 use clap::Parser;
+use sui_indexer_alt::api::ApiService;
 use sui_indexer_alt::args::Command;
 use sui_indexer_alt::bootstrap::bootstrap;
-use sui_indexer_alt::db::reset_database;
+use sui_indexer_alt::db::{reset_database, Db};
 use sui_indexer_alt::{
     args::Args,
     handlers::{
-        ev_emit_mod::EvEmitMod, ev_struct_inst::EvStructInst, kv_checkpoints::KvCheckpoints,
-        kv_objects::KvObjects, kv_transactions::KvTransactions, obj_versions::ObjVersions,
-        sum_coin_balances::SumCoinBalances, sum_displays::SumDisplays, sum_obj_types::SumObjTypes,
-        sum_packages::SumPackages, tx_affected_addresses::TxAffectedAddress,
-        tx_affected_objects::TxAffectedObjects, tx_balance_changes::TxBalanceChanges,
-        tx_calls_fun::TxCallsFun, tx_digests::TxDigests, tx_kinds::TxKinds,
-        wal_coin_balances::WalCoinBalances, wal_obj_types::WalObjTypes,
+        cp_timestamps::CpTimestamps, ev_emit_mod::EvEmitMod, ev_struct_inst::EvStructInst,
+        hist_coin_balances::HistCoinBalances, hist_obj_types::HistObjTypes,
+        kv_checkpoints::KvCheckpoints, kv_objects::KvObjects, kv_transactions::KvTransactions,
+        obj_versions::ObjVersions, sum_coin_balances::SumCoinBalances, sum_displays::SumDisplays,
+        sum_obj_types::SumObjTypes, sum_packages::SumPackages,
+        tx_affected_addresses::TxAffectedAddress, tx_affected_objects::TxAffectedObjects,
+        tx_balance_changes::TxBalanceChanges, tx_calls_fun::TxCallsFun, tx_digests::TxDigests,
+        tx_kinds::TxKinds, wal_coin_balances::WalCoinBalances, wal_obj_types::WalObjTypes,
     },
+    task::graceful_shutdown,
     Indexer,
 };
 use tokio_util::sync::CancellationToken;
 
+#[cfg(feature = "kafka")]
+use sui_indexer_alt::handlers::{
+    kafka_checkpoints::KafkaCheckpoints, kafka_obj_versions::KafkaObjVersions,
+    kafka_tx_affected_addresses::KafkaTxAffectedAddresses,
+};
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
@@ -42,8 +51,11 @@ async fn main() -> Result<()> {
 
             bootstrap(&indexer, retry_interval, cancel.clone()).await?;
 
+            indexer.concurrent_pipeline::<CpTimestamps>().await?;
             indexer.concurrent_pipeline::<EvEmitMod>().await?;
             indexer.concurrent_pipeline::<EvStructInst>().await?;
+            indexer.concurrent_pipeline::<HistCoinBalances>().await?;
+            indexer.concurrent_pipeline::<HistObjTypes>().await?;
             indexer.concurrent_pipeline::<KvCheckpoints>().await?;
             indexer.concurrent_pipeline::<KvObjects>().await?;
             indexer.concurrent_pipeline::<KvTransactions>().await?;
@@ -62,11 +74,30 @@ async fn main() -> Result<()> {
             indexer.sequential_pipeline::<SumObjTypes>(lag).await?;
             indexer.sequential_pipeline::<SumPackages>(None).await?;
 
+            #[cfg(feature = "kafka")]
+            {
+                indexer.kafka_sink::<KafkaCheckpoints>().await?;
+                indexer.kafka_sink::<KafkaObjVersions>().await?;
+                indexer.kafka_sink::<KafkaTxAffectedAddresses>().await?;
+            }
+
             let h_indexer = indexer.run().await.context("Failed to start indexer")?;
 
             cancel.cancelled().await;
             let _ = h_indexer.await;
         }
+        Command::Api { api_config } => {
+            let db = Db::new(args.db_config)
+                .await
+                .context("Failed to connect to database")?;
+
+            let h_api = ApiService::new(api_config, db, cancel.clone())
+                .run()
+                .await
+                .context("Failed to start API service")?;
+
+            graceful_shutdown(vec![h_api], cancel).await;
+        }
         Command::ResetDatabase { skip_migrations } => {
             reset_database(args.db_config, skip_migrations).await?;
         }
@@ -7,7 +7,7 @@ use sui_field_count::FieldCount;
 use sui_protocol_config::{Chain, ProtocolVersion};
 use sui_types::digests::{ChainIdentifier, CheckpointDigest};
 
-use crate::schema::{kv_checkpoints, kv_genesis};
+use crate::schema::{cp_timestamps, kv_checkpoints, kv_genesis};
 
 #[derive(Insertable, Debug, Clone, FieldCount)]
 #[diesel(table_name = kv_checkpoints)]
@@ -19,6 +19,13 @@ pub struct StoredCheckpoint {
     pub checkpoint_contents: Vec<u8>,
 }
 
+#[derive(Insertable, Debug, Clone, FieldCount)]
+#[diesel(table_name = cp_timestamps)]
+pub struct StoredCpTimestamp {
+    pub cp_sequence_number: i64,
+    pub timestamp_ms: i64,
+}
+
 #[derive(Insertable, Selectable, Queryable, Debug, Clone)]
 #[diesel(table_name = kv_genesis)]
 pub struct StoredGenesis {
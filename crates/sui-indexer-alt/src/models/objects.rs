@@ -9,7 +9,8 @@ use sui_field_count::FieldCount;
 use sui_types::base_types::ObjectID;
 
 use crate::schema::{
-    kv_objects, obj_versions, sum_coin_balances, sum_obj_types, wal_coin_balances, wal_obj_types,
+    hist_coin_balances, hist_obj_types, kv_objects, obj_versions, sum_coin_balances, sum_obj_types,
+    wal_coin_balances, wal_obj_types,
 };
 
 #[derive(Insertable, Debug, Clone, FieldCount)]
@@ -98,6 +99,31 @@ pub struct StoredWalObjType {
     pub cp_sequence_number: i64,
 }
 
+#[derive(Insertable, Selectable, Queryable, QueryableByName, Debug, Clone)]
+#[diesel(table_name = hist_coin_balances, primary_key(object_id, object_version))]
+pub struct StoredHistCoinBalance {
+    pub object_id: Vec<u8>,
+    pub object_version: i64,
+    pub owner_id: Option<Vec<u8>>,
+    pub coin_type: Option<Vec<u8>>,
+    pub coin_balance: Option<i64>,
+    pub cp_sequence_number: i64,
+}
+
+#[derive(Insertable, Selectable, Queryable, QueryableByName, Debug, Clone)]
+#[diesel(table_name = hist_obj_types, primary_key(object_id, object_version))]
+pub struct StoredHistObjType {
+    pub object_id: Vec<u8>,
+    pub object_version: i64,
+    pub owner_kind: Option<StoredOwnerKind>,
+    pub owner_id: Option<Vec<u8>>,
+    pub package: Option<Vec<u8>>,
+    pub module: Option<String>,
+    pub name: Option<String>,
+    pub instantiation: Option<Vec<u8>>,
+    pub cp_sequence_number: i64,
+}
+
 impl<DB: Backend> serialize::ToSql<SmallInt, DB> for StoredOwnerKind
 where
     i16: serialize::ToSql<SmallInt, DB>,
@@ -6,6 +6,7 @@ use std::borrow::Cow;
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
+use serde::{Deserialize, Serialize};
 use sui_field_count::FieldCount;
 
 use crate::{db::Connection, schema::watermarks};
@@ -25,7 +26,18 @@ pub struct StoredWatermark {
 }
 
 /// Fields that the committer is responsible for setting.
-#[derive(AsChangeset, Selectable, Queryable, Debug, Clone, FieldCount)]
+#[derive(
+    AsChangeset,
+    Selectable,
+    Queryable,
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    FieldCount,
+)]
 #[diesel(table_name = watermarks)]
 pub struct CommitterWatermark<'p> {
     pub pipeline: Cow<'p, str>,
@@ -37,10 +49,7 @@ pub struct CommitterWatermark<'p> {
 
 impl CommitterWatermark<'static> {
     /// Get the current high watermark for the pipeline.
-    pub async fn get(
-        conn: &mut Connection<'_>,
-        pipeline: &'static str,
-    ) -> QueryResult<Option<Self>> {
+    pub async fn get(conn: &mut Connection<'_>, pipeline: &str) -> QueryResult<Option<Self>> {
         watermarks::table
             .select(CommitterWatermark::as_select())
             .filter(watermarks::pipeline.eq(pipeline))
@@ -48,6 +57,17 @@ impl CommitterWatermark<'static> {
             .await
             .optional()
     }
+
+    /// Get the high watermarks of the shards writing to the pipeline (see [Shard]).
+    ///
+    /// [Shard]: crate::pipeline::Shard
+    pub async fn get_shards(conn: &mut Connection<'_>, pipeline: &str) -> QueryResult<Vec<Self>> {
+        watermarks::table
+            .select(CommitterWatermark::as_select())
+            .filter(watermarks::pipeline.like(format!("{pipeline}@%")))
+            .load(conn)
+            .await
+    }
 }
 
 impl<'p> CommitterWatermark<'p> {
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
+    borrow::Cow,
     cmp::Ordering,
     collections::{btree_map::Entry, BTreeMap},
     sync::Arc,
@@ -21,7 +22,8 @@ use crate::{
     metrics::IndexerMetrics,
     models::watermarks::CommitterWatermark,
     pipeline::{
-        PipelineConfig, WatermarkPart, LOUD_WATERMARK_UPDATE_INTERVAL, WARN_PENDING_WATERMARKS,
+        shard, PipelineConfig, WatermarkPart, LOUD_WATERMARK_UPDATE_INTERVAL,
+        WARN_PENDING_WATERMARKS,
     },
 };
 
@@ -41,6 +43,10 @@ use super::Handler;
 /// a warning, as this could be the indication of a memory leak, and the caller probably intended
 /// to run the indexer with watermarking disabled (e.g. if they are running a backfill).
 ///
+/// If the `config` specifies a shard, the task tracks the shard's watermark in its own row, which
+/// only needs to cover the checkpoints in the shard, and after updating it, advances the
+/// pipeline's row as far as the watermarks of all its shards allow.
+///
 /// The task regularly traces its progress, outputting at a higher log level every
 /// [LOUD_WATERMARK_UPDATE_INTERVAL]-many checkpoints.
 ///
@@ -70,11 +76,25 @@ pub(super) fn watermark<H: Handler + 'static>(
         // watermark as much as possible without going over any holes in the sequence of
         // checkpoints (entirely missing watermarks, or incomplete watermarks).
         let mut precommitted: BTreeMap<u64, WatermarkPart> = BTreeMap::new();
+
+        // Shards only see the checkpoints they own, so the next checkpoint is not necessarily the
+        // one after the watermark.
+        let shard = config.shard;
+        let name: Cow<'static, str> = match shard {
+            Some(shard) => shard.watermark_name(H::NAME).into(),
+            None => H::NAME.into(),
+        };
+
+        let next_after = |hi: Option<i64>| match shard {
+            Some(shard) => shard.next_after(hi.map(|hi| hi as u64)) as i64,
+            None => hi.map_or(0, |hi| hi + 1),
+        };
+
         let (mut watermark, mut next_checkpoint) = if let Some(watermark) = initial_watermark {
-            let next = watermark.checkpoint_hi_inclusive + 1;
+            let next = next_after(Some(watermark.checkpoint_hi_inclusive));
             (watermark, next)
         } else {
-            (CommitterWatermark::initial(H::NAME.into()), 0)
+            (CommitterWatermark::initial(name.clone()), next_after(None))
         };
 
         // The watermark task will periodically output a log message at a higher log level to
@@ -127,8 +147,9 @@ pub(super) fn watermark<H: Handler + 'static>(
                             // This is the next checkpoint -- include it.
                             Ordering::Equal => {
                                 watermark = pending.remove().watermark;
+                                watermark.pipeline = name.clone();
                                 watermark_needs_update = true;
-                                next_checkpoint += 1;
+                                next_checkpoint = next_after(Some(next_checkpoint));
                             }
 
                             // Next pending checkpoint is in the past. Out of order watermarks can
@@ -225,6 +246,19 @@ pub(super) fn watermark<H: Handler + 'static>(
                                         .set(watermark.timestamp_ms_hi_inclusive);
                                 }
 
+                                // Like the shard's own watermark, it's OK for the pipeline's
+                                // watermark to lag, and the next update will try again.
+                                if updated && shard.is_some() {
+                                    if let Err(e) =
+                                        shard::update_pipeline_watermark(&mut conn, H::NAME).await
+                                    {
+                                        warn!(
+                                            pipeline = H::NAME,
+                                            "Error updating watermark from shards: {e}",
+                                        );
+                                    }
+                                }
+
                                 if watermark.checkpoint_hi_inclusive > next_loud_watermark_update {
                                     next_loud_watermark_update += LOUD_WATERMARK_UPDATE_INTERVAL;
                                     info!(
@@ -0,0 +1,480 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{cmp::Ordering, collections::BTreeMap, sync::Arc};
+
+use futures::future::try_join_all;
+use mysten_metrics::spawn_monitored_task;
+use rdkafka::{
+    error::KafkaError,
+    message::{Header, OwnedHeaders},
+    producer::{FutureProducer, FutureRecord, Producer},
+};
+use tokio::{
+    sync::mpsc,
+    task::JoinHandle,
+    time::{interval, MissedTickBehavior},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    db::Db,
+    metrics::IndexerMetrics,
+    models::watermarks::CommitterWatermark,
+    pipeline::{Indexed, PipelineConfig, LOUD_WATERMARK_UPDATE_INTERVAL, WARN_PENDING_WATERMARKS},
+};
+
+use super::{Handler, KafkaConfig};
+
+/// Number of times to retry committing a transaction that failed with a retriable error, before
+/// giving up on it.
+const MAX_COMMIT_ATTEMPTS: usize = 3;
+
+/// Number of times to try publishing a batch whose transactions keep being aborted, before
+/// stopping the indexer.
+const MAX_PUBLISH_ATTEMPTS: usize = 10;
+
+/// A record to publish, ready to be borrowed by a [FutureRecord].
+struct Record {
+    topic: &'static str,
+    key: Vec<u8>,
+    payload: Vec<u8>,
+    checkpoint: String,
+}
+
+/// Why publishing a batch failed.
+enum Failure {
+    /// The transaction was aborted, and the batch can be published again.
+    Retry(anyhow::Error),
+
+    /// The producer can no longer be used, e.g. because it was fenced off by another producer with
+    /// the same transactional ID.
+    Fatal(anyhow::Error),
+}
+
+/// The committer task publishes values to Kafka, in checkpoint order.
+///
+/// Data arrives out of order, grouped by checkpoint, on `rx`. The task orders them and waits to
+/// publish them until either a configurable polling interval has passed (controlled by
+/// `config.collect_interval`), or `H::MIN_EAGER_ROWS` rows have been accumulated and we have
+/// received the next expected checkpoint.
+///
+/// Batches span whole checkpoints, and are published in a single Kafka transaction along with the
+/// sink's new watermark. The watermark is then written to the database, and sent back to the
+/// ingestion service, to unblock its regulator. Checkpoints at or below the watermark are
+/// discarded, so that they are not published twice.
+///
+/// The task can be shutdown using its `cancel` token or if either of its channels are closed. It
+/// cancels the indexer if publishing fails in a way that cannot be retried, or if publishing the
+/// same batch fails `MAX_PUBLISH_ATTEMPTS` times in a row.
+pub(super) fn committer<H: Handler + 'static>(
+    config: PipelineConfig,
+    kafka_config: KafkaConfig,
+    producer: FutureProducer,
+    watermark: Option<CommitterWatermark<'static>>,
+    mut rx: mpsc::Receiver<Indexed<H>>,
+    tx: mpsc::UnboundedSender<(&'static str, u64)>,
+    db: Db,
+    metrics: Arc<IndexerMetrics>,
+    cancel: CancellationToken,
+) -> JoinHandle<()> {
+    spawn_monitored_task!(async move {
+        let mut poll = interval(config.collect_interval);
+        poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        // Records of the next batch to publish, from checkpoints up to and including
+        // `next_checkpoint - 1`. The batch is kept across ticks if publishing it failed, and
+        // `attempt` counts the failures.
+        let mut attempt = 0;
+        let mut batch: Vec<Record> = vec![];
+        let mut batch_checkpoints = 0;
+
+        let (mut watermark, mut next_checkpoint) = if let Some(watermark) = watermark {
+            let next = watermark.checkpoint_hi_inclusive as u64 + 1;
+            (watermark, next)
+        } else {
+            (CommitterWatermark::initial(H::NAME.into()), 0)
+        };
+
+        let mut next_loud_watermark_update =
+            watermark.checkpoint_hi_inclusive + LOUD_WATERMARK_UPDATE_INTERVAL;
+
+        // Data for checkpoints that haven't been added to the batch yet.
+        let mut pending: BTreeMap<u64, Indexed<H>> = BTreeMap::new();
+        let mut pending_rows = 0;
+
+        info!(pipeline = H::NAME, ?watermark, "Starting committer");
+
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    info!(pipeline = H::NAME, "Shutdown received");
+                    break;
+                }
+
+                _ = poll.tick() => {
+                    if pending.len() > WARN_PENDING_WATERMARKS {
+                        warn!(
+                            pipeline = H::NAME,
+                            pending = pending.len(),
+                            "Pipeline has a large number of pending watermarks",
+                        );
+                    }
+
+                    let guard = metrics
+                        .collector_gather_latency
+                        .with_label_values(&[H::NAME])
+                        .start_timer();
+
+                    while batch_checkpoints < H::MAX_BATCH_CHECKPOINTS {
+                        let Some(entry) = pending.first_entry() else {
+                            break;
+                        };
+
+                        match next_checkpoint.cmp(entry.key()) {
+                            // Next pending checkpoint is from the future.
+                            Ordering::Less => break,
+
+                            // This is the next checkpoint -- include it.
+                            Ordering::Equal => {
+                                let indexed = entry.remove();
+                                pending_rows -= indexed.len();
+                                let checkpoint = indexed.checkpoint().to_string();
+                                for value in &indexed.values {
+                                    let payload = match H::payload(value) {
+                                        Ok(payload) => payload,
+                                        Err(e) => {
+                                            error!(pipeline = H::NAME, "Error encoding record: {e}");
+                                            cancel.cancel();
+                                            return;
+                                        }
+                                    };
+
+                                    batch.push(Record {
+                                        topic: H::TOPIC,
+                                        key: H::key(value),
+                                        payload,
+                                        checkpoint: checkpoint.clone(),
+                                    });
+                                }
+
+                                batch_checkpoints += 1;
+                                watermark = indexed.watermark;
+                                next_checkpoint += 1;
+                            }
+
+                            // Next pending checkpoint was already published, ignore it to avoid
+                            // publishing it twice.
+                            Ordering::Greater => {
+                                metrics
+                                    .total_watermarks_out_of_order
+                                    .with_label_values(&[H::NAME])
+                                    .inc();
+                                let indexed = entry.remove();
+                                pending_rows -= indexed.len();
+                            }
+                        }
+                    }
+
+                    let elapsed = guard.stop_and_record();
+                    debug!(
+                        pipeline = H::NAME,
+                        elapsed_ms = elapsed * 1000.0,
+                        rows = batch.len(),
+                        pending = pending_rows,
+                        "Gathered batch",
+                    );
+
+                    if batch_checkpoints == 0 {
+                        if rx.is_closed() && rx.is_empty() {
+                            info!(pipeline = H::NAME, "Processor closed channel, pending rows empty");
+                            break;
+                        }
+
+                        continue;
+                    }
+
+                    metrics
+                        .collector_batch_size
+                        .with_label_values(&[H::NAME])
+                        .observe(batch.len() as f64);
+
+                    metrics
+                        .total_committer_batches_attempted
+                        .with_label_values(&[H::NAME])
+                        .inc();
+
+                    metrics
+                        .watermark_epoch
+                        .with_label_values(&[H::NAME])
+                        .set(watermark.epoch_hi_inclusive);
+
+                    metrics
+                        .watermark_checkpoint
+                        .with_label_values(&[H::NAME])
+                        .set(watermark.checkpoint_hi_inclusive);
+
+                    metrics
+                        .watermark_transaction
+                        .with_label_values(&[H::NAME])
+                        .set(watermark.tx_hi);
+
+                    metrics
+                        .watermark_timestamp_ms
+                        .with_label_values(&[H::NAME])
+                        .set(watermark.timestamp_ms_hi_inclusive);
+
+                    let guard = metrics
+                        .committer_commit_latency
+                        .with_label_values(&[H::NAME])
+                        .start_timer();
+
+                    let published = publish(&producer, &kafka_config, &batch, &watermark).await;
+                    let elapsed = guard.stop_and_record();
+
+                    match published {
+                        Ok(()) => {}
+
+                        Err(Failure::Retry(e)) if attempt + 1 < MAX_PUBLISH_ATTEMPTS => {
+                            warn!(
+                                pipeline = H::NAME,
+                                elapsed_ms = elapsed * 1000.0,
+                                attempt,
+                                committed = batch.len(),
+                                pending = pending_rows,
+                                "Error publishing batch: {e}",
+                            );
+
+                            attempt += 1;
+                            continue;
+                        }
+
+                        Err(Failure::Retry(e)) => {
+                            error!(
+                                pipeline = H::NAME,
+                                attempt,
+                                "Error publishing batch, giving up: {e}",
+                            );
+                            cancel.cancel();
+                            break;
+                        }
+
+                        Err(Failure::Fatal(e)) => {
+                            error!(pipeline = H::NAME, "Error publishing batch: {e}");
+                            cancel.cancel();
+                            break;
+                        }
+                    }
+
+                    debug!(
+                        pipeline = H::NAME,
+                        elapsed_ms = elapsed * 1000.0,
+                        attempt,
+                        committed = batch.len(),
+                        pending = pending_rows,
+                        "Published batch",
+                    );
+
+                    metrics
+                        .total_committer_batches_succeeded
+                        .with_label_values(&[H::NAME])
+                        .inc();
+
+                    metrics
+                        .total_committer_rows_committed
+                        .with_label_values(&[H::NAME])
+                        .inc_by(batch.len() as u64);
+
+                    // The watermark published to Kafka is the source of truth, so a failure to
+                    // record it in the database is not fatal: It will be caught up when the sink
+                    // next writes its watermark, or restarts.
+                    match db.connect().await {
+                        Ok(mut conn) => match watermark.update(&mut conn).await {
+                            Ok(_) => {
+                                metrics
+                                    .watermark_epoch_in_db
+                                    .with_label_values(&[H::NAME])
+                                    .set(watermark.epoch_hi_inclusive);
+
+                                metrics
+                                    .watermark_checkpoint_in_db
+                                    .with_label_values(&[H::NAME])
+                                    .set(watermark.checkpoint_hi_inclusive);
+
+                                metrics
+                                    .watermark_transaction_in_db
+                                    .with_label_values(&[H::NAME])
+                                    .set(watermark.tx_hi);
+
+                                metrics
+                                    .watermark_timestamp_in_db_ms
+                                    .with_label_values(&[H::NAME])
+                                    .set(watermark.timestamp_ms_hi_inclusive);
+                            }
+
+                            Err(e) => {
+                                warn!(pipeline = H::NAME, "Error writing watermark: {e}");
+                            }
+                        },
+
+                        Err(e) => {
+                            warn!(pipeline = H::NAME, "Failed to get connection for DB: {e}");
+                        }
+                    }
+
+                    if watermark.checkpoint_hi_inclusive > next_loud_watermark_update {
+                        next_loud_watermark_update += LOUD_WATERMARK_UPDATE_INTERVAL;
+                        info!(
+                            pipeline = H::NAME,
+                            epoch = watermark.epoch_hi_inclusive,
+                            checkpoint = watermark.checkpoint_hi_inclusive,
+                            transaction = watermark.tx_hi,
+                            timestamp = %watermark.timestamp(),
+                            "Watermark",
+                        );
+                    } else {
+                        debug!(
+                            pipeline = H::NAME,
+                            epoch = watermark.epoch_hi_inclusive,
+                            checkpoint = watermark.checkpoint_hi_inclusive,
+                            transaction = watermark.tx_hi,
+                            timestamp = %watermark.timestamp(),
+                            "Watermark",
+                        );
+                    }
+
+                    // Ignore the result -- the ingestion service will close this channel once it
+                    // is done, but there may still be checkpoints buffered that need processing.
+                    let _ = tx.send((H::NAME, watermark.checkpoint_hi_inclusive as u64));
+
+                    batch.clear();
+                    batch_checkpoints = 0;
+                    attempt = 0;
+
+                    // If the next checkpoint is already pending, the committer can do more work
+                    // immediately (without waiting).
+                    //
+                    // Otherwise, if its channels have been closed, we know that it is guaranteed
+                    // not to make any more progress, and we can stop the task.
+                    if pending
+                        .first_key_value()
+                        .is_some_and(|(next, _)| *next <= next_checkpoint)
+                    {
+                        poll.reset_immediately();
+                    } else if rx.is_closed() && rx.is_empty() {
+                        info!(pipeline = H::NAME, "Processor closed channel, pending rows empty");
+                        break;
+                    }
+                }
+
+                Some(indexed) = rx.recv() => {
+                    pending_rows += indexed.len();
+                    pending.insert(indexed.checkpoint(), indexed);
+
+                    // Once data has been inserted, check if we need to schedule a publish before
+                    // the next polling interval. This is appropriate if there are a minimum number
+                    // of rows to publish, and the next checkpoint is available.
+                    if pending_rows < H::MIN_EAGER_ROWS {
+                        continue;
+                    }
+
+                    if pending
+                        .first_key_value()
+                        .is_some_and(|(next, _)| *next <= next_checkpoint)
+                    {
+                        poll.reset_immediately();
+                    }
+                }
+            }
+        }
+
+        info!(pipeline = H::NAME, ?watermark, "Stopping committer");
+    })
+}
+
+/// Publish `batch` and `watermark` in a single transaction.
+async fn publish(
+    producer: &FutureProducer,
+    config: &KafkaConfig,
+    batch: &[Record],
+    watermark: &CommitterWatermark<'static>,
+) -> Result<(), Failure> {
+    let watermark_payload = serde_json::to_vec(watermark)
+        .map_err(|e| Failure::Fatal(anyhow::anyhow!("Failed to serialize watermark: {e}")))?;
+
+    // A transaction that could not be started may have been left in an unknown state, and must
+    // be aborted before trying again.
+    if let Err(e) = producer.begin_transaction() {
+        return Err(abort(producer, config, e).await);
+    }
+
+    let records = batch.iter().map(|record| {
+        FutureRecord::to(record.topic)
+            .key(record.key.as_slice())
+            .payload(record.payload.as_slice())
+            .headers(OwnedHeaders::new().insert(Header {
+                key: "checkpoint",
+                value: Some(record.checkpoint.as_str()),
+            }))
+    });
+
+    let watermark_record = FutureRecord::to(&config.kafka_watermark_topic)
+        .key(watermark.pipeline.as_bytes())
+        .payload(watermark_payload.as_slice());
+
+    let sent = try_join_all(
+        records
+            .chain([watermark_record])
+            .map(|record| producer.send(record, config.kafka_timeout)),
+    )
+    .await;
+
+    if let Err((e, _)) = sent {
+        return Err(abort(producer, config, e).await);
+    }
+
+    // A commit that failed with a retriable error may or may not have gone through, and must be
+    // retried rather than aborted.
+    let mut attempt = 0;
+    loop {
+        let producer_ = producer.clone();
+        let timeout = config.kafka_timeout;
+        let committed = tokio::task::spawn_blocking(move || producer_.commit_transaction(timeout))
+            .await
+            .map_err(|e| Failure::Fatal(e.into()))?;
+
+        match committed {
+            Ok(()) => return Ok(()),
+
+            Err(KafkaError::Transaction(rd))
+                if rd.is_retriable() && attempt < MAX_COMMIT_ATTEMPTS =>
+            {
+                debug!(attempt, "Retrying commit: {rd}");
+                attempt += 1;
+            }
+
+            Err(e) => return Err(abort(producer, config, e).await),
+        }
+    }
+}
+
+/// Abort the current transaction after it failed with `e`, if that is possible. Errors that are
+/// not specific to transactions (e.g. a record could not be delivered) are retried this way.
+async fn abort(producer: &FutureProducer, config: &KafkaConfig, e: KafkaError) -> Failure {
+    if is_fatal(&e) {
+        return Failure::Fatal(e.into());
+    }
+
+    let producer = producer.clone();
+    let timeout = config.kafka_timeout;
+    match tokio::task::spawn_blocking(move || producer.abort_transaction(timeout)).await {
+        Ok(Ok(())) => Failure::Retry(e.into()),
+        Ok(Err(abort)) => Failure::Fatal(anyhow::anyhow!("{e}, and failed to abort: {abort}")),
+        Err(join) => Failure::Fatal(join.into()),
+    }
+}
+
+fn is_fatal(e: &KafkaError) -> bool {
+    matches!(e, KafkaError::Transaction(rd) if rd.is_fatal())
+}
@@ -0,0 +1,623 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use anyhow::{bail, Context};
+use rdkafka::{
+    admin::{AdminClient, AdminOptions, NewTopic, TopicReplication},
+    client::DefaultClientContext,
+    consumer::{BaseConsumer, Consumer},
+    error::KafkaError,
+    producer::{FutureProducer, Producer},
+    types::RDKafkaErrorCode,
+    ClientConfig, Message, Offset, TopicPartitionList,
+};
+use sui_types::full_checkpoint_content::CheckpointData;
+use tokio::{sync::mpsc, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::{db::Db, metrics::IndexerMetrics, models::watermarks::CommitterWatermark};
+
+use super::{processor::processor, PipelineConfig, Processor, PIPELINE_BUFFER};
+
+use self::committer::committer;
+
+mod committer;
+
+/// Handlers implement the logic for a given Kafka sink: How to process checkpoint data (by
+/// implementing [Processor]) into values, and how to turn those values into records for their
+/// topic.
+///
+/// Records are published in Kafka transactions, spanning one or more whole checkpoints, in
+/// checkpoint order. Each transaction also publishes the sink's watermark to the watermark topic,
+/// so the watermark in Kafka always reflects exactly the records that have been published, and is
+/// used to resume the sink without publishing records twice, even if the indexer stopped before it
+/// could update the sink's watermark in the database. Consumers must read with
+/// `isolation.level=read_committed` to benefit from this.
+///
+/// The sink's watermark is stored in the watermarks table under the name of its pipeline, so
+/// handlers for sinks need a different [Processor::NAME] from pipelines that write the same values
+/// to the database.
+pub trait Handler: Processor {
+    /// Topic to publish records to. Topics are not created by the sink, as the number of their
+    /// partitions and their retention depend on their consumers.
+    const TOPIC: &'static str = <Self as Processor>::NAME;
+
+    /// If at least this many rows are pending, the committer will publish them eagerly.
+    const MIN_EAGER_ROWS: usize = 50;
+
+    /// Maximum number of checkpoints to publish in a single transaction.
+    const MAX_BATCH_CHECKPOINTS: usize = 60;
+
+    /// The key of the record for `value`, which determines the partition it is published to, e.g.
+    /// the address or object ID it is about. Records with the same key are consumed in the order
+    /// they were published in.
+    fn key(value: &Self::Value) -> Vec<u8>;
+
+    /// The payload of the record for `value`.
+    fn payload(value: &Self::Value) -> anyhow::Result<Vec<u8>>;
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct KafkaConfig {
+    /// Comma-separated list of Kafka brokers to publish to. Kafka sinks are only run if this is
+    /// set.
+    #[arg(long)]
+    pub kafka_brokers: Option<String>,
+
+    /// Compacted topic that Kafka sinks publish their watermarks to, as part of each transaction.
+    /// It is created if it does not exist.
+    #[arg(long, default_value = "sui_indexer_alt_watermarks")]
+    kafka_watermark_topic: String,
+
+    /// Prefix of the transactional IDs of Kafka sinks' producers, which are suffixed with the name
+    /// of the sink. Starting a sink fences off any other producer with the same transactional ID.
+    #[arg(long, default_value = "sui-indexer-alt-")]
+    kafka_transactional_id_prefix: String,
+
+    /// Timeout for Kafka operations, including publishing a transaction.
+    #[arg(
+        long,
+        default_value = "60000",
+        value_name = "MILLISECONDS",
+        value_parser = |s: &str| s.parse().map(Duration::from_millis),
+    )]
+    kafka_timeout: Duration,
+
+    /// Additional librdkafka client properties, e.g. `security.protocol=SASL_SSL`.
+    #[arg(
+        long = "kafka-option",
+        value_name = "KEY=VALUE",
+        value_parser = parse_option,
+        action = clap::ArgAction::Append,
+    )]
+    kafka_options: Vec<(String, String)>,
+}
+
+impl KafkaConfig {
+    /// Client properties shared by producers, consumers and admin clients.
+    fn client_config(&self) -> anyhow::Result<ClientConfig> {
+        let Some(brokers) = &self.kafka_brokers else {
+            bail!("Kafka brokers not configured");
+        };
+
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", brokers);
+        for (key, value) in &self.kafka_options {
+            config.set(key, value);
+        }
+
+        Ok(config)
+    }
+}
+
+/// Start a new Kafka sink, served by the handler, `H`. Starting strictly after the `watermark` (or
+/// from the beginning if no watermark was provided).
+///
+/// Each sink consists of a processor which takes checkpoint data and breaks it down into values,
+/// and a committer which orders the values and publishes them through `producer`, a transactional
+/// producer created by [producer].
+///
+/// Like sequential pipelines, sinks share their watermarks with the ingestion service, which
+/// bounds the checkpoint height it pre-fetches to some constant additive factor above them.
+///
+/// The sink can be shutdown using its `cancel` token, and will also shutdown if any of its input or
+/// output channels close, or if publishing fails with an error that cannot be recovered from (e.g.
+/// another producer with the same transactional ID has been started).
+pub(crate) fn pipeline<H: Handler + 'static>(
+    initial_watermark: Option<CommitterWatermark<'static>>,
+    config: PipelineConfig,
+    kafka_config: KafkaConfig,
+    producer: FutureProducer,
+    db: Db,
+    checkpoint_rx: mpsc::Receiver<Arc<CheckpointData>>,
+    watermark_tx: mpsc::UnboundedSender<(&'static str, u64)>,
+    metrics: Arc<IndexerMetrics>,
+    cancel: CancellationToken,
+) -> (JoinHandle<()>, JoinHandle<()>) {
+    let (processor_tx, committer_rx) = mpsc::channel(H::FANOUT + PIPELINE_BUFFER);
+
+    let processor = processor::<H>(checkpoint_rx, processor_tx, metrics.clone(), cancel.clone());
+
+    let committer = committer::<H>(
+        config,
+        kafka_config,
+        producer,
+        initial_watermark,
+        committer_rx,
+        watermark_tx,
+        db,
+        metrics,
+        cancel,
+    );
+
+    (processor, committer)
+}
+
+/// Prepare to (re)start sink `H`: Create its transactional producer, and reconcile its watermark
+/// in the database with the one it last published to Kafka, returning the producer and the
+/// watermark to resume from.
+pub(crate) async fn start<H: Handler + 'static>(
+    config: &KafkaConfig,
+    db: &Db,
+    watermark: Option<CommitterWatermark<'static>>,
+) -> anyhow::Result<(FutureProducer, Option<CommitterWatermark<'static>>)> {
+    let broker = Kafka {
+        config,
+        pipeline: H::NAME,
+    };
+
+    resume(&broker, db, watermark).await
+}
+
+/// The operations on Kafka that a sink performs when it starts.
+#[async_trait::async_trait]
+trait Broker {
+    type Producer: Send;
+
+    /// Create the sink's transactional producer, fencing off any previous instance of the sink,
+    /// and aborting any transaction it left open.
+    async fn producer(&self) -> anyhow::Result<Self::Producer>;
+
+    /// The latest watermark that the sink published in a committed transaction, if any.
+    async fn published_watermark(&self) -> anyhow::Result<Option<CommitterWatermark<'static>>>;
+}
+
+/// The sink's connection to a Kafka cluster.
+struct Kafka<'c> {
+    config: &'c KafkaConfig,
+    pipeline: &'static str,
+}
+
+#[async_trait::async_trait]
+impl Broker for Kafka<'_> {
+    type Producer = FutureProducer;
+
+    async fn producer(&self) -> anyhow::Result<FutureProducer> {
+        let config = self.config;
+        let producer: FutureProducer = config
+            .client_config()?
+            .set(
+                "transactional.id",
+                format!("{}{}", config.kafka_transactional_id_prefix, self.pipeline),
+            )
+            .set("enable.idempotence", "true")
+            .set(
+                "transaction.timeout.ms",
+                config.kafka_timeout.as_millis().to_string(),
+            )
+            // Partition by the hash of the key in the same way as the Java client, so that
+            // consumers can predict which partition a key is in.
+            .set("partitioner", "murmur2_random")
+            .create()
+            .context("Failed to create Kafka producer")?;
+
+        let timeout = config.kafka_timeout;
+        let producer = tokio::task::spawn_blocking(move || {
+            producer.init_transactions(timeout).map(|_| producer)
+        })
+        .await?
+        .with_context(|| format!("Failed to initialize transactions for {}", self.pipeline))?;
+
+        Ok(producer)
+    }
+
+    async fn published_watermark(&self) -> anyhow::Result<Option<CommitterWatermark<'static>>> {
+        create_watermark_topic(self.config).await?;
+
+        let config = self.config.clone();
+        let pipeline = self.pipeline;
+        tokio::task::spawn_blocking(move || published_watermark(&config, pipeline))
+            .await?
+            .with_context(|| format!("Failed to read published watermark for {pipeline}"))
+    }
+}
+
+/// Create the sink's producer through `broker`, and then reconcile its `watermark` from the
+/// database with the one it last published.
+///
+/// The producer must be created first: Until it has aborted any transaction that a previous
+/// instance of the sink left open (e.g. because the indexer crashed while publishing), that
+/// transaction holds back the watermark topic's last stable offset, and the topic cannot be read to
+/// its end in `read_committed` mode.
+async fn resume<B: Broker>(
+    broker: &B,
+    db: &Db,
+    watermark: Option<CommitterWatermark<'static>>,
+) -> anyhow::Result<(B::Producer, Option<CommitterWatermark<'static>>)> {
+    let producer = broker.producer().await?;
+    let published = broker.published_watermark().await?;
+    let watermark = reconcile_watermark(db, watermark, published).await?;
+    Ok((producer, watermark))
+}
+
+/// Reconcile the sink's `watermark` in the database with the one it last `published` to Kafka,
+/// returning the watermark to resume from.
+///
+/// The watermark in the database is only updated after a transaction has been published, so it
+/// may be behind if the indexer stopped in between, in which case it is brought up-to-date.
+async fn reconcile_watermark(
+    db: &Db,
+    watermark: Option<CommitterWatermark<'static>>,
+    published: Option<CommitterWatermark<'static>>,
+) -> anyhow::Result<Option<CommitterWatermark<'static>>> {
+    match (watermark, published) {
+        (Some(watermark), Some(published))
+            if published.checkpoint_hi_inclusive < watermark.checkpoint_hi_inclusive =>
+        {
+            warn!(
+                pipeline = %watermark.pipeline,
+                ?watermark,
+                ?published,
+                "Watermark in the database is ahead of the watermark published to Kafka",
+            );
+            Ok(Some(watermark))
+        }
+
+        (watermark, None) => Ok(watermark),
+
+        (watermark, Some(published)) => {
+            if watermark.as_ref() != Some(&published) {
+                info!(
+                    pipeline = %published.pipeline,
+                    ?watermark,
+                    ?published,
+                    "Catching up with watermark published to Kafka",
+                );
+
+                let mut conn = db.connect().await?;
+                published.update(&mut conn).await?;
+            }
+
+            Ok(Some(published))
+        }
+    }
+}
+
+/// Create the watermark topic, compacted so that it only retains the latest watermark of each
+/// sink, if it does not exist yet.
+async fn create_watermark_topic(config: &KafkaConfig) -> anyhow::Result<()> {
+    let admin: AdminClient<DefaultClientContext> = config
+        .client_config()?
+        .create()
+        .context("Failed to create Kafka admin client")?;
+
+    // Use the broker's default replication factor.
+    let topic = NewTopic::new(
+        &config.kafka_watermark_topic,
+        1,
+        TopicReplication::Fixed(-1),
+    )
+    .set("cleanup.policy", "compact");
+
+    let results = admin
+        .create_topics(
+            [&topic],
+            &AdminOptions::new().operation_timeout(Some(config.kafka_timeout)),
+        )
+        .await?;
+
+    for result in results {
+        match result {
+            Ok(_) | Err((_, RDKafkaErrorCode::TopicAlreadyExists)) => {}
+            Err((topic, code)) => bail!("Failed to create topic {topic}: {code}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the watermark topic to its end, returning the latest watermark that `pipeline` published
+/// in a committed transaction, if any. This is a blocking operation.
+fn published_watermark(
+    config: &KafkaConfig,
+    pipeline: &str,
+) -> anyhow::Result<Option<CommitterWatermark<'static>>> {
+    let topic = &config.kafka_watermark_topic;
+    let consumer: BaseConsumer = config
+        .client_config()?
+        .set(
+            "group.id",
+            format!("{}{pipeline}", config.kafka_transactional_id_prefix),
+        )
+        .set("enable.auto.commit", "false")
+        .set("enable.partition.eof", "true")
+        .set("isolation.level", "read_committed")
+        .create()
+        .context("Failed to create Kafka consumer")?;
+
+    let metadata = consumer.fetch_metadata(Some(topic), config.kafka_timeout)?;
+    let mut partitions = TopicPartitionList::new();
+    for t in metadata.topics() {
+        for p in t.partitions() {
+            partitions.add_partition_offset(topic, p.id(), Offset::Beginning)?;
+        }
+    }
+
+    let mut remaining: BTreeSet<_> = partitions
+        .elements()
+        .iter()
+        .map(|e| e.partition())
+        .collect();
+    consumer.assign(&partitions)?;
+
+    let mut latest = None;
+    while !remaining.is_empty() {
+        match consumer.poll(config.kafka_timeout) {
+            None => bail!("Timed out reading {topic}"),
+            Some(Err(KafkaError::PartitionEOF(partition))) => {
+                remaining.remove(&partition);
+            }
+            Some(Err(e)) => return Err(e.into()),
+            Some(Ok(message)) if message.key() == Some(pipeline.as_bytes()) => {
+                latest = message
+                    .payload()
+                    .map(serde_json::from_slice::<CommitterWatermark>)
+                    .transpose()
+                    .context("Failed to deserialize watermark")?;
+            }
+            Some(Ok(_)) => {}
+        }
+    }
+
+    Ok(latest)
+}
+
+fn parse_option(s: &str) -> Result<(String, String), String> {
+    let Some((key, value)) = s.split_once('=') else {
+        return Err(format!("Expected KEY=VALUE, got {s:?}"));
+    };
+
+    Ok((key.trim().to_owned(), value.trim().to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use rdkafka::producer::FutureRecord;
+    use sui_pg_temp_db::TempDb;
+
+    use crate::db::DbConfig;
+
+    use super::*;
+
+    struct TestSink;
+
+    impl Processor for TestSink {
+        const NAME: &'static str = "test_sink";
+        type Value = u64;
+
+        fn process(_: &Arc<CheckpointData>) -> anyhow::Result<Vec<u64>> {
+            Ok(vec![])
+        }
+    }
+
+    impl Handler for TestSink {
+        fn key(value: &u64) -> Vec<u8> {
+            value.to_be_bytes().to_vec()
+        }
+
+        fn payload(value: &u64) -> anyhow::Result<Vec<u8>> {
+            Ok(value.to_string().into_bytes())
+        }
+    }
+
+    /// A broker that a previous instance of the sink crashed on, while it had a transaction open.
+    /// Like a real broker, the watermark topic cannot be read to its end until a new producer has
+    /// aborted that transaction.
+    struct CrashedBroker {
+        published: Option<CommitterWatermark<'static>>,
+        open_transaction: Mutex<bool>,
+    }
+
+    #[async_trait::async_trait]
+    impl Broker for CrashedBroker {
+        type Producer = ();
+
+        async fn producer(&self) -> anyhow::Result<()> {
+            *self.open_transaction.lock().unwrap() = false;
+            Ok(())
+        }
+
+        async fn published_watermark(&self) -> anyhow::Result<Option<CommitterWatermark<'static>>> {
+            if *self.open_transaction.lock().unwrap() {
+                bail!("Timed out reading watermarks");
+            }
+
+            Ok(self.published.clone())
+        }
+    }
+
+    async fn temp_db() -> (TempDb, Db) {
+        let temp_db = TempDb::new().unwrap();
+        let url = temp_db.database().url();
+        let db = Db::new(DbConfig::new(url.clone(), None, None))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+        (temp_db, db)
+    }
+
+    async fn db_watermark(db: &Db) -> Option<CommitterWatermark<'static>> {
+        let mut conn = db.connect().await.unwrap();
+        CommitterWatermark::get(&mut conn, TestSink::NAME)
+            .await
+            .unwrap()
+    }
+
+    fn watermark(checkpoint: i64) -> CommitterWatermark<'static> {
+        CommitterWatermark {
+            pipeline: TestSink::NAME.into(),
+            epoch_hi_inclusive: 0,
+            checkpoint_hi_inclusive: checkpoint,
+            tx_hi: checkpoint * 10,
+            timestamp_ms_hi_inclusive: checkpoint * 1000,
+        }
+    }
+
+    /// Publishes `watermark` in a transaction, and either commits or aborts it.
+    async fn publish_watermark(
+        producer: &FutureProducer,
+        config: &KafkaConfig,
+        watermark: &CommitterWatermark<'static>,
+        commit: bool,
+    ) {
+        producer.begin_transaction().unwrap();
+        let payload = serde_json::to_vec(watermark).unwrap();
+        producer
+            .send(
+                FutureRecord::to(&config.kafka_watermark_topic)
+                    .key(TestSink::NAME)
+                    .payload(&payload),
+                config.kafka_timeout,
+            )
+            .await
+            .unwrap();
+
+        if commit {
+            producer.commit_transaction(config.kafka_timeout).unwrap();
+        } else {
+            producer.abort_transaction(config.kafka_timeout).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_crash() {
+        let (_temp_db, db) = temp_db().await;
+        let broker = CrashedBroker {
+            published: Some(watermark(20)),
+            open_transaction: Mutex::new(true),
+        };
+
+        // The previous instance's transaction is aborted before the watermark topic is read, and
+        // the database catches up with the watermark that was published.
+        let ((), resumed) = resume(&broker, &db, Some(watermark(5))).await.unwrap();
+        assert_eq!(resumed, Some(watermark(20)));
+        assert_eq!(db_watermark(&db).await, Some(watermark(20)));
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_watermark() {
+        let (_temp_db, db) = temp_db().await;
+
+        // Nothing published yet.
+        assert_eq!(reconcile_watermark(&db, None, None).await.unwrap(), None);
+        assert_eq!(db_watermark(&db).await, None);
+
+        // The database only has a watermark if it was written by an earlier run.
+        assert_eq!(
+            reconcile_watermark(&db, None, Some(watermark(10)))
+                .await
+                .unwrap(),
+            Some(watermark(10)),
+        );
+        assert_eq!(db_watermark(&db).await, Some(watermark(10)));
+
+        // The database lags behind Kafka.
+        assert_eq!(
+            reconcile_watermark(&db, Some(watermark(10)), Some(watermark(20)))
+                .await
+                .unwrap(),
+            Some(watermark(20)),
+        );
+        assert_eq!(db_watermark(&db).await, Some(watermark(20)));
+
+        // A watermark in the database ahead of Kafka is kept.
+        assert_eq!(
+            reconcile_watermark(&db, Some(watermark(25)), Some(watermark(20)))
+                .await
+                .unwrap(),
+            Some(watermark(25)),
+        );
+
+        // A watermark in the database with nothing published is kept.
+        assert_eq!(
+            reconcile_watermark(&db, Some(watermark(20)), None)
+                .await
+                .unwrap(),
+            Some(watermark(20)),
+        );
+    }
+
+    /// Requires a Kafka-compatible broker (e.g. a local Redpanda container started with
+    /// `rpk container start`) at `$KAFKA_BROKERS`.
+    #[tokio::test]
+    #[ignore]
+    async fn test_start() {
+        let brokers = std::env::var("KAFKA_BROKERS").expect("KAFKA_BROKERS not set");
+        let config = KafkaConfig {
+            kafka_brokers: Some(brokers),
+            kafka_watermark_topic: format!("test_watermarks_{}", rand::random::<u32>()),
+            kafka_transactional_id_prefix: "test-".to_owned(),
+            kafka_timeout: Duration::from_secs(10),
+            kafka_options: vec![],
+        };
+
+        let (_temp_db, db) = temp_db().await;
+
+        // Nothing published yet.
+        let (producer, resumed) = start::<TestSink>(&config, &db, None).await.unwrap();
+        assert_eq!(resumed, None);
+
+        // The watermark from the last committed transaction is caught up with in the database,
+        // and aborted transactions are ignored.
+        publish_watermark(&producer, &config, &watermark(10), true).await;
+        publish_watermark(&producer, &config, &watermark(20), true).await;
+        publish_watermark(&producer, &config, &watermark(30), false).await;
+
+        // A transaction left open by a crashed instance is aborted when the sink restarts.
+        producer.begin_transaction().unwrap();
+        let payload = serde_json::to_vec(&watermark(40)).unwrap();
+        producer
+            .send(
+                FutureRecord::to(&config.kafka_watermark_topic)
+                    .key(TestSink::NAME)
+                    .payload(&payload),
+                config.kafka_timeout,
+            )
+            .await
+            .unwrap();
+
+        let (_, resumed) = start::<TestSink>(&config, &db, Some(watermark(5)))
+            .await
+            .unwrap();
+        assert_eq!(resumed, Some(watermark(20)));
+        assert_eq!(db_watermark(&db).await, Some(watermark(20)));
+    }
+
+    #[test]
+    fn test_parse_option() {
+        assert_eq!(
+            parse_option("security.protocol=SASL_SSL").unwrap(),
+            ("security.protocol".to_owned(), "SASL_SSL".to_owned())
+        );
+        assert_eq!(
+            parse_option("sasl.password=a=b").unwrap(),
+            ("sasl.password".to_owned(), "a=b".to_owned())
+        );
+        assert!(parse_option("security.protocol").is_err());
+    }
+}
@@ -5,11 +5,17 @@ use std::time::Duration;
 
 use crate::models::watermarks::CommitterWatermark;
 
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaConfig;
 pub use processor::Processor;
+pub use shard::Shard;
 
 pub(crate) mod concurrent;
+#[cfg(feature = "kafka")]
+pub(crate) mod kafka;
 mod processor;
 pub(crate) mod sequential;
+pub(crate) mod shard;
 
 /// Tracing message for the watermark update will be logged at info level at least this many
 /// checkpoints.
@@ -55,6 +61,12 @@ pub struct PipelineConfig {
     /// Avoid writing to the watermark table
     #[arg(long)]
     pub skip_watermark: bool,
+
+    /// Only write this shard of each concurrent pipeline, to split pipelines across several
+    /// indexers: Either a range of checkpoints (`FIRST..=LAST` or `FIRST..`), or `INDEX/COUNT` for
+    /// the checkpoints whose sequence numbers are `INDEX` modulo `COUNT`.
+    #[arg(long)]
+    pub shard: Option<Shard>,
 }
 
 /// Processed values associated with a single checkpoint. This is an internal type used to
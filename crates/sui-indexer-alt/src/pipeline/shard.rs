@@ -0,0 +1,474 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, ensure, Context};
+
+use crate::{db::Connection, models::watermarks::CommitterWatermark};
+
+/// The part of each concurrent pipeline that an indexer writes, when a pipeline is split across
+/// several indexers that all write to the same database.
+///
+/// Every shard tracks its own progress in the `watermarks` table, in a row named after the
+/// pipeline and the shard (e.g. `kv_objects@0/4` or `kv_objects@0..=999999`), and after every
+/// update to it, tries to advance the pipeline's own row to the highest checkpoint below which all
+/// shards have committed all their data. Readers can continue to rely on the pipeline's row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shard {
+    /// Checkpoints from `first` to `last` (inclusive), or onwards from `first` if there is no
+    /// `last`. Written as `FIRST..=LAST` or `FIRST..`.
+    Range { first: u64, last: Option<u64> },
+
+    /// Checkpoints whose sequence numbers are `index` modulo `count`, so that all the rows from
+    /// one checkpoint are written by the same shard. Written as `INDEX/COUNT`.
+    Hash { index: u64, count: u64 },
+}
+
+impl Shard {
+    /// Whether checkpoint `cp` is written by this shard.
+    pub fn owns(&self, cp: u64) -> bool {
+        match *self {
+            Shard::Range { first, last } => first <= cp && last.map_or(true, |l| cp <= l),
+            Shard::Hash { index, count } => cp % count == index,
+        }
+    }
+
+    /// The last checkpoint to ingest, if the shard has an end.
+    pub fn last(&self) -> Option<u64> {
+        match *self {
+            Shard::Range { last, .. } => last,
+            Shard::Hash { .. } => None,
+        }
+    }
+
+    /// The first checkpoint written by this shard after checkpoint `hi`, or from the start if
+    /// there is no `hi`. Ignores the end of ranges.
+    pub fn next_after(&self, hi: Option<u64>) -> u64 {
+        let lo = hi.map_or(0, |hi| hi + 1);
+        match *self {
+            Shard::Range { first, .. } => lo.max(first),
+            Shard::Hash { index, count } => lo + (index + count - lo % count) % count,
+        }
+    }
+
+    /// Name of the row in the `watermarks` table tracking this shard's progress in `pipeline`.
+    pub fn watermark_name(&self, pipeline: &str) -> String {
+        format!("{pipeline}@{self}")
+    }
+}
+
+/// The watermark that a shard of `pipeline` should resume from: its own, or the pipeline's if the
+/// pipeline has progressed further (e.g. because the shard has not run before). The watermark is
+/// named after the shard's row either way.
+pub(crate) async fn resume_watermark(
+    conn: &mut Connection<'_>,
+    pipeline: &'static str,
+    shard: Shard,
+    watermark: Option<CommitterWatermark<'static>>,
+) -> anyhow::Result<Option<CommitterWatermark<'static>>> {
+    let name = shard.watermark_name(pipeline);
+    let own = CommitterWatermark::get(conn, &name)
+        .await
+        .with_context(|| format!("Failed to get watermark for {name}"))?;
+
+    let resume = match (own, watermark) {
+        (Some(own), Some(w)) if w.checkpoint_hi_inclusive > own.checkpoint_hi_inclusive => Some(w),
+        (Some(own), _) => Some(own),
+        (None, w) => w,
+    };
+
+    Ok(resume.map(|w| CommitterWatermark {
+        pipeline: name.into(),
+        ..w
+    }))
+}
+
+/// Advance `pipeline`'s watermark as far as the watermarks of its shards allow. Returns whether
+/// the watermark was updated.
+pub(crate) async fn update_pipeline_watermark(
+    conn: &mut Connection<'_>,
+    pipeline: &'static str,
+) -> anyhow::Result<bool> {
+    let watermark = CommitterWatermark::get(conn, pipeline).await?;
+    let shards = CommitterWatermark::get_shards(conn, pipeline).await?;
+
+    let shards: Vec<_> = shards
+        .into_iter()
+        .filter_map(|w| {
+            let shard = w.pipeline.strip_prefix(pipeline)?.strip_prefix('@')?;
+            Some((shard.parse().ok()?, w))
+        })
+        .collect();
+
+    let Some(merged) = merge(watermark.as_ref(), &shards) else {
+        return Ok(false);
+    };
+
+    let merged = CommitterWatermark {
+        pipeline: pipeline.into(),
+        ..merged
+    };
+
+    Ok(merged.update(conn).await?)
+}
+
+/// The highest watermark that the pipeline's `watermark` can be advanced to given the watermarks
+/// of its `shards`, if it can be advanced at all.
+///
+/// Hash shards only cover a checkpoint once every shard with the same count has committed it, so
+/// they can advance the watermark to the lowest of their watermarks once all of them have one.
+/// Range shards cover the checkpoints from the start of their range to their watermark, so they
+/// can advance the watermark if their range starts at or before the checkpoint after it.
+fn merge(
+    watermark: Option<&CommitterWatermark<'static>>,
+    shards: &[(Shard, CommitterWatermark<'static>)],
+) -> Option<CommitterWatermark<'static>> {
+    let hi = |w: Option<&CommitterWatermark>| w.map_or(-1, |w| w.checkpoint_hi_inclusive);
+    let mut best = watermark;
+
+    let counts = shards.iter().filter_map(|(s, _)| match s {
+        Shard::Hash { count, .. } => Some(*count),
+        Shard::Range { .. } => None,
+    });
+
+    for count in counts {
+        let group: Vec<_> = shards
+            .iter()
+            .filter(|(s, _)| matches!(s, Shard::Hash { count: c, .. } if *c == count))
+            .collect();
+
+        if group.len() as u64 != count {
+            continue;
+        }
+
+        let lowest = group
+            .iter()
+            .map(|(_, w)| w)
+            .min_by_key(|w| w.checkpoint_hi_inclusive);
+        if hi(lowest) > hi(best) {
+            best = lowest;
+        }
+    }
+
+    loop {
+        let next = hi(best) + 1;
+        let extension = shards
+            .iter()
+            .filter(|(s, w)| match s {
+                Shard::Range { first, .. } => {
+                    *first as i64 <= next && w.checkpoint_hi_inclusive >= next
+                }
+                Shard::Hash { .. } => false,
+            })
+            .map(|(_, w)| w)
+            .max_by_key(|w| w.checkpoint_hi_inclusive);
+
+        match extension {
+            Some(w) => best = Some(w),
+            None => break,
+        }
+    }
+
+    (hi(best) > hi(watermark)).then(|| best.cloned()).flatten()
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shard::Range { first, last: None } => write!(f, "{first}.."),
+            Shard::Range {
+                first,
+                last: Some(last),
+            } => write!(f, "{first}..={last}"),
+            Shard::Hash { index, count } => write!(f, "{index}/{count}"),
+        }
+    }
+}
+
+impl FromStr for Shard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.parse::<u64>()
+                .map_err(|e| anyhow!("Invalid shard {s:?}: {e}"))
+        };
+
+        if let Some((index, count)) = s.split_once('/') {
+            let (index, count) = (parse(index)?, parse(count)?);
+            ensure!(
+                index < count,
+                "Invalid shard {s:?}: index must be below count"
+            );
+            Ok(Shard::Hash { index, count })
+        } else if let Some((first, last)) = s.split_once("..=") {
+            let (first, last) = (parse(first)?, parse(last)?);
+            ensure!(first <= last, "Invalid shard {s:?}: range is empty");
+            Ok(Shard::Range {
+                first,
+                last: Some(last),
+            })
+        } else if let Some(first) = s.strip_suffix("..") {
+            Ok(Shard::Range {
+                first: parse(first)?,
+                last: None,
+            })
+        } else {
+            Err(anyhow!(
+                "Invalid shard {s:?}: expected INDEX/COUNT, FIRST..=LAST or FIRST.."
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_pg_temp_db::TempDb;
+
+    use crate::db::{Db, DbConfig};
+
+    use super::*;
+
+    const PIPELINE: &str = "test_pipeline";
+
+    fn at(pipeline: String, checkpoint: i64) -> CommitterWatermark<'static> {
+        CommitterWatermark {
+            checkpoint_hi_inclusive: checkpoint,
+            ..CommitterWatermark::initial(pipeline.into())
+        }
+    }
+
+    fn shard(s: &str) -> Shard {
+        s.parse().unwrap()
+    }
+
+    fn shards(ws: &[(&str, i64)]) -> Vec<(Shard, CommitterWatermark<'static>)> {
+        ws.iter()
+            .map(|(s, cp)| (shard(s), at(format!("p@{s}"), *cp)))
+            .collect()
+    }
+
+    fn merged(watermark: Option<i64>, ws: &[(&str, i64)]) -> Option<i64> {
+        let watermark = watermark.map(|cp| at("p".to_owned(), cp));
+        merge(watermark.as_ref(), &shards(ws)).map(|w| w.checkpoint_hi_inclusive)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(shard("1/4"), Shard::Hash { index: 1, count: 4 });
+        assert_eq!(
+            shard("10..=20"),
+            Shard::Range {
+                first: 10,
+                last: Some(20)
+            }
+        );
+        assert_eq!(
+            shard("10.."),
+            Shard::Range {
+                first: 10,
+                last: None
+            }
+        );
+
+        for s in ["1/4", "10..=20", "10.."] {
+            assert_eq!(shard(s).to_string(), s);
+        }
+
+        for s in ["4/4", "1/0", "20..=10", "10", "a/4", "..10"] {
+            assert!(s.parse::<Shard>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_next_after() {
+        let hash = shard("1/4");
+        assert_eq!(hash.next_after(None), 1);
+        assert_eq!(hash.next_after(Some(0)), 1);
+        assert_eq!(hash.next_after(Some(1)), 5);
+        assert_eq!(hash.next_after(Some(3)), 5);
+        assert_eq!(hash.next_after(Some(4)), 5);
+        assert!(hash.owns(5) && !hash.owns(6));
+
+        let range = shard("10..=20");
+        assert_eq!(range.next_after(None), 10);
+        assert_eq!(range.next_after(Some(15)), 16);
+        assert!(range.owns(20) && !range.owns(21) && !range.owns(9));
+    }
+
+    #[test]
+    fn test_merge_hash() {
+        // Not all shards have a watermark yet.
+        assert_eq!(merged(None, &[("0/2", 10)]), None);
+
+        // The pipeline's watermark is the lowest of its shards'.
+        assert_eq!(merged(None, &[("0/2", 10), ("1/2", 7)]), Some(7));
+        assert_eq!(merged(Some(5), &[("0/2", 10), ("1/2", 7)]), Some(7));
+
+        // Never goes backwards.
+        assert_eq!(merged(Some(8), &[("0/2", 10), ("1/2", 7)]), None);
+
+        // Shards from a different split don't count towards this one.
+        assert_eq!(merged(None, &[("0/2", 10), ("1/3", 7)]), None);
+    }
+
+    #[test]
+    fn test_merge_range() {
+        // The range that would extend the watermark hasn't started.
+        assert_eq!(merged(Some(9), &[("20..=29", 25)]), None);
+
+        // Chains contiguous ranges.
+        assert_eq!(
+            merged(Some(9), &[("10..=19", 19), ("20..=29", 25), ("30..", 35)]),
+            Some(25)
+        );
+
+        // Stops at a hole.
+        assert_eq!(
+            merged(Some(9), &[("10..=19", 15), ("20..=29", 25)]),
+            Some(15)
+        );
+
+        // Starts from genesis.
+        assert_eq!(merged(None, &[("0..=9", 9), ("10..", 12)]), Some(12));
+    }
+
+    async fn temp_db() -> (TempDb, Db) {
+        let temp_db = TempDb::new().unwrap();
+        let url = temp_db.database().url();
+        let db = Db::new(DbConfig::new(url.clone(), None, None))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+        (temp_db, db)
+    }
+
+    /// Commit `checkpoint` as shard `s`'s watermark, and advance the pipeline's watermark from its
+    /// shards', like the watermark task does.
+    async fn commit(db: &Db, s: &str, checkpoint: i64) {
+        let mut conn = db.connect().await.unwrap();
+        let name = shard(s).watermark_name(PIPELINE);
+        assert!(at(name, checkpoint).update(&mut conn).await.unwrap());
+        update_pipeline_watermark(&mut conn, PIPELINE)
+            .await
+            .unwrap();
+    }
+
+    async fn pipeline_hi(db: &Db) -> Option<i64> {
+        let mut conn = db.connect().await.unwrap();
+        let watermark = CommitterWatermark::get(&mut conn, PIPELINE).await.unwrap();
+        watermark.map(|w| w.checkpoint_hi_inclusive)
+    }
+
+    /// The watermark that shard `s` resumes from, after a restart, and the next checkpoint it
+    /// writes.
+    async fn resume(db: &Db, s: &str) -> Option<(String, i64, u64)> {
+        let mut conn = db.connect().await.unwrap();
+        let watermark = CommitterWatermark::get(&mut conn, PIPELINE).await.unwrap();
+        let resumed = resume_watermark(&mut conn, PIPELINE, shard(s), watermark)
+            .await
+            .unwrap()?;
+
+        let hi = resumed.checkpoint_hi_inclusive;
+        let next = shard(s).next_after(Some(hi as u64));
+        Some((resumed.pipeline.into_owned(), hi, next))
+    }
+
+    #[tokio::test]
+    async fn test_hash_shards_resume_from_own_watermarks() {
+        let (_temp_db, db) = temp_db().await;
+
+        // Nothing has been written yet.
+        assert_eq!(resume(&db, "0/2").await, None);
+
+        // The pipeline's watermark only moves once both shards have one, and then trails the
+        // slowest of them.
+        commit(&db, "0/2", 4).await;
+        assert_eq!(pipeline_hi(&db).await, None);
+        commit(&db, "1/2", 7).await;
+        assert_eq!(pipeline_hi(&db).await, Some(4));
+        commit(&db, "0/2", 10).await;
+        assert_eq!(pipeline_hi(&db).await, Some(7));
+
+        // After a restart, each shard picks up after its own watermark, not the pipeline's, which
+        // would re-write checkpoints 8 and 10 in shard 0.
+        assert_eq!(
+            resume(&db, "0/2").await,
+            Some(("test_pipeline@0/2".to_owned(), 10, 12)),
+        );
+        assert_eq!(
+            resume(&db, "1/2").await,
+            Some(("test_pipeline@1/2".to_owned(), 7, 9)),
+        );
+
+        // Re-committing an old watermark (e.g. from a shard that was restarted from an older
+        // watermark) does not move either watermark backwards.
+        let mut conn = db.connect().await.unwrap();
+        let name = shard("0/2").watermark_name(PIPELINE);
+        assert!(!at(name, 6).update(&mut conn).await.unwrap());
+        assert!(!update_pipeline_watermark(&mut conn, PIPELINE)
+            .await
+            .unwrap());
+        assert_eq!(pipeline_hi(&db).await, Some(7));
+        assert_eq!(
+            resume(&db, "0/2").await,
+            Some(("test_pipeline@0/2".to_owned(), 10, 12)),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_range_shards_resume_from_own_watermarks() {
+        let (_temp_db, db) = temp_db().await;
+
+        // The later range gets ahead of the earlier one, which holds the pipeline's watermark
+        // back.
+        commit(&db, "0..=9", 5).await;
+        commit(&db, "10..", 15).await;
+        assert_eq!(pipeline_hi(&db).await, Some(5));
+
+        assert_eq!(
+            resume(&db, "0..=9").await,
+            Some(("test_pipeline@0..=9".to_owned(), 5, 6)),
+        );
+        assert_eq!(
+            resume(&db, "10..").await,
+            Some(("test_pipeline@10..".to_owned(), 15, 16)),
+        );
+
+        // Once the gap is filled, the pipeline catches up with the later range.
+        commit(&db, "0..=9", 9).await;
+        assert_eq!(pipeline_hi(&db).await, Some(15));
+    }
+
+    #[tokio::test]
+    async fn test_new_shard_resumes_from_pipeline_watermark() {
+        let (_temp_db, db) = temp_db().await;
+
+        // The pipeline ran unsharded up to checkpoint 20, and then is split into two shards,
+        // one of which has already written some of its checkpoints.
+        let mut conn = db.connect().await.unwrap();
+        assert!(at(PIPELINE.to_owned(), 20).update(&mut conn).await.unwrap());
+        drop(conn);
+        commit(&db, "1/2", 23).await;
+
+        // The new shard starts from the pipeline's watermark, under its own name, and the shard
+        // that has made progress carries on from its own.
+        assert_eq!(
+            resume(&db, "0/2").await,
+            Some(("test_pipeline@0/2".to_owned(), 20, 22)),
+        );
+        assert_eq!(
+            resume(&db, "1/2").await,
+            Some(("test_pipeline@1/2".to_owned(), 23, 25)),
+        );
+
+        // A shard whose own watermark is behind the pipeline's skips ahead to the pipeline's.
+        commit(&db, "0/2", 18).await;
+        assert_eq!(
+            resume(&db, "0/2").await,
+            Some(("test_pipeline@0/2".to_owned(), 20, 22)),
+        );
+        assert_eq!(pipeline_hi(&db).await, Some(20));
+    }
+}
@@ -2,6 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    cp_timestamps (cp_sequence_number) {
+        cp_sequence_number -> Int8,
+        timestamp_ms -> Int8,
+    }
+}
+
 diesel::table! {
     ev_emit_mod (package, module, tx_sequence_number) {
         package -> Bytea,
@@ -22,6 +29,31 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    hist_coin_balances (object_id, object_version) {
+        object_id -> Bytea,
+        object_version -> Int8,
+        owner_id -> Nullable<Bytea>,
+        coin_type -> Nullable<Bytea>,
+        coin_balance -> Nullable<Int8>,
+        cp_sequence_number -> Int8,
+    }
+}
+
+diesel::table! {
+    hist_obj_types (object_id, object_version) {
+        object_id -> Bytea,
+        object_version -> Int8,
+        owner_kind -> Nullable<Int2>,
+        owner_id -> Nullable<Bytea>,
+        package -> Nullable<Bytea>,
+        module -> Nullable<Text>,
+        name -> Nullable<Text>,
+        instantiation -> Nullable<Bytea>,
+        cp_sequence_number -> Int8,
+    }
+}
+
 diesel::table! {
     kv_checkpoints (sequence_number) {
         sequence_number -> Int8,
@@ -194,8 +226,11 @@ diesel::table! {
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
+    cp_timestamps,
     ev_emit_mod,
     ev_struct_inst,
+    hist_coin_balances,
+    hist_obj_types,
     kv_checkpoints,
     kv_genesis,
     kv_objects,
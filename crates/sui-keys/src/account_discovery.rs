@@ -0,0 +1,69 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bip32::DerivationPath;
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::{SignatureScheme, SuiKeyPair};
+
+use crate::key_derive::{derivation_path, derive_key_pair_from_path};
+
+/// Number of consecutive unused addresses after which discovery stops, by default.
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// Source of truth for whether an address has been used, typically backed by a chain reader.
+#[async_trait]
+pub trait AddressUsage: Send + Sync {
+    async fn is_used(&self, address: SuiAddress) -> Result<bool, anyhow::Error>;
+}
+
+/// A key found during discovery, whose address has been used.
+pub struct DiscoveredAccount {
+    pub address: SuiAddress,
+    pub derivation_path: DerivationPath,
+    pub key_pair: SuiKeyPair,
+}
+
+/// Derive the keys of `account` from `seed`, at index 0, 1, 2, ... in order, and return the ones
+/// whose addresses have been used according to `usage`. Discovery stops once `gap_limit`
+/// consecutive addresses are unused.
+pub async fn discover_accounts(
+    seed: &[u8],
+    key_scheme: SignatureScheme,
+    account: u32,
+    gap_limit: u32,
+    usage: &dyn AddressUsage,
+) -> Result<Vec<DiscoveredAccount>, anyhow::Error> {
+    if gap_limit == 0 {
+        anyhow::bail!("Gap limit must be positive");
+    }
+
+    let mut discovered = vec![];
+    let mut unused = 0;
+    let mut index = 0;
+
+    while unused < gap_limit {
+        let path = derivation_path(&key_scheme, account, index)
+            .map_err(|e| anyhow!("Invalid derivation path: {:?}", e))?;
+        let (address, key_pair) = derive_key_pair_from_path(seed, Some(path.clone()), &key_scheme)
+            .map_err(|e| anyhow!("error getting keypair {:?}", e))?;
+
+        if usage.is_used(address).await? {
+            discovered.push(DiscoveredAccount {
+                address,
+                derivation_path: path,
+                key_pair,
+            });
+            unused = 0;
+        } else {
+            unused += 1;
+        }
+
+        index = index
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("Ran out of indexes to derive"))?;
+    }
+
+    Ok(discovered)
+}
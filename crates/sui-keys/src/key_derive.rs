@@ -2,14 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::anyhow;
-use bip32::{ChildNumber, DerivationPath, XPrv};
+use bip32::{ChildNumber, DerivationPath, Prefix, XPrv, XPub};
 
 use bip39::{Language, Mnemonic, MnemonicType, Seed};
 use fastcrypto::ed25519::Ed25519KeyPair;
 use fastcrypto::secp256r1::{Secp256r1KeyPair, Secp256r1PrivateKey};
 use fastcrypto::{
     ed25519::Ed25519PrivateKey,
-    secp256k1::{Secp256k1KeyPair, Secp256k1PrivateKey},
+    secp256k1::{Secp256k1KeyPair, Secp256k1PrivateKey, Secp256k1PublicKey},
     traits::{KeyPair, ToFromBytes},
 };
 use slip10_ed25519::derive_ed25519_private_key;
@@ -168,6 +168,92 @@ pub fn validate_path(
     }
 }
 
+/// The derivation path for the key at `index` in `account`, following the layout described in
+/// [derive_key_pair_from_path]: m/44'/784'/{account}'/0'/{index}' for ed25519, and
+/// m/54'/784'/{account}'/0/{index} or m/74'/784'/{account}'/0/{index} for secp256k1 and secp256r1.
+pub fn derivation_path(
+    key_scheme: &SignatureScheme,
+    account: u32,
+    index: u32,
+) -> Result<DerivationPath, SuiError> {
+    let path = match key_scheme {
+        SignatureScheme::ED25519 => format!(
+            "m/{DERVIATION_PATH_PURPOSE_ED25519}'/{DERIVATION_PATH_COIN_TYPE}'/{account}'/0'/{index}'"
+        ),
+        SignatureScheme::Secp256k1 => format!(
+            "m/{DERVIATION_PATH_PURPOSE_SECP256K1}'/{DERIVATION_PATH_COIN_TYPE}'/{account}'/0/{index}"
+        ),
+        SignatureScheme::Secp256r1 => format!(
+            "m/{DERVIATION_PATH_PURPOSE_SECP256R1}'/{DERIVATION_PATH_COIN_TYPE}'/{account}'/0/{index}"
+        ),
+        _ => {
+            return Err(SuiError::UnsupportedFeatureError {
+                error: format!("key derivation not supported {:?}", key_scheme),
+            })
+        }
+    };
+
+    let path = path
+        .parse()
+        .map_err(|_| SuiError::SignatureKeyGenError("Cannot parse path".to_string()))?;
+    validate_path(key_scheme, Some(path))
+}
+
+/// Export the extended public key of `account`, at m/54'/784'/{account}'. The addresses of the
+/// account's keys can be derived from it with [derive_address_from_xpub], without access to the
+/// private keys, to set up a watch-only wallet.
+///
+/// Only secp256k1 is supported: ed25519 keys are derived on fully hardened paths, which cannot be
+/// derived from a public key, and secp256r1 keys are derived with secp256k1 arithmetic, so their
+/// public keys do not follow from the extended public key.
+pub fn export_xpub(
+    seed: &[u8],
+    key_scheme: &SignatureScheme,
+    account: u32,
+) -> Result<(DerivationPath, String), SuiError> {
+    if key_scheme != &SignatureScheme::Secp256k1 {
+        return Err(SuiError::UnsupportedFeatureError {
+            error: format!("extended public keys not supported {:?}", key_scheme),
+        });
+    }
+
+    let path: DerivationPath =
+        format!("m/{DERVIATION_PATH_PURPOSE_SECP256K1}'/{DERIVATION_PATH_COIN_TYPE}'/{account}'")
+            .parse()
+            .map_err(|_| SuiError::SignatureKeyGenError("Cannot parse path".to_string()))?;
+
+    let xprv = XPrv::derive_from_path(seed, &path)
+        .map_err(|e| SuiError::SignatureKeyGenError(e.to_string()))?;
+
+    Ok((path, xprv.public_key().to_string(Prefix::XPUB)))
+}
+
+/// The address of the key at `index` of the account that `xpub` was exported from by
+/// [export_xpub], i.e. the secp256k1 key at m/54'/784'/{account}'/0/{index}.
+pub fn derive_address_from_xpub(xpub: &str, index: u32) -> Result<SuiAddress, SuiError> {
+    let xpub: XPub = xpub
+        .parse()
+        .map_err(|e| SuiError::SignatureKeyGenError(format!("Invalid xpub: {e}")))?;
+
+    // Exported at m/54'/784'/{account}'
+    if xpub.attrs().depth != 3 {
+        return Err(SuiError::SignatureKeyGenError(
+            "Expected an account-level xpub".to_string(),
+        ));
+    }
+
+    let child = [0, index].into_iter().try_fold(xpub, |xpub, i| {
+        let i = ChildNumber::new(i, false)?;
+        xpub.derive_child(i)
+    });
+
+    let child = child.map_err(|e| SuiError::SignatureKeyGenError(e.to_string()))?;
+    let pk = Secp256k1PublicKey::from_bytes(&child.to_bytes())
+        .map_err(|e| SuiError::SignatureKeyGenError(e.to_string()))?;
+
+    Ok((&pk).into())
+}
+
 pub fn generate_new_key(
     key_scheme: SignatureScheme,
     derivation_path: Option<DerivationPath>,
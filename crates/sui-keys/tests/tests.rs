@@ -1,12 +1,19 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeSet;
 use std::fs;
 use std::str::FromStr;
 
+use async_trait::async_trait;
+use bip39::{Language, Mnemonic, Seed};
 use fastcrypto::hash::HashFunction;
 use fastcrypto::traits::EncodeDecodeBase64;
-use sui_keys::key_derive::generate_new_key;
+use sui_keys::account_discovery::{discover_accounts, AddressUsage};
+use sui_keys::key_derive::{
+    derivation_path, derive_address_from_xpub, derive_key_pair_from_path, export_xpub,
+    generate_new_key, validate_path,
+};
 use tempfile::TempDir;
 
 use sui_keys::keystore::{AccountKeystore, FileBasedKeystore, InMemKeystore, Keystore};
@@ -266,3 +273,90 @@ fn get_alias_by_address_test() {
     let address = generate_new_key(SignatureScheme::ED25519, None, None).unwrap();
     assert!(keystore.get_alias_by_address(&address.0).is_err())
 }
+
+fn test_seed() -> Vec<u8> {
+    let phrase = "result crisp session latin must fruit genuine question prevent start coconut brave speak student dismiss";
+    let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+    Seed::new(&mnemonic, "").as_bytes().to_vec()
+}
+
+#[test]
+fn derivation_path_test() {
+    for scheme in [
+        SignatureScheme::ED25519,
+        SignatureScheme::Secp256k1,
+        SignatureScheme::Secp256r1,
+    ] {
+        // The first key of the first account is the default key.
+        assert_eq!(
+            derivation_path(&scheme, 0, 0).unwrap(),
+            validate_path(&scheme, None).unwrap()
+        );
+    }
+
+    assert_eq!(
+        derivation_path(&SignatureScheme::ED25519, 1, 2)
+            .unwrap()
+            .to_string(),
+        "m/44'/784'/1'/0'/2'"
+    );
+    assert_eq!(
+        derivation_path(&SignatureScheme::Secp256k1, 1, 2)
+            .unwrap()
+            .to_string(),
+        "m/54'/784'/1'/0/2"
+    );
+}
+
+#[test]
+fn xpub_address_test() {
+    let seed = test_seed();
+    let (path, xpub) = export_xpub(&seed, &SignatureScheme::Secp256k1, 1).unwrap();
+    assert_eq!(path.to_string(), "m/54'/784'/1'");
+
+    for index in 0..5 {
+        let path = derivation_path(&SignatureScheme::Secp256k1, 1, index).unwrap();
+        let (address, _) =
+            derive_key_pair_from_path(&seed, Some(path), &SignatureScheme::Secp256k1).unwrap();
+        assert_eq!(derive_address_from_xpub(&xpub, index).unwrap(), address);
+    }
+
+    assert!(export_xpub(&seed, &SignatureScheme::ED25519, 0).is_err());
+    assert!(export_xpub(&seed, &SignatureScheme::Secp256r1, 0).is_err());
+}
+
+struct UsedAddresses(BTreeSet<SuiAddress>);
+
+#[async_trait]
+impl AddressUsage for UsedAddresses {
+    async fn is_used(&self, address: SuiAddress) -> Result<bool, anyhow::Error> {
+        Ok(self.0.contains(&address))
+    }
+}
+
+#[tokio::test]
+async fn discover_accounts_test() {
+    let seed = test_seed();
+    let scheme = SignatureScheme::ED25519;
+    let address = |index| {
+        let path = derivation_path(&scheme, 0, index).unwrap();
+        derive_key_pair_from_path(&seed, Some(path), &scheme)
+            .unwrap()
+            .0
+    };
+
+    let used = UsedAddresses([0, 2, 5].into_iter().map(address).collect());
+
+    // The gap between indexes 2 and 5 is too wide to find 5.
+    let discovered = discover_accounts(&seed, scheme, 0, 2, &used).await.unwrap();
+    let addresses: Vec<_> = discovered.iter().map(|a| a.address).collect();
+    assert_eq!(addresses, vec![address(0), address(2)]);
+
+    let discovered = discover_accounts(&seed, scheme, 0, 3, &used).await.unwrap();
+    let addresses: Vec<_> = discovered.iter().map(|a| a.address).collect();
+    assert_eq!(addresses, vec![address(0), address(2), address(5)]);
+    assert_eq!(
+        discovered[2].derivation_path.to_string(),
+        "m/44'/784'/0'/0'/5'"
+    );
+}
@@ -0,0 +1,786 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generate Rust bindings for a Move package.
+//!
+//! For every struct and enum in the package, the generated code contains a Rust type whose BCS
+//! layout matches the Move type, so that values read from chain can be deserialized with `bcs`.
+//! For every function that can be called from a programmable transaction (`public` or `entry`
+//! functions), it contains a helper that adds the corresponding `MoveCall` command to a
+//! `ProgrammableTransactionBuilder`.
+//!
+//! Function helpers take one Rust parameter per Move parameter (apart from a trailing
+//! `TxContext`), named after the Move parameter when the package's source maps are available (see
+//! [`generate_from_package`]), and `arg0`, `arg1`, ... otherwise. Their types depend on the Move
+//! type of the parameter:
+//!
+//! - Pure types -- primitives, `address`, `std::string::String`, `std::ascii::String`,
+//!   `sui::object::ID`, and `vector`s and `Option`s of pure types -- are accepted as Rust values
+//!   with the same layout (e.g. `u64`, `SuiAddress`, `String`, `Vec<T>`, `Option<T>`), which the
+//!   helper adds to the transaction as pure inputs.
+//! - Everything else -- objects, references, and values of generic types -- is accepted as
+//!   `impl Into<Argument>`, such as an object input, or the result of an earlier command.
+//!
+//! Helpers return the `Argument` for the call's result, or an error if a pure value could not be
+//! serialized.
+//!
+//! To pass the result of an earlier command as a pure parameter, add the `MoveCall` to the builder
+//! directly.
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt::{self, Write},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use move_binary_format::{
+    file_format::{
+        Ability, AbilitySet, DatatypeHandleIndex, FieldDefinition, FunctionDefinition,
+        FunctionDefinitionIndex, SignatureToken, StructFieldInformation, Visibility,
+    },
+    CompiledModule,
+};
+use move_bytecode_source_map::source_map::{SourceMap, SourceName};
+use move_core_types::account_address::AccountAddress;
+use sui_move_build::CompiledPackage;
+use sui_types::{MOVE_STDLIB_ADDRESS, SUI_FRAMEWORK_ADDRESS};
+
+/// Options controlling how bindings are generated.
+#[derive(Clone, Debug, Default)]
+pub struct BindgenConfig {
+    /// Rust paths to the bindings of packages that this package depends on, keyed by the
+    /// dependency's runtime address. A type `0xa::m::T` from a dependency with an entry
+    /// `0xa => "deps::a"` is referred to as `deps::a::m::T`.
+    ///
+    /// Types from the Move standard library and the Sui framework that have an equivalent in
+    /// `sui-types` (e.g. `UID`, `Balance`, `Coin`, `String`, `Option`) do not need an entry.
+    pub externs: BTreeMap<AccountAddress, String>,
+}
+
+/// Generate the source for a Rust module containing bindings for `modules`, which must all belong
+/// to the same package.
+///
+/// Each Move module becomes a Rust module of the same name. The generated code refers to
+/// `anyhow`, `serde`, `move_core_types` and `sui_types` through absolute paths, so the crate that
+/// includes it needs to depend on those crates.
+///
+/// Bytecode does not record the names of function parameters, so the parameters of function
+/// helpers are named `arg0`, `arg1`, ... Use [`generate_from_package`] to name them after their
+/// Move counterparts.
+pub fn generate<'m>(
+    modules: impl IntoIterator<Item = &'m CompiledModule>,
+    config: &BindgenConfig,
+) -> Result<String> {
+    generate_impl(modules.into_iter().map(|m| (m, None)), config)
+}
+
+/// Like [`generate`], for the modules of a package that was built from source (not including its
+/// dependencies). Function helpers take their parameter names from the package's source maps.
+pub fn generate_from_package(package: &CompiledPackage, config: &BindgenConfig) -> Result<String> {
+    generate_impl(
+        package
+            .package
+            .root_modules()
+            .map(|m| (&m.unit.module, Some(&m.unit.source_map))),
+        config,
+    )
+}
+
+fn generate_impl<'m>(
+    modules: impl IntoIterator<Item = (&'m CompiledModule, Option<&'m SourceMap>)>,
+    config: &BindgenConfig,
+) -> Result<String> {
+    let mut modules: Vec<_> = modules.into_iter().collect();
+    modules.sort_by_key(|(m, _)| m.name());
+
+    let Some((first, _)) = modules.first() else {
+        bail!("No modules to generate bindings for");
+    };
+
+    let address = *first.address();
+    for (module, _) in &modules {
+        ensure!(
+            *module.address() == address,
+            "Module {} does not belong to package {}",
+            module.self_id(),
+            address.to_hex_literal(),
+        );
+    }
+
+    let mut generator = Generator {
+        address,
+        config,
+        out: String::new(),
+    };
+
+    writeln!(
+        generator.out,
+        "// @generated by sui-move-bindgen for package {}. DO NOT EDIT.",
+        address.to_hex_literal(),
+    )?;
+
+    for (module, source_map) in modules {
+        generator
+            .module(module, source_map)
+            .with_context(|| format!("Failed to generate bindings for {}", module.self_id()))?;
+    }
+
+    Ok(generator.out)
+}
+
+struct Generator<'c> {
+    /// Runtime address of the package that bindings are being generated for.
+    address: AccountAddress,
+    config: &'c BindgenConfig,
+    out: String,
+}
+
+/// The Rust names for the type parameters of a datatype, or `None` for phantom type parameters,
+/// which do not contribute to the datatype's layout and are dropped from its Rust binding.
+type TypeParams = Vec<Option<String>>;
+
+impl Generator<'_> {
+    fn module(&mut self, module: &CompiledModule, source_map: Option<&SourceMap>) -> Result<()> {
+        let name = module.name().as_str();
+
+        writeln!(self.out)?;
+        writeln!(
+            self.out,
+            "#[allow(dead_code, non_camel_case_types, non_snake_case, clippy::too_many_arguments)]"
+        )?;
+        writeln!(self.out, "pub mod {} {{", ident(name))?;
+        writeln!(
+            self.out,
+            "    pub const MODULE_NAME: &::move_core_types::identifier::IdentStr = \
+             ::move_core_types::ident_str!({name:?});",
+        )?;
+
+        for def in module.struct_defs() {
+            let StructFieldInformation::Declared(fields) = &def.field_information else {
+                // Native structs have no declared layout to bind to.
+                continue;
+            };
+
+            let (name, type_params) = Self::datatype_header(module, def.struct_handle);
+            writeln!(self.out)?;
+            self.datatype_doc(module, def.struct_handle, "struct")?;
+            writeln!(
+                self.out,
+                "    pub struct {name}{} {{",
+                generics(&type_params)
+            )?;
+            for field in fields {
+                self.field(module, field, &type_params, "        pub ")?;
+            }
+            writeln!(self.out, "    }}")?;
+        }
+
+        for def in module.enum_defs() {
+            let (name, type_params) = Self::datatype_header(module, def.enum_handle);
+            writeln!(self.out)?;
+            self.datatype_doc(module, def.enum_handle, "enum")?;
+            writeln!(self.out, "    pub enum {name}{} {{", generics(&type_params))?;
+            for variant in &def.variants {
+                let variant_name = ident(module.identifier_at(variant.variant_name).as_str());
+                if variant.fields.is_empty() {
+                    writeln!(self.out, "        {variant_name},")?;
+                    continue;
+                }
+
+                writeln!(self.out, "        {variant_name} {{")?;
+                for field in &variant.fields {
+                    self.field(module, field, &type_params, "            ")?;
+                }
+                writeln!(self.out, "        }},")?;
+            }
+            writeln!(self.out, "    }}")?;
+        }
+
+        for (i, def) in module.function_defs().iter().enumerate() {
+            if def.visibility == Visibility::Public || def.is_entry {
+                let names = source_map.and_then(|s| {
+                    let function = s.get_function_source_map(FunctionDefinitionIndex(i as u16));
+                    Some(function.ok()?.parameters.as_slice())
+                });
+
+                self.function(module, def, names)?;
+            }
+        }
+
+        writeln!(self.out, "}}")?;
+        Ok(())
+    }
+
+    /// Returns the Rust name of the datatype with handle `ix`, alongside the Rust names of its
+    /// type parameters.
+    fn datatype_header(module: &CompiledModule, ix: DatatypeHandleIndex) -> (String, TypeParams) {
+        let handle = module.datatype_handle_at(ix);
+        let name = ident(module.identifier_at(handle.name).as_str()).into_owned();
+        let type_params = handle
+            .type_parameters
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (!p.is_phantom).then(|| format!("T{i}")))
+            .collect();
+
+        (name, type_params)
+    }
+
+    fn datatype_doc(
+        &mut self,
+        module: &CompiledModule,
+        ix: DatatypeHandleIndex,
+        kind: &str,
+    ) -> Result<()> {
+        let handle = module.datatype_handle_at(ix);
+        writeln!(
+            self.out,
+            "    /// Move {kind} `{}::{}::{}`{}.",
+            self.address.to_hex_literal(),
+            module.name(),
+            module.identifier_at(handle.name),
+            Abilities(handle.abilities),
+        )?;
+        writeln!(
+            self.out,
+            "    #[derive(Clone, Debug, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]"
+        )?;
+        Ok(())
+    }
+
+    fn field(
+        &mut self,
+        module: &CompiledModule,
+        field: &FieldDefinition,
+        type_params: &TypeParams,
+        prefix: &str,
+    ) -> Result<()> {
+        let name = module.identifier_at(field.name);
+        let type_ = self
+            .rust_type(module, &field.signature.0, type_params)
+            .with_context(|| format!("Failed to translate the type of field {name}"))?;
+
+        writeln!(self.out, "{prefix}{}: {type_},", ident(name.as_str()))?;
+        Ok(())
+    }
+
+    /// Generate a helper to call the function `def`. `names` are the names of its parameters
+    /// from the source map, if one is available.
+    fn function(
+        &mut self,
+        module: &CompiledModule,
+        def: &FunctionDefinition,
+        names: Option<&[SourceName]>,
+    ) -> Result<()> {
+        let handle = module.function_handle_at(def.function);
+        let name = module.identifier_at(handle.name).as_str();
+        let mut parameters = &module.signature_at(handle.parameters).0[..];
+
+        // Fall back to positional names if the source map doesn't line up with the bytecode.
+        let names = names.filter(|names| names.len() == parameters.len());
+
+        // The `TxContext` parameter is supplied by the runtime, rather than the transaction.
+        if let Some((last, rest)) = parameters.split_last() {
+            if is_tx_context(module, last) {
+                parameters = rest;
+            }
+        }
+
+        let parameters: Vec<_> = parameters
+            .iter()
+            .enumerate()
+            .map(|(i, sig)| {
+                let name = match names {
+                    Some(names) => parameter_name(i, &names[i].0),
+                    None => format!("arg{i}"),
+                };
+
+                (name, sig, pure_type(module, sig))
+            })
+            .collect();
+
+        let signature = parameters
+            .iter()
+            .map(|(_, sig, _)| move_type(module, sig))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(self.out)?;
+        writeln!(
+            self.out,
+            "    /// Call `{}::{name}({signature})`.",
+            module.name(),
+        )?;
+        writeln!(self.out, "    pub fn {}(", ident(name))?;
+        writeln!(
+            self.out,
+            "        builder: &mut ::sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder,"
+        )?;
+        writeln!(
+            self.out,
+            "        package: ::sui_types::base_types::ObjectID,"
+        )?;
+
+        let type_arity = handle.type_parameters.len();
+        if type_arity > 0 {
+            writeln!(
+                self.out,
+                "        type_arguments: [::move_core_types::language_storage::TypeTag; {type_arity}],"
+            )?;
+        }
+
+        for (name, _, pure) in &parameters {
+            match pure {
+                Some(type_) => writeln!(self.out, "        {name}: {type_},")?,
+                None => writeln!(
+                    self.out,
+                    "        {name}: impl Into<::sui_types::transaction::Argument>,"
+                )?,
+            }
+        }
+
+        writeln!(
+            self.out,
+            "    ) -> ::anyhow::Result<::sui_types::transaction::Argument> {{"
+        )?;
+
+        for (name, _, pure) in &parameters {
+            if pure.is_some() {
+                writeln!(self.out, "        let {name} = builder.pure({name})?;")?;
+            }
+        }
+
+        writeln!(self.out, "        Ok(builder.programmable_move_call(")?;
+        writeln!(self.out, "            package,")?;
+        writeln!(self.out, "            MODULE_NAME.to_owned(),")?;
+        writeln!(
+            self.out,
+            "            ::move_core_types::ident_str!({name:?}).to_owned(),"
+        )?;
+
+        if type_arity > 0 {
+            writeln!(self.out, "            type_arguments.into(),")?;
+        } else {
+            writeln!(self.out, "            vec![],")?;
+        }
+
+        let arguments = parameters
+            .iter()
+            .map(|(name, _, pure)| {
+                if pure.is_some() {
+                    name.clone()
+                } else {
+                    format!("{name}.into()")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(self.out, "            vec![{arguments}],")?;
+        writeln!(self.out, "        ))")?;
+        writeln!(self.out, "    }}")?;
+        Ok(())
+    }
+
+    /// The Rust type with the same BCS layout as the Move type `sig`, appearing in `module`, in a
+    /// context with type parameters `type_params`.
+    fn rust_type(
+        &self,
+        module: &CompiledModule,
+        sig: &SignatureToken,
+        type_params: &TypeParams,
+    ) -> Result<String> {
+        use SignatureToken as S;
+        Ok(match sig {
+            S::Bool => "bool".to_owned(),
+            S::U8 => "u8".to_owned(),
+            S::U16 => "u16".to_owned(),
+            S::U32 => "u32".to_owned(),
+            S::U64 => "u64".to_owned(),
+            S::U128 => "u128".to_owned(),
+            S::U256 => "::move_core_types::u256::U256".to_owned(),
+            S::Address => "::sui_types::base_types::SuiAddress".to_owned(),
+            S::Vector(inner) => format!("Vec<{}>", self.rust_type(module, inner, type_params)?),
+
+            S::TypeParameter(ix) => match type_params.get(*ix as usize) {
+                Some(Some(name)) => name.clone(),
+                Some(None) => bail!("Phantom type parameter {ix} used in a non-phantom position"),
+                None => bail!("Type parameter {ix} out of bounds"),
+            },
+
+            S::Datatype(ix) => self.datatype_path(module, *ix, &[], type_params)?,
+            S::DatatypeInstantiation(inst) => {
+                let (ix, type_args) = &**inst;
+                self.datatype_path(module, *ix, type_args, type_params)?
+            }
+
+            S::Signer | S::Reference(_) | S::MutableReference(_) => {
+                bail!("{} cannot appear in a datatype", move_type(module, sig))
+            }
+        })
+    }
+
+    fn datatype_path(
+        &self,
+        module: &CompiledModule,
+        ix: DatatypeHandleIndex,
+        type_args: &[SignatureToken],
+        type_params: &TypeParams,
+    ) -> Result<String> {
+        let handle = module.datatype_handle_at(ix);
+        let module_handle = module.module_handle_at(handle.module);
+        let address = *module.address_identifier_at(module_handle.address);
+        let module_name = module.identifier_at(module_handle.name).as_str();
+        let name = module.identifier_at(handle.name).as_str();
+
+        let mut path = if let Some(path) = well_known(&address, module_name, name) {
+            path.to_owned()
+        } else if address == self.address {
+            format!("super::{}::{}", ident(module_name), ident(name))
+        } else if let Some(prefix) = self.config.externs.get(&address) {
+            format!("{prefix}::{}::{}", ident(module_name), ident(name))
+        } else {
+            bail!(
+                "No bindings for type {}::{module_name}::{name}, from a dependency. Generate \
+                 bindings for the dependency and pass their path as an extern.",
+                address.to_hex_literal(),
+            );
+        };
+
+        // Phantom type arguments do not contribute to the type's layout, so the bindings for
+        // the type don't accept them.
+        let type_args = handle
+            .type_parameters
+            .iter()
+            .zip(type_args)
+            .filter(|(p, _)| !p.is_phantom)
+            .map(|(_, t)| self.rust_type(module, t, type_params))
+            .collect::<Result<Vec<_>>>()?;
+
+        if !type_args.is_empty() {
+            write!(path, "<{}>", type_args.join(", "))?;
+        }
+
+        Ok(path)
+    }
+}
+
+/// Rust types from the standard library or `sui-types` that have the same layout as Move types
+/// from the standard library or the Sui framework.
+fn well_known(address: &AccountAddress, module: &str, name: &str) -> Option<&'static str> {
+    if *address == MOVE_STDLIB_ADDRESS {
+        match (module, name) {
+            ("ascii", "String") | ("string", "String") | ("type_name", "TypeName") => {
+                Some("::std::string::String")
+            }
+            ("option", "Option") => Some("::std::option::Option"),
+            _ => None,
+        }
+    } else if *address == SUI_FRAMEWORK_ADDRESS {
+        match (module, name) {
+            ("object", "UID") => Some("::sui_types::id::UID"),
+            ("object", "ID") => Some("::sui_types::id::ID"),
+            ("balance", "Balance") => Some("::sui_types::balance::Balance"),
+            ("balance", "Supply") => Some("::sui_types::balance::Supply"),
+            ("coin", "Coin") => Some("::sui_types::coin::Coin"),
+            ("coin", "TreasuryCap") => Some("::sui_types::coin::TreasuryCap"),
+            ("bag", "Bag") => Some("::sui_types::collection_types::Bag"),
+            ("table", "Table") => Some("::sui_types::collection_types::Table"),
+            ("vec_map", "VecMap") => Some("::sui_types::collection_types::VecMap"),
+            ("vec_set", "VecSet") => Some("::sui_types::collection_types::VecSet"),
+            ("url", "Url") => Some("::std::string::String"),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// The Rust type that values of the Move type `sig` are accepted as, if `sig` can be passed to a
+/// function as a pure input, or `None` if it must be passed as an `Argument`.
+fn pure_type(module: &CompiledModule, sig: &SignatureToken) -> Option<String> {
+    use SignatureToken as S;
+    Some(match sig {
+        S::Bool => "bool".to_owned(),
+        S::U8 => "u8".to_owned(),
+        S::U16 => "u16".to_owned(),
+        S::U32 => "u32".to_owned(),
+        S::U64 => "u64".to_owned(),
+        S::U128 => "u128".to_owned(),
+        S::U256 => "::move_core_types::u256::U256".to_owned(),
+        S::Address => "::sui_types::base_types::SuiAddress".to_owned(),
+        S::Vector(inner) => format!("Vec<{}>", pure_type(module, inner)?),
+
+        S::Datatype(ix) => match datatype_id(module, *ix) {
+            (a, "ascii" | "string", "String") if a == MOVE_STDLIB_ADDRESS => {
+                "::std::string::String".to_owned()
+            }
+            (a, "object", "ID") if a == SUI_FRAMEWORK_ADDRESS => "::sui_types::id::ID".to_owned(),
+            _ => return None,
+        },
+
+        S::DatatypeInstantiation(inst) => match (datatype_id(module, inst.0), &inst.1[..]) {
+            ((a, "option", "Option"), [inner]) if a == MOVE_STDLIB_ADDRESS => {
+                format!("::std::option::Option<{}>", pure_type(module, inner)?)
+            }
+            _ => return None,
+        },
+
+        S::Signer | S::Reference(_) | S::MutableReference(_) | S::TypeParameter(_) => return None,
+    })
+}
+
+/// The address, module name and name of the datatype with handle `ix`.
+fn datatype_id(module: &CompiledModule, ix: DatatypeHandleIndex) -> (AccountAddress, &str, &str) {
+    let handle = module.datatype_handle_at(ix);
+    let module_handle = module.module_handle_at(handle.module);
+    (
+        *module.address_identifier_at(module_handle.address),
+        module.identifier_at(module_handle.name).as_str(),
+        module.identifier_at(handle.name).as_str(),
+    )
+}
+
+/// The Rust name for the `i`-th function parameter, given its name from the source map. Names in
+/// source maps carry suffixes added by the compiler (e.g. `x#0#0`), and may clash with the
+/// parameters that every function helper takes.
+fn parameter_name(i: usize, name: &str) -> String {
+    let name = name.split_once('#').map_or(name, |(n, _)| n);
+    match name {
+        "" | "_" => format!("arg{i}"),
+        "builder" | "package" | "type_arguments" => format!("{name}_"),
+        name => ident(name).into_owned(),
+    }
+}
+
+/// Whether `sig` is a reference to `sui::tx_context::TxContext`.
+fn is_tx_context(module: &CompiledModule, sig: &SignatureToken) -> bool {
+    use SignatureToken as S;
+    let (S::Reference(inner) | S::MutableReference(inner)) = sig else {
+        return false;
+    };
+
+    let S::Datatype(ix) = &**inner else {
+        return false;
+    };
+
+    datatype_id(module, *ix) == (SUI_FRAMEWORK_ADDRESS, "tx_context", "TxContext")
+}
+
+/// Render `sig` using Move syntax, for documentation.
+fn move_type(module: &CompiledModule, sig: &SignatureToken) -> String {
+    use SignatureToken as S;
+    match sig {
+        S::Bool => "bool".to_owned(),
+        S::U8 => "u8".to_owned(),
+        S::U16 => "u16".to_owned(),
+        S::U32 => "u32".to_owned(),
+        S::U64 => "u64".to_owned(),
+        S::U128 => "u128".to_owned(),
+        S::U256 => "u256".to_owned(),
+        S::Address => "address".to_owned(),
+        S::Signer => "signer".to_owned(),
+        S::Vector(inner) => format!("vector<{}>", move_type(module, inner)),
+        S::Reference(inner) => format!("&{}", move_type(module, inner)),
+        S::MutableReference(inner) => format!("&mut {}", move_type(module, inner)),
+        S::TypeParameter(ix) => format!("T{ix}"),
+        S::Datatype(ix) => datatype_name(module, *ix),
+        S::DatatypeInstantiation(inst) => {
+            let (ix, type_args) = &**inst;
+            let type_args = type_args
+                .iter()
+                .map(|t| move_type(module, t))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{}<{type_args}>", datatype_name(module, *ix))
+        }
+    }
+}
+
+fn datatype_name(module: &CompiledModule, ix: DatatypeHandleIndex) -> String {
+    let handle = module.datatype_handle_at(ix);
+    let module_handle = module.module_handle_at(handle.module);
+    format!(
+        "{}::{}::{}",
+        module
+            .address_identifier_at(module_handle.address)
+            .to_hex_literal(),
+        module.identifier_at(module_handle.name),
+        module.identifier_at(handle.name),
+    )
+}
+
+fn generics(type_params: &TypeParams) -> String {
+    let names: Vec<_> = type_params.iter().flatten().map(String::as_str).collect();
+    if names.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", names.join(", "))
+    }
+}
+
+/// Displays an ability set as a Move `has` clause.
+struct Abilities(AbilitySet);
+
+impl fmt::Display for Abilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut prefix = " has ";
+        for ability in self.0 {
+            let name = match ability {
+                Ability::Copy => "copy",
+                Ability::Drop => "drop",
+                Ability::Store => "store",
+                Ability::Key => "key",
+            };
+
+            write!(f, "{prefix}{name}")?;
+            prefix = ", ";
+        }
+
+        Ok(())
+    }
+}
+
+/// Escape Move identifiers that are reserved in Rust.
+fn ident(name: &str) -> Cow<'_, str> {
+    const RESERVED: &[&str] = &[
+        "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do",
+        "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in",
+        "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+        "return", "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe",
+        "unsized", "use", "virtual", "where", "while", "yield",
+    ];
+
+    match name {
+        // These keywords cannot be used as raw identifiers.
+        "self" | "Self" | "super" | "crate" => Cow::Owned(format!("{name}_")),
+        name if RESERVED.contains(&name) => Cow::Owned(format!("r#{name}")),
+        name => Cow::Borrowed(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use sui_move_build::BuildConfig;
+
+    use super::*;
+
+    fn bindings(dir: &str) -> String {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.extend(["tests", "packages", dir]);
+        let package = BuildConfig::new_for_testing().build(&path).unwrap();
+        generate_from_package(&package, &BindgenConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn test_structs() {
+        let code = bindings("example");
+
+        // Phantom type parameters are dropped.
+        assert!(code.contains("    pub struct Pool {\n"));
+        assert!(code.contains("        pub balance: ::sui_types::balance::Balance,\n"));
+        assert!(code.contains("        pub name: ::std::string::String,\n"));
+        assert!(code.contains("        pub admins: Vec<::sui_types::base_types::SuiAddress>,\n"));
+
+        assert!(code.contains("    pub struct Pair<T0, T1> {\n"));
+        assert!(code.contains("        pub first: T0,\n"));
+
+        // Positional fields.
+        assert!(code.contains("    pub struct Wrapper {\n"));
+        assert!(code.contains("        pub pos1: ::std::option::Option<u8>,\n"));
+    }
+
+    #[test]
+    fn test_enums() {
+        let code = bindings("example");
+        assert!(code.contains("    pub enum Action {\n"));
+        assert!(code.contains("        Stop,\n"));
+        assert!(code.contains("        Move {\n            x: u64,\n"));
+        assert!(code.contains("        Write {\n            pos0: Vec<u8>,\n"));
+    }
+
+    #[test]
+    fn test_cross_module() {
+        let code = bindings("example");
+        assert!(code.contains("        pub pair: super::example::Pair<u8, bool>,\n"));
+        assert!(code.contains("        pub action: super::example::Action,\n"));
+    }
+
+    #[test]
+    fn test_functions() {
+        let code = bindings("example");
+
+        // Generic public function: values of generic types are passed as arguments.
+        assert!(code.contains(
+            "    pub fn new_pair(\n        \
+             builder: &mut ::sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder,\n        \
+             package: ::sui_types::base_types::ObjectID,\n        \
+             type_arguments: [::move_core_types::language_storage::TypeTag; 2],\n        \
+             first: impl Into<::sui_types::transaction::Argument>,\n        \
+             second: impl Into<::sui_types::transaction::Argument>,\n    \
+             ) -> ::anyhow::Result<::sui_types::transaction::Argument> {\n"
+        ));
+        assert!(code.contains("            vec![first.into(), second.into()],\n"));
+
+        // The `TxContext` parameter is omitted, and pure parameters are accepted as values.
+        let create = &code[code.find("    pub fn create(").unwrap()..];
+        let create = &create[..create.find("\n    }\n").unwrap()];
+        assert!(code.contains("    /// Call `example::create(0x1::string::String)`.\n"));
+        assert!(create.contains("        name: ::std::string::String,\n"));
+        assert!(!create.contains("ctx"));
+        assert!(create.contains("        let name = builder.pure(name)?;\n"));
+        assert!(create.contains("            vec![],\n            vec![name],\n"));
+
+        // Functions that cannot be called from a transaction are skipped.
+        assert!(!code.contains("pub fn internal("));
+        assert!(!code.contains("pub fn private("));
+    }
+
+    #[test]
+    fn test_functions_without_source_map() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.extend(["tests", "packages", "example"]);
+        let package = BuildConfig::new_for_testing().build(&path).unwrap();
+        let code = generate(package.get_modules(), &BindgenConfig::default()).unwrap();
+
+        assert!(code.contains("        arg0: impl Into<::sui_types::transaction::Argument>,\n"));
+        assert!(code.contains("        arg1: impl Into<::sui_types::transaction::Argument>,\n"));
+        assert!(code.contains("        arg0: ::std::string::String,\n"));
+    }
+
+    #[test]
+    fn test_pure_types() {
+        let code = bindings("example");
+        assert!(code.contains("        amount: u64,\n"));
+        assert!(code.contains("        recipients: Vec<::sui_types::base_types::SuiAddress>,\n"));
+        assert!(code.contains("        id: ::std::option::Option<::sui_types::id::ID>,\n"));
+
+        // Objects and references are passed as arguments.
+        assert!(code.contains("        pool: impl Into<::sui_types::transaction::Argument>,\n"));
+        assert!(code.contains(
+            "        let amount = builder.pure(amount)?;\n        \
+             let recipients = builder.pure(recipients)?;\n        \
+             let id = builder.pure(id)?;\n"
+        ));
+        assert!(code.contains("            vec![pool.into(), amount, recipients, id],\n"));
+    }
+
+    #[test]
+    fn test_parameter_names() {
+        assert_eq!(parameter_name(0, "x#0#0"), "x");
+        assert_eq!(parameter_name(1, "_#1#0"), "arg1");
+        assert_eq!(parameter_name(2, "move"), "r#move");
+        assert_eq!(parameter_name(3, "builder"), "builder_");
+        assert_eq!(parameter_name(4, "type_arguments#1#0"), "type_arguments_");
+    }
+
+    #[test]
+    fn test_reserved_names() {
+        assert_eq!(ident("move"), "r#move");
+        assert_eq!(ident("self"), "self_");
+        assert_eq!(ident("coin"), "coin");
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fs, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use move_core_types::account_address::AccountAddress;
+use sui_json_rpc_types::SuiObjectDataOptions;
+use sui_move_bindgen::{generate, generate_from_package, BindgenConfig};
+use sui_move_build::BuildConfig;
+use sui_package_resolver::Package;
+use sui_sdk::SuiClientBuilder;
+use sui_types::{base_types::ObjectID, object::Object};
+
+/// Generate Rust bindings for the structs, enums and callable functions of a Move package.
+#[derive(Parser)]
+#[clap(name = "sui-move-bindgen", rename_all = "kebab-case")]
+struct Args {
+    /// Path to a local Move package to build and generate bindings for.
+    #[clap(
+        long,
+        conflicts_with = "package_id",
+        required_unless_present = "package_id"
+    )]
+    path: Option<PathBuf>,
+
+    /// ID of a published package to generate bindings for.
+    #[clap(long)]
+    package_id: Option<ObjectID>,
+
+    /// Fullnode JSON-RPC endpoint to fetch the package from, when using `--package-id`.
+    #[clap(long, default_value = "https://fullnode.mainnet.sui.io:443")]
+    rpc_url: String,
+
+    /// Rust path to bindings for a dependency, as `<address>=<path>`, e.g.
+    /// `0xabc=crate::bindings::dep`. Can be repeated.
+    #[clap(long = "extern", value_parser = parse_extern)]
+    externs: Vec<(AccountAddress, String)>,
+
+    /// File to write the bindings to. Bindings are written to stdout if this is not provided.
+    #[clap(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = BindgenConfig {
+        externs: args.externs.into_iter().collect(),
+    };
+
+    let code = if let Some(path) = args.path {
+        let package = BuildConfig::default()
+            .build(&path)
+            .with_context(|| format!("Failed to build package at {}", path.display()))?;
+        generate_from_package(&package, &config)?
+    } else if let Some(id) = args.package_id {
+        let package = fetch_package(&args.rpc_url, id).await?;
+        generate(package.modules().values().map(|m| m.bytecode()), &config)?
+    } else {
+        unreachable!("clap requires one of --path or --package-id");
+    };
+
+    match args.output {
+        Some(output) => fs::write(&output, code)
+            .with_context(|| format!("Failed to write bindings to {}", output.display()))?,
+        None => print!("{code}"),
+    }
+
+    Ok(())
+}
+
+/// Read the package at `id` from the fullnode at `rpc_url`.
+async fn fetch_package(rpc_url: &str, id: ObjectID) -> Result<Package> {
+    let client = SuiClientBuilder::default()
+        .build(rpc_url)
+        .await
+        .context("Failed to connect to fullnode")?;
+
+    let response = client
+        .read_api()
+        .get_object_with_options(id, SuiObjectDataOptions::bcs_lossless())
+        .await
+        .with_context(|| format!("Failed to fetch package {id}"))?;
+
+    let object: Object = response.into_object()?.try_into()?;
+    Ok(Package::read_from_object(&object)?)
+}
+
+fn parse_extern(s: &str) -> Result<(AccountAddress, String)> {
+    let (address, path) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected <address>=<path>, got {s:?}"))?;
+
+    let address = AccountAddress::from_str(address)
+        .with_context(|| format!("Invalid address in extern: {address:?}"))?;
+
+    Ok((address, path.to_owned()))
+}
@@ -0,0 +1,213 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that the bindings generated for `tests/packages/example` compile, and that values of
+//! the bindings round-trip through BCS against the layouts of the corresponding Move types.
+
+use std::{fmt::Debug, path::PathBuf, str::FromStr};
+
+use move_bytecode_utils::layout::TypeLayoutBuilder;
+use move_core_types::{annotated_value as A, language_storage::TypeTag};
+use serde::{de::DeserializeOwned, Serialize};
+use sui_move_bindgen::{generate_from_package, BindgenConfig};
+use sui_move_build::{BuildConfig, CompiledPackage};
+use sui_types::{
+    balance::Balance,
+    base_types::{ObjectID, SequenceNumber, SuiAddress},
+    id::{ID, UID},
+    programmable_transaction_builder::ProgrammableTransactionBuilder,
+    transaction::{Argument, CallArg, Command, ObjectArg},
+};
+
+// Kept exactly as generated, so that it can be compared with the generator's output.
+#[rustfmt::skip]
+#[path = "bindings/example.rs"]
+mod bindings;
+
+use bindings::{
+    example::{self, Action, Pair, Pool, Wrapper},
+    other::Holder,
+};
+
+const BINDINGS_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/bindings/example.rs");
+
+fn package() -> CompiledPackage {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.extend(["tests", "packages", "example"]);
+    BuildConfig::new_for_testing().build(&path).unwrap()
+}
+
+/// Serializes `value` with its binding and deserializes it as the Move type `type_`, checking
+/// that all the bytes are consumed and that both sides serialize the same bytes.
+fn round_trip<T>(package: &CompiledPackage, type_: &str, value: T) -> A::MoveValue
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let tag = TypeTag::from_str(type_).unwrap();
+    let layout = TypeLayoutBuilder::build_with_types(&tag, package).unwrap();
+
+    let bytes = bcs::to_bytes(&value).unwrap();
+    let move_value = A::MoveValue::simple_deserialize(&bytes, &layout)
+        .unwrap_or_else(|e| panic!("{value:?} does not deserialize as {type_}: {e}"));
+
+    assert_eq!(move_value.simple_serialize().unwrap(), bytes);
+    assert_eq!(bcs::from_bytes::<T>(&bytes).unwrap(), value);
+    move_value
+}
+
+/// The names of the fields of `value`, and their values.
+fn fields(value: &A::MoveValue) -> Vec<(&str, &A::MoveValue)> {
+    let fields = match value {
+        A::MoveValue::Struct(s) => &s.fields,
+        A::MoveValue::Variant(v) => &v.fields,
+        _ => panic!("Expected a struct or a variant, got {value:?}"),
+    };
+
+    fields.iter().map(|(n, v)| (n.as_str(), v)).collect()
+}
+
+#[test]
+fn test_bindings_are_up_to_date() {
+    let actual = generate_from_package(&package(), &BindgenConfig::default()).unwrap();
+
+    if std::env::var_os("UPDATE").is_some() {
+        std::fs::write(BINDINGS_FILE, &actual).unwrap();
+    }
+
+    let expected = std::fs::read_to_string(BINDINGS_FILE).unwrap();
+    assert!(
+        expected == actual,
+        "Generated and checked-in bindings do not match. Re-run with `UPDATE=1` to update them.\n\
+         \n\
+         Generated:\n{actual}"
+    );
+}
+
+#[test]
+fn test_struct_round_trip() {
+    let package = package();
+
+    let pool = round_trip(
+        &package,
+        "0x0::example::Pool<0x2::sui::SUI>",
+        Pool {
+            id: UID::new(ObjectID::from_single_byte(1)),
+            balance: Balance::new(100),
+            name: "pool".to_owned(),
+            admins: vec![SuiAddress::ZERO],
+        },
+    );
+    let names: Vec<_> = fields(&pool).into_iter().map(|(n, _)| n).collect();
+    assert_eq!(names, vec!["id", "balance", "name", "admins"]);
+
+    round_trip(
+        &package,
+        "0x0::example::Pair<u64, vector<u8>>",
+        Pair {
+            first: 42u64,
+            second: vec![1u8, 2, 3],
+        },
+    );
+
+    for pos1 in [None, Some(7)] {
+        let wrapper = round_trip(&package, "0x0::example::Wrapper", Wrapper { pos0: 1, pos1 });
+        assert_eq!(fields(&wrapper)[0], ("pos0", &A::MoveValue::U64(1)));
+    }
+}
+
+#[test]
+fn test_enum_round_trip() {
+    let package = package();
+
+    let variants = [
+        (Action::Stop, "Stop"),
+        (Action::Move { x: 1, y: 2 }, "Move"),
+        (Action::Write { pos0: vec![3, 4] }, "Write"),
+    ];
+
+    for (action, name) in variants {
+        let A::MoveValue::Variant(variant) = round_trip(&package, "0x0::example::Action", action)
+        else {
+            panic!("Expected a variant");
+        };
+        assert_eq!(variant.variant_name.as_str(), name);
+    }
+}
+
+#[test]
+fn test_cross_module_round_trip() {
+    let package = package();
+
+    let holder = round_trip(
+        &package,
+        "0x0::other::Holder",
+        Holder {
+            pair: Pair {
+                first: 5,
+                second: true,
+            },
+            action: Action::Move { x: 6, y: 7 },
+        },
+    );
+
+    let holder = fields(&holder);
+    assert_eq!(holder[0].0, "pair");
+    assert_eq!(holder[1].0, "action");
+    assert_eq!(
+        fields(holder[1].1),
+        vec![("x", &A::MoveValue::U64(6)), ("y", &A::MoveValue::U64(7))],
+    );
+}
+
+#[test]
+fn test_function_bindings() {
+    let mut builder = ProgrammableTransactionBuilder::new();
+    let package = ObjectID::from_single_byte(1);
+
+    let result = example::create(&mut builder, package, "pool".to_owned()).unwrap();
+    assert_eq!(result, Argument::Result(0));
+
+    let pool = ObjectArg::SharedObject {
+        id: ObjectID::from_single_byte(2),
+        initial_shared_version: SequenceNumber::from_u64(1),
+        mutable: true,
+    };
+    let pool_arg = builder.obj(pool).unwrap();
+    let recipients = vec![SuiAddress::ZERO];
+    let result =
+        example::add_admins(&mut builder, package, pool_arg, 2, recipients.clone(), None).unwrap();
+    assert_eq!(result, Argument::Result(1));
+
+    let pt = builder.finish();
+    let calls: Vec<_> = pt
+        .commands
+        .iter()
+        .map(|command| match command {
+            Command::MoveCall(call) => call,
+            _ => panic!("Expected a MoveCall, got {command:?}"),
+        })
+        .collect();
+
+    assert_eq!(calls[0].package, package);
+    assert_eq!(calls[0].module.as_str(), "example");
+    assert_eq!(calls[0].function.as_str(), "create");
+    assert_eq!(calls[0].arguments, vec![Argument::Input(0)]);
+
+    // Pure parameters are added as inputs, and other arguments are passed through.
+    assert_eq!(calls[1].function.as_str(), "add_admins");
+    assert_eq!(
+        calls[1].arguments,
+        (1..=4).map(Argument::Input).collect::<Vec<_>>(),
+    );
+
+    assert_eq!(
+        pt.inputs,
+        vec![
+            CallArg::Pure(bcs::to_bytes("pool").unwrap()),
+            CallArg::Object(pool),
+            CallArg::Pure(bcs::to_bytes(&2u64).unwrap()),
+            CallArg::Pure(bcs::to_bytes(&recipients).unwrap()),
+            CallArg::Pure(bcs::to_bytes(&None::<ID>).unwrap()),
+        ],
+    );
+}
@@ -0,0 +1,108 @@
+// @generated by sui-move-bindgen for package 0x0. DO NOT EDIT.
+
+#[allow(dead_code, non_camel_case_types, non_snake_case, clippy::too_many_arguments)]
+pub mod example {
+    pub const MODULE_NAME: &::move_core_types::identifier::IdentStr = ::move_core_types::ident_str!("example");
+
+    /// Move struct `0x0::example::Pool` has key.
+    #[derive(Clone, Debug, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+    pub struct Pool {
+        pub id: ::sui_types::id::UID,
+        pub balance: ::sui_types::balance::Balance,
+        pub name: ::std::string::String,
+        pub admins: Vec<::sui_types::base_types::SuiAddress>,
+    }
+
+    /// Move struct `0x0::example::Pair` has copy, drop, store.
+    #[derive(Clone, Debug, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+    pub struct Pair<T0, T1> {
+        pub first: T0,
+        pub second: T1,
+    }
+
+    /// Move struct `0x0::example::Wrapper` has copy, drop.
+    #[derive(Clone, Debug, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+    pub struct Wrapper {
+        pub pos0: u64,
+        pub pos1: ::std::option::Option<u8>,
+    }
+
+    /// Move enum `0x0::example::Action` has copy, drop, store.
+    #[derive(Clone, Debug, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+    pub enum Action {
+        Stop,
+        Move {
+            x: u64,
+            y: u64,
+        },
+        Write {
+            pos0: Vec<u8>,
+        },
+    }
+
+    /// Call `example::new_pair(T0, T1)`.
+    pub fn new_pair(
+        builder: &mut ::sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder,
+        package: ::sui_types::base_types::ObjectID,
+        type_arguments: [::move_core_types::language_storage::TypeTag; 2],
+        first: impl Into<::sui_types::transaction::Argument>,
+        second: impl Into<::sui_types::transaction::Argument>,
+    ) -> ::anyhow::Result<::sui_types::transaction::Argument> {
+        Ok(builder.programmable_move_call(
+            package,
+            MODULE_NAME.to_owned(),
+            ::move_core_types::ident_str!("new_pair").to_owned(),
+            type_arguments.into(),
+            vec![first.into(), second.into()],
+        ))
+    }
+
+    /// Call `example::create(0x1::string::String)`.
+    pub fn create(
+        builder: &mut ::sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder,
+        package: ::sui_types::base_types::ObjectID,
+        name: ::std::string::String,
+    ) -> ::anyhow::Result<::sui_types::transaction::Argument> {
+        let name = builder.pure(name)?;
+        Ok(builder.programmable_move_call(
+            package,
+            MODULE_NAME.to_owned(),
+            ::move_core_types::ident_str!("create").to_owned(),
+            vec![],
+            vec![name],
+        ))
+    }
+
+    /// Call `example::add_admins(&mut 0x0::example::Pool<0x2::sui::SUI>, u64, vector<address>, 0x1::option::Option<0x2::object::ID>)`.
+    pub fn add_admins(
+        builder: &mut ::sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder,
+        package: ::sui_types::base_types::ObjectID,
+        pool: impl Into<::sui_types::transaction::Argument>,
+        amount: u64,
+        recipients: Vec<::sui_types::base_types::SuiAddress>,
+        id: ::std::option::Option<::sui_types::id::ID>,
+    ) -> ::anyhow::Result<::sui_types::transaction::Argument> {
+        let amount = builder.pure(amount)?;
+        let recipients = builder.pure(recipients)?;
+        let id = builder.pure(id)?;
+        Ok(builder.programmable_move_call(
+            package,
+            MODULE_NAME.to_owned(),
+            ::move_core_types::ident_str!("add_admins").to_owned(),
+            vec![],
+            vec![pool.into(), amount, recipients, id],
+        ))
+    }
+}
+
+#[allow(dead_code, non_camel_case_types, non_snake_case, clippy::too_many_arguments)]
+pub mod other {
+    pub const MODULE_NAME: &::move_core_types::identifier::IdentStr = ::move_core_types::ident_str!("other");
+
+    /// Move struct `0x0::other::Holder` has store.
+    #[derive(Clone, Debug, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+    pub struct Holder {
+        pub pair: super::example::Pair<u8, bool>,
+        pub action: super::example::Action,
+    }
+}
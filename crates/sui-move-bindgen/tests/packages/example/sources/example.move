@@ -0,0 +1,61 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused_field)]
+module example::example {
+    use std::string::String;
+    use sui::balance::{Self, Balance};
+    use sui::sui::SUI;
+
+    public struct Pool<phantom T> has key {
+        id: UID,
+        balance: Balance<T>,
+        name: String,
+        admins: vector<address>,
+    }
+
+    public struct Pair<A, B> has copy, drop, store {
+        first: A,
+        second: B,
+    }
+
+    public struct Wrapper(u64, Option<u8>) has copy, drop;
+
+    public enum Action has copy, drop, store {
+        Stop,
+        Move { x: u64, y: u64 },
+        Write(vector<u8>),
+    }
+
+    public fun new_pair<A, B>(first: A, second: B): Pair<A, B> {
+        Pair { first, second }
+    }
+
+    public entry fun create(name: String, ctx: &mut TxContext) {
+        transfer::share_object(Pool<SUI> {
+            id: object::new(ctx),
+            balance: balance::zero(),
+            name,
+            admins: vector[],
+        })
+    }
+
+    public fun add_admins(
+        pool: &mut Pool<SUI>,
+        amount: u64,
+        recipients: vector<address>,
+        id: Option<ID>,
+    ) {
+        id.do!(|id| assert!(id == object::id(pool)));
+        assert!(recipients.length() <= amount);
+        pool.admins.append(recipients);
+    }
+
+    public(package) fun internal(): u64 {
+        private()
+    }
+
+    fun private(): u64 {
+        42
+    }
+}
@@ -0,0 +1,12 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused_field)]
+module example::other {
+    use example::example::{Action, Pair};
+
+    public struct Holder has store {
+        pair: Pair<u8, bool>,
+        action: Action,
+    }
+}
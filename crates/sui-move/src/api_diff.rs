@@ -0,0 +1,898 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::manage_package::resolve_lock_file_path;
+use anyhow::bail;
+use clap::{Parser, ValueEnum};
+use move_binary_format::{
+    file_format::{Ability, AbilitySet, DatatypeTyParameter, Visibility},
+    normalized::{Enum, Field, Function, Module, Struct, Type, Variant},
+    CompiledModule,
+};
+use move_cli::base;
+use move_core_types::account_address::AccountAddress;
+use move_package::BuildConfig as MoveBuildConfig;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+use sui_move_build::BuildConfig;
+use sui_types::base_types::ObjectID;
+
+/// Compare the public API of two versions of a package, and report the functions, types and
+/// friend declarations that were added, removed or changed.
+///
+/// Changes that break existing callers are marked as such, including the ones a compatible
+/// package upgrade still allows (e.g. changing the signature of a non-public entry function).
+#[derive(Parser)]
+#[group(id = "sui-move-api-diff")]
+pub struct ApiDiff {
+    /// The old version of the package: the ID of a package published on-chain, or the path to
+    /// the package's source.
+    #[clap(long)]
+    pub from: String,
+    /// Path to the new version of the package. Defaults to the package path.
+    #[clap(long)]
+    pub to: Option<PathBuf>,
+    /// How to output the changes.
+    #[clap(long, value_enum, default_value = "markdown")]
+    pub format: DiffFormat,
+    /// The modules of the on-chain package `from` refers to, if it is a package ID. These are
+    /// fetched before the command is executed, as this requires a network connection.
+    #[clap(skip)]
+    pub from_modules: Option<Vec<CompiledModule>>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum DiffFormat {
+    /// A Markdown changelog
+    Markdown,
+    /// A JSON document, for tools
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiItem {
+    Module,
+    Function,
+    Struct,
+    Enum,
+    Variant,
+    Friend,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub item: ApiItem,
+    /// The name of the item, e.g. `Coin` or `Coin::value`
+    pub name: String,
+    /// What changed about the item, for changed items, e.g. "abilities" or "signature"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aspect: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Whether code or clients using the old version of the item can stop working
+    pub breaking: bool,
+    /// Whether the change is allowed in a compatible package upgrade
+    pub compatible: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleDiff {
+    pub module: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageDiff {
+    pub from: String,
+    pub to: String,
+    /// Whether any change breaks existing callers
+    pub breaking: bool,
+    /// Whether every change is allowed in a compatible package upgrade
+    pub compatible: bool,
+    pub modules: Vec<ModuleDiff>,
+}
+
+impl ApiDiff {
+    /// The ID of the on-chain package to compare against, if `from` is not a path to a package.
+    pub fn from_package_id(&self) -> Option<ObjectID> {
+        if Path::new(&self.from).exists() {
+            return None;
+        }
+        ObjectID::from_hex_literal(&self.from).ok()
+    }
+
+    pub fn execute(self, path: Option<&Path>, config: MoveBuildConfig) -> anyhow::Result<()> {
+        // Package paths are resolved before building, which changes the working directory.
+        let from_modules = match self.from_modules {
+            Some(modules) => modules,
+            None if Path::new(&self.from).exists() => {
+                let from_path = Path::new(&self.from).canonicalize()?;
+                build_modules(&base::reroot_path(Some(&from_path))?, config.clone())?
+            }
+            None if self.from_package_id().is_some() => {
+                bail!("The modules of package {} have not been fetched", self.from)
+            }
+            None => bail!(
+                "'{}' is neither a path to a package nor a package ID",
+                self.from
+            ),
+        };
+        let to_path = match self.to.as_deref().or(path) {
+            Some(to) => Some(to.canonicalize()?),
+            None => None,
+        };
+        let to_display = to_path
+            .as_deref()
+            .map_or_else(|| ".".to_string(), |p| p.display().to_string());
+        let to_modules = build_modules(&base::reroot_path(to_path.as_deref())?, config)?;
+
+        let diff = diff_packages(self.from, to_display, &from_modules, &to_modules);
+        match self.format {
+            DiffFormat::Markdown => print!("{}", render_markdown(&diff)),
+            DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+        }
+        Ok(())
+    }
+}
+
+fn build_modules(path: &Path, config: MoveBuildConfig) -> anyhow::Result<Vec<CompiledModule>> {
+    let config = resolve_lock_file_path(config, Some(path))?;
+    let pkg = BuildConfig {
+        config,
+        run_bytecode_verifier: false,
+        print_diags_to_stderr: true,
+        chain_id: None,
+    }
+    .build(path)?;
+    Ok(pkg.get_modules().cloned().collect())
+}
+
+/// The changes to the API of each module of a package, from the `from` version to the `to`
+/// version. Modules without changes are left out.
+pub fn diff_packages(
+    from: String,
+    to: String,
+    from_modules: &[CompiledModule],
+    to_modules: &[CompiledModule],
+) -> PackageDiff {
+    let normalize = |modules: &[CompiledModule]| -> BTreeMap<String, Module> {
+        modules
+            .iter()
+            .map(|m| (m.self_id().name().to_string(), Module::new(m)))
+            .collect()
+    };
+    let old = normalize(from_modules);
+    let new = normalize(to_modules);
+
+    let mut modules = vec![];
+    for name in old.keys().chain(new.keys()).collect::<BTreeSet<_>>() {
+        let changes = match (old.get(name), new.get(name)) {
+            (Some(old), Some(new)) => diff_modules(old, new),
+            (Some(_), None) => vec![change(ChangeKind::Removed, ApiItem::Module, name)
+                .breaking(true)
+                .compatible(false)],
+            (None, Some(_)) => vec![change(ChangeKind::Added, ApiItem::Module, name)],
+            (None, None) => unreachable!(),
+        };
+        if !changes.is_empty() {
+            modules.push(ModuleDiff {
+                module: name.clone(),
+                changes,
+            });
+        }
+    }
+
+    let changes = || modules.iter().flat_map(|m| &m.changes);
+    PackageDiff {
+        from,
+        to,
+        breaking: changes().any(|c| c.breaking),
+        compatible: changes().all(|c| c.compatible),
+        modules,
+    }
+}
+
+fn diff_modules(old: &Module, new: &Module) -> Vec<Change> {
+    let mut changes = vec![];
+    diff_functions(old, new, &mut changes);
+    diff_structs(old, new, &mut changes);
+    diff_enums(old, new, &mut changes);
+    diff_friends(old, new, &mut changes);
+    changes
+}
+
+/// Public and entry functions are part of the API. Entry functions can be called from
+/// transactions, but a compatible upgrade may still change or remove the non-public ones.
+fn diff_functions(old: &Module, new: &Module, changes: &mut Vec<Change>) {
+    let is_api = |f: &Function| f.visibility == Visibility::Public || f.is_entry;
+
+    for (name, old_fun) in old.functions.iter().filter(|(_, f)| is_api(f)) {
+        let before = function_signature(old.address, name.as_str(), old_fun);
+        let was_public = old_fun.visibility == Visibility::Public;
+        let Some(new_fun) = new.functions.get(name).filter(|f| is_api(f)) else {
+            changes.push(
+                change(ChangeKind::Removed, ApiItem::Function, name)
+                    .before(before)
+                    .breaking(true)
+                    .compatible(!was_public),
+            );
+            continue;
+        };
+
+        let after = function_signature(new.address, name.as_str(), new_fun);
+        if before == after {
+            continue;
+        }
+        let is_public = new_fun.visibility == Visibility::Public;
+        let same_signature = types_match(old, new, &old_fun.parameters, &new_fun.parameters)
+            && types_match(old, new, &old_fun.return_, &new_fun.return_);
+        // Callers keep working if every instantiation they use is still allowed.
+        let looser_type_parameters = old_fun.type_parameters.len() == new_fun.type_parameters.len()
+            && old_fun
+                .type_parameters
+                .iter()
+                .zip(&new_fun.type_parameters)
+                .all(|(old, new)| new.is_subset(*old));
+        let breaking = !same_signature
+            || !looser_type_parameters
+            || (was_public && !is_public)
+            || (old_fun.is_entry && !new_fun.is_entry);
+        let compatible = !was_public || (is_public && same_signature && looser_type_parameters);
+        changes.push(
+            change(ChangeKind::Changed, ApiItem::Function, name)
+                .aspect("signature")
+                .before(before)
+                .after(after)
+                .breaking(breaking)
+                .compatible(compatible),
+        );
+    }
+
+    for (name, new_fun) in new.functions.iter().filter(|(_, f)| is_api(f)) {
+        if !old.functions.get(name).is_some_and(is_api) {
+            changes.push(
+                change(ChangeKind::Added, ApiItem::Function, name).after(function_signature(
+                    new.address,
+                    name.as_str(),
+                    new_fun,
+                )),
+            );
+        }
+    }
+}
+
+fn diff_structs(old: &Module, new: &Module, changes: &mut Vec<Change>) {
+    for (name, old_struct) in &old.structs {
+        let Some(new_struct) = new.structs.get(name) else {
+            changes.push(
+                change(ChangeKind::Removed, ApiItem::Struct, name)
+                    .before(struct_declaration(old.address, name.as_str(), old_struct))
+                    .breaking(true)
+                    .compatible(false),
+            );
+            continue;
+        };
+        diff_datatype(
+            ApiItem::Struct,
+            name.as_str(),
+            (old_struct.abilities, new_struct.abilities),
+            (&old_struct.type_parameters, &new_struct.type_parameters),
+            changes,
+        );
+        let before = fields(old.address, &old_struct.fields);
+        let after = fields(new.address, &new_struct.fields);
+        if before != after {
+            // Fields are private to their module, but changing them breaks clients that read
+            // the struct's values.
+            changes.push(
+                change(ChangeKind::Changed, ApiItem::Struct, name)
+                    .aspect("fields")
+                    .before(before)
+                    .after(after)
+                    .breaking(true)
+                    .compatible(false),
+            );
+        }
+    }
+
+    for (name, new_struct) in &new.structs {
+        if !old.structs.contains_key(name) {
+            changes.push(
+                change(ChangeKind::Added, ApiItem::Struct, name).after(struct_declaration(
+                    new.address,
+                    name.as_str(),
+                    new_struct,
+                )),
+            );
+        }
+    }
+}
+
+fn diff_enums(old: &Module, new: &Module, changes: &mut Vec<Change>) {
+    for (name, old_enum) in &old.enums {
+        let Some(new_enum) = new.enums.get(name) else {
+            changes.push(
+                change(ChangeKind::Removed, ApiItem::Enum, name)
+                    .before(enum_declaration(old.address, name.as_str(), old_enum))
+                    .breaking(true)
+                    .compatible(false),
+            );
+            continue;
+        };
+        diff_datatype(
+            ApiItem::Enum,
+            name.as_str(),
+            (old_enum.abilities, new_enum.abilities),
+            (&old_enum.type_parameters, &new_enum.type_parameters),
+            changes,
+        );
+
+        // Variants are identified by their position, which is their tag in serialized values.
+        for tag in 0..old_enum.variants.len().max(new_enum.variants.len()) {
+            let old_variant = old_enum.variants.get(tag);
+            let new_variant = new_enum.variants.get(tag);
+            let variant =
+                |address, v: &Variant| format!("{}{}", v.name, fields(address, &v.fields));
+            let variant_name = |v: &Variant| format!("{name}::{}", v.name);
+            changes.push(match (old_variant, new_variant) {
+                (Some(o), Some(n)) => {
+                    let before = variant(old.address, o);
+                    let after = variant(new.address, n);
+                    if before == after {
+                        continue;
+                    }
+                    change(ChangeKind::Changed, ApiItem::Variant, variant_name(o))
+                        .before(before)
+                        .after(after)
+                        .breaking(true)
+                        .compatible(false)
+                }
+                (Some(o), None) => change(ChangeKind::Removed, ApiItem::Variant, variant_name(o))
+                    .before(variant(old.address, o))
+                    .breaking(true)
+                    .compatible(false),
+                // New variants break clients that expect to know every variant, and are not
+                // allowed in upgrades.
+                (None, Some(n)) => change(ChangeKind::Added, ApiItem::Variant, variant_name(n))
+                    .after(variant(new.address, n))
+                    .breaking(true)
+                    .compatible(false),
+                (None, None) => unreachable!(),
+            });
+        }
+    }
+
+    for (name, new_enum) in &new.enums {
+        if !old.enums.contains_key(name) {
+            changes.push(
+                change(ChangeKind::Added, ApiItem::Enum, name).after(enum_declaration(
+                    new.address,
+                    name.as_str(),
+                    new_enum,
+                )),
+            );
+        }
+    }
+}
+
+/// Changes to the abilities and type parameters of a struct or enum.
+fn diff_datatype(
+    item: ApiItem,
+    name: &str,
+    (old_abilities, new_abilities): (AbilitySet, AbilitySet),
+    (old_params, new_params): (&[DatatypeTyParameter], &[DatatypeTyParameter]),
+    changes: &mut Vec<Change>,
+) {
+    if old_abilities != new_abilities {
+        // Adding abilities does not break existing uses, but upgrades may not add any.
+        changes.push(
+            change(ChangeKind::Changed, item, name)
+                .aspect("abilities")
+                .before(abilities(old_abilities))
+                .after(abilities(new_abilities))
+                .breaking(!old_abilities.is_subset(new_abilities))
+                .compatible(false),
+        );
+    }
+    let before = datatype_type_parameters(old_params);
+    let after = datatype_type_parameters(new_params);
+    if before != after {
+        changes.push(
+            change(ChangeKind::Changed, item, name)
+                .aspect("type parameters")
+                .before(before)
+                .after(after)
+                .breaking(true)
+                .compatible(false),
+        );
+    }
+}
+
+/// Friend declarations only affect modules of the same package, so changing them never breaks
+/// other packages.
+fn diff_friends(old: &Module, new: &Module, changes: &mut Vec<Change>) {
+    let friends = |m: &Module| -> BTreeSet<String> {
+        m.friends
+            .iter()
+            .map(|f| datatype_path(m.address, &f.address(), f.name().as_str()))
+            .collect()
+    };
+    let old_friends = friends(old);
+    let new_friends = friends(new);
+    for friend in old_friends.difference(&new_friends) {
+        changes.push(change(ChangeKind::Removed, ApiItem::Friend, friend));
+    }
+    for friend in new_friends.difference(&old_friends) {
+        changes.push(change(ChangeKind::Added, ApiItem::Friend, friend));
+    }
+}
+
+fn change(kind: ChangeKind, item: ApiItem, name: impl ToString) -> Change {
+    Change {
+        kind,
+        item,
+        name: name.to_string(),
+        aspect: None,
+        before: None,
+        after: None,
+        breaking: false,
+        compatible: true,
+    }
+}
+
+impl Change {
+    fn aspect(self, aspect: &'static str) -> Self {
+        Self {
+            aspect: Some(aspect),
+            ..self
+        }
+    }
+
+    fn before(self, before: String) -> Self {
+        Self {
+            before: Some(before),
+            ..self
+        }
+    }
+
+    fn after(self, after: String) -> Self {
+        Self {
+            after: Some(after),
+            ..self
+        }
+    }
+
+    fn breaking(self, breaking: bool) -> Self {
+        Self { breaking, ..self }
+    }
+
+    fn compatible(self, compatible: bool) -> Self {
+        Self { compatible, ..self }
+    }
+}
+
+/// Whether the types are the same, where types declared in either version of the package are
+/// treated as the same type.
+fn types_match(old: &Module, new: &Module, old_types: &[Type], new_types: &[Type]) -> bool {
+    let render = |m: &Module, types: &[Type]| -> Vec<String> {
+        types.iter().map(|t| type_(m.address, t)).collect()
+    };
+    render(old, old_types) == render(new, new_types)
+}
+
+// Rendering of items, in Move syntax. Types declared in the package itself are rendered without
+// their address, so that they compare equal across versions of the package even if one of the
+// versions is not published yet.
+
+fn function_signature(self_address: AccountAddress, name: &str, f: &Function) -> String {
+    let visibility = match f.visibility {
+        Visibility::Public => "public ",
+        Visibility::Friend => "public(package) ",
+        Visibility::Private => "",
+    };
+    let entry = if f.is_entry { "entry " } else { "" };
+    let type_parameters = type_parameters(f.type_parameters.iter().map(|a| (false, *a)));
+    let parameters = types(self_address, &f.parameters);
+    let return_ = match f.return_.as_slice() {
+        [] => String::new(),
+        [t] => format!(": {}", type_(self_address, t)),
+        ts => format!(": ({})", types(self_address, ts)),
+    };
+    format!("{visibility}{entry}fun {name}{type_parameters}({parameters}){return_}")
+}
+
+fn struct_declaration(self_address: AccountAddress, name: &str, s: &Struct) -> String {
+    format!(
+        "struct {name}{}{} {}",
+        datatype_type_parameters(&s.type_parameters),
+        has(s.abilities),
+        fields(self_address, &s.fields)
+    )
+}
+
+fn enum_declaration(self_address: AccountAddress, name: &str, e: &Enum) -> String {
+    let variants = e
+        .variants
+        .iter()
+        .map(|v| format!("{}{}", v.name, fields(self_address, &v.fields)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "enum {name}{}{} {{ {variants} }}",
+        datatype_type_parameters(&e.type_parameters),
+        has(e.abilities)
+    )
+}
+
+fn fields(self_address: AccountAddress, fields: &[Field]) -> String {
+    if fields.is_empty() {
+        return String::new();
+    }
+    let fields = fields
+        .iter()
+        .map(|f| format!("{}: {}", f.name, type_(self_address, &f.type_)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(" {{ {fields} }}")
+}
+
+fn datatype_type_parameters(params: &[DatatypeTyParameter]) -> String {
+    type_parameters(params.iter().map(|p| (p.is_phantom, p.constraints)))
+}
+
+fn type_parameters(params: impl Iterator<Item = (bool, AbilitySet)>) -> String {
+    let params: Vec<_> = params
+        .enumerate()
+        .map(|(i, (is_phantom, constraints))| {
+            let phantom = if is_phantom { "phantom " } else { "" };
+            let constraints = constraints
+                .into_iter()
+                .map(ability)
+                .collect::<Vec<_>>()
+                .join(" + ");
+            if constraints.is_empty() {
+                format!("{phantom}T{i}")
+            } else {
+                format!("{phantom}T{i}: {constraints}")
+            }
+        })
+        .collect();
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", params.join(", "))
+    }
+}
+
+fn has(set: AbilitySet) -> String {
+    if set == AbilitySet::EMPTY {
+        String::new()
+    } else {
+        format!(" has {}", abilities(set))
+    }
+}
+
+fn abilities(set: AbilitySet) -> String {
+    set.into_iter().map(ability).collect::<Vec<_>>().join(", ")
+}
+
+fn ability(ability: Ability) -> &'static str {
+    match ability {
+        Ability::Copy => "copy",
+        Ability::Drop => "drop",
+        Ability::Store => "store",
+        Ability::Key => "key",
+    }
+}
+
+fn types(self_address: AccountAddress, types: &[Type]) -> String {
+    types
+        .iter()
+        .map(|t| type_(self_address, t))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn type_(self_address: AccountAddress, t: &Type) -> String {
+    match t {
+        Type::Struct {
+            address,
+            module,
+            name,
+            type_arguments,
+        } => {
+            let path = datatype_path(self_address, address, &format!("{module}::{name}"));
+            if type_arguments.is_empty() {
+                path
+            } else {
+                format!("{path}<{}>", types(self_address, type_arguments))
+            }
+        }
+        Type::Vector(t) => format!("vector<{}>", type_(self_address, t)),
+        Type::Reference(t) => format!("&{}", type_(self_address, t)),
+        Type::MutableReference(t) => format!("&mut {}", type_(self_address, t)),
+        t => t.to_string(),
+    }
+}
+
+fn datatype_path(self_address: AccountAddress, address: &AccountAddress, path: &str) -> String {
+    if *address == self_address {
+        path.to_string()
+    } else {
+        format!("0x{}::{path}", address.short_str_lossless())
+    }
+}
+
+/// A changelog of the API changes, with a section per module.
+pub fn render_markdown(diff: &PackageDiff) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# API changes from `{}` to `{}`\n", diff.from, diff.to);
+    if diff.modules.is_empty() {
+        let _ = writeln!(out, "No changes.");
+        return out;
+    }
+
+    let changes = || diff.modules.iter().flat_map(|m| &m.changes);
+    let breaking = changes().filter(|c| c.breaking).count();
+    let breaking_compatible = changes().filter(|c| c.breaking && c.compatible).count();
+    let _ = writeln!(
+        out,
+        "{} changes, {breaking} of them breaking ({breaking_compatible} allowed in a compatible \
+         upgrade). The upgrade is {}.",
+        changes().count(),
+        if diff.compatible {
+            "compatible"
+        } else {
+            "incompatible"
+        }
+    );
+
+    for module in &diff.modules {
+        let _ = writeln!(out, "\n## Module `{}`\n", module.module);
+        for c in &module.changes {
+            let marker = match (c.breaking, c.compatible) {
+                (true, true) => "**breaking, though the upgrade is compatible** ",
+                (true, false) => "**breaking** ",
+                (false, false) => "**incompatible upgrade** ",
+                (false, true) => "",
+            };
+            let kind = match c.kind {
+                ChangeKind::Added => "Added",
+                ChangeKind::Removed => "Removed",
+                ChangeKind::Changed => "Changed",
+            };
+            let item = match c.item {
+                ApiItem::Module => "module",
+                ApiItem::Function => "function",
+                ApiItem::Struct => "struct",
+                ApiItem::Enum => "enum",
+                ApiItem::Variant => "variant",
+                ApiItem::Friend => "friend",
+            };
+            let aspect = c.aspect.map_or_else(String::new, |a| format!(" {a}"));
+            let detail = match (&c.before, &c.after) {
+                (Some(before), Some(after)) => format!(": `{before}` → `{after}`"),
+                (Some(code), None) | (None, Some(code)) => format!(": `{code}`"),
+                (None, None) => String::new(),
+            };
+            let _ = writeln!(out, "- {marker}{kind} {item} `{}`{aspect}{detail}", c.name);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_compiler::{
+        editions::Edition,
+        shared::{NumericalAddress, PackageConfig},
+        Compiler,
+    };
+
+    const V1: &str = r#"
+        module ADDR::m {
+            public struct Coin has store { value: u64 }
+            public enum Shape has copy, drop { Circle { r: u64 }, Square { side: u64 } }
+
+            public fun value(c: &Coin): u64 { c.value }
+            public fun keep<T: copy + drop>(x: T): T { x }
+            public fun promote(_x: u64) {}
+            entry fun run(_x: u64) {}
+            public(package) fun internal(): u64 { 0 }
+        }
+
+        module ADDR::n {
+            public fun call(): u64 { ADDR::m::internal() }
+        }
+    "#;
+
+    const V2: &str = r#"
+        module ADDR::m {
+            public struct Coin has store { value: u64 }
+            public enum Shape has copy, drop { Circle { r: u64 }, Square { side: u32 }, Point }
+
+            public fun value(c: &Coin): u64 { c.value }
+            public fun keep<T: drop>(x: T): T { x }
+            entry fun promote(_x: u64) {}
+            entry fun run(_x: u64, _y: u64) {}
+            public(package) fun internal(): u64 { 0 }
+        }
+
+        module ADDR::n {
+            public fun call(): u64 { 0 }
+        }
+    "#;
+
+    /// Compile the modules in `source`, with `ADDR` replaced by `address`.
+    fn compile(source: &str, address: &str) -> Vec<CompiledModule> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sources.move");
+        std::fs::write(&path, source.replace("ADDR", address)).unwrap();
+
+        let (_, units) = Compiler::from_files(
+            None,
+            vec![path.to_str().unwrap().to_string()],
+            vec![],
+            BTreeMap::<String, NumericalAddress>::new(),
+        )
+        .set_default_config(PackageConfig {
+            edition: Edition::E2024_BETA,
+            ..PackageConfig::default()
+        })
+        .build_and_report()
+        .unwrap();
+
+        units.into_iter().map(|u| u.named_module.module).collect()
+    }
+
+    fn diff(from: &[CompiledModule], to: &[CompiledModule]) -> PackageDiff {
+        diff_packages("v1".to_string(), "v2".to_string(), from, to)
+    }
+
+    #[test]
+    fn test_classification() {
+        let diff = diff(&compile(V1, "0x42"), &compile(V2, "0x42"));
+
+        // Module `n` only lost a call to a `public(package)` function, which is not part of the
+        // API.
+        assert_eq!(diff.modules.len(), 1);
+        assert_eq!(diff.modules[0].module, "m");
+
+        let changes: Vec<_> = diff.modules[0]
+            .changes
+            .iter()
+            .map(|c| {
+                (
+                    c.kind,
+                    c.item,
+                    c.name.as_str(),
+                    c.before.as_deref(),
+                    c.after.as_deref(),
+                    c.breaking,
+                    c.compatible,
+                )
+            })
+            .collect();
+
+        use ApiItem as I;
+        use ChangeKind as K;
+        assert_eq!(
+            changes,
+            vec![
+                // Callers' instantiations are all still allowed.
+                (
+                    K::Changed,
+                    I::Function,
+                    "keep",
+                    Some("public fun keep<T0: copy + drop>(T0): T0"),
+                    Some("public fun keep<T0: drop>(T0): T0"),
+                    false,
+                    true,
+                ),
+                // Other packages can no longer call it.
+                (
+                    K::Changed,
+                    I::Function,
+                    "promote",
+                    Some("public fun promote(u64)"),
+                    Some("entry fun promote(u64)"),
+                    true,
+                    false,
+                ),
+                // Breaks transactions, but upgrades can change non-public entry functions.
+                (
+                    K::Changed,
+                    I::Function,
+                    "run",
+                    Some("entry fun run(u64)"),
+                    Some("entry fun run(u64, u64)"),
+                    true,
+                    true,
+                ),
+                (
+                    K::Changed,
+                    I::Variant,
+                    "Shape::Square",
+                    Some("Square { side: u64 }"),
+                    Some("Square { side: u32 }"),
+                    true,
+                    false,
+                ),
+                (
+                    K::Added,
+                    I::Variant,
+                    "Shape::Point",
+                    None,
+                    Some("Point"),
+                    true,
+                    false,
+                ),
+                (K::Removed, I::Friend, "n", None, None, false, true),
+            ]
+        );
+
+        assert!(diff.breaking);
+        assert!(!diff.compatible);
+    }
+
+    #[test]
+    fn test_markdown() {
+        let diff = diff(&compile(V1, "0x42"), &compile(V2, "0x42"));
+        assert_eq!(
+            render_markdown(&diff),
+            "# API changes from `v1` to `v2`\n\
+             \n\
+             6 changes, 4 of them breaking (1 allowed in a compatible upgrade). The upgrade is \
+             incompatible.\n\
+             \n\
+             ## Module `m`\n\
+             \n\
+             - Changed function `keep` signature: \
+               `public fun keep<T0: copy + drop>(T0): T0` → `public fun keep<T0: drop>(T0): T0`\n\
+             - **breaking** Changed function `promote` signature: \
+               `public fun promote(u64)` → `entry fun promote(u64)`\n\
+             - **breaking, though the upgrade is compatible** Changed function `run` signature: \
+               `entry fun run(u64)` → `entry fun run(u64, u64)`\n\
+             - **breaking** Changed variant `Shape::Square`: \
+               `Square { side: u64 }` → `Square { side: u32 }`\n\
+             - **breaking** Added variant `Shape::Point`: `Point`\n\
+             - Removed friend `n`\n"
+        );
+    }
+
+    #[test]
+    fn test_self_address_normalization() {
+        // The same package, published at a different address, has the same API: types from the
+        // package itself are compared without their address.
+        let diff = diff(&compile(V1, "0x42"), &compile(V1, "0x43"));
+        assert!(diff.modules.is_empty(), "{diff:#?}");
+        assert_eq!(
+            render_markdown(&diff),
+            "# API changes from `v1` to `v2`\n\nNo changes.\n"
+        );
+
+        let package = AccountAddress::from_hex_literal("0x42").unwrap();
+        assert_eq!(datatype_path(package, &package, "m::Coin"), "m::Coin");
+        assert_eq!(
+            datatype_path(package, &AccountAddress::TWO, "coin::Coin"),
+            "0x2::coin::Coin"
+        );
+    }
+}
@@ -8,31 +8,43 @@ use move_package::BuildConfig;
 use std::path::Path;
 use sui_move_build::set_sui_flavor;
 
+#[cfg(feature = "build")]
+pub mod api_diff;
 #[cfg(feature = "build")]
 pub mod build;
 #[cfg(feature = "coverage")]
 pub mod coverage;
 #[cfg(feature = "disassemble")]
 pub mod disassemble;
+pub mod fmt;
 pub mod manage_package;
 pub mod migrate;
+#[cfg(feature = "unit_test")]
+pub mod mutate;
 pub mod new;
 #[cfg(feature = "unit_test")]
 pub mod unit_test;
+pub mod vendor;
 
 #[derive(Parser)]
 pub enum Command {
+    #[cfg(feature = "build")]
+    ApiDiff(api_diff::ApiDiff),
     #[cfg(feature = "build")]
     Build(build::Build),
     #[cfg(feature = "coverage")]
     Coverage(coverage::Coverage),
     #[cfg(feature = "disassemble")]
     Disassemble(disassemble::Disassemble),
+    Fmt(fmt::Fmt),
     ManagePackage(manage_package::ManagePackage),
     Migrate(migrate::Migrate),
+    #[cfg(feature = "unit_test")]
+    Mutate(mutate::Mutate),
     New(new::New),
     #[cfg(feature = "unit_test")]
     Test(unit_test::Test),
+    Vendor(vendor::Vendor),
 }
 #[derive(Parser)]
 pub struct Calib {
@@ -51,14 +63,19 @@ pub fn execute_move_command(
         anyhow::bail!(err_msg);
     }
     match command {
+        #[cfg(feature = "build")]
+        Command::ApiDiff(c) => c.execute(package_path, build_config),
         #[cfg(feature = "build")]
         Command::Build(c) => c.execute(package_path, build_config),
         #[cfg(feature = "coverage")]
         Command::Coverage(c) => c.execute(package_path, build_config),
         #[cfg(feature = "disassemble")]
         Command::Disassemble(c) => c.execute(package_path, build_config),
+        Command::Fmt(c) => c.execute(package_path, build_config),
         Command::ManagePackage(c) => c.execute(package_path, build_config),
         Command::Migrate(c) => c.execute(package_path, build_config),
+        #[cfg(feature = "unit_test")]
+        Command::Mutate(c) => c.execute(package_path, build_config),
         Command::New(c) => c.execute(package_path),
 
         #[cfg(feature = "unit_test")]
@@ -72,5 +89,6 @@ pub fn execute_move_command(
 
             Ok(())
         }
+        Command::Vendor(c) => c.execute(package_path, build_config),
     }
 }
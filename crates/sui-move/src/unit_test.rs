@@ -21,7 +21,7 @@ use sui_types::{
 };
 
 // Move unit tests will halt after executing this many steps. This is a protection to avoid divergence
-const MAX_UNIT_TEST_INSTRUCTIONS: u64 = 1_000_000;
+pub(crate) const MAX_UNIT_TEST_INSTRUCTIONS: u64 = 1_000_000;
 
 #[derive(Parser)]
 #[group(id = "sui-move-test")]
@@ -61,7 +61,7 @@ thread_local! {
 
 static TEST_STORE: Lazy<InMemoryTestStore> = Lazy::new(|| InMemoryTestStore(&TEST_STORE_INNER));
 
-static SET_EXTENSION_HOOK: Lazy<()> =
+pub(crate) static SET_EXTENSION_HOOK: Lazy<()> =
     Lazy::new(|| set_extension_hook(Box::new(new_testing_object_and_natives_cost_runtime)));
 
 /// This function returns a result of UnitTestResult. The outer result indicates whether it
@@ -0,0 +1,20 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use clap::Parser;
+use move_cli::base::vendor;
+use move_package::BuildConfig as MoveBuildConfig;
+use std::path::Path;
+
+#[derive(Parser)]
+#[group(id = "sui-move-vendor")]
+pub struct Vendor {
+    #[clap(flatten)]
+    pub vendor: vendor::Vendor,
+}
+
+impl Vendor {
+    pub fn execute(self, path: Option<&Path>, config: MoveBuildConfig) -> anyhow::Result<()> {
+        self.vendor.execute(path, config)
+    }
+}
@@ -18,6 +18,7 @@ use sui_telemetry::send_telemetry_event;
 use sui_types::committee::EpochId;
 use sui_types::messages_checkpoint::CheckpointSequenceNumber;
 use sui_types::multiaddr::Multiaddr;
+use sui_types::sui_system_state::SuiSystemStateTrait;
 use sui_types::supported_protocol_versions::SupportedProtocolVersions;
 
 // Define the `GIT_REVISION` and `VERSION` consts
@@ -81,6 +82,7 @@ fn main() {
         .with_env()
         .with_prom_registry(&prometheus_registry)
         .init();
+    filter_handle.add_otlp_metrics_registry(&prometheus_registry);
 
     drop(metrics_rt);
 
@@ -155,6 +157,26 @@ fn main() {
         };
 
         info!("Sui chain identifier: {chain_identifier}");
+        filter_handle.set_otlp_resource_attribute("sui.chain_id", chain_identifier.as_str());
+        let epoch_filter_handle = filter_handle.clone();
+        let mut epoch_rx = node.subscribe_to_epoch_change();
+        epoch_filter_handle.set_otlp_resource_attribute(
+            "sui.epoch",
+            node.state()
+                .load_epoch_store_one_call_per_task()
+                .epoch()
+                .to_string(),
+        );
+        tokio::spawn(async move {
+            loop {
+                match epoch_rx.recv().await {
+                    Ok(system_state) => epoch_filter_handle
+                        .set_otlp_resource_attribute("sui.epoch", system_state.epoch().to_string()),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
         prometheus_registry
             .register(mysten_metrics::uptime_metric(
                 if is_validator {
@@ -4,6 +4,7 @@
 pub use crate::json_rpc_error::Error as JsonRpcError;
 use sui_types::base_types::{SuiAddress, TransactionDigest};
 use sui_types::error::UserInputError;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
 use thiserror::Error;
 
 pub type SuiRpcResult<T = ()> = Result<T, Error>;
@@ -31,4 +32,8 @@ pub enum Error {
     },
     #[error("Insufficient fund for address [{address}], requested amount: {amount}")]
     InsufficientFund { address: SuiAddress, amount: u128 },
+    #[error("No RPC endpoint has reached checkpoint {0}")]
+    CheckpointNotReached(CheckpointSequenceNumber),
+    #[error("No RPC endpoint is available")]
+    NoEndpointAvailable,
 }
@@ -0,0 +1,1254 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Parallel transaction execution for a set of senders.
+//!
+//! Owned objects (including gas coins) can only be used by one transaction at a time: two
+//! transactions signed by the same sender that use the same owned object at the same version
+//! equivocate, and lock that object until the end of the epoch. The [ParallelExecutor] avoids
+//! this by:
+//!
+//! * Splitting each sender's SUI into a pool of gas coins, and handing out each coin to at most
+//!   one transaction at a time.
+//! * Tracking the latest versions of the objects it has seen in transaction effects, and
+//!   updating stale object references in the transactions it is asked to execute.
+//! * Queueing transactions that use the same owned objects, so that they run one after the
+//!   other, while transactions that touch disjoint sets of owned objects run in parallel.
+//!
+//! Gas coins whose balance drops below a threshold are retired from the pool, and merged back
+//! into a pool coin by a background task. If a transaction's outcome is unknown (e.g. because
+//! submitting it failed), its gas coin is only returned to the pool once its latest version has
+//! been found, from the transaction's effects or by reading the coin. If all of a sender's coins
+//! are retired, and together they are not worth a usable gas coin, the sender's pool is closed,
+//! and its transactions fail with an insufficient gas error.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context};
+use futures::stream::{self, StreamExt};
+use shared_crypto::intent::Intent;
+use sui_json_rpc_types::{
+    Coin, SuiObjectDataOptions, SuiTransactionBlockEffects, SuiTransactionBlockEffectsAPI,
+    SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions,
+};
+use sui_keys::keystore::{AccountKeystore, Keystore};
+use sui_types::base_types::{ObjectID, ObjectRef, SuiAddress};
+use sui_types::digests::TransactionDigest;
+use sui_types::error::SuiObjectResponseError;
+use sui_types::gas_coin::GasCoin;
+use sui_types::object::Owner;
+use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use sui_types::quorum_driver_types::ExecuteTransactionRequestType;
+use sui_types::transaction::{
+    CallArg, ObjectArg, ProgrammableTransaction, Transaction, TransactionData,
+};
+use tokio::sync::{Notify, OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::SuiClient;
+
+/// The maximum number of gas coins that can be used to pay for a single transaction.
+const MAX_GAS_PAYMENT_OBJECTS: usize = 256;
+
+/// How many times to look up the effects of a transaction whose outcome is unknown, before
+/// falling back to reading the latest versions of its gas coins.
+const RECOVERY_ATTEMPTS: usize = 5;
+
+/// How long to wait before each attempt to look up a transaction's effects.
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration for a [ParallelExecutor].
+#[derive(Clone, Debug)]
+pub struct ExecutorConfig {
+    /// Number of gas coins to split off for each sender, which bounds the number of that
+    /// sender's transactions that can be in flight at once. Must be positive.
+    pub gas_coins_per_sender: usize,
+    /// Balance (in MIST) of each gas coin split off for a sender.
+    pub gas_coin_balance: u64,
+    /// Gas coins whose balance drops below this amount (in MIST) are retired from the pool, and
+    /// merged back into another gas coin in the background. Must be at least `gas_budget`.
+    pub min_gas_coin_balance: u64,
+    /// Gas budget for every transaction the executor runs. Must be positive.
+    pub gas_budget: u64,
+    /// Maximum number of transactions that [ParallelExecutor::execute_all] runs concurrently.
+    /// Must be positive.
+    pub max_in_flight: usize,
+}
+
+/// Executes programmable transactions for a set of senders, in parallel, while avoiding
+/// equivocation on gas coins and owned objects. See the module documentation for details.
+pub struct ParallelExecutor {
+    inner: Arc<Inner>,
+    merge_task: JoinHandle<()>,
+}
+
+struct Inner {
+    client: SuiClient,
+    keystore: Arc<Keystore>,
+    config: ExecutorConfig,
+    gas_price: u64,
+    pools: RwLock<HashMap<SuiAddress, Arc<GasPool>>>,
+    /// The latest known reference for every object that has appeared in transaction effects.
+    versions: Mutex<HashMap<ObjectID, ObjectRef>>,
+    locks: ObjectLocks,
+    /// Signalled when gas coins are retired, to wake up the background merge task.
+    merge_requested: Notify,
+}
+
+/// A sender's gas coins. Each coin is either available, in use by exactly one transaction, or
+/// retired and waiting to be merged. The pool is closed if its coins could not be split off, or if
+/// its sender has run out of gas.
+struct GasPool {
+    coins: Mutex<PoolCoins>,
+    /// One permit per available coin.
+    permits: Arc<Semaphore>,
+}
+
+#[derive(Default)]
+struct PoolCoins {
+    available: Vec<PooledCoin>,
+    retired: Vec<PooledCoin>,
+    /// The number of coins checked out of the pool.
+    checked_out: usize,
+    /// Whether the coins split off for the pool's sender have been added to it.
+    filled: bool,
+    /// Why the pool was closed, if it has been.
+    closed: Option<String>,
+}
+
+/// Whether a [GasPool]'s sender can still pay for transactions.
+#[derive(Debug, PartialEq, Eq)]
+enum Solvency {
+    /// Some of the pool's coins are available or checked out (or it has not been filled yet).
+    Active,
+    /// All the pool's coins are retired, but they can be merged into a usable coin.
+    NeedsMerge,
+    /// All the pool's coins are retired, and their total balance cannot pay for a transaction that
+    /// leaves a usable coin.
+    Insufficient { balance: u64 },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct PooledCoin {
+    object_ref: ObjectRef,
+    balance: u64,
+}
+
+/// A gas coin checked out of a [GasPool], which must be returned through [GasPool::release] or
+/// [GasPool::remove].
+struct CheckedOutCoin {
+    coin: PooledCoin,
+    permit: OwnedSemaphorePermit,
+}
+
+/// Per-object locks, used to queue up transactions that use the same owned objects.
+#[derive(Default)]
+struct ObjectLocks {
+    locks: Mutex<HashMap<ObjectID, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// Holds the locks on a set of objects, releasing them when dropped.
+struct ObjectLockGuard<'l> {
+    locks: &'l ObjectLocks,
+    ids: Vec<ObjectID>,
+    guards: Vec<OwnedMutexGuard<()>>,
+}
+
+impl ParallelExecutor {
+    /// Create an executor that sends transactions through `client`, signed by keys from
+    /// `keystore`. Senders need to be registered with [Self::add_sender] before their
+    /// transactions can be executed.
+    pub async fn new(
+        client: SuiClient,
+        keystore: Arc<Keystore>,
+        config: ExecutorConfig,
+    ) -> anyhow::Result<Self> {
+        config.validate()?;
+
+        let gas_price = client
+            .governance_api()
+            .get_reference_gas_price()
+            .await
+            .context("Failed to fetch reference gas price")?;
+
+        let inner = Arc::new(Inner {
+            client,
+            keystore,
+            config,
+            gas_price,
+            pools: RwLock::new(HashMap::new()),
+            versions: Mutex::new(HashMap::new()),
+            locks: ObjectLocks::default(),
+            merge_requested: Notify::new(),
+        });
+
+        let merge_task = tokio::spawn(inner.clone().merge_retired_coins());
+        Ok(Self { inner, merge_task })
+    }
+
+    /// Split `sender`'s SUI into a pool of gas coins, so that transactions can be executed on
+    /// its behalf. The keystore must hold `sender`'s key. Transactions for `sender` that are
+    /// submitted while its coins are being split off wait for them to become available.
+    pub async fn add_sender(&self, sender: SuiAddress) -> anyhow::Result<()> {
+        let pool = match self.inner.pools.write().unwrap().entry(sender) {
+            Entry::Occupied(_) => bail!("Sender {sender} has already been added"),
+            Entry::Vacant(entry) => entry.insert(Arc::new(GasPool::new())).clone(),
+        };
+
+        let coins = match self.split_gas(sender).await {
+            Ok(coins) => coins,
+            Err(e) => {
+                self.inner.pools.write().unwrap().remove(&sender);
+                pool.close(format!(
+                    "Sender {sender} could not be added to the executor: {e:#}"
+                ));
+                return Err(e);
+            }
+        };
+
+        info!("Added sender {sender} with {} gas coins", coins.len());
+        pool.fill(coins, self.inner.config.min_gas_coin_balance);
+        Ok(())
+    }
+
+    /// Split `sender`'s SUI into the gas coins for its pool.
+    async fn split_gas(&self, sender: SuiAddress) -> anyhow::Result<Vec<PooledCoin>> {
+        let ExecutorConfig {
+            gas_coins_per_sender: count,
+            gas_coin_balance: balance,
+            min_gas_coin_balance,
+            gas_budget,
+            ..
+        } = self.inner.config;
+
+        let amount = count as u128 * balance as u128 + gas_budget as u128;
+        let coins = self
+            .inner
+            .client
+            .coin_read_api()
+            .select_coins(sender, None, amount, vec![])
+            .await?;
+
+        if coins.len() > MAX_GAS_PAYMENT_OBJECTS {
+            bail!(
+                "Sender {sender} needs {} coins to cover {amount} MIST, but at most \
+                 {MAX_GAS_PAYMENT_OBJECTS} can be used for gas",
+                coins.len(),
+            );
+        }
+
+        let total: u64 = coins.iter().map(|c| c.balance).sum();
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.pay_sui(vec![sender; count], vec![balance; count])?;
+
+        let gas = coins.iter().map(Coin::object_ref).collect();
+        let data = self.inner.transaction_data(sender, gas, builder.finish());
+        let response = self.inner.submit(sender, data).await?;
+        let effects = expect_success(&response)?;
+        self.inner.record_effects(effects);
+
+        let mut pooled: Vec<_> = effects
+            .created()
+            .iter()
+            .filter(|o| o.owner == Owner::AddressOwner(sender))
+            .map(|o| PooledCoin {
+                object_ref: o.reference.to_object_ref(),
+                balance,
+            })
+            .collect();
+
+        // Whatever is left over in the coin that paid for the split joins the pool as well, if
+        // it is large enough.
+        let remainder = (total - count as u64 * balance)
+            .saturating_add_signed(-effects.gas_cost_summary().net_gas_usage());
+        if remainder >= min_gas_coin_balance {
+            pooled.push(PooledCoin {
+                object_ref: effects.gas_object().reference.to_object_ref(),
+                balance: remainder,
+            });
+        }
+
+        Ok(pooled)
+    }
+
+    /// Execute `pt` on behalf of `sender`, paying for gas with a coin from the sender's pool.
+    ///
+    /// References to owned objects in `pt`'s inputs are replaced with the latest versions the
+    /// executor has seen, so callers can re-use object references across transactions. The
+    /// transaction waits until no other transaction from this executor is using any of its owned
+    /// inputs, and until one of the sender's gas coins is available.
+    pub async fn execute(
+        &self,
+        sender: SuiAddress,
+        pt: ProgrammableTransaction,
+    ) -> anyhow::Result<SuiTransactionBlockResponse> {
+        self.inner.execute(sender, pt).await
+    }
+
+    /// Execute every transaction in `txs`, running up to `max_in_flight` of them at a time.
+    /// Results are returned in the same order as the transactions were supplied.
+    pub async fn execute_all(
+        &self,
+        txs: impl IntoIterator<Item = (SuiAddress, ProgrammableTransaction)>,
+    ) -> Vec<anyhow::Result<SuiTransactionBlockResponse>> {
+        stream::iter(txs)
+            .map(|(sender, pt)| self.inner.execute(sender, pt))
+            .buffered(self.inner.config.max_in_flight)
+            .collect()
+            .await
+    }
+
+    /// The latest reference to `id` that the executor has seen in transaction effects, if any.
+    pub fn latest_object_ref(&self, id: &ObjectID) -> Option<ObjectRef> {
+        self.inner.versions.lock().unwrap().get(id).copied()
+    }
+}
+
+impl Drop for ParallelExecutor {
+    fn drop(&mut self) {
+        self.merge_task.abort();
+    }
+}
+
+impl Inner {
+    async fn execute(
+        &self,
+        sender: SuiAddress,
+        pt: ProgrammableTransaction,
+    ) -> anyhow::Result<SuiTransactionBlockResponse> {
+        let pool = self
+            .pools
+            .read()
+            .unwrap()
+            .get(&sender)
+            .cloned()
+            .ok_or_else(|| anyhow!("Sender {sender} has not been added to the executor"))?;
+
+        // Inputs are only refreshed once their locks are held, so that a transaction queued behind
+        // another one using the same owned object sees the version that transaction produced.
+        let owned = owned_inputs(&pt);
+        let _locks = self.locks.acquire(owned.clone()).await;
+        let pt = refresh_inputs(pt, &self.versions.lock().unwrap());
+        let checked_out = pool.acquire().await?;
+
+        let data = self.transaction_data(sender, vec![checked_out.coin.object_ref], pt);
+        let digest = *data.digest();
+        let response = self.submit(sender, data).await;
+
+        // If there are no effects, the transaction may or may not have been executed, so neither
+        // the gas coin nor the owned inputs can be re-used until their latest versions are known.
+        let effects = match &response {
+            Ok(SuiTransactionBlockResponse {
+                effects: Some(effects),
+                ..
+            }) => Some(effects.clone()),
+            _ => self.find_effects(digest).await,
+        };
+
+        let Some(effects) = effects else {
+            self.forget(&owned);
+            let coin = self.latest_coin(checked_out.coin).await;
+            self.return_coin(&pool, checked_out, coin);
+            return response;
+        };
+
+        self.record_effects(&effects);
+        let coin = PooledCoin {
+            object_ref: effects.gas_object().reference.to_object_ref(),
+            balance: checked_out
+                .coin
+                .balance
+                .saturating_add_signed(-effects.gas_cost_summary().net_gas_usage()),
+        };
+
+        self.return_coin(&pool, checked_out, Some(coin));
+        response
+    }
+
+    fn transaction_data(
+        &self,
+        sender: SuiAddress,
+        gas: Vec<ObjectRef>,
+        pt: ProgrammableTransaction,
+    ) -> TransactionData {
+        TransactionData::new_programmable(sender, gas, pt, self.config.gas_budget, self.gas_price)
+    }
+
+    /// Sign and execute a transaction, waiting for its effects.
+    async fn submit(
+        &self,
+        sender: SuiAddress,
+        data: TransactionData,
+    ) -> anyhow::Result<SuiTransactionBlockResponse> {
+        let signature = self
+            .keystore
+            .sign_secure(&sender, &data, Intent::sui_transaction())
+            .map_err(|e| anyhow!("Failed to sign transaction for {sender}: {e}"))?;
+
+        Ok(self
+            .client
+            .quorum_driver_api()
+            .execute_transaction_block(
+                Transaction::from_data(data, vec![signature]),
+                SuiTransactionBlockResponseOptions::new()
+                    .with_effects()
+                    .with_events()
+                    .with_object_changes()
+                    .with_balance_changes(),
+                Some(ExecuteTransactionRequestType::WaitForLocalExecution),
+            )
+            .await?)
+    }
+
+    fn record_effects(&self, effects: &SuiTransactionBlockEffects) {
+        let mut versions = self.versions.lock().unwrap();
+        for (changed, _) in effects.all_changed_objects() {
+            let object_ref = changed.reference.to_object_ref();
+            versions.insert(object_ref.0, object_ref);
+        }
+
+        for (deleted, _) in effects.all_deleted_objects() {
+            versions.remove(&deleted.object_id);
+        }
+    }
+
+    fn forget(&self, ids: &[ObjectID]) {
+        let mut versions = self.versions.lock().unwrap();
+        for id in ids {
+            versions.remove(id);
+        }
+    }
+
+    /// Look up the effects of a transaction whose outcome is unknown, giving it some time to
+    /// finish executing if it was submitted.
+    async fn find_effects(&self, digest: TransactionDigest) -> Option<SuiTransactionBlockEffects> {
+        for _ in 0..RECOVERY_ATTEMPTS {
+            tokio::time::sleep(RECOVERY_INTERVAL).await;
+            let response = self
+                .client
+                .read_api()
+                .get_transaction_with_options(
+                    digest,
+                    SuiTransactionBlockResponseOptions::new().with_effects(),
+                )
+                .await;
+
+            if let Ok(SuiTransactionBlockResponse {
+                effects: Some(effects),
+                ..
+            }) = response
+            {
+                return Some(effects);
+            }
+        }
+
+        warn!("Could not find effects for transaction {digest}");
+        None
+    }
+
+    /// The latest state of a gas coin, read from the network, or `None` if it has been deleted.
+    /// If the coin cannot be read, it is assumed to be unchanged: if that is wrong, the next
+    /// transaction to use it fails, and it is recovered again.
+    async fn latest_coin(&self, coin: PooledCoin) -> Option<PooledCoin> {
+        let id = coin.object_ref.0;
+        let response = self
+            .client
+            .read_api()
+            .get_object_with_options(id, SuiObjectDataOptions::new().with_content())
+            .await;
+
+        let object = match response.map(|r| r.into_object()) {
+            Ok(Ok(object)) => object,
+            Ok(Err(
+                SuiObjectResponseError::NotExists { .. } | SuiObjectResponseError::Deleted { .. },
+            )) => return None,
+            Ok(Err(e)) => {
+                warn!("Failed to read gas coin {id}: {e}");
+                return Some(coin);
+            }
+            Err(e) => {
+                warn!("Failed to read gas coin {id}: {e}");
+                return Some(coin);
+            }
+        };
+
+        match GasCoin::try_from(&object) {
+            Ok(gas) => Some(PooledCoin {
+                object_ref: object.object_ref(),
+                balance: gas.value(),
+            }),
+            Err(e) => {
+                warn!("Failed to read gas coin {id}: {e}");
+                Some(coin)
+            }
+        }
+    }
+
+    /// Return a checked out coin to the pool at its latest state, or remove it from the pool if
+    /// it has been deleted, waking up the merge task if the coin was retired or removed (so that
+    /// it can check whether the pool has run out of gas).
+    fn return_coin(&self, pool: &GasPool, checked_out: CheckedOutCoin, coin: Option<PooledCoin>) {
+        match coin {
+            Some(coin) => {
+                if pool.release(checked_out, coin, self.config.min_gas_coin_balance) {
+                    self.merge_requested.notify_one();
+                }
+            }
+            None => {
+                pool.remove(checked_out);
+                self.merge_requested.notify_one();
+            }
+        }
+    }
+
+    /// Background task that merges retired gas coins back into a coin from the same pool, by
+    /// using them all as gas payment for a transaction that sends the gas coin back to its owner.
+    async fn merge_retired_coins(self: Arc<Self>) {
+        loop {
+            self.merge_requested.notified().await;
+
+            let pools: Vec<_> = self
+                .pools
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(sender, pool)| (*sender, pool.clone()))
+                .collect();
+
+            for (sender, pool) in pools {
+                if let Err(e) = self.merge(sender, &pool).await {
+                    warn!("Failed to merge retired gas coins for {sender}: {e}");
+                }
+            }
+        }
+    }
+
+    /// Merge `pool`'s retired coins, and then check that its sender can still pay for
+    /// transactions. Once every coin is retired, no transaction will return a coin to the pool,
+    /// so either the retired coins are merged again, or the pool is closed, to fail the
+    /// transactions waiting on it rather than leaving them to wait forever.
+    async fn merge(&self, sender: SuiAddress, pool: &GasPool) -> anyhow::Result<()> {
+        let result = self.merge_retired(sender, pool).await;
+
+        match pool.solvency(self.config.gas_budget) {
+            Solvency::Active => {}
+            Solvency::NeedsMerge => self.merge_requested.notify_one(),
+            Solvency::Insufficient { balance } => {
+                warn!("Sender {sender} has run out of gas, closing its gas pool");
+                pool.close(format!(
+                    "Insufficient gas: sender {sender}'s remaining gas coins hold {balance} MIST, \
+                     which is less than the minimum gas coin balance ({})",
+                    self.config.min_gas_coin_balance,
+                ));
+            }
+        }
+
+        result
+    }
+
+    async fn merge_retired(&self, sender: SuiAddress, pool: &GasPool) -> anyhow::Result<()> {
+        let min_balance = self.config.min_gas_coin_balance;
+        let mut retired = pool.take_retired(MAX_GAS_PAYMENT_OBJECTS - 1);
+        if retired.is_empty() {
+            return Ok(());
+        }
+
+        // Prefer merging into an available coin, but don't wait for one: if every coin is in use
+        // or retired, merge the retired coins into each other.
+        let primary = match pool.try_acquire() {
+            Some(checked_out) => Primary::Pool(checked_out),
+            None if retired.len() > 1 => Primary::Retired(retired.remove(0)),
+            None => {
+                pool.restore(retired, min_balance);
+                return Ok(());
+            }
+        };
+
+        let primary_coin = primary.coin();
+        let total: u64 = retired.iter().map(|c| c.balance).sum::<u64>() + primary_coin.balance;
+        if total < self.config.gas_budget {
+            // Not enough SUI between these coins to pay for the merge yet.
+            primary.restore(pool, min_balance);
+            pool.restore(retired, min_balance);
+            return Ok(());
+        }
+
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.pay_all_sui(sender);
+
+        let gas = std::iter::once(primary_coin)
+            .chain(retired.iter().copied())
+            .map(|c| c.object_ref)
+            .collect();
+
+        let data = self.transaction_data(sender, gas, builder.finish());
+        let digest = *data.digest();
+        let (error, effects) = match self.submit(sender, data).await {
+            Ok(response) => (None, response.effects),
+            Err(e) => (Some(e), None),
+        };
+
+        let effects = match effects {
+            Some(effects) => Some(effects),
+            None => self.find_effects(digest).await,
+        };
+
+        let Some(effects) = effects else {
+            // Whether the coins were merged is unknown, so each of them goes back to the pool at
+            // its latest state.
+            let mut coins = vec![];
+            for coin in retired {
+                coins.extend(self.latest_coin(coin).await);
+            }
+
+            let latest = self.latest_coin(primary_coin).await;
+            match primary {
+                Primary::Pool(checked_out) => self.return_coin(pool, checked_out, latest),
+                Primary::Retired(_) => coins.extend(latest),
+            }
+
+            pool.restore(coins, min_balance);
+            return Err(error.unwrap_or_else(|| anyhow!("No effects for transaction {digest}")));
+        };
+
+        // Gas coins are merged into the first of them whether or not the transaction succeeds.
+        self.record_effects(&effects);
+        let coin = PooledCoin {
+            object_ref: effects.gas_object().reference.to_object_ref(),
+            balance: total.saturating_add_signed(-effects.gas_cost_summary().net_gas_usage()),
+        };
+
+        match primary {
+            Primary::Pool(checked_out) => pool.release(checked_out, coin, min_balance),
+            Primary::Retired(_) => pool.restore(vec![coin], min_balance),
+        };
+
+        if !effects.status().is_ok() {
+            bail!("Transaction {digest} failed: {:?}", effects.status());
+        }
+
+        info!(
+            "Merged {} retired gas coins for {sender} into {}",
+            retired.len(),
+            coin.object_ref.0,
+        );
+
+        Ok(())
+    }
+}
+
+/// The coin that retired gas coins are merged into.
+enum Primary {
+    /// A coin checked out of the pool.
+    Pool(CheckedOutCoin),
+    /// One of the retired coins.
+    Retired(PooledCoin),
+}
+
+impl Primary {
+    fn coin(&self) -> PooledCoin {
+        match self {
+            Primary::Pool(checked_out) => checked_out.coin,
+            Primary::Retired(coin) => *coin,
+        }
+    }
+
+    fn restore(self, pool: &GasPool, min_balance: u64) {
+        match self {
+            Primary::Pool(checked_out) => {
+                let coin = checked_out.coin;
+                pool.release(checked_out, coin, min_balance);
+            }
+            Primary::Retired(coin) => {
+                pool.restore(vec![coin], min_balance);
+            }
+        }
+    }
+}
+
+impl GasPool {
+    fn new() -> Self {
+        Self {
+            coins: Mutex::new(PoolCoins::default()),
+            permits: Arc::new(Semaphore::new(0)),
+        }
+    }
+
+    /// Wait for a coin to become available, and check it out of the pool. Fails if the pool has
+    /// been closed.
+    async fn acquire(&self) -> anyhow::Result<CheckedOutCoin> {
+        let Ok(permit) = self.permits.clone().acquire_owned().await else {
+            bail!("{}", self.closed_reason());
+        };
+
+        Ok(self.check_out(permit))
+    }
+
+    /// Check a coin out of the pool if one is available, without waiting.
+    fn try_acquire(&self) -> Option<CheckedOutCoin> {
+        let permit = self.permits.clone().try_acquire_owned().ok()?;
+        Some(self.check_out(permit))
+    }
+
+    fn check_out(&self, permit: OwnedSemaphorePermit) -> CheckedOutCoin {
+        let mut coins = self.coins.lock().unwrap();
+        let coin = coins
+            .available
+            .pop()
+            .expect("A permit is only held while a coin is available");
+
+        coins.checked_out += 1;
+        CheckedOutCoin { coin, permit }
+    }
+
+    /// Return a checked out coin to the pool at its new version. Coins whose balance has fallen
+    /// below `min_balance` are retired instead. Returns whether the coin was retired.
+    fn release(&self, checked_out: CheckedOutCoin, coin: PooledCoin, min_balance: u64) -> bool {
+        let CheckedOutCoin { permit, .. } = checked_out;
+        let mut coins = self.coins.lock().unwrap();
+        coins.checked_out -= 1;
+
+        if coin.balance < min_balance {
+            coins.retired.push(coin);
+            permit.forget();
+            return true;
+        }
+
+        coins.available.push(coin);
+        drop(permit);
+        false
+    }
+
+    /// Remove a checked out coin from circulation, because it has been deleted.
+    fn remove(&self, checked_out: CheckedOutCoin) {
+        warn!(
+            "Removing deleted gas coin {} from the pool",
+            checked_out.coin.object_ref.0,
+        );
+
+        self.coins.lock().unwrap().checked_out -= 1;
+        checked_out.permit.forget();
+    }
+
+    /// Wake up and fail any transactions waiting for a coin from this pool, with `reason`.
+    fn close(&self, reason: String) {
+        self.coins.lock().unwrap().closed.get_or_insert(reason);
+        self.permits.close();
+    }
+
+    fn closed_reason(&self) -> String {
+        let coins = self.coins.lock().unwrap();
+        coins
+            .closed
+            .clone()
+            .unwrap_or_else(|| "Gas pool has been closed".to_owned())
+    }
+
+    /// Add the coins split off for the pool's sender to the pool.
+    fn fill(&self, coins: Vec<PooledCoin>, min_balance: u64) {
+        self.restore(coins, min_balance);
+        self.coins.lock().unwrap().filled = true;
+    }
+
+    /// Add coins that were not checked out (e.g. coins that were retired) back to the pool,
+    /// retiring those whose balance is below `min_balance`. Returns whether any coin was retired.
+    fn restore(&self, coins: Vec<PooledCoin>, min_balance: u64) -> bool {
+        let (available, retired): (Vec<_>, Vec<_>) =
+            coins.into_iter().partition(|c| c.balance >= min_balance);
+
+        let any_retired = !retired.is_empty();
+        let added = available.len();
+
+        let mut coins = self.coins.lock().unwrap();
+        coins.retired.extend(retired);
+        coins.available.extend(available);
+        self.permits.add_permits(added);
+
+        any_retired
+    }
+
+    fn take_retired(&self, limit: usize) -> Vec<PooledCoin> {
+        let retired = &mut self.coins.lock().unwrap().retired;
+        let keep = retired.len().saturating_sub(limit);
+        retired.split_off(keep)
+    }
+
+    /// Whether the pool's sender can still pay for transactions with a budget of `gas_budget`,
+    /// once all its coins are retired.
+    fn solvency(&self, gas_budget: u64) -> Solvency {
+        let coins = self.coins.lock().unwrap();
+        if !coins.filled || !coins.available.is_empty() || coins.checked_out > 0 {
+            return Solvency::Active;
+        }
+
+        let balance = coins.retired.iter().map(|c| c.balance).sum();
+        if coins.retired.len() > 1 && balance >= gas_budget {
+            Solvency::NeedsMerge
+        } else {
+            Solvency::Insufficient { balance }
+        }
+    }
+}
+
+impl ObjectLocks {
+    /// Wait until no other transaction holds any of the objects in `ids`, and lock them. Locks are
+    /// taken in a consistent order to avoid deadlocks between transactions.
+    async fn acquire(&self, mut ids: Vec<ObjectID>) -> ObjectLockGuard<'_> {
+        ids.sort();
+        ids.dedup();
+
+        let mutexes: Vec<_> = {
+            let mut locks = self.locks.lock().unwrap();
+            ids.iter()
+                .map(|id| locks.entry(*id).or_default().clone())
+                .collect()
+        };
+
+        let mut guards = Vec::with_capacity(mutexes.len());
+        for mutex in mutexes {
+            guards.push(mutex.lock_owned().await);
+        }
+
+        ObjectLockGuard {
+            locks: self,
+            ids,
+            guards,
+        }
+    }
+}
+
+impl Drop for ObjectLockGuard<'_> {
+    fn drop(&mut self) {
+        self.guards.clear();
+
+        // Clean up locks that no other transaction is waiting on.
+        let mut locks = self.locks.locks.lock().unwrap();
+        for id in &self.ids {
+            if locks.get(id).is_some_and(|l| Arc::strong_count(l) == 1) {
+                locks.remove(id);
+            }
+        }
+    }
+}
+
+impl ExecutorConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.gas_coins_per_sender == 0 {
+            bail!("Gas coins per sender must be positive");
+        }
+
+        if self.gas_budget == 0 {
+            bail!("Gas budget must be positive");
+        }
+
+        if self.max_in_flight == 0 {
+            bail!("Maximum number of transactions in flight must be positive");
+        }
+
+        if self.min_gas_coin_balance < self.gas_budget {
+            bail!(
+                "Minimum gas coin balance ({}) must cover the gas budget ({})",
+                self.min_gas_coin_balance,
+                self.gas_budget,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        Self {
+            gas_coins_per_sender: 16,
+            gas_coin_balance: 1_000_000_000,
+            min_gas_coin_balance: 50_000_000,
+            gas_budget: 50_000_000,
+            max_in_flight: 64,
+        }
+    }
+}
+
+/// IDs of the owned objects (and objects being received) in `pt`'s inputs.
+fn owned_inputs(pt: &ProgrammableTransaction) -> Vec<ObjectID> {
+    pt.inputs
+        .iter()
+        .filter_map(|input| match input {
+            CallArg::Object(ObjectArg::ImmOrOwnedObject((id, _, _)))
+            | CallArg::Object(ObjectArg::Receiving((id, _, _))) => Some(*id),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Replace references to owned objects in `pt`'s inputs with newer versions from `versions`.
+fn refresh_inputs(
+    mut pt: ProgrammableTransaction,
+    versions: &HashMap<ObjectID, ObjectRef>,
+) -> ProgrammableTransaction {
+    for input in &mut pt.inputs {
+        let (CallArg::Object(ObjectArg::ImmOrOwnedObject(object_ref))
+        | CallArg::Object(ObjectArg::Receiving(object_ref))) = input
+        else {
+            continue;
+        };
+
+        if let Some(latest) = versions.get(&object_ref.0) {
+            if latest.1 > object_ref.1 {
+                *object_ref = *latest;
+            }
+        }
+    }
+
+    pt
+}
+
+fn expect_success(
+    response: &SuiTransactionBlockResponse,
+) -> anyhow::Result<&SuiTransactionBlockEffects> {
+    let effects = response
+        .effects
+        .as_ref()
+        .ok_or_else(|| anyhow!("No effects for transaction {}", response.digest))?;
+
+    if !effects.status().is_ok() {
+        bail!(
+            "Transaction {} failed: {:?}",
+            response.digest,
+            effects.status()
+        );
+    }
+
+    Ok(effects)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+
+    use sui_types::base_types::{random_object_ref, SequenceNumber};
+    use sui_types::digests::ObjectDigest;
+
+    use super::*;
+
+    const MIN_BALANCE: u64 = 100;
+    const GAS_BUDGET: u64 = 50;
+
+    fn coin(balance: u64) -> PooledCoin {
+        PooledCoin {
+            object_ref: random_object_ref(),
+            balance,
+        }
+    }
+
+    fn filled_pool(balances: &[u64]) -> GasPool {
+        let pool = GasPool::new();
+        pool.fill(balances.iter().map(|b| coin(*b)).collect(), MIN_BALANCE);
+        pool
+    }
+
+    /// Whether `f` is still waiting after giving it a moment to finish.
+    async fn is_pending<F: Future + Unpin>(f: &mut F) -> bool {
+        tokio::time::timeout(Duration::from_millis(100), f)
+            .await
+            .is_err()
+    }
+
+    #[tokio::test]
+    async fn test_acquire_and_release() {
+        let pool = filled_pool(&[1000, 2000]);
+
+        let a = pool.acquire().await.unwrap();
+        let b = pool.try_acquire().unwrap();
+        assert!(pool.try_acquire().is_none());
+
+        let mut waiting = Box::pin(pool.acquire());
+        assert!(is_pending(&mut waiting).await);
+
+        // Releasing a coin hands it out again at its new version.
+        let mut updated = a.coin;
+        updated.object_ref.1 = SequenceNumber::from_u64(updated.object_ref.1.value() + 1);
+        updated.balance -= 10;
+        assert!(!pool.release(a, updated, MIN_BALANCE));
+
+        let c = waiting.await.unwrap();
+        assert_eq!(c.coin.object_ref, updated.object_ref);
+        assert_eq!(c.coin.balance, updated.balance);
+
+        let coin = b.coin;
+        pool.release(b, coin, MIN_BALANCE);
+        assert!(pool.try_acquire().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_release_retires_low_balance_coins() {
+        let pool = filled_pool(&[1000]);
+
+        let a = pool.acquire().await.unwrap();
+        let mut spent = a.coin;
+        spent.balance = MIN_BALANCE - 1;
+        assert!(pool.release(a, spent, MIN_BALANCE));
+
+        // The retired coin is no longer handed out.
+        assert!(pool.try_acquire().is_none());
+
+        let retired = pool.take_retired(10);
+        assert_eq!(retired.len(), 1);
+        assert_eq!(retired[0].object_ref, spent.object_ref);
+        assert!(pool.take_retired(10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restore() {
+        let pool = filled_pool(&[]);
+        assert!(pool.try_acquire().is_none());
+
+        // Only coins above the minimum balance become available.
+        assert!(pool.restore(vec![coin(1000), coin(10), coin(20)], MIN_BALANCE));
+        let a = pool.try_acquire().unwrap();
+        assert_eq!(a.coin.balance, 1000);
+        assert!(pool.try_acquire().is_none());
+        assert_eq!(pool.take_retired(10).len(), 2);
+
+        assert!(!pool.restore(vec![coin(2000)], MIN_BALANCE));
+        assert_eq!(pool.try_acquire().unwrap().coin.balance, 2000);
+    }
+
+    #[test]
+    fn test_take_retired_limit() {
+        let pool = filled_pool(&[10, 20, 30]);
+        assert_eq!(pool.take_retired(2).len(), 2);
+        assert_eq!(pool.take_retired(2).len(), 1);
+        assert!(pool.take_retired(2).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_close_fails_waiting_transactions() {
+        let pool = filled_pool(&[1000]);
+        let _a = pool.acquire().await.unwrap();
+
+        let mut waiting = Box::pin(pool.acquire());
+        assert!(is_pending(&mut waiting).await);
+
+        pool.close("Pool closed for testing".to_owned());
+        let err = waiting.await.err().unwrap();
+        assert_eq!(err.to_string(), "Pool closed for testing");
+
+        // Only the first reason is kept.
+        pool.close("Closed again".to_owned());
+        let err = pool.acquire().await.err().unwrap();
+        assert_eq!(err.to_string(), "Pool closed for testing");
+    }
+
+    #[test]
+    fn test_solvency() {
+        // A pool that is still being filled has no coins yet, but is not out of gas.
+        assert_eq!(GasPool::new().solvency(GAS_BUDGET), Solvency::Active);
+
+        let pool = filled_pool(&[1000]);
+        assert_eq!(pool.solvency(GAS_BUDGET), Solvency::Active);
+
+        let a = pool.try_acquire().unwrap();
+        assert_eq!(pool.solvency(GAS_BUDGET), Solvency::Active);
+
+        // Retiring the last coin in circulation leaves nothing to merge it with.
+        let mut spent = a.coin;
+        spent.balance = 60;
+        pool.release(a, spent, MIN_BALANCE);
+        assert_eq!(
+            pool.solvency(GAS_BUDGET),
+            Solvency::Insufficient { balance: 60 },
+        );
+
+        // Two retired coins can pay for their merge.
+        pool.restore(vec![coin(30)], MIN_BALANCE);
+        assert_eq!(pool.solvency(GAS_BUDGET), Solvency::NeedsMerge);
+
+        // Unless their total balance is below the gas budget.
+        let pool = filled_pool(&[20, 25]);
+        assert_eq!(
+            pool.solvency(GAS_BUDGET),
+            Solvency::Insufficient { balance: 45 },
+        );
+
+        // Coins deleted while checked out leave nothing behind.
+        let pool = filled_pool(&[1000]);
+        pool.remove(pool.try_acquire().unwrap());
+        assert_eq!(
+            pool.solvency(GAS_BUDGET),
+            Solvency::Insufficient { balance: 0 },
+        );
+    }
+
+    fn pool_with_retired(balances: &[u64]) -> GasPool {
+        let pool = pool(balances);
+        assert!(pool.try_acquire().is_none());
+        pool
+    }
+
+    /// Once every coin is retired and they can't pay for a merge, the transactions waiting on the
+    /// pool fail, rather than waiting forever.
+    #[tokio::test]
+    async fn test_exhausted_pool_fails_waiting_transactions() {
+        let pool = filled_pool(&[1000]);
+        let a = pool.acquire().await.unwrap();
+
+        let mut waiting = Box::pin(pool.acquire());
+        assert!(is_pending(&mut waiting).await);
+
+        let mut spent = a.coin;
+        spent.balance = GAS_BUDGET - 1;
+        pool.release(a, spent, MIN_BALANCE);
+
+        let Solvency::Insufficient { balance } = pool.solvency(GAS_BUDGET) else {
+            panic!("Expected the pool to be out of gas");
+        };
+
+        pool.close(format!("Insufficient gas: {balance} MIST left"));
+        let err = waiting.await.err().unwrap();
+        assert_eq!(err.to_string(), "Insufficient gas: 49 MIST left");
+    }
+
+    #[tokio::test]
+    async fn test_object_locks_are_ordered_and_cleaned_up() {
+        let locks = ObjectLocks::default();
+        let (a, b) = (ObjectID::random(), ObjectID::random());
+        let (lo, hi) = (a.min(b), a.max(b));
+
+        let guard = locks.acquire(vec![hi, lo, hi]).await;
+        assert_eq!(guard.ids, vec![lo, hi]);
+        assert_eq!(locks.locks.lock().unwrap().len(), 2);
+
+        drop(guard);
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_object_locks_queue_transactions() {
+        let locks = ObjectLocks::default();
+        let (a, b) = (ObjectID::random(), ObjectID::random());
+
+        let first = locks.acquire(vec![a]).await;
+
+        // Transactions that share an object wait, while those that don't run straight away.
+        let mut second = Box::pin(locks.acquire(vec![a, b]));
+        assert!(is_pending(&mut second).await);
+        let other = locks.acquire(vec![ObjectID::random()]).await;
+        drop(other);
+
+        // The lock is kept while another transaction is waiting for it.
+        drop(first);
+        assert!(locks.locks.lock().unwrap().contains_key(&a));
+
+        let second = second.await;
+        assert_eq!(second.ids.len(), 2);
+        drop(second);
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_object_locks_do_not_deadlock() {
+        let locks = ObjectLocks::default();
+        let (a, b) = (ObjectID::random(), ObjectID::random());
+
+        let run = |ids| {
+            let locks = &locks;
+            async move {
+                let _guard = locks.acquire(ids).await;
+                tokio::task::yield_now().await;
+            }
+        };
+
+        // Both transactions lock the objects in the same order, whatever order they list them in.
+        let both = futures::future::join_all((0..10).map(|i| {
+            if i % 2 == 0 {
+                run(vec![a, b])
+            } else {
+                run(vec![b, a])
+            }
+        }));
+
+        tokio::time::timeout(Duration::from_secs(5), both)
+            .await
+            .expect("Transactions deadlocked");
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_owned_inputs_and_refresh() {
+        let owned = random_object_ref();
+        let receiving = random_object_ref();
+        let unseen = random_object_ref();
+        let shared = ObjectID::random();
+
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.obj(ObjectArg::ImmOrOwnedObject(owned)).unwrap();
+        builder.obj(ObjectArg::Receiving(receiving)).unwrap();
+        builder.obj(ObjectArg::ImmOrOwnedObject(unseen)).unwrap();
+        builder
+            .obj(ObjectArg::SharedObject {
+                id: shared,
+                initial_shared_version: SequenceNumber::from_u64(1),
+                mutable: true,
+            })
+            .unwrap();
+        builder.pure(42u64).unwrap();
+        let pt = builder.finish();
+
+        assert_eq!(owned_inputs(&pt), vec![owned.0, receiving.0, unseen.0]);
+
+        let newer = (
+            owned.0,
+            SequenceNumber::from_u64(owned.1.value() + 2),
+            ObjectDigest::random(),
+        );
+        let versions = HashMap::from([(owned.0, newer), (receiving.0, receiving)]);
+
+        // Only references with newer versions are replaced.
+        let pt = refresh_inputs(pt, &versions);
+        assert_eq!(
+            pt.inputs[0],
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(newer))
+        );
+        assert_eq!(
+            pt.inputs[1],
+            CallArg::Object(ObjectArg::Receiving(receiving))
+        );
+        assert_eq!(
+            pt.inputs[2],
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(unseen))
+        );
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(ExecutorConfig::default().validate().is_ok());
+
+        for config in [
+            ExecutorConfig {
+                gas_coins_per_sender: 0,
+                ..Default::default()
+            },
+            ExecutorConfig {
+                max_in_flight: 0,
+                ..Default::default()
+            },
+            ExecutorConfig {
+                gas_budget: 0,
+                min_gas_coin_balance: 0,
+                ..Default::default()
+            },
+            ExecutorConfig {
+                min_gas_coin_balance: 1,
+                gas_budget: 2,
+                ..Default::default()
+            },
+        ] {
+            assert!(config.validate().is_err(), "{config:?} should be rejected");
+        }
+    }
+}
@@ -0,0 +1,493 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A client that spreads requests over several fullnode endpoints.
+//!
+//! [FailoverClient] keeps a [SuiClient] for each endpoint it is given, and a background task that
+//! periodically checks every endpoint's health, latency and latest checkpoint. Reads go to the
+//! healthy endpoint with the lowest latency, and are retried on the next endpoint if they fail
+//! with a transport or transient error.
+//!
+//! Reads are also consistent with writes that went through the same client: after a transaction
+//! is executed, the client waits for it to be included in a checkpoint, and from then on only
+//! reads from endpoints that have caught up to that checkpoint, so that a read never observes
+//! state older than a previous write, even when it is served by a different endpoint.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use jsonrpsee::types::error::{CallError, INTERNAL_ERROR_CODE, SERVER_IS_BUSY_CODE};
+use sui_json_rpc_types::{SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions};
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use sui_types::quorum_driver_types::ExecuteTransactionRequestType;
+use sui_types::transaction::Transaction;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::error::{Error, JsonRpcError, SuiRpcResult};
+use crate::{SuiClient, SuiClientBuilder};
+
+/// How often to poll for progress when waiting for a transaction to be checkpointed, or for an
+/// endpoint to catch up to a checkpoint.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Weight given to the latest latency sample when updating an endpoint's average latency.
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// Builder for a [FailoverClient].
+///
+/// By default, endpoints are health-checked every 5 seconds, and the client waits up to 10
+/// seconds for a transaction to be checkpointed after it is executed, and for an endpoint to
+/// catch up to the latest checkpoint written through the client.
+pub struct FailoverClientBuilder {
+    client_builder: SuiClientBuilder,
+    health_check_interval: Duration,
+    checkpoint_timeout: Duration,
+}
+
+/// A client that routes requests to the fastest healthy endpoint out of several, retries reads
+/// on other endpoints, and keeps reads consistent with earlier writes. See the module
+/// documentation for details.
+pub struct FailoverClient {
+    inner: Arc<Inner>,
+    health_check: JoinHandle<()>,
+}
+
+/// The most recent information about an endpoint, gathered from health checks and requests.
+#[derive(Clone, Debug)]
+pub struct EndpointHealth {
+    pub url: String,
+    /// Whether the last request to this endpoint succeeded.
+    pub healthy: bool,
+    /// Moving average of the endpoint's response time.
+    pub latency: Option<Duration>,
+    /// The latest checkpoint the endpoint is known to have.
+    pub checkpoint: Option<CheckpointSequenceNumber>,
+}
+
+struct Inner {
+    endpoints: Vec<Endpoint>,
+    client_builder: SuiClientBuilder,
+    checkpoint_timeout: Duration,
+    /// Reads are only served by endpoints that have reached this checkpoint.
+    min_checkpoint: AtomicU64,
+}
+
+struct Endpoint {
+    /// `None` until a connection to the endpoint has been established.
+    client: tokio::sync::RwLock<Option<SuiClient>>,
+    health: Mutex<EndpointHealth>,
+}
+
+impl FailoverClientBuilder {
+    /// Set the builder used to connect to each endpoint.
+    pub fn client_builder(mut self, client_builder: SuiClientBuilder) -> Self {
+        self.client_builder = client_builder;
+        self
+    }
+
+    /// Set how often endpoints are health-checked.
+    pub fn health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = interval;
+        self
+    }
+
+    /// Set how long to wait for a transaction to be checkpointed after it is executed, and for
+    /// an endpoint to catch up to the checkpoint that reads are pinned to.
+    pub fn checkpoint_timeout(mut self, timeout: Duration) -> Self {
+        self.checkpoint_timeout = timeout;
+        self
+    }
+
+    /// Returns a [FailoverClient] for the endpoints at `urls`. Fails if none of the endpoints can
+    /// be reached. Endpoints that cannot be reached initially are retried by the health check.
+    pub async fn build(
+        self,
+        urls: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> SuiRpcResult<FailoverClient> {
+        let endpoints: Vec<_> = urls
+            .into_iter()
+            .map(|url| Endpoint {
+                client: tokio::sync::RwLock::new(None),
+                health: Mutex::new(EndpointHealth {
+                    url: url.as_ref().to_string(),
+                    healthy: false,
+                    latency: None,
+                    checkpoint: None,
+                }),
+            })
+            .collect();
+
+        let inner = Arc::new(Inner {
+            endpoints,
+            client_builder: self.client_builder,
+            checkpoint_timeout: self.checkpoint_timeout,
+            min_checkpoint: AtomicU64::new(0),
+        });
+
+        inner.check_health().await;
+        if !inner.endpoints.iter().any(|e| e.health().healthy) {
+            return Err(Error::NoEndpointAvailable);
+        }
+
+        let health_check =
+            tokio::spawn(inner.clone().health_check_loop(self.health_check_interval));
+        Ok(FailoverClient {
+            inner,
+            health_check,
+        })
+    }
+}
+
+impl FailoverClient {
+    /// Run the read `f` against the best available endpoint that has reached the client's
+    /// minimum checkpoint. If it fails with a transport or transient error, it is retried on the
+    /// next best endpoint. `f` may be called multiple times, so it should only perform reads.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use sui_sdk::failover::FailoverClientBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let sui = FailoverClientBuilder::default()
+    ///         .build(["http://127.0.0.1:9000", "http://127.0.0.1:9001"])
+    ///         .await?;
+    ///
+    ///     let total = sui
+    ///         .read(|client| async move { client.read_api().get_total_transaction_blocks().await })
+    ///         .await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn read<T, F, Fut>(&self, f: F) -> SuiRpcResult<T>
+    where
+        F: Fn(SuiClient) -> Fut,
+        Fut: Future<Output = SuiRpcResult<T>>,
+    {
+        let min_checkpoint = self.min_checkpoint();
+        let deadline = Instant::now() + self.inner.checkpoint_timeout;
+
+        loop {
+            let mut last_error = None;
+            let mut lagging = false;
+
+            for endpoint in self.inner.candidates() {
+                let Some(client) = endpoint.client().await else {
+                    continue;
+                };
+
+                if !endpoint.has_reached(&client, min_checkpoint).await {
+                    lagging = true;
+                    continue;
+                }
+
+                let start = Instant::now();
+                match f(client).await {
+                    Ok(value) => {
+                        endpoint.record_success(start.elapsed());
+                        return Ok(value);
+                    }
+
+                    Err(e) if is_retryable(&e) => {
+                        warn!(
+                            "Read from {} failed, trying next endpoint: {e}",
+                            endpoint.url()
+                        );
+                        endpoint.record_failure();
+                        last_error = Some(e);
+                    }
+
+                    Err(e) => return Err(e),
+                }
+            }
+
+            // Only wait if there are endpoints that could serve the read once they catch up.
+            if !lagging || Instant::now() >= deadline {
+                return Err(match last_error {
+                    Some(e) => e,
+                    None if lagging => Error::CheckpointNotReached(min_checkpoint),
+                    None => Error::NoEndpointAvailable,
+                });
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Execute a transaction on the best available endpoint. Unlike reads, transactions are not
+    /// retried on other endpoints.
+    ///
+    /// Once the transaction has been executed, the client waits for it to be checkpointed, and
+    /// pins subsequent reads to endpoints that have reached that checkpoint.
+    pub async fn execute_transaction_block(
+        &self,
+        tx: Transaction,
+        options: SuiTransactionBlockResponseOptions,
+        request_type: Option<ExecuteTransactionRequestType>,
+    ) -> SuiRpcResult<SuiTransactionBlockResponse> {
+        let mut candidates = self.inner.candidates().into_iter();
+        let (endpoint, client) = loop {
+            let Some(endpoint) = candidates.next() else {
+                return Err(Error::NoEndpointAvailable);
+            };
+
+            if let Some(client) = endpoint.client().await {
+                break (endpoint, client);
+            }
+        };
+
+        let start = Instant::now();
+        let response = match client
+            .quorum_driver_api()
+            .execute_transaction_block(tx, options, request_type)
+            .await
+        {
+            Ok(response) => {
+                endpoint.record_success(start.elapsed());
+                response
+            }
+            Err(e) => {
+                if is_retryable(&e) {
+                    endpoint.record_failure();
+                }
+                return Err(e);
+            }
+        };
+
+        match self.wait_for_checkpoint(&client, &response).await {
+            Some(checkpoint) => self.observe_checkpoint(checkpoint),
+            None => warn!(
+                "Transaction {} was not checkpointed within {:?}, subsequent reads may not \
+                 observe it",
+                response.digest, self.inner.checkpoint_timeout,
+            ),
+        }
+
+        Ok(response)
+    }
+
+    /// Prevent subsequent reads from being served by endpoints that have not reached
+    /// `checkpoint`. The minimum checkpoint never goes backwards.
+    pub fn observe_checkpoint(&self, checkpoint: CheckpointSequenceNumber) {
+        self.inner
+            .min_checkpoint
+            .fetch_max(checkpoint, Ordering::Relaxed);
+    }
+
+    /// The checkpoint that endpoints must have reached to serve reads.
+    pub fn min_checkpoint(&self) -> CheckpointSequenceNumber {
+        self.inner.min_checkpoint.load(Ordering::Relaxed)
+    }
+
+    /// The latest health information for every endpoint, in the order they were supplied.
+    pub fn endpoint_health(&self) -> Vec<EndpointHealth> {
+        self.inner.endpoints.iter().map(Endpoint::health).collect()
+    }
+
+    /// The checkpoint that `response`'s transaction was included in, waiting for it to be
+    /// checkpointed if necessary.
+    async fn wait_for_checkpoint(
+        &self,
+        client: &SuiClient,
+        response: &SuiTransactionBlockResponse,
+    ) -> Option<CheckpointSequenceNumber> {
+        if let Some(checkpoint) = response.checkpoint {
+            return Some(checkpoint);
+        }
+
+        let deadline = Instant::now() + self.inner.checkpoint_timeout;
+        while Instant::now() < deadline {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            match client
+                .read_api()
+                .get_transaction_with_options(
+                    response.digest,
+                    SuiTransactionBlockResponseOptions::new(),
+                )
+                .await
+            {
+                Ok(SuiTransactionBlockResponse {
+                    checkpoint: Some(checkpoint),
+                    ..
+                }) => return Some(checkpoint),
+                Ok(_) => continue,
+                Err(e) => debug!("Waiting for {} to be checkpointed: {e}", response.digest),
+            }
+        }
+
+        None
+    }
+}
+
+impl Drop for FailoverClient {
+    fn drop(&mut self) {
+        self.health_check.abort();
+    }
+}
+
+impl Inner {
+    async fn health_check_loop(self: Arc<Self>, interval: Duration) {
+        // The first check already happened when the client was built.
+        let start = tokio::time::Instant::now() + interval;
+        let mut interval = tokio::time::interval_at(start, interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.check_health().await;
+        }
+    }
+
+    /// Connect to endpoints that are not connected yet, and check the latency and latest
+    /// checkpoint of every endpoint.
+    async fn check_health(&self) {
+        join_all(self.endpoints.iter().map(|endpoint| async move {
+            if endpoint.client().await.is_none() {
+                endpoint.connect(&self.client_builder).await;
+            }
+
+            if let Some(client) = endpoint.client().await {
+                endpoint.refresh_checkpoint(&client).await;
+            }
+        }))
+        .await;
+    }
+
+    /// Endpoints in the order they should be tried: healthy endpoints first, in order of
+    /// increasing latency.
+    fn candidates(&self) -> Vec<&Endpoint> {
+        let mut candidates: Vec<_> = self
+            .endpoints
+            .iter()
+            .map(|endpoint| (endpoint.health(), endpoint))
+            .collect();
+
+        candidates.sort_by_key(|(health, _)| {
+            (
+                !health.healthy,
+                health.latency.is_none(),
+                health.latency.unwrap_or_default(),
+            )
+        });
+
+        candidates
+            .into_iter()
+            .map(|(_, endpoint)| endpoint)
+            .collect()
+    }
+}
+
+impl Endpoint {
+    fn url(&self) -> String {
+        self.health.lock().unwrap().url.clone()
+    }
+
+    fn health(&self) -> EndpointHealth {
+        self.health.lock().unwrap().clone()
+    }
+
+    async fn client(&self) -> Option<SuiClient> {
+        self.client.read().await.clone()
+    }
+
+    async fn connect(&self, builder: &SuiClientBuilder) {
+        let url = self.url();
+        match builder.clone().build(&url).await {
+            Ok(client) => {
+                if let Err(e) = client.check_api_version() {
+                    warn!("{url}: {e}");
+                }
+                *self.client.write().await = Some(client);
+            }
+            Err(e) => {
+                debug!("Failed to connect to {url}: {e}");
+                self.record_failure();
+            }
+        }
+    }
+
+    /// Whether this endpoint has reached `checkpoint`, asking the endpoint for its latest
+    /// checkpoint if the last known one is behind.
+    async fn has_reached(&self, client: &SuiClient, checkpoint: CheckpointSequenceNumber) -> bool {
+        if checkpoint == 0 || self.health().checkpoint >= Some(checkpoint) {
+            return true;
+        }
+
+        self.refresh_checkpoint(client).await >= Some(checkpoint)
+    }
+
+    async fn refresh_checkpoint(&self, client: &SuiClient) -> Option<CheckpointSequenceNumber> {
+        let start = Instant::now();
+        match client
+            .read_api()
+            .get_latest_checkpoint_sequence_number()
+            .await
+        {
+            Ok(checkpoint) => {
+                self.record_success(start.elapsed());
+                let mut health = self.health.lock().unwrap();
+                health.checkpoint = health.checkpoint.max(Some(checkpoint));
+                health.checkpoint
+            }
+            Err(e) => {
+                debug!("Health check for {} failed: {e}", self.url());
+                self.record_failure();
+                None
+            }
+        }
+    }
+
+    fn record_success(&self, elapsed: Duration) {
+        let mut health = self.health.lock().unwrap();
+        health.healthy = true;
+        health.latency = Some(match health.latency {
+            None => elapsed,
+            Some(latency) => {
+                latency.mul_f64(1.0 - LATENCY_SMOOTHING) + elapsed.mul_f64(LATENCY_SMOOTHING)
+            }
+        });
+    }
+
+    fn record_failure(&self) {
+        self.health.lock().unwrap().healthy = false;
+    }
+}
+
+impl Default for FailoverClientBuilder {
+    fn default() -> Self {
+        Self {
+            client_builder: SuiClientBuilder::default(),
+            health_check_interval: Duration::from_secs(5),
+            checkpoint_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Whether a request that failed with `error` is worth retrying on another endpoint: the request
+/// could not reach the endpoint, or the endpoint reported a transient error.
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        // Errors returned by the endpoint itself, as opposed to failures to reach it.
+        Error::RpcError(jsonrpsee::core::Error::Call(CallError::Custom(e))) => {
+            is_transient(&JsonRpcError {
+                code: e.code(),
+                message: e.message().to_string(),
+                data: None,
+            })
+        }
+        Error::RpcError(jsonrpsee::core::Error::Call(_)) => false,
+        Error::RpcError(_) => true,
+        Error::JsonRpcError(e) => is_transient(e) || !e.is_call_error(),
+        _ => false,
+    }
+}
+
+/// Whether another endpoint may not run into `error`: a transient error, an overloaded endpoint,
+/// or an internal error.
+fn is_transient(error: &JsonRpcError) -> bool {
+    error.is_transient_error() || matches!(error.code, SERVER_IS_BUSY_CODE | INTERNAL_ERROR_CODE)
+}
@@ -102,6 +102,8 @@ use crate::error::{Error, SuiRpcResult};
 
 pub mod apis;
 pub mod error;
+pub mod executor;
+pub mod failover;
 pub mod json_rpc_error;
 pub mod sui_client_config;
 pub mod wallet_context;
@@ -136,6 +138,7 @@ pub const SUI_TESTNET_URL: &str = "https://fullnode.testnet.sui.io:443";
 ///     Ok(())
 /// }
 /// ```
+#[derive(Clone)]
 pub struct SuiClientBuilder {
     request_timeout: Duration,
     max_concurrent_requests: usize,
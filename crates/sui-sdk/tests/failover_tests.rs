@@ -0,0 +1,201 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use jsonrpsee::types::error::INVALID_PARAMS_CODE;
+use serde_json::{json, Value};
+use sui_sdk::error::Error;
+use sui_sdk::failover::{FailoverClient, FailoverClientBuilder};
+use sui_sdk::json_rpc_error::TRANSIENT_ERROR_CODE;
+
+/// A fullnode that only supports the requests the failover client needs to connect to it and
+/// check its health. `sui_getTotalTransactionBlocks` returns the node's `id`, so tests can tell
+/// which node served a read.
+struct MockNode {
+    id: u64,
+    checkpoint: AtomicU64,
+    delay_ms: AtomicU64,
+    failing: AtomicBool,
+    /// Code of the JSON-RPC error that reads fail with, if not 0.
+    read_error_code: AtomicI32,
+}
+
+impl MockNode {
+    async fn start(id: u64, checkpoint: u64) -> (Arc<Self>, String) {
+        let node = Arc::new(Self {
+            id,
+            checkpoint: AtomicU64::new(checkpoint),
+            delay_ms: AtomicU64::new(0),
+            failing: AtomicBool::new(false),
+            read_error_code: AtomicI32::new(0),
+        });
+
+        let app = Router::new()
+            .route("/", post(handle_request))
+            .with_state(node.clone());
+
+        let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await
+            .unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (node, url)
+    }
+}
+
+async fn handle_request(State(node): State<Arc<MockNode>>, Json(request): Json<Value>) -> Response {
+    tokio::time::sleep(Duration::from_millis(node.delay_ms.load(Ordering::Relaxed))).await;
+    if node.failing.load(Ordering::Relaxed) {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+
+    let result = match request["method"].as_str() {
+        Some("rpc.discover") => json!({
+            "info": { "version": env!("CARGO_PKG_VERSION") },
+            "methods": [],
+        }),
+        Some("sui_getLatestCheckpointSequenceNumber") => {
+            json!(node.checkpoint.load(Ordering::Relaxed).to_string())
+        }
+        Some("sui_getTotalTransactionBlocks") => {
+            let code = node.read_error_code.load(Ordering::Relaxed);
+            if code != 0 {
+                let error = json!({ "code": code, "message": "Read failed" });
+                return Json(json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }))
+                    .into_response();
+            }
+            json!(node.id.to_string())
+        }
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })).into_response()
+}
+
+fn builder() -> FailoverClientBuilder {
+    // Health checks are left to the initial check in `build`, so tests control when endpoint
+    // information changes.
+    FailoverClientBuilder::default().health_check_interval(Duration::from_secs(3600))
+}
+
+/// The ID of the node that served the read.
+async fn read_node_id(client: &FailoverClient) -> Result<u64, Error> {
+    client
+        .read(|c| async move { c.read_api().get_total_transaction_blocks().await })
+        .await
+}
+
+#[tokio::test]
+async fn test_build_fails_without_endpoints() {
+    // Nothing is listening on the discard port.
+    let result = builder().build(["http://127.0.0.1:9"]).await;
+    assert!(matches!(result, Err(Error::NoEndpointAvailable)));
+}
+
+#[tokio::test]
+async fn test_read_fails_over_to_healthy_endpoint() {
+    let (a, url_a) = MockNode::start(1, 10).await;
+    let (_b, url_b) = MockNode::start(2, 10).await;
+    let client = builder().build([url_a, url_b]).await.unwrap();
+
+    a.failing.store(true, Ordering::Relaxed);
+    for _ in 0..3 {
+        assert_eq!(read_node_id(&client).await.unwrap(), 2);
+    }
+}
+
+#[tokio::test]
+async fn test_read_fails_over_on_transient_error() {
+    let (a, url_a) = MockNode::start(1, 10).await;
+    let (b, url_b) = MockNode::start(2, 10).await;
+    // Reads go to `a` first.
+    b.delay_ms.store(200, Ordering::Relaxed);
+    let client = builder().build([url_a, url_b]).await.unwrap();
+
+    a.read_error_code
+        .store(TRANSIENT_ERROR_CODE, Ordering::Relaxed);
+    assert_eq!(read_node_id(&client).await.unwrap(), 2);
+    assert!(!client.endpoint_health()[0].healthy);
+}
+
+#[tokio::test]
+async fn test_read_does_not_fail_over_on_call_error() {
+    let (a, url_a) = MockNode::start(1, 10).await;
+    let (b, url_b) = MockNode::start(2, 10).await;
+    b.delay_ms.store(200, Ordering::Relaxed);
+    let client = builder().build([url_a, url_b]).await.unwrap();
+
+    // Another endpoint would reject the same invalid request.
+    a.read_error_code
+        .store(INVALID_PARAMS_CODE, Ordering::Relaxed);
+    assert!(matches!(
+        read_node_id(&client).await,
+        Err(Error::RpcError(jsonrpsee::core::Error::Call(_)))
+    ));
+    assert!(client.endpoint_health()[0].healthy);
+}
+
+#[tokio::test]
+async fn test_read_prefers_lowest_latency() {
+    let (a, url_a) = MockNode::start(1, 10).await;
+    let (_b, url_b) = MockNode::start(2, 10).await;
+    a.delay_ms.store(200, Ordering::Relaxed);
+
+    let client = builder().build([url_a, url_b]).await.unwrap();
+    assert_eq!(read_node_id(&client).await.unwrap(), 2);
+
+    let health = client.endpoint_health();
+    assert!(health[0].latency > health[1].latency);
+}
+
+#[tokio::test]
+async fn test_read_skips_endpoints_behind_min_checkpoint() {
+    let (_a, url_a) = MockNode::start(1, 5).await;
+    let (_b, url_b) = MockNode::start(2, 10).await;
+    let client = builder()
+        .checkpoint_timeout(Duration::from_millis(500))
+        .build([url_a, url_b])
+        .await
+        .unwrap();
+
+    client.observe_checkpoint(8);
+    for _ in 0..3 {
+        assert_eq!(read_node_id(&client).await.unwrap(), 2);
+    }
+
+    // The minimum checkpoint never goes backwards.
+    client.observe_checkpoint(3);
+    assert_eq!(client.min_checkpoint(), 8);
+
+    // No endpoint catches up in time.
+    client.observe_checkpoint(20);
+    assert!(matches!(
+        read_node_id(&client).await,
+        Err(Error::CheckpointNotReached(20))
+    ));
+}
+
+#[tokio::test]
+async fn test_read_waits_for_endpoint_to_catch_up() {
+    let (a, url_a) = MockNode::start(1, 5).await;
+    let client = builder().build([url_a]).await.unwrap();
+    client.observe_checkpoint(8);
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        a.checkpoint.store(8, Ordering::Relaxed);
+    });
+
+    assert_eq!(read_node_id(&client).await.unwrap(), 1);
+    assert_eq!(client.endpoint_health()[0].checkpoint, Some(8));
+}
@@ -0,0 +1,96 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::anyhow;
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// An alert raised by a monitoring job.
+#[derive(Serialize, Debug, Clone)]
+pub struct Alert {
+    /// Identifies what the alert is about, so that sinks can deduplicate repeated alerts.
+    pub key: String,
+    pub title: String,
+    pub details: String,
+}
+
+/// A destination alerts are sent to.
+#[async_trait::async_trait]
+pub trait AlertSink: Send + Sync + 'static {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()>;
+}
+
+/// Prints alerts to stdout as JSON lines, e.g. for local runs.
+pub struct StdoutAlertSink;
+
+#[async_trait::async_trait]
+impl AlertSink for StdoutAlertSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        println!("{}", serde_json::to_string(alert)?);
+        Ok(())
+    }
+}
+
+/// Posts alerts as JSON to a generic webhook.
+pub struct WebhookAlertSink {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookAlertSink {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertSink for WebhookAlertSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        let response = self.client.post(&self.url).json(alert).send().await?;
+        if response.status().is_success() {
+            info!("Sent alert with key: {} to webhook", alert.key);
+            Ok(())
+        } else {
+            let status = response.status();
+            let text = response.text().await?;
+            Err(anyhow!(
+                "Failed to send alert to webhook: {} {}",
+                status,
+                text
+            ))
+        }
+    }
+}
+
+/// Sends every alert to all of its sinks, failing if any of them fails.
+pub struct AlertSinks(pub Vec<Arc<dyn AlertSink>>);
+
+#[async_trait::async_trait]
+impl AlertSink for AlertSinks {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        let mut failures = 0;
+        for sink in &self.0 {
+            // One failing sink should not prevent the alert from reaching the others.
+            if let Err(err) = sink.send(alert).await {
+                error!(
+                    "Failed to send alert with key: {} with err: {}",
+                    alert.key, err
+                );
+                failures += 1;
+            }
+        }
+        if failures > 0 {
+            Err(anyhow!(
+                "Failed to send alert to {} of {} sinks",
+                failures,
+                self.0.len()
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
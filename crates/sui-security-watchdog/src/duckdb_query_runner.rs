@@ -0,0 +1,160 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::query_runner::{parse_record_batches, uppercase_column_names, QueryRunner, Row};
+use crate::SecurityWatchdogConfig;
+use anyhow::anyhow;
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Decimal128Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, RecordBatch};
+use duckdb::Connection;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Runs queries with an embedded DuckDB, either against a database file, or in memory, in which
+/// case queries typically read files directly, e.g. the Parquet files written by
+/// `sui-analytics-indexer` with `read_parquet`.
+pub struct DuckDbQueryRunner {
+    path: Option<PathBuf>,
+}
+
+impl DuckDbQueryRunner {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path }
+    }
+
+    pub fn from_config(config: &SecurityWatchdogConfig) -> Self {
+        Self::new(config.duckdb_path.clone())
+    }
+
+    async fn query(&self, query: &str) -> anyhow::Result<Vec<RecordBatch>> {
+        let path = self.path.clone();
+        let query = query.to_string();
+        // DuckDB is synchronous, and queries over large Parquet files can take a while.
+        tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<RecordBatch>> {
+            let connection = match path {
+                Some(path) => Connection::open(path)?,
+                None => Connection::open_in_memory()?,
+            };
+            let mut statement = connection.prepare(&query)?;
+            let batches = statement.query_arrow([])?.collect();
+            Ok(batches)
+        })
+        .await?
+    }
+}
+
+macro_rules! try_as_f64 {
+    ($column:expr, $type:ty) => {
+        if let Some(value) = $column.as_primitive_opt::<$type>() {
+            return Ok(value.value(0) as f64);
+        }
+    };
+}
+
+/// Parses the first column of the first row of `batches` as a floating point number.
+fn parse_single_entry(batches: Vec<RecordBatch>) -> anyhow::Result<f64> {
+    let column = batches
+        .first()
+        .ok_or_else(|| anyhow!("No results found in RecordBatch"))?
+        .columns()
+        .first()
+        .ok_or_else(|| anyhow!("No columns found in record"))?;
+    if column.is_empty() {
+        return Err(anyhow!("No rows found in record"));
+    }
+    if column.is_null(0) {
+        return Err(anyhow!("First column is NULL"));
+    }
+    // `SUM` of integers returns a `HUGEINT`, which is exported as a decimal.
+    if let Some(value) = column.as_primitive_opt::<Decimal128Type>() {
+        return Ok(value.value(0) as f64 / 10f64.powi(value.scale() as i32));
+    }
+    try_as_f64!(column, Float64Type);
+    try_as_f64!(column, Float32Type);
+    try_as_f64!(column, Int64Type);
+    try_as_f64!(column, Int32Type);
+    try_as_f64!(column, Int16Type);
+    try_as_f64!(column, Int8Type);
+    try_as_f64!(column, UInt64Type);
+    try_as_f64!(column, UInt32Type);
+    try_as_f64!(column, UInt16Type);
+    try_as_f64!(column, UInt8Type);
+    Err(anyhow!(
+        "Column of type {:?} is not a number",
+        column.data_type()
+    ))
+}
+
+#[async_trait::async_trait]
+impl QueryRunner for DuckDbQueryRunner {
+    async fn run_single_entry(&self, query: &str) -> anyhow::Result<f64> {
+        parse_single_entry(self.query(query).await?)
+    }
+
+    async fn run(&self, query: &str) -> anyhow::Result<Vec<Row>> {
+        info!("Running query: {}", query);
+        let rows = parse_record_batches(self.query(query).await?)?;
+        Ok(uppercase_column_names(rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{ArrayRef, Decimal128Array, Float64Array, Int64Array, StringArray};
+    use std::sync::Arc;
+
+    fn batch(column: impl Array + 'static) -> Vec<RecordBatch> {
+        vec![RecordBatch::try_from_iter([("value", Arc::new(column) as ArrayRef)]).unwrap()]
+    }
+
+    #[test]
+    fn test_parse_single_entry() {
+        let decimal = Decimal128Array::from(vec![12_345, 1])
+            .with_precision_and_scale(38, 2)
+            .unwrap();
+        assert_eq!(parse_single_entry(batch(decimal)).unwrap(), 123.45);
+        let hugeint = Decimal128Array::from(vec![i64::MAX as i128 * 4])
+            .with_precision_and_scale(38, 0)
+            .unwrap();
+        assert_eq!(
+            parse_single_entry(batch(hugeint)).unwrap(),
+            i64::MAX as f64 * 4.0
+        );
+        assert_eq!(
+            parse_single_entry(batch(Float64Array::from(vec![0.5]))).unwrap(),
+            0.5
+        );
+        assert_eq!(
+            parse_single_entry(batch(Int64Array::from(vec![-7, 3]))).unwrap(),
+            -7.0
+        );
+    }
+
+    #[test]
+    fn test_parse_single_entry_errors() {
+        assert!(parse_single_entry(vec![]).is_err());
+        assert!(parse_single_entry(batch(Int64Array::from(Vec::<i64>::new()))).is_err());
+        // A `SUM` over no rows is NULL, which must not be read as 0.
+        assert!(parse_single_entry(batch(Int64Array::from(vec![None, Some(1)]))).is_err());
+        assert!(parse_single_entry(batch(StringArray::from(vec!["1"]))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_single_entry() {
+        let runner = DuckDbQueryRunner::new(None);
+        let sum = runner
+            .run_single_entry("SELECT SUM(x) FROM (VALUES (1), (2), (39)) t(x)")
+            .await
+            .unwrap();
+        assert_eq!(sum, 42.0);
+        assert!(runner
+            .run_single_entry("SELECT SUM(x) FROM (VALUES (1)) t(x) WHERE x > 1")
+            .await
+            .is_err());
+    }
+}
@@ -1,13 +1,33 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use std::path::PathBuf;
 
+mod alert_sink;
+mod duckdb_query_runner;
 mod metrics;
 mod pagerduty;
+mod postgres_query_runner;
 mod query_runner;
 pub mod scheduler;
 
+/// The database monitoring queries run against.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryBackend {
+    Snowflake,
+    Postgres,
+    #[clap(name = "duckdb")]
+    DuckDb,
+}
+
+/// Where alerts raised by monitoring jobs are sent.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertSinkKind {
+    Pagerduty,
+    Webhook,
+    Stdout,
+}
+
 #[derive(Parser, Clone, Debug)]
 #[clap(
     name = "Sui Security Watchdog",
@@ -16,9 +36,32 @@ pub mod scheduler;
 )]
 pub struct SecurityWatchdogConfig {
     #[clap(long)]
-    pub pd_wallet_monitoring_service_id: String,
+    pub pd_wallet_monitoring_service_id: Option<String>,
     #[clap(long)]
     pub config: PathBuf,
+    /// The database to run monitoring queries against.
+    #[clap(long, value_enum, default_value = "snowflake", global = true)]
+    pub query_backend: QueryBackend,
+    /// Connection URL of the Postgres database, e.g. the database of `sui-indexer-alt`. The
+    /// password can be passed separately in the `PG_PASSWORD` env var. Add `?sslmode=require` to
+    /// refuse unencrypted connections.
+    #[clap(long, default_value = None, global = true)]
+    pub pg_url: Option<String>,
+    /// Path of the DuckDB database file. Queries run against an in-memory database if unset.
+    #[clap(long, default_value = None, global = true)]
+    pub duckdb_path: Option<PathBuf>,
+    /// Comma separated list of sinks to send alerts to.
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "pagerduty",
+        global = true
+    )]
+    pub alert_sinks: Vec<AlertSinkKind>,
+    /// URL the webhook alert sink posts alerts to.
+    #[clap(long, default_value = None, global = true)]
+    pub alert_webhook_url: Option<String>,
     #[clap(long, default_value = None, global = true)]
     pub sf_account_identifier: Option<String>,
     #[clap(long, default_value = None, global = true)]
@@ -7,7 +7,7 @@ use anyhow::Result;
 use clap::*;
 use std::env;
 use sui_security_watchdog::scheduler::SchedulerService;
-use sui_security_watchdog::SecurityWatchdogConfig;
+use sui_security_watchdog::{QueryBackend, SecurityWatchdogConfig};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -16,8 +16,14 @@ async fn main() -> Result<()> {
         .init();
     env_logger::init();
     let config = SecurityWatchdogConfig::parse();
-    let pd_api_key = env::var("PD_API_KEY").expect("PD_API_KEY env var must be set");
-    let sf_password = env::var("SF_PASSWORD").expect("SF_PASSWORD env var must be set");
+    let pd_api_key = env::var("PD_API_KEY").ok();
+    let db_password = match config.query_backend {
+        QueryBackend::Snowflake => {
+            Some(env::var("SF_PASSWORD").expect("SF_PASSWORD env var must be set"))
+        }
+        QueryBackend::Postgres => env::var("PG_PASSWORD").ok(),
+        QueryBackend::DuckDb => None,
+    };
 
     let registry_service = mysten_metrics::start_prometheus_server(
         format!(
@@ -36,7 +42,7 @@ async fn main() -> Result<()> {
             "N/A",
         ))
         .unwrap();
-    let service = SchedulerService::new(&config, &registry, pd_api_key, sf_password).await?;
+    let service = SchedulerService::new(&config, &registry, pd_api_key, db_password).await?;
     service.schedule().await?;
     service.start().await?;
     tokio::signal::ctrl_c().await?;
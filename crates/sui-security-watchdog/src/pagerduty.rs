@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::alert_sink::{Alert, AlertSink};
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -122,3 +123,40 @@ impl Pagerduty {
         }
     }
 }
+
+/// Creates a PagerDuty incident on `service_id` for every alert, deduplicated by the alert key.
+pub struct PagerdutyAlertSink {
+    pagerduty: Pagerduty,
+    service_id: String,
+}
+
+impl PagerdutyAlertSink {
+    pub fn new(pagerduty: Pagerduty, service_id: String) -> Self {
+        Self {
+            pagerduty,
+            service_id,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertSink for PagerdutyAlertSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        let incident = Incident {
+            title: alert.title.clone(),
+            service: Service {
+                id: self.service_id.clone(),
+                ..Default::default()
+            },
+            incident_key: alert.key.clone(),
+            body: Body {
+                details: alert.details.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        self.pagerduty
+            .create_incident("sadhan@mystenlabs.com", CreateIncident { incident })
+            .await
+    }
+}
@@ -0,0 +1,374 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::query_runner::{QueryRunner, Row};
+use crate::SecurityWatchdogConfig;
+use anyhow::anyhow;
+use bb8::{ManageConnection, Pool, PooledConnection};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use std::any::Any;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_postgres::types::{FromSql, Type};
+use tokio_postgres::Client;
+use tokio_postgres_rustls::MakeRustlsConnect;
+use tracing::{error, info};
+
+/// Maximum number of connections kept open to the database. Monitors run on a schedule, so only a
+/// handful of queries are ever in flight at once.
+const MAX_CONNECTIONS: u32 = 4;
+
+/// How long to wait for a connection from the pool before failing the query.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs queries against a Postgres database, such as the database of `sui-indexer-alt`.
+///
+/// Connections are pooled and reused across queries. TLS is negotiated according to the
+/// `sslmode` in the database URL (`prefer` by default, like `libpq`).
+pub struct PostgresQueryRunner {
+    pool: Pool<ConnectionManager>,
+}
+
+/// Opens connections to the database for the pool, over TLS if the server supports it.
+struct ConnectionManager {
+    config: tokio_postgres::Config,
+    tls: MakeRustlsConnect,
+}
+
+/// Accepts any server certificate, to match the behavior of `libpq`, which only encrypts the
+/// connection unless `sslmode` is `verify-ca` or `verify-full`. Signatures are still checked, so
+/// the server must hold the key for the certificate it presents.
+#[derive(Debug)]
+struct SkipServerCertCheck(Arc<CryptoProvider>);
+
+impl PostgresQueryRunner {
+    /// Creates a new `PostgresQueryRunner` connecting to the database at `url`, with `password`
+    /// overriding the password in the URL, if any. Connections are opened lazily, when queries
+    /// are run.
+    pub fn new(url: &str, password: Option<String>) -> anyhow::Result<Self> {
+        let mut config: tokio_postgres::Config = url.parse()?;
+        if let Some(password) = password {
+            config.password(password);
+        }
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let tls_config = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerCertCheck(provider)))
+            .with_no_client_auth();
+
+        let manager = ConnectionManager {
+            config,
+            tls: MakeRustlsConnect::new(tls_config),
+        };
+
+        let pool = Pool::builder()
+            .max_size(MAX_CONNECTIONS)
+            .connection_timeout(CONNECTION_TIMEOUT)
+            .build_unchecked(manager);
+
+        Ok(Self { pool })
+    }
+
+    pub fn from_config(
+        config: &SecurityWatchdogConfig,
+        pg_password: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let url = config
+            .pg_url
+            .as_ref()
+            .ok_or_else(|| anyhow!("--pg-url is required by the postgres query backend"))?;
+        Self::new(url, pg_password)
+    }
+
+    async fn connect(&self) -> anyhow::Result<PooledConnection<'_, ConnectionManager>> {
+        self.pool
+            .get()
+            .await
+            .map_err(|e| anyhow!("Failed to get a Postgres connection: {e}"))
+    }
+}
+
+#[async_trait::async_trait]
+impl ManageConnection for ConnectionManager {
+    type Connection = Client;
+    type Error = tokio_postgres::Error;
+
+    async fn connect(&self) -> Result<Client, tokio_postgres::Error> {
+        let (client, connection) = self.config.connect(self.tls.clone()).await?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                error!("Postgres connection failed with err: {}", err);
+            }
+        });
+        Ok(client)
+    }
+
+    async fn is_valid(&self, client: &mut Client) -> Result<(), tokio_postgres::Error> {
+        client.simple_query("").await.map(|_| ())
+    }
+
+    fn has_broken(&self, client: &mut Client) -> bool {
+        client.is_closed()
+    }
+}
+
+impl ServerCertVerifier for SkipServerCertCheck {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// A Postgres `NUMERIC`, which is what `SUM` returns for integer columns.
+#[derive(Debug, PartialEq)]
+enum Numeric {
+    Integer(i128),
+    Decimal(f64),
+}
+
+impl<'a> FromSql<'a> for Numeric {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        // The binary format is a header of 4 16-bit words: the number of digits, the weight of
+        // the first digit, the sign and the display scale, followed by the base 10000 digits.
+        let word = |i: usize| -> Result<i16, Box<dyn Error + Sync + Send>> {
+            raw.get(2 * i..2 * i + 2)
+                .map(|bytes| i16::from_be_bytes([bytes[0], bytes[1]]))
+                .ok_or_else(|| "Truncated NUMERIC".into())
+        };
+        let num_digits = word(0)? as usize;
+        let weight = word(1)? as i32;
+        let sign = word(2)? as u16;
+        let scale = word(3)?;
+        let negative = match sign {
+            0x0000 => false,
+            0x4000 => true,
+            _ => return Err("NUMERIC is not a finite number".into()),
+        };
+        let digits = (0..num_digits)
+            .map(|i| word(4 + i))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if scale == 0 {
+            let integer = digits
+                .iter()
+                .try_fold(0i128, |acc, digit| {
+                    acc.checked_mul(10_000)?.checked_add(*digit as i128)
+                })
+                .and_then(|value| {
+                    let trailing_zero_digits = weight + 1 - num_digits as i32;
+                    value.checked_mul(10_000i128.checked_pow(trailing_zero_digits.max(0) as u32)?)
+                });
+            if let Some(integer) = integer {
+                return Ok(Numeric::Integer(if negative { -integer } else { integer }));
+            }
+        }
+        let decimal: f64 = digits
+            .iter()
+            .enumerate()
+            .map(|(i, digit)| *digit as f64 * 10_000f64.powi(weight - i as i32))
+            .sum();
+        Ok(Numeric::Decimal(if negative { -decimal } else { decimal }))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::NUMERIC
+    }
+}
+
+/// Reads column `index` of `row` into a type the monitors understand, or `None` if the value is
+/// `NULL` or of an unsupported type. `BYTEA` columns, which `sui-indexer-alt` uses for addresses
+/// and object IDs, are read as hex strings.
+fn read_column(
+    row: &tokio_postgres::Row,
+    index: usize,
+) -> anyhow::Result<Option<Box<dyn Any + Send>>> {
+    fn boxed<T: Any + Send>(value: Option<T>) -> Option<Box<dyn Any + Send>> {
+        value.map(|value| Box::new(value) as Box<dyn Any + Send>)
+    }
+    let column = &row.columns()[index];
+    Ok(match *column.type_() {
+        Type::BOOL => boxed(row.try_get::<_, Option<bool>>(index)?),
+        Type::INT2 => boxed(row.try_get::<_, Option<i16>>(index)?),
+        Type::INT4 => boxed(row.try_get::<_, Option<i32>>(index)?),
+        Type::INT8 => boxed(row.try_get::<_, Option<i64>>(index)?),
+        Type::FLOAT4 => boxed(row.try_get::<_, Option<f32>>(index)?),
+        Type::FLOAT8 => boxed(row.try_get::<_, Option<f64>>(index)?),
+        Type::NUMERIC => match row.try_get::<_, Option<Numeric>>(index)? {
+            Some(Numeric::Integer(value)) => boxed(Some(value)),
+            Some(Numeric::Decimal(value)) => boxed(Some(value)),
+            None => None,
+        },
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => {
+            boxed(row.try_get::<_, Option<String>>(index)?)
+        }
+        Type::BYTEA => boxed(
+            row.try_get::<_, Option<Vec<u8>>>(index)?
+                .map(|bytes| format!("0x{}", hex::encode(bytes))),
+        ),
+        _ => {
+            info!(
+                "Skipping column: {}, data_type: {:?}",
+                column.name(),
+                column.type_()
+            );
+            None
+        }
+    })
+}
+
+#[async_trait::async_trait]
+impl QueryRunner for PostgresQueryRunner {
+    async fn run_single_entry(&self, query: &str) -> anyhow::Result<f64> {
+        let rows = self.connect().await?.query(query, &[]).await?;
+        let row = rows
+            .first()
+            .ok_or_else(|| anyhow!("No rows returned by query"))?;
+        let value = read_column(row, 0)?.ok_or_else(|| anyhow!("First column is NULL"))?;
+        if let Some(value) = value.downcast_ref::<f64>() {
+            Ok(*value)
+        } else if let Some(value) = value.downcast_ref::<f32>() {
+            Ok(*value as f64)
+        } else if let Some(value) = value.downcast_ref::<i128>() {
+            Ok(*value as f64)
+        } else if let Some(value) = value.downcast_ref::<i64>() {
+            Ok(*value as f64)
+        } else if let Some(value) = value.downcast_ref::<i32>() {
+            Ok(*value as f64)
+        } else if let Some(value) = value.downcast_ref::<i16>() {
+            Ok(*value as f64)
+        } else {
+            Err(anyhow!("First column is not a number"))
+        }
+    }
+
+    async fn run(&self, query: &str) -> anyhow::Result<Vec<Row>> {
+        info!("Running query: {}", query);
+        let pg_rows = self.connect().await?.query(query, &[]).await?;
+        let mut rows: Vec<Row> = Vec::with_capacity(pg_rows.len());
+        for pg_row in &pg_rows {
+            let mut row = HashMap::new();
+            for (index, column) in pg_row.columns().iter().enumerate() {
+                if let Some(value) = read_column(pg_row, index)? {
+                    // Upper-cased as Snowflake does for unquoted identifiers, so that monitors
+                    // read the same columns whatever the backend they run against.
+                    row.insert(column.name().to_uppercase(), value);
+                }
+            }
+            rows.push(row);
+        }
+        info!("Found {} rows", rows.len());
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The binary encoding of a `NUMERIC` with the given header and base 10000 `digits`.
+    fn numeric(weight: i16, sign: u16, scale: i16, digits: &[i16]) -> Vec<u8> {
+        [digits.len() as i16, weight, sign as i16, scale]
+            .iter()
+            .chain(digits)
+            .flat_map(|word| word.to_be_bytes())
+            .collect()
+    }
+
+    fn from_sql(raw: &[u8]) -> Result<Numeric, Box<dyn Error + Sync + Send>> {
+        Numeric::from_sql(&Type::NUMERIC, raw)
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(from_sql(&[0; 8]).unwrap(), Numeric::Integer(0));
+        assert_eq!(
+            from_sql(&numeric(1, 0x0000, 0, &[1234, 5678])).unwrap(),
+            Numeric::Integer(12_345_678)
+        );
+        assert_eq!(
+            from_sql(&numeric(0, 0x4000, 0, &[42])).unwrap(),
+            Numeric::Integer(-42)
+        );
+        // Trailing zero digits are not sent, the weight tells how many there are.
+        assert_eq!(
+            from_sql(&numeric(3, 0x0000, 0, &[1])).unwrap(),
+            Numeric::Integer(1_000_000_000_000)
+        );
+        assert_eq!(
+            from_sql(&numeric(2, 0x4000, 0, &[12, 0, 5])).unwrap(),
+            Numeric::Integer(-1_200_000_005)
+        );
+    }
+
+    #[test]
+    fn test_decimals() {
+        assert_eq!(
+            from_sql(&numeric(0, 0x0000, 1, &[1, 5000])).unwrap(),
+            Numeric::Decimal(1.5)
+        );
+        assert_eq!(
+            from_sql(&numeric(-1, 0x4000, 2, &[2500])).unwrap(),
+            Numeric::Decimal(-0.25)
+        );
+        // Integers that don't fit in an i128 are read as floating point numbers.
+        let Numeric::Decimal(value) = from_sql(&numeric(10, 0x0000, 0, &[1])).unwrap() else {
+            panic!("Expected an overflowing NUMERIC to be read as a decimal");
+        };
+        assert!((value / 1e40 - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_invalid() {
+        // NaN, +Infinity and -Infinity.
+        for sign in [0xC000, 0xD000, 0xF000] {
+            assert!(from_sql(&numeric(0, sign, 0, &[])).is_err());
+        }
+        assert!(from_sql(&[0, 1, 0, 0]).is_err());
+        assert!(from_sql(&numeric(0, 0x0000, 0, &[1])[..9]).is_err());
+    }
+}
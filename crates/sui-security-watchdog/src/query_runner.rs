@@ -157,45 +157,6 @@ impl SnowflakeQueryRunner {
             .as_f64();
         Ok(value)
     }
-
-    fn parse_record_batch(&self, batch: RecordBatch) -> anyhow::Result<Vec<Row>> {
-        let mut rows: Vec<Row> = Vec::new();
-        for (index, column) in batch.columns().iter().enumerate() {
-            let name = batch.schema().fields()[index].name().clone();
-            insert_primitive_values!(rows, column, name, Int8Type);
-            insert_primitive_values!(rows, column, name, Int16Type);
-            insert_primitive_values!(rows, column, name, Int32Type);
-            insert_primitive_values!(rows, column, name, Int64Type);
-            insert_primitive_values!(rows, column, name, UInt8Type);
-            insert_primitive_values!(rows, column, name, UInt16Type);
-            insert_primitive_values!(rows, column, name, UInt32Type);
-            insert_primitive_values!(rows, column, name, UInt64Type);
-            insert_primitive_values!(rows, column, name, Float16Type);
-            insert_primitive_values!(rows, column, name, Float32Type);
-            insert_primitive_values!(rows, column, name, Float64Type);
-            insert_primitive_values!(rows, column, name, Decimal128Type);
-            insert_string_values!(rows, column, name, i32);
-            insert_string_values!(rows, column, name, i64);
-            let schema = batch.schema();
-            let data_type = schema.fields()[index].data_type();
-            let metadata = schema.fields()[index].metadata();
-            info!(
-                "Skipping column: {}, data_type: {:?}, metadata: {:?}",
-                name, data_type, metadata
-            );
-        }
-        Ok(rows)
-    }
-
-    fn parse_record_batches(&self, batches: Vec<RecordBatch>) -> anyhow::Result<Vec<Row>> {
-        let mut rows: Vec<Row> = Vec::new();
-        for batch in batches {
-            let mut batch_rows = self.parse_record_batch(batch)?;
-            rows.append(&mut batch_rows);
-        }
-        info!("Found {} rows", rows.len());
-        Ok(rows)
-    }
 }
 
 #[async_trait::async_trait]
@@ -213,10 +174,62 @@ impl QueryRunner for SnowflakeQueryRunner {
         info!("Running query: {}", query);
         let res = self.make_snowflake_api()?.exec(query).await?;
         match res {
-            QueryResult::Arrow(records) => self.parse_record_batches(records),
+            QueryResult::Arrow(records) => parse_record_batches(records),
             QueryResult::Empty => Ok(Vec::new()),
             // Handle other result types Json with a unified error message
             _ => Err(anyhow!("Unexpected query result type")),
         }
     }
 }
+
+fn parse_record_batch(batch: RecordBatch) -> anyhow::Result<Vec<Row>> {
+    let mut rows: Vec<Row> = Vec::new();
+    for (index, column) in batch.columns().iter().enumerate() {
+        let name = batch.schema().fields()[index].name().clone();
+        insert_primitive_values!(rows, column, name, Int8Type);
+        insert_primitive_values!(rows, column, name, Int16Type);
+        insert_primitive_values!(rows, column, name, Int32Type);
+        insert_primitive_values!(rows, column, name, Int64Type);
+        insert_primitive_values!(rows, column, name, UInt8Type);
+        insert_primitive_values!(rows, column, name, UInt16Type);
+        insert_primitive_values!(rows, column, name, UInt32Type);
+        insert_primitive_values!(rows, column, name, UInt64Type);
+        insert_primitive_values!(rows, column, name, Float16Type);
+        insert_primitive_values!(rows, column, name, Float32Type);
+        insert_primitive_values!(rows, column, name, Float64Type);
+        insert_primitive_values!(rows, column, name, Decimal128Type);
+        insert_string_values!(rows, column, name, i32);
+        insert_string_values!(rows, column, name, i64);
+        let schema = batch.schema();
+        let data_type = schema.fields()[index].data_type();
+        let metadata = schema.fields()[index].metadata();
+        info!(
+            "Skipping column: {}, data_type: {:?}, metadata: {:?}",
+            name, data_type, metadata
+        );
+    }
+    Ok(rows)
+}
+
+/// Parses Arrow record batches into rows, skipping columns of unsupported types.
+pub(crate) fn parse_record_batches(batches: Vec<RecordBatch>) -> anyhow::Result<Vec<Row>> {
+    let mut rows: Vec<Row> = Vec::new();
+    for batch in batches {
+        let mut batch_rows = parse_record_batch(batch)?;
+        rows.append(&mut batch_rows);
+    }
+    info!("Found {} rows", rows.len());
+    Ok(rows)
+}
+
+/// Upper-cases the column names of `rows`, as Snowflake does for unquoted identifiers, so that
+/// monitors read the same columns whatever the backend they run against.
+pub(crate) fn uppercase_column_names(rows: Vec<Row>) -> Vec<Row> {
+    rows.into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(name, value)| (name.to_uppercase(), value))
+                .collect()
+        })
+        .collect()
+}
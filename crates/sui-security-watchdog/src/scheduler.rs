@@ -1,10 +1,13 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::alert_sink::{Alert, AlertSink, AlertSinks, StdoutAlertSink, WebhookAlertSink};
+use crate::duckdb_query_runner::DuckDbQueryRunner;
 use crate::metrics::WatchdogMetrics;
-use crate::pagerduty::{Body, CreateIncident, Incident, Pagerduty, Service};
+use crate::pagerduty::{Pagerduty, PagerdutyAlertSink};
+use crate::postgres_query_runner::PostgresQueryRunner;
 use crate::query_runner::{QueryRunner, SnowflakeQueryRunner};
-use crate::SecurityWatchdogConfig;
+use crate::{AlertSinkKind, QueryBackend, SecurityWatchdogConfig};
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
 use prometheus::{IntGauge, Registry};
@@ -43,7 +46,7 @@ pub struct MetricPublishingEntry {
 }
 
 // WalletMonitoringEntry is a struct that represents the configuration of a job which monitors wallet balances.
-// It creates pagerduty incidents based on the given SQL query and cron schedule.
+// It sends alerts for the wallets returned by the given SQL query on a cron schedule.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct WalletMonitoringEntry {
     name: String,
@@ -56,28 +59,72 @@ pub struct SchedulerService {
     query_runner: Arc<dyn QueryRunner>,
     metrics: Arc<WatchdogMetrics>,
     entries: Vec<MonitoringEntry>,
-    pagerduty: Pagerduty,
-    pd_wallet_monitoring_service_id: String,
+    alert_sink: Arc<dyn AlertSink>,
 }
 
 impl SchedulerService {
     pub async fn new(
         config: &SecurityWatchdogConfig,
         registry: &Registry,
-        pd_api_key: String,
-        sf_password: String,
+        pd_api_key: Option<String>,
+        db_password: Option<String>,
     ) -> anyhow::Result<Self> {
         let scheduler = JobScheduler::new().await?;
         Ok(Self {
             scheduler,
-            query_runner: Arc::new(SnowflakeQueryRunner::from_config(config, sf_password)?),
+            query_runner: Self::query_runner(config, db_password)?,
             metrics: Arc::new(WatchdogMetrics::new(registry)),
             entries: Self::from_config(config)?,
-            pagerduty: Pagerduty::new(pd_api_key.clone()),
-            pd_wallet_monitoring_service_id: config.pd_wallet_monitoring_service_id.clone(),
+            alert_sink: Self::alert_sink(config, pd_api_key)?,
         })
     }
 
+    fn query_runner(
+        config: &SecurityWatchdogConfig,
+        db_password: Option<String>,
+    ) -> anyhow::Result<Arc<dyn QueryRunner>> {
+        Ok(match config.query_backend {
+            QueryBackend::Snowflake => {
+                let sf_password = db_password
+                    .ok_or_else(|| anyhow!("A password is required by the snowflake backend"))?;
+                Arc::new(SnowflakeQueryRunner::from_config(config, sf_password)?)
+            }
+            QueryBackend::Postgres => {
+                Arc::new(PostgresQueryRunner::from_config(config, db_password)?)
+            }
+            QueryBackend::DuckDb => Arc::new(DuckDbQueryRunner::from_config(config)),
+        })
+    }
+
+    fn alert_sink(
+        config: &SecurityWatchdogConfig,
+        pd_api_key: Option<String>,
+    ) -> anyhow::Result<Arc<dyn AlertSink>> {
+        let mut sinks: Vec<Arc<dyn AlertSink>> = vec![];
+        for kind in &config.alert_sinks {
+            sinks.push(match kind {
+                AlertSinkKind::Pagerduty => {
+                    let api_key = pd_api_key
+                        .clone()
+                        .ok_or_else(|| anyhow!("PD_API_KEY is required by the pagerduty sink"))?;
+                    let service_id = config
+                        .pd_wallet_monitoring_service_id
+                        .clone()
+                        .ok_or_else(|| anyhow!("The pagerduty sink requires a service id"))?;
+                    Arc::new(PagerdutyAlertSink::new(Pagerduty::new(api_key), service_id))
+                }
+                AlertSinkKind::Webhook => {
+                    let url = config.alert_webhook_url.clone().ok_or_else(|| {
+                        anyhow!("--alert-webhook-url is required by the webhook sink")
+                    })?;
+                    Arc::new(WebhookAlertSink::new(url))
+                }
+                AlertSinkKind::Stdout => Arc::new(StdoutAlertSink),
+            });
+        }
+        Ok(Arc::new(AlertSinks(sinks)))
+    }
+
     pub async fn schedule(&self) -> anyhow::Result<()> {
         for monitoring_entry in &self.entries {
             match monitoring_entry {
@@ -95,9 +142,8 @@ impl SchedulerService {
                         entry.clone(),
                         self.scheduler.clone(),
                         self.query_runner.clone(),
-                        self.pd_wallet_monitoring_service_id.clone(),
                         self.metrics.clone(),
-                        self.pagerduty.clone(),
+                        self.alert_sink.clone(),
                     )
                     .await?;
                 }
@@ -124,23 +170,20 @@ impl SchedulerService {
         entry: WalletMonitoringEntry,
         scheduler: JobScheduler,
         query_runner: Arc<dyn QueryRunner>,
-        pd_service_id: String,
         metrics: Arc<WatchdogMetrics>,
-        pagerduty: Pagerduty,
+        alert_sink: Arc<dyn AlertSink>,
     ) -> anyhow::Result<Uuid> {
         let name = entry.name.clone();
         let cron_schedule = entry.cron_schedule.clone();
         let job = Job::new_async(cron_schedule.as_str(), move |_uuid, _lock| {
             let entry = entry.clone();
             let query_runner = query_runner.clone();
-            let pd_service_id = pd_service_id.to_string();
-            let pd = pagerduty.clone();
+            let alert_sink = alert_sink.clone();
             let metrics = metrics.clone();
             Box::pin(async move {
                 info!("Running wallet monitoring job: {}", entry.name);
                 if let Err(err) =
-                    Self::run_wallet_monitoring_job(&pd, &pd_service_id, &query_runner, &entry)
-                        .await
+                    Self::run_wallet_monitoring_job(&alert_sink, &query_runner, &entry).await
                 {
                     error!("Failed to run wallet monitoring job with err: {}", err);
                     metrics
@@ -157,8 +200,7 @@ impl SchedulerService {
     }
 
     async fn run_wallet_monitoring_job(
-        pagerduty: &Pagerduty,
-        service_id: &str,
+        alert_sink: &Arc<dyn AlertSink>,
         query_runner: &Arc<dyn QueryRunner>,
         entry: &WalletMonitoringEntry,
     ) -> anyhow::Result<()> {
@@ -181,49 +223,22 @@ impl SchedulerService {
                     .ok_or_else(|| anyhow!("Missing lower_bound"))?,
             )
             .ok_or(anyhow!("Failed to downcast lower_bound"))?;
-            Self::create_wallet_monitoring_incident(
-                pagerduty,
-                &wallet_id,
-                current_balance,
-                lower_bound,
-                service_id,
-            )
-            .await?;
+            let alert = Self::wallet_monitoring_alert(&wallet_id, current_balance, lower_bound);
+            alert_sink.send(&alert).await?;
         }
         Ok(())
     }
 
-    async fn create_wallet_monitoring_incident(
-        pagerduty: &Pagerduty,
-        wallet_id: &str,
-        current_balance: i128,
-        lower_bound: i128,
-        service_id: &str,
-    ) -> anyhow::Result<()> {
-        let service = Service {
-            id: service_id.to_string(),
-            ..Default::default()
-        };
-        let incident_body = Body {
+    fn wallet_monitoring_alert(wallet_id: &str, current_balance: i128, lower_bound: i128) -> Alert {
+        Alert {
+            key: wallet_id.to_string(),
+            title: format!("Wallet: {} is out of compliance", wallet_id),
             details: format!(
                 "Current balance: {} SUI, Lower bound: {} SUI",
                 current_balance / MIST_PER_SUI,
                 lower_bound / MIST_PER_SUI
             ),
-            ..Default::default()
-        };
-        let incident = Incident {
-            title: format!("Wallet: {} is out of compliance", wallet_id),
-            service,
-            incident_key: wallet_id.to_string(),
-            body: incident_body,
-            ..Default::default()
-        };
-        let create_incident = CreateIncident { incident };
-        pagerduty
-            .create_incident("sadhan@mystenlabs.com", create_incident)
-            .await?;
-        Ok(())
+        }
     }
 
     async fn schedule_metric_publish_job(
@@ -0,0 +1,73 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sui_types::base_types::SuiAddress;
+use sui_types::digests::TransactionDigest;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::policy::Violation;
+
+/// Append-only record of signing requests, written as one JSON object per line.
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+/// A single signing request, and how it was handled.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    /// The client that made the request, if it was authenticated.
+    pub client: Option<String>,
+    /// The key the request asked to sign with, if the request could be parsed.
+    pub address: Option<SuiAddress>,
+    /// The digest of the transaction, if it could be decoded.
+    pub digest: Option<TransactionDigest>,
+    pub outcome: Outcome,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "result")]
+pub enum Outcome {
+    /// The transaction was signed, producing this (Base64 encoded) signature.
+    Signed { signature: String },
+    /// The transaction violated the key's policy, and was not signed.
+    Denied { violations: Vec<Violation> },
+    /// The request was not authenticated, or was malformed, and was not reviewed.
+    Rejected { error: String },
+}
+
+impl AuditLog {
+    pub async fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to open audit log at {}", path.display()))?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append `entry` to the log, and wait for it to reach disk. Signatures must not be released
+    /// unless this succeeds.
+    pub async fn record(&self, entry: &AuditEntry) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().await;
+        file.write_all(&line).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use sui_types::base_types::{ObjectID, SuiAddress};
+
+/// Configuration for the signing service, read from a YAML file:
+///
+/// ```yaml
+/// listen-address: 127.0.0.1:9500
+/// keystore-path: /home/ops/.sui/sui_config/sui.keystore
+/// audit-log-path: /var/log/sui-signer/audit.jsonl
+/// clients:
+///   - name: payouts
+///     token: <secret>
+///   - name: trading
+///     token: <secret>
+/// keys:
+///   # Pays out limited amounts to known addresses.
+///   - address: 0x1234...
+///     allowed-clients: [payouts]
+///     max-amount: 1000000000000
+///     allowed-recipients: [0x5678...]
+///   # Calls functions in a known package.
+///   - address: 0x9abc...
+///     allowed-clients: [trading]
+///     allowed-packages: [0xdef0...]
+///     max-gas-budget: 50000000
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SignerConfig {
+    /// Address to serve the signing API on. This should be a loopback or otherwise private
+    /// address.
+    pub listen_address: SocketAddr,
+
+    /// Keystore holding the keys to sign with, in the format used by `sui keytool`.
+    pub keystore_path: PathBuf,
+
+    /// File that a record of every signing request is appended to, as JSON lines.
+    pub audit_log_path: PathBuf,
+
+    /// Clients that are allowed to use the service. Requests must carry one of these clients'
+    /// tokens as a bearer token.
+    pub clients: Vec<ClientConfig>,
+
+    /// Keys that the service signs with, and the policies their signatures are subject to. Keys
+    /// in the keystore that are not listed here are never used.
+    pub keys: Vec<KeyConfig>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ClientConfig {
+    /// Name of the client, recorded in the audit log.
+    pub name: String,
+    pub token: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct KeyConfig {
+    pub address: SuiAddress,
+    #[serde(flatten)]
+    pub policy: KeyPolicy,
+}
+
+/// Restrictions on the transactions a key signs. Restrictions that are not set are not enforced.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct KeyPolicy {
+    /// Names of the clients that can request signatures from this key. All clients can if this
+    /// is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_clients: Option<BTreeSet<String>>,
+
+    /// Packages that the transaction can call functions from. Transactions that publish or
+    /// upgrade packages are rejected if this is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_packages: Option<BTreeSet<ObjectID>>,
+
+    /// Upper bound on the total amount split off of coins by the transaction, in the coins' base
+    /// unit. Transactions that transfer the gas coin itself are rejected if this is set.
+    ///
+    /// This only accounts for `SplitCoins` commands, so if it is set, transactions that pass
+    /// anything but pure inputs to a function call are rejected, whatever `allowed-packages` is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<u64>,
+
+    /// Upper bound on the transaction's gas budget, in MIST.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_gas_budget: Option<u64>,
+
+    /// Addresses that the transaction can transfer objects to. This only accounts for
+    /// `TransferObjects` commands, so like `max-amount`, it rejects transactions that pass anything
+    /// but pure inputs to a function call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_recipients: Option<BTreeSet<SuiAddress>>,
+}
+
+impl SignerConfig {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config from {}", path.display()))?;
+
+        let config: Self = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config at {}", path.display()))?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let mut names = BTreeSet::new();
+        let mut tokens = BTreeSet::new();
+        for client in &self.clients {
+            if client.token.is_empty() {
+                bail!("Client {:?} has an empty token", client.name);
+            }
+            if !names.insert(&client.name) {
+                bail!("Client {:?} is configured more than once", client.name);
+            }
+            if !tokens.insert(&client.token) {
+                bail!(
+                    "Client {:?} shares its token with another client",
+                    client.name
+                );
+            }
+        }
+
+        let mut addresses = BTreeSet::new();
+        for key in &self.keys {
+            if !addresses.insert(key.address) {
+                bail!("Key {} is configured more than once", key.address);
+            }
+
+            for client in key.policy.allowed_clients.iter().flatten() {
+                if !names.contains(client) {
+                    bail!("Key {} allows unknown client {client:?}", key.address);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
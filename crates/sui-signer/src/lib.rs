@@ -0,0 +1,376 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A signing service that holds a keystore, and signs transactions on behalf of authenticated
+//! clients, subject to per-key policies.
+//!
+//! Requests and responses follow `sui keytool sign`: transactions are submitted as Base64 encoded
+//! BCS `TransactionData`, optionally with a hex encoded intent, and signatures are returned as
+//! Base64 encoded `flag || signature || pubkey`. Every signing request is recorded in an audit
+//! log, whether it is signed, refused by the key's policy, or rejected before it is reviewed
+//! (because it is not authenticated, or is malformed).
+//!
+//! The service exposes the following routes, which all expect an `Authorization: Bearer <token>`
+//! header with one of the configured client tokens:
+//!
+//! - `GET /keys` lists the keys the client can use, and their policies.
+//! - `POST /decode` decodes a transaction for review, and lists the ways it violates the signing
+//!   key's policy, without signing it.
+//! - `POST /sign` signs a transaction, if it does not violate the signing key's policy.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use fastcrypto::encoding::{Base64, Encoding};
+use fastcrypto::hash::HashFunction;
+use serde::{Deserialize, Serialize};
+use shared_crypto::intent::{Intent, IntentMessage, IntentScope};
+use sui_keys::keystore::{AccountKeystore, Keystore};
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::DefaultHash;
+use sui_types::digests::TransactionDigest;
+use sui_types::signature::GenericSignature;
+use sui_types::transaction::TransactionData;
+use tracing::{error, info};
+
+use crate::audit::{AuditEntry, AuditLog, Outcome};
+use crate::config::{ClientConfig, KeyPolicy, SignerConfig};
+use crate::policy::Violation;
+
+pub mod audit;
+pub mod config;
+pub mod policy;
+
+/// State shared by all requests to the signing service.
+pub struct Signer {
+    keystore: Keystore,
+    audit_log: AuditLog,
+    clients: Vec<ClientConfig>,
+    policies: BTreeMap<SuiAddress, KeyPolicy>,
+}
+
+/// Request to decode or sign a transaction.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignRequest {
+    /// The key to sign with.
+    pub address: SuiAddress,
+    /// Base64 encoded BCS serialized `TransactionData`.
+    pub data: String,
+    /// Hex encoded intent. The default transaction intent is used if this is absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodeResponse {
+    pub sui_address: SuiAddress,
+    pub tx_digest: TransactionDigest,
+    pub tx_data: TransactionData,
+    /// Reasons the transaction would not be signed. The transaction would be signed if this is
+    /// empty.
+    pub violations: Vec<String>,
+}
+
+/// A signature, in the same format as the output of `sui keytool sign`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignResponse {
+    pub sui_address: SuiAddress,
+    /// Base64 encoded BCS serialized transaction data.
+    pub raw_tx_data: String,
+    pub intent: Intent,
+    /// Base64 encoded intent message, `intent || message`.
+    pub raw_intent_msg: String,
+    /// Base64 encoded blake2b hash of the intent message, which the signature commits to.
+    pub digest: String,
+    /// Base64 encoded `flag || signature || pubkey`.
+    pub sui_signature: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyResponse {
+    pub address: SuiAddress,
+    pub policy: KeyPolicy,
+}
+
+#[derive(Debug)]
+enum ApiError {
+    Unauthorized,
+    BadRequest(String),
+    Forbidden(Vec<Violation>),
+    Internal(anyhow::Error),
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    violations: Vec<String>,
+}
+
+/// Error message for requests without a valid client token.
+const UNAUTHORIZED: &str = "Missing or invalid bearer token";
+
+impl Signer {
+    /// Set up a signer from `config`, using the keys in `keystore`. Fails if any of the
+    /// configured keys are missing from the keystore.
+    pub async fn new(config: &SignerConfig, keystore: Keystore) -> anyhow::Result<Self> {
+        let mut policies = BTreeMap::new();
+        for key in &config.keys {
+            keystore
+                .get_key(&key.address)
+                .with_context(|| format!("Key {} is not in the keystore", key.address))?;
+            policies.insert(key.address, key.policy.clone());
+        }
+
+        Ok(Self {
+            keystore,
+            audit_log: AuditLog::open(&config.audit_log_path).await?,
+            clients: config.clients.clone(),
+            policies,
+        })
+    }
+
+    /// Routes for the signing API.
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/keys", get(keys))
+            .route("/decode", post(decode))
+            .route("/sign", post(sign))
+            .with_state(self)
+    }
+
+    /// The name of the client whose token is in the request's `Authorization` header.
+    fn authenticate(&self, headers: &HeaderMap) -> Result<&str, ApiError> {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(ApiError::Unauthorized)?;
+
+        self.clients
+            .iter()
+            .find(|client| constant_time_eq(client.token.as_bytes(), token.as_bytes()))
+            .map(|client| client.name.as_str())
+            .ok_or(ApiError::Unauthorized)
+    }
+
+    /// Decode the transaction in `request` and check it against the signing key's policy.
+    fn review(
+        &self,
+        client: &str,
+        request: &SignRequest,
+    ) -> Result<(Intent, TransactionData, Vec<Violation>), ApiError> {
+        let Some(policy) = self.policies.get(&request.address) else {
+            return Err(ApiError::BadRequest(format!(
+                "Key {} is not available for signing",
+                request.address
+            )));
+        };
+
+        let intent = match &request.intent {
+            None => Intent::sui_transaction(),
+            Some(intent) => Intent::from_str(intent)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid intent: {e}")))?,
+        };
+
+        if intent.scope != IntentScope::TransactionData {
+            return Err(ApiError::BadRequest(
+                "Only transaction intents can be signed".to_owned(),
+            ));
+        }
+
+        let bytes = Base64::decode(&request.data)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid Base64 data: {e}")))?;
+
+        let tx_data: TransactionData = bcs::from_bytes(&bytes).map_err(|e| {
+            ApiError::BadRequest(format!("Cannot deserialize data as TransactionData: {e}"))
+        })?;
+
+        let violations = policy::check(policy, client, request.address, &tx_data);
+        Ok((intent, tx_data, violations))
+    }
+}
+
+async fn keys(State(signer): State<Arc<Signer>>, headers: HeaderMap) -> Result<Response, ApiError> {
+    let client = signer.authenticate(&headers)?;
+    let keys: Vec<_> = signer
+        .policies
+        .iter()
+        .filter(|(_, policy)| {
+            policy
+                .allowed_clients
+                .as_ref()
+                .map_or(true, |clients| clients.contains(client))
+        })
+        .map(|(address, policy)| KeyResponse {
+            address: *address,
+            policy: policy.clone(),
+        })
+        .collect();
+
+    Ok(Json(keys).into_response())
+}
+
+async fn decode(
+    State(signer): State<Arc<Signer>>,
+    headers: HeaderMap,
+    Json(request): Json<SignRequest>,
+) -> Result<Response, ApiError> {
+    let client = signer.authenticate(&headers)?;
+    let (_, tx_data, violations) = signer.review(client, &request)?;
+
+    Ok(Json(DecodeResponse {
+        sui_address: request.address,
+        tx_digest: tx_data.digest(),
+        tx_data,
+        violations: violations.iter().map(|v| v.to_string()).collect(),
+    })
+    .into_response())
+}
+
+async fn sign(
+    State(signer): State<Arc<Signer>>,
+    headers: HeaderMap,
+    request: Result<Json<SignRequest>, JsonRejection>,
+) -> Result<Response, ApiError> {
+    let mut entry = AuditEntry {
+        timestamp: chrono::Utc::now(),
+        client: None,
+        address: None,
+        digest: None,
+        outcome: Outcome::Rejected {
+            error: String::new(),
+        },
+    };
+
+    let result = sign_request(&signer, &headers, request, &mut entry).await;
+
+    // Requests that are turned away before they are reviewed against a policy are audited here,
+    // so that unauthenticated and malformed attempts show up in the log as well.
+    let error = match &result {
+        Err(ApiError::Unauthorized) => UNAUTHORIZED.to_owned(),
+        Err(ApiError::BadRequest(error)) => error.clone(),
+        _ => return result,
+    };
+
+    info!("Rejected signing request: {error}");
+    entry.outcome = Outcome::Rejected { error };
+    signer
+        .audit_log
+        .record(&entry)
+        .await
+        .map_err(ApiError::Internal)?;
+
+    result
+}
+
+/// Sign the transaction in `request`, filling in `entry` with the details of the request as they
+/// become known. Records `entry` in the audit log once the request has been reviewed against the
+/// signing key's policy.
+async fn sign_request(
+    signer: &Signer,
+    headers: &HeaderMap,
+    request: Result<Json<SignRequest>, JsonRejection>,
+    entry: &mut AuditEntry,
+) -> Result<Response, ApiError> {
+    let client = signer.authenticate(headers)?;
+    entry.client = Some(client.to_owned());
+
+    let Json(request) = request.map_err(|e| ApiError::BadRequest(e.body_text()))?;
+    entry.address = Some(request.address);
+
+    let (intent, tx_data, violations) = signer.review(client, &request)?;
+    let digest = tx_data.digest();
+    entry.digest = Some(digest);
+
+    if !violations.is_empty() {
+        info!(
+            "Refused to sign {digest} with {} for {client}",
+            request.address
+        );
+        entry.outcome = Outcome::Denied {
+            violations: violations.clone(),
+        };
+        signer
+            .audit_log
+            .record(entry)
+            .await
+            .map_err(ApiError::Internal)?;
+        return Err(ApiError::Forbidden(violations));
+    }
+
+    let signature = signer
+        .keystore
+        .sign_secure(&request.address, &tx_data, intent.clone())
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to sign transaction: {e}")))?;
+    let sui_signature = Base64::encode(GenericSignature::Signature(signature).as_ref());
+
+    // The signature is only released once it has been recorded.
+    entry.outcome = Outcome::Signed {
+        signature: sui_signature.clone(),
+    };
+    signer
+        .audit_log
+        .record(entry)
+        .await
+        .map_err(ApiError::Internal)?;
+    info!("Signed {digest} with {} for {client}", request.address);
+
+    let intent_msg = IntentMessage::new(intent.clone(), tx_data);
+    let raw_intent_msg = bcs::to_bytes(&intent_msg).map_err(|e| ApiError::Internal(e.into()))?;
+    let mut hasher = DefaultHash::default();
+    hasher.update(&raw_intent_msg);
+
+    Ok(Json(SignResponse {
+        sui_address: request.address,
+        raw_tx_data: request.data,
+        intent,
+        raw_intent_msg: Base64::encode(&raw_intent_msg),
+        digest: Base64::encode(hasher.finalize().digest),
+        sui_signature,
+    })
+    .into_response())
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error, violations) = match self {
+            ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, UNAUTHORIZED.to_owned(), vec![]),
+
+            ApiError::BadRequest(error) => (StatusCode::BAD_REQUEST, error, vec![]),
+
+            ApiError::Forbidden(violations) => (
+                StatusCode::FORBIDDEN,
+                "Transaction violates the key's policy".to_owned(),
+                violations.iter().map(|v| v.to_string()).collect(),
+            ),
+
+            ApiError::Internal(e) => {
+                error!("Signing request failed: {e:#}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal error".to_owned(),
+                    vec![],
+                )
+            }
+        };
+
+        (status, Json(ErrorResponse { error, violations })).into_response()
+    }
+}
+
+/// Compare `a` and `b` in time that only depends on their lengths, so that tokens can't be
+/// guessed byte by byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,46 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use clap::Parser;
+use sui_keys::keystore::{FileBasedKeystore, Keystore};
+use sui_signer::config::SignerConfig;
+use sui_signer::Signer;
+use telemetry_subscribers::TelemetryConfig;
+use tracing::info;
+
+/// Serve a local API for signing transactions with keys from a keystore, subject to per-key
+/// policies.
+#[derive(Parser)]
+#[clap(name = "sui-signer", rename_all = "kebab-case")]
+struct Args {
+    /// Path to the signer's YAML config.
+    #[clap(long)]
+    config: PathBuf,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let _guard = TelemetryConfig::new().with_env().init();
+
+    let config = SignerConfig::load(&args.config)?;
+    let keystore = Keystore::from(FileBasedKeystore::new(&config.keystore_path).with_context(
+        || {
+            format!(
+                "Failed to open keystore at {}",
+                config.keystore_path.display()
+            )
+        },
+    )?);
+
+    let signer = Arc::new(Signer::new(&config, keystore).await?);
+    let listener = tokio::net::TcpListener::bind(config.listen_address).await?;
+
+    info!("Serving signing API on {}", config.listen_address);
+    axum::serve(listener, signer.router()).await?;
+    Ok(())
+}
@@ -0,0 +1,563 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::transaction::{
+    Argument, CallArg, Command, ProgrammableTransaction, TransactionData, TransactionDataAPI,
+    TransactionKind,
+};
+
+use crate::config::KeyPolicy;
+
+/// A reason for refusing to sign a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(rename_all = "kebab-case", tag = "kind")]
+pub enum Violation {
+    #[error("Client {client:?} is not allowed to use this key")]
+    ClientNotAllowed { client: String },
+
+    #[error("Key {signer} is neither the sender nor the gas owner of the transaction")]
+    NotAParticipant { signer: SuiAddress },
+
+    #[error("Only programmable transactions can be signed")]
+    UnsupportedTransactionKind,
+
+    #[error("Calls to package {package} are not allowed")]
+    PackageNotAllowed { package: ObjectID },
+
+    #[error("Publishing and upgrading packages is not allowed")]
+    PublishNotAllowed,
+
+    #[error("Amount {amount} exceeds the limit of {limit}")]
+    AmountExceeded { amount: u128, limit: u64 },
+
+    #[error("Amounts that are not constant inputs cannot be checked against the amount limit")]
+    UnknownAmount,
+
+    #[error("Transferring the gas coin cannot be checked against the amount limit")]
+    GasCoinTransferred,
+
+    #[error(
+        "Transferring objects other than coins split off by constant amounts cannot be checked \
+         against the amount limit"
+    )]
+    UnboundedTransfer,
+
+    #[error("Gas budget {budget} exceeds the limit of {limit}")]
+    GasBudgetExceeded { budget: u64, limit: u64 },
+
+    #[error("Transfers to {recipient} are not allowed")]
+    RecipientNotAllowed { recipient: SuiAddress },
+
+    #[error("Recipients that are not constant inputs cannot be checked against the allowlist")]
+    UnknownRecipient,
+
+    #[error(
+        "Function calls that take objects cannot be checked against the amount limit or the \
+         recipient allowlist"
+    )]
+    UncheckedMoveCall,
+}
+
+/// Check `tx` against `policy`, for `client` asking for a signature from `signer`. Returns all the
+/// policy's violations, so the transaction can be signed if this is empty.
+pub fn check(
+    policy: &KeyPolicy,
+    client: &str,
+    signer: SuiAddress,
+    tx: &TransactionData,
+) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    if let Some(clients) = &policy.allowed_clients {
+        if !clients.contains(client) {
+            violations.push(Violation::ClientNotAllowed {
+                client: client.to_owned(),
+            });
+        }
+    }
+
+    if signer != tx.sender() && signer != tx.gas_owner() {
+        violations.push(Violation::NotAParticipant { signer });
+    }
+
+    if let Some(limit) = policy.max_gas_budget {
+        let budget = tx.gas_budget();
+        if budget > limit {
+            violations.push(Violation::GasBudgetExceeded { budget, limit });
+        }
+    }
+
+    let TransactionKind::ProgrammableTransaction(pt) = tx.kind() else {
+        violations.push(Violation::UnsupportedTransactionKind);
+        return violations;
+    };
+
+    check_packages(policy, pt, &mut violations);
+    check_amounts(policy, pt, &mut violations);
+    check_recipients(policy, pt, &mut violations);
+    check_move_calls(policy, pt, &mut violations);
+    violations
+}
+
+fn check_packages(
+    policy: &KeyPolicy,
+    pt: &ProgrammableTransaction,
+    violations: &mut Vec<Violation>,
+) {
+    let Some(packages) = &policy.allowed_packages else {
+        return;
+    };
+
+    for command in &pt.commands {
+        match command {
+            Command::MoveCall(call) if !packages.contains(&call.package) => {
+                let package = call.package;
+                let violation = Violation::PackageNotAllowed { package };
+                if !violations.contains(&violation) {
+                    violations.push(violation);
+                }
+            }
+
+            Command::Publish(..) | Command::Upgrade(..)
+                if !violations.contains(&Violation::PublishNotAllowed) =>
+            {
+                violations.push(Violation::PublishNotAllowed)
+            }
+
+            _ => {}
+        }
+    }
+}
+
+fn check_amounts(
+    policy: &KeyPolicy,
+    pt: &ProgrammableTransaction,
+    violations: &mut Vec<Violation>,
+) {
+    let Some(limit) = policy.max_amount else {
+        return;
+    };
+
+    let mut total: u128 = 0;
+    let mut unknown = false;
+    let mut gas_coin = false;
+    let mut unbounded = false;
+
+    // Commands whose results are coins split off by constant amounts, which are the only objects
+    // whose transfers are accounted for in the total.
+    let mut bounded = BTreeSet::new();
+
+    for (ix, command) in pt.commands.iter().enumerate() {
+        match command {
+            Command::SplitCoins(_, amounts) => {
+                let mut known = true;
+                for amount in amounts {
+                    match pure_input::<u64>(pt, amount) {
+                        Some(amount) => total += amount as u128,
+                        None => known = false,
+                    }
+                }
+
+                unknown |= !known;
+                if known {
+                    bounded.insert(ix as u16);
+                }
+            }
+
+            // Merging into a split coin adds an unknown amount to it.
+            Command::MergeCoins(Argument::Result(i) | Argument::NestedResult(i, _), _) => {
+                bounded.remove(i);
+            }
+
+            Command::TransferObjects(objects, _) => {
+                for object in objects {
+                    match object {
+                        Argument::GasCoin => gas_coin = true,
+                        Argument::Result(i) | Argument::NestedResult(i, _)
+                            if bounded.contains(i) => {}
+                        _ => unbounded = true,
+                    }
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    if unknown {
+        violations.push(Violation::UnknownAmount);
+    }
+
+    if gas_coin {
+        violations.push(Violation::GasCoinTransferred);
+    }
+
+    if unbounded {
+        violations.push(Violation::UnboundedTransfer);
+    }
+
+    if total > limit as u128 {
+        violations.push(Violation::AmountExceeded {
+            amount: total,
+            limit,
+        });
+    }
+}
+
+fn check_recipients(
+    policy: &KeyPolicy,
+    pt: &ProgrammableTransaction,
+    violations: &mut Vec<Violation>,
+) {
+    let Some(recipients) = &policy.allowed_recipients else {
+        return;
+    };
+
+    for command in &pt.commands {
+        let Command::TransferObjects(_, recipient) = command else {
+            continue;
+        };
+
+        let violation = match pure_input::<SuiAddress>(pt, recipient) {
+            Some(recipient) if recipients.contains(&recipient) => continue,
+            Some(recipient) => Violation::RecipientNotAllowed { recipient },
+            None => Violation::UnknownRecipient,
+        };
+
+        if !violations.contains(&violation) {
+            violations.push(violation);
+        }
+    }
+}
+
+/// Function calls can split, merge and transfer coins (e.g. `0x2::pay::split_and_transfer`)
+/// without going through the commands that the amount and recipient checks inspect, so if either
+/// is set, calls are only allowed if all their arguments are pure inputs, meaning they cannot be
+/// passed the gas coin, an owned coin, or a coin produced by an earlier command.
+fn check_move_calls(
+    policy: &KeyPolicy,
+    pt: &ProgrammableTransaction,
+    violations: &mut Vec<Violation>,
+) {
+    if policy.max_amount.is_none() && policy.allowed_recipients.is_none() {
+        return;
+    }
+
+    let unchecked = pt.commands.iter().any(|command| {
+        let Command::MoveCall(call) = command else {
+            return false;
+        };
+
+        call.arguments.iter().any(|arg| !is_pure_input(pt, arg))
+    });
+
+    if unchecked {
+        violations.push(Violation::UncheckedMoveCall);
+    }
+}
+
+/// Whether `arg` refers to a pure input.
+fn is_pure_input(pt: &ProgrammableTransaction, arg: &Argument) -> bool {
+    let Argument::Input(ix) = arg else {
+        return false;
+    };
+
+    matches!(pt.inputs.get(*ix as usize), Some(CallArg::Pure(_)))
+}
+
+/// The value of `arg`, if it refers to a pure input that deserializes as a `T`.
+fn pure_input<T: serde::de::DeserializeOwned>(
+    pt: &ProgrammableTransaction,
+    arg: &Argument,
+) -> Option<T> {
+    let Argument::Input(ix) = arg else {
+        return None;
+    };
+
+    let CallArg::Pure(bytes) = pt.inputs.get(*ix as usize)? else {
+        return None;
+    };
+
+    bcs::from_bytes(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use move_core_types::ident_str;
+    use sui_types::base_types::{random_object_ref, ObjectID};
+    use sui_types::gas_coin::GAS;
+    use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+    use sui_types::transaction::{ObjectArg, TEST_ONLY_GAS_UNIT_FOR_TRANSFER};
+    use sui_types::SUI_FRAMEWORK_PACKAGE_ID;
+
+    use super::*;
+
+    fn transaction(sender: SuiAddress, pt: ProgrammableTransaction) -> TransactionData {
+        TransactionData::new_programmable(
+            sender,
+            vec![random_object_ref()],
+            pt,
+            TEST_ONLY_GAS_UNIT_FOR_TRANSFER,
+            1000,
+        )
+    }
+
+    fn pay(recipients: Vec<SuiAddress>, amounts: Vec<u64>) -> ProgrammableTransaction {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.pay_sui(recipients, amounts).unwrap();
+        builder.finish()
+    }
+
+    #[test]
+    fn test_unrestricted_policy() {
+        let sender = SuiAddress::random_for_testing_only();
+        let recipient = SuiAddress::random_for_testing_only();
+        let tx = transaction(sender, pay(vec![recipient], vec![u64::MAX]));
+        assert_eq!(check(&KeyPolicy::default(), "ops", sender, &tx), vec![]);
+    }
+
+    #[test]
+    fn test_signer_must_participate() {
+        let sender = SuiAddress::random_for_testing_only();
+        let signer = SuiAddress::random_for_testing_only();
+        let tx = transaction(sender, pay(vec![sender], vec![1]));
+        assert_eq!(
+            check(&KeyPolicy::default(), "ops", signer, &tx),
+            vec![Violation::NotAParticipant { signer }],
+        );
+    }
+
+    #[test]
+    fn test_allowed_clients() {
+        let sender = SuiAddress::random_for_testing_only();
+        let policy = KeyPolicy {
+            allowed_clients: Some(BTreeSet::from(["ops".to_owned()])),
+            ..Default::default()
+        };
+
+        let tx = transaction(sender, pay(vec![sender], vec![1]));
+        assert_eq!(check(&policy, "ops", sender, &tx), vec![]);
+        assert_eq!(
+            check(&policy, "dev", sender, &tx),
+            vec![Violation::ClientNotAllowed {
+                client: "dev".to_owned()
+            }],
+        );
+    }
+
+    #[test]
+    fn test_max_amount() {
+        let sender = SuiAddress::random_for_testing_only();
+        let policy = KeyPolicy {
+            max_amount: Some(100),
+            ..Default::default()
+        };
+
+        let tx = transaction(sender, pay(vec![sender, sender], vec![50, 50]));
+        assert_eq!(check(&policy, "ops", sender, &tx), vec![]);
+
+        let tx = transaction(sender, pay(vec![sender, sender], vec![50, 51]));
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![Violation::AmountExceeded {
+                amount: 101,
+                limit: 100
+            }],
+        );
+
+        // Transferring the whole gas coin has no bound on the amount.
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.pay_all_sui(sender);
+        let tx = transaction(sender, builder.finish());
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![Violation::GasCoinTransferred],
+        );
+    }
+
+    #[test]
+    fn test_max_amount_owned_coin_transferred() {
+        let sender = SuiAddress::random_for_testing_only();
+        let policy = KeyPolicy {
+            max_amount: Some(100),
+            ..Default::default()
+        };
+
+        // Transferring an owned coin directly moves its whole balance.
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder
+            .transfer_object(sender, random_object_ref())
+            .unwrap();
+        let tx = transaction(sender, builder.finish());
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![Violation::UnboundedTransfer],
+        );
+    }
+
+    #[test]
+    fn test_max_amount_merged_coin_transferred() {
+        let sender = SuiAddress::random_for_testing_only();
+        let policy = KeyPolicy {
+            max_amount: Some(100),
+            ..Default::default()
+        };
+
+        // Merging an owned coin into a split coin adds its balance to what is transferred.
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(10u64).unwrap();
+        let coin = builder
+            .obj(ObjectArg::ImmOrOwnedObject(random_object_ref()))
+            .unwrap();
+        let split = builder.command(Command::SplitCoins(Argument::GasCoin, vec![amount]));
+        builder.command(Command::MergeCoins(split, vec![coin]));
+        builder.transfer_arg(sender, split);
+        let tx = transaction(sender, builder.finish());
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![Violation::UnboundedTransfer],
+        );
+
+        // Neither is transferring the coin that owned coins were merged into.
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let coin = builder
+            .obj(ObjectArg::ImmOrOwnedObject(random_object_ref()))
+            .unwrap();
+        let other = builder
+            .obj(ObjectArg::ImmOrOwnedObject(random_object_ref()))
+            .unwrap();
+        builder.command(Command::MergeCoins(coin, vec![other]));
+        builder.transfer_arg(sender, coin);
+        let tx = transaction(sender, builder.finish());
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![Violation::UnboundedTransfer],
+        );
+    }
+
+    #[test]
+    fn test_allowed_recipients() {
+        let sender = SuiAddress::random_for_testing_only();
+        let allowed = SuiAddress::random_for_testing_only();
+        let other = SuiAddress::random_for_testing_only();
+        let policy = KeyPolicy {
+            allowed_recipients: Some(BTreeSet::from([allowed])),
+            ..Default::default()
+        };
+
+        let tx = transaction(sender, pay(vec![allowed], vec![1]));
+        assert_eq!(check(&policy, "ops", sender, &tx), vec![]);
+
+        let tx = transaction(sender, pay(vec![allowed, other], vec![1, 1]));
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![Violation::RecipientNotAllowed { recipient: other }],
+        );
+    }
+
+    #[test]
+    fn test_allowed_packages() {
+        let sender = SuiAddress::random_for_testing_only();
+        let allowed = ObjectID::random();
+        let other = ObjectID::random();
+        let policy = KeyPolicy {
+            allowed_packages: Some(BTreeSet::from([allowed])),
+            ..Default::default()
+        };
+
+        let mut builder = ProgrammableTransactionBuilder::new();
+        for package in [allowed, other, other] {
+            builder.programmable_move_call(
+                package,
+                ident_str!("m").to_owned(),
+                ident_str!("f").to_owned(),
+                vec![],
+                vec![],
+            );
+        }
+        builder.publish_upgradeable(vec![], vec![]);
+
+        let tx = transaction(sender, builder.finish());
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![
+                Violation::PackageNotAllowed { package: other },
+                Violation::PublishNotAllowed,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_move_call_transfer() {
+        let sender = SuiAddress::random_for_testing_only();
+        let attacker = SuiAddress::random_for_testing_only();
+
+        // Splitting and transferring the gas coin by calling a function skips the commands that
+        // the amount and recipient checks look at.
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(1_000_000u64).unwrap();
+        let recipient = builder.pure(attacker).unwrap();
+        builder.programmable_move_call(
+            SUI_FRAMEWORK_PACKAGE_ID,
+            ident_str!("pay").to_owned(),
+            ident_str!("split_and_transfer").to_owned(),
+            vec![GAS::type_tag()],
+            vec![Argument::GasCoin, amount, recipient],
+        );
+        let tx = transaction(sender, builder.finish());
+
+        let policy = KeyPolicy {
+            max_amount: Some(100),
+            allowed_packages: Some(BTreeSet::from([SUI_FRAMEWORK_PACKAGE_ID])),
+            ..Default::default()
+        };
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![Violation::UncheckedMoveCall],
+        );
+
+        let policy = KeyPolicy {
+            allowed_recipients: Some(BTreeSet::from([sender])),
+            ..Default::default()
+        };
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![Violation::UncheckedMoveCall],
+        );
+
+        // Transferring a coin produced by an earlier command is caught as well.
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(10u64).unwrap();
+        let split = builder.command(Command::SplitCoins(Argument::GasCoin, vec![amount]));
+        let recipient = builder.pure(attacker).unwrap();
+        builder.programmable_move_call(
+            SUI_FRAMEWORK_PACKAGE_ID,
+            ident_str!("transfer").to_owned(),
+            ident_str!("public_transfer").to_owned(),
+            vec![GAS::type_tag()],
+            vec![split, recipient],
+        );
+        let tx = transaction(sender, builder.finish());
+        assert_eq!(
+            check(&policy, "ops", sender, &tx),
+            vec![Violation::UncheckedMoveCall],
+        );
+
+        // Calls that only take pure inputs cannot move the signer's coins.
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let value = builder.pure(42u64).unwrap();
+        builder.programmable_move_call(
+            SUI_FRAMEWORK_PACKAGE_ID,
+            ident_str!("m").to_owned(),
+            ident_str!("f").to_owned(),
+            vec![],
+            vec![value],
+        );
+        let tx = transaction(sender, builder.finish());
+        assert_eq!(check(&policy, "ops", sender, &tx), vec![]);
+    }
+}
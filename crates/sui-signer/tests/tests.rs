@@ -0,0 +1,179 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use fastcrypto::encoding::{Base64, Encoding};
+use serde_json::{json, Value};
+use sui_keys::keystore::{AccountKeystore, InMemKeystore, Keystore};
+use sui_signer::config::{ClientConfig, KeyConfig, KeyPolicy, SignerConfig};
+use sui_signer::Signer;
+use sui_types::base_types::{random_object_ref, SuiAddress};
+use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use sui_types::transaction::{TransactionData, TEST_ONLY_GAS_UNIT_FOR_TRANSFER};
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+const TOKEN: &str = "secret";
+
+async fn signer(dir: &TempDir) -> (Router, SuiAddress) {
+    let keystore = Keystore::from(InMemKeystore::new_insecure_for_tests(1));
+    let address = keystore.addresses()[0];
+
+    let config = SignerConfig {
+        listen_address: "127.0.0.1:0".parse().unwrap(),
+        keystore_path: dir.path().join("sui.keystore"),
+        audit_log_path: dir.path().join("audit.jsonl"),
+        clients: vec![ClientConfig {
+            name: "ops".to_owned(),
+            token: TOKEN.to_owned(),
+        }],
+        keys: vec![KeyConfig {
+            address,
+            policy: KeyPolicy {
+                max_amount: Some(100),
+                ..Default::default()
+            },
+        }],
+    };
+
+    let signer = Signer::new(&config, keystore).await.unwrap();
+    (Arc::new(signer).router(), address)
+}
+
+fn pay(sender: SuiAddress, amount: u64) -> String {
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder.pay_sui(vec![sender], vec![amount]).unwrap();
+    let tx = TransactionData::new_programmable(
+        sender,
+        vec![random_object_ref()],
+        builder.finish(),
+        TEST_ONLY_GAS_UNIT_FOR_TRANSFER,
+        1000,
+    );
+
+    Base64::encode(bcs::to_bytes(&tx).unwrap())
+}
+
+async fn post(router: &Router, route: &str, token: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::post(route)
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_rejects_unknown_token() {
+    let dir = TempDir::new().unwrap();
+    let (router, address) = signer(&dir).await;
+    let request = json!({ "address": address, "data": pay(address, 1) });
+
+    let (status, _) = post(&router, "/sign", "wrong", request).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_decode() {
+    let dir = TempDir::new().unwrap();
+    let (router, address) = signer(&dir).await;
+
+    let request = json!({ "address": address, "data": pay(address, 1000) });
+    let (status, response) = post(&router, "/decode", TOKEN, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        response["violations"],
+        json!(["Amount 1000 exceeds the limit of 100"])
+    );
+
+    // Decoding does not sign, so nothing is audited.
+    let audit = std::fs::read_to_string(dir.path().join("audit.jsonl")).unwrap();
+    assert!(audit.is_empty());
+}
+
+#[tokio::test]
+async fn test_sign_enforces_policy_and_audits() {
+    let dir = TempDir::new().unwrap();
+    let (router, address) = signer(&dir).await;
+
+    let request = json!({ "address": address, "data": pay(address, 50) });
+    let (status, response) = post(&router, "/sign", TOKEN, request).await;
+    assert_eq!(status, StatusCode::OK);
+    let signature = response["suiSignature"].as_str().unwrap().to_owned();
+
+    let request = json!({ "address": address, "data": pay(address, 500) });
+    let (status, response) = post(&router, "/sign", TOKEN, request).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(
+        response["violations"],
+        json!(["Amount 500 exceeds the limit of 100"])
+    );
+
+    let audit = std::fs::read_to_string(dir.path().join("audit.jsonl")).unwrap();
+    let entries: Vec<Value> = audit
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["client"], "ops");
+    assert_eq!(entries[0]["outcome"]["result"], "signed");
+    assert_eq!(entries[0]["outcome"]["signature"], signature.as_str());
+    assert_eq!(entries[1]["outcome"]["result"], "denied");
+}
+
+#[tokio::test]
+async fn test_sign_audits_rejected_requests() {
+    let dir = TempDir::new().unwrap();
+    let (router, address) = signer(&dir).await;
+
+    let request = json!({ "address": address, "data": pay(address, 1) });
+    let (status, _) = post(&router, "/sign", "wrong", request).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let request = json!({ "address": address, "data": "not base64!" });
+    let (status, _) = post(&router, "/sign", TOKEN, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let request = json!({ "data": pay(address, 1) });
+    let (status, _) = post(&router, "/sign", TOKEN, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let audit = std::fs::read_to_string(dir.path().join("audit.jsonl")).unwrap();
+    let entries: Vec<Value> = audit
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(entries.len(), 3);
+    assert!(entries
+        .iter()
+        .all(|entry| entry["outcome"]["result"] == "rejected"));
+
+    // Unauthenticated requests are recorded without a client.
+    assert_eq!(entries[0]["client"], Value::Null);
+    assert_eq!(
+        entries[0]["outcome"]["error"],
+        "Missing or invalid bearer token"
+    );
+
+    // Undecodable transactions are recorded against the client and key, but without a digest.
+    assert_eq!(entries[1]["client"], "ops");
+    assert_eq!(entries[1]["address"], json!(address));
+    assert_eq!(entries[1]["digest"], Value::Null);
+
+    // Requests that cannot be parsed are recorded against the client only.
+    assert_eq!(entries[2]["client"], "ops");
+    assert_eq!(entries[2]["address"], Value::Null);
+}
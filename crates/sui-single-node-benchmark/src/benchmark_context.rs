@@ -8,11 +8,13 @@ use crate::single_node::SingleValidator;
 use crate::tx_generator::SharedObjectCreateTxGenerator;
 use crate::tx_generator::{RootObjectCreateTxGenerator, TxGenerator};
 use crate::workload::Workload;
+use futures::future::join_all;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use std::collections::{BTreeMap, HashMap};
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::Duration;
 use sui_config::node::RunWithRange;
 use sui_test_transaction_builder::PublishData;
 use sui_types::base_types::{ObjectID, ObjectRef, SequenceNumber, SuiAddress};
@@ -54,9 +56,12 @@ impl BenchmarkContext {
             batch_create_account_and_gas(num_accounts, gas_object_num_per_account).await;
         assert_eq!(genesis_gas_objects.len() as u64, total);
         let (_, admin_account) = user_accounts.pop_last().unwrap();
+        let senders: Vec<_> = user_accounts.keys().copied().collect();
+        let mut genesis_objects = genesis_gas_objects;
+        genesis_objects.extend(workload.genesis_objects(&senders));
 
         info!("Initializing validator");
-        let validator = SingleValidator::new(&genesis_gas_objects, benchmark_component).await;
+        let validator = SingleValidator::new(&genesis_objects, benchmark_component).await;
 
         Self {
             validator,
@@ -70,6 +75,10 @@ impl BenchmarkContext {
         self.validator.clone()
     }
 
+    pub(crate) fn user_accounts(&self) -> &BTreeMap<SuiAddress, Account> {
+        &self.user_accounts
+    }
+
     pub(crate) async fn publish_package(&mut self, publish_data: PublishData) -> ObjectRef {
         let mut gas_objects = self.admin_account.gas_objects.deref().clone();
         let (package, updated_gas) = self
@@ -197,6 +206,7 @@ impl BenchmarkContext {
         shared_objects
     }
 
+    /// Generate one transaction per user account, in the order of the accounts.
     pub(crate) async fn generate_transactions(
         &self,
         tx_generator: Arc<dyn TxGenerator>,
@@ -206,7 +216,7 @@ impl BenchmarkContext {
             tx_generator.name(),
             self.user_accounts.len()
         );
-        let tasks: FuturesUnordered<_> = self
+        let tasks: Vec<_> = self
             .user_accounts
             .values()
             .map(|account| {
@@ -215,7 +225,7 @@ impl BenchmarkContext {
                 tokio::spawn(async move { tx_generator.generate_tx(account) })
             })
             .collect();
-        let results: Vec<_> = tasks.collect().await;
+        let results = join_all(tasks).await;
         results.into_iter().map(|r| r.unwrap()).collect()
     }
 
@@ -225,7 +235,9 @@ impl BenchmarkContext {
         skip_signing: bool,
     ) -> Vec<CertifiedTransaction> {
         info!("Creating transaction certificates");
-        let tasks: FuturesUnordered<_> = transactions
+        // Keep the order of the transactions, which decides the order in which shared objects
+        // are accessed.
+        let tasks: Vec<_> = transactions
             .into_iter()
             .map(|tx| {
                 let validator = self.validator();
@@ -252,14 +264,17 @@ impl BenchmarkContext {
                 })
             })
             .collect();
-        let results: Vec<_> = tasks.collect().await;
+        let results = join_all(tasks).await;
         results.into_iter().map(|r| r.unwrap()).collect()
     }
 
+    /// Execute `transactions`, as fast as possible unless `submission_offsets` tells when to
+    /// submit each of them relative to the start of the benchmark.
     pub(crate) async fn benchmark_transaction_execution(
         &self,
         transactions: Vec<CertifiedTransaction>,
         print_sample_tx: bool,
+        submission_offsets: Option<Vec<Duration>>,
     ) {
         if print_sample_tx {
             // We must use remove(0) in case there are shared objects and the transactions
@@ -275,10 +290,25 @@ impl BenchmarkContext {
             transactions.len()
         );
 
+        let submission_times: Vec<_> = match submission_offsets {
+            Some(offsets) => {
+                let start = tokio::time::Instant::now();
+                offsets.into_iter().map(|o| Some(start + o)).collect()
+            }
+            None => vec![None; tx_count],
+        };
+        assert_eq!(
+            submission_times.len(),
+            tx_count,
+            "Every transaction must have a submission time"
+        );
         let has_shared_object = transactions.iter().any(|tx| tx.contains_shared_object());
         if has_shared_object {
             // With shared objects, we must execute each transaction in order.
-            for transaction in transactions {
+            for (transaction, submission_time) in transactions.into_iter().zip(submission_times) {
+                if let Some(submission_time) = submission_time {
+                    tokio::time::sleep_until(submission_time).await;
+                }
                 self.validator
                     .execute_certificate(transaction, self.benchmark_component)
                     .await;
@@ -286,10 +316,16 @@ impl BenchmarkContext {
         } else {
             let tasks: FuturesUnordered<_> = transactions
                 .into_iter()
-                .map(|tx| {
+                .zip(submission_times)
+                .map(|(tx, submission_time)| {
                     let validator = self.validator();
                     let component = self.benchmark_component;
-                    tokio::spawn(async move { validator.execute_certificate(tx, component).await })
+                    tokio::spawn(async move {
+                        if let Some(submission_time) = submission_time {
+                            tokio::time::sleep_until(submission_time).await;
+                        }
+                        validator.execute_certificate(tx, component).await
+                    })
                 })
                 .collect();
             let results: Vec<_> = tasks.collect().await;
@@ -28,7 +28,7 @@ pub struct Command {
     pub checkpoint_size: usize,
     #[arg(
         long,
-        help = "Whether to print out a sample transaction and effects that is going to be benchmarked on"
+        help = "Whether to print out a sample transaction and effects that is going to be benchmarked on. Ignored by replay workloads"
     )]
     pub print_sample_tx: bool,
     #[arg(
@@ -144,6 +144,44 @@ pub enum WorkloadKind {
         )]
         manifest_file: PathBuf,
     },
+    Replay {
+        #[arg(
+            long,
+            required_unless_present = "remote_store_url",
+            conflicts_with = "remote_store_url",
+            help = "Directory containing the checkpoints to replay, where checkpoint n is stored in \
+            the file `<n>.chk`, such as a local ingestion directory. At most `tx-count` \
+            transactions are replayed."
+        )]
+        checkpoint_dir: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "URL of a checkpoint archive to download the checkpoints to replay from, which \
+            stores checkpoint n as `<n>.chk`, such as https://checkpoints.mainnet.sui.io"
+        )]
+        remote_store_url: Option<String>,
+        #[arg(long, help = "First checkpoint to replay")]
+        first_checkpoint: u64,
+        #[arg(long, help = "Last checkpoint to replay")]
+        last_checkpoint: u64,
+        #[arg(
+            long,
+            help = "Submit transactions this many times faster than they were recorded, based on \
+            their checkpoint timestamps. If not set, transactions are submitted as fast as possible. \
+            Only applies to components that execute transactions through the validator.",
+            value_parser = parse_speedup
+        )]
+        speedup: Option<f64>,
+    },
+}
+
+fn parse_speedup(s: &str) -> Result<f64, String> {
+    let speedup: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    if speedup.is_finite() && speedup > 0.0 {
+        Ok(speedup)
+    } else {
+        Err(format!("must be a positive number, got {speedup}"))
+    }
 }
 
 impl WorkloadKind {
@@ -152,6 +190,51 @@ impl WorkloadKind {
             // Each transaction will always have 1 gas object, plus the number of owned objects that will be transferred.
             WorkloadKind::PTB { num_transfers, .. } => *num_transfers + 1,
             WorkloadKind::Publish { .. } => 1,
+            // Each recorded transaction is replayed from its own account, with a single gas object.
+            WorkloadKind::Replay { .. } => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(args: &[&str]) -> Result<Command, clap::Error> {
+        let mut argv = vec![
+            "sui-single-node-benchmark",
+            "replay",
+            "--first-checkpoint",
+            "0",
+            "--last-checkpoint",
+            "1",
+        ];
+        argv.extend(args);
+        Command::try_parse_from(argv)
+    }
+
+    #[test]
+    fn test_replay_source() {
+        assert!(replay(&["--checkpoint-dir", "checkpoints"]).is_ok());
+        assert!(replay(&["--remote-store-url", "https://checkpoints.mainnet.sui.io"]).is_ok());
+        assert!(replay(&[]).is_err());
+        assert!(replay(&[
+            "--checkpoint-dir",
+            "checkpoints",
+            "--remote-store-url",
+            "https://checkpoints.mainnet.sui.io",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_replay_speedup() {
+        assert!(replay(&["--checkpoint-dir", "checkpoints", "--speedup", "0.5"]).is_ok());
+        for speedup in ["0", "-1", "inf", "NaN", "fast"] {
+            assert!(
+                replay(&["--checkpoint-dir", "checkpoints", "--speedup", speedup]).is_err(),
+                "{speedup}"
+            );
         }
     }
 }
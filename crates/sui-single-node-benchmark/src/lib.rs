@@ -9,6 +9,7 @@ pub(crate) mod benchmark_context;
 pub mod command;
 pub(crate) mod mock_account;
 pub(crate) mod mock_storage;
+pub(crate) mod recorded_checkpoints;
 pub(crate) mod single_node;
 pub(crate) mod tx_generator;
 pub mod workload;
@@ -24,6 +25,9 @@ pub async fn run_benchmark(
     print_sample_tx: bool,
     skip_signing: bool,
 ) {
+    // The transactions of fixed workloads are replayed in order, and each of them is only executed
+    // once, so none of them can be set aside and executed as a sample.
+    let print_sample_tx = print_sample_tx && !workload.has_fixed_transactions();
     let mut ctx = BenchmarkContext::new(workload.clone(), component, print_sample_tx).await;
     let tx_generator = workload.create_tx_generator(&mut ctx).await;
     let transactions = ctx.generate_transactions(tx_generator).await;
@@ -47,8 +51,12 @@ pub async fn run_benchmark(
                 .await;
         }
         _ => {
-            ctx.benchmark_transaction_execution(transactions, print_sample_tx)
-                .await;
+            ctx.benchmark_transaction_execution(
+                transactions,
+                print_sample_tx,
+                workload.submission_offsets(),
+            )
+            .await;
         }
     }
 }
@@ -7,7 +7,7 @@ use sui_single_node_benchmark::run_benchmark;
 use sui_single_node_benchmark::workload::Workload;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> anyhow::Result<()> {
     let _guard = telemetry_subscribers::TelemetryConfig::new()
         .with_log_level("off,sui_single_node_benchmark=info")
         .with_env()
@@ -15,7 +15,7 @@ async fn main() {
 
     let args = Command::parse();
     run_benchmark(
-        Workload::new(args.tx_count, args.workload),
+        Workload::new(args.tx_count, args.workload).await?,
         args.component,
         args.checkpoint_size,
         args.print_sample_tx,
@@ -28,4 +28,5 @@ async fn main() {
         println!("You can ctrl-c to exit once you see trace data appearing in grafana");
         tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
     }
+    Ok(())
 }
@@ -0,0 +1,618 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{ensure, Context};
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
+use sui_data_ingestion_core::create_remote_store_client;
+use sui_storage::blob::Blob;
+use sui_types::base_types::{ObjectID, SequenceNumber, SuiAddress};
+use sui_types::effects::TransactionEffectsAPI;
+use sui_types::full_checkpoint_content::{CheckpointData, CheckpointTransaction};
+use sui_types::object::{Object, Owner};
+use sui_types::transaction::{
+    CallArg, Command, InputObjectKind, ObjectArg, ProgrammableTransaction, TransactionDataAPI,
+    TransactionKind,
+};
+use sui_types::{
+    is_system_package, SUI_AUTHENTICATOR_STATE_OBJECT_ID, SUI_BRIDGE_OBJECT_ID,
+    SUI_CLOCK_OBJECT_ID, SUI_DENY_LIST_OBJECT_ID, SUI_RANDOMNESS_STATE_OBJECT_ID,
+    SUI_SYSTEM_STATE_OBJECT_ID,
+};
+use tracing::info;
+
+/// Shared objects that the benchmark genesis creates itself, and hence are never seeded.
+const BUILT_IN_OBJECTS: [ObjectID; 6] = [
+    SUI_SYSTEM_STATE_OBJECT_ID,
+    SUI_CLOCK_OBJECT_ID,
+    SUI_AUTHENTICATOR_STATE_OBJECT_ID,
+    SUI_RANDOMNESS_STATE_OBJECT_ID,
+    SUI_BRIDGE_OBJECT_ID,
+    SUI_DENY_LIST_OBJECT_ID,
+];
+
+/// How many checkpoints are read at once.
+const READ_CONCURRENCY: usize = 10;
+
+/// Timeout of each request to a remote checkpoint store.
+const REMOTE_TIMEOUT_SECS: u64 = 60;
+
+/// Every recorded version of every object in the loaded checkpoints.
+type ObjectVersions<'a> = HashMap<ObjectID, BTreeMap<SequenceNumber, &'a Object>>;
+
+pub(crate) struct RecordedTransaction {
+    /// Timestamp of the checkpoint the transaction was recorded in.
+    pub timestamp_ms: u64,
+    pub transaction: ProgrammableTransaction,
+    pub gas_budget: u64,
+    pub gas_price: u64,
+}
+
+/// User transactions recorded in a range of checkpoints, along with the objects that must be in
+/// genesis to replay them from benchmark accounts.
+///
+/// Every replayed transaction is sent by its own benchmark account, which owns the gas coin and the
+/// owned objects it uses. An owned object can therefore only be used by a single replayed
+/// transaction, while shared objects are seeded at the version the first transaction using them
+/// saw, so that the shared-object contention of the recorded transactions is replayed as is.
+pub(crate) struct RecordedCheckpoints {
+    transactions: Vec<RecordedTransaction>,
+    /// Packages, shared, immutable and child objects.
+    objects: BTreeMap<ObjectID, Object>,
+    /// Owned objects, along with the index of the transaction using them.
+    owned_objects: BTreeMap<ObjectID, (usize, Object)>,
+}
+
+/// Where the checkpoints to replay are read from. Both store checkpoint `n` as `<n>.chk`.
+pub(crate) enum CheckpointSource {
+    /// A local directory, such as a local ingestion directory.
+    Dir(PathBuf),
+    /// A remote object store, such as a checkpoint archive.
+    Remote {
+        url: String,
+        store: Box<dyn ObjectStore>,
+    },
+}
+
+impl CheckpointSource {
+    pub(crate) fn remote(url: String) -> anyhow::Result<Self> {
+        let store = create_remote_store_client(url.clone(), vec![], REMOTE_TIMEOUT_SECS)
+            .with_context(|| format!("Failed to create a client for {url}"))?;
+        Ok(Self::Remote { url, store })
+    }
+
+    async fn read(&self, sequence_number: u64) -> anyhow::Result<CheckpointData> {
+        let file = format!("{sequence_number}.chk");
+        let (bytes, location) = match self {
+            Self::Dir(dir) => {
+                let path = dir.join(&file);
+                let bytes = std::fs::read(&path).with_context(|| {
+                    format!(
+                        "Failed to read checkpoint {sequence_number} from {}",
+                        path.display()
+                    )
+                })?;
+                (bytes, path.display().to_string())
+            }
+            Self::Remote { url, store } => {
+                let context = || format!("Failed to read checkpoint {sequence_number} from {url}");
+                let response = store
+                    .get(&ObjectPath::from(file))
+                    .await
+                    .with_context(context)?;
+                let bytes = response.bytes().await.with_context(context)?;
+                (
+                    bytes.to_vec(),
+                    format!("checkpoint {sequence_number} from {url}"),
+                )
+            }
+        };
+        Blob::from_bytes::<CheckpointData>(&bytes)
+            .with_context(|| format!("Failed to decode {location}"))
+    }
+}
+
+impl std::fmt::Display for CheckpointSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dir(dir) => write!(f, "{}", dir.display()),
+            Self::Remote { url, .. } => write!(f, "{url}"),
+        }
+    }
+}
+
+impl RecordedCheckpoints {
+    /// Load checkpoints `first..=last` from `source`. Stops after `max_transactions`
+    /// transactions.
+    pub(crate) async fn load(
+        source: &CheckpointSource,
+        first: u64,
+        last: u64,
+        max_transactions: u64,
+    ) -> anyhow::Result<Self> {
+        info!("Loading checkpoints {} to {} from {}", first, last, source);
+        let checkpoints: Vec<_> = futures::stream::iter(first..=last)
+            .map(|sequence_number| source.read(sequence_number))
+            .buffered(READ_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        // Transactions only record the objects they modify, so the packages, immutable objects
+        // and shared objects they just read are looked up among all the recorded objects.
+        let mut versions = ObjectVersions::new();
+        for transaction in checkpoints.iter().flat_map(|c| &c.transactions) {
+            for object in transaction
+                .input_objects
+                .iter()
+                .chain(&transaction.output_objects)
+            {
+                versions
+                    .entry(object.id())
+                    .or_default()
+                    .insert(object.version(), object);
+            }
+        }
+
+        let mut recorded = Self {
+            transactions: vec![],
+            objects: BTreeMap::new(),
+            owned_objects: BTreeMap::new(),
+        };
+        let mut skipped: BTreeMap<&'static str, u64> = BTreeMap::new();
+        'checkpoints: for checkpoint in &checkpoints {
+            for transaction in &checkpoint.transactions {
+                if recorded.transactions.len() as u64 >= max_transactions {
+                    break 'checkpoints;
+                }
+                let timestamp_ms = checkpoint.checkpoint_summary.timestamp_ms;
+                if let Err(reason) = recorded.add(transaction, timestamp_ms, &versions) {
+                    *skipped.entry(reason).or_default() += 1;
+                }
+            }
+        }
+        for (reason, count) in skipped {
+            info!("Skipped {} transactions that {}", count, reason);
+        }
+        info!(
+            "Loaded {} transactions to replay, seeding {} objects",
+            recorded.transactions.len(),
+            recorded.objects.len() + recorded.owned_objects.len()
+        );
+        ensure!(
+            !recorded.transactions.is_empty(),
+            "No transaction to replay in checkpoints {first} to {last}"
+        );
+        Ok(recorded)
+    }
+
+    fn add(
+        &mut self,
+        transaction: &CheckpointTransaction,
+        timestamp_ms: u64,
+        versions: &ObjectVersions,
+    ) -> Result<(), &'static str> {
+        let data = transaction.transaction.data().transaction_data();
+        let TransactionKind::ProgrammableTransaction(pt) = data.kind() else {
+            return Err("are not programmable transactions");
+        };
+        if !transaction.effects.status().is_ok() {
+            return Err("failed when recorded");
+        }
+        // Published packages would get a different ID when replayed, so they are seeded instead.
+        if pt
+            .commands
+            .iter()
+            .any(|c| matches!(c, Command::Publish(..) | Command::Upgrade(..)))
+        {
+            return Err("publish or upgrade packages");
+        }
+        if pt
+            .inputs
+            .iter()
+            .any(|i| matches!(i, CallArg::Object(ObjectArg::Receiving(_))))
+        {
+            return Err("receive objects");
+        }
+
+        let modified: HashMap<_, _> = transaction
+            .input_objects
+            .iter()
+            .map(|object| (object.id(), object))
+            .collect();
+        let mut objects = BTreeMap::new();
+        let mut owned_objects = vec![];
+        for input in pt.input_objects().map_err(|_| "have invalid inputs")? {
+            match input {
+                InputObjectKind::MovePackage(id) => self.add_package(id, versions, &mut objects)?,
+                InputObjectKind::ImmOrOwnedMoveObject((id, version, _)) => {
+                    let object = modified
+                        .get(&id)
+                        .or_else(|| versions.get(&id).and_then(|v| v.get(&version)))
+                        .ok_or("use objects missing from the checkpoints")?;
+                    if object.is_address_owned() {
+                        if self.owned_objects.contains_key(&id) {
+                            return Err("use owned objects used by an earlier transaction");
+                        }
+                        owned_objects.push((*object).clone());
+                    } else {
+                        objects.insert(id, (*object).clone());
+                    }
+                }
+                InputObjectKind::SharedMoveObject { id, .. } => {
+                    if BUILT_IN_OBJECTS.contains(&id) || self.objects.contains_key(&id) {
+                        continue;
+                    }
+                    let object = modified
+                        .get(&id)
+                        .or_else(|| versions.get(&id).and_then(|v| v.values().next()))
+                        .ok_or("use shared objects missing from the checkpoints")?;
+                    objects.insert(id, (*object).clone());
+                }
+            }
+        }
+        // Dynamic fields are loaded at runtime, so only those the transaction modified are known.
+        for object in &transaction.input_objects {
+            if matches!(object.owner, Owner::ObjectOwner(_)) {
+                objects.entry(object.id()).or_insert_with(|| object.clone());
+            }
+        }
+
+        let index = self.transactions.len();
+        self.owned_objects.extend(
+            owned_objects
+                .into_iter()
+                .map(|object| (object.id(), (index, object))),
+        );
+        for (id, object) in objects {
+            self.objects.entry(id).or_insert(object);
+        }
+        self.transactions.push(RecordedTransaction {
+            timestamp_ms,
+            transaction: pt.clone(),
+            gas_budget: data.gas_budget(),
+            gas_price: data.gas_price(),
+        });
+        Ok(())
+    }
+
+    /// Add package `id` and the packages it links to.
+    fn add_package(
+        &self,
+        id: ObjectID,
+        versions: &ObjectVersions,
+        objects: &mut BTreeMap<ObjectID, Object>,
+    ) -> Result<(), &'static str> {
+        if is_system_package(id) || self.objects.contains_key(&id) || objects.contains_key(&id) {
+            return Ok(());
+        }
+        let object = versions
+            .get(&id)
+            .and_then(|v| v.values().next())
+            .filter(|object| object.is_package())
+            .ok_or("use packages missing from the checkpoints")?;
+        objects.insert(id, (*object).clone());
+        let package = object.data.try_as_package().unwrap();
+        for upgrade in package.linkage_table().values() {
+            self.add_package(upgrade.upgraded_id, versions, objects)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub(crate) fn transaction(&self, index: usize) -> &RecordedTransaction {
+        &self.transactions[index]
+    }
+
+    /// The objects to seed into genesis, where `senders[i]` replays the `i`-th transaction and
+    /// hence owns the owned objects it uses.
+    pub(crate) fn genesis_objects(&self, senders: &[SuiAddress]) -> Vec<Object> {
+        assert_eq!(senders.len(), self.transactions.len());
+        self.objects
+            .values()
+            .cloned()
+            .chain(
+                self.owned_objects
+                    .values()
+                    .map(|(index, object)| owned_by(object, senders[*index])),
+            )
+            .collect()
+    }
+
+    /// The `index`-th transaction, rewritten to be sent by `sender`.
+    pub(crate) fn programmable_transaction(
+        &self,
+        index: usize,
+        sender: SuiAddress,
+    ) -> ProgrammableTransaction {
+        let mut pt = self.transactions[index].transaction.clone();
+        // The owned objects are seeded with `sender` as their owner, which changes their digest.
+        for input in pt.inputs.iter_mut() {
+            if let CallArg::Object(ObjectArg::ImmOrOwnedObject(object_ref)) = input {
+                if let Some((_, object)) = self.owned_objects.get(&object_ref.0) {
+                    *object_ref = owned_by(object, sender).compute_object_reference();
+                }
+            }
+        }
+        pt
+    }
+
+    /// When each transaction should be submitted, relative to the first one, to replay them
+    /// `speedup` times faster than they were recorded.
+    pub(crate) fn submission_offsets(&self, speedup: f64) -> Vec<Duration> {
+        let start_ms = self.transactions[0].timestamp_ms;
+        self.transactions
+            .iter()
+            .map(|t| Duration::from_millis(t.timestamp_ms - start_ms).div_f64(speedup))
+            .collect()
+    }
+}
+
+fn owned_by(object: &Object, sender: SuiAddress) -> Object {
+    let mut object = object.clone();
+    object.transfer(sender);
+    object
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::local::LocalFileSystem;
+    use std::path::Path;
+    use sui_types::base_types::random_object_ref;
+    use sui_types::effects::TransactionEffects;
+    use sui_types::execution_status::{ExecutionFailureStatus, ExecutionStatus};
+    use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+    use sui_types::transaction::{Transaction, TransactionData};
+
+    fn owned(id: ObjectID, version: u64, owner: SuiAddress) -> Object {
+        Object::with_id_owner_version_for_testing(id, SequenceNumber::from_u64(version), owner)
+    }
+
+    fn shared(id: ObjectID, version: u64) -> Object {
+        let mut object = owned(id, version, SuiAddress::ZERO);
+        object.owner = Owner::Shared {
+            initial_shared_version: SequenceNumber::from_u64(1),
+        };
+        object
+    }
+
+    fn shared_arg(id: ObjectID) -> ObjectArg {
+        ObjectArg::SharedObject {
+            id,
+            initial_shared_version: SequenceNumber::from_u64(1),
+            mutable: true,
+        }
+    }
+
+    /// A transaction transferring `inputs`, with `commands` run first.
+    fn programmable(inputs: Vec<ObjectArg>, commands: Vec<Command>) -> TransactionKind {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        for command in commands {
+            builder.command(command);
+        }
+        let args = inputs
+            .into_iter()
+            .map(|input| builder.obj(input).unwrap())
+            .collect();
+        builder.transfer_args(SuiAddress::ZERO, args);
+        TransactionKind::ProgrammableTransaction(builder.finish())
+    }
+
+    fn checkpoint_transaction(
+        kind: TransactionKind,
+        input_objects: Vec<Object>,
+    ) -> CheckpointTransaction {
+        let data = TransactionData::new(kind, SuiAddress::ZERO, random_object_ref(), 1000, 1);
+        CheckpointTransaction {
+            transaction: Transaction::from_data(data, vec![]),
+            effects: TransactionEffects::default(),
+            events: None,
+            input_objects,
+            output_objects: vec![],
+        }
+    }
+
+    fn object_versions(objects: &[Object]) -> ObjectVersions {
+        let mut versions = ObjectVersions::new();
+        for object in objects {
+            versions
+                .entry(object.id())
+                .or_default()
+                .insert(object.version(), object);
+        }
+        versions
+    }
+
+    fn empty() -> RecordedCheckpoints {
+        RecordedCheckpoints {
+            transactions: vec![],
+            objects: BTreeMap::new(),
+            owned_objects: BTreeMap::new(),
+        }
+    }
+
+    fn owned_ref(object: &Object) -> ObjectArg {
+        ObjectArg::ImmOrOwnedObject(object.compute_object_reference())
+    }
+
+    #[test]
+    fn test_skipped_transactions() {
+        let coin = owned(ObjectID::random(), 3, SuiAddress::random_for_testing_only());
+        let versions = ObjectVersions::new();
+        let mut recorded = empty();
+
+        let end_of_epoch =
+            checkpoint_transaction(TransactionKind::EndOfEpochTransaction(vec![]), vec![]);
+        assert_eq!(
+            recorded.add(&end_of_epoch, 0, &versions),
+            Err("are not programmable transactions")
+        );
+
+        let mut failed = checkpoint_transaction(programmable(vec![], vec![]), vec![]);
+        *failed.effects.status_mut_for_testing() =
+            ExecutionStatus::new_failure(ExecutionFailureStatus::InsufficientGas, None);
+        assert_eq!(
+            recorded.add(&failed, 0, &versions),
+            Err("failed when recorded")
+        );
+
+        let publish = checkpoint_transaction(
+            programmable(vec![], vec![Command::Publish(vec![], vec![])]),
+            vec![],
+        );
+        assert_eq!(
+            recorded.add(&publish, 0, &versions),
+            Err("publish or upgrade packages")
+        );
+
+        let receive = checkpoint_transaction(
+            programmable(vec![ObjectArg::Receiving(random_object_ref())], vec![]),
+            vec![],
+        );
+        assert_eq!(recorded.add(&receive, 0, &versions), Err("receive objects"));
+
+        let missing_owned =
+            checkpoint_transaction(programmable(vec![owned_ref(&coin)], vec![]), vec![]);
+        assert_eq!(
+            recorded.add(&missing_owned, 0, &versions),
+            Err("use objects missing from the checkpoints")
+        );
+
+        let missing_shared = checkpoint_transaction(
+            programmable(vec![shared_arg(ObjectID::random())], vec![]),
+            vec![],
+        );
+        assert_eq!(
+            recorded.add(&missing_shared, 0, &versions),
+            Err("use shared objects missing from the checkpoints")
+        );
+
+        let first = checkpoint_transaction(
+            programmable(vec![owned_ref(&coin)], vec![]),
+            vec![coin.clone()],
+        );
+        assert_eq!(recorded.add(&first, 0, &versions), Ok(()));
+        assert_eq!(
+            recorded.add(&first, 0, &versions),
+            Err("use owned objects used by an earlier transaction")
+        );
+        assert_eq!(recorded.len(), 1);
+    }
+
+    #[test]
+    fn test_shared_objects_seeded_at_first_version() {
+        let id = ObjectID::random();
+        let recorded_versions = vec![shared(id, 7), shared(id, 3)];
+        let versions = object_versions(&recorded_versions);
+        let mut recorded = empty();
+
+        // Read-only shared inputs are not among the transaction's input objects.
+        let reads = checkpoint_transaction(programmable(vec![shared_arg(id)], vec![]), vec![]);
+        let writes = checkpoint_transaction(
+            programmable(vec![shared_arg(id)], vec![]),
+            vec![shared(id, 7)],
+        );
+        let clock = checkpoint_transaction(
+            programmable(vec![shared_arg(SUI_CLOCK_OBJECT_ID)], vec![]),
+            vec![],
+        );
+        for transaction in [&reads, &writes, &clock] {
+            assert_eq!(recorded.add(transaction, 0, &versions), Ok(()));
+        }
+
+        let senders: Vec<_> = (0..3)
+            .map(|_| SuiAddress::random_for_testing_only())
+            .collect();
+        let genesis = recorded.genesis_objects(&senders);
+        assert_eq!(genesis.len(), 1);
+        assert_eq!(genesis[0].id(), id);
+        assert_eq!(genesis[0].version(), SequenceNumber::from_u64(3));
+    }
+
+    #[test]
+    fn test_owned_inputs_rewritten_for_sender() {
+        let coin = owned(ObjectID::random(), 3, SuiAddress::random_for_testing_only());
+        let transaction = checkpoint_transaction(
+            programmable(vec![owned_ref(&coin)], vec![]),
+            vec![coin.clone()],
+        );
+        let mut recorded = empty();
+        recorded
+            .add(&transaction, 0, &ObjectVersions::new())
+            .unwrap();
+
+        let sender = SuiAddress::random_for_testing_only();
+        let genesis = recorded.genesis_objects(&[sender]);
+        assert_eq!(genesis.len(), 1);
+        assert_eq!(genesis[0].owner, Owner::AddressOwner(sender));
+
+        let pt = recorded.programmable_transaction(0, sender);
+        let expected = genesis[0].compute_object_reference();
+        assert_ne!(expected, coin.compute_object_reference());
+        assert_eq!(
+            pt.inputs,
+            vec![CallArg::Object(ObjectArg::ImmOrOwnedObject(expected))]
+        );
+        // The recorded transaction itself is left untouched.
+        assert_eq!(
+            recorded.transaction(0).transaction.inputs,
+            vec![CallArg::Object(owned_ref(&coin))]
+        );
+    }
+
+    #[test]
+    fn test_submission_offsets() {
+        let mut recorded = empty();
+        for timestamp_ms in [1000, 1000, 3000, 5000] {
+            recorded.transactions.push(RecordedTransaction {
+                timestamp_ms,
+                transaction: ProgrammableTransactionBuilder::new().finish(),
+                gas_budget: 1000,
+                gas_price: 1,
+            });
+        }
+        assert_eq!(
+            recorded.submission_offsets(2.0),
+            vec![
+                Duration::ZERO,
+                Duration::ZERO,
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_checkpoint() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+        let error = RecordedCheckpoints::load(&CheckpointSource::Dir(dir), 0, 0, u64::MAX)
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().starts_with("Failed to read checkpoint 0"));
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_remote_checkpoint() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+        let source = CheckpointSource::Remote {
+            url: "file://tests".to_string(),
+            store: Box::new(LocalFileSystem::new_with_prefix(dir).unwrap()),
+        };
+        let error = RecordedCheckpoints::load(&source, 0, 0, u64::MAX)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Failed to read checkpoint 0 from file://tests"
+        );
+    }
+}
@@ -4,12 +4,14 @@
 use crate::mock_account::Account;
 pub use move_tx_generator::MoveTxGenerator;
 pub use package_publish_tx_generator::PackagePublishTxGenerator;
+pub use replay_tx_generator::ReplayTxGenerator;
 pub use root_object_create_tx_generator::RootObjectCreateTxGenerator;
 pub use shared_object_create_tx_generator::SharedObjectCreateTxGenerator;
 use sui_types::transaction::Transaction;
 
 mod move_tx_generator;
 mod package_publish_tx_generator;
+mod replay_tx_generator;
 mod root_object_create_tx_generator;
 mod shared_object_create_tx_generator;
 
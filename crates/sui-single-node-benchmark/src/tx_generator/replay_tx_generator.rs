@@ -0,0 +1,62 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::benchmark_context::BenchmarkContext;
+use crate::mock_account::Account;
+use crate::recorded_checkpoints::RecordedCheckpoints;
+use crate::tx_generator::TxGenerator;
+use std::collections::HashMap;
+use std::sync::Arc;
+use sui_test_transaction_builder::TestTransactionBuilder;
+use sui_types::base_types::SuiAddress;
+use sui_types::transaction::Transaction;
+
+pub struct ReplayTxGenerator {
+    checkpoints: Arc<RecordedCheckpoints>,
+    /// The index of the recorded transaction each account replays.
+    transaction_indices: HashMap<SuiAddress, usize>,
+    reference_gas_price: u64,
+    max_tx_gas: u64,
+}
+
+impl ReplayTxGenerator {
+    pub fn new(ctx: &BenchmarkContext, checkpoints: Arc<RecordedCheckpoints>) -> Self {
+        let epoch_store = ctx.validator().get_epoch_store().clone();
+        // Must match the order in which `Workload::genesis_objects` assigns owned objects.
+        let transaction_indices = ctx
+            .user_accounts()
+            .keys()
+            .enumerate()
+            .map(|(index, sender)| (*sender, index))
+            .collect();
+        Self {
+            checkpoints,
+            transaction_indices,
+            reference_gas_price: epoch_store.reference_gas_price(),
+            max_tx_gas: epoch_store.protocol_config().max_tx_gas(),
+        }
+    }
+}
+
+impl TxGenerator for ReplayTxGenerator {
+    fn generate_tx(&self, account: Account) -> Transaction {
+        let index = self.transaction_indices[&account.sender];
+        let recorded = self.checkpoints.transaction(index);
+        // Pay at least the reference gas price, keeping the budget in gas units unchanged.
+        let gas_price = recorded.gas_price.max(self.reference_gas_price);
+        let gas_budget = (recorded.gas_budget as u128 * gas_price as u128
+            / recorded.gas_price.max(1) as u128)
+            .min(self.max_tx_gas as u128) as u64;
+        TestTransactionBuilder::new(account.sender, account.gas_objects[0], gas_price)
+            .with_gas_budget(gas_budget)
+            .programmable(
+                self.checkpoints
+                    .programmable_transaction(index, account.sender),
+            )
+            .build_and_sign(account.keypair.as_ref())
+    }
+
+    fn name(&self) -> &'static str {
+        "Recorded Checkpoint Replay Transaction Generator"
+    }
+}
@@ -3,27 +3,96 @@
 
 use crate::benchmark_context::BenchmarkContext;
 use crate::command::WorkloadKind;
-use crate::tx_generator::{MoveTxGenerator, PackagePublishTxGenerator, TxGenerator};
+use crate::recorded_checkpoints::{CheckpointSource, RecordedCheckpoints};
+use crate::tx_generator::{
+    MoveTxGenerator, PackagePublishTxGenerator, ReplayTxGenerator, TxGenerator,
+};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use sui_test_transaction_builder::PublishData;
+use sui_types::base_types::SuiAddress;
+use sui_types::object::Object;
 
 #[derive(Clone)]
 pub struct Workload {
     pub tx_count: u64,
     pub workload_kind: WorkloadKind,
+    recorded_checkpoints: Option<Arc<RecordedCheckpoints>>,
 }
 
 impl Workload {
-    pub fn new(tx_count: u64, workload_kind: WorkloadKind) -> Self {
-        Self {
+    /// Fails if the workload replays checkpoints that cannot be loaded.
+    pub async fn new(tx_count: u64, workload_kind: WorkloadKind) -> anyhow::Result<Self> {
+        let recorded_checkpoints = match &workload_kind {
+            WorkloadKind::Replay {
+                checkpoint_dir,
+                remote_store_url,
+                first_checkpoint,
+                last_checkpoint,
+                ..
+            } => {
+                let source = match (checkpoint_dir, remote_store_url) {
+                    (Some(dir), _) => CheckpointSource::Dir(dir.clone()),
+                    (None, Some(url)) => CheckpointSource::remote(url.clone())?,
+                    (None, None) => {
+                        anyhow::bail!("No checkpoint directory or remote store to replay from")
+                    }
+                };
+                Some(Arc::new(
+                    RecordedCheckpoints::load(
+                        &source,
+                        *first_checkpoint,
+                        *last_checkpoint,
+                        tx_count,
+                    )
+                    .await?,
+                ))
+            }
+            _ => None,
+        };
+        Ok(Self {
             tx_count,
             workload_kind,
-        }
+            recorded_checkpoints,
+        })
     }
 
     pub(crate) fn num_accounts(&self) -> u64 {
-        self.tx_count
+        match &self.recorded_checkpoints {
+            Some(recorded_checkpoints) => recorded_checkpoints.len() as u64,
+            None => self.tx_count,
+        }
+    }
+
+    /// Whether the workload's transactions are fixed in advance, in which case there is no extra
+    /// sample transaction to print.
+    pub(crate) fn has_fixed_transactions(&self) -> bool {
+        self.recorded_checkpoints.is_some()
+    }
+
+    /// Objects other than gas that must be in genesis, given the accounts that will send the
+    /// workload's transactions.
+    pub(crate) fn genesis_objects(&self, senders: &[SuiAddress]) -> Vec<Object> {
+        match &self.recorded_checkpoints {
+            Some(recorded_checkpoints) => recorded_checkpoints.genesis_objects(senders),
+            None => vec![],
+        }
+    }
+
+    /// When each transaction should be submitted relative to the first one, if the workload
+    /// paces its transactions.
+    pub(crate) fn submission_offsets(&self) -> Option<Vec<Duration>> {
+        match (&self.workload_kind, &self.recorded_checkpoints) {
+            (
+                WorkloadKind::Replay {
+                    speedup: Some(speedup),
+                    ..
+                },
+                Some(recorded_checkpoints),
+            ) => Some(recorded_checkpoints.submission_offsets(*speedup)),
+            _ => None,
+        }
     }
 
     pub(crate) fn gas_object_num_per_account(&self) -> u64 {
@@ -69,6 +138,10 @@ impl Workload {
             WorkloadKind::Publish {
                 manifest_file: manifest_path,
             } => Arc::new(PackagePublishTxGenerator::new(ctx, manifest_path.clone()).await),
+            WorkloadKind::Replay { .. } => Arc::new(ReplayTxGenerator::new(
+                ctx,
+                self.recorded_checkpoints.clone().unwrap(),
+            )),
         }
     }
 }
@@ -25,7 +25,9 @@ async fn benchmark_non_move_transactions_smoke_test() {
                         nft_size: 528,
                         use_batch_mint: false,
                     },
-                ),
+                )
+                .await
+                .unwrap(),
                 component,
                 1000,
                 false,
@@ -53,7 +55,9 @@ async fn benchmark_move_transactions_smoke_test() {
                         nft_size: 528,
                         use_batch_mint: false,
                     },
-                ),
+                )
+                .await
+                .unwrap(),
                 component,
                 1000,
                 false,
@@ -81,7 +85,9 @@ async fn benchmark_batch_mint_smoke_test() {
                         nft_size: 256,
                         use_batch_mint: true,
                     },
-                ),
+                )
+                .await
+                .unwrap(),
                 component,
                 1000,
                 false,
@@ -109,7 +115,9 @@ async fn benchmark_publish_from_source() {
                 WorkloadKind::Publish {
                     manifest_file: path.clone(),
                 },
-            ),
+            )
+            .await
+            .unwrap(),
             component,
             1000,
             false,
@@ -136,7 +144,9 @@ async fn benchmark_publish_from_bytecode() {
                 WorkloadKind::Publish {
                     manifest_file: path.clone(),
                 },
-            ),
+            )
+            .await
+            .unwrap(),
             component,
             1000,
             false,
@@ -0,0 +1,207 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::types::{Cursor, DeadLetter, Subscription, SubscriptionFilter, SubscriptionId};
+use crate::{ReplayError, WebhookService};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+
+/// Routes of the admin API:
+/// - `POST /subscriptions`: registers a webhook, returning the secret its deliveries are signed
+///   with.
+/// - `GET /subscriptions`, `GET /subscriptions/:id`: subscriptions along with their cursor.
+/// - `DELETE /subscriptions/:id`: removes a subscription and its pending deliveries.
+/// - `POST /subscriptions/:id/replay`: delivers the matches of checkpoints from a given one again.
+/// - `GET /subscriptions/:id/dead-letters`: deliveries that failed too many times.
+/// - `POST /subscriptions/:id/dead-letters/redrive`: retries the dead letters.
+pub fn router(service: WebhookService) -> Router {
+    Router::new()
+        .route(
+            "/subscriptions",
+            post(create_subscription).get(list_subscriptions),
+        )
+        .route(
+            "/subscriptions/:id",
+            get(get_subscription).delete(delete_subscription),
+        )
+        .route("/subscriptions/:id/replay", post(replay))
+        .route("/subscriptions/:id/dead-letters", get(dead_letters))
+        .route(
+            "/subscriptions/:id/dead-letters/redrive",
+            post(redrive_dead_letters),
+        )
+        .with_state(service)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSubscriptionRequest {
+    pub url: String,
+    pub filter: SubscriptionFilter,
+    /// Checkpoint to deliver matches from, which may be in the past.
+    pub start_checkpoint: Option<CheckpointSequenceNumber>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayRequest {
+    pub from_checkpoint: CheckpointSequenceNumber,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayResponse {
+    pub from_checkpoint: CheckpointSequenceNumber,
+    pub to_checkpoint: CheckpointSequenceNumber,
+}
+
+/// A subscription, without its secret, and where its subscriber is.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionStatus {
+    pub id: SubscriptionId,
+    pub url: String,
+    pub filter: SubscriptionFilter,
+    pub start_checkpoint: CheckpointSequenceNumber,
+    pub cursor: Cursor,
+    pub pending_deliveries: usize,
+    pub dead_letters: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedriveResponse {
+    pub redriven: usize,
+}
+
+pub enum AdminError {
+    NotFound(SubscriptionId),
+    BadRequest(anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for AdminError {
+    fn from(e: anyhow::Error) -> Self {
+        if e.downcast_ref::<ReplayError>().is_some() {
+            AdminError::BadRequest(e)
+        } else {
+            AdminError::Internal(e)
+        }
+    }
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        match self {
+            AdminError::NotFound(id) => (
+                StatusCode::NOT_FOUND,
+                format!("Subscription {id} not found"),
+            )
+                .into_response(),
+            AdminError::BadRequest(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+            AdminError::Internal(e) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            }
+        }
+    }
+}
+
+fn status(
+    service: &WebhookService,
+    subscription: Subscription,
+) -> Result<SubscriptionStatus, AdminError> {
+    let store = service.store();
+    Ok(SubscriptionStatus {
+        cursor: store.cursor(subscription.id)?,
+        pending_deliveries: store.pending_deliveries(subscription.id)?,
+        dead_letters: store.dead_letters(subscription.id)?.len(),
+        id: subscription.id,
+        url: subscription.url,
+        filter: subscription.filter,
+        start_checkpoint: subscription.start_checkpoint,
+    })
+}
+
+fn subscription(service: &WebhookService, id: SubscriptionId) -> Result<Subscription, AdminError> {
+    service
+        .store()
+        .subscription(id)
+        .ok_or(AdminError::NotFound(id))
+}
+
+async fn create_subscription(
+    State(service): State<WebhookService>,
+    Json(request): Json<CreateSubscriptionRequest>,
+) -> Result<(StatusCode, Json<Subscription>), AdminError> {
+    reqwest::Url::parse(&request.url)
+        .map_err(|e| AdminError::BadRequest(anyhow::anyhow!("Invalid url: {e}")))?;
+    let subscription = service.subscribe(request.url, request.filter, request.start_checkpoint)?;
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+async fn list_subscriptions(
+    State(service): State<WebhookService>,
+) -> Result<Json<Vec<SubscriptionStatus>>, AdminError> {
+    let statuses = service
+        .store()
+        .subscriptions()
+        .into_iter()
+        .map(|subscription| status(&service, subscription))
+        .collect::<Result<_, _>>()?;
+    Ok(Json(statuses))
+}
+
+async fn get_subscription(
+    State(service): State<WebhookService>,
+    Path(id): Path<SubscriptionId>,
+) -> Result<Json<SubscriptionStatus>, AdminError> {
+    let subscription = subscription(&service, id)?;
+    Ok(Json(status(&service, subscription)?))
+}
+
+async fn delete_subscription(
+    State(service): State<WebhookService>,
+    Path(id): Path<SubscriptionId>,
+) -> Result<StatusCode, AdminError> {
+    if service.unsubscribe(id)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AdminError::NotFound(id))
+    }
+}
+
+async fn replay(
+    State(service): State<WebhookService>,
+    Path(id): Path<SubscriptionId>,
+    Json(request): Json<ReplayRequest>,
+) -> Result<Json<ReplayResponse>, AdminError> {
+    subscription(&service, id)?;
+    let to_checkpoint = service.replay(id, request.from_checkpoint)?;
+    Ok(Json(ReplayResponse {
+        from_checkpoint: request.from_checkpoint,
+        to_checkpoint,
+    }))
+}
+
+async fn dead_letters(
+    State(service): State<WebhookService>,
+    Path(id): Path<SubscriptionId>,
+) -> Result<Json<Vec<DeadLetter>>, AdminError> {
+    subscription(&service, id)?;
+    Ok(Json(service.store().dead_letters(id)?))
+}
+
+async fn redrive_dead_letters(
+    State(service): State<WebhookService>,
+    Path(id): Path<SubscriptionId>,
+) -> Result<Json<RedriveResponse>, AdminError> {
+    subscription(&service, id)?;
+    Ok(Json(RedriveResponse {
+        redriven: service.store().redrive_dead_letters(id)?,
+    }))
+}
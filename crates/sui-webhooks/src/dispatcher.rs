@@ -0,0 +1,226 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::metrics::WebhookMetrics;
+use crate::store::WebhookStore;
+use crate::types::{delivery_id, DeliveryKey, Subscription, SubscriptionId};
+use anyhow::{anyhow, Result};
+use fastcrypto::hmac::{hmac_sha3_256, HmacKey};
+use fastcrypto::traits::ToFromBytes;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+pub const ID_HEADER: &str = "X-Sui-Webhook-Id";
+pub const TIMESTAMP_HEADER: &str = "X-Sui-Webhook-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Sui-Webhook-Signature";
+
+/// How long a dispatcher without pending deliveries waits before checking again, in case it missed
+/// a notification.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Attempts after which a delivery is dead-lettered.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// How long to wait after the `attempts`-th failed attempt.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(31);
+        self.initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff)
+    }
+}
+
+/// Signs the body of a delivery sent at `timestamp_ms`, as HMAC-SHA3-256 of
+/// `<timestamp_ms>.<body>` keyed by the subscription secret, hex encoded.
+pub fn sign(secret: &str, timestamp_ms: u64, body: &str) -> String {
+    let key = HmacKey::from_bytes(secret.as_bytes())
+        .expect("HMAC key can be of any length and from_bytes should always succeed");
+    let message = format!("{timestamp_ms}.{body}");
+    hex::encode(hmac_sha3_256(&key, message.as_bytes()).to_vec())
+}
+
+/// Runs one delivery loop per subscription, which delivers the subscription's deliveries in order,
+/// one at a time.
+#[derive(Clone)]
+pub struct Dispatcher {
+    store: WebhookStore,
+    client: reqwest::Client,
+    retry_policy: RetryPolicy,
+    metrics: Arc<WebhookMetrics>,
+    tasks: Arc<Mutex<HashMap<SubscriptionId, JoinHandle<()>>>>,
+}
+
+impl Dispatcher {
+    pub fn new(
+        store: WebhookStore,
+        request_timeout: Duration,
+        retry_policy: RetryPolicy,
+        metrics: Arc<WebhookMetrics>,
+    ) -> Result<Self> {
+        Ok(Self {
+            store,
+            client: reqwest::Client::builder()
+                .timeout(request_timeout)
+                .build()?,
+            retry_policy,
+            metrics,
+            tasks: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn start(&self, subscription: Subscription) {
+        let dispatcher = self.clone();
+        let id = subscription.id;
+        let handle = tokio::spawn(async move { dispatcher.run(subscription).await });
+        if let Some(previous) = self.tasks.lock().unwrap().insert(id, handle) {
+            previous.abort();
+        }
+    }
+
+    pub fn stop(&self, id: SubscriptionId) {
+        if let Some(handle) = self.tasks.lock().unwrap().remove(&id) {
+            handle.abort();
+        }
+    }
+
+    async fn run(&self, subscription: Subscription) {
+        info!("Starting deliveries to subscription {}", subscription.id);
+        let mut attempts = match self.store.cursor(subscription.id) {
+            Ok(cursor) => cursor.attempts,
+            Err(e) => {
+                error!(
+                    "Failed to read cursor of subscription {}: {e}",
+                    subscription.id
+                );
+                0
+            }
+        };
+        loop {
+            let notified = self.store.new_deliveries.notified();
+            let next = match self.store.next_delivery(subscription.id) {
+                Ok(next) => next,
+                Err(e) => {
+                    error!(
+                        "Failed to read deliveries of subscription {}: {e}",
+                        subscription.id
+                    );
+                    None
+                }
+            };
+            let Some((key, body)) = next else {
+                let _ = tokio::time::timeout(IDLE_POLL_INTERVAL, notified).await;
+                continue;
+            };
+
+            match self.deliver(&subscription, &key, &body).await {
+                Ok(()) => {
+                    self.metrics
+                        .delivery_attempts
+                        .with_label_values(&["success"])
+                        .inc();
+                    attempts = 0;
+                    if let Err(e) = self.store.acknowledge(key) {
+                        error!("Failed to acknowledge delivery {}: {e}", delivery_id(&key));
+                    }
+                }
+                Err(e) => {
+                    self.metrics
+                        .delivery_attempts
+                        .with_label_values(&["failure"])
+                        .inc();
+                    attempts += 1;
+                    let error = e.to_string();
+                    warn!(
+                        "Attempt {attempts} to deliver {} failed: {error}",
+                        delivery_id(&key)
+                    );
+                    if attempts >= self.retry_policy.max_attempts {
+                        self.metrics.dead_letters.inc();
+                        if let Err(e) = self.store.dead_letter(key, &body, attempts, error) {
+                            error!("Failed to dead-letter delivery {}: {e}", delivery_id(&key));
+                        }
+                        attempts = 0;
+                    } else {
+                        if let Err(e) = self.store.record_failure(subscription.id, attempts, error)
+                        {
+                            error!(
+                                "Failed to record failed delivery {}: {e}",
+                                delivery_id(&key)
+                            );
+                        }
+                        tokio::time::sleep(self.retry_policy.backoff(attempts)).await;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn deliver(
+        &self,
+        subscription: &Subscription,
+        key: &DeliveryKey,
+        body: &str,
+    ) -> Result<()> {
+        let _timer = self.metrics.delivery_latency.start_timer();
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let response = self
+            .client
+            .post(&subscription.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(ID_HEADER, delivery_id(key))
+            .header(TIMESTAMP_HEADER, timestamp_ms.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                sign(&subscription.secret, timestamp_ms, body),
+            )
+            .body(body.to_owned())
+            .send()
+            .await?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(anyhow!("Subscriber responded with {}", response.status()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(5), Duration::from_secs(16));
+        assert_eq!(policy.backoff(7), Duration::from_secs(60));
+        assert_eq!(policy.backoff(100), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_signature_covers_timestamp_and_body() {
+        let signature = sign("secret", 1, "{}");
+        assert_eq!(signature.len(), 64);
+        assert_eq!(signature, sign("secret", 1, "{}"));
+        assert_ne!(signature, sign("secret", 2, "{}"));
+        assert_ne!(signature, sign("secret", 1, "{ }"));
+        assert_ne!(signature, sign("other", 1, "{}"));
+    }
+}
@@ -0,0 +1,456 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A service delivering the events and transactions matching registered filters to webhooks.
+//!
+//! Checkpoints are matched against subscriptions as they are ingested, and the resulting
+//! deliveries are stored before being POSTed, in order, to each subscriber. Deliveries are retried
+//! with exponential backoff until acknowledged with a 2xx response, or dead-lettered after too many
+//! attempts, so that neither restarts nor unavailable subscribers lose deliveries.
+
+use crate::dispatcher::{Dispatcher, RetryPolicy};
+use crate::metrics::WebhookMetrics;
+use crate::store::{ReplayProgressStore, WebhookProgressStore, WebhookStore};
+use crate::types::{Subscription, SubscriptionFilter, SubscriptionId};
+use crate::worker::{WebhookPackageStore, WebhookReducer, WebhookWorker};
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use prometheus::Registry;
+use rand::RngCore;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use sui_data_ingestion_core::{DataIngestionMetrics, IndexerExecutor, ReaderOptions, WorkerPool};
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use tempfile::TempDir;
+use tokio::sync::oneshot;
+use tracing::{error, info};
+
+pub mod admin;
+pub mod dispatcher;
+pub mod metrics;
+pub mod store;
+pub mod types;
+pub mod worker;
+
+const INGESTION_TASK: &str = "webhooks";
+
+#[derive(Parser, Clone, Debug)]
+#[clap(
+    name = "Sui Webhooks",
+    about = "Delivers the events and transactions matching registered filters to webhooks.",
+    rename_all = "kebab-case"
+)]
+pub struct WebhookConfig {
+    /// Path of the database storing subscriptions and pending deliveries.
+    #[clap(long)]
+    pub db_path: PathBuf,
+    /// Remote store to read checkpoints from, e.g. https://checkpoints.mainnet.sui.io.
+    #[clap(long)]
+    pub remote_store_url: Option<String>,
+    /// Local directory to read checkpoints from.
+    #[clap(long)]
+    pub checkpoints_dir: Option<PathBuf>,
+    /// Full node REST API, to fetch the packages of events published before the first ingested
+    /// checkpoint.
+    #[clap(long)]
+    pub rest_url: String,
+    /// Checkpoint to start ingesting from, the first time the service runs.
+    #[clap(long, default_value_t = 0)]
+    pub start_checkpoint: CheckpointSequenceNumber,
+    /// Number of checkpoints matched concurrently.
+    #[clap(long, default_value_t = 5)]
+    pub concurrency: usize,
+    /// Address of the admin API, which should not be exposed publicly.
+    #[clap(long, default_value = "127.0.0.1:9190")]
+    pub admin_address: SocketAddr,
+    #[clap(long, default_value = "0.0.0.0:9184")]
+    pub metrics_address: SocketAddr,
+    /// Attempts after which a delivery is dead-lettered.
+    #[clap(long, default_value_t = 10)]
+    pub max_attempts: u32,
+    #[clap(long, default_value_t = 1_000)]
+    pub initial_backoff_ms: u64,
+    #[clap(long, default_value_t = 600_000)]
+    pub max_backoff_ms: u64,
+    #[clap(long, default_value_t = 10_000)]
+    pub request_timeout_ms: u64,
+}
+
+/// Shared state of the ingestion pipeline, the dispatcher and the admin API.
+#[derive(Clone)]
+pub struct WebhookService {
+    config: WebhookConfig,
+    store: WebhookStore,
+    dispatcher: Dispatcher,
+    metrics: Arc<WebhookMetrics>,
+    /// Directory checkpoints are read from, before falling back to the remote store.
+    checkpoints_path: PathBuf,
+    /// Holds the directory checkpoints are read from when none is configured, removing it once the
+    /// service is dropped.
+    _checkpoints_tempdir: Option<Arc<TempDir>>,
+}
+
+/// Replays of checkpoints that cannot be read, as opposed to failures reading them.
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    #[error("Checkpoint {0} was not ingested yet")]
+    NotIngested(CheckpointSequenceNumber),
+
+    #[error(
+        "Checkpoint {0} is no longer in the checkpoints directory, and there is no remote store to \
+         read it from"
+    )]
+    Unavailable(CheckpointSequenceNumber),
+}
+
+impl WebhookService {
+    pub fn new(config: WebhookConfig, registry: &Registry) -> Result<Self> {
+        if config.remote_store_url.is_none() && config.checkpoints_dir.is_none() {
+            bail!("One of --remote-store-url or --checkpoints-dir is required");
+        }
+        let (checkpoints_path, checkpoints_tempdir) = match &config.checkpoints_dir {
+            Some(dir) => (dir.clone(), None),
+            None => {
+                let dir = tempfile::tempdir()
+                    .map_err(|e| anyhow!("Failed to create checkpoints directory: {e}"))?;
+                (dir.path().to_path_buf(), Some(Arc::new(dir)))
+            }
+        };
+        let store = WebhookStore::open(&config.db_path)?;
+        let metrics = Arc::new(WebhookMetrics::new(registry));
+        let retry_policy = RetryPolicy {
+            max_attempts: config.max_attempts,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+        };
+        let dispatcher = Dispatcher::new(
+            store.clone(),
+            Duration::from_millis(config.request_timeout_ms),
+            retry_policy,
+            metrics.clone(),
+        )?;
+        Ok(Self {
+            config,
+            store,
+            dispatcher,
+            metrics,
+            checkpoints_path,
+            _checkpoints_tempdir: checkpoints_tempdir,
+        })
+    }
+
+    pub fn store(&self) -> &WebhookStore {
+        &self.store
+    }
+
+    /// Starts delivering to existing subscriptions, serving the admin API, and ingesting
+    /// checkpoints until the ingestion stops.
+    pub async fn run(self, registry: &Registry) -> Result<()> {
+        for subscription in self.store.subscriptions() {
+            self.dispatcher.start(subscription);
+        }
+
+        let listener = tokio::net::TcpListener::bind(self.config.admin_address).await?;
+        info!("Admin API listening on {}", self.config.admin_address);
+        let router = admin::router(self.clone());
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                error!("Admin API failed: {e}");
+            }
+        });
+
+        let mut executor = IndexerExecutor::new(
+            WebhookProgressStore::new(self.store.clone(), self.config.start_checkpoint),
+            1,
+            DataIngestionMetrics::new(registry),
+        );
+        executor
+            .register(WorkerPool::new_with_reducer(
+                self.worker(None),
+                INGESTION_TASK.to_string(),
+                self.config.concurrency,
+                Box::new(WebhookReducer::new(self.store.clone())),
+            ))
+            .await?;
+        let (_exit_sender, exit_receiver) = oneshot::channel();
+        executor
+            .run(
+                self.checkpoints_path.clone(),
+                self.config.remote_store_url.clone(),
+                vec![],
+                ReaderOptions::default(),
+                exit_receiver,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Registers a webhook. Checkpoints are matched from `start_checkpoint` if set, replaying those
+    /// that were already ingested or are being matched, or from the next checkpoint that is not
+    /// being matched yet otherwise. Fails without registering the webhook if the checkpoints to
+    /// replay cannot be read.
+    pub fn subscribe(
+        &self,
+        url: String,
+        filter: SubscriptionFilter,
+        start_checkpoint: Option<CheckpointSequenceNumber>,
+    ) -> Result<Subscription> {
+        let next_checkpoint = self.next_checkpoint()?;
+        if let Some(from_checkpoint) = start_checkpoint.filter(|cp| *cp < next_checkpoint) {
+            self.ensure_readable(from_checkpoint, next_checkpoint - 1)?;
+        }
+
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let subscription = self.store.create_subscription(
+            url,
+            hex::encode(secret),
+            filter,
+            start_checkpoint
+                .unwrap_or(next_checkpoint)
+                .max(next_checkpoint),
+        )?;
+        info!(
+            "Created subscription {} to {} from checkpoint {}",
+            subscription.id, subscription.url, subscription.start_checkpoint
+        );
+        self.dispatcher.start(subscription.clone());
+        // The subscription takes effect after the checkpoints that are already being matched,
+        // which are replayed for it along with the ingested ones. They are not garbage collected
+        // before they are committed, so they are still readable.
+        if let Some(from_checkpoint) =
+            start_checkpoint.filter(|cp| *cp < subscription.start_checkpoint)
+        {
+            self.spawn_replay(
+                subscription.id,
+                from_checkpoint,
+                subscription.start_checkpoint - 1,
+            );
+        }
+        Ok(subscription)
+    }
+
+    pub fn unsubscribe(&self, id: SubscriptionId) -> Result<bool> {
+        self.dispatcher.stop(id);
+        self.store.delete_subscription(id)
+    }
+
+    /// Matches checkpoints from `from_checkpoint` up to the last ingested one against a
+    /// subscription again, and delivers their matches, ahead of the subscription's newer pending
+    /// deliveries. Returns the last replayed checkpoint.
+    pub fn replay(
+        &self,
+        id: SubscriptionId,
+        from_checkpoint: CheckpointSequenceNumber,
+    ) -> Result<CheckpointSequenceNumber> {
+        if self.store.subscription(id).is_none() {
+            bail!("Subscription {id} not found");
+        }
+        let next_checkpoint = self.next_checkpoint()?;
+        if from_checkpoint >= next_checkpoint {
+            return Err(ReplayError::NotIngested(from_checkpoint).into());
+        }
+        let last_checkpoint = next_checkpoint - 1;
+        self.ensure_readable(from_checkpoint, last_checkpoint)?;
+        self.spawn_replay(id, from_checkpoint, last_checkpoint);
+        Ok(last_checkpoint)
+    }
+
+    /// Replays checkpoints `from_checkpoint` to `last_checkpoint` for a subscription in the
+    /// background.
+    fn spawn_replay(
+        &self,
+        id: SubscriptionId,
+        from_checkpoint: CheckpointSequenceNumber,
+        last_checkpoint: CheckpointSequenceNumber,
+    ) {
+        let service = self.clone();
+        info!("Replaying checkpoints {from_checkpoint} to {last_checkpoint} for subscription {id}");
+        tokio::spawn(async move {
+            match service
+                .run_replay(id, from_checkpoint, last_checkpoint)
+                .await
+            {
+                Ok(reached) => {
+                    info!("Replayed checkpoints up to {reached} for subscription {id}")
+                }
+                Err(e) => error!("Failed to replay checkpoints for subscription {id}: {e}"),
+            }
+        });
+    }
+
+    /// Matches checkpoints `from_checkpoint` to `last_checkpoint` against a subscription, until
+    /// the matches of all of them are enqueued. Returns the last checkpoint whose matches were
+    /// enqueued.
+    async fn run_replay(
+        &self,
+        id: SubscriptionId,
+        from_checkpoint: CheckpointSequenceNumber,
+        last_checkpoint: CheckpointSequenceNumber,
+    ) -> Result<CheckpointSequenceNumber> {
+        let task_name = format!("replay-{id}");
+        let (progress_store, mut watermark) = ReplayProgressStore::new(from_checkpoint);
+        let mut executor = IndexerExecutor::new(
+            progress_store,
+            1,
+            DataIngestionMetrics::new(&Registry::new()),
+        );
+        executor
+            .register(WorkerPool::new_with_reducer(
+                self.worker(Some((id, last_checkpoint))),
+                task_name.clone(),
+                self.config.concurrency,
+                Box::new(WebhookReducer::new(self.store.clone())),
+            ))
+            .await?;
+
+        // The executor stops as soon as it reads a checkpoint past its upper limit, dropping the
+        // checkpoints that are still being matched. So the worker ignores the checkpoints after the
+        // last one instead, and the executor is only stopped once the reducer has committed it.
+        let (exit_sender, exit_receiver) = oneshot::channel();
+        tokio::spawn(async move {
+            // Fails if the executor stopped on its own, dropping the progress store.
+            let _ = watermark.wait_for(|cp| *cp > last_checkpoint).await;
+            let _ = exit_sender.send(());
+        });
+        let reader_options = ReaderOptions {
+            // Checkpoints are still needed by the main ingestion or other replays.
+            gc_checkpoint_files: false,
+            ..Default::default()
+        };
+        let progress = executor
+            .run(
+                self.checkpoints_path.clone(),
+                self.config.remote_store_url.clone(),
+                vec![],
+                reader_options,
+                exit_receiver,
+            )
+            .await?;
+
+        // The watermark is the next checkpoint to match.
+        let watermark = progress.get(&task_name).copied().unwrap_or(from_checkpoint);
+        if watermark <= last_checkpoint {
+            bail!("Replay stopped before checkpoint {watermark}");
+        }
+        Ok(watermark - 1)
+    }
+
+    /// The next checkpoint the main ingestion will match.
+    fn next_checkpoint(&self) -> Result<CheckpointSequenceNumber> {
+        Ok(self
+            .store
+            .watermark(INGESTION_TASK)?
+            .unwrap_or(self.config.start_checkpoint))
+    }
+
+    fn worker(&self, replay: Option<(SubscriptionId, CheckpointSequenceNumber)>) -> WebhookWorker {
+        WebhookWorker::new(
+            self.store.clone(),
+            WebhookPackageStore::new(self.store.clone(), &self.config.rest_url),
+            replay,
+            self.metrics.clone(),
+        )
+    }
+
+    /// Without a remote store, checkpoints can only be replayed from the checkpoints directory,
+    /// which ingestion garbage collects, so check they are still there rather than waiting for them
+    /// forever.
+    fn ensure_readable(
+        &self,
+        from_checkpoint: CheckpointSequenceNumber,
+        to_checkpoint: CheckpointSequenceNumber,
+    ) -> Result<()> {
+        if self.config.remote_store_url.is_some() {
+            return Ok(());
+        }
+        match (from_checkpoint..=to_checkpoint)
+            .find(|cp| !checkpoint_file(&self.checkpoints_path, *cp).exists())
+        {
+            Some(missing) => Err(ReplayError::Unavailable(missing).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The file a checkpoint is read from in the checkpoints directory.
+fn checkpoint_file(dir: &Path, checkpoint: CheckpointSequenceNumber) -> PathBuf {
+    dir.join(format!("{checkpoint}.chk"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_json_rpc_types::TransactionFilter;
+    use sui_types::base_types::SuiAddress;
+
+    fn service(dir: &Path, remote_store_url: Option<&str>) -> WebhookService {
+        let mut args = vec![
+            "sui-webhooks".to_string(),
+            format!("--db-path={}", dir.join("db").display()),
+            format!("--checkpoints-dir={}", dir.join("checkpoints").display()),
+            "--rest-url=http://localhost:9000".to_string(),
+            "--start-checkpoint=5".to_string(),
+        ];
+        if let Some(url) = remote_store_url {
+            args.push(format!("--remote-store-url={url}"));
+        }
+        std::fs::create_dir_all(dir.join("checkpoints")).unwrap();
+        WebhookService::new(WebhookConfig::parse_from(args), &Registry::new()).unwrap()
+    }
+
+    fn filter() -> SubscriptionFilter {
+        SubscriptionFilter::Transaction(TransactionFilter::FromAddress(SuiAddress::ZERO))
+    }
+
+    fn replay_error(result: Result<impl std::fmt::Debug>) -> ReplayError {
+        result.unwrap_err().downcast().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replay_garbage_collected_checkpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path(), None);
+        let id = service
+            .subscribe("http://localhost/hook".to_string(), filter(), None)
+            .unwrap()
+            .id;
+
+        assert!(matches!(
+            replay_error(service.replay(id, 5)),
+            ReplayError::NotIngested(5)
+        ));
+        assert!(matches!(
+            replay_error(service.replay(id, 2)),
+            ReplayError::Unavailable(2)
+        ));
+
+        // Subscribing from a checkpoint that cannot be replayed does not register the webhook.
+        assert!(matches!(
+            replay_error(service.subscribe("http://localhost/hook".to_string(), filter(), Some(2))),
+            ReplayError::Unavailable(2)
+        ));
+        assert_eq!(service.store().subscriptions().len(), 1);
+
+        // Only some of the checkpoints are left in the directory.
+        for cp in 3..5 {
+            std::fs::write(checkpoint_file(&service.checkpoints_path, cp), b"").unwrap();
+        }
+        assert!(matches!(
+            replay_error(service.replay(id, 2)),
+            ReplayError::Unavailable(2)
+        ));
+        assert!(service.ensure_readable(3, 4).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_replay_from_remote_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path(), Some("http://localhost:9001"));
+
+        // Checkpoints missing from the directory can still be read from the remote store.
+        assert!(service.ensure_readable(0, 4).is_ok());
+    }
+}
@@ -0,0 +1,21 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Parser;
+use prometheus::Registry;
+use sui_webhooks::{WebhookConfig, WebhookService};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let _guard = telemetry_subscribers::TelemetryConfig::new()
+        .with_env()
+        .init();
+    let config = WebhookConfig::parse();
+
+    let registry_service = mysten_metrics::start_prometheus_server(config.metrics_address);
+    let registry: Registry = registry_service.default_registry();
+    mysten_metrics::init_metrics(&registry);
+
+    WebhookService::new(config, &registry)?.run(&registry).await
+}
@@ -0,0 +1,54 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use prometheus::{
+    register_histogram_with_registry, register_int_counter_vec_with_registry,
+    register_int_counter_with_registry, Histogram, IntCounter, IntCounterVec, Registry,
+};
+
+#[derive(Clone)]
+pub struct WebhookMetrics {
+    pub matches: IntCounter,
+    pub event_decode_failures: IntCounter,
+    pub delivery_attempts: IntCounterVec,
+    pub dead_letters: IntCounter,
+    pub delivery_latency: Histogram,
+}
+
+impl WebhookMetrics {
+    pub fn new(registry: &Registry) -> Self {
+        Self {
+            matches: register_int_counter_with_registry!(
+                "webhook_matches",
+                "Number of deliveries produced by matching checkpoints against subscriptions.",
+                registry,
+            )
+            .unwrap(),
+            event_decode_failures: register_int_counter_with_registry!(
+                "webhook_event_decode_failures",
+                "Number of matched events that could not be decoded.",
+                registry,
+            )
+            .unwrap(),
+            delivery_attempts: register_int_counter_vec_with_registry!(
+                "webhook_delivery_attempts",
+                "Number of attempts to deliver to subscribers, by result.",
+                &["result"],
+                registry,
+            )
+            .unwrap(),
+            dead_letters: register_int_counter_with_registry!(
+                "webhook_dead_letters",
+                "Number of deliveries dead-lettered after too many failed attempts.",
+                registry,
+            )
+            .unwrap(),
+            delivery_latency: register_histogram_with_registry!(
+                "webhook_delivery_latency",
+                "Latency of delivery attempts, in seconds.",
+                registry,
+            )
+            .unwrap(),
+        }
+    }
+}
@@ -0,0 +1,569 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::types::{
+    Cursor, DeadLetter, DeliveryKey, Subscription, SubscriptionFilter, SubscriptionId,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use sui_data_ingestion_core::ProgressStore;
+use sui_types::base_types::ObjectID;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use sui_types::object::Object;
+use tokio::sync::{watch, Notify};
+use typed_store::rocks::{DBMap, MetricConf};
+use typed_store::traits::{TableSummary, TypedStoreDebug};
+use typed_store::{DBMapUtils, Map};
+
+const NEXT_SUBSCRIPTION_ID: &str = "next_subscription_id";
+
+/// Subscriptions, deliveries and dead letters are stored as JSON, as filters and payloads only
+/// support human readable serialization.
+#[derive(DBMapUtils)]
+pub struct WebhookTables {
+    subscriptions: DBMap<SubscriptionId, String>,
+    cursors: DBMap<SubscriptionId, Cursor>,
+    /// Deliveries waiting to be acknowledged by their subscriber, in delivery order.
+    deliveries: DBMap<DeliveryKey, String>,
+    dead_letters: DBMap<DeliveryKey, String>,
+    /// Ingestion progress, by task name, and counters.
+    watermarks: DBMap<String, u64>,
+    /// Packages, to resolve the layout of events.
+    pub(crate) packages: DBMap<ObjectID, Object>,
+}
+
+/// Durable state of the webhook service.
+#[derive(Clone)]
+pub struct WebhookStore {
+    pub(crate) tables: Arc<WebhookTables>,
+    /// Subscriptions, cached as they are matched against every checkpoint. Holding the lock also
+    /// serializes the creation and deletion of subscriptions with matching and enqueuing.
+    subscriptions: Arc<RwLock<BTreeMap<SubscriptionId, Subscription>>>,
+    /// The checkpoint after the highest one whose subscriptions were read for matching, which new
+    /// subscriptions take effect from at the earliest.
+    next_unmatched: Arc<AtomicU64>,
+    /// Notified when new deliveries are enqueued.
+    pub(crate) new_deliveries: Arc<Notify>,
+}
+
+impl WebhookStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let tables = Arc::new(WebhookTables::open_tables_read_write(
+            path.to_path_buf(),
+            MetricConf::new("webhooks"),
+            None,
+            None,
+        ));
+        let subscriptions = tables
+            .subscriptions
+            .safe_iter()
+            .map(|entry| {
+                let (id, subscription) = entry?;
+                Ok((id, serde_json::from_str(&subscription)?))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            tables,
+            subscriptions: Arc::new(RwLock::new(subscriptions)),
+            next_unmatched: Arc::new(AtomicU64::new(0)),
+            new_deliveries: Arc::new(Notify::new()),
+        })
+    }
+
+    /// Creates a subscription taking effect from `start_checkpoint`, or from the first checkpoint
+    /// whose subscriptions were not read for matching yet if that is later. Checkpoints are matched
+    /// concurrently, so some after the ingestion watermark may already have been matched without
+    /// the new subscription.
+    pub fn create_subscription(
+        &self,
+        url: String,
+        secret: String,
+        filter: SubscriptionFilter,
+        start_checkpoint: CheckpointSequenceNumber,
+    ) -> Result<Subscription> {
+        let mut subscriptions = self.subscriptions.write().unwrap();
+        let start_checkpoint = start_checkpoint.max(self.next_unmatched.load(Ordering::SeqCst));
+        let id = self
+            .tables
+            .watermarks
+            .get(&NEXT_SUBSCRIPTION_ID.to_string())?
+            .unwrap_or(0);
+        let subscription = Subscription {
+            id,
+            url,
+            secret,
+            filter,
+            start_checkpoint,
+        };
+        let mut batch = self.tables.subscriptions.batch();
+        batch.insert_batch(
+            &self.tables.subscriptions,
+            [(id, serde_json::to_string(&subscription)?)],
+        )?;
+        batch.insert_batch(&self.tables.cursors, [(id, Cursor::default())])?;
+        batch.insert_batch(
+            &self.tables.watermarks,
+            [(NEXT_SUBSCRIPTION_ID.to_string(), id + 1)],
+        )?;
+        batch.write()?;
+        subscriptions.insert(id, subscription.clone());
+        Ok(subscription)
+    }
+
+    /// Removes a subscription, along with its pending deliveries and dead letters.
+    pub fn delete_subscription(&self, id: SubscriptionId) -> Result<bool> {
+        // Deliveries cannot be enqueued while the lock is held, so none are left behind.
+        let mut subscriptions = self.subscriptions.write().unwrap();
+        if !subscriptions.contains_key(&id) {
+            return Ok(false);
+        }
+        let range = (id, 0, 0, 0)..=(id, u64::MAX, u64::MAX, u64::MAX);
+        let deliveries = self
+            .tables
+            .deliveries
+            .safe_range_iter(range.clone())
+            .map(|entry| entry.map(|(key, _)| key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let dead_letters = self
+            .tables
+            .dead_letters
+            .safe_range_iter(range)
+            .map(|entry| entry.map(|(key, _)| key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut batch = self.tables.subscriptions.batch();
+        batch.delete_batch(&self.tables.subscriptions, [id])?;
+        batch.delete_batch(&self.tables.cursors, [id])?;
+        batch.delete_batch(&self.tables.deliveries, deliveries)?;
+        batch.delete_batch(&self.tables.dead_letters, dead_letters)?;
+        batch.write()?;
+        subscriptions.remove(&id);
+        Ok(true)
+    }
+
+    pub fn subscription(&self, id: SubscriptionId) -> Option<Subscription> {
+        self.subscriptions.read().unwrap().get(&id).cloned()
+    }
+
+    pub fn subscriptions(&self) -> Vec<Subscription> {
+        self.subscriptions
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// The subscriptions a checkpoint is matched against, recording that it is being matched so
+    /// that subscriptions created from now on only take effect after it.
+    pub fn subscriptions_for_checkpoint(
+        &self,
+        checkpoint: CheckpointSequenceNumber,
+    ) -> Vec<Subscription> {
+        let subscriptions = self.subscriptions.read().unwrap();
+        self.next_unmatched
+            .fetch_max(checkpoint + 1, Ordering::SeqCst);
+        subscriptions
+            .values()
+            .filter(|s| s.start_checkpoint <= checkpoint)
+            .cloned()
+            .collect()
+    }
+
+    pub fn cursor(&self, id: SubscriptionId) -> Result<Cursor> {
+        Ok(self.tables.cursors.get(&id)?.unwrap_or_default())
+    }
+
+    /// Enqueues deliveries, dropping those of subscriptions deleted in the meantime. The lock on
+    /// subscriptions is held until the deliveries are written, so that a subscription cannot be
+    /// deleted in between, leaving deliveries behind that belong to no subscription.
+    pub fn enqueue(&self, deliveries: Vec<(DeliveryKey, String)>) -> Result<()> {
+        let subscriptions = self.subscriptions.read().unwrap();
+        let deliveries: Vec<_> = deliveries
+            .into_iter()
+            .filter(|(key, _)| subscriptions.contains_key(&key.0))
+            .collect();
+        if deliveries.is_empty() {
+            return Ok(());
+        }
+        let mut batch = self.tables.deliveries.batch();
+        batch.insert_batch(&self.tables.deliveries, deliveries)?;
+        batch.write()?;
+        drop(subscriptions);
+        self.new_deliveries.notify_waiters();
+        Ok(())
+    }
+
+    /// The next delivery of a subscription, as its key and its JSON serialization.
+    pub fn next_delivery(&self, id: SubscriptionId) -> Result<Option<(DeliveryKey, String)>> {
+        Ok(self
+            .tables
+            .deliveries
+            .safe_range_iter((id, 0, 0, 0)..=(id, u64::MAX, u64::MAX, u64::MAX))
+            .next()
+            .transpose()?)
+    }
+
+    pub fn pending_deliveries(&self, id: SubscriptionId) -> Result<usize> {
+        Ok(self
+            .tables
+            .deliveries
+            .safe_range_iter((id, 0, 0, 0)..=(id, u64::MAX, u64::MAX, u64::MAX))
+            .count())
+    }
+
+    /// Records that a subscriber acknowledged a delivery.
+    pub fn acknowledge(&self, key: DeliveryKey) -> Result<()> {
+        let cursor = Cursor {
+            last_delivered: Some(key),
+            attempts: 0,
+            last_error: None,
+        };
+        let mut batch = self.tables.deliveries.batch();
+        batch.delete_batch(&self.tables.deliveries, [key])?;
+        batch.insert_batch(&self.tables.cursors, [(key.0, cursor)])?;
+        batch.write()?;
+        Ok(())
+    }
+
+    /// Records a failed attempt to deliver the next delivery of a subscription.
+    pub fn record_failure(&self, id: SubscriptionId, attempts: u32, error: String) -> Result<()> {
+        let mut cursor = self.cursor(id)?;
+        cursor.attempts = attempts;
+        cursor.last_error = Some(error);
+        self.tables.cursors.insert(&id, &cursor)?;
+        Ok(())
+    }
+
+    /// Moves a delivery that failed too many times to the dead letters of its subscription.
+    pub fn dead_letter(
+        &self,
+        key: DeliveryKey,
+        delivery: &str,
+        attempts: u32,
+        error: String,
+    ) -> Result<()> {
+        let dead_letter = DeadLetter {
+            delivery: serde_json::from_str(delivery)?,
+            attempts,
+            last_error: error,
+        };
+        let cursor = Cursor {
+            last_delivered: Some(key),
+            attempts: 0,
+            last_error: None,
+        };
+        let mut batch = self.tables.deliveries.batch();
+        batch.delete_batch(&self.tables.deliveries, [key])?;
+        batch.insert_batch(
+            &self.tables.dead_letters,
+            [(key, serde_json::to_string(&dead_letter)?)],
+        )?;
+        batch.insert_batch(&self.tables.cursors, [(key.0, cursor)])?;
+        batch.write()?;
+        Ok(())
+    }
+
+    pub fn dead_letters(&self, id: SubscriptionId) -> Result<Vec<DeadLetter>> {
+        self.tables
+            .dead_letters
+            .safe_range_iter((id, 0, 0, 0)..=(id, u64::MAX, u64::MAX, u64::MAX))
+            .map(|entry| Ok(serde_json::from_str(&entry?.1)?))
+            .collect()
+    }
+
+    /// Moves the dead letters of a subscription back to its pending deliveries, returning how many
+    /// were moved.
+    pub fn redrive_dead_letters(&self, id: SubscriptionId) -> Result<usize> {
+        let dead_letters = self
+            .tables
+            .dead_letters
+            .safe_range_iter((id, 0, 0, 0)..=(id, u64::MAX, u64::MAX, u64::MAX))
+            .map(|entry| {
+                let (key, dead_letter) = entry?;
+                let dead_letter: DeadLetter = serde_json::from_str(&dead_letter)?;
+                Ok((key, serde_json::to_string(&dead_letter.delivery)?))
+            })
+            .collect::<Result<Vec<(DeliveryKey, String)>>>()?;
+        let count = dead_letters.len();
+        let mut batch = self.tables.deliveries.batch();
+        batch.delete_batch(
+            &self.tables.dead_letters,
+            dead_letters.iter().map(|(key, _)| *key),
+        )?;
+        batch.insert_batch(&self.tables.deliveries, dead_letters)?;
+        batch.write()?;
+        self.new_deliveries.notify_waiters();
+        Ok(count)
+    }
+
+    pub fn watermark(&self, task_name: &str) -> Result<Option<CheckpointSequenceNumber>> {
+        Ok(self.tables.watermarks.get(&task_name.to_string())?)
+    }
+
+    pub(crate) fn save_watermark(
+        &self,
+        task_name: String,
+        checkpoint: CheckpointSequenceNumber,
+    ) -> Result<()> {
+        Ok(self.tables.watermarks.insert(&task_name, &checkpoint)?)
+    }
+}
+
+/// Stores the ingestion progress alongside the deliveries, starting from `initial_checkpoint`.
+pub struct WebhookProgressStore {
+    store: WebhookStore,
+    initial_checkpoint: CheckpointSequenceNumber,
+}
+
+impl WebhookProgressStore {
+    pub fn new(store: WebhookStore, initial_checkpoint: CheckpointSequenceNumber) -> Self {
+        Self {
+            store,
+            initial_checkpoint,
+        }
+    }
+}
+
+#[async_trait]
+impl ProgressStore for WebhookProgressStore {
+    async fn load(&mut self, task_name: String) -> Result<CheckpointSequenceNumber> {
+        Ok(self
+            .store
+            .watermark(&task_name)?
+            .unwrap_or(self.initial_checkpoint))
+    }
+
+    async fn save(
+        &mut self,
+        task_name: String,
+        checkpoint_number: CheckpointSequenceNumber,
+    ) -> Result<()> {
+        self.store.save_watermark(task_name, checkpoint_number)
+    }
+}
+
+/// Progress of a replay, which is not persisted, as interrupted replays are requested again.
+/// Publishes the watermark, so that the replay can be stopped once the matches of its last
+/// checkpoint are enqueued.
+pub struct ReplayProgressStore {
+    initial_checkpoint: CheckpointSequenceNumber,
+    watermark: watch::Sender<CheckpointSequenceNumber>,
+}
+
+impl ReplayProgressStore {
+    pub fn new(
+        initial_checkpoint: CheckpointSequenceNumber,
+    ) -> (Self, watch::Receiver<CheckpointSequenceNumber>) {
+        let (watermark, receiver) = watch::channel(initial_checkpoint);
+        let store = Self {
+            initial_checkpoint,
+            watermark,
+        };
+        (store, receiver)
+    }
+}
+
+#[async_trait]
+impl ProgressStore for ReplayProgressStore {
+    async fn load(&mut self, _: String) -> Result<CheckpointSequenceNumber> {
+        Ok(self.initial_checkpoint)
+    }
+
+    async fn save(&mut self, _: String, checkpoint_number: CheckpointSequenceNumber) -> Result<()> {
+        self.watermark.send_replace(checkpoint_number);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{delivery_id, Delivery, Payload, TransactionPayload};
+    use sui_json_rpc_types::{SuiTransactionBlockEffects, TransactionFilter};
+    use sui_types::base_types::SuiAddress;
+    use sui_types::digests::TransactionDigest;
+    use sui_types::effects::TransactionEffects;
+
+    fn subscribe(store: &WebhookStore) -> SubscriptionId {
+        store
+            .create_subscription(
+                "http://localhost/hook".to_string(),
+                "secret".to_string(),
+                SubscriptionFilter::Transaction(TransactionFilter::FromAddress(SuiAddress::ZERO)),
+                0,
+            )
+            .unwrap()
+            .id
+    }
+
+    fn delivery(key: DeliveryKey) -> (DeliveryKey, String) {
+        let delivery = Delivery {
+            id: delivery_id(&key),
+            subscription_id: key.0,
+            checkpoint: key.1,
+            timestamp_ms: 0,
+            payload: Payload::Transaction(TransactionPayload {
+                digest: TransactionDigest::random(),
+                sender: SuiAddress::ZERO,
+                effects: SuiTransactionBlockEffects::try_from(TransactionEffects::default())
+                    .unwrap(),
+            }),
+            error: None,
+        };
+        (key, serde_json::to_string(&delivery).unwrap())
+    }
+
+    fn next_key(store: &WebhookStore, id: SubscriptionId) -> Option<DeliveryKey> {
+        store.next_delivery(id).unwrap().map(|(key, _)| key)
+    }
+
+    #[test]
+    fn test_deliveries_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = WebhookStore::open(dir.path()).unwrap();
+        let a = subscribe(&store);
+        let b = subscribe(&store);
+        assert_eq!((a, b), (0, 1));
+
+        let deliveries = vec![
+            delivery((a, 2, 0, 0)),
+            delivery((a, 1, 1, 0)),
+            delivery((a, 1, 0, 3)),
+            delivery((b, 1, 0, 0)),
+            // Deliveries of unknown subscriptions are dropped.
+            delivery((7, 1, 0, 0)),
+        ];
+        store.enqueue(deliveries.clone()).unwrap();
+        // Enqueuing the same checkpoints again doesn't duplicate deliveries.
+        store.enqueue(deliveries).unwrap();
+        assert_eq!(store.pending_deliveries(a).unwrap(), 3);
+        assert_eq!(store.pending_deliveries(b).unwrap(), 1);
+        assert_eq!(store.pending_deliveries(7).unwrap(), 0);
+        assert_eq!(next_key(&store, a), Some((a, 1, 0, 3)));
+
+        store.acknowledge((a, 1, 0, 3)).unwrap();
+        assert_eq!(next_key(&store, a), Some((a, 1, 1, 0)));
+        assert_eq!(store.cursor(a).unwrap().last_delivered, Some((a, 1, 0, 3)));
+        store.record_failure(a, 2, "Timed out".to_string()).unwrap();
+        drop(store);
+
+        let store = WebhookStore::open(dir.path()).unwrap();
+        let ids: Vec<_> = store.subscriptions().iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![a, b]);
+        assert_eq!(next_key(&store, a), Some((a, 1, 1, 0)));
+        assert_eq!(next_key(&store, b), Some((b, 1, 0, 0)));
+        let cursor = store.cursor(a).unwrap();
+        assert_eq!(cursor.last_delivered, Some((a, 1, 0, 3)));
+        assert_eq!(cursor.attempts, 2);
+        assert_eq!(cursor.last_error.as_deref(), Some("Timed out"));
+        // Subscription IDs are never reused.
+        assert_eq!(subscribe(&store), 2);
+    }
+
+    #[test]
+    fn test_dead_letters_are_redriven() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = WebhookStore::open(dir.path()).unwrap();
+        let a = subscribe(&store);
+        store
+            .enqueue(vec![delivery((a, 1, 0, 0)), delivery((a, 2, 0, 0))])
+            .unwrap();
+
+        let (key, payload) = store.next_delivery(a).unwrap().unwrap();
+        store.record_failure(a, 3, "500".to_string()).unwrap();
+        store
+            .dead_letter(key, &payload, 3, "500".to_string())
+            .unwrap();
+        assert_eq!(next_key(&store, a), Some((a, 2, 0, 0)));
+        let cursor = store.cursor(a).unwrap();
+        assert_eq!(cursor.last_delivered, Some(key));
+        assert_eq!(cursor.attempts, 0);
+        assert_eq!(cursor.last_error, None);
+        let dead_letters = store.dead_letters(a).unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].delivery.id, delivery_id(&key));
+        assert_eq!(dead_letters[0].attempts, 3);
+        assert_eq!(dead_letters[0].last_error, "500");
+
+        assert_eq!(store.redrive_dead_letters(a).unwrap(), 1);
+        assert!(store.dead_letters(a).unwrap().is_empty());
+        assert_eq!(next_key(&store, a), Some(key));
+        assert_eq!(store.pending_deliveries(a).unwrap(), 2);
+        assert_eq!(store.redrive_dead_letters(a).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_subscriptions_take_effect_after_matched_checkpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = WebhookStore::open(dir.path()).unwrap();
+        let create = |start_checkpoint| {
+            store
+                .create_subscription(
+                    "http://localhost/hook".to_string(),
+                    "secret".to_string(),
+                    SubscriptionFilter::Transaction(TransactionFilter::FromAddress(
+                        SuiAddress::ZERO,
+                    )),
+                    start_checkpoint,
+                )
+                .unwrap()
+        };
+        let ids = |checkpoint| -> Vec<_> {
+            store
+                .subscriptions_for_checkpoint(checkpoint)
+                .iter()
+                .map(|s| s.id)
+                .collect()
+        };
+        let a = create(0).id;
+        let later = create(10).id;
+        assert_eq!(ids(7), vec![a]);
+
+        // Checkpoint 7 may have been matched before the ones before it were committed, so a new
+        // subscription only takes effect after it.
+        let b = create(3);
+        assert_eq!(b.start_checkpoint, 8);
+        assert_eq!(ids(7), vec![a]);
+        assert_eq!(ids(8), vec![a, b.id]);
+        assert_eq!(ids(10), vec![a, later, b.id]);
+        assert_eq!(create(20).start_checkpoint, 20);
+    }
+
+    #[test]
+    fn test_delete_subscription() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = WebhookStore::open(dir.path()).unwrap();
+        let a = subscribe(&store);
+        let b = subscribe(&store);
+        store
+            .enqueue(vec![
+                delivery((a, 1, 0, 0)),
+                delivery((a, 2, 0, 0)),
+                delivery((b, 1, 0, 0)),
+            ])
+            .unwrap();
+        let (key, payload) = store.next_delivery(a).unwrap().unwrap();
+        store
+            .dead_letter(key, &payload, 1, "404".to_string())
+            .unwrap();
+
+        assert!(store.delete_subscription(a).unwrap());
+        assert!(!store.delete_subscription(a).unwrap());
+        assert!(store.subscription(a).is_none());
+        assert_eq!(store.pending_deliveries(a).unwrap(), 0);
+        assert!(store.dead_letters(a).unwrap().is_empty());
+        assert_eq!(store.cursor(a).unwrap().last_delivered, None);
+        store.enqueue(vec![delivery((a, 3, 0, 0))]).unwrap();
+        assert_eq!(store.pending_deliveries(a).unwrap(), 0);
+
+        assert_eq!(next_key(&store, b), Some((b, 1, 0, 0)));
+        drop(store);
+        let store = WebhookStore::open(dir.path()).unwrap();
+        assert!(store.subscription(a).is_none());
+        assert!(store.subscription(b).is_some());
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use sui_json_rpc_types::{EventFilter, SuiEvent, SuiTransactionBlockEffects, TransactionFilter};
+use sui_types::base_types::SuiAddress;
+use sui_types::digests::TransactionDigest;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+
+pub type SubscriptionId = u64;
+
+/// Identifies a delivery, and orders the deliveries of a subscription: (subscription id,
+/// checkpoint, index of the transaction in the checkpoint, index of the event in the transaction).
+/// Deliveries are keyed deterministically, so that enqueuing the matches of a checkpoint again,
+/// e.g. after a restart or a replay, never duplicates pending deliveries.
+pub type DeliveryKey = (SubscriptionId, CheckpointSequenceNumber, u64, u64);
+
+/// What a subscription matches.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionFilter {
+    Event(EventFilter),
+    Transaction(TransactionFilter),
+}
+
+/// A webhook registered to receive the events or transactions matching its filter.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+    pub id: SubscriptionId,
+    pub url: String,
+    /// Key deliveries are signed with, using HMAC-SHA3-256.
+    pub secret: String,
+    pub filter: SubscriptionFilter,
+    /// Checkpoints before this one are not matched against the filter, unless replayed.
+    pub start_checkpoint: CheckpointSequenceNumber,
+}
+
+/// What is POSTed to a subscriber.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Delivery {
+    /// Unique ID of the delivery, which subscribers can use to deduplicate retried deliveries.
+    pub id: String,
+    pub subscription_id: SubscriptionId,
+    pub checkpoint: CheckpointSequenceNumber,
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub payload: Payload,
+    /// Why the payload could not be fully decoded, e.g. when the package of an event could not be
+    /// fetched, in which case the event is delivered with a null `parsedJson`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum Payload {
+    Event(SuiEvent),
+    Transaction(TransactionPayload),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionPayload {
+    pub digest: TransactionDigest,
+    pub sender: SuiAddress,
+    pub effects: SuiTransactionBlockEffects,
+}
+
+/// Where a subscriber is in its stream of deliveries.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cursor {
+    /// The last delivery that was acknowledged or dead-lettered.
+    pub last_delivered: Option<DeliveryKey>,
+    /// Failed attempts to deliver the next pending delivery.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+/// A delivery that failed too many times, and was set aside so that the deliveries after it can
+/// go through.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetter {
+    pub delivery: Delivery,
+    pub attempts: u32,
+    pub last_error: String,
+}
+
+pub fn delivery_id(key: &DeliveryKey) -> String {
+    let (subscription_id, checkpoint, transaction, event) = key;
+    format!("{subscription_id}-{checkpoint}-{transaction}-{event}")
+}
@@ -0,0 +1,490 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::metrics::WebhookMetrics;
+use crate::store::WebhookStore;
+use crate::types::{
+    delivery_id, Delivery, DeliveryKey, Payload, Subscription, SubscriptionFilter, SubscriptionId,
+    TransactionPayload,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::annotated_value::MoveValue;
+use move_core_types::language_storage::TypeTag;
+use std::sync::Arc;
+use sui_data_ingestion_core::{Reducer, Worker};
+use sui_json_rpc_types::{
+    type_and_fields_from_move_event_data, EffectsWithInput, EventID, Filter, SuiEvent,
+    SuiTransactionBlockEffects,
+};
+use sui_package_resolver::{
+    error::Error as PackageResolverError, Package, PackageStore, PackageStoreWithLruCache, Resolver,
+};
+use sui_rest_api::Client;
+use sui_types::base_types::ObjectID;
+use sui_types::event::Event;
+use sui_types::full_checkpoint_content::{CheckpointData, CheckpointTransaction};
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use sui_types::transaction::TransactionDataAPI;
+use sui_types::SYSTEM_PACKAGE_ADDRESSES;
+use tracing::warn;
+use typed_store::Map;
+
+/// Packages are read from the store, which is kept up to date with the packages published in
+/// ingested checkpoints, and fetched from a full node otherwise, e.g. when starting from a
+/// recent checkpoint.
+#[derive(Clone)]
+pub struct WebhookPackageStore {
+    store: WebhookStore,
+    fallback_client: Client,
+}
+
+impl WebhookPackageStore {
+    pub fn new(store: WebhookStore, rest_url: &str) -> Self {
+        Self {
+            store,
+            fallback_client: Client::new(rest_url),
+        }
+    }
+}
+
+#[async_trait]
+impl PackageStore for WebhookPackageStore {
+    async fn fetch(&self, id: AccountAddress) -> sui_package_resolver::Result<Arc<Package>> {
+        let packages = &self.store.tables.packages;
+        let store_error = |e: typed_store::TypedStoreError| PackageResolverError::Store {
+            store: "RocksDB",
+            error: e.to_string(),
+        };
+        let object = match packages.get(&ObjectID::from(id)).map_err(store_error)? {
+            Some(object) => object,
+            None => {
+                let object = self
+                    .fallback_client
+                    .get_object(ObjectID::from(id))
+                    .await
+                    .map_err(|_| PackageResolverError::PackageNotFound(id))?;
+                packages
+                    .insert(&object.id(), &object)
+                    .map_err(store_error)?;
+                object
+            }
+        };
+        Ok(Arc::new(Package::read_from_object(&object)?))
+    }
+}
+
+/// Matches the transactions and events of checkpoints against subscriptions, and serializes the
+/// deliveries they produce.
+pub struct WebhookWorker {
+    store: WebhookStore,
+    resolver: Arc<Resolver<PackageStoreWithLruCache<WebhookPackageStore>>>,
+    /// When replaying checkpoints for a subscription, only match it, and only up to this
+    /// checkpoint.
+    replay: Option<(SubscriptionId, CheckpointSequenceNumber)>,
+    metrics: Arc<WebhookMetrics>,
+}
+
+impl WebhookWorker {
+    pub fn new(
+        store: WebhookStore,
+        package_store: WebhookPackageStore,
+        replay: Option<(SubscriptionId, CheckpointSequenceNumber)>,
+        metrics: Arc<WebhookMetrics>,
+    ) -> Self {
+        Self {
+            store,
+            resolver: Arc::new(Resolver::new(PackageStoreWithLruCache::new(package_store))),
+            replay,
+            metrics,
+        }
+    }
+
+    async fn match_transaction(
+        &self,
+        subscriptions: &[Subscription],
+        checkpoint: &CheckpointData,
+        index: usize,
+        transaction: &CheckpointTransaction,
+        deliveries: &mut Vec<(DeliveryKey, Delivery)>,
+    ) -> Result<()> {
+        let summary = &checkpoint.checkpoint_summary;
+        let digest = *transaction.transaction.digest();
+        let delivery = |key: DeliveryKey, payload: Payload, error: Option<String>| {
+            (
+                key,
+                Delivery {
+                    id: delivery_id(&key),
+                    subscription_id: key.0,
+                    checkpoint: summary.sequence_number,
+                    timestamp_ms: summary.timestamp_ms,
+                    payload,
+                    error,
+                },
+            )
+        };
+
+        let transaction_subscriptions: Vec<_> = subscriptions
+            .iter()
+            .filter_map(|s| match &s.filter {
+                SubscriptionFilter::Transaction(filter) => Some((s.id, filter)),
+                SubscriptionFilter::Event(_) => None,
+            })
+            .collect();
+        if !transaction_subscriptions.is_empty() {
+            let item = EffectsWithInput {
+                effects: SuiTransactionBlockEffects::try_from(transaction.effects.clone())?,
+                input: transaction.transaction.data().transaction_data().clone(),
+            };
+            for (id, filter) in transaction_subscriptions {
+                if filter.matches(&item) {
+                    let payload = Payload::Transaction(TransactionPayload {
+                        digest,
+                        sender: item.input.sender(),
+                        effects: item.effects.clone(),
+                    });
+                    deliveries.push(delivery(
+                        (id, summary.sequence_number, index as u64, 0),
+                        payload,
+                        None,
+                    ));
+                }
+            }
+        }
+
+        let event_subscriptions: Vec<_> = subscriptions
+            .iter()
+            .filter_map(|s| match &s.filter {
+                SubscriptionFilter::Event(filter) => Some((s.id, filter)),
+                SubscriptionFilter::Transaction(_) => None,
+            })
+            .collect();
+        let Some(events) = &transaction.events else {
+            return Ok(());
+        };
+        if event_subscriptions.is_empty() {
+            return Ok(());
+        }
+        for (event_seq, event) in events.data.iter().enumerate() {
+            // Filters don't look at the contents of events, which are only decoded for matches.
+            let mut sui_event = SuiEvent {
+                id: EventID {
+                    tx_digest: digest,
+                    event_seq: event_seq as u64,
+                },
+                package_id: event.package_id,
+                transaction_module: event.transaction_module.clone(),
+                sender: event.sender,
+                type_: event.type_.clone(),
+                parsed_json: serde_json::Value::Null,
+                bcs: event.contents.clone(),
+                timestamp_ms: Some(summary.timestamp_ms),
+            };
+            let matches: Vec<_> = event_subscriptions
+                .iter()
+                .filter(|(_, filter)| filter.matches(&sui_event))
+                .map(|(id, _)| *id)
+                .collect();
+            if matches.is_empty() {
+                continue;
+            }
+            // An event that cannot be decoded, e.g. because its package cannot be fetched, is
+            // delivered without its contents rather than failing the checkpoint, which would halt
+            // ingestion for every subscription.
+            let error = match self.parse_event(event).await {
+                Ok(parsed_json) => {
+                    sui_event.parsed_json = parsed_json;
+                    None
+                }
+                Err(e) => {
+                    warn!("Failed to decode event {event_seq} of transaction {digest}: {e}");
+                    self.metrics.event_decode_failures.inc();
+                    Some(format!("Failed to decode event: {e}"))
+                }
+            };
+            for id in matches {
+                deliveries.push(delivery(
+                    (id, summary.sequence_number, index as u64, event_seq as u64),
+                    Payload::Event(sui_event.clone()),
+                    error.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The contents of an event as JSON.
+    async fn parse_event(&self, event: &Event) -> Result<serde_json::Value> {
+        let layout = self
+            .resolver
+            .type_layout(TypeTag::Struct(Box::new(event.type_.clone())))
+            .await?;
+        let move_value = MoveValue::simple_deserialize(&event.contents, &layout)?;
+        Ok(type_and_fields_from_move_event_data(move_value)?.1)
+    }
+}
+
+#[async_trait]
+impl Worker for WebhookWorker {
+    type Result = Vec<(DeliveryKey, String)>;
+
+    async fn process_checkpoint(&self, checkpoint: &CheckpointData) -> Result<Self::Result> {
+        let summary = &checkpoint.checkpoint_summary;
+        for object in checkpoint
+            .transactions
+            .iter()
+            .flat_map(|t| &t.output_objects)
+        {
+            if object.is_package() {
+                self.store.tables.packages.insert(&object.id(), object)?;
+            }
+        }
+
+        let subscriptions = match self.replay {
+            Some((_, last_checkpoint)) if summary.sequence_number > last_checkpoint => vec![],
+            Some((id, _)) => self.store.subscription(id).into_iter().collect(),
+            None => self
+                .store
+                .subscriptions_for_checkpoint(summary.sequence_number),
+        };
+        let mut deliveries = vec![];
+        if !subscriptions.is_empty() {
+            for (index, transaction) in checkpoint.transactions.iter().enumerate() {
+                self.match_transaction(
+                    &subscriptions,
+                    checkpoint,
+                    index,
+                    transaction,
+                    &mut deliveries,
+                )
+                .await?;
+            }
+        }
+
+        if summary.end_of_epoch_data.is_some() {
+            // System packages are upgraded in place at epoch boundaries.
+            self.resolver
+                .package_store()
+                .evict(SYSTEM_PACKAGE_ADDRESSES.iter().copied());
+        }
+        self.metrics.matches.inc_by(deliveries.len() as u64);
+        deliveries
+            .into_iter()
+            .map(|(key, delivery)| Ok((key, serde_json::to_string(&delivery)?)))
+            .collect()
+    }
+}
+
+/// Enqueues the deliveries of checkpoints in checkpoint order, so that each subscriber receives
+/// its deliveries in order.
+pub struct WebhookReducer {
+    store: WebhookStore,
+}
+
+impl WebhookReducer {
+    pub fn new(store: WebhookStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Reducer<Vec<(DeliveryKey, String)>> for WebhookReducer {
+    async fn commit(&self, batch: Vec<Vec<(DeliveryKey, String)>>) -> Result<()> {
+        self.store.enqueue(batch.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Delivery;
+    use prometheus::Registry;
+    use sui_json_rpc_types::{EventFilter, TransactionFilter};
+    use sui_types::base_types::{random_object_ref, SuiAddress};
+    use sui_types::committee::Committee;
+    use sui_types::effects::{TransactionEffects, TransactionEvents};
+    use sui_types::gas::GasCostSummary;
+    use sui_types::messages_checkpoint::{
+        CertifiedCheckpointSummary, CheckpointContents, CheckpointSummary,
+    };
+    use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+    use sui_types::transaction::{Transaction, TransactionData};
+    use sui_types::{parse_sui_struct_tag, Identifier};
+
+    fn transaction(sender: SuiAddress, events: Vec<Event>) -> CheckpointTransaction {
+        let data = TransactionData::new_programmable(
+            sender,
+            vec![random_object_ref()],
+            ProgrammableTransactionBuilder::new().finish(),
+            1000,
+            1,
+        );
+        CheckpointTransaction {
+            transaction: Transaction::from_data(data, vec![]),
+            effects: TransactionEffects::default(),
+            events: Some(TransactionEvents { data: events }),
+            input_objects: vec![],
+            output_objects: vec![],
+        }
+    }
+
+    fn checkpoint(
+        sequence_number: u64,
+        timestamp_ms: u64,
+        transactions: Vec<CheckpointTransaction>,
+    ) -> CheckpointData {
+        let checkpoint_contents = CheckpointContents::new_with_digests_and_signatures([], vec![]);
+        let summary = CheckpointSummary {
+            epoch: 0,
+            sequence_number,
+            network_total_transactions: transactions.len() as u64,
+            content_digest: *checkpoint_contents.digest(),
+            previous_digest: None,
+            epoch_rolling_gas_cost_summary: GasCostSummary::default(),
+            timestamp_ms,
+            checkpoint_commitments: vec![],
+            end_of_epoch_data: None,
+            version_specific_data: vec![],
+        };
+        let (committee, keys) = Committee::new_simple_test_committee_of_size(1);
+        CheckpointData {
+            checkpoint_summary: CertifiedCheckpointSummary::new_from_keypairs_for_testing(
+                summary, &keys, &committee,
+            ),
+            checkpoint_contents,
+            transactions,
+        }
+    }
+
+    fn worker(
+        store: &WebhookStore,
+        replay: Option<(SubscriptionId, CheckpointSequenceNumber)>,
+    ) -> WebhookWorker {
+        WebhookWorker::new(
+            store.clone(),
+            WebhookPackageStore::new(store.clone(), "http://localhost:9000"),
+            replay,
+            Arc::new(WebhookMetrics::new(&Registry::new())),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_match_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = WebhookStore::open(dir.path()).unwrap();
+        let sender = SuiAddress::random_for_testing_only();
+        let other = SuiAddress::random_for_testing_only();
+        let subscribe = |filter, start_checkpoint| {
+            store
+                .create_subscription(
+                    "http://localhost/hook".to_string(),
+                    "secret".to_string(),
+                    filter,
+                    start_checkpoint,
+                )
+                .unwrap()
+                .id
+        };
+        let from_sender = TransactionFilter::FromAddress(sender);
+        let matching = subscribe(SubscriptionFilter::Transaction(from_sender.clone()), 0);
+        subscribe(
+            SubscriptionFilter::Transaction(TransactionFilter::FromAddress(other)),
+            0,
+        );
+        let not_started = subscribe(SubscriptionFilter::Transaction(from_sender), 10);
+        // Events are only decoded when they match, so no package is needed for this one.
+        subscribe(SubscriptionFilter::Event(EventFilter::Sender(other)), 0);
+
+        let event = Event {
+            package_id: ObjectID::random(),
+            transaction_module: Identifier::new("m").unwrap(),
+            sender,
+            type_: parse_sui_struct_tag("0x42::m::E").unwrap(),
+            contents: vec![],
+        };
+        let checkpoint = checkpoint(
+            5,
+            1234,
+            vec![
+                transaction(SuiAddress::random_for_testing_only(), vec![]),
+                transaction(sender, vec![event]),
+            ],
+        );
+        let digest = *checkpoint.transactions[1].transaction.digest();
+
+        let deliveries = worker(&store, None)
+            .process_checkpoint(&checkpoint)
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        let (key, delivery) = &deliveries[0];
+        assert_eq!(*key, (matching, 5, 1, 0));
+        let delivery: Delivery = serde_json::from_str(delivery).unwrap();
+        assert_eq!(delivery.id, format!("{matching}-5-1-0"));
+        assert_eq!(delivery.subscription_id, matching);
+        assert_eq!(delivery.checkpoint, 5);
+        assert_eq!(delivery.timestamp_ms, 1234);
+        let Payload::Transaction(payload) = delivery.payload else {
+            panic!("Expected a transaction delivery");
+        };
+        assert_eq!(payload.digest, digest);
+        assert_eq!(payload.sender, sender);
+
+        // Replaying for a subscription ignores its start checkpoint, and other subscriptions.
+        let deliveries = worker(&store, Some((not_started, 5)))
+            .process_checkpoint(&checkpoint)
+            .await
+            .unwrap();
+        let keys: Vec<_> = deliveries.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec![(not_started, 5, 1, 0)]);
+
+        // Checkpoints after the replayed ones are left to the main ingestion.
+        let deliveries = worker(&store, Some((not_started, 4)))
+            .process_checkpoint(&checkpoint)
+            .await
+            .unwrap();
+        assert!(deliveries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = WebhookStore::open(dir.path()).unwrap();
+        let sender = SuiAddress::random_for_testing_only();
+        let id = store
+            .create_subscription(
+                "http://localhost/hook".to_string(),
+                "secret".to_string(),
+                SubscriptionFilter::Event(EventFilter::Sender(sender)),
+                0,
+            )
+            .unwrap()
+            .id;
+
+        // The package of the event is neither stored nor available from the full node.
+        let event = Event {
+            package_id: ObjectID::random(),
+            transaction_module: Identifier::new("m").unwrap(),
+            sender,
+            type_: parse_sui_struct_tag("0x42::m::E").unwrap(),
+            contents: vec![],
+        };
+        let checkpoint = checkpoint(5, 1234, vec![transaction(sender, vec![event])]);
+
+        let deliveries = worker(&store, None)
+            .process_checkpoint(&checkpoint)
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        let (key, delivery) = &deliveries[0];
+        assert_eq!(*key, (id, 5, 0, 0));
+        let delivery: Delivery = serde_json::from_str(delivery).unwrap();
+        assert!(delivery.error.is_some());
+        let Payload::Event(event) = delivery.payload else {
+            panic!("Expected an event delivery");
+        };
+        assert_eq!(event.sender, sender);
+        assert_eq!(event.parsed_json, serde_json::Value::Null);
+    }
+}
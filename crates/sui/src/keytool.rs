@@ -3,7 +3,9 @@
 use crate::key_identity::{get_identity_address_from_keystore, KeyIdentity};
 use crate::zklogin_commands_util::{perform_zk_login_test_tx, read_cli_line};
 use anyhow::anyhow;
+use async_trait::async_trait;
 use bip32::DerivationPath;
+use bip39::{Language, Mnemonic, Seed};
 use clap::*;
 use fastcrypto::ed25519::Ed25519KeyPair;
 use fastcrypto::encoding::{Base64, Encoding, Hex};
@@ -33,12 +35,15 @@ use std::fmt::{Debug, Display, Formatter};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use sui_keys::key_derive::generate_new_key;
+use sui_json_rpc_types::{SuiTransactionBlockResponseQuery, TransactionFilter};
+use sui_keys::account_discovery::{discover_accounts, AddressUsage, DEFAULT_GAP_LIMIT};
+use sui_keys::key_derive::{derive_address_from_xpub, export_xpub, generate_new_key};
 use sui_keys::keypair_file::{
     read_authority_keypair_from_file, read_keypair_from_file, write_authority_keypair_to_file,
     write_keypair_to_file,
 };
 use sui_keys::keystore::{AccountKeystore, Keystore};
+use sui_sdk::{SuiClient, SuiClientBuilder};
 use sui_types::base_types::SuiAddress;
 use sui_types::committee::EpochId;
 use sui_types::crypto::{
@@ -99,6 +104,36 @@ pub enum KeyToolCommand {
         #[clap(long, default_value = "0")]
         cur_epoch: u64,
     },
+    /// Derive the addresses of an account from an extended public key exported by
+    /// `sui keytool export-xpub`, without access to the private keys.
+    DeriveXpubAddresses {
+        xpub: String,
+        /// Index of the first address to derive.
+        #[clap(long, default_value_t = 0)]
+        start: u32,
+        /// Number of addresses to derive.
+        #[clap(long, default_value_t = 20)]
+        count: u32,
+    },
+    /// Find the keys derived from a mnemonic phrase that have been used on chain. Keys are derived
+    /// at m/44'/784'/{account}'/0'/{index}' for ed25519, or m/54'/784'/{account}'/0/{index} for
+    /// secp256k1 or m/74'/784'/{account}'/0/{index} for secp256r1, for index = 0, 1, 2, ... until
+    /// `gap-limit` consecutive addresses have neither owned objects nor sent transactions.
+    /// Pass --import to add the keys that were found to Sui CLI Keystore.
+    DiscoverAccounts {
+        mnemonic_phrase: String,
+        key_scheme: SignatureScheme,
+        #[clap(long, default_value_t = 0)]
+        account: u32,
+        #[clap(long, default_value_t = DEFAULT_GAP_LIMIT)]
+        gap_limit: u32,
+        /// The fullnode to check address usage against.
+        #[clap(long, default_value = "https://fullnode.mainnet.sui.io:443")]
+        fullnode_url: String,
+        /// Add the keys that were found to the keystore.
+        #[clap(long)]
+        import: bool,
+    },
     /// Generate a new keypair with key scheme flag {ed25519 | secp256k1 | secp256r1}
     /// with optional derivation path, default to m/44'/784'/0'/0'/0' for ed25519 or
     /// m/54'/784'/0'/0/0 for secp256k1 or m/74'/784'/0'/0/0 for secp256r1. Word
@@ -135,6 +170,16 @@ pub enum KeyToolCommand {
         #[clap(long)]
         key_identity: KeyIdentity,
     },
+    /// Output the extended public key of an account derived from a mnemonic phrase, at
+    /// m/54'/784'/{account}'. Only secp256k1 is supported. The addresses of the account's keys
+    /// can be derived from it with `sui keytool derive-xpub-addresses`, to set up a watch-only
+    /// wallet.
+    ExportXpub {
+        mnemonic_phrase: String,
+        key_scheme: SignatureScheme,
+        #[clap(long, default_value_t = 0)]
+        account: u32,
+    },
     /// List all keys by its Sui address, Base64 encoded public key, key scheme name in
     /// sui.keystore.
     List {
@@ -339,6 +384,28 @@ pub struct Key {
     peer_id: Option<String>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredAccountOutput {
+    sui_address: SuiAddress,
+    derivation_path: String,
+    imported: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XpubOutput {
+    xpub: String,
+    derivation_path: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XpubAddress {
+    index: u32,
+    sui_address: SuiAddress,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExportedKey {
@@ -455,10 +522,13 @@ pub enum CommandOutput {
     Convert(ConvertOutput),
     DecodeMultiSig(DecodedMultiSigOutput),
     DecodeOrVerifyTx(DecodeOrVerifyTxOutput),
+    DeriveXpubAddresses(Vec<XpubAddress>),
+    DiscoverAccounts(Vec<DiscoveredAccountOutput>),
     Error(String),
     Generate(Key),
     Import(Key),
     Export(ExportedKey),
+    ExportXpub(XpubOutput),
     List(Vec<Key>),
     LoadKeypair(KeypairData),
     MultiSigAddress(MultiSigAddress),
@@ -661,6 +731,59 @@ impl KeyToolCommand {
                     }
                 }
             }
+            KeyToolCommand::ExportXpub {
+                mnemonic_phrase,
+                key_scheme,
+                account,
+            } => {
+                let seed = seed_from_mnemonic(&mnemonic_phrase)?;
+                let (derivation_path, xpub) = export_xpub(&seed, &key_scheme, account)
+                    .map_err(|e| anyhow!("Failed to export xpub: {:?}", e))?;
+                CommandOutput::ExportXpub(XpubOutput {
+                    xpub,
+                    derivation_path: derivation_path.to_string(),
+                })
+            }
+            KeyToolCommand::DeriveXpubAddresses { xpub, start, count } => {
+                let addresses = (start..start.saturating_add(count))
+                    .map(|index| {
+                        let sui_address = derive_address_from_xpub(&xpub, index)
+                            .map_err(|e| anyhow!("Failed to derive address: {:?}", e))?;
+                        Ok(XpubAddress { index, sui_address })
+                    })
+                    .collect::<Result<_, anyhow::Error>>()?;
+                CommandOutput::DeriveXpubAddresses(addresses)
+            }
+            KeyToolCommand::DiscoverAccounts {
+                mnemonic_phrase,
+                key_scheme,
+                account,
+                gap_limit,
+                fullnode_url,
+                import,
+            } => {
+                let seed = seed_from_mnemonic(&mnemonic_phrase)?;
+                let client = SuiClientBuilder::default().build(&fullnode_url).await?;
+                let usage = ChainAddressUsage(client);
+                let discovered =
+                    discover_accounts(&seed, key_scheme, account, gap_limit, &usage).await?;
+
+                let mut accounts = vec![];
+                for account in discovered {
+                    // Keys that are already in the keystore are left as they are.
+                    let imported = import && keystore.get_key(&account.address).is_err();
+                    if imported {
+                        keystore.add_key(None, account.key_pair)?;
+                    }
+
+                    accounts.push(DiscoveredAccountOutput {
+                        sui_address: account.address,
+                        derivation_path: account.derivation_path.to_string(),
+                        imported,
+                    });
+                }
+                CommandOutput::DiscoverAccounts(accounts)
+            }
             KeyToolCommand::Export { key_identity } => {
                 let address = get_identity_address_from_keystore(key_identity, keystore)?;
                 let skp = keystore.get_key(&address)?;
@@ -1391,6 +1514,39 @@ impl Debug for CommandOutput {
     }
 }
 
+/// Considers an address used if it owns objects or has sent transactions.
+struct ChainAddressUsage(SuiClient);
+
+#[async_trait]
+impl AddressUsage for ChainAddressUsage {
+    async fn is_used(&self, address: SuiAddress) -> Result<bool, anyhow::Error> {
+        let objects = self
+            .0
+            .read_api()
+            .get_owned_objects(address, None, None, Some(1))
+            .await?;
+        if !objects.data.is_empty() {
+            return Ok(true);
+        }
+
+        let query = SuiTransactionBlockResponseQuery::new_with_filter(
+            TransactionFilter::FromAddress(address),
+        );
+        let transactions = self
+            .0
+            .read_api()
+            .query_transaction_blocks(query, None, Some(1), false)
+            .await?;
+        Ok(!transactions.data.is_empty())
+    }
+}
+
+fn seed_from_mnemonic(phrase: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let mnemonic = Mnemonic::from_phrase(phrase, Language::English)
+        .map_err(|e| anyhow!("Invalid mnemonic phrase: {:?}", e))?;
+    Ok(Seed::new(&mnemonic, "").as_bytes().to_vec())
+}
+
 /// Converts legacy formatted private key to 33 bytes bech32 encoded private key or vice versa.
 /// It can handle:
 /// 1) Hex encoded 32 byte private key (assumes scheme is Ed25519), this is the legacy wallet format
@@ -11,6 +11,7 @@ use anyhow::{anyhow, bail, ensure, Context};
 use clap::*;
 use fastcrypto::traits::KeyPair;
 use move_analyzer::analyzer;
+use move_binary_format::{binary_config::BinaryConfig, CompiledModule};
 use move_package::BuildConfig;
 use rand::rngs::OsRng;
 use std::io::{stderr, stdout, Write};
@@ -42,18 +43,20 @@ use sui_graphql_rpc::{
     test_infra::cluster::start_graphql_server_with_fn_rpc,
 };
 
+use sui_json_rpc_types::{SuiObjectDataOptions, SuiRawData};
 use sui_keys::keypair_file::read_key;
 use sui_keys::keystore::{AccountKeystore, FileBasedKeystore, Keystore};
 use sui_move::{self, execute_move_command};
 use sui_move_build::SuiPackageHooks;
 use sui_sdk::sui_client_config::{SuiClientConfig, SuiEnv};
 use sui_sdk::wallet_context::WalletContext;
+use sui_sdk::SuiClient;
 use sui_swarm::memory::Swarm;
 use sui_swarm_config::genesis_config::{GenesisConfig, DEFAULT_NUMBER_OF_AUTHORITIES};
 use sui_swarm_config::network_config::NetworkConfig;
 use sui_swarm_config::network_config_builder::ConfigBuilder;
 use sui_swarm_config::node_config_builder::FullnodeConfigBuilder;
-use sui_types::base_types::SuiAddress;
+use sui_types::base_types::{ObjectID, SuiAddress};
 use sui_types::crypto::{SignatureScheme, SuiKeyPair, ToFromBytes};
 use tempfile::tempdir;
 use tracing;
@@ -479,6 +482,18 @@ impl SuiCommand {
                         let chain_id = client.read_api().get_chain_identifier().await.ok();
                         build.chain_id = chain_id.clone();
                     }
+                    sui_move::Command::ApiDiff(api_diff) => {
+                        // Comparing against an on-chain package requires fetching its modules.
+                        if let Some(package_id) = api_diff.from_package_id() {
+                            let config =
+                                client_config.unwrap_or(sui_config_dir()?.join(SUI_CLIENT_CONFIG));
+                            prompt_if_no_config(&config, false).await?;
+                            let context = WalletContext::new(&config, None, None)?;
+                            let client = context.get_client().await?;
+                            api_diff.from_modules =
+                                Some(fetch_package_modules(&client, package_id).await?);
+                        }
+                    }
                     _ => (),
                 };
                 execute_move_command(package_path.as_deref(), build_config, cmd)
@@ -1194,6 +1209,28 @@ async fn prompt_if_no_config(
     Ok(())
 }
 
+/// The modules of the package published at `package_id`.
+async fn fetch_package_modules(
+    client: &SuiClient,
+    package_id: ObjectID,
+) -> Result<Vec<CompiledModule>, anyhow::Error> {
+    let object = client
+        .read_api()
+        .get_object_with_options(package_id, SuiObjectDataOptions::bcs_lossless())
+        .await?
+        .into_object()
+        .with_context(|| format!("Unable to get package {package_id}"))?;
+    let Some(SuiRawData::Package(package)) = object.bcs else {
+        bail!("Object {package_id} is not a package");
+    };
+    package
+        .module_map
+        .values()
+        .map(|bytes| CompiledModule::deserialize_with_config(bytes, &BinaryConfig::standard()))
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("Unable to deserialize the modules of package {package_id}"))
+}
+
 fn read_line() -> Result<String, anyhow::Error> {
     let mut s = String::new();
     let _ = stdout().flush();
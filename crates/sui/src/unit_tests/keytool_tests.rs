@@ -345,6 +345,51 @@ async fn test_mnemonics_secp256k1() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[test]
+async fn test_xpub_export_and_derive() -> Result<(), anyhow::Error> {
+    // Same key as the first secp256k1 mnemonic test case, which is at m/54'/784'/0'/0/0.
+    let mnemonic = "film crazy soon outside stand loop subway crumble thrive popular green nuclear struggle pistol arm wife phrase warfare march wheat nephew ask sunny firm";
+    let expected =
+        SuiAddress::from_str("9e8f732575cc5386f8df3c784cd3ed1b53ce538da79926b2ad54dcc1197d2532")?;
+
+    let mut keystore = Keystore::from(InMemKeystore::new_insecure_for_tests(0));
+    let output = KeyToolCommand::ExportXpub {
+        mnemonic_phrase: mnemonic.to_string(),
+        key_scheme: SignatureScheme::Secp256k1,
+        account: 0,
+    }
+    .execute(&mut keystore)
+    .await?;
+    let CommandOutput::ExportXpub(xpub) = output else {
+        panic!("Expected xpub output");
+    };
+    assert_eq!(xpub.derivation_path, "m/54'/784'/0'");
+
+    let output = KeyToolCommand::DeriveXpubAddresses {
+        xpub: xpub.xpub,
+        start: 0,
+        count: 3,
+    }
+    .execute(&mut keystore)
+    .await?;
+    let CommandOutput::DeriveXpubAddresses(addresses) = output else {
+        panic!("Expected address output");
+    };
+    assert_eq!(addresses.len(), 3);
+    assert_eq!(addresses[0].sui_address, expected);
+
+    // Hardened ed25519 paths cannot be derived from a public key.
+    assert!(KeyToolCommand::ExportXpub {
+        mnemonic_phrase: mnemonic.to_string(),
+        key_scheme: SignatureScheme::ED25519,
+        account: 0,
+    }
+    .execute(&mut keystore)
+    .await
+    .is_err());
+    Ok(())
+}
+
 #[test]
 async fn test_mnemonics_secp256r1() -> Result<(), anyhow::Error> {
     // Test case matches with /mysten/sui/sdk/typescript/test/unit/cryptography/secp256r1-keypair.test.ts
@@ -30,8 +30,10 @@ use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
 use tracing_subscriber::{filter, fmt, layer::SubscriberExt, reload, EnvFilter, Layer, Registry};
 
 use crate::file_exporter::{CachedOpenFile, FileExporter};
+use crate::otlp_export::{MetricsRegistry, OtlpLogLayer, OtlpResource};
 
 mod file_exporter;
+mod otlp_export;
 pub mod span_latency_prom;
 
 /// Alias for a type-erased error type.
@@ -45,6 +47,19 @@ pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
 #[derive(Default, Clone, Debug)]
 pub struct TelemetryConfig {
     pub enable_otlp_tracing: bool,
+    /// Export the metrics of `otlp_metrics_registries` over OTLP
+    pub enable_otlp_metrics: bool,
+    /// Export log events over OTLP, in addition to writing them to stdout or `log_file`
+    pub enable_otlp_logs: bool,
+    /// OTLP gRPC endpoint traces, metrics and logs are exported to. If set to None, then
+    /// defaults to the `OTLP_ENDPOINT` env var, or http://localhost:4317
+    pub otlp_endpoint: Option<String>,
+    /// Prometheus registries whose metrics are exported over OTLP
+    pub otlp_metrics_registries: Vec<prometheus::Registry>,
+    /// How often metrics are exported over OTLP. If set to None, then defaults to 15 seconds
+    pub otlp_metrics_interval: Option<Duration>,
+    /// Attributes of the OTLP resource, e.g. node name or chain id, in addition to service.name
+    pub otlp_resource_attributes: Vec<(String, String)>,
     /// Enables Tokio Console debugging on port 6669
     pub tokio_console: bool,
     /// Output JSON logs.
@@ -111,11 +126,14 @@ impl FilterHandle {
     }
 }
 
+#[derive(Clone)]
 pub struct TracingHandle {
     log: FilterHandle,
     trace: Option<FilterHandle>,
     file_output: CachedOpenFile,
     sampler: SamplingFilter,
+    otlp_resource: OtlpResource,
+    otlp_metrics_registries: Arc<Mutex<Vec<MetricsRegistry>>>,
 }
 
 impl TracingHandle {
@@ -131,6 +149,20 @@ impl TracingHandle {
         self.sampler.update_sampling_rate(sample_rate);
     }
 
+    /// Sets an attribute of the resource metrics and logs are exported over OTLP with, e.g. to
+    /// update the epoch.
+    pub fn set_otlp_resource_attribute(&self, key: impl Into<String>, value: impl Into<String>) {
+        self.otlp_resource.set_attribute(key.into(), value.into());
+    }
+
+    /// Exports the metrics of `registry` over OTLP, if OTLP metrics are enabled.
+    pub fn add_otlp_metrics_registry(&self, registry: &prometheus::Registry) {
+        self.otlp_metrics_registries
+            .lock()
+            .unwrap()
+            .push(MetricsRegistry::new(registry.clone()));
+    }
+
     pub fn update_trace_file<S: AsRef<str>>(&self, trace_file: S) -> Result<(), BoxError> {
         let trace_path = PathBuf::from_str(trace_file.as_ref())?;
         self.file_output.update_path(trace_path)?;
@@ -226,6 +258,25 @@ fn set_panic_hook(crash_on_panic: bool) {
 static GLOBAL_CONFIG: Lazy<Arc<Mutex<Option<TelemetryConfig>>>> =
     Lazy::new(|| Arc::new(Mutex::new(None)));
 
+/// Parses the comma separated `key=value` pairs of `OTEL_RESOURCE_ATTRIBUTES`. Pairs without a
+/// `=` are skipped with a warning, rather than failing to start the process over a label.
+fn parse_resource_attributes(attributes: &str) -> Vec<(String, String)> {
+    attributes
+        .split(',')
+        .filter(|a| !a.trim().is_empty())
+        .filter_map(|attribute| match attribute.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Some((key.trim().to_owned(), value.trim().to_owned()))
+            }
+            _ => {
+                // Tracing is not set up yet while the config is read.
+                eprintln!("Ignoring invalid OTEL_RESOURCE_ATTRIBUTES entry {attribute:?}, expected key=value");
+                None
+            }
+        })
+        .collect()
+}
+
 fn set_global_telemetry_config(config: TelemetryConfig) {
     let mut global_config = GLOBAL_CONFIG.lock().unwrap();
     assert!(global_config.is_none());
@@ -246,6 +297,12 @@ impl TelemetryConfig {
     pub fn new() -> Self {
         Self {
             enable_otlp_tracing: false,
+            enable_otlp_metrics: false,
+            enable_otlp_logs: false,
+            otlp_endpoint: None,
+            otlp_metrics_registries: vec![],
+            otlp_metrics_interval: None,
+            otlp_resource_attributes: vec![],
             tokio_console: false,
             json_log_output: false,
             log_file: None,
@@ -284,6 +341,28 @@ impl TelemetryConfig {
         self
     }
 
+    pub fn with_otlp_endpoint(mut self, endpoint: &str) -> Self {
+        self.otlp_endpoint = Some(endpoint.to_owned());
+        self
+    }
+
+    pub fn with_otlp_metrics_registry(mut self, registry: &prometheus::Registry) -> Self {
+        self.enable_otlp_metrics = true;
+        self.otlp_metrics_registries.push(registry.clone());
+        self
+    }
+
+    pub fn with_otlp_logs(mut self) -> Self {
+        self.enable_otlp_logs = true;
+        self
+    }
+
+    pub fn with_otlp_resource_attribute(mut self, key: &str, value: &str) -> Self {
+        self.otlp_resource_attributes
+            .push((key.to_owned(), value.to_owned()));
+        self
+    }
+
     pub fn with_sample_rate(mut self, rate: f64) -> Self {
         self.sample_rate = rate;
         self
@@ -307,6 +386,24 @@ impl TelemetryConfig {
             self.enable_otlp_tracing = true
         }
 
+        if env::var("OTLP_METRICS").is_ok() {
+            self.enable_otlp_metrics = true;
+        }
+
+        if env::var("OTLP_LOGS").is_ok() {
+            self.enable_otlp_logs = true;
+        }
+
+        if let Ok(endpoint) = env::var("OTLP_ENDPOINT") {
+            self.otlp_endpoint = Some(endpoint);
+        }
+
+        // Standard OpenTelemetry env var, as a comma separated list of key=value pairs.
+        if let Ok(attributes) = env::var("OTEL_RESOURCE_ATTRIBUTES") {
+            self.otlp_resource_attributes
+                .extend(parse_resource_attributes(&attributes));
+        }
+
         if env::var("RUST_LOG_JSON").is_ok() {
             self.json_log_output = true;
         }
@@ -345,8 +442,8 @@ impl TelemetryConfig {
                 directives.push_str(&format!(",{}=trace", target));
             }
         }
-        let env_filter =
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(directives));
+        let env_filter = EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| EnvFilter::new(directives.clone()));
         let (log_filter, reload_handle) = reload::Layer::new(env_filter);
         let log_filter_handle = FilterHandle(reload_handle);
 
@@ -379,15 +476,57 @@ impl TelemetryConfig {
         let mut provider = None;
         let sampler = SamplingFilter::new(config.sample_rate);
         let service_name = env::var("OTEL_SERVICE_NAME").unwrap_or("sui-node".to_owned());
+        let otlp_endpoint = config
+            .otlp_endpoint
+            .clone()
+            .or_else(|| env::var("OTLP_ENDPOINT").ok())
+            .unwrap_or_else(|| "http://localhost:4317".to_string());
+        let otlp_resource =
+            OtlpResource::new(service_name.clone(), &config.otlp_resource_attributes);
+        let otlp_metrics_registries = Arc::new(Mutex::new(
+            config
+                .otlp_metrics_registries
+                .iter()
+                .cloned()
+                .map(MetricsRegistry::new)
+                .collect(),
+        ));
+
+        if config.enable_otlp_metrics {
+            otlp_export::spawn_metrics_exporter(
+                otlp_endpoint.clone(),
+                config
+                    .otlp_metrics_interval
+                    .unwrap_or(Duration::from_secs(15)),
+                otlp_metrics_registries.clone(),
+                otlp_resource.clone(),
+            );
+        }
+
+        if config.enable_otlp_logs {
+            // Logs exported over OTLP are filtered by OTLP_LOG_FILTER if set, or like other logs.
+            let otlp_log_filter = EnvFilter::try_from_env("OTLP_LOG_FILTER")
+                .or_else(|_| EnvFilter::try_from_default_env())
+                .unwrap_or_else(|_| EnvFilter::new(&directives));
+            let otlp_log_layer = OtlpLogLayer::spawn(otlp_endpoint.clone(), otlp_resource.clone());
+            layers.push(otlp_log_layer.with_filter(otlp_log_filter).boxed());
+        }
 
         if config.enable_otlp_tracing {
             let trace_file = env::var("TRACE_FILE").ok();
 
+            let mut resource_attributes = vec![opentelemetry::KeyValue::new(
+                "service.name",
+                service_name.clone(),
+            )];
+            resource_attributes.extend(
+                config
+                    .otlp_resource_attributes
+                    .iter()
+                    .map(|(key, value)| opentelemetry::KeyValue::new(key.clone(), value.clone())),
+            );
             let config = opentelemetry_sdk::trace::Config::default()
-                .with_resource(Resource::new(vec![opentelemetry::KeyValue::new(
-                    "service.name",
-                    service_name.clone(),
-                )]))
+                .with_resource(Resource::new(resource_attributes))
                 .with_sampler(Sampler::ParentBased(Box::new(sampler.clone())));
 
             // We can either do file output or OTLP, but not both. tracing-opentelemetry
@@ -408,15 +547,12 @@ impl TelemetryConfig {
 
                 tracing_opentelemetry::layer().with_tracer(tracer)
             } else {
-                let endpoint = env::var("OTLP_ENDPOINT")
-                    .unwrap_or_else(|_| "http://localhost:4317".to_string());
-
                 let p = opentelemetry_otlp::new_pipeline()
                     .tracing()
                     .with_exporter(
                         opentelemetry_otlp::new_exporter()
                             .tonic()
-                            .with_endpoint(endpoint),
+                            .with_endpoint(otlp_endpoint),
                     )
                     .with_trace_config(config)
                     .install_batch(runtime::Tokio)
@@ -479,6 +615,8 @@ impl TelemetryConfig {
                 trace: trace_filter_handle,
                 file_output,
                 sampler,
+                otlp_resource,
+                otlp_metrics_registries,
             },
         )
     }
@@ -595,6 +733,17 @@ mod tests {
         panic!("This should cause error logs to be printed out!");
     }
 
+    #[test]
+    fn test_parse_resource_attributes() {
+        assert_eq!(
+            parse_resource_attributes("host.name = node-1,invalid,=empty,, chain=mainnet"),
+            vec![
+                ("host.name".to_owned(), "node-1".to_owned()),
+                ("chain".to_owned(), "mainnet".to_owned()),
+            ]
+        );
+    }
+
     // Both the following tests should be able to "race" to initialize logging without causing a
     // panic
     #[test]
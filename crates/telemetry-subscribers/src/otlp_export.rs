@@ -0,0 +1,528 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! OTLP export of Prometheus registries as OpenTelemetry metrics, and of log events as
+//! OpenTelemetry logs.
+//!
+//! Both are pushed over gRPC to an OTLP collector with the same resource, whose attributes (e.g.
+//! the node name, epoch or chain id) can be updated while the process runs.
+
+use opentelemetry_proto::tonic::{
+    collector::{
+        logs::v1::{logs_service_client::LogsServiceClient, ExportLogsServiceRequest},
+        metrics::v1::{metrics_service_client::MetricsServiceClient, ExportMetricsServiceRequest},
+    },
+    common::v1::{any_value, AnyValue, InstrumentationScope, KeyValue},
+    logs::v1::{LogRecord, ResourceLogs, ScopeLogs, SeverityNumber},
+    metrics::v1::{
+        metric, number_data_point, summary_data_point, AggregationTemporality, Gauge, Histogram,
+        HistogramDataPoint, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum, Summary,
+        SummaryDataPoint,
+    },
+    resource::v1::Resource,
+};
+use prometheus::proto::{MetricFamily, MetricType};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::{warn, Event, Level, Subscriber};
+use tracing_subscriber::{layer::Context, Layer};
+
+/// Maximum number of log records buffered before new ones are dropped.
+const LOG_BUFFER_SIZE: usize = 10_000;
+/// Maximum number of log records sent in a single export request.
+const MAX_LOG_BATCH_SIZE: usize = 512;
+
+/// Events from these targets are not exported as logs, as exporting logs emits them, and would
+/// otherwise never stop.
+const IGNORED_LOG_TARGETS: [&str; 5] = [module_path!(), "h2", "hyper", "tonic", "tower"];
+
+/// The resource metrics and logs are exported with.
+#[derive(Clone, Debug)]
+pub(crate) struct OtlpResource {
+    attributes: Arc<RwLock<BTreeMap<String, String>>>,
+}
+
+impl OtlpResource {
+    pub fn new(service_name: String, attributes: &[(String, String)]) -> Self {
+        let mut all_attributes = BTreeMap::from([("service.name".to_owned(), service_name)]);
+        all_attributes.extend(attributes.iter().cloned());
+        Self {
+            attributes: Arc::new(RwLock::new(all_attributes)),
+        }
+    }
+
+    pub fn set_attribute(&self, key: String, value: String) {
+        self.attributes.write().unwrap().insert(key, value);
+    }
+
+    fn to_proto(&self) -> Resource {
+        Resource {
+            attributes: self
+                .attributes
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(key, value)| string_attribute(key, value.clone()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+fn string_attribute(key: &str, value: String) -> KeyValue {
+    KeyValue {
+        key: key.to_owned(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value)),
+        }),
+    }
+}
+
+fn scope() -> InstrumentationScope {
+    InstrumentationScope {
+        name: env!("CARGO_PKG_NAME").to_owned(),
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        ..Default::default()
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
+/// A Prometheus registry whose metrics are exported over OTLP.
+#[derive(Clone)]
+pub(crate) struct MetricsRegistry {
+    registry: prometheus::Registry,
+    /// When the registry started being exported. Prometheus counters and histograms are
+    /// cumulative since the registry was created, which is not known, so this is the closest start
+    /// time the exporter can report for them.
+    start_time_unix_nano: u64,
+}
+
+impl MetricsRegistry {
+    pub(crate) fn new(registry: prometheus::Registry) -> Self {
+        Self {
+            registry,
+            start_time_unix_nano: unix_nanos(SystemTime::now()),
+        }
+    }
+}
+
+/// Periodically exports the metrics of a set of Prometheus registries, which can grow after
+/// the exporter started.
+pub(crate) fn spawn_metrics_exporter(
+    endpoint: String,
+    interval: Duration,
+    registries: Arc<Mutex<Vec<MetricsRegistry>>>,
+    resource: OtlpResource,
+) {
+    tokio::spawn(async move {
+        let mut client = None;
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            let registries = registries.lock().unwrap().clone();
+            let request = metrics_request(&registries, &resource, unix_nanos(SystemTime::now()));
+            if client.is_none() {
+                match MetricsServiceClient::connect(endpoint.clone()).await {
+                    Ok(c) => client = Some(c),
+                    Err(e) => {
+                        warn!("failed to connect to OTLP endpoint {}: {}", endpoint, e);
+                        continue;
+                    }
+                }
+            }
+            if let Err(e) = client.as_mut().unwrap().export(request).await {
+                warn!("failed to export metrics over OTLP: {}", e);
+                client = None;
+            }
+        }
+    });
+}
+
+fn metrics_request(
+    registries: &[MetricsRegistry],
+    resource: &OtlpResource,
+    time_unix_nano: u64,
+) -> ExportMetricsServiceRequest {
+    let metrics = registries
+        .iter()
+        .flat_map(|registry| {
+            registry.registry.gather().into_iter().map(|family| {
+                metric_family_to_proto(&family, registry.start_time_unix_nano, time_unix_nano)
+            })
+        })
+        .collect();
+    ExportMetricsServiceRequest {
+        resource_metrics: vec![ResourceMetrics {
+            resource: Some(resource.to_proto()),
+            scope_metrics: vec![ScopeMetrics {
+                scope: Some(scope()),
+                metrics,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+fn metric_family_to_proto(
+    family: &MetricFamily,
+    start_time_unix_nano: u64,
+    time_unix_nano: u64,
+) -> Metric {
+    let data = match family.get_field_type() {
+        MetricType::COUNTER => metric::Data::Sum(Sum {
+            data_points: family
+                .get_metric()
+                .iter()
+                .map(|m| {
+                    number_data_point(
+                        m,
+                        m.get_counter().get_value(),
+                        start_time_unix_nano,
+                        time_unix_nano,
+                    )
+                })
+                .collect(),
+            aggregation_temporality: AggregationTemporality::Cumulative as i32,
+            is_monotonic: true,
+        }),
+        MetricType::GAUGE | MetricType::UNTYPED => metric::Data::Gauge(Gauge {
+            data_points: family
+                .get_metric()
+                .iter()
+                .map(|m| {
+                    let value = if family.get_field_type() == MetricType::GAUGE {
+                        m.get_gauge().get_value()
+                    } else {
+                        m.get_untyped().get_value()
+                    };
+                    number_data_point(m, value, start_time_unix_nano, time_unix_nano)
+                })
+                .collect(),
+        }),
+        MetricType::HISTOGRAM => metric::Data::Histogram(Histogram {
+            data_points: family
+                .get_metric()
+                .iter()
+                .map(|m| {
+                    let histogram = m.get_histogram();
+                    // Prometheus buckets are cumulative, while OTLP buckets are not, and have an
+                    // implicit last bucket for values above the last bound.
+                    let buckets: Vec<_> = histogram
+                        .get_bucket()
+                        .iter()
+                        .filter(|b| b.get_upper_bound().is_finite())
+                        .collect();
+                    let mut bucket_counts = Vec::with_capacity(buckets.len() + 1);
+                    let mut previous = 0;
+                    for bucket in &buckets {
+                        let count = bucket.get_cumulative_count();
+                        bucket_counts.push(count.saturating_sub(previous));
+                        previous = count;
+                    }
+                    bucket_counts.push(histogram.get_sample_count().saturating_sub(previous));
+                    HistogramDataPoint {
+                        attributes: labels(m),
+                        start_time_unix_nano,
+                        time_unix_nano,
+                        count: histogram.get_sample_count(),
+                        sum: Some(histogram.get_sample_sum()),
+                        bucket_counts,
+                        explicit_bounds: buckets.iter().map(|b| b.get_upper_bound()).collect(),
+                        ..Default::default()
+                    }
+                })
+                .collect(),
+            aggregation_temporality: AggregationTemporality::Cumulative as i32,
+        }),
+        MetricType::SUMMARY => metric::Data::Summary(Summary {
+            data_points: family
+                .get_metric()
+                .iter()
+                .map(|m| {
+                    let summary = m.get_summary();
+                    SummaryDataPoint {
+                        attributes: labels(m),
+                        start_time_unix_nano,
+                        time_unix_nano,
+                        count: summary.get_sample_count(),
+                        sum: summary.get_sample_sum(),
+                        quantile_values: summary
+                            .get_quantile()
+                            .iter()
+                            .map(|q| summary_data_point::ValueAtQuantile {
+                                quantile: q.get_quantile(),
+                                value: q.get_value(),
+                            })
+                            .collect(),
+                        ..Default::default()
+                    }
+                })
+                .collect(),
+        }),
+    };
+    Metric {
+        name: family.get_name().to_owned(),
+        description: family.get_help().to_owned(),
+        data: Some(data),
+        ..Default::default()
+    }
+}
+
+fn labels(metric: &prometheus::proto::Metric) -> Vec<KeyValue> {
+    metric
+        .get_label()
+        .iter()
+        .map(|label| string_attribute(label.get_name(), label.get_value().to_owned()))
+        .collect()
+}
+
+fn number_data_point(
+    metric: &prometheus::proto::Metric,
+    value: f64,
+    start_time_unix_nano: u64,
+    time_unix_nano: u64,
+) -> NumberDataPoint {
+    NumberDataPoint {
+        attributes: labels(metric),
+        start_time_unix_nano,
+        time_unix_nano,
+        value: Some(number_data_point::Value::AsDouble(value)),
+        ..Default::default()
+    }
+}
+
+/// A layer exporting log events over OTLP. Events are buffered and exported in batches by a
+/// background task, and dropped if the buffer is full, so that logging never blocks.
+pub(crate) struct OtlpLogLayer {
+    sender: mpsc::Sender<LogRecord>,
+}
+
+impl OtlpLogLayer {
+    pub fn spawn(endpoint: String, resource: OtlpResource) -> Self {
+        let (sender, mut receiver) = mpsc::channel(LOG_BUFFER_SIZE);
+        tokio::spawn(async move {
+            let mut client = None;
+            while let Some(record) = receiver.recv().await {
+                let mut log_records = vec![record];
+                while log_records.len() < MAX_LOG_BATCH_SIZE {
+                    match receiver.try_recv() {
+                        Ok(record) => log_records.push(record),
+                        Err(_) => break,
+                    }
+                }
+                let request = ExportLogsServiceRequest {
+                    resource_logs: vec![ResourceLogs {
+                        resource: Some(resource.to_proto()),
+                        scope_logs: vec![ScopeLogs {
+                            scope: Some(scope()),
+                            log_records,
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }],
+                };
+                if client.is_none() {
+                    match LogsServiceClient::connect(endpoint.clone()).await {
+                        Ok(c) => client = Some(c),
+                        Err(e) => {
+                            warn!("failed to connect to OTLP endpoint {}: {}", endpoint, e);
+                            continue;
+                        }
+                    }
+                }
+                if let Err(e) = client.as_mut().unwrap().export(request).await {
+                    warn!("failed to export logs over OTLP: {}", e);
+                    client = None;
+                }
+            }
+        });
+        Self { sender }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for OtlpLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if IGNORED_LOG_TARGETS
+            .iter()
+            .any(|target| metadata.target().starts_with(target))
+        {
+            return;
+        }
+
+        let mut visitor = LogRecordVisitor::default();
+        event.record(&mut visitor);
+        let mut attributes = visitor.attributes;
+        attributes.push(string_attribute(
+            "code.namespace",
+            metadata.target().to_owned(),
+        ));
+        if let Some(file) = metadata.file() {
+            attributes.push(string_attribute("code.filepath", file.to_owned()));
+        }
+        if let Some(line) = metadata.line() {
+            attributes.push(KeyValue {
+                key: "code.lineno".to_owned(),
+                value: Some(AnyValue {
+                    value: Some(any_value::Value::IntValue(line as i64)),
+                }),
+            });
+        }
+
+        let now = unix_nanos(SystemTime::now());
+        let level = *metadata.level();
+        let record = LogRecord {
+            time_unix_nano: now,
+            observed_time_unix_nano: now,
+            severity_number: severity_number(level) as i32,
+            severity_text: level.to_string(),
+            body: visitor.message.map(|message| AnyValue {
+                value: Some(any_value::Value::StringValue(message)),
+            }),
+            attributes,
+            ..Default::default()
+        };
+        // Drop the record rather than block if the exporter can't keep up.
+        let _ = self.sender.try_send(record);
+    }
+}
+
+fn severity_number(level: Level) -> SeverityNumber {
+    match level {
+        Level::TRACE => SeverityNumber::Trace,
+        Level::DEBUG => SeverityNumber::Debug,
+        Level::INFO => SeverityNumber::Info,
+        Level::WARN => SeverityNumber::Warn,
+        Level::ERROR => SeverityNumber::Error,
+    }
+}
+
+#[derive(Default)]
+struct LogRecordVisitor {
+    message: Option<String>,
+    attributes: Vec<KeyValue>,
+}
+
+impl LogRecordVisitor {
+    fn record(&mut self, field: &Field, value: any_value::Value) {
+        if field.name() == "message" {
+            if let any_value::Value::StringValue(message) = value {
+                self.message = Some(message);
+                return;
+            }
+        }
+        self.attributes.push(KeyValue {
+            key: field.name().to_owned(),
+            value: Some(AnyValue { value: Some(value) }),
+        });
+    }
+}
+
+impl Visit for LogRecordVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record(field, any_value::Value::DoubleValue(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record(field, any_value::Value::IntValue(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match i64::try_from(value) {
+            Ok(value) => self.record(field, any_value::Value::IntValue(value)),
+            Err(_) => self.record(field, any_value::Value::StringValue(value.to_string())),
+        }
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record(field, any_value::Value::BoolValue(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, any_value::Value::StringValue(value.to_owned()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.record(field, any_value::Value::StringValue(format!("{:?}", value)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{
+        register_histogram_with_registry, register_int_counter_with_registry, Registry,
+    };
+
+    #[test]
+    fn test_histogram_buckets_are_not_cumulative() {
+        let registry = Registry::new();
+        let histogram = register_histogram_with_registry!(
+            "latency",
+            "Latency of something",
+            vec![1.0, 2.0, 4.0],
+            registry
+        )
+        .unwrap();
+        for value in [0.5, 0.5, 1.5, 3.0, 10.0] {
+            histogram.observe(value);
+        }
+
+        let metric = metric_family_to_proto(&registry.gather()[0], 1, 2);
+        let Some(metric::Data::Histogram(histogram)) = metric.data else {
+            panic!("Expected a histogram, got {:?}", metric.data);
+        };
+        let point = &histogram.data_points[0];
+        assert_eq!(point.explicit_bounds, vec![1.0, 2.0, 4.0]);
+        assert_eq!(point.bucket_counts, vec![2, 1, 1, 1]);
+        assert_eq!(point.count, 5);
+        assert_eq!(point.sum, Some(15.5));
+    }
+
+    #[test]
+    fn test_start_time_of_each_registry() {
+        let registries: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|start_time_unix_nano| {
+                let registry = Registry::new();
+                register_int_counter_with_registry!(
+                    format!("counter_{start_time_unix_nano}"),
+                    "Count of something",
+                    registry
+                )
+                .unwrap();
+                MetricsRegistry {
+                    registry,
+                    start_time_unix_nano,
+                }
+            })
+            .collect();
+        let resource = OtlpResource::new("test".to_owned(), &[]);
+
+        let request = metrics_request(&registries, &resource, 3);
+        let metrics = &request.resource_metrics[0].scope_metrics[0].metrics;
+        let start_times: Vec<_> = metrics
+            .iter()
+            .map(|metric| {
+                let Some(metric::Data::Sum(sum)) = &metric.data else {
+                    panic!("Expected a sum, got {:?}", metric.data);
+                };
+                (
+                    metric.name.as_str(),
+                    sum.data_points[0].start_time_unix_nano,
+                )
+            })
+            .collect();
+        assert_eq!(start_times, vec![("counter_1", 1), ("counter_2", 2)]);
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use opentelemetry_proto::tonic::collector::logs::v1::{
+    logs_service_server::{LogsService, LogsServiceServer},
+    ExportLogsServiceRequest, ExportLogsServiceResponse,
+};
+use opentelemetry_proto::tonic::collector::metrics::v1::{
+    metrics_service_server::{MetricsService, MetricsServiceServer},
+    ExportMetricsServiceRequest, ExportMetricsServiceResponse,
+};
+use opentelemetry_proto::tonic::common::v1::{any_value, KeyValue};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use telemetry_subscribers::TelemetryConfig;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+/// Stands in for an OTLP collector, recording the requests it receives.
+#[derive(Clone, Default)]
+struct Collector {
+    metrics: Arc<Mutex<Vec<ExportMetricsServiceRequest>>>,
+    logs: Arc<Mutex<Vec<ExportLogsServiceRequest>>>,
+}
+
+#[tonic::async_trait]
+impl MetricsService for Collector {
+    async fn export(
+        &self,
+        request: Request<ExportMetricsServiceRequest>,
+    ) -> Result<Response<ExportMetricsServiceResponse>, Status> {
+        self.metrics.lock().unwrap().push(request.into_inner());
+        Ok(Response::new(ExportMetricsServiceResponse::default()))
+    }
+}
+
+#[tonic::async_trait]
+impl LogsService for Collector {
+    async fn export(
+        &self,
+        request: Request<ExportLogsServiceRequest>,
+    ) -> Result<Response<ExportLogsServiceResponse>, Status> {
+        self.logs.lock().unwrap().push(request.into_inner());
+        Ok(Response::new(ExportLogsServiceResponse::default()))
+    }
+}
+
+fn string_value(attributes: &[KeyValue], key: &str) -> Option<String> {
+    attributes
+        .iter()
+        .find(|a| a.key == key)
+        .and_then(|a| a.value.as_ref()?.value.clone())
+        .and_then(|v| match v {
+            any_value::Value::StringValue(s) => Some(s),
+            _ => None,
+        })
+}
+
+fn resource_value(resource: &Option<Resource>, key: &str) -> Option<String> {
+    string_value(&resource.as_ref()?.attributes, key)
+}
+
+/// Polls until `condition` holds, or fails after 10 seconds.
+async fn wait_for(mut condition: impl FnMut() -> bool) {
+    for _ in 0..100 {
+        if condition() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Timed out waiting for the collector to receive exports");
+}
+
+#[tokio::test]
+async fn export_metrics_and_logs() {
+    let collector = Collector::default();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(MetricsServiceServer::new(collector.clone()))
+            .add_service(LogsServiceServer::new(collector.clone()))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    let registry = prometheus::Registry::new();
+    let counter =
+        prometheus::register_int_counter_with_registry!("requests", "Requests served", registry)
+            .unwrap();
+    counter.inc_by(3);
+
+    let mut config = TelemetryConfig::new()
+        .with_otlp_endpoint(&endpoint)
+        .with_otlp_metrics_registry(&registry)
+        .with_otlp_logs()
+        .with_otlp_resource_attribute("sui.node.name", "node-0")
+        .with_otlp_resource_attribute("sui.chain_id", "4c78adac");
+    config.otlp_metrics_interval = Some(Duration::from_millis(100));
+    config.panic_hook = false;
+    let (_guard, handle) = config.init();
+
+    info!(epoch = 1, "Hello collector");
+    wait_for(|| {
+        collector.logs.lock().unwrap().iter().any(|request| {
+            request.resource_logs.iter().any(|resource_logs| {
+                resource_value(&resource_logs.resource, "sui.node.name").as_deref()
+                    == Some("node-0")
+                    && resource_logs.scope_logs.iter().any(|scope_logs| {
+                        scope_logs.log_records.iter().any(|record| {
+                            record.severity_text == "INFO"
+                                && matches!(
+                                    record.body.as_ref().and_then(|b| b.value.clone()),
+                                    Some(any_value::Value::StringValue(body)) if body == "Hello collector"
+                                )
+                        })
+                    })
+            })
+        })
+    })
+    .await;
+
+    let later_registry = prometheus::Registry::new();
+    prometheus::register_int_gauge_with_registry!("pending", "Pending requests", later_registry)
+        .unwrap()
+        .set(7);
+    handle.add_otlp_metrics_registry(&later_registry);
+    handle.set_otlp_resource_attribute("sui.epoch", "2");
+    wait_for(|| {
+        collector.metrics.lock().unwrap().iter().any(|request| {
+            request.resource_metrics.iter().any(|resource_metrics| {
+                let names: Vec<_> = resource_metrics
+                    .scope_metrics
+                    .iter()
+                    .flat_map(|s| s.metrics.iter().map(|m| m.name.as_str()))
+                    .collect();
+                resource_value(&resource_metrics.resource, "sui.chain_id").as_deref()
+                    == Some("4c78adac")
+                    && resource_value(&resource_metrics.resource, "sui.epoch").as_deref()
+                        == Some("2")
+                    && names.contains(&"requests")
+                    && names.contains(&"pending")
+            })
+        })
+    })
+    .await;
+}
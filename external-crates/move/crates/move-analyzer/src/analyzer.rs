@@ -19,7 +19,7 @@ use std::{
 };
 
 use crate::{
-    completions::on_completion_request, context::Context, inlay_hints, symbols,
+    completions::on_completion_request, context::Context, formatting, inlay_hints, symbols,
     vfs::on_text_document_sync_notification,
 };
 use url::Url;
@@ -108,6 +108,7 @@ pub fn run() {
                 resolve_provider: None,
             },
         ))),
+        document_formatting_provider: Some(OneOf::Left(true)),
         ..Default::default()
     })
     .expect("could not serialize server capabilities");
@@ -319,6 +320,9 @@ fn on_request(
         lsp_types::request::InlayHintRequest::METHOD => {
             inlay_hints::on_inlay_hint_request(context, request);
         }
+        lsp_types::request::Formatting::METHOD => {
+            formatting::on_formatting_request(context, request, ide_files_root.clone());
+        }
         lsp_types::request::Shutdown::METHOD => {
             eprintln!("Shutdown request received");
             let response =
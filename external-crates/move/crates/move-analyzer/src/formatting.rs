@@ -0,0 +1,75 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{context::Context, symbols::SymbolicatorRunner};
+use lsp_server::Request;
+use lsp_types::{DocumentFormattingParams, Position, Range, TextEdit};
+use move_formatter::{format_source, package_settings};
+use std::path::Path;
+use vfs::VfsPath;
+
+/// Handles formatting request of the language server
+pub fn on_formatting_request(context: &Context, request: &Request, ide_files_root: VfsPath) {
+    let parameters = serde_json::from_value::<DocumentFormattingParams>(request.params.clone())
+        .expect("could not deserialize formatting request");
+
+    let fpath = parameters.text_document.uri.to_file_path().unwrap();
+    eprintln!("formatting_request: {:?}", fpath);
+
+    // a `null` result tells the client that the file could not be formatted
+    let edits = formatting_edits(&fpath, ide_files_root);
+    let response = lsp_server::Response::new_ok(request.id.clone(), edits);
+    if let Err(err) = context
+        .connection
+        .sender
+        .send(lsp_server::Message::Response(response))
+    {
+        eprintln!("could not send formatting response: {:?}", err);
+    }
+}
+
+fn formatting_edits(fpath: &Path, ide_files_root: VfsPath) -> Option<Vec<TextEdit>> {
+    // prefer the contents of the buffer being edited over the file on disk
+    let source = ide_files_root
+        .join(fpath.to_string_lossy())
+        .ok()
+        .and_then(|vfs_path| vfs_path.read_to_string().ok())
+        .or_else(|| std::fs::read_to_string(fpath).ok())?;
+
+    let pkg_path = SymbolicatorRunner::root_dir(fpath)?;
+    let (edition, config) = match package_settings(&pkg_path, None) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("could not read formatting settings: {:?}", err);
+            return None;
+        }
+    };
+
+    let formatted = match format_source(&source, edition, &config) {
+        Ok(formatted) => formatted,
+        Err(err) => {
+            eprintln!("could not format {:?}: {}", fpath, err);
+            return None;
+        }
+    };
+
+    if formatted == source {
+        return Some(vec![]);
+    }
+
+    Some(vec![TextEdit {
+        range: Range {
+            start: Position::new(0, 0),
+            end: end_position(&source),
+        },
+        new_text: formatted,
+    }])
+}
+
+/// The position just past the end of `text`, with the character offset in UTF-16 code units as
+/// LSP expects by default.
+fn end_position(text: &str) -> Position {
+    let line = text.matches('\n').count();
+    let last_line = text.rsplit('\n').next().unwrap_or_default();
+    Position::new(line as u32, last_line.encode_utf16().count() as u32)
+}
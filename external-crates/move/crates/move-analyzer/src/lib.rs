@@ -11,6 +11,7 @@ pub mod compiler_info;
 pub mod completions;
 pub mod context;
 pub mod diagnostics;
+pub mod formatting;
 pub mod inlay_hints;
 pub mod symbols;
 pub mod utils;
@@ -5,12 +5,24 @@ use super::reroot_path;
 use clap::*;
 use move_compiler::compiled_unit::NamedCompiledModule;
 use move_coverage::{
-    coverage_map::CoverageMap, format_csv_summary, format_human_summary,
+    coverage_map::CoverageMap, export::CoverageReport, format_csv_summary, format_human_summary,
     source_coverage::SourceCoverageBuilder, summary::summarize_inst_cov,
 };
 use move_disassembler::disassembler::Disassembler;
 use move_package::BuildConfig;
-use std::path::Path;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ExportFormat {
+    /// LCOV tracefile
+    Lcov,
+    /// Cobertura XML report
+    Cobertura,
+}
 
 #[derive(Parser)]
 pub enum CoverageSummaryOptions {
@@ -36,6 +48,16 @@ pub enum CoverageSummaryOptions {
         #[clap(long = "module")]
         module_name: String,
     },
+    /// Export line and branch coverage for all modules in this package, for use by other tools
+    #[clap(name = "export")]
+    Export {
+        /// Format of the exported coverage
+        #[clap(long = "format", value_enum, default_value = "lcov")]
+        format: ExportFormat,
+        /// File to write the coverage to, instead of standard output
+        #[clap(long = "output-file", short = 'o')]
+        output_file: Option<PathBuf>,
+    },
 }
 
 /// Inspect test coverage for this package. A previous test run with the `--coverage` flag must
@@ -95,6 +117,29 @@ impl Coverage {
                 disassembler.add_coverage_map(coverage_map.to_unified_exec_map());
                 println!("{}", disassembler.disassemble()?);
             }
+            CoverageSummaryOptions::Export {
+                format,
+                output_file,
+            } => {
+                let coverage_map = coverage_map.to_unified_exec_map();
+                let mut report = CoverageReport::new();
+                for unit in package.root_modules() {
+                    let NamedCompiledModule {
+                        module, source_map, ..
+                    } = &unit.unit;
+                    report.add_module(module, &coverage_map, source_map, &unit.source_path)?;
+                }
+
+                let mut writer: Box<dyn Write> = match output_file {
+                    Some(output_file) => Box::new(BufWriter::new(File::create(output_file)?)),
+                    None => Box::new(std::io::stdout()),
+                };
+                match format {
+                    ExportFormat::Lcov => report.write_lcov(&mut writer)?,
+                    ExportFormat::Cobertura => report.write_cobertura(&mut writer, &path)?,
+                }
+                writer.flush()?;
+            }
         }
         Ok(())
     }
@@ -0,0 +1,86 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::reroot_path;
+use clap::*;
+use move_command_line_common::files::{find_move_filenames, FileHash};
+use move_compiler::{
+    diagnostics::report_diagnostics_to_buffer_with_env_color, shared::files::MappedFiles,
+};
+use move_formatter::{format_source, package_settings, FormatError};
+use move_package::{source_package::layout::SourcePackageLayout, BuildConfig};
+use std::{io::Write, path::Path, sync::Arc};
+
+/// Format the Move sources of the package at `path`. If no path is provided defaults to current directory.
+///
+/// Settings are read from the `[format]` section of the package's manifest.
+#[derive(Parser)]
+#[clap(name = "fmt")]
+pub struct Fmt {
+    /// Check that the sources are formatted, without changing them. Fails if any are not.
+    #[clap(long = "check")]
+    pub check: bool,
+}
+
+impl Fmt {
+    pub fn execute(self, path: Option<&Path>, config: BuildConfig) -> anyhow::Result<()> {
+        let rerooted_path = reroot_path(path)?;
+        let (edition, format_config) = package_settings(&rerooted_path, config.default_edition)?;
+
+        let dirs: Vec<_> = [
+            SourcePackageLayout::Sources,
+            SourcePackageLayout::Tests,
+            SourcePackageLayout::Scripts,
+            SourcePackageLayout::Examples,
+        ]
+        .iter()
+        .map(|layout| rerooted_path.join(layout.path()))
+        .filter(|dir| dir.exists())
+        .collect();
+
+        let mut unformatted = vec![];
+        let mut failed = 0;
+        for file in find_move_filenames(&dirs, false)? {
+            let source = std::fs::read_to_string(&file)?;
+            match format_source(&source, edition, &format_config) {
+                Ok(formatted) if formatted == source => {}
+                Ok(_) if self.check => unformatted.push(file),
+                Ok(formatted) => {
+                    std::fs::write(&file, formatted)?;
+                    println!("Formatted {file}");
+                }
+                Err(FormatError::Syntax(diags)) => {
+                    let mut files = MappedFiles::empty();
+                    files.add(
+                        FileHash::new(&source),
+                        file.as_str().into(),
+                        Arc::from(source),
+                    );
+                    let report = report_diagnostics_to_buffer_with_env_color(&files, diags);
+                    std::io::stderr().write_all(&report)?;
+                    failed += 1;
+                }
+                Err(e) => {
+                    eprintln!("Failed to format {file}: {e}");
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            anyhow::bail!("Failed to format {failed} file(s)");
+        }
+
+        if !unformatted.is_empty() {
+            for file in &unformatted {
+                println!("{file} is not formatted");
+            }
+            anyhow::bail!(
+                "{} file(s) are not formatted, run the command without --check to format them",
+                unformatted.len()
+            );
+        }
+
+        Ok(())
+    }
+}
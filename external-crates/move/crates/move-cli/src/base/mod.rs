@@ -5,10 +5,13 @@ pub mod build;
 pub mod coverage;
 pub mod disassemble;
 pub mod docgen;
+pub mod fmt;
 pub mod info;
 pub mod migrate;
+pub mod mutate;
 pub mod new;
 pub mod test;
+pub mod vendor;
 
 use move_package::source_package::layout::SourcePackageLayout;
 use std::path::{Path, PathBuf};
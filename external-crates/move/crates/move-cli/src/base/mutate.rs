@@ -0,0 +1,253 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{reroot_path, test::build_test_plan};
+use crate::NativeFunctionRecord;
+use anyhow::{bail, Result};
+use clap::*;
+use move_bytecode_verifier::verify_module_unmetered;
+use move_compiler::unit_test::TestPlan;
+use move_core_types::{
+    identifier::{IdentStr, Identifier},
+    language_storage::ModuleId,
+};
+use move_package::BuildConfig;
+use move_unit_test::{
+    mutation::{self, Mutation},
+    UnitTestingConfig,
+};
+use move_vm_test_utils::gas_schedule::CostTable;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+    path::Path,
+};
+
+/// Run the unit tests of this package against mutated versions of its code, and report the
+/// mutations that no test catches.
+///
+/// Each mutation changes a single operation of a non-test function: a comparison is negated or
+/// has its boundary shifted, an arithmetic, bitwise or logical operator is swapped, an `assert!`
+/// is removed, or a literal is replaced. A mutant is killed if at least one test fails against
+/// it, and survives otherwise.
+#[derive(Parser)]
+#[clap(name = "mutate")]
+pub struct Mutate {
+    /// Only mutate functions whose fully qualified (<addr>::<module_name>::<fn_name>) name
+    /// contains this string.
+    #[clap(name = "filter")]
+    pub filter: Option<String>,
+    /// Bound the amount of gas used by any one test.
+    #[clap(name = "gas-limit", short = 'i', long = "gas-limit")]
+    pub gas_limit: Option<u64>,
+    /// Number of threads to use for running tests.
+    #[clap(
+        name = "num-threads",
+        default_value = "8",
+        short = 't',
+        long = "threads"
+    )]
+    pub num_threads: usize,
+    /// Fail if the percentage of killed mutants is lower than this.
+    #[clap(name = "min-score", long = "min-score")]
+    pub min_score: Option<f64>,
+}
+
+/// The outcome of running the tests against a mutant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MutantStatus {
+    /// At least one test failed.
+    Killed,
+    /// All tests passed.
+    Survived,
+    /// The mutated module does not pass the bytecode verifier, so it could never be published.
+    Invalid,
+}
+
+impl Mutate {
+    pub fn execute(
+        self,
+        path: Option<&Path>,
+        config: BuildConfig,
+        natives: Vec<NativeFunctionRecord>,
+        cost_table: Option<CostTable>,
+    ) -> Result<()> {
+        let rerooted_path = reroot_path(path)?;
+        let mut stdout = io::stdout();
+
+        // Only code that is part of the published package is mutated: test modules, and test and
+        // test-only functions, are found by their absence from a build without test code.
+        let mut production_functions: BTreeMap<ModuleId, BTreeSet<Identifier>> = BTreeMap::new();
+        let mut build_config = config.clone();
+        build_config.test_mode = false;
+        let package = build_config.compile_package(&rerooted_path, &mut Vec::new())?;
+        for unit in package.root_modules() {
+            let module = &unit.unit.module;
+            let functions = module.function_defs().iter().map(|function_def| {
+                let handle = module.function_handle_at(function_def.function);
+                module.identifier_at(handle.name).to_owned()
+            });
+            production_functions.insert(module.self_id(), functions.collect());
+        }
+
+        let mut unit_test_config = UnitTestingConfig {
+            gas_limit: self.gas_limit,
+            num_threads: self.num_threads,
+            ..UnitTestingConfig::default_with_bound(None)
+        };
+        let (test_plan, _) =
+            build_test_plan(&rerooted_path, config, &mut unit_test_config, &mut stdout)?;
+
+        // The mutants are only meaningful if the tests pass against the original code.
+        let (_, passed) = unit_test_config.run_and_report_unit_tests(
+            copy_test_plan(&test_plan),
+            Some(natives.clone()),
+            cost_table.clone(),
+            Vec::new(),
+        )?;
+        if !passed {
+            bail!("The unit tests of this package fail without mutations; fix them first");
+        }
+
+        let mut mutants = vec![];
+        for (module_id, info) in &test_plan.module_info {
+            let Some(functions) = production_functions.get(module_id) else {
+                continue;
+            };
+            let should_mutate = |name: &IdentStr| {
+                functions.contains(name)
+                    && self.filter.as_ref().map_or(true, |filter| {
+                        format!("{}::{}", module_id.short_str_lossless(), name).contains(filter)
+                    })
+            };
+            for mutation in mutation::mutations(&info.module, should_mutate) {
+                mutants.push((module_id.clone(), mutation));
+            }
+        }
+        writeln!(stdout, "MUTATING {} mutants", mutants.len())?;
+
+        let mut survivors = vec![];
+        let (mut killed, mut invalid) = (0, 0);
+        for (module_id, mutation) in &mutants {
+            let status = run_mutant(
+                &test_plan,
+                module_id,
+                mutation,
+                &unit_test_config,
+                &natives,
+                &cost_table,
+            )?;
+            let location = mutation_location(&test_plan, module_id, mutation);
+            match status {
+                MutantStatus::Killed => {
+                    killed += 1;
+                    writeln!(stdout, "[ KILLED   ] {location}: {}", mutation.description)?;
+                }
+                MutantStatus::Survived => {
+                    writeln!(stdout, "[ SURVIVED ] {location}: {}", mutation.description)?;
+                    survivors.push((location, mutation));
+                }
+                MutantStatus::Invalid => invalid += 1,
+            }
+        }
+
+        if !survivors.is_empty() {
+            writeln!(stdout, "\nSurviving mutants:\n")?;
+            for (location, mutation) in &survivors {
+                writeln!(
+                    stdout,
+                    "┌── {location}\n│ {} ({})\n└──────────────────\n",
+                    mutation.description, mutation.operator
+                )?;
+            }
+        }
+
+        let tested = killed + survivors.len();
+        let score = if tested == 0 {
+            100.0
+        } else {
+            killed as f64 * 100.0 / tested as f64
+        };
+        writeln!(
+            stdout,
+            "Mutation score: {score:.2}% ({killed} killed, {} survived, {invalid} not verifiable)",
+            survivors.len()
+        )?;
+
+        if let Some(min_score) = self.min_score {
+            if score < min_score {
+                bail!("Mutation score {score:.2}% is below the minimum of {min_score}%");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn run_mutant(
+    test_plan: &TestPlan,
+    module_id: &ModuleId,
+    mutation: &Mutation,
+    unit_test_config: &UnitTestingConfig,
+    natives: &[NativeFunctionRecord],
+    cost_table: &Option<CostTable>,
+) -> Result<MutantStatus> {
+    let mut mutant_plan = copy_test_plan(test_plan);
+    let info = mutant_plan
+        .module_info
+        .get_mut(module_id)
+        .expect("mutations are only made to modules in the test plan");
+    info.module = mutation.apply(&info.module);
+    if verify_module_unmetered(&info.module).is_err() {
+        return Ok(MutantStatus::Invalid);
+    }
+
+    let (_, passed) = unit_test_config.run_and_report_unit_tests(
+        mutant_plan,
+        Some(natives.to_vec()),
+        cost_table.clone(),
+        Vec::new(),
+    )?;
+    Ok(if passed {
+        MutantStatus::Survived
+    } else {
+        MutantStatus::Killed
+    })
+}
+
+fn copy_test_plan(test_plan: &TestPlan) -> TestPlan {
+    TestPlan {
+        mapped_files: test_plan.mapped_files.clone(),
+        module_tests: test_plan.module_tests.clone(),
+        module_info: test_plan.module_info.clone(),
+        bytecode_deps_modules: test_plan.bytecode_deps_modules.clone(),
+    }
+}
+
+/// The `file:line:column` of the mutated code, falling back to the function it is in.
+fn mutation_location(test_plan: &TestPlan, module_id: &ModuleId, mutation: &Mutation) -> String {
+    let info = &test_plan.module_info[module_id];
+    let function = {
+        let function_def = info.module.function_def_at(mutation.function);
+        let handle = info.module.function_handle_at(function_def.function);
+        format!(
+            "{}::{}",
+            module_id.short_str_lossless(),
+            info.module.identifier_at(handle.name)
+        )
+    };
+    let loc = info
+        .source_map
+        .get_function_source_map(mutation.function)
+        .ok()
+        .and_then(|function_map| function_map.get_code_location(mutation.offset));
+    let mapped_files = &test_plan.mapped_files;
+    match loc.and_then(|loc| Some((loc, mapped_files.position_opt(&loc)?))) {
+        Some((loc, position)) => format!(
+            "{}:{}:{} in {function}",
+            mapped_files.filename(&loc.file_hash()),
+            position.start.user_line(),
+            position.start.user_column(),
+        ),
+        None => format!("{function} at offset {}", mutation.offset),
+    }
+}
@@ -65,6 +65,17 @@ pub struct Test {
     #[clap(name = "seed", long = "seed")]
     pub seed: Option<u64>,
 
+    /// Rerun a single failing case of a #[random_test]: run only the test named
+    /// `<module_name>::<test_name>`, once, with the arguments generated from `--seed`. If it
+    /// fails, those arguments are shrunk again to the same minimal counterexample.
+    #[clap(
+        name = "replay",
+        long = "replay",
+        value_name = "TEST",
+        requires = "seed"
+    )]
+    pub replay: Option<String>,
+
     /// The number of iterations to run each test that uses generated values (only used with #[random_test]).
     #[clap(name = "rand-num-iters", long = "rand-num-iters")]
     pub rand_num_iters: Option<u64>,
@@ -72,6 +83,15 @@ pub struct Test {
     // Enable tracing for tests
     #[clap(long = "trace-execution", value_name = "PATH")]
     pub trace_execution: Option<Option<String>>,
+
+    /// Write a report of every test run to PATH, in FORMAT ('junit' or 'json').
+    #[clap(long = "report", num_args = 2, value_names = ["FORMAT", "PATH"])]
+    pub report: Option<Vec<String>>,
+
+    /// Write a gas profile of each test to PATH (by default, `gas-profiles`), in the speedscope
+    /// format, and rank the tests by the gas they used.
+    #[clap(long = "profile", value_name = "PATH")]
+    pub profile: Option<Option<String>>,
 }
 
 impl Test {
@@ -111,8 +131,11 @@ impl Test {
             verbose_mode,
             compute_coverage: _,
             seed,
+            replay,
             rand_num_iters,
             trace_execution,
+            report,
+            profile,
         } = self;
         UnitTestingConfig {
             gas_limit,
@@ -122,8 +145,11 @@ impl Test {
             report_statistics,
             verbose: verbose_mode,
             seed,
+            replay,
             rand_num_iters,
             trace_execution,
+            report,
+            profile,
             ..UnitTestingConfig::default_with_bound(None)
         }
     }
@@ -138,13 +164,61 @@ pub enum UnitTestResult {
 
 pub fn run_move_unit_tests<W: Write + Send>(
     pkg_path: &Path,
-    mut build_config: move_package::BuildConfig,
+    build_config: move_package::BuildConfig,
     mut unit_test_config: UnitTestingConfig,
     natives: Vec<NativeFunctionRecord>,
     cost_table: Option<CostTable>,
     compute_coverage: bool,
     writer: &mut W,
 ) -> Result<(UnitTestResult, Option<Diagnostics>)> {
+    let (test_plan, warning_diags) =
+        build_test_plan(pkg_path, build_config, &mut unit_test_config, writer)?;
+    let no_tests = test_plan.module_tests.is_empty();
+
+    let trace_path = pkg_path.join(".trace");
+    let coverage_map_path = pkg_path
+        .join(".coverage_map")
+        .with_extension(MOVE_COVERAGE_MAP_EXTENSION);
+    let cleanup_trace = || {
+        if compute_coverage && trace_path.exists() {
+            std::fs::remove_file(&trace_path).unwrap();
+        }
+    };
+
+    cleanup_trace();
+
+    // If we need to compute test coverage set the VM tracking environment variable since we will
+    // need this trace to construct the coverage information.
+    if compute_coverage {
+        std::env::set_var("MOVE_VM_TRACE", &trace_path);
+    }
+
+    // Run the tests. If any of the tests fail, then we don't produce a coverage report, so cleanup
+    // the trace files.
+    if !unit_test_config
+        .run_and_report_unit_tests(test_plan, Some(natives), cost_table, writer)?
+        .1
+    {
+        cleanup_trace();
+        return Ok((UnitTestResult::Failure, warning_diags));
+    }
+
+    // Compute the coverage map. This will be used by other commands after this.
+    if compute_coverage && !no_tests {
+        let coverage_map = CoverageMap::from_trace_file(trace_path);
+        output_map_to_file(coverage_map_path, &coverage_map).unwrap();
+    }
+    Ok((UnitTestResult::Success, warning_diags))
+}
+
+/// Compile the package at `pkg_path` in test mode and build the plan of the unit tests to run,
+/// setting the named addresses of `unit_test_config` to the package's.
+pub fn build_test_plan<W: Write + Send>(
+    pkg_path: &Path,
+    mut build_config: move_package::BuildConfig,
+    unit_test_config: &mut UnitTestingConfig,
+    writer: &mut W,
+) -> Result<(TestPlan, Option<Diagnostics>)> {
     let mut test_plan = None;
     build_config.test_mode = true;
     build_config.dev_mode = true;
@@ -216,44 +290,13 @@ pub fn run_move_unit_tests<W: Write + Send>(
     })?;
 
     let (test_plan, mapped_files, units) = test_plan.unwrap();
-    let test_plan = test_plan.unwrap();
-    let no_tests = test_plan.is_empty();
-    let test_plan = TestPlan::new(test_plan, mapped_files, units, bytecode_deps_modules);
-
-    let trace_path = pkg_path.join(".trace");
-    let coverage_map_path = pkg_path
-        .join(".coverage_map")
-        .with_extension(MOVE_COVERAGE_MAP_EXTENSION);
-    let cleanup_trace = || {
-        if compute_coverage && trace_path.exists() {
-            std::fs::remove_file(&trace_path).unwrap();
-        }
-    };
-
-    cleanup_trace();
-
-    // If we need to compute test coverage set the VM tracking environment variable since we will
-    // need this trace to construct the coverage information.
-    if compute_coverage {
-        std::env::set_var("MOVE_VM_TRACE", &trace_path);
-    }
-
-    // Run the tests. If any of the tests fail, then we don't produce a coverage report, so cleanup
-    // the trace files.
-    if !unit_test_config
-        .run_and_report_unit_tests(test_plan, Some(natives), cost_table, writer)?
-        .1
-    {
-        cleanup_trace();
-        return Ok((UnitTestResult::Failure, warning_diags));
-    }
-
-    // Compute the coverage map. This will be used by other commands after this.
-    if compute_coverage && !no_tests {
-        let coverage_map = CoverageMap::from_trace_file(trace_path);
-        output_map_to_file(coverage_map_path, &coverage_map).unwrap();
-    }
-    Ok((UnitTestResult::Success, warning_diags))
+    let test_plan = TestPlan::new(
+        test_plan.unwrap(),
+        mapped_files,
+        units,
+        bytecode_deps_modules,
+    );
+    Ok((test_plan, warning_diags))
 }
 
 impl From<UnitTestResult> for ExitStatus {
@@ -0,0 +1,23 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::reroot_path;
+use clap::*;
+use move_package::BuildConfig;
+use std::path::Path;
+
+/// Copy the git and on-chain dependencies of the package into its `vendor` directory, and pin them
+/// in `Move.lock`, so that the package can be built without network access.
+///
+/// The vendored copies are used in place of the dependencies by all later builds, which fail if a
+/// vendored copy no longer matches the digest it is pinned to. Re-run this command to refresh them.
+#[derive(Parser)]
+#[clap(name = "vendor")]
+pub struct Vendor;
+
+impl Vendor {
+    pub fn execute(self, path: Option<&Path>, config: BuildConfig) -> anyhow::Result<()> {
+        let rerooted_path = reroot_path(path)?;
+        config.vendor_deps_for_package(&rerooted_path, &mut std::io::stdout())
+    }
+}
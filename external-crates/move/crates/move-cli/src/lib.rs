@@ -3,8 +3,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use base::{
-    build::Build, coverage::Coverage, disassemble::Disassemble, docgen::Docgen, info::Info,
-    migrate::Migrate, new::New, test::Test,
+    build::Build, coverage::Coverage, disassemble::Disassemble, docgen::Docgen, fmt::Fmt,
+    info::Info, migrate::Migrate, mutate::Mutate, new::New, test::Test, vendor::Vendor,
 };
 use move_package::BuildConfig;
 
@@ -60,10 +60,13 @@ pub enum Command {
     Coverage(Coverage),
     Disassemble(Disassemble),
     Docgen(Docgen),
+    Fmt(Fmt),
     Info(Info),
     Migrate(Migrate),
+    Mutate(Mutate),
     New(New),
     Test(Test),
+    Vendor(Vendor),
     /// Execute a sandbox command.
     #[clap(name = "sandbox")]
     Sandbox {
@@ -94,8 +97,15 @@ pub fn run_cli(
             c.execute(move_args.package_path.as_deref(), move_args.build_config)
         }
         Command::Docgen(c) => c.execute(move_args.package_path.as_deref(), move_args.build_config),
+        Command::Fmt(c) => c.execute(move_args.package_path.as_deref(), move_args.build_config),
         Command::Info(c) => c.execute(move_args.package_path.as_deref(), move_args.build_config),
         Command::Migrate(c) => c.execute(move_args.package_path.as_deref(), move_args.build_config),
+        Command::Mutate(c) => c.execute(
+            move_args.package_path.as_deref(),
+            move_args.build_config,
+            natives,
+            Some(cost_table.clone()),
+        ),
         Command::New(c) => c.execute_with_defaults(move_args.package_path.as_deref()),
         Command::Test(c) => c.execute(
             move_args.package_path.as_deref(),
@@ -103,6 +113,7 @@ pub fn run_cli(
             natives,
             Some(cost_table.clone()),
         ),
+        Command::Vendor(c) => c.execute(move_args.package_path.as_deref(), move_args.build_config),
         Command::Sandbox { storage_dir, cmd } => {
             cmd.handle_command(natives, cost_table, &move_args, &storage_dir)
         }
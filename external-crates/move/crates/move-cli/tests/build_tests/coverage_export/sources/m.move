@@ -0,0 +1,12 @@
+module 0x42::m {
+    public fun clamp(x: u64): u64 {
+        let mut y = x;
+        if (x > 10) {
+            y = 10;
+        };
+        y
+    }
+
+    #[test]
+    fun small() { clamp(3); }
+}
@@ -0,0 +1,10 @@
+module 0x42::m {
+    public fun is_less(a: u64, b: u64): bool {
+        a < b
+    }
+
+    #[test]
+    fun one_less_than_two() {
+        assert!(is_less(1, 2), 0);
+    }
+}
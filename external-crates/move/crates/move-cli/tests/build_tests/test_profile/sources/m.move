@@ -0,0 +1,9 @@
+module 0x42::m {
+    public fun inc(x: u64): u64 { x + 1 }
+
+    #[test]
+    fun empty() {}
+
+    #[test]
+    fun calls_inc() { inc(1); }
+}
@@ -61,3 +61,12 @@ pub fn try_render_constant(constant: &Constant) -> RenderResult {
         | SignatureToken::TypeParameter(_) => RenderResult::NotRendered,
     }
 }
+
+/// Escape `s` for use in XML text and double-quoted attribute values, as in the JUnit and
+/// Cobertura reports of test results and coverage.
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -423,7 +423,7 @@ impl<'env> DiagnosticReporter<'env> {
                     );
                     diag.add_note(help)
                 }
-                if self.flags.warnings_are_errors() {
+                if self.flags.warnings_are_errors() || self.warning_filters_scope.is_denied(&diag) {
                     diag = diag.set_severity(Severity::NonblockingError)
                 }
             }
@@ -100,6 +100,8 @@ unsafe impl Sync for WarningFilters {}
 /// Used to filter out diagnostics, specifically used for warning suppression
 pub struct WarningFiltersBuilder {
     filters: BTreeMap<ExternalPrefix, UnprefixedWarningFilters>,
+    /// Warnings that are reported as errors, unless they are also filtered out
+    denied: BTreeMap<ExternalPrefix, UnprefixedWarningFilters>,
     for_dependency: bool, // if false, the filters are used for source code
 }
 
@@ -192,6 +194,23 @@ impl WarningFiltersScope {
         }
     }
 
+    /// Whether a warning that is not filtered out should be reported as an error.
+    pub fn is_denied(&self, diag: &Diagnostic) -> bool {
+        let mut scope = &self.0;
+        loop {
+            match scope {
+                WarningFiltersScope_::Empty => return false,
+                WarningFiltersScope_::Static(filters) => return filters.is_denied(diag),
+                WarningFiltersScope_::Node(node) => {
+                    if node.filters.is_denied(diag) {
+                        return true;
+                    }
+                    scope = &node.prev;
+                }
+            }
+        }
+    }
+
     pub fn is_filtered_for_dependency(&self) -> bool {
         let mut scope = &self.0;
         loop {
@@ -238,6 +257,10 @@ impl WarningFilters {
         self.borrow().is_filtered(diag)
     }
 
+    pub fn is_denied(&self, diag: &Diagnostic) -> bool {
+        self.borrow().is_denied(diag)
+    }
+
     pub fn for_dependency(&self) -> bool {
         self.borrow().for_dependency()
     }
@@ -251,6 +274,7 @@ impl WarningFiltersBuilder {
     pub const fn new_for_source() -> Self {
         Self {
             filters: BTreeMap::new(),
+            denied: BTreeMap::new(),
             for_dependency: false,
         }
     }
@@ -258,6 +282,7 @@ impl WarningFiltersBuilder {
     pub const fn new_for_dependency() -> Self {
         Self {
             filters: BTreeMap::new(),
+            denied: BTreeMap::new(),
             for_dependency: true,
         }
     }
@@ -273,6 +298,13 @@ impl WarningFiltersBuilder {
             .is_some_and(|filters| filters.is_filtered_by_info(info))
     }
 
+    pub fn is_denied(&self, diag: &Diagnostic) -> bool {
+        let prefix = diag.info.external_prefix();
+        self.denied
+            .get(&prefix)
+            .is_some_and(|denied| denied.is_filtered_by_info(&diag.info))
+    }
+
     pub fn union(&mut self, other: &Self) {
         for (prefix, filters) in &other.filters {
             self.filters
@@ -280,6 +312,12 @@ impl WarningFiltersBuilder {
                 .or_insert_with(UnprefixedWarningFilters::new)
                 .union(filters);
         }
+        for (prefix, denied) in &other.denied {
+            self.denied
+                .entry(*prefix)
+                .or_insert_with(UnprefixedWarningFilters::new)
+                .union(denied);
+        }
         // if there is a dependency code filter on the stack, it means we are filtering dependent
         // code and this information must be preserved when stacking up additional filters (which
         // involves union of the current filter with the new one)
@@ -287,9 +325,22 @@ impl WarningFiltersBuilder {
     }
 
     pub fn add(&mut self, filter: WarningFilter) {
+        Self::add_to(&mut self.filters, filter)
+    }
+
+    /// Report the warnings matched by `filter` as errors, e.g. for lints set to `deny` for a
+    /// whole package.
+    pub fn add_denied(&mut self, filter: WarningFilter) {
+        Self::add_to(&mut self.denied, filter)
+    }
+
+    fn add_to(
+        filters: &mut BTreeMap<ExternalPrefix, UnprefixedWarningFilters>,
+        filter: WarningFilter,
+    ) {
         let (prefix, category, code, name) = match filter {
             WarningFilter::All(prefix) => {
-                self.filters.insert(prefix, UnprefixedWarningFilters::All);
+                filters.insert(prefix, UnprefixedWarningFilters::All);
                 return;
             }
             WarningFilter::Category {
@@ -304,7 +355,7 @@ impl WarningFiltersBuilder {
                 name,
             } => (prefix, category, Some(code), name),
         };
-        self.filters
+        filters
             .entry(prefix)
             .or_insert(UnprefixedWarningFilters::Empty)
             .add(category, code, name)
@@ -316,6 +367,7 @@ impl WarningFiltersBuilder {
                 None,
                 UnprefixedWarningFilters::unused_warnings_filter_for_test(),
             )]),
+            denied: BTreeMap::new(),
             for_dependency: false,
         }
     }
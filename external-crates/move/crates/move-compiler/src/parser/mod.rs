@@ -11,8 +11,10 @@ pub(crate) mod syntax;
 mod token_set;
 pub(crate) mod verification_attribute_filter;
 
+pub use syntax::parse_file_string;
+
 use crate::{
-    parser::{self, ast::PackageDefinition, syntax::parse_file_string},
+    parser::{self, ast::PackageDefinition},
     shared::{
         files::MappedFiles, CompilationEnv, IndexedVfsPackagePath, NamedAddressMapIndex,
         NamedAddressMaps,
@@ -163,28 +163,61 @@ pub fn known_filters() -> (Option<Symbol>, Vec<WarningFilter>) {
     (Some(ALLOW_ATTR_CATEGORY.into()), filters)
 }
 
+/// The lints, by the filter name of their warnings, with the level they start running at: lints
+/// at `LintLevel::Default` run without `--lint`, the others only run at `LintLevel::All`.
+const LINTS: &[(&str, LintLevel, fn() -> Visitor)] = &[
+    (SHARE_OWNED_FILTER_NAME, LintLevel::Default, || {
+        share_owned::ShareOwnedVerifier.visitor()
+    }),
+    (SELF_TRANSFER_FILTER_NAME, LintLevel::Default, || {
+        self_transfer::SelfTransferVerifier.visitor()
+    }),
+    (CUSTOM_STATE_CHANGE_FILTER_NAME, LintLevel::Default, || {
+        custom_state_change::CustomStateChangeVerifier.visitor()
+    }),
+    (COIN_FIELD_FILTER_NAME, LintLevel::Default, || {
+        coin_field::CoinFieldVisitor.visitor()
+    }),
+    (FREEZE_WRAPPED_FILTER_NAME, LintLevel::Default, || {
+        freeze_wrapped::FreezeWrappedVisitor.visitor()
+    }),
+    (COLLECTION_EQUALITY_FILTER_NAME, LintLevel::Default, || {
+        collection_equality::CollectionEqualityVisitor.visitor()
+    }),
+    (PUBLIC_RANDOM_FILTER_NAME, LintLevel::Default, || {
+        public_random::PublicRandomVisitor.visitor()
+    }),
+    (MISSING_KEY_FILTER_NAME, LintLevel::Default, || {
+        missing_key::MissingKeyVisitor.visitor()
+    }),
+    (FREEZING_CAPABILITY_FILTER_NAME, LintLevel::All, || {
+        freezing_capability::WarnFreezeCapability.visitor()
+    }),
+    (
+        PREFER_MUTABLE_TX_CONTEXT_FILTER_NAME,
+        LintLevel::All,
+        || public_mut_tx_context::PreferMutableTxContext.visitor(),
+    ),
+];
+
+/// Whether the lint whose warnings are filtered by `filter_name` runs at `LintLevel::Default`,
+/// rather than only at `LintLevel::All`.
+pub fn is_default_lint(filter_name: &str) -> bool {
+    LINTS
+        .iter()
+        .any(|(name, level, _)| *name == filter_name && *level == LintLevel::Default)
+}
+
 pub fn linter_visitors(level: LintLevel) -> Vec<Visitor> {
-    match level {
-        LintLevel::None => vec![],
-        LintLevel::Default => vec![
-            share_owned::ShareOwnedVerifier.visitor(),
-            self_transfer::SelfTransferVerifier.visitor(),
-            custom_state_change::CustomStateChangeVerifier.visitor(),
-            coin_field::CoinFieldVisitor.visitor(),
-            freeze_wrapped::FreezeWrappedVisitor.visitor(),
-            collection_equality::CollectionEqualityVisitor.visitor(),
-            public_random::PublicRandomVisitor.visitor(),
-            missing_key::MissingKeyVisitor.visitor(),
-        ],
-        LintLevel::All => {
-            let mut visitors = linter_visitors(LintLevel::Default);
-            visitors.extend([
-                freezing_capability::WarnFreezeCapability.visitor(),
-                public_mut_tx_context::PreferMutableTxContext.visitor(),
-            ]);
-            visitors
-        }
-    }
+    LINTS
+        .iter()
+        .filter(|(_, lint_level, _)| match level {
+            LintLevel::None => false,
+            LintLevel::Default => *lint_level == LintLevel::Default,
+            LintLevel::All => true,
+        })
+        .map(|(_, _, visitor)| visitor())
+        .collect()
 }
 
 pub fn base_type(t: &N::Type) -> Option<&N::Type> {
@@ -0,0 +1,381 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Export of line, branch and function coverage in the formats read by external tools: LCOV
+//! tracefiles and Cobertura XML reports.
+
+use crate::coverage_map::ExecCoverageMap;
+use anyhow::{bail, Result};
+use codespan::Files;
+use move_binary_format::{
+    file_format::{Bytecode, CodeOffset, FunctionDefinitionIndex},
+    CompiledModule,
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_command_line_common::display::escape_xml;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Coverage of a set of source files, built up one module at a time.
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    pub files: BTreeMap<PathBuf, FileCoverage>,
+}
+
+/// Coverage of a single source file, across all modules defined in it. Line numbers are 1-based.
+#[derive(Debug, Default)]
+pub struct FileCoverage {
+    pub modules: Vec<ModuleCoverage>,
+    /// Execution count of each line that has code on it.
+    pub lines: BTreeMap<u32, u64>,
+    /// The arms of each branch instruction, by line. An arm's count is `None` if the branch
+    /// itself was never executed. Branches whose arms' counts cannot be determined are left out.
+    pub branches: BTreeMap<u32, Vec<Option<u64>>>,
+}
+
+#[derive(Debug)]
+pub struct ModuleCoverage {
+    pub name: String,
+    pub functions: Vec<FunctionHits>,
+}
+
+#[derive(Debug)]
+pub struct FunctionHits {
+    pub name: String,
+    pub line: u32,
+    /// Number of times the function was called.
+    pub hits: u64,
+}
+
+impl CoverageReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the coverage of `module`, compiled from the source file at `source_path`, according
+    /// to a unified `coverage_map`.
+    ///
+    /// A line is attributed the highest execution count of the instructions that start on it.
+    ///
+    /// Execution counts are recorded per instruction rather than per edge, so the number of times
+    /// a branch arm was taken is only known if the branch is the only way to reach the arm's
+    /// first instruction, or if the counts of all the other arms are known (in which case the arm
+    /// was taken whenever they were not). Branches with arms that are neither are left out.
+    pub fn add_module(
+        &mut self,
+        module: &CompiledModule,
+        coverage_map: &ExecCoverageMap,
+        source_map: &SourceMap,
+        source_path: &Path,
+    ) -> Result<()> {
+        let file_contents = fs::read_to_string(source_path)?;
+        if !source_map.check(&file_contents) {
+            bail!(
+                "File contents of {} out of sync with source map",
+                source_path.display()
+            );
+        }
+
+        let mut files = Files::new();
+        let file_id = files.add(source_path.as_os_str().to_os_string(), file_contents);
+        let line_of = |byte_index: u32| files.location(file_id, byte_index).unwrap().line.0 + 1;
+
+        let module_id = module.self_id();
+        let module_map = coverage_map
+            .module_maps
+            .get(&(*module_id.address(), module_id.name().to_owned()));
+
+        let file = self.files.entry(source_path.to_path_buf()).or_default();
+        let mut functions = vec![];
+
+        for (function_def_idx, function_def) in module.function_defs().iter().enumerate() {
+            let Some(code_unit) = &function_def.code else {
+                continue;
+            };
+
+            let fn_handle = module.function_handle_at(function_def.function);
+            let fn_name = module.identifier_at(fn_handle.name);
+            let function_def_idx = FunctionDefinitionIndex(function_def_idx as u16);
+            let function_map = source_map.get_function_source_map(function_def_idx)?;
+            let function_coverage =
+                module_map.and_then(|module_map| module_map.get_function_coverage(fn_name));
+            let count = |offset: CodeOffset| -> u64 {
+                function_coverage
+                    .and_then(|coverage| coverage.get(&(offset as u64)))
+                    .copied()
+                    .unwrap_or(0)
+            };
+
+            functions.push(FunctionHits {
+                name: fn_name.to_string(),
+                line: line_of(function_map.definition_location.start()),
+                hits: count(0),
+            });
+
+            // Number of edges into each instruction, counting the function's entry.
+            let code = &code_unit.code;
+            let mut predecessors = vec![0usize; code.len()];
+            if let Some(entry) = predecessors.first_mut() {
+                *entry += 1;
+            }
+            for offset in 0..code.len() {
+                for successor in
+                    Bytecode::get_successors(offset as CodeOffset, code, &code_unit.jump_tables)
+                {
+                    predecessors[successor as usize] += 1;
+                }
+            }
+
+            for (offset, instruction) in code_unit.code.iter().enumerate() {
+                let offset = offset as CodeOffset;
+                let Some(loc) = function_map.get_code_location(offset) else {
+                    continue;
+                };
+
+                let line = line_of(loc.start());
+                let hits = file.lines.entry(line).or_insert(0);
+                *hits = (*hits).max(count(offset));
+
+                if !matches!(
+                    instruction,
+                    Bytecode::BrTrue(_) | Bytecode::BrFalse(_) | Bytecode::VariantSwitch(_)
+                ) {
+                    continue;
+                }
+
+                let successors = Bytecode::get_successors(offset, code, &code_unit.jump_tables);
+                let executed = count(offset);
+                if executed == 0 {
+                    let arms = successors.iter().map(|_| None);
+                    file.branches.entry(line).or_default().extend(arms);
+                    continue;
+                }
+
+                let mut arms: Vec<_> = successors
+                    .iter()
+                    .map(|s| (predecessors[*s as usize] == 1).then(|| count(*s)))
+                    .collect();
+
+                let mut unknown = arms.iter_mut().filter(|arm| arm.is_none());
+                match (unknown.next(), unknown.next()) {
+                    (None, _) => {}
+                    (Some(arm), None) => {
+                        let known: u64 = successors
+                            .iter()
+                            .filter(|s| predecessors[**s as usize] == 1)
+                            .map(|s| count(*s))
+                            .sum();
+                        *arm = Some(executed.saturating_sub(known));
+                    }
+                    (Some(_), Some(_)) => continue,
+                }
+
+                file.branches.entry(line).or_default().extend(arms);
+            }
+        }
+
+        file.modules.push(ModuleCoverage {
+            name: module_id.name().to_string(),
+            functions,
+        });
+        Ok(())
+    }
+
+    /// Write the report as an LCOV tracefile.
+    pub fn write_lcov<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (path, file) in &self.files {
+            writeln!(writer, "TN:")?;
+            writeln!(writer, "SF:{}", path.display())?;
+
+            // Function names are qualified by their module, as a file may define several.
+            let functions = file.modules.iter().flat_map(|module| {
+                module
+                    .functions
+                    .iter()
+                    .map(move |function| (format!("{}::{}", module.name, function.name), function))
+            });
+            for (name, function) in functions.clone() {
+                writeln!(writer, "FN:{},{name}", function.line)?;
+            }
+            for (name, function) in functions.clone() {
+                writeln!(writer, "FNDA:{},{name}", function.hits)?;
+            }
+            writeln!(writer, "FNF:{}", functions.clone().count())?;
+            writeln!(
+                writer,
+                "FNH:{}",
+                functions.filter(|(_, f)| f.hits > 0).count()
+            )?;
+
+            for (line, arms) in &file.branches {
+                for (arm, taken) in arms.iter().enumerate() {
+                    match taken {
+                        Some(taken) => writeln!(writer, "BRDA:{line},0,{arm},{taken}")?,
+                        None => writeln!(writer, "BRDA:{line},0,{arm},-")?,
+                    }
+                }
+            }
+            let (branches_covered, branches_valid) = file.branch_counts();
+            writeln!(writer, "BRF:{branches_valid}")?;
+            writeln!(writer, "BRH:{branches_covered}")?;
+
+            for (line, hits) in &file.lines {
+                writeln!(writer, "DA:{line},{hits}")?;
+            }
+            let (lines_covered, lines_valid) = file.line_counts();
+            writeln!(writer, "LF:{lines_valid}")?;
+            writeln!(writer, "LH:{lines_covered}")?;
+            writeln!(writer, "end_of_record")?;
+        }
+        Ok(())
+    }
+
+    /// Write the report as a Cobertura XML report, with one class per source file. File names
+    /// are written relative to `source_root` where possible.
+    pub fn write_cobertura<W: Write>(&self, writer: &mut W, source_root: &Path) -> io::Result<()> {
+        let (mut lines_covered, mut lines_valid) = (0, 0);
+        let (mut branches_covered, mut branches_valid) = (0, 0);
+        for file in self.files.values() {
+            let (covered, valid) = file.line_counts();
+            lines_covered += covered;
+            lines_valid += valid;
+            let (covered, valid) = file.branch_counts();
+            branches_covered += covered;
+            branches_valid += valid;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        writeln!(writer, r#"<?xml version="1.0" ?>"#)?;
+        writeln!(
+            writer,
+            r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+        )?;
+        writeln!(
+            writer,
+            r#"<coverage line-rate="{}" branch-rate="{}" lines-covered="{lines_covered}" lines-valid="{lines_valid}" branches-covered="{branches_covered}" branches-valid="{branches_valid}" complexity="0" version="1.9" timestamp="{timestamp}">"#,
+            rate(lines_covered, lines_valid),
+            rate(branches_covered, branches_valid),
+        )?;
+        writeln!(writer, "  <sources>")?;
+        writeln!(
+            writer,
+            "    <source>{}</source>",
+            escape_xml(&source_root.display().to_string())
+        )?;
+        writeln!(writer, "  </sources>")?;
+        writeln!(writer, "  <packages>")?;
+        writeln!(
+            writer,
+            r#"    <package name="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+            escape_xml(
+                &source_root
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            ),
+            rate(lines_covered, lines_valid),
+            rate(branches_covered, branches_valid),
+        )?;
+        writeln!(writer, "      <classes>")?;
+
+        for (path, file) in &self.files {
+            let filename = path.strip_prefix(source_root).unwrap_or(path);
+            let name = file
+                .modules
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>()
+                .join(",");
+            let (lines_covered, lines_valid) = file.line_counts();
+            let (branches_covered, branches_valid) = file.branch_counts();
+
+            writeln!(
+                writer,
+                r#"        <class name="{}" filename="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+                escape_xml(&name),
+                escape_xml(&filename.display().to_string()),
+                rate(lines_covered, lines_valid),
+                rate(branches_covered, branches_valid),
+            )?;
+
+            writeln!(writer, "          <methods>")?;
+            for function in file.modules.iter().flat_map(|m| &m.functions) {
+                writeln!(
+                    writer,
+                    r#"            <method name="{}" signature="" line-rate="{}" branch-rate="0" complexity="0">"#,
+                    escape_xml(&function.name),
+                    rate((function.hits > 0) as usize, 1),
+                )?;
+                writeln!(writer, "              <lines>")?;
+                writeln!(
+                    writer,
+                    r#"                <line number="{}" hits="{}"/>"#,
+                    function.line, function.hits
+                )?;
+                writeln!(writer, "              </lines>")?;
+                writeln!(writer, "            </method>")?;
+            }
+            writeln!(writer, "          </methods>")?;
+
+            writeln!(writer, "          <lines>")?;
+            for (line, hits) in &file.lines {
+                match file.branches.get(line) {
+                    None => writeln!(
+                        writer,
+                        r#"            <line number="{line}" hits="{hits}" branch="false"/>"#
+                    )?,
+                    Some(arms) => {
+                        let covered = arms.iter().filter(|arm| arm.unwrap_or(0) > 0).count();
+                        writeln!(
+                            writer,
+                            r#"            <line number="{line}" hits="{hits}" branch="true" condition-coverage="{}% ({covered}/{})"/>"#,
+                            covered * 100 / arms.len().max(1),
+                            arms.len(),
+                        )?
+                    }
+                }
+            }
+            writeln!(writer, "          </lines>")?;
+            writeln!(writer, "        </class>")?;
+        }
+
+        writeln!(writer, "      </classes>")?;
+        writeln!(writer, "    </package>")?;
+        writeln!(writer, "  </packages>")?;
+        writeln!(writer, "</coverage>")?;
+        Ok(())
+    }
+}
+
+impl FileCoverage {
+    /// The number of covered lines, and the number of lines with code on them.
+    pub fn line_counts(&self) -> (usize, usize) {
+        let covered = self.lines.values().filter(|hits| **hits > 0).count();
+        (covered, self.lines.len())
+    }
+
+    /// The number of branch arms taken, and the total number of branch arms.
+    pub fn branch_counts(&self) -> (usize, usize) {
+        let arms = self.branches.values().flatten();
+        let covered = arms.clone().filter(|arm| arm.unwrap_or(0) > 0).count();
+        (covered, arms.count())
+    }
+}
+
+fn rate(covered: usize, valid: usize) -> String {
+    if valid == 0 {
+        "1".to_string()
+    } else {
+        format!("{:.4}", covered as f64 / valid as f64)
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Documents: text with the places where it may be broken across lines, and the rendering of
+//! them to fit a maximum width, after Wadler's "A prettier printer".
+//!
+//! A group is written on one line if it fits in the rest of the line, and otherwise all of the
+//! optional line breaks directly in it are taken. Groups are decided outermost first, so inner
+//! groups are only broken if they still do not fit once the groups enclosing them are.
+
+#[derive(Debug)]
+pub(crate) enum Doc<'a> {
+    Text(&'a str),
+    /// A space, or a line break if the enclosing group is broken.
+    Line,
+    /// Nothing, or a line break if the enclosing group is broken.
+    SoftLine,
+    /// A line break, which also breaks all the groups enclosing it.
+    HardLine,
+    /// A line break followed by this many blank lines.
+    BlankLines(usize),
+    /// Text that is only written if the enclosing group is broken, like a trailing comma.
+    IfBreak(&'static str),
+    /// Docs whose line breaks are indented one more level.
+    Nest(Vec<Doc<'a>>),
+    /// Docs whose line breaks are indented one more level if the enclosing group is broken.
+    BreakNest(Vec<Doc<'a>>),
+    /// Docs that may run over several lines without breaking the enclosing group, like the last
+    /// argument of a call when it is a lambda with a block body. The enclosing group is written
+    /// on one line if it fits up to the first line break these docs could take.
+    Hug(Vec<Doc<'a>>),
+    /// Docs whose line breaks are taken together. A group that is `broken` (because it contains a
+    /// hard line break, or because it is always written over several lines) is never flattened.
+    Group {
+        docs: Vec<Doc<'a>>,
+        broken: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+struct Renderer {
+    indent_width: usize,
+    max_width: usize,
+    out: String,
+    /// Whether nothing has been written to the current line yet.
+    line_start: bool,
+    /// Indentation level of the current line, applied when text is first written to it.
+    level: usize,
+    /// Width of the current line so far.
+    column: usize,
+}
+
+impl<'a> Doc<'a> {
+    pub fn group(docs: Vec<Doc<'a>>, broken: bool) -> Self {
+        let broken = broken || docs.iter().any(Doc::breaks);
+        Doc::Group { docs, broken }
+    }
+
+    /// Whether this doc contains a line break that is always taken.
+    pub fn breaks(&self) -> bool {
+        match self {
+            Doc::Text(text) => text.contains('\n'),
+            Doc::HardLine | Doc::BlankLines(_) => true,
+            Doc::Line | Doc::SoftLine | Doc::IfBreak(_) | Doc::Hug(_) => false,
+            Doc::Nest(docs) | Doc::BreakNest(docs) => docs.iter().any(Doc::breaks),
+            Doc::Group { broken, .. } => *broken,
+        }
+    }
+}
+
+pub(crate) fn render(doc: &Doc, indent_width: usize, max_width: usize) -> String {
+    let mut renderer = Renderer {
+        indent_width,
+        max_width,
+        out: String::new(),
+        line_start: true,
+        level: 0,
+        column: 0,
+    };
+
+    let mut stack = vec![(0, Mode::Break, doc)];
+    while let Some((level, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => renderer.text(text),
+            Doc::Line if mode == Mode::Flat => {
+                if !renderer.line_start {
+                    renderer.text(" ");
+                }
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => renderer.newline(level, 0),
+            Doc::BlankLines(n) => renderer.newline(level, *n),
+            Doc::IfBreak(text) => {
+                if mode == Mode::Break {
+                    renderer.text(text)
+                }
+            }
+            Doc::Nest(docs) => stack.extend(docs.iter().rev().map(|d| (level + 1, mode, d))),
+            Doc::BreakNest(docs) => {
+                let level = level + (mode == Mode::Break) as usize;
+                stack.extend(docs.iter().rev().map(|d| (level, mode, d)))
+            }
+            Doc::Hug(docs) => stack.extend(docs.iter().rev().map(|d| (level, mode, d))),
+            Doc::Group { docs, broken } => {
+                let fits = !broken && {
+                    let width = renderer.max_width as isize - renderer.column as isize;
+                    fits(docs, &stack, width)
+                };
+                let mode = if fits { Mode::Flat } else { Mode::Break };
+                stack.extend(docs.iter().rev().map(|d| (level, mode, d)));
+            }
+        }
+    }
+
+    renderer.out
+}
+
+/// Whether `docs` fit in `width` when written on one line, followed by the docs on the `rest` of
+/// the stack up to their first line break.
+fn fits(docs: &[Doc], rest: &[(usize, Mode, &Doc)], mut width: isize) -> bool {
+    let mut stack: Vec<_> = docs.iter().rev().map(|d| (Mode::Flat, d)).collect();
+    let mut rest = rest.iter().rev().map(|(_, mode, d)| (*mode, *d));
+
+    loop {
+        let Some((mode, doc)) = stack.pop().or_else(|| rest.next()) else {
+            return true;
+        };
+
+        match doc {
+            Doc::Text(text) => {
+                let first_line = text.split('\n').next().unwrap_or_default();
+                width -= first_line.chars().count() as isize;
+                if text.contains('\n') {
+                    return width >= 0;
+                }
+            }
+            Doc::Line if mode == Mode::Flat => width -= 1,
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine | Doc::BlankLines(_) => return true,
+            Doc::IfBreak(text) => {
+                if mode == Mode::Break {
+                    width -= text.len() as isize;
+                }
+            }
+            Doc::Nest(docs) | Doc::BreakNest(docs) => {
+                stack.extend(docs.iter().rev().map(|d| (mode, d)))
+            }
+            Doc::Hug(docs) => stack.extend(docs.iter().rev().map(|d| (Mode::Break, d))),
+            Doc::Group { docs, broken } => {
+                let mode = if *broken { Mode::Break } else { mode };
+                stack.extend(docs.iter().rev().map(|d| (mode, d)));
+            }
+        }
+
+        if width < 0 {
+            return false;
+        }
+    }
+}
+
+impl Renderer {
+    fn text(&mut self, text: &str) {
+        if self.line_start {
+            let indent = self.level * self.indent_width;
+            self.out.extend(std::iter::repeat(' ').take(indent));
+            self.column = indent;
+            self.line_start = false;
+        }
+
+        self.out.push_str(text);
+        match text.rsplit_once('\n') {
+            Some((_, last)) => self.column = last.chars().count(),
+            None => self.column += text.chars().count(),
+        }
+    }
+
+    /// Start a new line at `level`, after `blank` blank lines. Consecutive line breaks are merged,
+    /// keeping the most blank lines asked for and the level of the last one.
+    fn newline(&mut self, level: usize, blank: usize) {
+        self.level = level;
+        self.column = level * self.indent_width;
+        if self.out.is_empty() {
+            return;
+        }
+
+        if !self.line_start {
+            self.out
+                .truncate(self.out.trim_end_matches([' ', '\t']).len());
+            self.out.push('\n');
+            self.line_start = true;
+        }
+
+        let newlines = self.out.len() - self.out.trim_end_matches('\n').len();
+        for _ in newlines..=blank {
+            self.out.push('\n');
+        }
+    }
+}
@@ -0,0 +1,853 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Where the lines of a file may be broken, from its AST.
+//!
+//! The printer writes the tokens of the file in order, and the layout tells it which brackets hold
+//! blocks or lists, where the items in them start, and which expressions may be broken before
+//! their operators, their method calls, or the right-hand side of their `=` or `=>`. Everything is
+//! recorded by token index, and anything whose location does not line up with the tokens the way
+//! it is expected to is left out, so the layout can only change where lines are broken.
+
+use crate::printer::Token;
+use move_compiler::{
+    parser::{ast::*, lexer::Tok},
+    shared::Identifier,
+};
+use move_ir_types::location::{Loc, Spanned};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Default)]
+pub(crate) struct Layout {
+    /// The first tokens of the items at the top level of the file: modules, and the members of
+    /// modules declared with `module a::m;`.
+    pub items: Vec<usize>,
+    /// Blocks and lists, by the index of their opening bracket.
+    pub brackets: BTreeMap<usize, Bracket>,
+    /// Expressions and statements whose optional line breaks are taken together, as
+    /// `[start, end)` token ranges.
+    pub ranges: Vec<(usize, usize)>,
+    /// The ranges starting at each token, longest first.
+    pub range_starts: BTreeMap<usize, Vec<usize>>,
+    /// Optional line breaks, by the index of the token they come before, with the range they
+    /// belong to.
+    pub breaks: BTreeMap<usize, (usize, Break)>,
+    /// The closing `>` of type parameter lists, by the index of their opening `<`.
+    pub angles: BTreeMap<usize, usize>,
+    /// Tokens that are always followed by a line break, like the `]` closing an attribute.
+    pub hard_breaks: BTreeSet<usize>,
+    /// Binary operators, which are written with a space on either side.
+    pub operators: BTreeSet<usize>,
+}
+
+pub(crate) enum Bracket {
+    /// A block of statements or module members, each starting on its own line.
+    Block(Vec<usize>),
+    List(List),
+}
+
+/// A comma separated list, written on one line if it fits, and with one item per line otherwise.
+pub(crate) struct List {
+    /// The first token of each item.
+    pub items: Vec<usize>,
+    /// Whether the list is written with spaces inside its brackets when it is on one line, like
+    /// `S { x }`.
+    pub spaced: bool,
+    /// Whether a trailing comma is added when the list is broken.
+    pub trailing_comma: bool,
+    /// Whether the list is always written with one item per line.
+    pub broken: bool,
+    /// Whether the last item may start on the same line as the rest of the list and run over
+    /// several lines, like a lambda with a block body.
+    pub hug: bool,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Break {
+    /// A space when the range is on one line, like before a binary operator.
+    Space,
+    /// Nothing when the range is on one line, like before the `.` of a method call.
+    Soft,
+}
+
+struct Walker<'t, 'a> {
+    source: &'a str,
+    tokens: &'t [Token<'a>],
+    /// The innermost bracket enclosing each token.
+    parents: Vec<Option<usize>>,
+    layout: Layout,
+}
+
+impl List {
+    fn new(spaced: bool, trailing_comma: bool) -> Self {
+        Self {
+            items: vec![],
+            spaced,
+            trailing_comma,
+            broken: false,
+            hug: false,
+        }
+    }
+
+    fn broken(self) -> Self {
+        Self {
+            broken: true,
+            ..self
+        }
+    }
+
+    fn hug(self, hug: bool) -> Self {
+        Self { hug, ..self }
+    }
+}
+
+pub(crate) fn layout(source: &str, definitions: &[Definition], tokens: &[Token]) -> Layout {
+    let mut parents = Vec::with_capacity(tokens.len());
+    let mut open = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        parents.push(open.last().copied());
+        match token.tok {
+            Tok::LParen | Tok::LBracket | Tok::LBrace => open.push(i),
+            Tok::RParen | Tok::RBracket | Tok::RBrace => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+
+    let mut walker = Walker {
+        source,
+        tokens,
+        parents,
+        layout: Layout::default(),
+    };
+
+    let mut items = vec![];
+    for definition in definitions {
+        match definition {
+            Definition::Module(module) => items.extend(walker.module(module)),
+            Definition::Address(address) => {
+                items.push(walker.item_start(&address.attributes, address.loc));
+                let modules: Vec<_> = address
+                    .modules
+                    .iter()
+                    .flat_map(|m| walker.module(m))
+                    .collect();
+                if let Some(open) = walker.open_after(address.addr.loc.end()) {
+                    walker.block(open, &modules);
+                }
+            }
+        }
+    }
+
+    let items = walker.items(None, &items);
+    walker.layout.items = items;
+    walker.layout
+}
+
+impl Walker<'_, '_> {
+    /// The index of the token starting at `pos`.
+    fn index(&self, pos: u32) -> Option<usize> {
+        let pos = pos as usize;
+        self.tokens.binary_search_by_key(&pos, |t| t.start).ok()
+    }
+
+    /// The index of the first token at or after `pos`.
+    fn index_after(&self, pos: u32) -> usize {
+        self.tokens.partition_point(|t| t.start < pos as usize)
+    }
+
+    /// Whether the token starting at `pos` is the first on its line.
+    fn starts_line(&self, pos: u32) -> bool {
+        match self.index(pos) {
+            Some(i) if i > 0 => {
+                self.source[self.tokens[i - 1].end..self.tokens[i].start].contains('\n')
+            }
+            _ => false,
+        }
+    }
+
+    /// The `{` that opens the first block at or after `pos`.
+    fn open_after(&self, pos: u32) -> Option<usize> {
+        let start = self.index_after(pos);
+        (start..self.tokens.len()).find(|i| self.tokens[*i].tok == Tok::LBrace)
+    }
+
+    /// Where an item with `attributes` starts.
+    fn item_start(&self, attributes: &[Attributes], loc: Loc) -> u32 {
+        attributes
+            .iter()
+            .map(|a| a.loc.start())
+            .chain([loc.start()])
+            .min()
+            .unwrap()
+    }
+
+    /// The items starting at `starts` in the block opened by `open` (or at the top level), leaving
+    /// out any that do not start a statement or member.
+    fn items(&self, open: Option<usize>, starts: &[u32]) -> Vec<usize> {
+        let mut items: Vec<usize> = vec![];
+        for start in starts {
+            let Some(i) = self.index(*start) else {
+                continue;
+            };
+            let after = match i.checked_sub(1) {
+                None => true,
+                Some(prev) => {
+                    Some(prev) == open
+                        || matches!(self.tokens[prev].tok, Tok::Semicolon | Tok::RBrace)
+                }
+            };
+            if self.parents[i] == open && after && items.last().map_or(true, |last| *last < i) {
+                items.push(i);
+            }
+        }
+        items
+    }
+
+    fn block(&mut self, open: usize, starts: &[u32]) {
+        if self.tokens[open].tok != Tok::LBrace {
+            return;
+        }
+        let items = self.items(Some(open), starts);
+        self.layout
+            .brackets
+            .entry(open)
+            .or_insert(Bracket::Block(items));
+    }
+
+    /// Record a list whose items start at `starts`, if they are all directly in a bracket at or
+    /// after `node`, separated by commas.
+    fn list(&mut self, node: u32, starts: impl IntoIterator<Item = u32>, mut list: List) {
+        let items: Option<Vec<_>> = starts.into_iter().map(|s| self.index(s)).collect();
+        let Some(items) = items else {
+            return;
+        };
+        let Some(open) = items.first().and_then(|first| self.parents[*first]) else {
+            return;
+        };
+
+        let expected = if list.spaced {
+            self.tokens[open].tok == Tok::LBrace
+        } else {
+            matches!(
+                self.tokens[open].tok,
+                Tok::LParen | Tok::LBracket | Tok::LBrace
+            )
+        };
+        let separated = items.iter().enumerate().all(|(k, i)| {
+            let prev = i - 1;
+            self.parents[*i] == Some(open)
+                && if k == 0 {
+                    prev == open
+                } else {
+                    self.tokens[prev].tok == Tok::Comma && items[k - 1] < prev
+                }
+        });
+        if !expected || !separated || open < self.index_after(node) {
+            return;
+        }
+
+        list.items = items;
+        self.layout
+            .brackets
+            .entry(open)
+            .or_insert(Bracket::List(list));
+    }
+
+    /// Record the range of `loc`, with optional line breaks before the tokens starting at
+    /// `breaks`.
+    fn range(&mut self, loc: Loc, breaks: impl IntoIterator<Item = (u32, Break)>) {
+        let Some(start) = self.index(loc.start()) else {
+            return;
+        };
+        let end = self.index_after(loc.end());
+        let breaks: Vec<_> = breaks
+            .into_iter()
+            .filter_map(|(pos, brk)| Some((self.index(pos)?, brk)))
+            .filter(|(i, _)| start < *i && *i < end)
+            .collect();
+        if breaks.is_empty() {
+            return;
+        }
+
+        let layout = &mut self.layout;
+        let id = match layout.ranges.iter().position(|r| *r == (start, end)) {
+            Some(id) => id,
+            None => {
+                layout.ranges.push((start, end));
+                let id = layout.ranges.len() - 1;
+                let starts = layout.range_starts.entry(start).or_default();
+                starts.push(id);
+                let ranges = &layout.ranges;
+                starts.sort_by_key(|id| std::cmp::Reverse(ranges[*id].1));
+                id
+            }
+        };
+        for (i, brk) in breaks {
+            layout.breaks.entry(i).or_insert((id, brk));
+        }
+    }
+
+    /// Record a break after the token before `rhs` if it is `tok`, and `rhs` does not start with
+    /// something that can be broken itself.
+    fn hanging(&mut self, loc: Loc, tok: Tok, rhs: &Exp) {
+        use Exp_ as E;
+        let breakable = matches!(
+            rhs.value,
+            E::Call(..)
+                | E::Pack(..)
+                | E::Vector(..)
+                | E::IfElse(..)
+                | E::Match(..)
+                | E::While(..)
+                | E::Loop(..)
+                | E::Labeled(..)
+                | E::Block(..)
+                | E::Lambda(..)
+                | E::ExpList(..)
+                | E::Parens(..)
+                | E::Annotate(..)
+                | E::Unit
+                | E::Index(..)
+                | E::Abort(..)
+                | E::Return(..)
+        );
+
+        let Some(i) = self.index(rhs.loc.start()) else {
+            return;
+        };
+        if !breakable && i > 0 && self.tokens[i - 1].tok == tok {
+            self.range(loc, [(rhs.loc.start(), Break::Space)]);
+        }
+    }
+
+    fn attributes(&mut self, attributes: &[Attributes]) {
+        for attribute in attributes {
+            self.attribute_list(attribute);
+            let end = self.index_after(attribute.loc.end());
+            if end > 0 && self.tokens[end - 1].tok == Tok::RBracket {
+                self.layout.hard_breaks.insert(end - 1);
+            }
+        }
+    }
+
+    fn attribute_list(&mut self, attributes: &Attributes) {
+        let starts = attributes.value.iter().map(|a| a.loc.start());
+        self.list(attributes.loc.start(), starts, List::new(false, true));
+        for attribute in &attributes.value {
+            if let Attribute_::Parameterized(_, inner) = &attribute.value {
+                self.attribute_list(inner);
+            }
+        }
+    }
+
+    /// Record the type parameters of a declaration, `<T: copy, U>`, whose items start at token
+    /// `starts`, as a list, if they directly follow the token ending at `name_end`.
+    fn type_parameters(&mut self, name_end: u32, starts: Vec<Option<usize>>) {
+        let open = self.index_after(name_end);
+        let Some(items) = starts.into_iter().collect::<Option<Vec<_>>>() else {
+            return;
+        };
+        let Some(last) = items.last() else {
+            return;
+        };
+        if self.tokens.get(open).map(|t| t.tok) != Some(Tok::Less) {
+            return;
+        }
+        let Some(close) = (last + 1..self.tokens.len()).find(|i| {
+            !matches!(
+                self.tokens[*i].tok,
+                Tok::Identifier | Tok::Copy | Tok::Colon | Tok::Plus | Tok::Comma
+            )
+        }) else {
+            return;
+        };
+
+        let separated = items.iter().enumerate().all(|(k, i)| {
+            let prev = if k == 0 { open } else { items[k - 1] };
+            *i > prev && ((k == 0 && *i == open + 1) || self.tokens[i - 1].tok == Tok::Comma)
+        });
+        if self.tokens[close].tok != Tok::Greater || !separated {
+            return;
+        }
+
+        self.layout.angles.insert(open, close);
+        let mut list = List::new(false, true);
+        list.items = items;
+        self.layout
+            .brackets
+            .entry(open)
+            .or_insert(Bracket::List(list));
+    }
+
+    /// The type parameters of a struct or enum, which may start with `phantom`.
+    fn datatype_type_parameters(&mut self, name: &DatatypeName, params: &[DatatypeTypeParameter]) {
+        let starts = params
+            .iter()
+            .map(|param| {
+                let i = self.index(param.name.loc.start())?;
+                Some(if param.is_phantom {
+                    i.checked_sub(1)?
+                } else {
+                    i
+                })
+            })
+            .collect();
+        self.type_parameters(name.loc().end(), starts);
+    }
+
+    /// Record the tuples in `ty` as lists.
+    fn ty(&mut self, ty: &Type) {
+        match &ty.value {
+            Type_::Multiple(types) => {
+                let starts = types.iter().map(|t| t.loc.start());
+                self.list(ty.loc.start(), starts, List::new(false, true));
+                for t in types {
+                    self.ty(t);
+                }
+            }
+            Type_::Ref(_, t) => self.ty(t),
+            Type_::Apply(_) | Type_::Fun(..) | Type_::Unit | Type_::UnresolvedError => {}
+        }
+    }
+
+    /// Walk `module`, returning where the items it adds to the enclosing block or file start:
+    /// the module itself, and its members if it is declared with `module a::m;`.
+    fn module(&mut self, module: &ModuleDefinition) -> Vec<u32> {
+        self.attributes(&module.attributes);
+        let mut items = vec![self.item_start(&module.attributes, module.loc)];
+        let members: Vec<_> = module.members.iter().flat_map(|m| self.member(m)).collect();
+        match module.definition_mode {
+            ModuleDefinitionMode::Braces => {
+                if let Some(open) = self.open_after(module.name.loc().end()) {
+                    self.block(open, &members);
+                }
+            }
+            ModuleDefinitionMode::Semicolon => items.extend(members),
+        }
+        items
+    }
+
+    /// Walk `member`, returning where it starts.
+    fn member(&mut self, member: &ModuleMember) -> Option<u32> {
+        match member {
+            ModuleMember::Function(f) => {
+                self.attributes(&f.attributes);
+                let params = f.signature.parameters.iter();
+                let starts = params.map(|(mut_, var, _)| mut_.unwrap_or(var.loc()).start());
+                self.list(f.name.loc().end(), starts, List::new(false, true));
+                let type_params = f.signature.type_parameters.iter();
+                let starts = type_params
+                    .map(|(name, _)| self.index(name.loc.start()))
+                    .collect();
+                self.type_parameters(f.name.loc().end(), starts);
+                self.ty(&f.signature.return_type);
+                if let FunctionBody_::Defined(seq) = &f.body.value {
+                    if let Some(open) = self.open_after(f.body.loc.start()) {
+                        self.sequence(open, seq);
+                    }
+                }
+                Some(self.item_start(&f.attributes, f.loc))
+            }
+            ModuleMember::Struct(s) => {
+                self.attributes(&s.attributes);
+                self.datatype_type_parameters(&s.name, &s.type_parameters);
+                let node = s.name.loc().end();
+                match &s.fields {
+                    StructFields::Named(fields) => {
+                        let starts = fields.iter().map(|(f, _)| f.loc().start());
+                        self.list(node, starts, List::new(true, true).broken());
+                    }
+                    StructFields::Positional(types) => {
+                        let starts = types.iter().map(|t| t.loc.start());
+                        self.list(node, starts, List::new(false, true));
+                    }
+                    StructFields::Native(_) => {}
+                }
+                Some(self.item_start(&s.attributes, s.loc))
+            }
+            ModuleMember::Enum(e) => {
+                self.attributes(&e.attributes);
+                self.datatype_type_parameters(&e.name, &e.type_parameters);
+                let starts = e.variants.iter().map(|v| v.loc.start());
+                let node = e.name.loc().end();
+                self.list(node, starts, List::new(true, true).broken());
+                for variant in &e.variants {
+                    let node = variant.name.loc().end();
+                    match &variant.fields {
+                        VariantFields::Named(fields) => {
+                            let starts = fields.iter().map(|(f, _)| f.loc().start());
+                            self.list(node, starts, List::new(true, true));
+                        }
+                        VariantFields::Positional(types) => {
+                            let starts = types.iter().map(|t| t.loc.start());
+                            self.list(node, starts, List::new(false, true));
+                        }
+                        VariantFields::Empty => {}
+                    }
+                }
+                Some(self.item_start(&e.attributes, e.loc))
+            }
+            ModuleMember::Use(u) => {
+                self.use_decl(u);
+                Some(self.item_start(&u.attributes, u.loc))
+            }
+            ModuleMember::Friend(f) => {
+                self.attributes(&f.attributes);
+                Some(self.item_start(&f.attributes, f.loc))
+            }
+            ModuleMember::Constant(c) => {
+                self.attributes(&c.attributes);
+                self.exp(&c.value);
+                self.hanging(c.loc, Tok::Equal, &c.value);
+                Some(self.item_start(&c.attributes, c.loc))
+            }
+            ModuleMember::Spec(spec) => Some(spec.loc.start()),
+        }
+    }
+
+    fn use_decl(&mut self, use_decl: &UseDecl) {
+        self.attributes(&use_decl.attributes);
+        let node = use_decl.loc.start();
+        match &use_decl.use_ {
+            Use::ModuleUse(_, module_use) => self.module_use(node, module_use),
+            Use::NestedModuleUses(_, uses) => {
+                let starts = uses.iter().map(|(m, _)| m.loc().start());
+                self.list(node, starts, List::new(false, true));
+                for (_, module_use) in uses {
+                    self.module_use(node, module_use);
+                }
+            }
+            Use::Fun { .. } | Use::Partial { .. } => {}
+        }
+    }
+
+    fn module_use(&mut self, node: u32, module_use: &ModuleUse) {
+        if let ModuleUse::Members(members) = module_use {
+            let starts = members.iter().map(|(name, _)| name.loc.start());
+            self.list(node, starts, List::new(false, true));
+        }
+    }
+
+    fn sequence(&mut self, open: usize, (uses, items, _, last): &Sequence) {
+        let mut starts = vec![];
+        for use_decl in uses {
+            self.use_decl(use_decl);
+            starts.push(self.item_start(&use_decl.attributes, use_decl.loc));
+        }
+
+        for item in items {
+            starts.push(item.loc.start());
+            match &item.value {
+                SequenceItem_::Seq(e) => self.exp(e),
+                SequenceItem_::Declare(binds, _) => self.bind_list(binds),
+                SequenceItem_::Bind(binds, _, e) => {
+                    self.bind_list(binds);
+                    self.exp(e);
+                    self.hanging(item.loc, Tok::Equal, e);
+                }
+            }
+        }
+
+        if let Some(e) = last.as_ref() {
+            starts.push(e.loc.start());
+            self.exp(e);
+        }
+
+        self.block(open, &starts);
+    }
+
+    fn bind_list(&mut self, binds: &BindList) {
+        let starts = binds.value.iter().map(|b| b.loc.start());
+        self.list(binds.loc.start(), starts, List::new(false, true));
+        for bind in &binds.value {
+            self.bind(bind);
+        }
+    }
+
+    fn bind(&mut self, bind: &Bind) {
+        let Bind_::Unpack(_, fields) = &bind.value else {
+            return;
+        };
+
+        let node = bind.loc.start();
+        match fields {
+            FieldBindings::Named(fields) => {
+                let starts = fields.iter().map(|field| match field {
+                    Ellipsis::Binder((f, _)) => f.loc().start(),
+                    Ellipsis::Ellipsis(loc) => loc.start(),
+                });
+                self.list(node, starts, List::new(true, true));
+                for field in fields {
+                    if let Ellipsis::Binder((_, b)) = field {
+                        self.bind(b);
+                    }
+                }
+            }
+            FieldBindings::Positional(fields) => {
+                let starts = fields.iter().map(|field| match field {
+                    Ellipsis::Binder(b) => b.loc.start(),
+                    Ellipsis::Ellipsis(loc) => loc.start(),
+                });
+                self.list(node, starts, List::new(false, true));
+                for field in fields {
+                    if let Ellipsis::Binder(b) = field {
+                        self.bind(b);
+                    }
+                }
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &MatchPattern) {
+        use MatchPattern_ as P;
+        let node = pattern.loc.start();
+        match &pattern.value {
+            P::PositionalConstructor(_, fields) => {
+                let starts = fields.value.iter().map(|field| match field {
+                    Ellipsis::Binder(p) => p.loc.start(),
+                    Ellipsis::Ellipsis(loc) => loc.start(),
+                });
+                self.list(node, starts, List::new(false, true));
+                for field in &fields.value {
+                    if let Ellipsis::Binder(p) = field {
+                        self.pattern(p);
+                    }
+                }
+            }
+            P::FieldConstructor(_, fields) => {
+                let starts = fields.value.iter().map(|field| match field {
+                    Ellipsis::Binder((f, _)) => f.loc().start(),
+                    Ellipsis::Ellipsis(loc) => loc.start(),
+                });
+                self.list(node, starts, List::new(true, true));
+                for field in &fields.value {
+                    if let Ellipsis::Binder((_, p)) = field {
+                        self.pattern(p);
+                    }
+                }
+            }
+            P::Or(lhs, rhs) => {
+                self.pattern(lhs);
+                self.pattern(rhs);
+            }
+            P::At(_, p) => self.pattern(p),
+            P::Name(..) | P::Literal(_) => {}
+        }
+    }
+
+    /// Record the arguments of a call as a list.
+    fn arguments(&mut self, node: u32, args: &Spanned<Vec<Exp>>) {
+        let hug = args.value.last().is_some_and(|e| match &e.value {
+            Exp_::Block(_) | Exp_::Pack(..) | Exp_::Vector(..) => true,
+            Exp_::Lambda(_, _, body) => matches!(body.value, Exp_::Block(_)),
+            _ => false,
+        });
+
+        let starts = args.value.iter().map(|e| e.loc.start());
+        self.list(node, starts, List::new(false, true).hug(hug));
+        for arg in &args.value {
+            self.exp(arg);
+        }
+    }
+
+    /// Record the parenthesized condition of an `if`, `while` or `match` starting at `node`.
+    fn condition(&mut self, node: u32, cond: &Exp) {
+        self.list(node, [cond.loc.start()], List::new(false, false));
+        self.exp(cond);
+    }
+
+    fn exp(&mut self, e: &Exp) {
+        use Exp_ as E;
+        let node = e.loc.start();
+        match &e.value {
+            E::Value(_)
+            | E::Name(_)
+            | E::Unit
+            | E::Continue(_)
+            | E::Spec(_)
+            | E::Quant(..)
+            | E::UnresolvedError => {}
+
+            E::Move(_, e)
+            | E::Copy(_, e)
+            | E::Dereference(e)
+            | E::UnaryExp(_, e)
+            | E::Borrow(_, e)
+            | E::Labeled(_, e)
+            | E::Loop(e)
+            | E::Cast(e, _)
+            | E::DotUnresolved(_, e)
+            | E::Abort(Some(e))
+            | E::Return(_, Some(e))
+            | E::Break(_, Some(e)) => self.exp(e),
+            E::Abort(None) | E::Return(_, None) | E::Break(_, None) => {}
+
+            E::Call(_, args) | E::Vector(_, _, args) => self.arguments(node, args),
+            E::Pack(_, fields) => {
+                let starts: Vec<_> = fields.iter().map(|(f, _)| f.loc().start()).collect();
+                // A struct that the author wrote over several lines stays that way, like an object
+                // literal in Prettier.
+                let list = List::new(true, true);
+                let list = match starts.first() {
+                    Some(first) if self.starts_line(*first) => list.broken(),
+                    _ => list,
+                };
+                self.list(node, starts, list);
+                for (_, e) in fields {
+                    self.exp(e);
+                }
+            }
+
+            E::IfElse(cond, if_true, if_false) => {
+                self.condition(node, cond);
+                self.exp(if_true);
+                if let Some(if_false) = if_false {
+                    self.exp(if_false);
+                }
+            }
+            E::While(cond, body) => {
+                self.condition(node, cond);
+                self.exp(body);
+            }
+            E::Match(subject, arms) => {
+                self.condition(node, subject);
+                let starts = arms.value.iter().map(|arm| arm.loc.start());
+                self.list(node, starts, List::new(true, true).broken());
+                for arm in &arms.value {
+                    self.pattern(&arm.value.pattern);
+                    if let Some(guard) = &arm.value.guard {
+                        self.condition(arm.loc.start(), guard);
+                    }
+                    self.exp(&arm.value.rhs);
+                    self.hanging(arm.loc, Tok::EqualGreater, &arm.value.rhs);
+                }
+            }
+            E::Block(seq) => {
+                if let Some(open) = self.index(node) {
+                    self.sequence(open, seq);
+                }
+            }
+            E::Lambda(binds, _, body) => {
+                for (binds, _) in &binds.value {
+                    for bind in &binds.value {
+                        self.bind(bind);
+                    }
+                }
+                self.exp(body);
+            }
+
+            E::ExpList(es) => {
+                let starts = es.iter().map(|e| e.loc.start());
+                self.list(node, starts, List::new(false, true));
+                for e in es {
+                    self.exp(e);
+                }
+            }
+            E::Parens(inner) | E::Annotate(inner, _) => self.condition(node, inner),
+            E::Index(e, args) => {
+                self.exp(e);
+                let starts = args.value.iter().map(|e| e.loc.start());
+                self.list(node, starts, List::new(false, false));
+                for arg in &args.value {
+                    self.exp(arg);
+                }
+            }
+
+            E::Assign(lhs, rhs) => {
+                self.exp(lhs);
+                self.exp(rhs);
+                self.hanging(e.loc, Tok::Equal, rhs);
+            }
+            E::BinopExp(..) => self.operators(e),
+            E::Dot(..) | E::DotCall(..) => self.method_calls(e),
+        }
+    }
+
+    /// A chain of binary operators of the same precedence, which are broken before.
+    fn operators(&mut self, e: &Exp) {
+        fn flatten<'e>(e: &'e Exp, level: u8, operands: &mut Vec<&'e Exp>, ops: &mut Vec<u32>) {
+            match &e.value {
+                Exp_::BinopExp(lhs, op, rhs) if precedence(&op.value) == level => {
+                    flatten(lhs, level, operands, ops);
+                    ops.push(op.loc.start());
+                    flatten(rhs, level, operands, ops);
+                }
+                _ => operands.push(e),
+            }
+        }
+
+        let Exp_::BinopExp(_, op, _) = &e.value else {
+            return;
+        };
+        let mut operands = vec![];
+        let mut ops = vec![];
+        flatten(e, precedence(&op.value), &mut operands, &mut ops);
+
+        for op in &ops {
+            if let Some(i) = self.index(*op) {
+                self.layout.operators.insert(i);
+            }
+        }
+        self.range(e.loc, ops.into_iter().map(|op| (op, Break::Space)));
+        for operand in operands {
+            self.exp(operand);
+        }
+    }
+
+    /// A chain of field accesses and method calls, which is broken before the `.` of each call if
+    /// it has more than one.
+    fn method_calls(&mut self, e: &Exp) {
+        let mut calls = vec![];
+        let mut receiver = e;
+        loop {
+            match &receiver.value {
+                Exp_::DotCall(inner, name, _, _, args) => {
+                    calls.push(name.loc.start());
+                    self.arguments(name.loc.start(), args);
+                    receiver = inner;
+                }
+                Exp_::Dot(inner, _) => receiver = inner,
+                _ => break,
+            }
+        }
+        self.exp(receiver);
+
+        if calls.len() < 2 {
+            return;
+        }
+        // A plain name stays on the line of its first call.
+        if matches!(receiver.value, Exp_::Name(_)) {
+            calls.pop();
+        }
+
+        let dots: Vec<_> = calls
+            .into_iter()
+            .filter_map(|name| {
+                let i = self.index(name)?;
+                let dot = &self.tokens[i.checked_sub(1)?];
+                (dot.tok == Tok::Period).then_some((dot.start as u32, Break::Soft))
+            })
+            .collect();
+        self.range(e.loc, dots);
+    }
+}
+
+/// Binary operators that bind equally tightly, which are broken together.
+fn precedence(op: &BinOp_) -> u8 {
+    use BinOp_ as B;
+    match op {
+        B::Implies | B::Iff => 1,
+        B::Or => 2,
+        B::And => 3,
+        B::Eq | B::Neq | B::Lt | B::Gt | B::Le | B::Ge => 4,
+        B::Range => 5,
+        B::BitOr => 6,
+        B::Xor => 7,
+        B::BitAnd => 8,
+        B::Shl | B::Shr => 9,
+        B::Add | B::Sub => 10,
+        B::Mul | B::Div | B::Mod => 11,
+    }
+}
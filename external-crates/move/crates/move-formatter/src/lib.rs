@@ -0,0 +1,379 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A formatter for Move source files.
+//!
+//! Files are laid out from their AST: blocks and module bodies get one statement or member per
+//! line, and bracketed lists, chains of binary operators and method calls, and the right-hand
+//! sides of `=` and `=>` are kept on one line if they fit in the maximum width, and broken
+//! otherwise. The tokens themselves are written from the source, with the comments between them
+//! and up to the configured number of blank lines between statements and members, so only files
+//! that parse are formatted, and the result is checked to contain exactly the same tokens (up to
+//! trailing commas) and comments as the original.
+
+mod doc;
+mod layout;
+mod printer;
+
+use anyhow::Context;
+use move_command_line_common::files::FileHash;
+use move_compiler::{
+    diagnostics::{codes::Severity, Diagnostics},
+    editions::Edition,
+    parser::{ast::Definition, lexer::Tok, parse_file_string},
+    shared::{CompilationEnv, Flags, PackageConfig},
+};
+use move_package::source_package::{
+    layout::SourcePackageLayout,
+    manifest_parser::{parse_move_manifest_string, parse_source_manifest, FORMAT_NAME},
+};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, path::Path};
+
+/// Formatting settings, read from the `[format]` section of a package's `Move.toml`:
+///
+/// ```toml
+/// [format]
+/// indent-width = 4
+/// max-blank-lines = 1
+/// max-width = 100
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FormatConfig {
+    /// Number of spaces per indentation level.
+    pub indent_width: usize,
+    /// Maximum number of consecutive blank lines to keep.
+    pub max_blank_lines: usize,
+    /// Width that lines are kept within where they can be broken.
+    pub max_width: usize,
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    /// The source does not parse, so it is left as it is.
+    Syntax(Diagnostics),
+    /// Formatting would have changed the tokens or comments of the source. This is a bug in the
+    /// formatter, and the source is left as it is.
+    Changed,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            max_blank_lines: 1,
+            max_width: 100,
+        }
+    }
+}
+
+impl FormatConfig {
+    /// Read the `[format]` section of a parsed manifest, using defaults for any settings that are
+    /// missing.
+    pub fn from_manifest(manifest: &toml::Value) -> anyhow::Result<Self> {
+        let Some(section) = manifest.get(FORMAT_NAME) else {
+            return Ok(Self::default());
+        };
+
+        let config: Self = section
+            .clone()
+            .try_into()
+            .with_context(|| format!("Error parsing '[{FORMAT_NAME}]' section of manifest"))?;
+
+        if config.indent_width == 0 {
+            anyhow::bail!("'indent-width' in '[{FORMAT_NAME}]' must be positive");
+        }
+        if config.max_width == 0 {
+            anyhow::bail!("'max-width' in '[{FORMAT_NAME}]' must be positive");
+        }
+        Ok(config)
+    }
+}
+
+/// The edition and formatting settings of the package rooted at `package_root`, from its
+/// manifest. Packages that do not declare an edition are treated as using `default_edition`, or
+/// the legacy edition if that is not set, matching the compiler.
+pub fn package_settings(
+    package_root: &Path,
+    default_edition: Option<Edition>,
+) -> anyhow::Result<(Edition, FormatConfig)> {
+    let manifest_path = package_root.join(SourcePackageLayout::Manifest.path());
+    let manifest = std::fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Unable to read manifest at {}", manifest_path.display()))?;
+
+    let manifest = parse_move_manifest_string(manifest)?;
+    let config = FormatConfig::from_manifest(&manifest)?;
+    let edition = parse_source_manifest(manifest)?
+        .package
+        .edition
+        .or(default_edition)
+        .unwrap_or(Edition::LEGACY);
+
+    Ok((edition, config))
+}
+
+/// Format `source`, a Move source file written in `edition`.
+pub fn format_source(
+    source: &str,
+    edition: Edition,
+    config: &FormatConfig,
+) -> Result<String, FormatError> {
+    let definitions = parse(source, edition).map_err(FormatError::Syntax)?;
+
+    let tokens = printer::tokens(source, edition)
+        .map_err(|diag| FormatError::Syntax(Diagnostics::from(vec![*diag])))?;
+    let layout = layout::layout(source, &definitions, &tokens);
+    let formatted = printer::print(source, &tokens, &layout, config);
+
+    let Ok(formatted_tokens) = printer::tokens(&formatted, edition) else {
+        return Err(FormatError::Changed);
+    };
+
+    // Trailing commas are added to lists that are broken, and removed from lists that are not.
+    let significant = |tokens: &[printer::Token<'_>]| -> Vec<(Tok, String)> {
+        let trailing = |i: usize| {
+            tokens[i].tok == Tok::Comma
+                && tokens.get(i + 1).is_some_and(|next| {
+                    matches!(
+                        next.tok,
+                        Tok::RParen | Tok::RBracket | Tok::RBrace | Tok::Greater
+                    )
+                })
+        };
+        (0..tokens.len())
+            .filter(|i| !trailing(*i))
+            .map(|i| (tokens[i].tok, tokens[i].text.to_owned()))
+            .collect()
+    };
+
+    let same_tokens = significant(&tokens) == significant(&formatted_tokens);
+    let same_comments =
+        printer::comments(source, &tokens) == printer::comments(&formatted, &formatted_tokens);
+
+    if same_tokens && same_comments {
+        Ok(formatted)
+    } else {
+        Err(FormatError::Changed)
+    }
+}
+
+fn parse(source: &str, edition: Edition) -> Result<Vec<Definition>, Diagnostics> {
+    let config = PackageConfig {
+        edition,
+        ..PackageConfig::default()
+    };
+    let env = CompilationEnv::new(
+        Flags::empty(),
+        vec![],
+        vec![],
+        None,
+        BTreeMap::new(),
+        Some(config),
+    );
+
+    let (definitions, _) = parse_file_string(&env, FileHash::new(source), source, None)?;
+    env.check_diags_at_or_above_severity(Severity::NonblockingError)?;
+    Ok(definitions)
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Syntax(diags) => {
+                write!(f, "Source has {} syntax error(s)", diags.len())
+            }
+            FormatError::Changed => write!(f, "Formatting would change the meaning of the source"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str) -> String {
+        let config = FormatConfig::default();
+        let formatted = format_source(source, Edition::E2024_BETA, &config).unwrap();
+        let again = format_source(&formatted, Edition::E2024_BETA, &config).unwrap();
+        assert_eq!(formatted, again, "formatting is not idempotent");
+        formatted
+    }
+
+    #[test]
+    fn test_indentation_and_spacing() {
+        let source = r#"
+module a::m;
+use sui::coin::{Self,Coin};
+
+public struct Pair<T: copy + drop>(T,T) has copy, drop;
+
+public enum Shape {
+  Circle { radius: u64 },
+      Square(u64),
+}
+
+public fun area(s: &Shape): u64 {
+match (s) {
+Shape::Circle { radius } => 3 * *radius * *radius,
+  Shape::Square(side) =>
+  *side * *side,
+  }
+}
+
+#[test]
+fun test_area(){
+let v = vector[1,2,3];
+  let total = v.fold!(0,|acc, x| acc+x);
+assert!(total==6 , 0);
+}
+"#;
+
+        let expected = r#"module a::m;
+use sui::coin::{Self, Coin};
+
+public struct Pair<T: copy + drop>(T, T) has copy, drop;
+
+public enum Shape {
+    Circle { radius: u64 },
+    Square(u64),
+}
+
+public fun area(s: &Shape): u64 {
+    match (s) {
+        Shape::Circle { radius } => 3 * *radius * *radius,
+        Shape::Square(side) => *side * *side,
+    }
+}
+
+#[test]
+fun test_area() {
+    let v = vector[1, 2, 3];
+    let total = v.fold!(0, |acc, x| acc + x);
+    assert!(total == 6, 0);
+}
+"#;
+
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn test_comments_and_blank_lines() {
+        let source = "module a::m {   // trailing\n\n\n    /// Doc\n  public fun f(): u64 {\n\n        /* block */ 1   \n\n\n\n    }\n// last\n}";
+        let expected = "module a::m { // trailing\n\n    /// Doc\n    public fun f(): u64 {\n        /* block */ 1\n    }\n    // last\n}\n";
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn test_joining_lines() {
+        let source = "module a::m;\nfun f(x: u64): u64 {\nlet y = x\n+ 1;\nlet z = call(\ny,\n);\nz\n.method()\n}\n";
+        let expected = "module a::m;\nfun f(x: u64): u64 {\n    let y = x + 1;\n    let z = call(y);\n    z.method()\n}\n";
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let source = r#"
+module a::m;
+public fun f<Coin: key + store, Currency: store, Witness: drop>(first: u64, second: u64, third: &mut Coin): u64 {
+    let total = first + second * 2 + first_value(third) + second_value(third) + third_value(third) + 1;
+    let v = vector[first, second].map!(|x| x + 1).filter!(|x| *x > 2).fold!(0, |acc, x| acc + x);
+    v.do!(|x| { assert!(x > 0, 0); });
+    let p = Point { x: first, y: second };
+    call_something_long(first, second, total, v, p, b"a byte string that is long") // keep me
+}
+"#;
+
+        let expected = r#"module a::m;
+public fun f<Coin: key + store, Currency: store, Witness: drop>(
+    first: u64,
+    second: u64,
+    third: &mut Coin,
+): u64 {
+    let total =
+        first + second * 2 + first_value(third) + second_value(third) + third_value(third) + 1;
+    let v = vector[first, second].map!(|x| x + 1).filter!(|x| *x > 2).fold!(0, |acc, x| acc + x);
+    v.do!(|x| {
+        assert!(x > 0, 0);
+    });
+    let p = Point { x: first, y: second };
+    call_something_long(first, second, total, v, p, b"a byte string that is long") // keep me
+}
+"#;
+        assert_eq!(format(source), expected);
+
+        let narrow = r#"module a::m;
+public fun f<
+    Coin: key + store,
+    Currency: store,
+    Witness: drop,
+>(
+    first: u64,
+    second: u64,
+    third: &mut Coin,
+): u64 {
+    let total =
+        first
+            + second * 2
+            + first_value(third)
+            + second_value(third)
+            + third_value(third)
+            + 1;
+    let v =
+        vector[first, second]
+            .map!(|x| x + 1)
+            .filter!(|x| *x > 2)
+            .fold!(0, |acc, x| acc + x);
+    v.do!(|x| {
+        assert!(x > 0, 0);
+    });
+    let p = Point { x: first, y: second };
+    call_something_long(
+        first,
+        second,
+        total,
+        v,
+        p,
+        b"a byte string that is long",
+    ) // keep me
+}
+"#;
+        let config = FormatConfig {
+            max_width: 50,
+            ..FormatConfig::default()
+        };
+        let formatted = format_source(source, Edition::E2024_BETA, &config).unwrap();
+        assert_eq!(formatted, narrow);
+        let again = format_source(&formatted, Edition::E2024_BETA, &config).unwrap();
+        assert_eq!(formatted, again, "formatting is not idempotent");
+    }
+
+    #[test]
+    fn test_config() {
+        let manifest: toml::Value =
+            toml::from_str("[package]\nname = \"p\"\n[format]\nindent-width = 2\nmax-width = 80\n")
+                .unwrap();
+        let config = FormatConfig::from_manifest(&manifest).unwrap();
+        assert_eq!(config.indent_width, 2);
+        assert_eq!(config.max_blank_lines, 1);
+        assert_eq!(config.max_width, 80);
+
+        let formatted = format_source("module a::m { fun f() {} }\n", Edition::E2024_BETA, &config);
+        assert_eq!(formatted.unwrap(), "module a::m {\n  fun f() {}\n}\n");
+
+        let manifest: toml::Value = toml::from_str("[format]\nindent = 2\n").unwrap();
+        assert!(FormatConfig::from_manifest(&manifest).is_err());
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let result = format_source(
+            "module a::m { fun }",
+            Edition::E2024_BETA,
+            &FormatConfig::default(),
+        );
+        assert!(matches!(result, Err(FormatError::Syntax(_))));
+    }
+}
@@ -0,0 +1,565 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Printing the tokens of a file as a document, following its layout.
+//!
+//! Every token is written in order, with the comments before it, so the layout only decides where
+//! the optional and required line breaks between them go. Tokens on the same line are separated
+//! according to `space_between`, and comments stay on the line they were on: a comment that
+//! followed a token on the same line still does, and one on a line of its own is written on its
+//! own line, keeping up to the configured number of blank lines before it.
+
+use crate::{
+    doc::{self, Doc},
+    layout::{Bracket, Break, Layout, List},
+    FormatConfig,
+};
+use move_command_line_common::files::FileHash;
+use move_compiler::{
+    diagnostics::Diagnostic,
+    editions::Edition,
+    parser::lexer::{Lexer, Tok},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Token<'a> {
+    pub tok: Tok,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The whitespace before a token or comment.
+#[derive(Clone, Copy, Default)]
+struct Spacing {
+    newlines: usize,
+    space: bool,
+}
+
+/// The comments before a token, each with the whitespace before it, and the whitespace between the
+/// last comment and the token.
+type Gap<'a> = (Vec<(Spacing, &'a str)>, Spacing);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Prev {
+    Token(usize),
+    Comment,
+    /// A line break, and whether it is only taken if its group is broken.
+    Break {
+        soft: bool,
+    },
+}
+
+/// The ranges and brackets being written, innermost last.
+enum Frame {
+    /// A range, and the index in its docs of its first line break.
+    Range(usize, Option<usize>),
+    /// A bracket, which the line breaks of the ranges enclosing it do not apply in.
+    Bracket,
+}
+
+struct Builder<'a, 'l> {
+    config: &'l FormatConfig,
+    tokens: &'l [Token<'a>],
+    layout: &'l Layout,
+    /// The gap before each token, and after the last one.
+    gaps: Vec<Gap<'a>>,
+    /// How many of the comments in each gap have been written.
+    written: Vec<usize>,
+    /// The index of the closing bracket of each opening one.
+    closes: Vec<Option<usize>>,
+    /// The index of the next token to write.
+    next: usize,
+    frames: Vec<Frame>,
+    prev: Prev,
+    /// Whether the last token or comment written was an opening bracket, after which blank lines
+    /// are dropped.
+    opened: bool,
+}
+
+pub(crate) fn tokens(source: &str, edition: Edition) -> Result<Vec<Token<'_>>, Box<Diagnostic>> {
+    let mut lexer = Lexer::new(source, FileHash::new(source), edition);
+    let mut tokens = vec![];
+
+    lexer.advance()?;
+    while lexer.peek() != Tok::EOF {
+        let start = lexer.start_loc();
+        let content = lexer.content();
+        tokens.push(Token {
+            tok: lexer.peek(),
+            // `&mut` is lexed with the whitespace that follows it.
+            text: content.trim_end(),
+            start,
+            end: start + content.len(),
+        });
+        lexer.advance()?;
+    }
+
+    Ok(tokens)
+}
+
+/// The comments in `source`, in order, given its `tokens`.
+pub(crate) fn comments<'a>(source: &'a str, tokens: &[Token]) -> Vec<&'a str> {
+    gaps(source, tokens)
+        .into_iter()
+        .flat_map(|gap| split_gap(gap).0)
+        .map(|(_, comment)| comment)
+        .collect()
+}
+
+pub(crate) fn print(
+    source: &str,
+    tokens: &[Token],
+    layout: &Layout,
+    config: &FormatConfig,
+) -> String {
+    let mut closes = vec![None; tokens.len()];
+    let mut open = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        if is_open(token.tok) {
+            open.push(i);
+        } else if is_close(token.tok) {
+            if let Some(o) = open.pop() {
+                closes[o] = Some(i);
+            }
+        }
+    }
+
+    for (open, close) in &layout.angles {
+        closes[*open] = Some(*close);
+    }
+
+    let mut builder = Builder {
+        config,
+        tokens,
+        layout,
+        gaps: gaps(source, tokens).into_iter().map(split_gap).collect(),
+        written: vec![0; tokens.len() + 1],
+        closes,
+        next: 0,
+        frames: vec![],
+        prev: Prev::Break { soft: false },
+        opened: false,
+    };
+
+    let mut docs = vec![];
+    builder.items(&layout.items, tokens.len(), &mut docs);
+    builder.comments(tokens.len(), &mut docs);
+
+    let doc = Doc::group(docs, false);
+    let mut out = doc::render(&doc, config.indent_width, config.max_width);
+    out.truncate(out.trim_end().len());
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// The text before each token, and after the last one.
+fn gaps<'a>(source: &'a str, tokens: &[Token]) -> Vec<&'a str> {
+    let starts = tokens.iter().map(|t| t.start).chain([source.len()]);
+    let ends = [0].into_iter().chain(tokens.iter().map(|t| t.end));
+    ends.zip(starts)
+        .map(|(end, start)| &source[end..start])
+        .collect()
+}
+
+/// Split the whitespace and comments between two tokens into the comments, each with the
+/// whitespace before it, and the whitespace after the last comment.
+fn split_gap(gap: &str) -> (Vec<(Spacing, &str)>, Spacing) {
+    let mut comments = vec![];
+    let mut spacing = Spacing::default();
+    let mut rest = gap;
+
+    loop {
+        let trimmed = rest.trim_start();
+        let whitespace = &rest[..rest.len() - trimmed.len()];
+        spacing.newlines += whitespace.matches('\n').count();
+        spacing.space |= !whitespace.is_empty();
+        rest = trimmed;
+
+        if rest.is_empty() {
+            break;
+        }
+
+        let len = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else {
+            block_comment_len(rest)
+        };
+
+        comments.push((spacing, rest[..len].trim_end()));
+        spacing = Spacing::default();
+        rest = &rest[len..];
+    }
+
+    (comments, spacing)
+}
+
+/// The length of the (possibly nested) block comment at the start of `text`.
+fn block_comment_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"/*") {
+            depth += 1;
+            i += 2;
+        } else if bytes[i..].starts_with(b"*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    text.len()
+}
+
+impl<'a> Builder<'a, '_> {
+    /// Write the tokens up to `end`, each item in `items` starting on a new line.
+    fn items(&mut self, items: &[usize], end: usize, docs: &mut Vec<Doc<'a>>) {
+        for (k, item) in items.iter().enumerate() {
+            self.seq(*item, docs);
+            self.item_break(*item, docs);
+            self.seq(items.get(k + 1).copied().unwrap_or(end), docs);
+        }
+        self.seq(end, docs);
+    }
+
+    /// Write the tokens up to `end`.
+    fn seq(&mut self, end: usize, docs: &mut Vec<Doc<'a>>) {
+        while self.next < end {
+            let i = self.next;
+
+            if let Some((range, brk)) = self.layout.breaks.get(&i) {
+                if let Some(Frame::Range(active, first)) = self.frames.last_mut() {
+                    if active == range {
+                        first.get_or_insert(docs.len());
+                        let doc = match brk {
+                            Break::Space => Doc::Line,
+                            Break::Soft => Doc::SoftLine,
+                        };
+                        self.line(i, doc, docs);
+                    }
+                }
+            }
+
+            if let Some(range) = self.range_at(i, end) {
+                self.range(range, docs);
+            } else if let Some(close) = self.closes[i].filter(|close| *close < end) {
+                self.bracket(i, close, docs);
+            } else {
+                self.token(i, docs);
+            }
+
+            if self.layout.hard_breaks.contains(&(self.next - 1)) {
+                self.line(self.next, Doc::HardLine, docs);
+            }
+        }
+    }
+
+    /// The outermost range starting at token `i` that ends by `end` and is not being written.
+    fn range_at(&self, i: usize, end: usize) -> Option<usize> {
+        let ranges = self.layout.range_starts.get(&i)?;
+        ranges.iter().copied().find(|range| {
+            self.layout.ranges[*range].1 <= end
+                && !self
+                    .frames
+                    .iter()
+                    .any(|f| matches!(f, Frame::Range(r, _) if r == range))
+        })
+    }
+
+    /// Write a range as a group, indenting the lines after its first line break.
+    fn range(&mut self, range: usize, docs: &mut Vec<Doc<'a>>) {
+        // Comments before the range are not part of it, so they do not break it.
+        self.comments(self.next, docs);
+        let mut inner = vec![];
+        self.frames.push(Frame::Range(range, None));
+        self.seq(self.layout.ranges[range].1, &mut inner);
+        let Some(Frame::Range(_, first)) = self.frames.pop() else {
+            unreachable!()
+        };
+
+        let rest = inner.split_off(first.unwrap_or(inner.len()));
+        inner.push(Doc::Nest(rest));
+        docs.push(Doc::group(inner, false));
+    }
+
+    /// Write the bracket opened by `open` and closed by `close`, and everything in it.
+    fn bracket(&mut self, open: usize, close: usize, docs: &mut Vec<Doc<'a>>) {
+        self.frames.push(Frame::Bracket);
+        match self.layout.brackets.get(&open) {
+            None => {
+                self.token(open, docs);
+                self.seq(close, docs);
+                self.token(close, docs);
+            }
+            Some(Bracket::Block(items)) => {
+                self.token(open, docs);
+                let mut inner = vec![];
+                if items.is_empty() && self.next < close {
+                    self.item_break(self.next, &mut inner);
+                }
+                self.items(items, close, &mut inner);
+                self.trailing_comments(close, &mut inner);
+                self.comments(close, &mut inner);
+                if !inner.is_empty() {
+                    docs.push(Doc::Nest(inner));
+                    self.line(close, Doc::HardLine, docs);
+                }
+                self.token(close, docs);
+            }
+            Some(Bracket::List(list)) => self.list(list, open, close, docs),
+        }
+        self.frames.pop();
+    }
+
+    fn list(&mut self, list: &List, open: usize, close: usize, docs: &mut Vec<Doc<'a>>) {
+        self.comments(open, docs);
+        let mut group = vec![];
+        self.token(open, &mut group);
+
+        let mut inner = vec![];
+        for (k, item) in list.items.iter().enumerate() {
+            self.seq(*item, &mut inner);
+            if list.broken {
+                self.item_break(*item, &mut inner);
+            } else if k == 0 && !list.spaced {
+                self.line(*item, Doc::SoftLine, &mut inner);
+            } else {
+                self.line(*item, Doc::Line, &mut inner);
+            }
+
+            let Some(next) = list.items.get(k + 1) else {
+                break;
+            };
+            self.seq(*next, &mut inner);
+        }
+
+        let trailing = close - 1;
+        let has_trailing = self.tokens[trailing].tok == Tok::Comma
+            && list.items.last().is_some_and(|last| *last < trailing);
+        let end = if list.trailing_comma && has_trailing {
+            trailing
+        } else {
+            close
+        };
+
+        if list.hug {
+            let mut last = vec![];
+            self.seq(end, &mut last);
+            inner.push(Doc::Hug(last));
+        } else {
+            self.seq(end, &mut inner);
+        }
+        if list.trailing_comma {
+            if has_trailing {
+                self.comments(trailing, &mut inner);
+                self.next = close;
+            }
+            inner.push(Doc::IfBreak(","));
+        }
+        self.trailing_comments(close, &mut inner);
+        self.comments(close, &mut inner);
+
+        // A hugged item is not indented when the list is on one line.
+        group.push(if list.hug {
+            Doc::BreakNest(inner)
+        } else {
+            Doc::Nest(inner)
+        });
+
+        let brk = if list.spaced {
+            Doc::Line
+        } else {
+            Doc::SoftLine
+        };
+        self.line(close, brk, &mut group);
+        self.token(close, &mut group);
+        docs.push(Doc::group(group, list.broken));
+    }
+
+    /// Start the item at token `i` on a new line, keeping the blank lines before it.
+    fn item_break(&mut self, i: usize, docs: &mut Vec<Doc<'a>>) {
+        self.trailing_comments(i, docs);
+        let (comments, spacing) = &self.gaps[i];
+        let newlines = comments
+            .get(self.written[i])
+            .map_or(spacing.newlines, |(spacing, _)| spacing.newlines);
+        docs.push(self.blank_lines(newlines));
+        self.prev = Prev::Break { soft: false };
+    }
+
+    /// Add a line break before token `i`, after any comments on the line before it.
+    fn line(&mut self, i: usize, doc: Doc<'a>, docs: &mut Vec<Doc<'a>>) {
+        self.trailing_comments(i, docs);
+        let soft = matches!(doc, Doc::Line | Doc::SoftLine);
+        if !(soft && self.prev == (Prev::Break { soft: true })) {
+            docs.push(doc);
+            self.prev = Prev::Break { soft };
+        }
+    }
+
+    /// A line break after which `newlines` line breaks were in the source.
+    fn blank_lines(&self, newlines: usize) -> Doc<'a> {
+        let blank = newlines.saturating_sub(1).min(self.config.max_blank_lines);
+        if blank == 0 || self.opened {
+            Doc::HardLine
+        } else {
+            Doc::BlankLines(blank)
+        }
+    }
+
+    fn token(&mut self, i: usize, docs: &mut Vec<Doc<'a>>) {
+        self.comments(i, docs);
+        let token = self.tokens[i];
+        let space = match self.prev {
+            Prev::Token(prev) => self.space_between(prev, i),
+            Prev::Comment => self.gaps[i].1.space,
+            Prev::Break { .. } => false,
+        };
+        if space {
+            docs.push(Doc::Text(" "));
+        }
+
+        docs.push(Doc::Text(token.text));
+        self.prev = Prev::Token(i);
+        self.opened = is_open(token.tok);
+        self.next = i + 1;
+    }
+
+    /// Write the comments before token `i` that are on the same line as the token before them.
+    fn trailing_comments(&mut self, i: usize, docs: &mut Vec<Doc<'a>>) {
+        while let Some((spacing, text)) = self.gaps[i].0.get(self.written[i]).copied() {
+            if spacing.newlines > 0 {
+                break;
+            }
+            self.comment(spacing, text, docs);
+            self.written[i] += 1;
+        }
+    }
+
+    /// Write the comments before token `i` that have not been written yet.
+    fn comments(&mut self, i: usize, docs: &mut Vec<Doc<'a>>) {
+        let (comments, spacing) = &self.gaps[i];
+        let start = self.written[i];
+        if start == comments.len() {
+            return;
+        }
+
+        let spacing = *spacing;
+        for k in start..comments.len() {
+            let (spacing, text) = self.gaps[i].0[k];
+            self.comment(spacing, text, docs);
+        }
+        self.written[i] = self.gaps[i].0.len();
+
+        if spacing.newlines > 0 {
+            docs.push(self.blank_lines(spacing.newlines));
+            self.prev = Prev::Break { soft: false };
+        }
+    }
+
+    fn comment(&mut self, spacing: Spacing, text: &'a str, docs: &mut Vec<Doc<'a>>) {
+        if spacing.newlines > 0 {
+            docs.push(self.blank_lines(spacing.newlines));
+        } else if !matches!(self.prev, Prev::Break { .. })
+            && (spacing.space || text.starts_with("//"))
+        {
+            docs.push(Doc::Text(" "));
+        }
+
+        docs.push(Doc::Text(text));
+        self.opened = false;
+        if text.starts_with("//") {
+            docs.push(Doc::HardLine);
+            self.prev = Prev::Break { soft: false };
+        } else {
+            self.prev = Prev::Comment;
+        }
+    }
+
+    /// Whether to separate tokens `prev` and `next` with a space when they are on the same line.
+    fn space_between(&self, prev: usize, next: usize) -> bool {
+        let operators = &self.layout.operators;
+        operators.contains(&prev)
+            || operators.contains(&next)
+            || space_between(
+                self.tokens[prev].tok,
+                self.tokens[next].tok,
+                self.gaps[next].1.space,
+            )
+    }
+}
+
+fn is_open(tok: Tok) -> bool {
+    matches!(tok, Tok::LParen | Tok::LBracket | Tok::LBrace)
+}
+
+fn is_close(tok: Tok) -> bool {
+    matches!(tok, Tok::RParen | Tok::RBracket | Tok::RBrace)
+}
+
+/// Operators that are always written with a space on either side.
+fn is_binary_operator(tok: Tok) -> bool {
+    use Tok::*;
+    matches!(
+        tok,
+        Equal
+            | EqualEqual
+            | ExclaimEqual
+            | LessEqual
+            | GreaterEqual
+            | AmpAmp
+            | PipePipe
+            | Plus
+            | Minus
+            | Slash
+            | Percent
+            | Caret
+            | LessLess
+            | EqualGreater
+            | EqualEqualGreater
+            | LessEqualEqualGreater
+            | MinusGreater
+    )
+}
+
+/// Tokens whose role depends on context the formatter does not track, like `<` in a type argument
+/// list or a comparison, or `&` as a reference or a bitwise and. The author's spacing around
+/// them is kept.
+fn is_ambiguous(tok: Tok) -> bool {
+    use Tok::*;
+    matches!(
+        tok,
+        Less | Greater | GreaterGreater | Amp | Star | Pipe | PeriodPeriod
+    )
+}
+
+/// Whether to separate `prev` and `next` on the same line with a space, given whether they were
+/// separated by whitespace in the original source.
+fn space_between(prev: Tok, next: Tok, original: bool) -> bool {
+    use Tok::*;
+    match (prev, next) {
+        (LParen | LBracket | ColonColon | Period | NumSign | AtSign | Exclaim, _) => false,
+        (AmpMut, _) => true,
+        (_, RParen | RBracket | Comma | Semicolon | Colon | Period | ColonColon) => false,
+        (LBrace, RBrace) => false,
+        (LBrace | RBrace, _) | (_, LBrace | RBrace) => true,
+        (Comma | Semicolon | Colon, _) => true,
+        // Calls, indexing and macro invocations.
+        (Identifier | RestrictedIdentifier | SyntaxIdentifier, LParen | LBracket | Exclaim) => {
+            false
+        }
+        (RParen | RBracket, LBracket) | (Public, LParen) => false,
+        (If | While | Match, LParen) => true,
+        _ if is_binary_operator(prev) || is_binary_operator(next) => true,
+        _ if is_ambiguous(prev) || is_ambiguous(next) => original,
+        (_, LParen | LBracket) => original,
+        _ => true,
+    }
+}
@@ -20,7 +20,10 @@ use move_compiler::{
 };
 use move_core_types::account_address::AccountAddress;
 use move_model::model::GlobalEnv;
-use resolution::{dependency_graph::DependencyGraphBuilder, resolution_graph::ResolvedGraph};
+use resolution::{
+    dependency_graph::DependencyGraphBuilder, resolution_graph::ResolvedGraph,
+    vendor::VendoredDependencies,
+};
 use serde::{Deserialize, Serialize};
 use source_package::{
     layout::SourcePackageLayout,
@@ -80,6 +83,11 @@ pub struct BuildConfig {
     #[clap(long = "skip-fetch-latest-git-deps", global = true)]
     pub skip_fetch_latest_git_deps: bool,
 
+    /// Fail instead of accessing the network, e.g. to fetch git dependencies. Only vendored
+    /// dependencies and dependencies that were fetched before are used
+    #[clap(long = "offline", global = true)]
+    pub offline: bool,
+
     /// Default flavor for move compilation, if not specified in the package's config
     #[clap(long = "default-move-flavor", global = true)]
     pub default_flavor: Option<Flavor>,
@@ -97,8 +105,12 @@ pub struct BuildConfig {
     #[clap(long = move_compiler::command_line::SILENCE_WARNINGS, global = true)]
     pub silence_warnings: bool,
 
-    /// If set, warnings become errors
-    #[clap(long = move_compiler::command_line::WARNINGS_ARE_ERRORS, global = true)]
+    /// If set, warnings become errors. Also available as `--deny-warnings`
+    #[clap(
+        long = move_compiler::command_line::WARNINGS_ARE_ERRORS,
+        alias = "deny-warnings",
+        global = true
+    )]
     pub warnings_are_errors: bool,
 
     /// If set, reports errors at JSON
@@ -268,6 +280,18 @@ impl BuildConfig {
         Ok(())
     }
 
+    /// Copy the remote dependencies of the package at `path` into its `vendor` directory, and pin
+    /// their digests in its lock file, so that the package can be built without network access.
+    pub fn vendor_deps_for_package<W: Write>(&self, path: &Path, writer: &mut W) -> Result<()> {
+        let path = SourcePackageLayout::try_find_root(path)?;
+        let manifest_string =
+            std::fs::read_to_string(path.join(SourcePackageLayout::Manifest.path()))?;
+        let lock_string = std::fs::read_to_string(path.join(SourcePackageLayout::Lock.path())).ok();
+        let _mutx = PackageLock::lock(); // held until function returns
+
+        resolution::vendor::vendor_dependencies(manifest_string, lock_string, self, &path, writer)
+    }
+
     pub fn resolution_graph_for_package<W: Write>(
         mut self,
         path: &Path,
@@ -287,11 +311,14 @@ impl BuildConfig {
         let install_dir_set = self.install_dir.is_some();
         let install_dir = self.install_dir.as_ref().unwrap_or(&path).to_owned();
 
+        let vendored = VendoredDependencies::read(&path, lock_string.as_deref())?;
         let mut dep_graph_builder = DependencyGraphBuilder::new(
             self.skip_fetch_latest_git_deps,
             writer,
             install_dir.clone(),
-        );
+        )
+        .offline(self.offline)
+        .vendored(vendored);
         let (dependency_graph, modified) = dep_graph_builder.get_graph(
             &DependencyKind::default(),
             path,
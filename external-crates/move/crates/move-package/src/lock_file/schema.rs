@@ -6,7 +6,7 @@
 //! crate related to serializing types as inline tables.
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     io::{Read, Seek, Write},
 };
 
@@ -43,6 +43,9 @@ const LATEST_PUBLISHED_ID_KEY: &str = "latest-published-id";
 const PUBLISHED_VERSION_KEY: &str = "published-version";
 const CHAIN_ID_KEY: &str = "chain-id";
 
+/// Table in `[move]` pinning the digests of vendored dependencies.
+const VENDOR_TABLE_NAME: &str = "vendor";
+
 #[derive(Deserialize)]
 pub struct Packages {
     #[serde(rename = "package")]
@@ -112,6 +115,13 @@ pub struct ManagedPackage {
     pub version: String,
 }
 
+/// Digests of the dependencies vendored into a package, by their path in its vendor directory.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct VendoredPackages {
+    #[serde(flatten)]
+    pub digests: BTreeMap<String, String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Header {
     pub version: u16,
@@ -187,6 +197,27 @@ impl ManagedPackage {
     }
 }
 
+impl VendoredPackages {
+    /// Read the digests of vendored dependencies from the lock file. Returns successfully with no
+    /// digests if parsing the lock file succeeds but an entry for `[move.vendor]` does not exist.
+    pub fn read(lock: &mut impl Read) -> Result<VendoredPackages> {
+        let contents = {
+            let mut buf = String::new();
+            lock.read_to_string(&mut buf).context("Reading lock file")?;
+            buf
+        };
+
+        #[derive(Deserialize)]
+        struct VP {
+            vendor: Option<VendoredPackages>,
+        }
+        let Schema { move_: value } = toml::de::from_str::<Schema<VP>>(&contents)
+            .context("Deserializing vendored packages")?;
+
+        Ok(value.vendor.unwrap_or_default())
+    }
+}
+
 impl Header {
     /// Read lock file header after verifying that the version of the lock is not newer than the version
     /// supported by this library.
@@ -312,6 +343,30 @@ pub fn update_compiler_toolchain(
     Ok(())
 }
 
+/// Replaces the digests of vendored dependencies in the lock file, removing the `[move.vendor]`
+/// table if there are none.
+pub fn update_vendored_packages(file: &mut LockFile, vendored: &VendoredPackages) -> Result<()> {
+    let mut toml_string = String::new();
+    file.rewind()?;
+    file.read_to_string(&mut toml_string)?;
+    let mut toml = toml_string.parse::<toml_edit::Document>()?;
+    let move_table = toml["move"]
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("Could not find move table in Move.lock"))?;
+    if vendored.digests.is_empty() {
+        move_table.remove(VENDOR_TABLE_NAME);
+    } else {
+        move_table[VENDOR_TABLE_NAME] = to_toml_edit_value(&toml::Value::try_from(vendored)?);
+    }
+
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{}", toml)?;
+    file.flush()?;
+    file.rewind()?;
+    Ok(())
+}
+
 fn to_toml_edit_value(value: &toml::Value) -> toml_edit::Item {
     match value {
         Value::String(v) => EItem(EValue::from(v.clone())),
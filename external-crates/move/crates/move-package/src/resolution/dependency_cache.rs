@@ -1,7 +1,7 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use colored::Colorize;
 use std::{
     collections::BTreeSet,
@@ -16,7 +16,7 @@ use crate::{
     source_package::parsed_manifest::{DependencyKind, GitInfo, PackageName},
 };
 
-use super::repository_path;
+use super::{local_path, repository_path, vendor::VendoredDependencies};
 
 /// Fetches remote dependencies and caches information about those already fetched when building a
 /// given package.
@@ -28,6 +28,12 @@ pub struct DependencyCache {
     /// Should a dependency fetched when building a different package be refreshed to the newest
     /// version when building a new package
     skip_fetch_latest_git_deps: bool,
+
+    /// Fail instead of fetching dependencies over the network
+    offline: bool,
+
+    /// Dependencies whose vendored copies are used instead of fetching them
+    vendored: VendoredDependencies,
 }
 
 impl DependencyCache {
@@ -36,23 +42,64 @@ impl DependencyCache {
         DependencyCache {
             fetched_deps,
             skip_fetch_latest_git_deps,
+            offline: false,
+            vendored: VendoredDependencies::default(),
         }
     }
 
+    /// Fail instead of fetching dependencies over the network: only vendored dependencies and
+    /// dependencies that were fetched before can be used.
+    pub fn offline(self, offline: bool) -> DependencyCache {
+        DependencyCache { offline, ..self }
+    }
+
+    /// Use the vendored copies of `vendored` dependencies, instead of fetching them.
+    pub fn vendored(self, vendored: VendoredDependencies) -> DependencyCache {
+        DependencyCache { vendored, ..self }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// The path that the dependency of kind `kind` is found at locally, after it is fetched.
+    pub fn local_path(&self, kind: &DependencyKind) -> PathBuf {
+        self.vendored.path(kind).unwrap_or_else(|| local_path(kind))
+    }
+
     pub fn download_and_update_if_remote<Progress: Write>(
         &mut self,
         dep_name: PackageName,
         kind: &DependencyKind,
         progress_output: &mut Progress,
     ) -> Result<()> {
+        if let Some(vendored_path) = self.vendored.path(kind) {
+            // check the vendored copy the first time it is used
+            if self.fetched_deps.insert(vendored_path) {
+                self.vendored.verify(dep_name, kind)?;
+            }
+            return Ok(());
+        }
+
         match kind {
             DependencyKind::Local(_) => Ok(()),
 
             DependencyKind::OnChain(info) => {
                 // check if a give dependency type has already been fetched
-                if !self.fetched_deps.insert(repository_path(kind)) {
+                let repository_path = repository_path(kind);
+                if !self.fetched_deps.insert(repository_path.clone()) {
                     return Ok(());
                 }
+                if self.offline {
+                    if repository_path.exists() {
+                        return Ok(());
+                    }
+                    bail!(
+                        "On-chain dependency '{}' has not been fetched or vendored, and cannot be \
+                         fetched with --offline",
+                        dep_name
+                    );
+                }
                 package_hooks::resolve_on_chain_dependency(dep_name, info)
             }
 
@@ -67,6 +114,19 @@ impl DependencyCache {
                     return Ok(());
                 }
 
+                // dependencies fetched before are used as they are
+                if self.offline {
+                    if repository_path.exists() {
+                        return Ok(());
+                    }
+                    bail!(
+                        "Git dependency '{}' has not been fetched or vendored, and cannot be \
+                         fetched from {} with --offline",
+                        dep_name,
+                        git_url
+                    );
+                }
+
                 if Command::new("git").arg("--version").output().is_err() {
                     writeln!(progress_output, "Git is not installed or not in the PATH.")?;
                     return Err(anyhow::anyhow!("Git is not installed or not in the PATH."));
@@ -31,7 +31,7 @@ use crate::{
 use super::{
     dependency_cache::DependencyCache,
     digest::{digest_str, hashed_files_digest},
-    local_path,
+    vendor::VendoredDependencies,
 };
 
 /// A representation of the transitive dependency graph of a Move package.  If successfully created,
@@ -214,6 +214,18 @@ impl<Progress: Write> DependencyGraphBuilder<Progress> {
         }
     }
 
+    /// Fail instead of accessing the network to resolve dependencies.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.dependency_cache = self.dependency_cache.offline(offline);
+        self
+    }
+
+    /// Use the vendored copies of `vendored` dependencies, instead of fetching them.
+    pub fn vendored(mut self, vendored: VendoredDependencies) -> Self {
+        self.dependency_cache = self.dependency_cache.vendored(vendored);
+        self
+    }
+
     /// Get a new graph by either reading it from Move.lock file (if this file is up-to-date, in
     /// which case also return false) or by computing a new graph based on the content of the
     /// Move.toml (manifest) file (in which case also return true).
@@ -449,7 +461,7 @@ impl<Progress: Write> DependencyGraphBuilder<Progress> {
                 self.dependency_cache
                     .download_and_update_if_remote(dep_pkg_name, &d.kind, &mut self.progress_output)
                     .with_context(|| format!("Fetching '{}'", dep_pkg_name))?;
-                let pkg_path = dep_pkg_path.join(local_path(&d.kind));
+                let pkg_path = dep_pkg_path.join(self.dependency_cache.local_path(&d.kind));
                 let manifest_string =
                     std::fs::read_to_string(pkg_path.join(SourcePackageLayout::Manifest.path()))
                         .with_context(|| format!("Parsing manifest for '{}'", dep_pkg_name))?;
@@ -498,6 +510,13 @@ impl<Progress: Write> DependencyGraphBuilder<Progress> {
                 )])
             }
             PM::Dependency::External(resolver) => {
+                // external resolvers are free to access the network
+                if self.dependency_cache.is_offline() {
+                    bail!(
+                        "Dependency '{dep_pkg_name}' is resolved by the external resolver \
+                         '{resolver}', which cannot be used with --offline"
+                    );
+                }
                 let external_deps = DependencyGraph::get_external(
                     mode,
                     parent_pkg_id,
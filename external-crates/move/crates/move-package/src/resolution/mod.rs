@@ -14,13 +14,14 @@ use crate::{
     BuildConfig,
 };
 
-use self::dependency_graph::DependencyGraphBuilder;
+use self::{dependency_graph::DependencyGraphBuilder, vendor::VendoredDependencies};
 
 pub mod dependency_cache;
 pub mod dependency_graph;
 mod digest;
 pub mod resolution_graph;
 pub mod resolving_table;
+pub mod vendor;
 
 pub fn download_dependency_repos<Progress: Write>(
     manifest_string: String,
@@ -34,11 +35,14 @@ pub fn download_dependency_repos<Progress: Write>(
         .as_ref()
         .unwrap_or(&root_path.to_path_buf())
         .to_owned();
+    let vendored = VendoredDependencies::read(root_path, lock_string.as_deref())?;
     let mut dep_graph_builder = DependencyGraphBuilder::new(
         build_options.skip_fetch_latest_git_deps,
         progress_output,
         install_dir,
-    );
+    )
+    .offline(build_options.offline)
+    .vendored(vendored);
     let (graph, _) = dep_graph_builder.get_graph(
         &DependencyKind::default(),
         root_path.to_path_buf(),
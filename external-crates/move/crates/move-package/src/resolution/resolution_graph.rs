@@ -8,7 +8,11 @@ use move_command_line_common::files::{
 };
 use move_compiler::command_line::DEFAULT_OUTPUT_DIR;
 use move_compiler::editions::Edition;
-use move_compiler::{diagnostics::warning_filters::WarningFiltersBuilder, shared::PackageConfig};
+use move_compiler::{
+    diagnostics::warning_filters::{WarningFiltersBuilder, FILTER_ALL},
+    linters::LintLevel,
+    shared::PackageConfig,
+};
 use move_core_types::account_address::AccountAddress;
 use move_symbol_pool::Symbol;
 use std::fs::File;
@@ -27,16 +31,17 @@ use crate::source_package::parsed_manifest as PM;
 use crate::{
     source_package::{
         layout::SourcePackageLayout,
-        manifest_parser::parse_move_manifest_from_file,
+        manifest_parser::{known_lints, parse_move_manifest_from_file},
         parsed_manifest::{
-            FileName, NamedAddress, PackageDigest, PackageName, SourceManifest, SubstOrRename,
+            FileName, LintSetting, NamedAddress, PackageDigest, PackageName, SourceManifest,
+            SubstOrRename,
         },
     },
     BuildConfig,
 };
 
 use super::{
-    dependency_cache::DependencyCache, dependency_graph as DG, digest::compute_digest, local_path,
+    dependency_cache::DependencyCache, dependency_graph as DG, digest::compute_digest,
     resolving_table::ResolvingTable,
 };
 
@@ -111,7 +116,7 @@ impl ResolvedGraph {
                 dependency_cache
                     .download_and_update_if_remote(pkg_id, &pkg.kind, progress_output)
                     .with_context(|| format!("Fetching '{pkg_id}'"))?;
-                graph.root_path.join(local_path(&pkg.kind))
+                graph.root_path.join(dependency_cache.local_path(&pkg.kind))
             };
 
             let mut resolved_pkg = Package::new(package_path, &build_options)
@@ -128,7 +133,9 @@ impl ResolvedGraph {
                         if let PM::DependencyKind::OnChain(_) = internal.kind {
                             continue;
                         }
-                        let dep_path = &resolved_pkg.package_path.join(local_path(&internal.kind));
+                        let dep_path = &resolved_pkg
+                            .package_path
+                            .join(dependency_cache.local_path(&internal.kind));
                         let dep_manifest = parse_move_manifest_from_file(dep_path)?;
                         if dep_name != &dep_manifest.package.name {
                             bail!(
@@ -266,6 +273,27 @@ impl ResolvedGraph {
         self.package_table.get(&name).unwrap()
     }
 
+    /// The level to lint at: the one set on the command line, unless that is the default level
+    /// and the root package's manifest turns on lints that only run with `--lint`.
+    pub fn lint_level(&self) -> LintLevel {
+        let lint_level = self.build_options.lint_flag.get();
+        if lint_level != LintLevel::Default {
+            return lint_level;
+        }
+        let Some(root) = self.package_table.get(&self.root_package()) else {
+            return lint_level;
+        };
+        let known_lints = known_lints();
+        let enables_extra_lints = root.source_package.lints.iter().any(|(name, setting)| {
+            *setting != LintSetting::Allow && known_lints.get(name).is_some_and(|(_, d)| !d)
+        });
+        if enables_extra_lints {
+            LintLevel::All
+        } else {
+            lint_level
+        }
+    }
+
     /// Return the names of packages in this resolution graph in topological order.
     pub fn topological_order(&self) -> Vec<PackageName> {
         let mut order = self.graph.topological_order();
@@ -586,8 +614,39 @@ impl Package {
                 .edition
                 .or(config.default_edition)
                 .unwrap_or(Edition::LEGACY), // TODO require edition
-            warning_filter: WarningFiltersBuilder::new_for_source(),
+            warning_filter: self.lint_filters(config),
+        }
+    }
+
+    /// The package-wide warning filters set by the '[lints]' section of the manifest. Without
+    /// `--lint`, the lints that only run with it are allowed unless the manifest turns them on,
+    /// as they run for all packages once any root package turns one on.
+    fn lint_filters(&self, config: &BuildConfig) -> WarningFiltersBuilder {
+        let lints = &self.source_package.lints;
+        let known_lints = known_lints();
+        let mut filters = WarningFiltersBuilder::new_for_source();
+        for (name, setting) in lints {
+            let Some((filter, _)) = known_lints.get(name) else {
+                continue;
+            };
+            match setting {
+                LintSetting::Allow => filters.add(*filter),
+                LintSetting::Warn => (),
+                LintSetting::Deny => filters.add_denied(*filter),
+            }
+        }
+
+        let all_enabled = lints
+            .get(&Symbol::from(FILTER_ALL))
+            .is_some_and(|setting| *setting != LintSetting::Allow);
+        if config.lint_flag.get() == LintLevel::Default && !all_enabled {
+            for (name, (filter, runs_by_default)) in &known_lints {
+                if !runs_by_default && name.as_str() != FILTER_ALL && !lints.contains_key(name) {
+                    filters.add(*filter)
+                }
+            }
         }
+        filters
     }
 }
 
@@ -0,0 +1,199 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Vendoring copies the remote (git and on-chain) dependencies of a package into its `vendor`
+//! directory, and pins the digest of each copy in the package's lock file. Builds then use the
+//! vendored copies in place of the remote dependencies, without accessing the network.
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    compilation::package_layout::CompiledPackageLayout,
+    lock_file::schema::{self, VendoredPackages},
+    source_package::{
+        layout::SourcePackageLayout,
+        parsed_manifest::{DependencyKind, GitInfo, PackageName},
+    },
+    BuildConfig,
+};
+
+use super::{
+    dependency_graph::DependencyGraphBuilder, digest::compute_digest, local_path, repository_path,
+};
+
+/// The dependencies vendored into a package, and the digests they are pinned to.
+#[derive(Debug, Clone, Default)]
+pub struct VendoredDependencies {
+    /// The package's vendor directory.
+    dir: PathBuf,
+    /// The digest of each vendored package, by its path relative to `dir`.
+    digests: VendoredPackages,
+}
+
+impl VendoredDependencies {
+    /// The dependencies vendored into the package at `root_path`, as pinned in its lock file.
+    pub fn read(root_path: &Path, lock_string: Option<&str>) -> Result<Self> {
+        let digests = match lock_string {
+            Some(lock_string) => VendoredPackages::read(&mut lock_string.as_bytes())?,
+            None => VendoredPackages::default(),
+        };
+        // Dependencies are found relative to the packages that depend on them, so the vendor
+        // directory has to be absolute.
+        let dir = root_path
+            .canonicalize()
+            .with_context(|| format!("Resolving package path {}", root_path.display()))?
+            .join(SourcePackageLayout::Vendor.path());
+        Ok(Self { dir, digests })
+    }
+
+    /// The path the dependency of kind `kind` is vendored at, if it is.
+    pub fn path(&self, kind: &DependencyKind) -> Option<PathBuf> {
+        let key = vendored_key(kind)?;
+        self.digests
+            .digests
+            .contains_key(&key)
+            .then(|| self.dir.join(key))
+    }
+
+    /// Check that the vendored copy of `dep_name` is unchanged since it was vendored.
+    pub fn verify(&self, dep_name: PackageName, kind: &DependencyKind) -> Result<()> {
+        let Some(key) = vendored_key(kind) else {
+            return Ok(());
+        };
+        let Some(expected) = self.digests.digests.get(&key) else {
+            return Ok(());
+        };
+
+        let path = self.dir.join(&key);
+        if !path.is_dir() {
+            bail!(
+                "The vendored copy of '{dep_name}' is missing from {}, re-run `vendor` to restore it",
+                path.display()
+            );
+        }
+        let digest = compute_digest(&[path.clone()])?;
+        if digest.as_str() != expected {
+            bail!(
+                "The vendored copy of '{dep_name}' at {} has digest {digest}, which does not \
+                 match the digest {expected} it is pinned to in Move.lock. Re-run `vendor` to \
+                 refresh it",
+                path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Copies the remote dependencies of the package at `root_path` into its vendor directory
+/// (replacing its previous contents), and pins the digest of each copy in the package's lock
+/// file.
+pub fn vendor_dependencies<Progress: Write>(
+    manifest_string: String,
+    lock_string: Option<String>,
+    build_options: &BuildConfig,
+    root_path: &Path,
+    progress_output: &mut Progress,
+) -> Result<()> {
+    let install_dir = build_options
+        .install_dir
+        .as_ref()
+        .unwrap_or(&root_path.to_path_buf())
+        .to_owned();
+    // Dependencies are vendored from their remote sources (or the copies of them fetched
+    // already), rather than from the vendor directory being replaced.
+    let mut dep_graph_builder = DependencyGraphBuilder::new(
+        build_options.skip_fetch_latest_git_deps,
+        progress_output,
+        install_dir.clone(),
+    )
+    .offline(build_options.offline);
+    let (graph, _) = dep_graph_builder.get_graph(
+        &DependencyKind::default(),
+        root_path.to_path_buf(),
+        manifest_string,
+        lock_string,
+    )?;
+
+    let vendor_dir = root_path.join(SourcePackageLayout::Vendor.path());
+    if vendor_dir.exists() {
+        fs::remove_dir_all(&vendor_dir)
+            .with_context(|| format!("Removing {}", vendor_dir.display()))?;
+    }
+
+    let mut vendored = VendoredPackages::default();
+    for (pkg_id, package) in &graph.package_table {
+        let Some(key) = vendored_key(&package.kind) else {
+            continue;
+        };
+
+        let DependencyGraphBuilder {
+            ref mut dependency_cache,
+            ref mut progress_output,
+            ..
+        } = dep_graph_builder;
+        dependency_cache
+            .download_and_update_if_remote(*pkg_id, &package.kind, progress_output)
+            .with_context(|| format!("Fetching '{pkg_id}'"))?;
+
+        let vendored_path = vendor_dir.join(&key);
+        copy_package(&local_path(&package.kind), &vendored_path)
+            .with_context(|| format!("Vendoring '{pkg_id}'"))?;
+        let digest = compute_digest(&[vendored_path])?;
+        writeln!(
+            progress_output,
+            "{} {pkg_id} {} {key}",
+            "VENDORED".bold().green(),
+            "TO".bold().green(),
+        )?;
+        vendored.digests.insert(key, digest.to_string());
+    }
+
+    let lock_path = root_path.join(SourcePackageLayout::Lock.path());
+    let mut lock = graph.write_to_lock(install_dir, Some(lock_path.clone()))?;
+    schema::update_vendored_packages(&mut lock, &vendored)?;
+    lock.commit(lock_path)
+}
+
+/// The path the dependency of kind `kind` is vendored at, relative to the vendor directory, or
+/// `None` for dependencies that are not vendored (local ones). This mirrors the dependency's
+/// location in the cache of fetched dependencies.
+fn vendored_key(kind: &DependencyKind) -> Option<String> {
+    let mut key = match kind {
+        DependencyKind::Local(_) => return None,
+        DependencyKind::Git(_) | DependencyKind::OnChain(_) => repository_path(kind)
+            .file_name()?
+            .to_string_lossy()
+            .to_string(),
+    };
+    if let DependencyKind::Git(GitInfo { subdir, .. }) = kind {
+        for component in subdir.components() {
+            key.push('/');
+            key.push_str(&component.as_os_str().to_string_lossy());
+        }
+    }
+    Some(key)
+}
+
+/// Copies the package at `from` to `to`, leaving out version control and build output.
+fn copy_package(from: &Path, to: &Path) -> Result<()> {
+    let build_dir = from.join(CompiledPackageLayout::Root.path());
+    let entries = walkdir::WalkDir::new(from)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git" && entry.path() != build_dir);
+    for entry in entries {
+        let entry = entry?;
+        let target = to.join(entry.path().strip_prefix(from)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
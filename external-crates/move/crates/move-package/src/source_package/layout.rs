@@ -19,6 +19,7 @@ pub enum SourcePackageLayout {
     Manifest,
     Lock,
     DocTemplates,
+    Vendor,
 }
 
 impl SourcePackageLayout {
@@ -31,6 +32,7 @@ impl SourcePackageLayout {
     /// ├── scripts        (optional)
     /// ├── specifications (optional)
     /// ├── doc_templates      (optional)
+    /// ├── vendor         (optional, vendored dependencies)
     /// └── tests          (optional, test mode)
     pub fn path(&self) -> &Path {
         Path::new(self.location_str())
@@ -62,6 +64,7 @@ impl SourcePackageLayout {
             Self::Examples => "examples",
             Self::Specifications => "specifications",
             Self::DocTemplates => "doc_templates",
+            Self::Vendor => "vendor",
         }
     }
 
@@ -73,6 +76,7 @@ impl SourcePackageLayout {
             | Self::Examples
             | Self::Specifications
             | Self::DocTemplates
+            | Self::Vendor
             | Self::Lock => true,
         }
     }
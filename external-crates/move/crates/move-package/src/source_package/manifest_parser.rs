@@ -4,7 +4,11 @@
 
 use crate::{package_hooks, source_package::parsed_manifest as PM};
 use anyhow::{anyhow, bail, format_err, Context, Result};
-use move_compiler::editions::{Edition, Flavor};
+use move_compiler::{
+    diagnostics::warning_filters::WarningFilter,
+    editions::{Edition, Flavor},
+    linters, sui_mode,
+};
 use move_core_types::account_address::{AccountAddress, AccountAddressParseError};
 use move_symbol_pool::symbol::Symbol;
 use std::{
@@ -24,6 +28,9 @@ const ADDRESSES_NAME: &str = "addresses";
 const DEV_ADDRESSES_NAME: &str = "dev-addresses";
 const DEPENDENCY_NAME: &str = "dependencies";
 const DEV_DEPENDENCY_NAME: &str = "dev-dependencies";
+const LINTS_NAME: &str = "lints";
+/// Settings for `move fmt`, which are read by the formatter rather than the package system.
+pub const FORMAT_NAME: &str = "format";
 
 const EXTERNAL_RESOLVER_PREFIX: &str = "r";
 
@@ -35,6 +42,8 @@ const KNOWN_NAMES: &[&str] = &[
     DEPENDENCY_NAME,
     DEV_DEPENDENCY_NAME,
     EXTERNAL_RESOLVER_PREFIX,
+    FORMAT_NAME,
+    LINTS_NAME,
 ];
 
 const REQUIRED_FIELDS: &[&str] = &[PACKAGE_NAME];
@@ -97,6 +106,12 @@ pub fn parse_source_manifest(tval: TV) -> Result<PM::SourceManifest> {
                 .transpose()
                 .context("Error parsing '[dev-dependencies]' section of manifest")?
                 .unwrap_or_default();
+            let lints = table
+                .remove(LINTS_NAME)
+                .map(parse_lints)
+                .transpose()
+                .context("Error parsing '[lints]' section of manifest")?
+                .unwrap_or_default();
             Ok(PM::SourceManifest {
                 package,
                 addresses,
@@ -104,6 +119,7 @@ pub fn parse_source_manifest(tval: TV) -> Result<PM::SourceManifest> {
                 build,
                 dependencies,
                 dev_dependencies,
+                lints,
             })
         }
         x => {
@@ -241,6 +257,62 @@ pub fn parse_build_info(tval: TV) -> Result<PM::BuildInfo> {
     }
 }
 
+pub fn parse_lints(tval: TV) -> Result<PM::Lints> {
+    match tval {
+        TV::Table(table) => {
+            let known_lints = known_lints();
+            let mut lints = BTreeMap::new();
+            for (name, setting) in table.into_iter() {
+                let name = Symbol::from(name);
+                if !known_lints.contains_key(&name) {
+                    bail!(
+                        "Unknown lint '{}'. Known lints are: {}",
+                        name,
+                        known_lints
+                            .keys()
+                            .map(|name| name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+                let setting = match setting.as_str() {
+                    Some("allow") => PM::LintSetting::Allow,
+                    Some("warn") => PM::LintSetting::Warn,
+                    Some("deny") => PM::LintSetting::Deny,
+                    _ => bail!(
+                        "Invalid level {} for lint '{}'. Expected \"allow\", \"warn\" or \"deny\"",
+                        setting,
+                        name
+                    ),
+                };
+                lints.insert(name, setting);
+            }
+            Ok(lints)
+        }
+        x => bail!(
+            "Malformed section in manifest {}. Expected a table, but encountered a {}",
+            x,
+            x.type_str()
+        ),
+    }
+}
+
+/// The lints that can be configured in the '[lints]' section of a manifest, by name: the filter
+/// for the lint's warnings, and whether the lint runs without `--lint`.
+pub fn known_lints() -> BTreeMap<Symbol, (WarningFilter, bool)> {
+    let (_, move_lints) = linters::known_filters();
+    let (_, sui_lints) = sui_mode::linters::known_filters();
+    move_lints
+        .into_iter()
+        .chain(sui_lints)
+        .filter_map(|filter| {
+            let name = filter.to_str()?;
+            let runs_by_default = sui_mode::linters::is_default_lint(name);
+            Some((Symbol::from(name), (filter, runs_by_default)))
+        })
+        .collect()
+}
+
 pub fn parse_addresses(tval: TV) -> Result<PM::AddressDeclarations> {
     match tval {
         TV::Table(table) => {
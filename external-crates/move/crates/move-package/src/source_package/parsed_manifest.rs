@@ -23,6 +23,7 @@ pub type DevAddressDeclarations = BTreeMap<NamedAddress, AccountAddress>;
 pub type Version = (u64, u64, u64);
 pub type Dependencies = BTreeMap<PackageName, Dependency>;
 pub type Substitution = BTreeMap<NamedAddress, SubstOrRename>;
+pub type Lints = BTreeMap<Symbol, LintSetting>;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SourceManifest {
@@ -32,6 +33,7 @@ pub struct SourceManifest {
     pub build: Option<BuildInfo>,
     pub dependencies: Dependencies,
     pub dev_dependencies: Dependencies,
+    pub lints: Lints,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -87,6 +89,17 @@ pub struct BuildInfo {
     pub language_version: Option<Version>,
 }
 
+/// How the warnings of a lint are reported for a whole package, as set in its `[lints]` section.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum LintSetting {
+    /// The lint's warnings are not reported
+    Allow,
+    /// The lint's warnings are reported as warnings, and the lint runs even without `--lint`
+    Warn,
+    /// The lint's warnings are reported as errors, and the lint runs even without `--lint`
+    Deny,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub enum SubstOrRename {
     RenameFrom(NamedAddress),
@@ -10,8 +10,11 @@ use std::{
 
 use move_package::{
     lock_file::LockFile,
-    resolution::dependency_graph::{
-        DependencyGraph, DependencyGraphBuilder, DependencyGraphInfo, DependencyMode,
+    resolution::{
+        dependency_graph::{
+            DependencyGraph, DependencyGraphBuilder, DependencyGraphInfo, DependencyMode,
+        },
+        vendor::VendoredDependencies,
     },
     source_package::{
         layout::SourcePackageLayout,
@@ -19,6 +22,7 @@ use move_package::{
     },
 };
 use move_symbol_pool::Symbol;
+use tempfile::TempDir;
 
 macro_rules! assert_error_contains {
     ($err:expr, $sub:expr) => {
@@ -603,6 +607,93 @@ fn immediate_dependencies() {
     assert_eq!(deps(d, DependencyMode::DevOnly), BTreeSet::from([]));
 }
 
+#[test]
+fn offline_unfetched_git_dep() {
+    let pkg = git_dep_test_package("https://example.com/unfetched.git");
+
+    let manifest_string = fs::read_to_string(pkg.path().join(SourcePackageLayout::Manifest.path()))
+        .expect("Loading manifest");
+    let mut dep_graph_builder = DependencyGraphBuilder::new(
+        /* skip_fetch_latest_git_deps */ false,
+        std::io::sink(),
+        tempfile::tempdir().unwrap().path().to_path_buf(),
+    )
+    .offline(true);
+    let Err(err) = dep_graph_builder.get_graph(
+        &DependencyKind::default(),
+        pkg.path().to_path_buf(),
+        manifest_string,
+        /* lock_string_opt */ None,
+    ) else {
+        panic!("Unfetched git dependency resolved offline");
+    };
+
+    assert_error_contains!(
+        format!("{err:#}"),
+        "Git dependency 'Dep' has not been fetched or vendored, and cannot be fetched from \
+         https://example.com/unfetched.git with --offline"
+    );
+}
+
+#[test]
+fn vendored_dep_digest_mismatch() {
+    let pkg = git_dep_test_package("https://example.com/vendored.git");
+
+    // Vendor a copy of the dependency, but pin a different digest for it.
+    let vendored = pkg
+        .path()
+        .join(SourcePackageLayout::Vendor.path())
+        .join("https___example_com_vendored_git_main")
+        .join("dep");
+    fs::create_dir_all(vendored.join(SourcePackageLayout::Sources.path())).unwrap();
+    fs::write(
+        vendored.join(SourcePackageLayout::Manifest.path()),
+        "[package]\nname = \"Dep\"\n",
+    )
+    .unwrap();
+    let lock_string = format!(
+        "{EMPTY_LOCK}\n[move.vendor]\n\"https___example_com_vendored_git_main/dep\" = \"0\"\n"
+    );
+
+    let manifest_string = fs::read_to_string(pkg.path().join(SourcePackageLayout::Manifest.path()))
+        .expect("Loading manifest");
+    let mut dep_graph_builder = DependencyGraphBuilder::new(
+        /* skip_fetch_latest_git_deps */ false,
+        std::io::sink(),
+        tempfile::tempdir().unwrap().path().to_path_buf(),
+    )
+    .offline(true)
+    .vendored(VendoredDependencies::read(pkg.path(), Some(&lock_string)).unwrap());
+    let Err(err) = dep_graph_builder.get_graph(
+        &DependencyKind::default(),
+        pkg.path().to_path_buf(),
+        manifest_string,
+        Some(lock_string),
+    ) else {
+        panic!("Vendored dependency with the wrong digest resolved");
+    };
+
+    assert_error_contains!(
+        format!("{err:#}"),
+        "which does not match the digest 0 it is pinned to in Move.lock"
+    );
+}
+
+/// A package in a temporary directory with a single git dependency, on the `dep` directory of the
+/// repository at `git_url`.
+fn git_dep_test_package(git_url: &str) -> TempDir {
+    let pkg = tempfile::tempdir().unwrap();
+    fs::write(
+        pkg.path().join(SourcePackageLayout::Manifest.path()),
+        format!(
+            "[package]\nname = \"Root\"\n\n[dependencies]\n\
+             Dep = {{ git = \"{git_url}\", rev = \"main\", subdir = \"dep\" }}\n"
+        ),
+    )
+    .unwrap();
+    pkg
+}
+
 fn no_dep_test_package() -> PathBuf {
     [".", "tests", "test_sources", "basic_no_deps"]
         .into_iter()
@@ -0,0 +1,106 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_compiler::diagnostics::{codes::Severity, Diagnostics};
+use move_package::{compilation::build_plan::BuildPlan, BuildConfig, LintFlag};
+use std::fs;
+use tempfile::tempdir;
+
+const CONSTANT_NAMING: &str = "constant should follow naming convention";
+
+/// Build a package whose only module breaks the `constant_naming` lint, with `lints` as the
+/// `[lints]` section of its manifest. Returns the diagnostics, as an error if the build failed.
+fn build(lint_flag: LintFlag, lints: &str) -> Result<Diagnostics, Diagnostics> {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("Move.toml"),
+        format!(
+            "[package]\n\
+             name = \"Lints\"\n\
+             edition = \"2024.beta\"\n\
+             \n\
+             [addresses]\n\
+             lints = \"0x1\"\n\
+             \n\
+             [lints]\n\
+             {lints}\n"
+        ),
+    )
+    .unwrap();
+
+    fs::create_dir(dir.path().join("sources")).unwrap();
+    fs::write(
+        dir.path().join("sources").join("m.move"),
+        "module lints::m {\n    const Bad_name: u64 = 0;\n\n    public fun f(): u64 { Bad_name }\n}\n",
+    )
+    .unwrap();
+
+    let config = BuildConfig {
+        dev_mode: true,
+        install_dir: Some(dir.path().join("build")),
+        lint_flag,
+        ..Default::default()
+    };
+
+    let resolved_graph = config
+        .resolution_graph_for_package(dir.path(), None, &mut Vec::new())
+        .unwrap();
+
+    let mut diags = None;
+    let _ = BuildPlan::create(resolved_graph)
+        .unwrap()
+        .compile_with_driver(&mut Vec::new(), |compiler| {
+            let (files, units) = compiler.build()?;
+            match units {
+                Ok((units, warnings)) => {
+                    diags = Some(Ok(warnings));
+                    Ok((files, units))
+                }
+                Err(errors) => {
+                    diags = Some(Err(errors));
+                    anyhow::bail!("Failed to compile")
+                }
+            }
+        });
+
+    diags.expect("Compiler did not run")
+}
+
+/// The severities of the `constant_naming` diagnostics in `diags`.
+fn constant_naming(diags: Diagnostics) -> Vec<Severity> {
+    diags
+        .into_vec()
+        .into_iter()
+        .filter(|diag| diag.info().message() == CONSTANT_NAMING)
+        .map(|diag| diag.info().severity())
+        .collect()
+}
+
+#[test]
+fn test_lint_runs_only_when_enabled() {
+    // `constant_naming` only runs with `--lint`, or when the manifest sets it.
+    let diags = build(LintFlag::LEVEL_DEFAULT, "").unwrap();
+    assert_eq!(constant_naming(diags), vec![]);
+
+    let diags = build(LintFlag::LEVEL_ALL, "").unwrap();
+    assert_eq!(constant_naming(diags), vec![Severity::Warning]);
+
+    let diags = build(LintFlag::LEVEL_DEFAULT, "constant_naming = \"warn\"").unwrap();
+    assert_eq!(constant_naming(diags), vec![Severity::Warning]);
+}
+
+#[test]
+fn test_deny_lint() {
+    let diags = build(LintFlag::LEVEL_DEFAULT, "constant_naming = \"deny\"").unwrap_err();
+    assert_eq!(constant_naming(diags), vec![Severity::NonblockingError]);
+
+    let diags = build(LintFlag::LEVEL_ALL, "constant_naming = \"deny\"").unwrap_err();
+    assert_eq!(constant_naming(diags), vec![Severity::NonblockingError]);
+}
+
+#[test]
+fn test_allow_lint() {
+    // Even with `--lint`, an allowed lint is not reported.
+    let diags = build(LintFlag::LEVEL_ALL, "constant_naming = \"allow\"").unwrap();
+    assert_eq!(constant_naming(diags), vec![]);
+}
@@ -3,6 +3,7 @@
 
 use expect_test::expect;
 use std::{
+    collections::BTreeMap,
     fs::{self, File},
     io::{self, Read, Write},
     path::PathBuf,
@@ -11,7 +12,8 @@ use tempfile::TempDir;
 
 use move_compiler::editions::{Edition, Flavor};
 use move_package::lock_file::schema::{
-    update_managed_address, ManagedAddressUpdate, ManagedPackage, ToolchainVersion,
+    update_managed_address, update_vendored_packages, ManagedAddressUpdate, ManagedPackage,
+    ToolchainVersion, VendoredPackages,
 };
 use move_package::lock_file::LockFile;
 use move_package::resolution::dependency_graph::DependencyGraph;
@@ -261,6 +263,46 @@ fn test_update_managed_address() {
     expected.assert_debug_eq(&envs);
 }
 
+#[test]
+fn update_lock_file_vendored_packages() {
+    let pkg = create_test_package().unwrap();
+    let lock_path = pkg.path().join("Move.lock");
+
+    // Initialize lock file.
+    let lock = LockFile::new(
+        pkg.path().to_path_buf(),
+        /* manifest_digest */ "42".to_string(),
+        /* deps_digest */ "7".to_string(),
+    )
+    .unwrap();
+    lock.commit(&lock_path).unwrap();
+
+    let vendored = VendoredPackages {
+        digests: BTreeMap::from([
+            (
+                "https___example_com_repo_git_main/packages/a".to_string(),
+                "ABC".to_string(),
+            ),
+            ("0x2".to_string(), "DEF".to_string()),
+        ]),
+    };
+    let mut lock = LockFile::from(pkg.path().to_path_buf(), &lock_path).unwrap();
+    update_vendored_packages(&mut lock, &vendored).unwrap();
+    lock.commit(&lock_path).unwrap();
+
+    let mut lock_file = File::open(&lock_path).unwrap();
+    let read = VendoredPackages::read(&mut lock_file).unwrap();
+    assert_eq!(read.digests, vendored.digests);
+
+    // Vendoring no packages removes the table.
+    let mut lock = LockFile::from(pkg.path().to_path_buf(), &lock_path).unwrap();
+    update_vendored_packages(&mut lock, &VendoredPackages::default()).unwrap();
+    lock.commit(&lock_path).unwrap();
+
+    let contents = fs::read_to_string(&lock_path).unwrap();
+    assert!(!contents.contains("vendor"), "{contents}");
+}
+
 /// Create a simple Move package with no sources (just a manifest and an output directory) in a
 /// temporary directory, and return it.
 fn create_test_package() -> io::Result<TempDir> {
@@ -0,0 +1,531 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A Debug Adapter Protocol server, which lets DAP-capable editors step through a trace.
+//!
+//! The adapter speaks the base protocol (`Content-Length` framed JSON messages) over a pair of
+//! streams, and implements the subset of requests needed to step, set breakpoints and inspect
+//! locals. Traces have a single thread of execution, which is reported as thread 1.
+
+use crate::{
+    replay::{Replay, StopReason},
+    source::SourceResolver,
+    trace::{MoveTrace, TraceIndex},
+};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+const THREAD_ID: u64 = 1;
+
+/// Values longer than this are abbreviated in the variables view, and can be expanded instead.
+const MAX_VALUE_DISPLAY_LEN: usize = 80;
+
+#[derive(Deserialize)]
+struct Request {
+    seq: u64,
+    command: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchArguments {
+    /// Path to the trace to debug.
+    trace: PathBuf,
+    /// The package's build directory, for source maps and sources. Defaults to the `build`
+    /// directory next to the `traces` directory the trace is in.
+    build_directory: Option<PathBuf>,
+    #[serde(default)]
+    stop_on_entry: bool,
+}
+
+/// Something that can be expanded in the variables view.
+enum Variables {
+    Locals(TraceIndex),
+    Value(Value),
+}
+
+pub struct Adapter<R, W> {
+    input: R,
+    output: W,
+    seq: u64,
+    replay: Option<Replay>,
+    stop_on_entry: bool,
+    /// Expandable variables, referred to by their index plus one. Cleared whenever execution
+    /// moves, as the handles are only valid while stopped.
+    variables: Vec<Variables>,
+}
+
+impl<R: BufRead, W: Write> Adapter<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            seq: 0,
+            replay: None,
+            stop_on_entry: false,
+            variables: vec![],
+        }
+    }
+
+    /// Serve requests until the client disconnects or closes the input stream.
+    pub fn run(mut self) -> Result<()> {
+        while let Some(request) = self.read_request()? {
+            let disconnect = request.command == "disconnect";
+            match self.handle(&request) {
+                Ok(body) => self.respond(&request, Ok(body))?,
+                Err(e) => self.respond(&request, Err(format!("{e:#}")))?,
+            }
+
+            if disconnect {
+                break;
+            }
+
+            self.after(&request.command)?;
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, request: &Request) -> Result<Value> {
+        let args = &request.arguments;
+        Ok(match request.command.as_str() {
+            "initialize" => json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsStepBack": true,
+            }),
+
+            "launch" => {
+                let args: LaunchArguments =
+                    serde_json::from_value(args.clone()).context("Invalid launch arguments")?;
+                self.launch(args)?;
+                Value::Null
+            }
+
+            "setBreakpoints" => self.set_breakpoints(args)?,
+            "configurationDone" | "disconnect" => Value::Null,
+            "threads" => json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+            "stackTrace" => self.stack_trace()?,
+
+            "scopes" => {
+                let frame_id = args["frameId"]
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("Missing frameId"))?
+                    as TraceIndex;
+                if !self.replay()?.is_frame(frame_id) {
+                    bail!("Unknown frameId {frame_id}");
+                }
+
+                let reference = self.add_variables(Variables::Locals(frame_id));
+                json!({ "scopes": [{
+                    "name": "Locals",
+                    "presentationHint": "locals",
+                    "variablesReference": reference,
+                    "expensive": false,
+                }]})
+            }
+
+            "variables" => {
+                let reference = args["variablesReference"]
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("Missing variablesReference"))?;
+                self.variables(reference)?
+            }
+
+            "continue" => json!({ "allThreadsContinued": true }),
+            "next" | "stepIn" | "stepOut" | "stepBack" | "reverseContinue" => Value::Null,
+            command => bail!("Unsupported request: {command}"),
+        })
+    }
+
+    /// Work done after responding to a request, which the protocol requires to follow the
+    /// response, like reporting where execution stopped.
+    fn after(&mut self, command: &str) -> Result<()> {
+        let Some(replay) = self.replay.as_mut() else {
+            return Ok(());
+        };
+
+        let reason = match command {
+            "launch" => return self.event("initialized", Value::Null),
+            "configurationDone" if self.stop_on_entry => StopReason::Entry,
+            "configurationDone" => replay.start(),
+            "continue" => replay.resume(),
+            "next" => replay.step_over(),
+            "stepIn" => replay.step_in(),
+            "stepOut" => replay.step_out(),
+            "stepBack" => replay.step_back(),
+            "reverseContinue" => replay.reverse_resume(),
+            _ => return Ok(()),
+        };
+
+        self.variables.clear();
+        let mut body = json!({ "threadId": THREAD_ID, "allThreadsStopped": true });
+        match reason {
+            StopReason::Entry => body["reason"] = json!("entry"),
+            StopReason::Step => body["reason"] = json!("step"),
+            StopReason::Breakpoint => body["reason"] = json!("breakpoint"),
+            StopReason::Exception(error) => {
+                body["reason"] = json!("exception");
+                body["text"] = json!(error);
+            }
+            StopReason::End => {
+                body["reason"] = json!("pause");
+                body["description"] = json!("End of trace");
+            }
+        }
+
+        self.event("stopped", body)
+    }
+
+    fn launch(&mut self, args: LaunchArguments) -> Result<()> {
+        let trace = MoveTrace::from_file(&args.trace)?;
+        let build_dir = match args.build_directory {
+            Some(dir) => dir,
+            None => default_build_dir(&args.trace)?,
+        };
+
+        let sources = SourceResolver::load(&build_dir)?;
+        self.replay = Some(Replay::new(trace, sources)?);
+        self.stop_on_entry = args.stop_on_entry;
+        Ok(())
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Result<Value> {
+        let path = args["source"]["path"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing source path"))?;
+        let lines: Vec<usize> = args["breakpoints"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|bp| bp["line"].as_u64())
+            .map(|line| line as usize)
+            .collect();
+
+        let verified = match self.replay.as_mut() {
+            Some(replay) => match replay.sources_mut().add_file(Path::new(path))? {
+                Some(file) => replay.set_breakpoints(file, &lines),
+                None => vec![false; lines.len()],
+            },
+            None => vec![false; lines.len()],
+        };
+
+        let breakpoints: Vec<_> = lines
+            .iter()
+            .zip(verified)
+            .map(|(line, verified)| json!({ "line": line, "verified": verified }))
+            .collect();
+
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn stack_trace(&self) -> Result<Value> {
+        let replay = self.replay()?;
+        let frames: Vec<_> = replay
+            .stack()
+            .into_iter()
+            .map(|frame| {
+                let name = format!(
+                    "{}::{}::{}",
+                    frame.module.address().short_str_lossless(),
+                    frame.module.name(),
+                    frame.function_name,
+                );
+
+                let mut json = json!({
+                    "id": frame.frame_id,
+                    "name": name,
+                    "line": 0,
+                    "column": 0,
+                    "instructionPointerReference": frame.pc.to_string(),
+                });
+
+                let file = frame
+                    .source
+                    .and_then(|loc| Some((loc, replay.sources().file(&loc.file)?)));
+                if let Some((loc, file)) = file {
+                    json["source"] = json!({ "path": file.path });
+                    json["line"] = json!(loc.line);
+                    json["column"] = json!(loc.column);
+                }
+
+                json
+            })
+            .collect();
+
+        Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+    }
+
+    fn variables(&mut self, reference: u64) -> Result<Value> {
+        let entries: Vec<(String, Value)> = match reference
+            .checked_sub(1)
+            .and_then(|i| self.variables.get(i as usize))
+        {
+            Some(Variables::Locals(frame_id)) => self
+                .replay()?
+                .locals(*frame_id)?
+                .into_iter()
+                .map(|v| (v.name, v.value))
+                .collect(),
+            Some(Variables::Value(Value::Object(fields))) => fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            Some(Variables::Value(Value::Array(elements))) => elements
+                .iter()
+                .enumerate()
+                .map(|(i, value)| (i.to_string(), value.clone()))
+                .collect(),
+            Some(Variables::Value(_)) => vec![],
+            None => bail!("Unknown variables reference {reference}"),
+        };
+
+        let variables: Vec<_> = entries
+            .into_iter()
+            .map(|(name, value)| {
+                let display = display_value(&value);
+                let reference = match &value {
+                    Value::Object(_) | Value::Array(_) => {
+                        self.add_variables(Variables::Value(value))
+                    }
+                    _ => 0,
+                };
+                json!({ "name": name, "value": display, "variablesReference": reference })
+            })
+            .collect();
+
+        Ok(json!({ "variables": variables }))
+    }
+
+    fn add_variables(&mut self, variables: Variables) -> usize {
+        self.variables.push(variables);
+        self.variables.len()
+    }
+
+    fn replay(&self) -> Result<&Replay> {
+        self.replay
+            .as_ref()
+            .ok_or_else(|| anyhow!("No trace has been launched"))
+    }
+
+    /// Read the next request, skipping any other messages. Returns `None` at the end of the
+    /// input.
+    fn read_request(&mut self) -> Result<Option<Request>> {
+        loop {
+            let mut content_length = None;
+            loop {
+                let mut header = String::new();
+                if self.input.read_line(&mut header)? == 0 {
+                    return Ok(None);
+                }
+
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+
+                if let Some(length) = header.strip_prefix("Content-Length:") {
+                    content_length = Some(length.trim().parse::<usize>()?);
+                }
+            }
+
+            let length = content_length.context("Message is missing its Content-Length")?;
+            let mut content = vec![0; length];
+            self.input.read_exact(&mut content)?;
+
+            let message: Value = serde_json::from_slice(&content)?;
+            if message["type"] == "request" {
+                return Ok(Some(serde_json::from_value(message)?));
+            }
+        }
+    }
+
+    fn respond(&mut self, request: &Request, result: Result<Value, String>) -> Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "command": request.command,
+            "success": result.is_ok(),
+        });
+
+        match result {
+            Ok(Value::Null) => {}
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+
+        self.send(response)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> Result<()> {
+        let mut message = json!({ "type": "event", "event": event });
+        if !body.is_null() {
+            message["body"] = body;
+        }
+        self.send(message)
+    }
+
+    fn send(&mut self, mut message: Value) -> Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+
+        let content = message.to_string();
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{content}",
+            content.len()
+        )?;
+        self.output.flush()?;
+        Ok(())
+    }
+}
+
+/// Traces are written to `<package>/traces`, so look for the build directory next to that.
+fn default_build_dir(trace: &Path) -> Result<PathBuf> {
+    let package = trace
+        .canonicalize()?
+        .parent()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .ok_or_else(|| anyhow!("Cannot find the package of {}", trace.display()))?;
+
+    let build_dir = package.join("build");
+    if !build_dir.is_dir() {
+        bail!(
+            "No build directory at {}, set buildDirectory in the launch configuration",
+            build_dir.display()
+        );
+    }
+    Ok(build_dir)
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        _ => {
+            let mut display = value.to_string();
+            if display.len() > MAX_VALUE_DISPLAY_LEN {
+                let mut end = MAX_VALUE_DISPLAY_LEN;
+                while !display.is_char_boundary(end) {
+                    end -= 1;
+                }
+                display.truncate(end);
+                display.push_str("...");
+            }
+            display
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(json: Value) -> String {
+        let content = json.to_string();
+        format!("Content-Length: {}\r\n\r\n{content}", content.len())
+    }
+
+    fn responses(output: &[u8]) -> Vec<Value> {
+        let output = std::str::from_utf8(output).unwrap();
+        output
+            .split("Content-Length: ")
+            .skip(1)
+            .map(|m| serde_json::from_str(m.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_initialize_and_disconnect() {
+        let input = [
+            message(json!({ "seq": 1, "type": "request", "command": "initialize" })),
+            message(json!({ "seq": 2, "type": "request", "command": "stackTrace" })),
+            message(json!({ "seq": 3, "type": "request", "command": "disconnect" })),
+            message(json!({ "seq": 4, "type": "request", "command": "threads" })),
+        ]
+        .concat();
+
+        let mut output = vec![];
+        Adapter::new(input.as_bytes(), &mut output).run().unwrap();
+
+        let messages = responses(&output);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["request_seq"], 1);
+        assert_eq!(messages[0]["body"]["supportsStepBack"], true);
+
+        // Nothing has been launched, so there is no stack to show.
+        assert_eq!(messages[1]["success"], false);
+        assert_eq!(messages[2]["command"], "disconnect");
+    }
+
+    #[test]
+    fn test_scopes_of_unknown_frame() {
+        let trace = json!({ "version": 1, "events": [
+            { "OpenFrame": {
+                "frame": {
+                    "frame_id": 0,
+                    "function_name": "f",
+                    "module": { "address": "0x1", "name": "m" },
+                    "binary_member_index": 0,
+                    "type_instantiation": [],
+                    "parameters": [],
+                    "return_types": [],
+                    "locals_types": [],
+                    "is_native": false,
+                },
+                "gas_left": 100,
+            }},
+            { "Instruction": {
+                "type_parameters": [],
+                "pc": 0,
+                "gas_left": 100,
+                "instruction": "RET",
+            }},
+        ]});
+
+        let scopes = |seq: u64, arguments: Value| {
+            message(json!({
+                "seq": seq,
+                "type": "request",
+                "command": "scopes",
+                "arguments": arguments,
+            }))
+        };
+
+        let input = [
+            scopes(1, json!({ "frameId": 0 })),
+            scopes(2, json!({ "frameId": 1 })),
+            scopes(3, json!({ "frameId": 100 })),
+            scopes(4, json!({})),
+        ]
+        .concat();
+
+        let mut output = vec![];
+        let mut adapter = Adapter::new(input.as_bytes(), &mut output);
+        adapter.replay = Some(
+            Replay::new(
+                MoveTrace::from_json(&trace.to_string()).unwrap(),
+                SourceResolver::default(),
+            )
+            .unwrap(),
+        );
+        adapter.run().unwrap();
+
+        let messages = responses(&output);
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0]["success"], true);
+
+        // Frame IDs that are not the index of an `OpenFrame` event are rejected, rather than
+        // looked up.
+        assert_eq!(messages[1]["success"], false);
+        assert_eq!(messages[1]["message"], "Unknown frameId 1");
+        assert_eq!(messages[2]["success"], false);
+        assert_eq!(messages[2]["message"], "Unknown frameId 100");
+        assert_eq!(messages[3]["message"], "Missing frameId");
+    }
+}
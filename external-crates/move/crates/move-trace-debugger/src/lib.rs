@@ -0,0 +1,11 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A debugger for Move execution traces, such as those written by `move-unit-test` when tracing
+//! execution. Traces are stepped through forwards and backwards, and shown against the package's
+//! sources, over the Debug Adapter Protocol.
+
+pub mod adapter;
+pub mod replay;
+pub mod source;
+pub mod trace;
@@ -0,0 +1,17 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use clap::*;
+use move_trace_debugger::adapter::Adapter;
+
+/// Debug Adapter Protocol server for Move execution traces. Speaks the protocol over stdin and
+/// stdout, and is meant to be launched by an editor. The trace and the package's build directory
+/// are passed as arguments to the `launch` request.
+#[derive(Parser)]
+#[clap(name = "move-trace-debugger", version)]
+struct Args {}
+
+fn main() -> anyhow::Result<()> {
+    let Args {} = Args::parse();
+    Adapter::new(std::io::stdin().lock(), std::io::stdout().lock()).run()
+}
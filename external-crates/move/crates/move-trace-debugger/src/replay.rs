@@ -0,0 +1,685 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Stepping forwards and backwards through the instructions of a trace.
+//!
+//! A trace records every instruction executed, so the debugger never runs any code: its position
+//! is an index into the trace's instructions, and the state at that position (the call stack and
+//! the values of locals) is reconstructed from the events that precede it.
+
+use crate::{
+    source::{SourceLocation, SourceResolver},
+    trace::{trace_value, Effect, Frame, Location, MoveTrace, TraceEvent, TraceIndex},
+};
+use anyhow::{bail, Result};
+use move_command_line_common::files::FileHash;
+use move_core_types::language_storage::ModuleId;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+/// Why execution stopped after a request to move through the trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// At the first instruction of the trace.
+    Entry,
+    Step,
+    Breakpoint,
+    /// At an instruction that aborted or otherwise failed, with the error's description.
+    Exception(String),
+    /// At the last instruction of the trace.
+    End,
+}
+
+/// A frame on the call stack at the current position.
+#[derive(Clone, Debug)]
+pub struct StackFrame {
+    pub frame_id: TraceIndex,
+    pub module: ModuleId,
+    pub function_name: String,
+    /// The offset of the instruction the frame is executing, or is waiting on a call at.
+    pub pc: u16,
+    pub source: Option<SourceLocation>,
+}
+
+/// A local of a frame that holds a value at the current position.
+#[derive(Clone, Debug)]
+pub struct Variable {
+    pub name: String,
+    pub value: Value,
+}
+
+pub struct Replay {
+    trace: MoveTrace,
+    sources: SourceResolver,
+    steps: Vec<Step>,
+    /// Index into `steps` of the instruction that is about to execute.
+    cursor: usize,
+    breakpoints: HashMap<FileHash, BTreeSet<usize>>,
+}
+
+/// An executed instruction, and the context it executed in.
+struct Step {
+    /// Index of the instruction's event in the trace.
+    event: TraceIndex,
+    /// The frames open when the instruction executed, outermost first, with the offset each was
+    /// at.
+    stack: Vec<(TraceIndex, u16)>,
+    position: Position,
+    /// The error raised executing the instruction, if it failed.
+    error: Option<String>,
+    /// Whether the instruction's frame was last at a different line, so that a breakpoint on the
+    /// line is hit once per visit, and not again when a call made from the line returns.
+    enters_line: bool,
+}
+
+/// Granularity of stepping: consecutive instructions at the same position are stepped over
+/// together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Position {
+    frame_id: TraceIndex,
+    line: Line,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Line {
+    Source(FileHash, usize),
+    /// Instructions without a source location are stepped through one at a time.
+    Bytecode(u16),
+}
+
+impl Replay {
+    pub fn new(trace: MoveTrace, sources: SourceResolver) -> Result<Self> {
+        let mut steps: Vec<Step> = vec![];
+        let mut stack: Vec<(TraceIndex, u16)> = vec![];
+        let mut lines: HashMap<TraceIndex, Line> = HashMap::new();
+
+        for (event, e) in trace.events.iter().enumerate() {
+            match e {
+                TraceEvent::OpenFrame { frame, .. } => stack.push((frame.frame_id, 0)),
+
+                TraceEvent::CloseFrame { frame_id, .. } => {
+                    if let Some(depth) = stack.iter().rposition(|(id, _)| id == frame_id) {
+                        stack.truncate(depth);
+                    }
+                    lines.remove(frame_id);
+                }
+
+                TraceEvent::Instruction { pc, .. } => {
+                    let Some((frame_id, top)) = stack.last_mut() else {
+                        bail!("Instruction at event {event} is outside of any frame");
+                    };
+
+                    *top = *pc;
+                    let frame_id = *frame_id;
+                    let frame = open_frame(&trace, frame_id);
+                    let line = match sources.location(&frame.module, frame.binary_member_index, *pc)
+                    {
+                        Some(loc) => Line::Source(loc.file, loc.line),
+                        None => Line::Bytecode(*pc),
+                    };
+
+                    let enters_line = lines.insert(frame_id, line) != Some(line);
+                    steps.push(Step {
+                        event,
+                        stack: stack.clone(),
+                        position: Position { frame_id, line },
+                        error: None,
+                        enters_line,
+                    });
+                }
+
+                TraceEvent::Effect(Effect::ExecutionError(error)) => {
+                    if let Some(step) = steps.last_mut() {
+                        step.error = Some(error.clone());
+                    }
+                }
+
+                TraceEvent::Effect(_) | TraceEvent::External(_) => {}
+            }
+        }
+
+        if steps.is_empty() {
+            bail!("Trace does not contain any instructions");
+        }
+
+        Ok(Self {
+            trace,
+            sources,
+            steps,
+            cursor: 0,
+            breakpoints: HashMap::new(),
+        })
+    }
+
+    pub fn sources(&self) -> &SourceResolver {
+        &self.sources
+    }
+
+    pub fn sources_mut(&mut self) -> &mut SourceResolver {
+        &mut self.sources
+    }
+
+    /// Replace the breakpoints in `file` with breakpoints at `lines`. Returns whether each line
+    /// was executed by the trace, and so whether its breakpoint can be hit.
+    pub fn set_breakpoints(&mut self, file: FileHash, lines: &[usize]) -> Vec<bool> {
+        let verified = lines
+            .iter()
+            .map(|line| {
+                self.steps
+                    .iter()
+                    .any(|step| step.position.line == Line::Source(file, *line))
+            })
+            .collect();
+
+        self.breakpoints
+            .insert(file, lines.iter().copied().collect());
+        verified
+    }
+
+    /// The error raised by the current instruction, if it failed.
+    pub fn error(&self) -> Option<&str> {
+        self.steps[self.cursor].error.as_deref()
+    }
+
+    /// The mnemonic of the current instruction.
+    pub fn instruction(&self) -> &str {
+        match &self.trace.events[self.steps[self.cursor].event] {
+            TraceEvent::Instruction { instruction, .. } => instruction,
+            _ => unreachable!("Steps are only created for instructions"),
+        }
+    }
+
+    /// The call stack at the current position, innermost frame first.
+    pub fn stack(&self) -> Vec<StackFrame> {
+        self.steps[self.cursor]
+            .stack
+            .iter()
+            .rev()
+            .map(|(frame_id, pc)| {
+                let frame = open_frame(&self.trace, *frame_id);
+                StackFrame {
+                    frame_id: *frame_id,
+                    module: frame.module.clone(),
+                    function_name: frame.function_name.clone(),
+                    pc: *pc,
+                    source: self
+                        .sources
+                        .location(&frame.module, frame.binary_member_index, *pc),
+                }
+            })
+            .collect()
+    }
+
+    /// Whether `frame_id` identifies a frame, i.e. is the index of an event that opened one.
+    pub fn is_frame(&self, frame_id: TraceIndex) -> bool {
+        matches!(
+            self.trace.events.get(frame_id),
+            Some(TraceEvent::OpenFrame { .. })
+        )
+    }
+
+    /// The locals of frame `frame_id` that hold values at the current position, in declaration
+    /// order. Locals that have not been assigned yet, or have been moved out of, are omitted.
+    pub fn locals(&self, frame_id: TraceIndex) -> Result<Vec<Variable>> {
+        if !self.is_frame(frame_id) {
+            bail!("No frame with ID {frame_id}");
+        }
+
+        let mut locals: Vec<Option<Value>> = vec![];
+
+        for event in &self.trace.events[..self.steps[self.cursor].event] {
+            match event {
+                TraceEvent::OpenFrame { frame, .. } if frame.frame_id == frame_id => {
+                    locals = frame
+                        .parameters
+                        .iter()
+                        .map(|p| Some(trace_value(p).clone()))
+                        .collect();
+                    locals.resize(frame.locals_types.len().max(locals.len()), None);
+                }
+
+                TraceEvent::Effect(Effect::Write(write)) => {
+                    if let Some((frame, index)) = write.location.root_local() {
+                        if frame == frame_id && index < locals.len() {
+                            locals[index] =
+                                Some(trace_value(&write.root_value_after_write).clone());
+                        }
+                    }
+                }
+
+                TraceEvent::Effect(Effect::Read(read)) if read.moved => {
+                    if let Location::Local(frame, index) = read.location {
+                        if frame == frame_id && index < locals.len() {
+                            locals[index] = None;
+                        }
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        let frame = open_frame(&self.trace, frame_id);
+        Ok(locals
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, value)| {
+                let name = self
+                    .sources
+                    .local_name(&frame.module, frame.binary_member_index, index)
+                    .unwrap_or_else(|| format!("l{index}"));
+                Some(Variable {
+                    name,
+                    value: value?,
+                })
+            })
+            .collect())
+    }
+
+    /// Move to the next instruction at a different position, entering calls.
+    pub fn step_in(&mut self) -> StopReason {
+        let current = self.steps[self.cursor].position;
+        self.forward(|_, step| (step.position != current).then_some(StopReason::Step))
+    }
+
+    /// Move to the next instruction at a different position in the current frame, or to its
+    /// caller if it returns.
+    pub fn step_over(&mut self) -> StopReason {
+        let current = &self.steps[self.cursor];
+        let (depth, position) = (current.stack.len(), current.position);
+        self.forward(|_, step| {
+            let stop = step.stack.len() < depth
+                || (step.stack.len() == depth && step.position != position);
+            stop.then_some(StopReason::Step)
+        })
+    }
+
+    /// Move to the caller of the current frame, after it returns.
+    pub fn step_out(&mut self) -> StopReason {
+        let depth = self.steps[self.cursor].stack.len();
+        self.forward(|_, step| (step.stack.len() < depth).then_some(StopReason::Step))
+    }
+
+    /// Move from the start of the trace to the first breakpoint or failed instruction, which may
+    /// be the first instruction itself.
+    pub fn start(&mut self) -> StopReason {
+        self.cursor = 0;
+        match self.stop_at(&self.steps[0]) {
+            Some(reason) => reason,
+            None => self.resume(),
+        }
+    }
+
+    /// Move forwards to the next breakpoint or failed instruction. Instructions on the line
+    /// execution is stopped at do not hit its breakpoint again.
+    pub fn resume(&mut self) -> StopReason {
+        self.forward(Self::stop_at)
+    }
+
+    /// Move to the start of the previous position, undoing a step in.
+    pub fn step_back(&mut self) -> StopReason {
+        let current = self.steps[self.cursor].position;
+        let Some(mut i) = (0..self.cursor)
+            .rev()
+            .find(|i| self.steps[*i].position != current)
+        else {
+            self.cursor = 0;
+            return StopReason::Entry;
+        };
+
+        while !self.starts_position(i) {
+            i -= 1;
+        }
+
+        self.cursor = i;
+        StopReason::Step
+    }
+
+    /// Move backwards to the previous breakpoint.
+    pub fn reverse_resume(&mut self) -> StopReason {
+        match (0..self.cursor)
+            .rev()
+            .find(|i| self.at_breakpoint(&self.steps[*i]))
+        {
+            Some(i) => {
+                self.cursor = i;
+                StopReason::Breakpoint
+            }
+            None => {
+                self.cursor = 0;
+                StopReason::Entry
+            }
+        }
+    }
+
+    /// Move to the first instruction after the current one that `stop` stops at, or to the end of
+    /// the trace if there is none.
+    fn forward(&mut self, stop: impl Fn(&Self, &Step) -> Option<StopReason>) -> StopReason {
+        for i in self.cursor + 1..self.steps.len() {
+            if let Some(reason) = stop(self, &self.steps[i]) {
+                self.cursor = i;
+                return reason;
+            }
+        }
+
+        self.cursor = self.steps.len() - 1;
+        StopReason::End
+    }
+
+    /// Whether step `i` is the first of a run of instructions at the same position.
+    fn starts_position(&self, i: usize) -> bool {
+        i == 0 || self.steps[i - 1].position != self.steps[i].position
+    }
+
+    /// Why execution would stop at `step` when resuming, if it would.
+    fn stop_at(&self, step: &Step) -> Option<StopReason> {
+        if let Some(error) = &step.error {
+            Some(StopReason::Exception(error.clone()))
+        } else {
+            self.at_breakpoint(step).then_some(StopReason::Breakpoint)
+        }
+    }
+
+    /// Whether `step` moves onto a line with a breakpoint.
+    fn at_breakpoint(&self, step: &Step) -> bool {
+        let Line::Source(file, line) = step.position.line else {
+            return false;
+        };
+
+        if !step.enters_line {
+            return false;
+        }
+
+        self.breakpoints
+            .get(&file)
+            .is_some_and(|lines| lines.contains(&line))
+    }
+}
+
+/// The frame opened by event `frame_id`.
+fn open_frame(trace: &MoveTrace, frame_id: TraceIndex) -> &Frame {
+    match &trace.events[frame_id] {
+        TraceEvent::OpenFrame { frame, .. } => frame,
+        _ => unreachable!("Frame IDs are the indices of OpenFrame events"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::FunctionDefinitionIndex;
+    use move_bytecode_source_map::source_map::SourceMap;
+    use move_core_types::account_address::AccountAddress;
+    use move_ir_types::{
+        ast::{ModuleIdent, ModuleName},
+        location::Loc,
+    };
+    use serde_json::json;
+    use std::path::Path;
+
+    /// The source of the module `replay` executes, where `f` runs instructions 0 to 2 on the line
+    /// that calls `g`.
+    const SOURCE: &str = "\
+module 0x1::m {
+    fun f(x: u64) {
+        let y = g(x);
+        abort y
+    }
+
+    fun g() {
+        return
+    }
+}
+";
+
+    fn frame(frame_id: usize, function_name: &str, index: u16, parameters: Value) -> Value {
+        json!({ "OpenFrame": {
+            "frame": {
+                "frame_id": frame_id,
+                "function_name": function_name,
+                "module": { "address": "0x1", "name": "m" },
+                "binary_member_index": index,
+                "type_instantiation": [],
+                "parameters": parameters,
+                "return_types": [],
+                "locals_types": [{ "type_": "u64", "ref_type": null }, { "type_": "u64", "ref_type": null }],
+                "is_native": false,
+            },
+            "gas_left": 100,
+        }})
+    }
+
+    fn instruction(pc: u16, instruction: &str) -> Value {
+        json!({ "Instruction": {
+            "type_parameters": [],
+            "pc": pc,
+            "gas_left": 100,
+            "instruction": instruction,
+        }})
+    }
+
+    /// `f(1)` which calls `g()`, stores 7 in its second local, and then aborts.
+    fn replay() -> Replay {
+        replay_with(SourceResolver::default())
+    }
+
+    fn replay_with(sources: SourceResolver) -> Replay {
+        let events = vec![
+            frame(0, "f", 0, json!([{ "RuntimeValue": { "value": 1 } }])),
+            instruction(0, "COPY_LOC"),
+            json!({ "Effect": { "Read": {
+                "location": { "Local": [0, 0] },
+                "root_value_read": { "RuntimeValue": { "value": 1 } },
+                "moved": false,
+            }}}),
+            json!({ "Effect": { "Push": { "RuntimeValue": { "value": 1 } } } }),
+            instruction(1, "CALL"),
+            frame(5, "g", 1, json!([])),
+            instruction(0, "RET"),
+            json!({ "CloseFrame": { "frame_id": 5, "return_": [], "gas_left": 100 } }),
+            instruction(2, "ST_LOC"),
+            json!({ "Effect": { "Write": {
+                "location": { "Local": [0, 1] },
+                "root_value_after_write": { "RuntimeValue": { "value": 7 } },
+            }}}),
+            instruction(3, "ABORT"),
+            json!({ "Effect": { "ExecutionError": "ABORTED" } }),
+        ];
+
+        let trace = json!({ "version": 1, "events": events }).to_string();
+        Replay::new(MoveTrace::from_json(&trace).unwrap(), sources).unwrap()
+    }
+
+    /// Sources that map the instructions `replay` executes to lines of `SOURCE`.
+    fn sources() -> (SourceResolver, FileHash) {
+        let file = FileHash::new(SOURCE);
+        let loc = |text: &str| {
+            let start = SOURCE.find(text).unwrap();
+            Loc::new(file, start as u32, (start + text.len()) as u32)
+        };
+
+        let module = ModuleIdent::new(ModuleName("m".into()), AccountAddress::ONE);
+        let mut source_map = SourceMap::new(loc("module"), module);
+        let functions = [
+            (0, "fun f", vec![(0, "let y"), (3, "abort")]),
+            (1, "fun g", vec![(0, "return")]),
+        ];
+
+        for (index, definition, code) in functions {
+            let index = FunctionDefinitionIndex(index);
+            source_map
+                .add_top_level_function_mapping(index, loc(definition), false)
+                .unwrap();
+            for (pc, text) in code {
+                source_map.add_code_mapping(index, pc, loc(text)).unwrap();
+            }
+        }
+
+        let mut sources = SourceResolver::default();
+        sources.insert(source_map, Path::new("m.move"), SOURCE);
+        (sources, file)
+    }
+
+    fn position(replay: &Replay) -> Vec<(String, u16)> {
+        replay
+            .stack()
+            .into_iter()
+            .map(|f| (f.function_name, f.pc))
+            .collect()
+    }
+
+    fn at(frames: &[(&str, u16)]) -> Vec<(String, u16)> {
+        frames.iter().map(|(f, pc)| (f.to_string(), *pc)).collect()
+    }
+
+    #[test]
+    fn test_step_in_and_out() {
+        let mut replay = replay();
+        assert_eq!(position(&replay), at(&[("f", 0)]));
+
+        assert_eq!(replay.step_in(), StopReason::Step);
+        assert_eq!(replay.step_in(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("g", 0), ("f", 1)]));
+
+        assert_eq!(replay.step_out(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("f", 2)]));
+    }
+
+    #[test]
+    fn test_step_over_call() {
+        let mut replay = replay();
+        replay.step_over();
+        assert_eq!(position(&replay), at(&[("f", 1)]));
+        replay.step_over();
+        assert_eq!(position(&replay), at(&[("f", 2)]));
+    }
+
+    #[test]
+    fn test_locals() {
+        let mut replay = replay();
+        let locals = |r: &Replay| -> Vec<(String, Value)> {
+            r.locals(0)
+                .unwrap()
+                .into_iter()
+                .map(|v| (v.name, v.value))
+                .collect()
+        };
+
+        assert_eq!(locals(&replay), vec![("l0".to_owned(), json!(1))]);
+
+        replay.resume();
+        assert_eq!(
+            locals(&replay),
+            vec![("l0".to_owned(), json!(1)), ("l1".to_owned(), json!(7))]
+        );
+    }
+
+    #[test]
+    fn test_resume_stops_at_error() {
+        let mut replay = replay();
+        assert_eq!(replay.resume(), StopReason::Exception("ABORTED".to_owned()));
+        assert_eq!(replay.instruction(), "ABORT");
+        assert_eq!(replay.resume(), StopReason::End);
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut replay = replay();
+        replay.resume();
+        assert_eq!(replay.step_back(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("f", 2)]));
+        assert_eq!(replay.step_back(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("g", 0), ("f", 1)]));
+        assert_eq!(replay.reverse_resume(), StopReason::Entry);
+        assert_eq!(position(&replay), at(&[("f", 0)]));
+    }
+
+    #[test]
+    fn test_locals_of_unknown_frame() {
+        let replay = replay();
+        assert!(replay.is_frame(5));
+        assert!(!replay.is_frame(1));
+        assert!(!replay.is_frame(100));
+        assert!(replay.locals(1).is_err());
+        assert!(replay.locals(100).is_err());
+    }
+
+    #[test]
+    fn test_set_breakpoints() {
+        let (sources, file) = sources();
+        let mut replay = replay_with(sources);
+
+        // Only lines that instructions executed on can be hit.
+        assert_eq!(
+            replay.set_breakpoints(file, &[3, 5, 8]),
+            vec![true, false, true]
+        );
+
+        // Breakpoints replace the ones previously set in the same file.
+        assert_eq!(replay.set_breakpoints(file, &[8]), vec![true]);
+        assert_eq!(replay.start(), StopReason::Breakpoint);
+        assert_eq!(position(&replay), at(&[("g", 0), ("f", 1)]));
+    }
+
+    #[test]
+    fn test_breakpoint_at_entry() {
+        let (sources, file) = sources();
+        let mut replay = replay_with(sources);
+        replay.set_breakpoints(file, &[3]);
+
+        assert_eq!(replay.start(), StopReason::Breakpoint);
+        assert_eq!(position(&replay), at(&[("f", 0)]));
+
+        // The rest of the line, including after the call on it returns, does not hit the
+        // breakpoint again.
+        assert_eq!(replay.resume(), StopReason::Exception("ABORTED".to_owned()));
+        assert_eq!(position(&replay), at(&[("f", 3)]));
+    }
+
+    #[test]
+    fn test_resume_between_breakpoints() {
+        let (sources, file) = sources();
+        let mut replay = replay_with(sources);
+        replay.set_breakpoints(file, &[3, 8]);
+
+        assert_eq!(replay.start(), StopReason::Breakpoint);
+        assert_eq!(position(&replay), at(&[("f", 0)]));
+        assert_eq!(replay.resume(), StopReason::Breakpoint);
+        assert_eq!(position(&replay), at(&[("g", 0), ("f", 1)]));
+        assert_eq!(replay.resume(), StopReason::Exception("ABORTED".to_owned()));
+
+        assert_eq!(replay.reverse_resume(), StopReason::Breakpoint);
+        assert_eq!(position(&replay), at(&[("g", 0), ("f", 1)]));
+        assert_eq!(replay.reverse_resume(), StopReason::Breakpoint);
+        assert_eq!(position(&replay), at(&[("f", 0)]));
+        assert_eq!(replay.reverse_resume(), StopReason::Entry);
+    }
+
+    #[test]
+    fn test_step_by_line() {
+        let (sources, file) = sources();
+        let mut replay = replay_with(sources);
+        replay.set_breakpoints(file, &[3]);
+
+        // Stepping over the line skips the rest of its instructions, and the call on it.
+        assert_eq!(replay.step_over(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("f", 3)]));
+
+        // Stepping back undoes each step in, through the call.
+        assert_eq!(replay.step_back(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("f", 2)]));
+        assert_eq!(replay.step_back(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("g", 0), ("f", 1)]));
+        assert_eq!(replay.step_back(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("f", 0)]));
+
+        assert_eq!(replay.step_in(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("g", 0), ("f", 1)]));
+
+        // Stepping out returns to the middle of the line with the breakpoint, which resuming
+        // does not stop at again.
+        assert_eq!(replay.step_out(), StopReason::Step);
+        assert_eq!(position(&replay), at(&[("f", 2)]));
+        assert_eq!(replay.resume(), StopReason::Exception("ABORTED".to_owned()));
+    }
+}
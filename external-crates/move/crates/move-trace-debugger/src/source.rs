@@ -0,0 +1,158 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Mapping bytecode offsets in a trace back to source locations, using the source maps and
+//! sources of a package's build directory.
+
+use anyhow::{Context, Result};
+use move_binary_format::file_format::FunctionDefinitionIndex;
+use move_bytecode_source_map::{source_map::SourceMap, utils::source_map_from_file};
+use move_command_line_common::files::FileHash;
+use move_core_types::{
+    account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+const SOURCE_MAP_EXTENSION: &str = "mvsm";
+const MOVE_EXTENSION: &str = "move";
+
+/// A line in a source file, identified by the hash of the file's contents, so that it matches
+/// any copy of the file (e.g. the copy in the build directory and the original in the package).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    pub file: FileHash,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number.
+    pub column: usize,
+}
+
+pub struct SourceFile {
+    pub path: PathBuf,
+    /// Byte offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+#[derive(Default)]
+pub struct SourceResolver {
+    source_maps: BTreeMap<(AccountAddress, Identifier), SourceMap>,
+    files: HashMap<FileHash, SourceFile>,
+}
+
+impl SourceResolver {
+    /// Load all source maps and Move sources found under `build_dir`, which is typically the
+    /// `build/<package>` directory of a package, including its dependencies.
+    pub fn load(build_dir: &Path) -> Result<Self> {
+        let mut resolver = Self::default();
+        for entry in WalkDir::new(build_dir).follow_links(true) {
+            let entry = entry?;
+            let path = entry.path();
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some(SOURCE_MAP_EXTENSION) => {
+                    let source_map = source_map_from_file(path).with_context(|| {
+                        format!("Failed to read source map from {}", path.display())
+                    })?;
+                    resolver
+                        .source_maps
+                        .insert(source_map.module_name.clone(), source_map);
+                }
+                Some(MOVE_EXTENSION) => resolver.load_file(path)?,
+                _ => {}
+            }
+        }
+        Ok(resolver)
+    }
+
+    /// Register `path` as the preferred path for its contents, if they match a source file that
+    /// was loaded from the build directory, so that locations in it are reported at `path` rather
+    /// than at the copy in the build directory. Returns the file's hash if it matches.
+    pub fn add_file(&mut self, path: &Path) -> Result<Option<FileHash>> {
+        let contents = read_source(path)?;
+        let hash = FileHash::new(&contents);
+        let Some(file) = self.files.get_mut(&hash) else {
+            return Ok(None);
+        };
+
+        file.path = path.to_path_buf();
+        Ok(Some(hash))
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<()> {
+        let contents = read_source(path)?;
+        self.insert_file(path, &contents);
+        Ok(())
+    }
+
+    fn insert_file(&mut self, path: &Path, contents: &str) {
+        let line_starts = std::iter::once(0)
+            .chain(contents.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        self.files.insert(
+            FileHash::new(contents),
+            SourceFile {
+                path: path.to_path_buf(),
+                line_starts,
+            },
+        );
+    }
+
+    /// Add a source map and the source it maps into, without reading them from a build directory.
+    #[cfg(test)]
+    pub(crate) fn insert(&mut self, source_map: SourceMap, path: &Path, contents: &str) {
+        self.source_maps
+            .insert(source_map.module_name.clone(), source_map);
+        self.insert_file(path, contents);
+    }
+
+    pub fn file(&self, hash: &FileHash) -> Option<&SourceFile> {
+        self.files.get(hash)
+    }
+
+    /// The source location of the instruction at `pc` in function `function` of `module`.
+    pub fn location(&self, module: &ModuleId, function: u16, pc: u16) -> Option<SourceLocation> {
+        let loc = self
+            .source_map(module)?
+            .get_code_location(FunctionDefinitionIndex(function), pc)
+            .ok()?;
+
+        let file = self.files.get(&loc.file_hash())?;
+        let offset = loc.start() as usize;
+        let line = file.line_starts.partition_point(|start| *start <= offset);
+        let column = offset - file.line_starts[line - 1] + 1;
+
+        Some(SourceLocation {
+            file: loc.file_hash(),
+            line,
+            column,
+        })
+    }
+
+    /// The source name of parameter or local `index` of function `function` in `module`.
+    pub fn local_name(&self, module: &ModuleId, function: u16, index: usize) -> Option<String> {
+        let (mut name, _) = self
+            .source_map(module)?
+            .get_parameter_or_local_name(FunctionDefinitionIndex(function), index as u64)
+            .ok()?;
+
+        // The compiler disambiguates shadowed locals with `#` suffixes.
+        if let Some(suffix) = name.find('#').filter(|i| *i > 0) {
+            name.truncate(suffix);
+        }
+        Some(name)
+    }
+
+    fn source_map(&self, module: &ModuleId) -> Option<&SourceMap> {
+        self.source_maps
+            .get(&(*module.address(), module.name().to_owned()))
+    }
+}
+
+fn read_source(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read source from {}", path.display()))
+}
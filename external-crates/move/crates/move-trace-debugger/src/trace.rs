@@ -0,0 +1,139 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reading traces written in the `move-trace-format` JSON encoding.
+//!
+//! The types in `move-trace-format` can only be serialized: values are written as annotated
+//! `MoveValue`s, which do not carry enough type information to be read back. The types here
+//! mirror the serialized shape of a trace, keeping values as JSON, which is all the debugger needs
+//! to display them.
+
+use anyhow::{bail, Context, Result};
+use move_core_types::language_storage::ModuleId;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// An index into the trace's events.
+pub type TraceIndex = usize;
+
+/// The only version of the trace format this debugger understands.
+const TRACE_VERSION: u64 = 1;
+
+#[derive(Debug, Deserialize)]
+pub struct MoveTrace {
+    pub version: u64,
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+pub enum TraceEvent {
+    OpenFrame {
+        frame: Frame,
+        gas_left: u64,
+    },
+    CloseFrame {
+        frame_id: TraceIndex,
+        return_: Vec<Value>,
+        gas_left: u64,
+    },
+    Instruction {
+        type_parameters: Vec<Value>,
+        pc: u16,
+        gas_left: u64,
+        instruction: String,
+    },
+    Effect(Effect),
+    External(Value),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Frame {
+    /// The index of the event that opened this frame.
+    pub frame_id: TraceIndex,
+    pub function_name: String,
+    pub module: ModuleId,
+    /// The function's `FunctionDefinitionIndex` in its module.
+    pub binary_member_index: u16,
+    pub type_instantiation: Vec<Value>,
+    pub parameters: Vec<Value>,
+    pub return_types: Vec<Value>,
+    pub locals_types: Vec<Value>,
+    pub is_native: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub enum Effect {
+    Pop(Value),
+    Read(Read),
+    Push(Value),
+    Write(Write),
+    DataLoad(Value),
+    ExecutionError(String),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Read {
+    pub location: Location,
+    pub root_value_read: Value,
+    pub moved: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Write {
+    pub location: Location,
+    pub root_value_after_write: Value,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum Location {
+    /// Local `.1` of the frame opened by event `.0`.
+    Local(TraceIndex, usize),
+    Indexed(Box<Location>, usize),
+    Global(TraceIndex),
+}
+
+impl MoveTrace {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read trace from {}", path.display()))?;
+        Self::from_json(&contents)
+            .with_context(|| format!("Failed to parse trace at {}", path.display()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let trace: Self = serde_json::from_str(json)?;
+        if trace.version != TRACE_VERSION {
+            bail!(
+                "Unsupported trace version {}, expected {TRACE_VERSION}",
+                trace.version
+            );
+        }
+        Ok(trace)
+    }
+}
+
+impl Location {
+    /// The local that this location is rooted in, if any.
+    pub fn root_local(&self) -> Option<(TraceIndex, usize)> {
+        match self {
+            Location::Local(frame, local) => Some((*frame, *local)),
+            Location::Indexed(location, _) => location.root_local(),
+            Location::Global(_) => None,
+        }
+    }
+}
+
+/// The value held by a serialized `TraceValue`. References are shown as the snapshot of the value
+/// they point to.
+pub fn trace_value(value: &Value) -> &Value {
+    let Some((kind, inner)) = value.as_object().and_then(|o| o.iter().next()) else {
+        return value;
+    };
+
+    match kind.as_str() {
+        "RuntimeValue" => &inner["value"],
+        "ImmRef" | "MutRef" => &inner["snapshot"],
+        _ => value,
+    }
+}
@@ -0,0 +1,601 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Gas profiling for unit tests. The VM's own `GasProfiler` is only available when it is built with
+//! the `tracing` feature, so unit tests are profiled from the calls the VM makes to its gas meter
+//! instead, which are made in every build.
+
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::{
+    gas_algebra::{InternalGas, NumArgs, NumBytes},
+    language_storage::ModuleId,
+};
+use move_vm_profiler::GasProfiler;
+use move_vm_test_utils::gas_schedule::GasStatus;
+use move_vm_types::{
+    gas::{GasMeter, SimpleInstruction},
+    views::{TypeView, ValueView},
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, path::Path};
+
+/// The gas used by a test, attributed to the Move functions it called and the instructions they
+/// executed. Serializes to the speedscope file format, which flamegraph viewers also accept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasProfile {
+    exporter: &'static str,
+    name: String,
+    active_profile_index: u64,
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    shared: Shared,
+    profiles: Vec<Profile>,
+
+    /// The gas used by each function itself, excluding the functions it called, by frame index.
+    #[serde(skip)]
+    self_gas: BTreeMap<usize, u64>,
+    /// The frame index of each function that has not returned yet, innermost last.
+    #[serde(skip)]
+    call_stack: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Shared {
+    frames: Vec<Frame>,
+    #[serde(skip)]
+    frame_table: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Frame {
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Profile {
+    #[serde(rename = "type")]
+    ty: &'static str,
+    name: String,
+    unit: &'static str,
+    start_value: u64,
+    end_value: u64,
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Event {
+    #[serde(rename = "type")]
+    ty: &'static str,
+    frame: usize,
+    at: u64,
+}
+
+/// A gas meter for unit tests, which profiles the gas it charges if asked to.
+pub struct ProfilingGasMeter<'a> {
+    gas_status: GasStatus<'a>,
+    start_gas: u64,
+    profile: Option<GasProfile>,
+}
+
+impl GasProfile {
+    const OPEN_FRAME: &'static str = "O";
+    const CLOSE_FRAME: &'static str = "C";
+
+    fn new(name: String) -> Self {
+        Self {
+            exporter: "speedscope@1.15.2",
+            name: name.clone(),
+            active_profile_index: 0,
+            schema: "https://www.speedscope.app/file-format-schema.json",
+            shared: Shared {
+                frames: vec![],
+                frame_table: BTreeMap::new(),
+            },
+            profiles: vec![Profile {
+                ty: "evented",
+                name,
+                unit: "none",
+                start_value: 0,
+                end_value: 0,
+                events: vec![],
+            }],
+            self_gas: BTreeMap::new(),
+            call_stack: vec![],
+        }
+    }
+
+    /// The total gas used by the test.
+    pub fn gas_used(&self) -> u64 {
+        self.profiles[0].end_value
+    }
+
+    /// The function that used the most gas itself, and how much of it.
+    pub fn hottest_function(&self) -> Option<(&str, u64)> {
+        let (frame, gas) = self
+            .self_gas
+            .iter()
+            .max_by_key(|(frame, gas)| (**gas, std::cmp::Reverse(**frame)))?;
+        Some((self.shared.frames[*frame].name.as_str(), *gas))
+    }
+
+    /// Write the profile to `path` in the speedscope file format.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Drop the events of the profile, keeping only its summary, once it has been saved.
+    pub fn discard_events(&mut self) {
+        self.profiles[0].events = vec![];
+    }
+
+    fn frame(&mut self, name: String) -> usize {
+        if let Some(frame) = self.shared.frame_table.get(&name) {
+            return *frame;
+        }
+        let frame = self.shared.frames.len();
+        self.shared.frames.push(Frame { name: name.clone() });
+        self.shared.frame_table.insert(name, frame);
+        frame
+    }
+
+    fn event(&mut self, ty: &'static str, frame: usize, at: u64) {
+        let profile = &mut self.profiles[0];
+        profile.events.push(Event { ty, frame, at });
+        profile.end_value = at;
+    }
+
+    fn open_function(&mut self, name: String, at: u64) {
+        let frame = self.frame(name);
+        self.event(Self::OPEN_FRAME, frame, at);
+        self.call_stack.push(frame);
+    }
+
+    fn close_function(&mut self, at: u64) {
+        if let Some(frame) = self.call_stack.pop() {
+            self.event(Self::CLOSE_FRAME, frame, at);
+        }
+    }
+
+    /// Record that `instr` used the gas between `before` and `after`.
+    fn instruction(&mut self, instr: String, before: u64, after: u64) {
+        if after <= before {
+            return;
+        }
+        let frame = self.frame(instr);
+        self.event(Self::OPEN_FRAME, frame, before);
+        self.event(Self::CLOSE_FRAME, frame, after);
+        if let Some(function) = self.call_stack.last() {
+            *self.self_gas.entry(*function).or_default() += after - before;
+        }
+    }
+}
+
+impl<'a> ProfilingGasMeter<'a> {
+    /// A gas meter charging according to `gas_status`.
+    pub fn new(gas_status: GasStatus<'a>) -> Self {
+        let start_gas = GasMeter::remaining_gas(&gas_status).into();
+        Self {
+            gas_status,
+            start_gas,
+            profile: None,
+        }
+    }
+
+    /// Profile the gas charged for calling `function_name` in `module_id`.
+    pub fn with_profile(mut self, module_id: &ModuleId, function_name: &str) -> Self {
+        let name = format!("{}::{}", module_id.short_str_lossless(), function_name);
+        let mut profile = GasProfile::new(name.clone());
+        profile.open_function(name, 0);
+        self.profile = Some(profile);
+        self
+    }
+
+    /// The gas status the meter charges.
+    pub fn gas_status(&self) -> &GasStatus<'a> {
+        &self.gas_status
+    }
+
+    /// The profile of the gas charged, with the frames of functions that did not return (because
+    /// the test aborted or ran out of gas) closed.
+    pub fn finish(mut self) -> Option<GasProfile> {
+        let used = self.used_gas();
+        let mut profile = self.profile.take()?;
+        while !profile.call_stack.is_empty() {
+            profile.close_function(used);
+        }
+        Some(profile)
+    }
+
+    fn used_gas(&self) -> u64 {
+        let remaining: u64 = GasMeter::remaining_gas(&self.gas_status).into();
+        self.start_gas - remaining
+    }
+
+    /// Charge for `instr` with `charge`, attributing the gas it uses to the instruction.
+    fn charge(
+        &mut self,
+        instr: impl FnOnce() -> String,
+        charge: impl FnOnce(&mut GasStatus<'a>) -> PartialVMResult<()>,
+    ) -> PartialVMResult<()> {
+        if self.profile.is_none() {
+            return charge(&mut self.gas_status);
+        }
+        let before = self.used_gas();
+        let result = charge(&mut self.gas_status);
+        let after = self.used_gas();
+        if let Some(profile) = &mut self.profile {
+            profile.instruction(instr(), before, after);
+        }
+        result
+    }
+
+    fn open_function(&mut self, module_id: &ModuleId, func_name: &str) {
+        let at = self.used_gas();
+        if let Some(profile) = &mut self.profile {
+            profile.open_function(
+                format!("{}::{}", module_id.short_str_lossless(), func_name),
+                at,
+            );
+        }
+    }
+
+    fn close_function(&mut self) {
+        let at = self.used_gas();
+        if let Some(profile) = &mut self.profile {
+            profile.close_function(at);
+        }
+    }
+}
+
+impl<'a> GasMeter for ProfilingGasMeter<'a> {
+    fn charge_simple_instr(&mut self, instr: SimpleInstruction) -> PartialVMResult<()> {
+        self.charge(
+            || format!("{instr:?}"),
+            |gas| gas.charge_simple_instr(instr),
+        )
+    }
+
+    fn charge_pop(&mut self, popped_val: impl ValueView) -> PartialVMResult<()> {
+        self.charge(|| "Pop".to_owned(), |gas| gas.charge_pop(popped_val))
+    }
+
+    fn charge_call(
+        &mut self,
+        module_id: &ModuleId,
+        func_name: &str,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+        num_locals: NumArgs,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "Call".to_owned(),
+            |gas| gas.charge_call(module_id, func_name, args, num_locals),
+        )?;
+        self.open_function(module_id, func_name);
+        Ok(())
+    }
+
+    fn charge_call_generic(
+        &mut self,
+        module_id: &ModuleId,
+        func_name: &str,
+        ty_args: impl ExactSizeIterator<Item = impl TypeView>,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+        num_locals: NumArgs,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "CallGeneric".to_owned(),
+            |gas| gas.charge_call_generic(module_id, func_name, ty_args, args, num_locals),
+        )?;
+        self.open_function(module_id, func_name);
+        Ok(())
+    }
+
+    fn charge_ld_const(&mut self, size: NumBytes) -> PartialVMResult<()> {
+        self.charge(|| "LdConst".to_owned(), |gas| gas.charge_ld_const(size))
+    }
+
+    fn charge_ld_const_after_deserialization(
+        &mut self,
+        val: impl ValueView,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "LdConst".to_owned(),
+            |gas| gas.charge_ld_const_after_deserialization(val),
+        )
+    }
+
+    fn charge_copy_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge(|| "CopyLoc".to_owned(), |gas| gas.charge_copy_loc(val))
+    }
+
+    fn charge_move_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge(|| "MoveLoc".to_owned(), |gas| gas.charge_move_loc(val))
+    }
+
+    fn charge_store_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge(|| "StLoc".to_owned(), |gas| gas.charge_store_loc(val))
+    }
+
+    fn charge_pack(
+        &mut self,
+        is_generic: bool,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "Pack".to_owned(),
+            |gas| gas.charge_pack(is_generic, args),
+        )
+    }
+
+    fn charge_unpack(
+        &mut self,
+        is_generic: bool,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "Unpack".to_owned(),
+            |gas| gas.charge_unpack(is_generic, args),
+        )
+    }
+
+    fn charge_variant_switch(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge(
+            || "VariantSwitch".to_owned(),
+            |gas| gas.charge_variant_switch(val),
+        )
+    }
+
+    fn charge_read_ref(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge(|| "ReadRef".to_owned(), |gas| gas.charge_read_ref(val))
+    }
+
+    fn charge_write_ref(
+        &mut self,
+        new_val: impl ValueView,
+        old_val: impl ValueView,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "WriteRef".to_owned(),
+            |gas| gas.charge_write_ref(new_val, old_val),
+        )
+    }
+
+    fn charge_eq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
+        self.charge(|| "Eq".to_owned(), |gas| gas.charge_eq(lhs, rhs))
+    }
+
+    fn charge_neq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
+        self.charge(|| "Neq".to_owned(), |gas| gas.charge_neq(lhs, rhs))
+    }
+
+    fn charge_vec_pack<'b>(
+        &mut self,
+        ty: impl TypeView + 'b,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge(|| "VecPack".to_owned(), |gas| gas.charge_vec_pack(ty, args))
+    }
+
+    fn charge_vec_len(&mut self, ty: impl TypeView) -> PartialVMResult<()> {
+        self.charge(|| "VecLen".to_owned(), |gas| gas.charge_vec_len(ty))
+    }
+
+    fn charge_vec_borrow(
+        &mut self,
+        is_mut: bool,
+        ty: impl TypeView,
+        is_success: bool,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || {
+                if is_mut {
+                    "VecMutBorrow"
+                } else {
+                    "VecImmBorrow"
+                }
+                .to_owned()
+            },
+            |gas| gas.charge_vec_borrow(is_mut, ty, is_success),
+        )
+    }
+
+    fn charge_vec_push_back(
+        &mut self,
+        ty: impl TypeView,
+        val: impl ValueView,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "VecPushBack".to_owned(),
+            |gas| gas.charge_vec_push_back(ty, val),
+        )
+    }
+
+    fn charge_vec_pop_back(
+        &mut self,
+        ty: impl TypeView,
+        val: Option<impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "VecPopBack".to_owned(),
+            |gas| gas.charge_vec_pop_back(ty, val),
+        )
+    }
+
+    fn charge_vec_unpack(
+        &mut self,
+        ty: impl TypeView,
+        expect_num_elements: NumArgs,
+        elems: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "VecUnpack".to_owned(),
+            |gas| gas.charge_vec_unpack(ty, expect_num_elements, elems),
+        )
+    }
+
+    fn charge_vec_swap(&mut self, ty: impl TypeView) -> PartialVMResult<()> {
+        self.charge(|| "VecSwap".to_owned(), |gas| gas.charge_vec_swap(ty))
+    }
+
+    // A native function's frame is closed once it has been charged for, as natives do not drop a
+    // frame when they return.
+    fn charge_native_function(
+        &mut self,
+        amount: InternalGas,
+        ret_vals: Option<impl ExactSizeIterator<Item = impl ValueView>>,
+    ) -> PartialVMResult<()> {
+        let result = self.charge(
+            || "Native".to_owned(),
+            |gas| gas.charge_native_function(amount, ret_vals),
+        );
+        self.close_function();
+        result
+    }
+
+    fn charge_native_function_before_execution(
+        &mut self,
+        ty_args: impl ExactSizeIterator<Item = impl TypeView>,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge(
+            || "Native".to_owned(),
+            |gas| gas.charge_native_function_before_execution(ty_args, args),
+        )
+    }
+
+    fn charge_drop_frame(
+        &mut self,
+        locals: impl Iterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let result = self.charge(
+            || "DropFrame".to_owned(),
+            |gas| gas.charge_drop_frame(locals),
+        );
+        self.close_function();
+        result
+    }
+
+    fn remaining_gas(&self) -> InternalGas {
+        GasMeter::remaining_gas(&self.gas_status)
+    }
+
+    fn get_profiler_mut(&mut self) -> Option<&mut GasProfiler> {
+        self.gas_status.get_profiler_mut()
+    }
+
+    fn set_profiler(&mut self, profiler: GasProfiler) {
+        self.gas_status.set_profiler(profiler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+    use move_vm_test_utils::gas_schedule::{unit_cost_schedule, Gas};
+    use move_vm_types::values::Value;
+    use serde_json::json;
+    use std::iter;
+
+    #[test]
+    fn test_gas_attribution() {
+        let cost_table = unit_cost_schedule();
+        let module_id = ModuleId::new(AccountAddress::ONE, Identifier::new("m").unwrap());
+        let mut meter = ProfilingGasMeter::new(GasStatus::new(&cost_table, Gas::new(1000)))
+            .with_profile(&module_id, "test");
+
+        // The test loads a constant and calls `f`, which adds and returns, before returning
+        // itself. Every instruction costs 2 in the unit cost schedule.
+        meter.charge_simple_instr(SimpleInstruction::LdU64).unwrap();
+        meter
+            .charge_call(&module_id, "f", iter::empty::<Value>(), NumArgs::new(0))
+            .unwrap();
+        meter.charge_simple_instr(SimpleInstruction::Add).unwrap();
+        meter.charge_simple_instr(SimpleInstruction::Ret).unwrap();
+        meter.charge_drop_frame(iter::empty::<Value>()).unwrap();
+        meter.charge_simple_instr(SimpleInstruction::Ret).unwrap();
+        meter.charge_drop_frame(iter::empty::<Value>()).unwrap();
+
+        let consumed = meter.start_gas - u64::from(GasMeter::remaining_gas(meter.gas_status()));
+        let profile = meter.finish().unwrap();
+
+        // All the gas the meter charged is attributed to some function.
+        assert_eq!(consumed, 10);
+        assert_eq!(profile.gas_used(), consumed);
+        assert_eq!(profile.self_gas.values().sum::<u64>(), consumed);
+        assert_eq!(profile.hottest_function(), Some(("0x1::m::test", 6)));
+
+        let event = |ty, frame, at| json!({ "type": ty, "frame": frame, "at": at });
+        assert_eq!(
+            serde_json::to_value(&profile).unwrap(),
+            json!({
+                "exporter": "speedscope@1.15.2",
+                "name": "0x1::m::test",
+                "activeProfileIndex": 0,
+                "$schema": "https://www.speedscope.app/file-format-schema.json",
+                "shared": {
+                    "frames": [
+                        { "name": "0x1::m::test" },
+                        { "name": "LdU64" },
+                        { "name": "Call" },
+                        { "name": "0x1::m::f" },
+                        { "name": "Add" },
+                        { "name": "Ret" },
+                    ],
+                },
+                "profiles": [{
+                    "type": "evented",
+                    "name": "0x1::m::test",
+                    "unit": "none",
+                    "startValue": 0,
+                    "endValue": 10,
+                    "events": [
+                        event("O", 0, 0),
+                        event("O", 1, 0),
+                        event("C", 1, 2),
+                        event("O", 2, 2),
+                        event("C", 2, 4),
+                        event("O", 3, 4),
+                        event("O", 4, 4),
+                        event("C", 4, 6),
+                        event("O", 5, 6),
+                        event("C", 5, 8),
+                        event("C", 3, 8),
+                        event("O", 5, 8),
+                        event("C", 5, 10),
+                        event("C", 0, 10),
+                    ],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_unfinished_frames_closed() {
+        let cost_table = unit_cost_schedule();
+        let module_id = ModuleId::new(AccountAddress::ONE, Identifier::new("m").unwrap());
+        let mut meter = ProfilingGasMeter::new(GasStatus::new(&cost_table, Gas::new(1000)))
+            .with_profile(&module_id, "test");
+
+        // The test calls `f`, which aborts.
+        meter
+            .charge_call(&module_id, "f", iter::empty::<Value>(), NumArgs::new(0))
+            .unwrap();
+        meter.charge_simple_instr(SimpleInstruction::Abort).unwrap();
+
+        let profile = meter.finish().unwrap();
+        assert_eq!(profile.gas_used(), 4);
+        assert_eq!(profile.self_gas.values().sum::<u64>(), 4);
+        assert!(profile.call_stack.is_empty());
+
+        let events = &profile.profiles[0].events;
+        let closes = events.iter().filter(|e| e.ty == GasProfile::CLOSE_FRAME);
+        assert_eq!(closes.count(), events.len() / 2);
+    }
+}
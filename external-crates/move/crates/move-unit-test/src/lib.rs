@@ -4,10 +4,13 @@
 
 pub mod cargo_runner;
 pub mod extensions;
+pub mod gas_profiler;
+pub mod mutation;
+mod shrink;
 pub mod test_reporter;
 pub mod test_runner;
 
-use crate::test_runner::TestRunner;
+use crate::{test_reporter::ReportFormat, test_runner::TestRunner};
 use anyhow::{bail, Result};
 use clap::*;
 use move_binary_format::CompiledModule;
@@ -23,7 +26,7 @@ use move_compiler::{
 use move_core_types::language_storage::ModuleId;
 use move_vm_runtime::native_functions::NativeFunctionTable;
 use move_vm_test_utils::gas_schedule::CostTable;
-use std::{collections::BTreeMap, io::Write, marker::Send, sync::Mutex};
+use std::{collections::BTreeMap, io::Write, marker::Send, path::Path, sync::Mutex};
 
 /// The default value bounding the amount of gas consumed in a test.
 const DEFAULT_EXECUTION_BOUND: u64 = 1_000_000;
@@ -34,6 +37,9 @@ const DEFAULT_RAND_ITERS: u64 = 10;
 const RAND_NUM_ITERS_FLAG: &str = "rand-num-iters";
 const SEED_FLAG: &str = "seed";
 const TRACE_FLAG: &str = "trace-execution";
+const REPORT_FLAG: &str = "report";
+const REPLAY_FLAG: &str = "replay";
+const PROFILE_FLAG: &str = "profile";
 
 #[derive(Debug, Parser, Clone)]
 #[clap(author, version, about)]
@@ -118,6 +124,12 @@ pub struct UnitTestingConfig {
     #[clap(long = SEED_FLAG)]
     pub seed: Option<u64>,
 
+    /// Rerun a single failing case of a #[random_test]: run only the test named
+    /// `<module_name>::<test_name>`, once, with the arguments generated from the given seed. If it
+    /// fails, those arguments are shrunk again to the same minimal counterexample
+    #[clap(long = REPLAY_FLAG, value_name = "TEST", requires = SEED_FLAG)]
+    pub replay: Option<String>,
+
     // Deterministically generate the same arguments for #[random_test]s between test runs.
     // WARNING: You should only use this flag for debugging and meta-testing purposes!
     #[clap(skip)]
@@ -126,6 +138,16 @@ pub struct UnitTestingConfig {
     // Enable tracing for tests
     #[clap(long = TRACE_FLAG, value_name = "PATH")]
     pub trace_execution: Option<Option<String>>,
+
+    /// Write a report of every test run to PATH, in FORMAT ('junit' or 'json')
+    #[clap(long = REPORT_FLAG, num_args = 2, value_names = ["FORMAT", "PATH"])]
+    pub report: Option<Vec<String>>,
+
+    /// Write a gas profile of each test to PATH (by default, `gas-profiles`), attributing the gas
+    /// it used to the functions it called and the instructions they executed, and rank the tests
+    /// by the gas they used. Profiles are in the speedscope format.
+    #[clap(long = PROFILE_FLAG, value_name = "PATH")]
+    pub profile: Option<Option<String>>,
 }
 
 fn format_module_id(
@@ -156,8 +178,11 @@ impl UnitTestingConfig {
             named_address_values: vec![],
             rand_num_iters: Some(DEFAULT_RAND_ITERS),
             seed: None,
+            replay: None,
             deterministic_generation: false,
             trace_execution: None,
+            report: None,
+            profile: None,
         }
     }
 
@@ -254,6 +279,20 @@ impl UnitTestingConfig {
             None => DEFAULT_RAND_ITERS,
         };
 
+        let report = match self.report.as_deref() {
+            Some([format, path]) => {
+                let Ok(format) = ReportFormat::from_str(format, /* ignore_case */ true) else {
+                    bail!(
+                        "Invalid argument -- unknown '{REPORT_FLAG}' format '{format}'. \
+                        Expected 'junit' or 'json'."
+                    )
+                };
+                Some((format, Path::new(path)))
+            }
+            Some(_) => bail!("Invalid arguments -- '{REPORT_FLAG}' takes a format and a path."),
+            None => None,
+        };
+
         if self.list {
             for (module_id, module_test_plan) in &test_plan.module_tests {
                 for test_name in module_test_plan.tests.keys() {
@@ -274,6 +313,11 @@ impl UnitTestingConfig {
             Some(Some(path)) => Some(path.clone()),
             None => None,
         };
+        let profile_location = match &self.profile {
+            Some(None) => Some("gas-profiles".to_string()),
+            Some(Some(path)) => Some(path.clone()),
+            None => None,
+        };
         let mut test_runner = TestRunner::new(
             self.gas_limit.unwrap_or(DEFAULT_EXECUTION_BOUND),
             self.num_threads,
@@ -282,6 +326,7 @@ impl UnitTestingConfig {
             rand_num_iters,
             self.deterministic_generation,
             trace_location,
+            profile_location,
             test_plan,
             native_function_table,
             cost_table,
@@ -292,11 +337,28 @@ impl UnitTestingConfig {
             test_runner.filter(filter_str)
         }
 
+        if let Some(test_name) = &self.replay {
+            if !test_runner.filter_exact(test_name) {
+                bail!(
+                    "Invalid argument -- no test named '{test_name}' to '{REPLAY_FLAG}'. \
+                    Tests are named '<module_name>::<test_name>'."
+                )
+            }
+        }
+
         let test_results = test_runner.run(&shared_writer).unwrap();
         if let Some(report_type) = &self.report_statistics {
             test_results.report_statistics(&shared_writer, report_type)?;
         }
 
+        if self.profile.is_some() {
+            test_results.report_gas_profiles(&shared_writer)?;
+        }
+
+        if let Some((format, path)) = report {
+            test_results.write_report(format, path)?;
+        }
+
         let ok = test_results.summarize(&shared_writer)?;
 
         let writer = shared_writer.into_inner().unwrap();
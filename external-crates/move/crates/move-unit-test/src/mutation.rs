@@ -0,0 +1,447 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Operator-level mutations of compiled modules, for mutation testing: each mutation changes a
+//! single instruction of a function, and a package's tests are expected to fail for every
+//! mutated ("mutant") version of its code. Mutants the tests still pass for point at behavior the
+//! tests do not check.
+
+use move_binary_format::{
+    file_format::{Bytecode, CodeOffset, FunctionDefinitionIndex, JumpTableInner},
+    CompiledModule,
+};
+use move_core_types::{identifier::IdentStr, u256::U256};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MutationOperator {
+    /// Replace a comparison with its negation, e.g. `<` with `>=`
+    NegateComparison,
+    /// Replace a comparison with its non-strict or strict version, e.g. `<` with `<=`
+    ShiftBoundary,
+    /// Replace an arithmetic, bitwise or logical operator with a related one, e.g. `+` with `-`
+    SwapOperator,
+    /// Remove the abort of an `assert!` (or `if (..) abort ..`), so execution always continues
+    DropAssert,
+    /// Replace a literal with a different value
+    ReplaceConstant,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mutation {
+    pub function: FunctionDefinitionIndex,
+    pub offset: CodeOffset,
+    pub operator: MutationOperator,
+    /// What the mutation changes, e.g. "replaced `+` with `-`"
+    pub description: String,
+    /// The instructions that replace the one at `offset`
+    replacement: Vec<Bytecode>,
+}
+
+/// All mutations of the functions in `module` that `should_mutate` accepts, by their names.
+pub fn mutations(
+    module: &CompiledModule,
+    should_mutate: impl Fn(&IdentStr) -> bool,
+) -> Vec<Mutation> {
+    let mut mutations = vec![];
+    for (idx, function_def) in module.function_defs().iter().enumerate() {
+        let Some(code_unit) = &function_def.code else {
+            continue;
+        };
+        let handle = module.function_handle_at(function_def.function);
+        if !should_mutate(module.identifier_at(handle.name)) {
+            continue;
+        }
+
+        let function = FunctionDefinitionIndex(idx as u16);
+        let code = &code_unit.code;
+        for (offset, instruction) in code.iter().enumerate() {
+            let offset = offset as CodeOffset;
+            let mut push = |operator, replacement: Vec<Bytecode>, description: String| {
+                mutations.push(Mutation {
+                    function,
+                    offset,
+                    operator,
+                    description,
+                    replacement,
+                })
+            };
+
+            if let Some(negated) = negate_comparison(instruction) {
+                push(
+                    MutationOperator::NegateComparison,
+                    vec![negated.clone()],
+                    replaced(instruction, &negated),
+                );
+            }
+            if let Some(shifted) = shift_boundary(instruction) {
+                push(
+                    MutationOperator::ShiftBoundary,
+                    vec![shifted.clone()],
+                    replaced(instruction, &shifted),
+                );
+            }
+            for swapped in swap_operator(instruction) {
+                push(
+                    MutationOperator::SwapOperator,
+                    vec![swapped.clone()],
+                    replaced(instruction, &swapped),
+                );
+            }
+            if let Some(replacement) = drop_assert(code, offset) {
+                push(
+                    MutationOperator::DropAssert,
+                    replacement,
+                    "removed `assert!`".to_string(),
+                );
+            }
+            // Abort codes are left alone, as changing them is only caught by tests that expect
+            // the abort.
+            let is_abort_code = matches!(code.get(offset as usize + 1), Some(Bytecode::Abort));
+            if !is_abort_code {
+                for constant in replace_constant(instruction) {
+                    let description = format!(
+                        "replaced `{}` with `{}`",
+                        literal(instruction),
+                        literal(&constant)
+                    );
+                    push(
+                        MutationOperator::ReplaceConstant,
+                        vec![constant],
+                        description,
+                    );
+                }
+            }
+        }
+    }
+    mutations
+}
+
+impl Mutation {
+    /// A copy of `module` with this mutation applied.
+    pub fn apply(&self, module: &CompiledModule) -> CompiledModule {
+        let mut module = module.clone();
+        let code_unit = module.function_defs[self.function.0 as usize]
+            .code
+            .as_mut()
+            .expect("mutations are only made to functions with code");
+
+        // Replacing one instruction with several moves the instructions after it, so branches to
+        // them are moved too.
+        let offset = self.offset;
+        let shift = self.replacement.len() as CodeOffset - 1;
+        code_unit.code.splice(
+            offset as usize..offset as usize + 1,
+            self.replacement.iter().cloned(),
+        );
+        if shift > 0 {
+            let moved = |target: &mut CodeOffset| {
+                if *target > offset {
+                    *target += shift
+                }
+            };
+            for instruction in code_unit.code.iter_mut() {
+                if let Bytecode::Branch(target)
+                | Bytecode::BrTrue(target)
+                | Bytecode::BrFalse(target) = instruction
+                {
+                    moved(target)
+                }
+            }
+            for jump_table in code_unit.jump_tables.iter_mut() {
+                let JumpTableInner::Full(targets) = &mut jump_table.jump_table;
+                targets.iter_mut().for_each(moved);
+            }
+        }
+        module
+    }
+}
+
+impl fmt::Display for MutationOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MutationOperator::NegateComparison => "negate comparison",
+            MutationOperator::ShiftBoundary => "shift boundary",
+            MutationOperator::SwapOperator => "swap operator",
+            MutationOperator::DropAssert => "drop assert",
+            MutationOperator::ReplaceConstant => "replace constant",
+        };
+        write!(f, "{name}")
+    }
+}
+
+fn negate_comparison(instruction: &Bytecode) -> Option<Bytecode> {
+    use Bytecode as B;
+    Some(match instruction {
+        B::Lt => B::Ge,
+        B::Ge => B::Lt,
+        B::Gt => B::Le,
+        B::Le => B::Gt,
+        B::Eq => B::Neq,
+        B::Neq => B::Eq,
+        _ => return None,
+    })
+}
+
+fn shift_boundary(instruction: &Bytecode) -> Option<Bytecode> {
+    use Bytecode as B;
+    Some(match instruction {
+        B::Lt => B::Le,
+        B::Le => B::Lt,
+        B::Gt => B::Ge,
+        B::Ge => B::Gt,
+        _ => return None,
+    })
+}
+
+/// Operators that take and return the same types as `instruction`, so replacing it keeps the
+/// module well typed.
+fn swap_operator(instruction: &Bytecode) -> Vec<Bytecode> {
+    use Bytecode as B;
+    match instruction {
+        B::Add => vec![B::Sub, B::Mul],
+        B::Sub => vec![B::Add],
+        B::Mul => vec![B::Div, B::Add],
+        B::Div => vec![B::Mul],
+        B::Mod => vec![B::Div],
+        B::BitOr => vec![B::BitAnd],
+        B::BitAnd => vec![B::BitOr],
+        B::Xor => vec![B::BitOr],
+        B::Shl => vec![B::Shr],
+        B::Shr => vec![B::Shl],
+        B::And => vec![B::Or],
+        B::Or => vec![B::And],
+        _ => vec![],
+    }
+}
+
+/// If the instruction at `offset` branches to an abort, the instructions that always continue
+/// without aborting instead.
+fn drop_assert(code: &[Bytecode], offset: CodeOffset) -> Option<Vec<Bytecode>> {
+    let is_abort = |at: CodeOffset| {
+        matches!(
+            (code.get(at as usize), code.get(at as usize + 1)),
+            (
+                Some(Bytecode::LdU64(_) | Bytecode::LdConst(_)),
+                Some(Bytecode::Abort)
+            )
+        )
+    };
+
+    match &code[offset as usize] {
+        // The condition is popped, and execution falls through to the code after the assert.
+        Bytecode::BrTrue(target) | Bytecode::BrFalse(target) if is_abort(*target) => {
+            Some(vec![Bytecode::Pop])
+        }
+        // The condition is popped, and execution jumps over the abort.
+        Bytecode::BrTrue(target) | Bytecode::BrFalse(target) if is_abort(offset + 1) => {
+            Some(vec![Bytecode::Pop, Bytecode::Branch(*target)])
+        }
+        _ => None,
+    }
+}
+
+fn replace_constant(instruction: &Bytecode) -> Vec<Bytecode> {
+    use Bytecode as B;
+    macro_rules! replacements {
+        ($n:expr, $zero:expr, $one:expr, $ctor:expr) => {{
+            let n = $n;
+            let mut values = vec![if n == $zero { $one } else { $zero }];
+            if let Some(next) = n.checked_add($one) {
+                if !values.contains(&next) {
+                    values.push(next);
+                }
+            }
+            values.into_iter().map($ctor).collect()
+        }};
+    }
+
+    match instruction {
+        B::LdTrue => vec![B::LdFalse],
+        B::LdFalse => vec![B::LdTrue],
+        B::LdU8(n) => replacements!(*n, 0, 1, B::LdU8),
+        B::LdU16(n) => replacements!(*n, 0, 1, B::LdU16),
+        B::LdU32(n) => replacements!(*n, 0, 1, B::LdU32),
+        B::LdU64(n) => replacements!(*n, 0, 1, B::LdU64),
+        B::LdU128(n) => replacements!(**n, 0, 1, |n| B::LdU128(Box::new(n))),
+        B::LdU256(n) => replacements!(**n, U256::from(0u8), U256::from(1u8), |n| B::LdU256(
+            Box::new(n)
+        )),
+        _ => vec![],
+    }
+}
+
+fn replaced(from: &Bytecode, to: &Bytecode) -> String {
+    format!("replaced `{}` with `{}`", operator(from), operator(to))
+}
+
+fn operator(instruction: &Bytecode) -> &'static str {
+    use Bytecode as B;
+    match instruction {
+        B::Lt => "<",
+        B::Le => "<=",
+        B::Gt => ">",
+        B::Ge => ">=",
+        B::Eq => "==",
+        B::Neq => "!=",
+        B::Add => "+",
+        B::Sub => "-",
+        B::Mul => "*",
+        B::Div => "/",
+        B::Mod => "%",
+        B::BitOr => "|",
+        B::BitAnd => "&",
+        B::Xor => "^",
+        B::Shl => "<<",
+        B::Shr => ">>",
+        B::And => "&&",
+        B::Or => "||",
+        _ => "?",
+    }
+}
+
+fn literal(instruction: &Bytecode) -> String {
+    use Bytecode as B;
+    match instruction {
+        B::LdTrue => "true".to_string(),
+        B::LdFalse => "false".to_string(),
+        B::LdU8(n) => format!("{n}u8"),
+        B::LdU16(n) => format!("{n}u16"),
+        B::LdU32(n) => format!("{n}u32"),
+        B::LdU64(n) => format!("{n}"),
+        B::LdU128(n) => format!("{n}u128"),
+        B::LdU256(n) => format!("{n}u256"),
+        _ => "?".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::{
+        basic_test_module, CodeUnit, ConstantPoolIndex, EnumDefinitionIndex, SignatureIndex,
+        VariantJumpTable,
+    };
+
+    /// `assert!(5 < 3, 7)` followed by a branch past the assert, a branch before it, and a jump
+    /// table with one target on each side.
+    fn module() -> CompiledModule {
+        use Bytecode as B;
+        let mut module = basic_test_module();
+        module.function_defs[0].code = Some(CodeUnit {
+            locals: SignatureIndex(0),
+            code: vec![
+                B::LdU64(5),
+                B::LdU64(3),
+                B::Lt,
+                B::BrTrue(6),
+                B::LdU64(7),
+                B::Abort,
+                B::Branch(8),
+                B::Branch(0),
+                B::Ret,
+            ],
+            jump_tables: vec![VariantJumpTable {
+                head_enum: EnumDefinitionIndex(0),
+                jump_table: JumpTableInner::Full(vec![6, 0]),
+            }],
+        });
+        module
+    }
+
+    fn code(module: &CompiledModule) -> &[Bytecode] {
+        &module.function_defs[0].code.as_ref().unwrap().code
+    }
+
+    #[test]
+    fn test_mutations() {
+        use MutationOperator as M;
+        let module = module();
+        let found: Vec<_> = mutations(&module, |_| true)
+            .into_iter()
+            .map(|m| (m.offset, m.operator, m.description))
+            .collect();
+
+        // The abort code at offset 4 is not replaced.
+        assert_eq!(
+            found,
+            vec![
+                (0, M::ReplaceConstant, "replaced `5` with `0`".to_string()),
+                (0, M::ReplaceConstant, "replaced `5` with `6`".to_string()),
+                (1, M::ReplaceConstant, "replaced `3` with `0`".to_string()),
+                (1, M::ReplaceConstant, "replaced `3` with `4`".to_string()),
+                (2, M::NegateComparison, "replaced `<` with `>=`".to_string()),
+                (2, M::ShiftBoundary, "replaced `<` with `<=`".to_string()),
+                (3, M::DropAssert, "removed `assert!`".to_string()),
+            ]
+        );
+
+        assert!(mutations(&module, |name| name.as_str() != "foo").is_empty());
+    }
+
+    #[test]
+    fn test_apply_in_place() {
+        let module = module();
+        let negate = mutations(&module, |_| true)
+            .into_iter()
+            .find(|m| m.operator == MutationOperator::NegateComparison)
+            .unwrap();
+
+        let mutant = negate.apply(&module);
+        let mut expected = code(&module).to_vec();
+        expected[2] = Bytecode::Ge;
+        assert_eq!(code(&mutant), expected);
+        assert_eq!(
+            mutant.function_defs[0].code.as_ref().unwrap().jump_tables,
+            module.function_defs[0].code.as_ref().unwrap().jump_tables,
+        );
+    }
+
+    #[test]
+    fn test_apply_drop_assert() {
+        use Bytecode as B;
+        let module = module();
+        let drop_assert = mutations(&module, |_| true)
+            .into_iter()
+            .find(|m| m.operator == MutationOperator::DropAssert)
+            .unwrap();
+
+        // The condition is popped and the abort jumped over. Branches to instructions after the
+        // assert, including the one replacing it, follow them as they move.
+        let mutant = drop_assert.apply(&module);
+        assert_eq!(
+            code(&mutant),
+            [
+                B::LdU64(5),
+                B::LdU64(3),
+                B::Lt,
+                B::Pop,
+                B::Branch(7),
+                B::LdU64(7),
+                B::Abort,
+                B::Branch(9),
+                B::Branch(0),
+                B::Ret,
+            ]
+        );
+
+        let JumpTableInner::Full(targets) =
+            &mutant.function_defs[0].code.as_ref().unwrap().jump_tables[0].jump_table;
+        assert_eq!(targets, &[7, 0]);
+    }
+
+    #[test]
+    fn test_drop_assert_branching_to_abort() {
+        use Bytecode as B;
+        // `if (c) abort 7` compiled with the abort as the branch target.
+        let code = [
+            B::LdTrue,
+            B::BrTrue(3),
+            B::Ret,
+            B::LdConst(ConstantPoolIndex(0)),
+            B::Abort,
+        ];
+        assert_eq!(drop_assert(&code, 1), Some(vec![B::Pop]));
+        assert_eq!(drop_assert(&code, 0), None);
+    }
+}
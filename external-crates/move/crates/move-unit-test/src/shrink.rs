@@ -0,0 +1,221 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shrinking of the generated arguments of failing `#[random_test]`s, to find a smaller
+//! counterexample that is easier to debug.
+
+use crate::test_reporter::FailureReason;
+use move_core_types::{account_address::AccountAddress, runtime_value::MoveValue, u256::U256};
+use std::iter;
+
+/// Vectors longer than this only have their first elements removed or shrunk one at a time.
+const MAX_ELEMENT_CANDIDATES: usize = 64;
+
+/// Values that are simpler than `value`, from simplest to least simple: smaller integers,
+/// shorter vectors, vectors with simpler elements, `false` for `true`, and addresses with fewer
+/// non-zero bytes.
+pub(crate) fn candidates(value: &MoveValue) -> Box<dyn Iterator<Item = MoveValue> + '_> {
+    match value {
+        MoveValue::Bool(true) => Box::new(iter::once(MoveValue::Bool(false))),
+        MoveValue::Bool(false) => Box::new(iter::empty()),
+        MoveValue::U8(n) => {
+            Box::new(integer_candidates(U256::from(*n)).map(|n| MoveValue::U8(n.unchecked_as_u8())))
+        }
+        MoveValue::U16(n) => Box::new(
+            integer_candidates(U256::from(*n)).map(|n| MoveValue::U16(n.unchecked_as_u16())),
+        ),
+        MoveValue::U32(n) => Box::new(
+            integer_candidates(U256::from(*n)).map(|n| MoveValue::U32(n.unchecked_as_u32())),
+        ),
+        MoveValue::U64(n) => Box::new(
+            integer_candidates(U256::from(*n)).map(|n| MoveValue::U64(n.unchecked_as_u64())),
+        ),
+        MoveValue::U128(n) => Box::new(
+            integer_candidates(U256::from(*n)).map(|n| MoveValue::U128(n.unchecked_as_u128())),
+        ),
+        MoveValue::U256(n) => Box::new(integer_candidates(*n).map(MoveValue::U256)),
+        MoveValue::Address(address) => Box::new(address_candidates(address)),
+        MoveValue::Vector(elements) => Box::new(vector_candidates(elements)),
+        // Only the types above are generated for `#[random_test]`s
+        MoveValue::Signer(_) | MoveValue::Struct(_) | MoveValue::Variant(_) => {
+            Box::new(iter::empty())
+        }
+    }
+}
+
+/// Whether a test failed for the same reason with shrunk arguments as it did originally, so the
+/// shrunk arguments still reproduce the original failure rather than a different one.
+pub(crate) fn same_failure(shrunk: &FailureReason, original: &FailureReason) -> bool {
+    use FailureReason as F;
+    match (shrunk, original) {
+        (F::NoError(_), F::NoError(_)) | (F::Timeout(_), F::Timeout(_)) => true,
+        (F::WrongError(_, _, shrunk), F::WrongError(_, _, original))
+        | (F::WrongAbortDEPRECATED(_, _, shrunk), F::WrongAbortDEPRECATED(_, _, original))
+        | (F::UnexpectedError(_, shrunk), F::UnexpectedError(_, original)) => shrunk == original,
+        _ => false,
+    }
+}
+
+/// `n - n`, `n - n / 2`, `n - n / 4`, ..., `n - 1`: zero first, then values approaching `n`.
+fn integer_candidates(n: U256) -> impl Iterator<Item = U256> {
+    let zero = U256::from(0u8);
+    let mut delta = n;
+    iter::from_fn(move || {
+        if delta == zero {
+            return None;
+        }
+        let candidate = n - delta;
+        delta = delta >> 1u8;
+        Some(candidate)
+    })
+}
+
+/// The address with all but its last 1, 2, 4, 8 and 16 bytes cleared, and then the integer
+/// candidates of the address as a number.
+fn address_candidates(address: &AccountAddress) -> impl Iterator<Item = MoveValue> + '_ {
+    let bytes = address.into_bytes();
+    let masked = [32, 31, 30, 28, 24, 16].into_iter().map(move |cleared| {
+        let mut masked = [0u8; AccountAddress::LENGTH];
+        masked[cleared..].copy_from_slice(&bytes[cleared..]);
+        masked
+    });
+    let numeric = integer_candidates(U256::from_be_bytes(&bytes)).map(|n| n.to_be_bytes());
+
+    masked
+        .chain(numeric)
+        .filter(move |candidate| candidate != &bytes)
+        .map(|candidate| MoveValue::Address(AccountAddress::new(candidate)))
+}
+
+/// The empty vector, each half of the vector, the vector without each of its elements, and then
+/// the vector with each of its elements shrunk.
+fn vector_candidates(elements: &[MoveValue]) -> impl Iterator<Item = MoveValue> + '_ {
+    let len = elements.len();
+    let halves = (len > 1)
+        .then(|| [elements[len / 2..].to_vec(), elements[..len / 2].to_vec()])
+        .into_iter()
+        .flatten();
+    let removed = (0..len.min(MAX_ELEMENT_CANDIDATES)).map(move |i| {
+        let mut removed = elements.to_vec();
+        removed.remove(i);
+        removed
+    });
+    let shrunk = (0..len.min(MAX_ELEMENT_CANDIDATES)).flat_map(move |i| {
+        candidates(&elements[i]).map(move |candidate| {
+            let mut shrunk = elements.to_vec();
+            shrunk[i] = candidate;
+            shrunk
+        })
+    });
+
+    (len > 0)
+        .then(Vec::new)
+        .into_iter()
+        .chain(halves)
+        .chain(removed)
+        .chain(shrunk)
+        .map(MoveValue::Vector)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_reporter::MoveError;
+    use move_binary_format::errors::Location;
+    use move_compiler::unit_test::MoveErrorType;
+    use move_core_types::vm_status::StatusCode;
+
+    fn abort(code: u64) -> FailureReason {
+        FailureReason::unexpected_error(MoveError(
+            StatusCode::ABORTED,
+            Some(MoveErrorType::Code(code)),
+            Location::Undefined,
+        ))
+    }
+
+    #[test]
+    fn test_integer_candidates() {
+        let shrunk: Vec<_> = candidates(&MoveValue::U8(10)).collect();
+        assert_eq!(
+            shrunk,
+            [0, 5, 8, 9].map(MoveValue::U8).to_vec(),
+            "Zero first, then approaching the original"
+        );
+
+        assert_eq!(candidates(&MoveValue::U8(0)).count(), 0);
+
+        let shrunk: Vec<_> = candidates(&MoveValue::U64(u64::MAX)).collect();
+        assert_eq!(shrunk.len(), 64);
+        assert_eq!(shrunk.first(), Some(&MoveValue::U64(0)));
+        assert_eq!(shrunk.last(), Some(&MoveValue::U64(u64::MAX - 1)));
+    }
+
+    #[test]
+    fn test_bool_candidates() {
+        let shrunk: Vec<_> = candidates(&MoveValue::Bool(true)).collect();
+        assert_eq!(shrunk, vec![MoveValue::Bool(false)]);
+        assert_eq!(candidates(&MoveValue::Bool(false)).count(), 0);
+    }
+
+    #[test]
+    fn test_address_candidates() {
+        let address = AccountAddress::from_hex_literal("0x102").unwrap();
+        let shrunk: Vec<_> = candidates(&MoveValue::Address(address)).collect();
+
+        // Masking the last two bytes or more leaves the address as it was, so those candidates
+        // are skipped.
+        assert_eq!(
+            shrunk[..2],
+            [
+                MoveValue::Address(AccountAddress::ZERO),
+                MoveValue::Address(AccountAddress::from_hex_literal("0x2").unwrap()),
+            ]
+        );
+        assert_eq!(shrunk.len(), 2 + 9);
+        assert!(!shrunk.contains(&MoveValue::Address(address)));
+    }
+
+    #[test]
+    fn test_vector_candidates() {
+        let vector = |ns: &[u8]| MoveValue::Vector(ns.iter().copied().map(MoveValue::U8).collect());
+        let shrunk: Vec<_> = candidates(&vector(&[1, 2])).collect();
+        assert_eq!(
+            shrunk,
+            vec![
+                // Empty
+                vector(&[]),
+                // Halves
+                vector(&[2]),
+                vector(&[1]),
+                // Without each element
+                vector(&[2]),
+                vector(&[1]),
+                // With each element shrunk
+                vector(&[0, 2]),
+                vector(&[1, 0]),
+                vector(&[1, 1]),
+            ]
+        );
+
+        assert_eq!(candidates(&vector(&[])).count(), 0);
+
+        // Only the first elements of long vectors are removed or shrunk.
+        let long = vector(&[0; 100]);
+        assert_eq!(candidates(&long).count(), 1 + 2 + MAX_ELEMENT_CANDIDATES);
+    }
+
+    #[test]
+    fn test_same_failure() {
+        let timeout = FailureReason::timeout();
+        let no_error = FailureReason::no_error();
+
+        assert!(same_failure(&timeout, &timeout));
+        assert!(same_failure(&no_error, &no_error));
+        assert!(same_failure(&abort(1), &abort(1)));
+
+        assert!(!same_failure(&abort(1), &abort(2)));
+        assert!(!same_failure(&abort(1), &timeout));
+        assert!(!same_failure(&timeout, &no_error));
+        assert!(!same_failure(&no_error, &abort(1)));
+    }
+}
@@ -2,23 +2,29 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::format_module_id;
+use crate::{format_module_id, gas_profiler::GasProfile};
 use colored::{control, Colorize};
-use move_binary_format::errors::{ExecutionState, Location, VMError};
-use move_command_line_common::error_bitset::ErrorBitset;
+use move_binary_format::{
+    errors::{ExecutionState, Location, VMError},
+    file_format::FunctionDefinitionIndex,
+};
+use move_command_line_common::{display::escape_xml, error_bitset::ErrorBitset};
 use move_compiler::{
+    compiled_unit::NamedCompiledModule,
     diagnostics::{self, Diagnostic, Diagnostics},
     unit_test::{ModuleTestPlan, MoveErrorType, TestPlan},
 };
 use move_core_types::{
     language_storage::ModuleId,
+    runtime_value::MoveValue,
     vm_status::{StatusCode, StatusType},
 };
 use move_ir_types::location::Loc;
 use move_trace_format::format::MoveTrace;
+use serde::Serialize;
 use std::{
     collections::{BTreeMap, BTreeSet},
-    io::{Result, Write},
+    io::{BufWriter, Result, Write},
     path::Path,
     sync::Mutex,
     time::Duration,
@@ -48,6 +54,9 @@ pub struct TestFailure {
     pub vm_error: Option<VMError>,
     pub failure_reason: FailureReason,
     pub prng_seed: Option<u64>,
+    /// The smallest arguments a failing `#[random_test]` was found to fail with, after shrinking
+    /// its generated arguments
+    pub counterexample: Option<Vec<MoveValue>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -55,10 +64,76 @@ pub struct TestRunInfo {
     pub elapsed_time: Duration,
     pub instructions_executed: u64,
     pub trace: Option<MoveTrace>,
+    pub gas_profile: Option<GasProfile>,
 }
 
 type TestRuns<T> = BTreeMap<String, Vec<T>>;
 
+/// Format of a machine-readable report of a test run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Junit,
+    Json,
+}
+
+/// A report of a test run, in the shape it is written in the JSON format.
+#[derive(Debug, Serialize)]
+struct RunReport {
+    passed: usize,
+    failed: usize,
+    tests: Vec<TestReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct TestReport {
+    module: String,
+    name: String,
+    status: TestStatus,
+    location: Option<SourceLocation>,
+    /// Total time taken by all runs of the test, in seconds
+    duration: f64,
+    /// Total gas used by all runs of the test
+    gas_used: u64,
+    /// Number of times the test was run, more than one for `#[random_test]`s
+    runs: usize,
+    failures: Vec<FailureReport>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum TestStatus {
+    Passed,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+struct FailureReport {
+    message: String,
+    /// The error the test gave, if it gave one, including its abort code or error constant
+    error: Option<String>,
+    abort_code: Option<u64>,
+    location: Option<SourceLocation>,
+    /// The seed the arguments of a failed `#[random_test]` were generated from
+    seed: Option<u64>,
+    /// The smallest arguments a failed `#[random_test]` was found to fail with
+    counterexample: Option<Vec<ArgumentReport>>,
+    duration: f64,
+    gas_used: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ArgumentReport {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SourceLocation {
+    file: String,
+    line: usize,
+    column: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct TestStatistics {
     passed: BTreeMap<ModuleId, TestRuns<TestRunInfo>>,
@@ -86,11 +161,13 @@ impl TestRunInfo {
         elapsed_time: Duration,
         instructions_executed: u64,
         trace: Option<MoveTrace>,
+        gas_profile: Option<GasProfile>,
     ) -> Self {
         Self {
             elapsed_time,
             instructions_executed,
             trace,
+            gas_profile,
         }
     }
 
@@ -174,10 +251,20 @@ impl TestFailure {
             vm_error,
             failure_reason,
             prng_seed,
+            counterexample: None,
         }
     }
 
+    pub fn with_counterexample(mut self, arguments: Vec<MoveValue>) -> Self {
+        self.counterexample = Some(arguments);
+        self
+    }
+
     pub fn render_error(&self, test_plan: &TestPlan) -> String {
+        self.render_error_impl(test_plan, control::SHOULD_COLORIZE.should_colorize())
+    }
+
+    fn render_error_impl(&self, test_plan: &TestPlan, colorize: bool) -> String {
         match &self.failure_reason {
             FailureReason::NoError(message) => message.to_string(),
             FailureReason::Timeout(message) => message.to_string(),
@@ -189,7 +276,7 @@ impl TestFailure {
                         .present_tense(),
                     actual.with_context(&test_plan.module_info).past_tense(),
                 );
-                Self::report_error_with_location(test_plan, base_message, &self.vm_error)
+                Self::report_error_with_location(test_plan, base_message, &self.vm_error, colorize)
             }
             FailureReason::WrongAbortDEPRECATED(message, expected_code, actual) => {
                 let base_message = format!(
@@ -199,7 +286,7 @@ impl TestFailure {
                     expected_code,
                     actual.with_context(&test_plan.module_info).past_tense(),
                 );
-                Self::report_error_with_location(test_plan, base_message, &self.vm_error)
+                Self::report_error_with_location(test_plan, base_message, &self.vm_error, colorize)
             }
             FailureReason::UnexpectedError(message, error) => {
                 let prefix = match error.0.status_type() {
@@ -223,7 +310,7 @@ impl TestFailure {
                     message,
                     error.with_context(&test_plan.module_info).past_tense(),
                 );
-                Self::report_error_with_location(test_plan, base_message, &self.vm_error)
+                Self::report_error_with_location(test_plan, base_message, &self.vm_error, colorize)
             }
             FailureReason::Property(message) => message.clone(),
         }
@@ -277,16 +364,41 @@ impl TestFailure {
         buf
     }
 
+    /// The location `vm_error` originated from, and the definition location of the function it
+    /// originated in.
+    fn error_locations(test_plan: &TestPlan, vm_error: &VMError) -> Option<(Loc, Loc)> {
+        let Location::Module(module_id) = vm_error.location() else {
+            return None;
+        };
+        let (fdef_idx, offset) = vm_error.offsets().first()?;
+        let function_source_map = test_plan
+            .module_info
+            .get(module_id)?
+            .source_map
+            .get_function_source_map(*fdef_idx)
+            .ok()?;
+        let loc = function_source_map.get_code_location(*offset).unwrap();
+
+        let alternate_location_opt = clever_error_line_number_to_loc(test_plan, vm_error);
+        let loc = if alternate_location_opt.is_some_and(|alt_loc| !loc.overlaps(&alt_loc)) {
+            alternate_location_opt.unwrap()
+        } else {
+            loc
+        };
+        Some((loc, function_source_map.definition_location))
+    }
+
     fn report_error_with_location(
         test_plan: &TestPlan,
         base_message: String,
         vm_error: &Option<VMError>,
+        colorize: bool,
     ) -> String {
         let report_diagnostics = |mapped_files, diags| {
             diagnostics::report_diagnostics_to_buffer_with_mapped_files(
                 mapped_files,
                 diags,
-                control::SHOULD_COLORIZE.should_colorize(),
+                colorize,
             )
         };
 
@@ -297,34 +409,18 @@ impl TestFailure {
 
         let diags = match vm_error.location() {
             Location::Module(module_id) => {
-                let diag_opt = vm_error.offsets().first().and_then(|(fdef_idx, offset)| {
-                    let function_source_map = test_plan
-                        .module_info
-                        .get(module_id)?
-                        .source_map
-                        .get_function_source_map(*fdef_idx)
-                        .ok()?;
-                    let loc = function_source_map.get_code_location(*offset).unwrap();
-
-                    let alternate_location_opt =
-                        clever_error_line_number_to_loc(test_plan, vm_error);
-                    let loc =
-                        if alternate_location_opt.is_some_and(|alt_loc| !loc.overlaps(&alt_loc)) {
-                            alternate_location_opt.unwrap()
-                        } else {
-                            loc
-                        };
+                let diag_opt = Self::error_locations(test_plan, vm_error).map(|(loc, fn_loc)| {
                     let msg = format!(
                         "In this function in {}",
                         format_module_id(&test_plan.module_info, module_id)
                     );
                     // TODO(tzakian) maybe migrate off of move-langs diagnostics?
-                    Some(Diagnostic::new(
+                    Diagnostic::new(
                         diagnostics::codes::Tests::TestFailed,
                         (loc, base_message.clone()),
-                        vec![(function_source_map.definition_location, msg)],
+                        vec![(fn_loc, msg)],
                         std::iter::empty::<String>(),
-                    ))
+                    )
                 });
                 match diag_opt {
                     None => base_message,
@@ -567,6 +663,280 @@ impl TestResults {
         writeln!(writer.lock().unwrap())
     }
 
+    /// Rank the tests that were profiled by the gas they used, most expensive first, along with
+    /// the function in each that used the most gas itself. For tests that were run more than once,
+    /// the most expensive run is reported.
+    pub fn report_gas_profiles<W: Write>(&self, writer: &Mutex<W>) -> Result<()> {
+        let mut ranking = BTreeMap::new();
+        let passed = self
+            .final_statistics
+            .passed
+            .iter()
+            .flat_map(|(module_id, tests)| {
+                tests.iter().flat_map(move |(name, runs)| {
+                    runs.iter().map(move |run| (module_id, name, run))
+                })
+            });
+        let failed = self
+            .final_statistics
+            .failed
+            .iter()
+            .flat_map(|(module_id, tests)| {
+                tests.iter().flat_map(move |(name, failures)| {
+                    failures
+                        .iter()
+                        .map(move |failure| (module_id, name, &failure.test_run_info))
+                })
+            });
+        for (module_id, function_name, run) in passed.chain(failed) {
+            let Some(profile) = &run.gas_profile else {
+                continue;
+            };
+            let qualified_function_name = format!(
+                "{}::{}",
+                format_module_id(&self.test_plan.module_info, module_id),
+                function_name,
+            );
+            let entry = ranking
+                .entry(qualified_function_name)
+                .or_insert((run.instructions_executed, profile));
+            if run.instructions_executed > entry.0 {
+                *entry = (run.instructions_executed, profile);
+            }
+        }
+
+        let mut ranking: Vec<_> = ranking.into_iter().collect();
+        ranking.sort_by(|(_, (a, _)), (_, (b, _))| b.cmp(a));
+
+        writeln!(writer.lock().unwrap(), "\nGas Profile:\n")?;
+        if ranking.is_empty() {
+            return writeln!(writer.lock().unwrap());
+        }
+
+        let hottest_functions: Vec<_> = ranking
+            .iter()
+            .map(|(_, (_, profile))| match profile.hottest_function() {
+                Some((name, gas)) => {
+                    let share = 100.0 * gas as f64 / profile.gas_used().max(1) as f64;
+                    format!("{name} ({share:.1}%)")
+                }
+                None => "-".to_string(),
+            })
+            .collect();
+        let name_width = ranking
+            .iter()
+            .map(|(name, _)| name.len())
+            .chain(std::iter::once("Test Name".len()))
+            .max()
+            .unwrap_or_default();
+        let function_width = hottest_functions
+            .iter()
+            .map(|name| name.len())
+            .chain(std::iter::once("Hottest Function".len()))
+            .max()
+            .unwrap_or_default();
+
+        let mut w = writer.lock().unwrap();
+        let rule = |w: &mut W, left: &str, middle: &str, right: &str| {
+            writeln!(
+                w,
+                "{left}─{:─^4}─{middle}─{:─^name_width$}─{middle}─{:─^15}─{middle}─{:─^function_width$}─{right}",
+                "", "", "", "",
+            )
+        };
+        rule(&mut w, "┌", "┬", "┐")?;
+        writeln!(
+            w,
+            "│ {:^4} │ {:^name_width$} │ {:^15} │ {:^function_width$} │",
+            "Rank", "Test Name", "Gas Used", "Hottest Function",
+        )?;
+        for (rank, ((name, (gas_used, _)), hottest_function)) in
+            ranking.iter().zip(&hottest_functions).enumerate()
+        {
+            rule(&mut w, "├", "┼", "┤")?;
+            writeln!(
+                w,
+                "│ {:>4} │ {name:<name_width$} │ {gas_used:>15} │ {hottest_function:<function_width$} │",
+                rank + 1,
+            )?;
+        }
+        rule(&mut w, "└", "┴", "┘")?;
+        writeln!(w)
+    }
+
+    /// Write a machine-readable report of every test that was run to `path`, in `format`.
+    pub fn write_report(&self, format: ReportFormat, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        let report = self.run_report();
+        match format {
+            ReportFormat::Json => serde_json::to_writer_pretty(&mut writer, &report)?,
+            ReportFormat::Junit => write_junit(&mut writer, &report)?,
+        }
+        writer.flush()
+    }
+
+    fn run_report(&self) -> RunReport {
+        let mut tests = vec![];
+        for (module_id, module_test_plan) in &self.test_plan.module_tests {
+            let passed = self.final_statistics.passed.get(module_id);
+            let failed = self.final_statistics.failed.get(module_id);
+            for test_name in module_test_plan.tests.keys() {
+                let passes = passed
+                    .and_then(|p| p.get(test_name))
+                    .map_or(&[][..], Vec::as_slice);
+                let failures = failed
+                    .and_then(|f| f.get(test_name))
+                    .map_or(&[][..], Vec::as_slice);
+                // Tests that were filtered out were not run at all
+                if passes.is_empty() && failures.is_empty() {
+                    continue;
+                }
+
+                let (duration, gas_used) = calculate_run_statistics(
+                    passes
+                        .iter()
+                        .chain(failures.iter().map(|f| &f.test_run_info)),
+                );
+                tests.push(TestReport {
+                    module: format_module_id(&self.test_plan.module_info, module_id),
+                    name: test_name.clone(),
+                    status: if failures.is_empty() {
+                        TestStatus::Passed
+                    } else {
+                        TestStatus::Failed
+                    },
+                    location: self.test_location(module_id, test_name),
+                    duration: duration.as_secs_f64(),
+                    gas_used,
+                    runs: passes.len() + failures.len(),
+                    failures: failures
+                        .iter()
+                        .map(|f| self.failure_report(module_id, test_name, f))
+                        .collect(),
+                });
+            }
+        }
+
+        let failed = tests.iter().filter(|t| !t.failures.is_empty()).count();
+        RunReport {
+            passed: tests.len() - failed,
+            failed,
+            tests,
+        }
+    }
+
+    fn failure_report(
+        &self,
+        module_id: &ModuleId,
+        test_name: &str,
+        failure: &TestFailure,
+    ) -> FailureReport {
+        let error = match &failure.failure_reason {
+            FailureReason::WrongError(_, _, actual)
+            | FailureReason::WrongAbortDEPRECATED(_, _, actual)
+            | FailureReason::UnexpectedError(_, actual) => Some(
+                actual
+                    .with_context(&self.test_plan.module_info)
+                    .past_tense()
+                    .to_string(),
+            ),
+            FailureReason::NoError(_) | FailureReason::Timeout(_) | FailureReason::Property(_) => {
+                None
+            }
+        };
+        let vm_error = failure.vm_error.as_ref();
+        let abort_code = vm_error.and_then(|e| match (e.major_status(), e.sub_status()) {
+            (StatusCode::ABORTED, Some(abort_code)) => Some(abort_code),
+            _ => None,
+        });
+        let location = vm_error
+            .and_then(|e| TestFailure::error_locations(&self.test_plan, e))
+            .and_then(|(loc, _)| self.source_location(&loc));
+
+        FailureReport {
+            message: failure.render_error_impl(&self.test_plan, /* colorize */ false),
+            error,
+            abort_code,
+            location,
+            seed: failure.prng_seed,
+            counterexample: failure.counterexample.as_ref().map(|arguments| {
+                self.named_arguments(module_id, test_name, arguments)
+                    .map(|(name, value)| ArgumentReport {
+                        name,
+                        value: value.to_string(),
+                    })
+                    .collect()
+            }),
+            duration: failure.test_run_info.elapsed_time.as_secs_f64(),
+            gas_used: failure.test_run_info.instructions_executed,
+        }
+    }
+
+    /// The location of the definition of test `test_name` in `module_id`
+    fn test_location(&self, module_id: &ModuleId, test_name: &str) -> Option<SourceLocation> {
+        let (named_module, fdef_idx) = self.test_function(module_id, test_name)?;
+        let function_source_map = named_module
+            .source_map
+            .get_function_source_map(fdef_idx)
+            .ok()?;
+        self.source_location(&function_source_map.definition_location)
+    }
+
+    /// The module defining test `test_name` in `module_id`, and the test's index in it
+    fn test_function(
+        &self,
+        module_id: &ModuleId,
+        test_name: &str,
+    ) -> Option<(&NamedCompiledModule, FunctionDefinitionIndex)> {
+        let named_module = self.test_plan.module_info.get(module_id)?;
+        let module = &named_module.module;
+        let fdef_idx = module.function_defs().iter().position(|fdef| {
+            let handle = module.function_handle_at(fdef.function);
+            module.identifier_at(handle.name).as_str() == test_name
+        })?;
+        Some((named_module, FunctionDefinitionIndex(fdef_idx as u16)))
+    }
+
+    /// Pair `arguments` to test `test_name` in `module_id` with the names of its parameters
+    fn named_arguments<'a>(
+        &self,
+        module_id: &ModuleId,
+        test_name: &str,
+        arguments: &'a [MoveValue],
+    ) -> impl Iterator<Item = (String, &'a MoveValue)> {
+        let parameters = self
+            .test_function(module_id, test_name)
+            .and_then(|(named_module, fdef_idx)| {
+                named_module
+                    .source_map
+                    .get_function_source_map(fdef_idx)
+                    .ok()
+            })
+            .map(|function_source_map| function_source_map.parameters.clone())
+            .unwrap_or_default();
+        arguments.iter().enumerate().map(move |(i, argument)| {
+            // Parameter names in the source map carry the compiler's `#`-separated suffixes.
+            let name = parameters.get(i).map_or_else(
+                || format!("arg{i}"),
+                |(name, _)| name.split('#').next().unwrap_or(name).to_string(),
+            );
+            (name, argument)
+        })
+    }
+
+    fn source_location(&self, loc: &Loc) -> Option<SourceLocation> {
+        let mapped_files = &self.test_plan.mapped_files;
+        let position = mapped_files.position_opt(loc)?;
+        Some(SourceLocation {
+            file: mapped_files.filename(&loc.file_hash()).to_string(),
+            line: position.start.user_line(),
+            column: position.start.user_column(),
+        })
+    }
+
     /// Returns `true` if all tests passed, `false` if there was a test failure/timeout
     pub fn summarize<W: Write>(self, writer: &Mutex<W>) -> Result<bool> {
         let num_failed_tests = self
@@ -606,15 +976,35 @@ impl TestResults {
                                 .render_error(&self.test_plan)
                                 .replace('\n', "\n│ ")
                         )?;
+                        if let Some(arguments) = &test_failure.counterexample {
+                            let arguments = self
+                                .named_arguments(module_id, test_name, arguments)
+                                .map(|(name, value)| format!("{name}: {value}"))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            writeln!(
+                                writer.lock().unwrap(),
+                                "│ Minimal counterexample: ({})",
+                                arguments.bold()
+                            )?;
+                        }
                         if let Some(seed) = test_failure.prng_seed {
+                            let shrinking = if test_failure.counterexample.is_some() {
+                                " It runs the test with the arguments generated from the seed, \
+                                and shrinks them again to the minimal counterexample above."
+                            } else {
+                                ""
+                            };
                             writeln!(writer.lock().unwrap(),
                             "│ {}",
                             format!(
-                                "This test uses randomly generated inputs. Rerun with `{}` to recreate this test failure.\n",
-                                format!("test {} --seed {}",
+                                "This test uses randomly generated inputs. Rerun with `{}` to recreate this test failure.{}\n",
+                                format!("test --replay {}::{} --seed {}",
+                                    module_id.name(),
                                     test_name,
                                     seed
-                                ).bright_red().bold()
+                                ).bright_red().bold(),
+                                shrinking,
                             ).replace('\n', "\n│ ")
                         )?;
                         }
@@ -639,3 +1029,250 @@ impl TestResults {
         Ok(num_failed_tests == 0)
     }
 }
+
+/// Write `report` in the JUnit XML format, with one test suite per module.
+fn write_junit<W: Write>(writer: &mut W, report: &RunReport) -> Result<()> {
+    let mut modules: BTreeMap<&str, Vec<&TestReport>> = BTreeMap::new();
+    for test in &report.tests {
+        modules.entry(&test.module).or_default().push(test);
+    }
+    let total_time: f64 = report.tests.iter().map(|t| t.duration).sum();
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<testsuites name="move-unit-tests" tests="{}" failures="{}" errors="0" time="{total_time:.6}">"#,
+        report.tests.len(),
+        report.failed,
+    )?;
+    for (module, tests) in modules {
+        let failures = tests.iter().filter(|t| !t.failures.is_empty()).count();
+        let time: f64 = tests.iter().map(|t| t.duration).sum();
+        writeln!(
+            writer,
+            r#"  <testsuite name="{}" tests="{}" failures="{failures}" errors="0" skipped="0" time="{time:.6}">"#,
+            escape_xml(module),
+            tests.len(),
+        )?;
+        for test in tests {
+            let location = match &test.location {
+                Some(SourceLocation { file, line, .. }) => {
+                    format!(r#" file="{}" line="{line}""#, escape_xml(file))
+                }
+                None => String::new(),
+            };
+            writeln!(
+                writer,
+                r#"    <testcase name="{}" classname="{}" time="{:.6}"{location}>"#,
+                escape_xml(&test.name),
+                escape_xml(module),
+                test.duration,
+            )?;
+            writeln!(writer, "      <properties>")?;
+            writeln!(
+                writer,
+                r#"        <property name="gas_used" value="{}"/>"#,
+                test.gas_used
+            )?;
+            writeln!(
+                writer,
+                r#"        <property name="runs" value="{}"/>"#,
+                test.runs
+            )?;
+            for failure in &test.failures {
+                if let Some(seed) = failure.seed {
+                    writeln!(writer, r#"        <property name="seed" value="{seed}"/>"#)?;
+                }
+            }
+            writeln!(writer, "      </properties>")?;
+            for failure in &test.failures {
+                let summary = failure
+                    .error
+                    .clone()
+                    .or_else(|| failure.message.lines().next().map(str::to_string))
+                    .unwrap_or_default();
+                let failure_type = match failure.abort_code {
+                    Some(_) => "abort",
+                    None => "failure",
+                };
+                writeln!(
+                    writer,
+                    r#"      <failure message="{}" type="{failure_type}">{}</failure>"#,
+                    escape_xml(&summary),
+                    escape_xml(&failure.message),
+                )?;
+            }
+            writeln!(writer, "    </testcase>")?;
+        }
+        writeln!(writer, "  </testsuite>")?;
+    }
+    writeln!(writer, "</testsuites>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn report() -> RunReport {
+        let test = |name: &str, duration: f64, gas_used: u64, failures: Vec<_>| TestReport {
+            module: "0x1::m".to_string(),
+            name: name.to_string(),
+            status: if failures.is_empty() {
+                TestStatus::Passed
+            } else {
+                TestStatus::Failed
+            },
+            location: None,
+            duration,
+            gas_used,
+            runs: 1,
+            failures,
+        };
+
+        let failure = |message: &str, error: Option<&str>, abort_code, seed| FailureReport {
+            message: message.to_string(),
+            error: error.map(str::to_string),
+            abort_code,
+            location: None,
+            seed,
+            counterexample: None,
+            duration: 0.25,
+            gas_used: 20,
+        };
+
+        let mut passes = test("passes", 0.5, 10, vec![]);
+        passes.location = Some(SourceLocation {
+            file: "sources/m.move".to_string(),
+            line: 3,
+            column: 5,
+        });
+
+        let fails = test(
+            "fails",
+            0.25,
+            20,
+            vec![failure(
+                "Test was not expected to error, but it aborted with code 1 <here>\n\
+                 `assert!(a < b && c > \"d\")`",
+                Some("aborted with code 1 & <more>"),
+                Some(1),
+                Some(42),
+            )],
+        );
+
+        let times_out = test(
+            "times_out",
+            1.0,
+            30,
+            vec![failure("Test timed out", None, None, None)],
+        );
+
+        RunReport {
+            passed: 1,
+            failed: 2,
+            tests: vec![passes, fails, times_out],
+        }
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let mut output = vec![];
+        write_junit(&mut output, &report()).unwrap();
+
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="move-unit-tests" tests="3" failures="2" errors="0" time="1.750000">
+  <testsuite name="0x1::m" tests="3" failures="2" errors="0" skipped="0" time="1.750000">
+    <testcase name="passes" classname="0x1::m" time="0.500000" file="sources/m.move" line="3">
+      <properties>
+        <property name="gas_used" value="10"/>
+        <property name="runs" value="1"/>
+      </properties>
+    </testcase>
+    <testcase name="fails" classname="0x1::m" time="0.250000">
+      <properties>
+        <property name="gas_used" value="20"/>
+        <property name="runs" value="1"/>
+        <property name="seed" value="42"/>
+      </properties>
+      <failure message="aborted with code 1 &amp; &lt;more&gt;" type="abort">Test was not expected to error, but it aborted with code 1 &lt;here&gt;
+`assert!(a &lt; b &amp;&amp; c &gt; &quot;d&quot;)`</failure>
+    </testcase>
+    <testcase name="times_out" classname="0x1::m" time="1.000000">
+      <properties>
+        <property name="gas_used" value="30"/>
+        <property name="runs" value="1"/>
+      </properties>
+      <failure message="Test timed out" type="failure">Test timed out</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#;
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_json_report() {
+        let report = serde_json::to_value(report()).unwrap();
+        let failure =
+            |message: &str, error: Option<&str>, abort_code: Option<u64>, seed: Option<u64>| {
+                json!({
+                    "message": message,
+                    "error": error,
+                    "abort_code": abort_code,
+                    "location": null,
+                    "seed": seed,
+                    "counterexample": null,
+                    "duration": 0.25,
+                    "gas_used": 20,
+                })
+            };
+
+        assert_eq!(
+            report,
+            json!({
+                "passed": 1,
+                "failed": 2,
+                "tests": [
+                    {
+                        "module": "0x1::m",
+                        "name": "passes",
+                        "status": "passed",
+                        "location": { "file": "sources/m.move", "line": 3, "column": 5 },
+                        "duration": 0.5,
+                        "gas_used": 10,
+                        "runs": 1,
+                        "failures": [],
+                    },
+                    {
+                        "module": "0x1::m",
+                        "name": "fails",
+                        "status": "failed",
+                        "location": null,
+                        "duration": 0.25,
+                        "gas_used": 20,
+                        "runs": 1,
+                        "failures": [failure(
+                            "Test was not expected to error, but it aborted with code 1 <here>\n\
+                             `assert!(a < b && c > \"d\")`",
+                            Some("aborted with code 1 & <more>"),
+                            Some(1),
+                            Some(42),
+                        )],
+                    },
+                    {
+                        "module": "0x1::m",
+                        "name": "times_out",
+                        "status": "failed",
+                        "location": null,
+                        "duration": 1.0,
+                        "gas_used": 30,
+                        "runs": 1,
+                        "failures": [failure("Test timed out", None, None, None)],
+                    },
+                ],
+            })
+        );
+    }
+}
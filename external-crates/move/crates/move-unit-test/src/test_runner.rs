@@ -4,6 +4,8 @@
 
 use crate::{
     extensions, format_module_id,
+    gas_profiler::ProfilingGasMeter,
+    shrink,
     test_reporter::{
         FailureReason, MoveError, TestFailure, TestResults, TestRunInfo, TestStatistics,
     },
@@ -12,7 +14,7 @@ use anyhow::Result;
 use colored::*;
 
 use move_binary_format::{
-    errors::{Location, VMResult},
+    errors::{Location, VMError, VMResult},
     file_format::CompiledModule,
 };
 use move_bytecode_utils::Modules;
@@ -38,10 +40,14 @@ use move_vm_test_utils::{
 };
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use rayon::prelude::*;
-use std::{collections::BTreeMap, io::Write, marker::Send, sync::Mutex, time::Instant};
+use std::{collections::BTreeMap, io::Write, marker::Send, path::Path, sync::Mutex, time::Instant};
 
 use move_vm_runtime::native_extensions::NativeContextExtensions;
 
+/// The maximum number of extra times a failing `#[random_test]` is run while shrinking its
+/// arguments.
+const MAX_SHRINK_RUNS: usize = 1000;
+
 /// Test state common to all tests
 pub struct SharedTestingConfig {
     report_stacktrace_on_abort: bool,
@@ -53,6 +59,7 @@ pub struct SharedTestingConfig {
     num_iters: u64,
     deterministic_generation: bool,
     trace_location: Option<String>,
+    profile_location: Option<String>,
 }
 
 pub struct TestRunner {
@@ -115,6 +122,7 @@ impl TestRunner {
         num_iters: u64,
         deterministic_generation: bool,
         trace_location: Option<String>,
+        profile_location: Option<String>,
         tests: TestPlan,
         // TODO: maybe we should require the clients to always pass in a list of native functions so
         // we don't have to make assumptions about their gas parameters.
@@ -147,6 +155,7 @@ impl TestRunner {
                 num_iters,
                 deterministic_generation,
                 trace_location,
+                profile_location,
             },
             num_threads,
             tests,
@@ -176,6 +185,19 @@ impl TestRunner {
             })
     }
 
+    /// Keep only the test whose fully qualified name, `<module_name>::<test_name>`, is `full_name`.
+    /// Returns whether there is such a test.
+    pub fn filter_exact(&mut self, full_name: &str) -> bool {
+        let mut found = false;
+        for (module_id, module_test) in self.tests.module_tests.iter_mut() {
+            module_test.tests.retain(|test_name, _| {
+                format!("{}::{}", module_id.name().as_str(), test_name.as_str()) == full_name
+            });
+            found |= !module_test.tests.is_empty();
+        }
+        found
+    }
+
     pub fn filter(&mut self, test_name_slice: &str) {
         for (module_id, module_test) in self.tests.module_tests.iter_mut() {
             if module_id.name().as_str().contains(test_name_slice) {
@@ -261,7 +283,13 @@ impl SharedTestingConfig {
 
         let mut session =
             move_vm.new_session_with_extensions(&self.starting_storage_state, extensions);
-        let mut gas_meter = GasStatus::new(&self.cost_table, Gas::new(self.execution_bound));
+        let mut gas_meter = ProfilingGasMeter::new(GasStatus::new(
+            &self.cost_table,
+            Gas::new(self.execution_bound),
+        ));
+        if self.profile_location.is_some() {
+            gas_meter = gas_meter.with_profile(&test_plan.module_id, function_name);
+        }
         move_vm_profiler::tracing_feature_enabled! {
             use move_vm_profiler::GasProfiler;
             use move_vm_types::gas::GasMeter;
@@ -303,10 +331,11 @@ impl SharedTestingConfig {
             // TODO(Gas): This doesn't look quite right...
             //            We're not computing the number of instructions executed even with a unit gas schedule.
             Gas::new(self.execution_bound)
-                .checked_sub(gas_meter.remaining_gas())
+                .checked_sub(gas_meter.gas_status().remaining_gas())
                 .unwrap()
                 .into(),
             trace,
+            gas_meter.finish(),
         );
         match session.finish_with_extensions().0 {
             Ok((cs, extensions)) => (Ok(cs), Ok(extensions), return_result, test_run_info),
@@ -423,8 +452,39 @@ impl SharedTestingConfig {
         prng_seed: Option<u64>,
         is_last_execution_of_test: bool,
     ) -> bool {
-        let (_cs_result, _ext_result, exec_result, test_run_info) =
-            self.execute_via_move_vm(test_plan, function_name, arguments);
+        let (mut test_run_info, mut result) = self.run_test_once(
+            test_plan,
+            global_test_context,
+            function_name,
+            test_info,
+            arguments.clone(),
+        );
+
+        // Look for a smaller set of generated arguments that the test fails with in the same way,
+        // and report that failure instead. Timeouts are not shrunk: every run that reproduces one
+        // uses up the whole execution bound, so they are reported without a counterexample.
+        let mut counterexample = None;
+        let failure_to_shrink = match (&result, prng_seed) {
+            (Err(failure), Some(_)) if !matches!(failure.0, FailureReason::Timeout(_)) => {
+                Some(failure)
+            }
+            _ => None,
+        };
+        if let Some(failure) = failure_to_shrink {
+            let (minimal_arguments, minimal_run_info, minimal_failure) = self.shrink_arguments(
+                test_plan,
+                global_test_context,
+                function_name,
+                test_info,
+                arguments,
+                failure,
+            );
+            if let Some(minimal_failure) = minimal_failure {
+                test_run_info = minimal_run_info.unwrap();
+                result = Err(minimal_failure);
+            }
+            counterexample = Some(minimal_arguments);
+        }
 
         // Save the trace -- one per test -- for each test that we have traced (and if tracing is
         // enabled).
@@ -445,7 +505,67 @@ impl SharedTestingConfig {
             }
         }
 
-        match exec_result {
+        // Likewise save the gas profile of each test that we have profiled, keeping only its
+        // summary in memory.
+        if let (Some(location), Some(profile)) =
+            (&self.profile_location, &mut test_run_info.gas_profile)
+        {
+            let profile_file_location = format!(
+                "{}/{}__{}{}.json",
+                location,
+                format_module_id(output.test_info, &output.test_plan.module_id).replace("::", "__"),
+                function_name,
+                if let Some(seed) = prng_seed {
+                    format!("_seed_{}", seed)
+                } else {
+                    "".to_string()
+                }
+            );
+            if let Err(e) = profile.save(Path::new(&profile_file_location)) {
+                eprintln!(
+                    "Unable to save gas profile to {profile_file_location} -- {:?}",
+                    e
+                );
+            }
+            profile.discard_events();
+        }
+
+        match result {
+            Ok(()) => {
+                if is_last_execution_of_test {
+                    output.pass(function_name);
+                }
+                stats.test_success(function_name.to_string(), test_run_info, test_plan)
+            }
+            Err((failure_reason, vm_error)) => {
+                if matches!(failure_reason, FailureReason::Timeout(_)) {
+                    output.timeout(function_name);
+                } else {
+                    output.fail(function_name);
+                }
+                let mut failure =
+                    TestFailure::new(failure_reason, test_run_info, vm_error, prng_seed);
+                if let Some(arguments) = counterexample {
+                    failure = failure.with_counterexample(arguments);
+                }
+                stats.test_failure(function_name.to_string(), failure, test_plan)
+            }
+        }
+    }
+
+    /// Run a test once with `arguments`, returning how it failed if it did not pass.
+    fn run_test_once(
+        &self,
+        test_plan: &ModuleTestPlan,
+        global_test_context: &BTreeMap<ModuleId, NamedCompiledModule>,
+        function_name: &str,
+        test_info: &TestCase,
+        arguments: Vec<MoveValue>,
+    ) -> (TestRunInfo, Result<(), (FailureReason, Option<VMError>)>) {
+        let (_cs_result, _ext_result, exec_result, test_run_info) =
+            self.execute_via_move_vm(test_plan, function_name, arguments);
+
+        let result = match exec_result {
             Err(err) => {
                 let sub_status = err.sub_status().and_then(|status| {
                     convert_clever_move_abort_error(status, err.location(), global_test_context)
@@ -453,106 +573,103 @@ impl SharedTestingConfig {
                 let actual_err = MoveError(err.major_status(), sub_status, err.location().clone());
                 assert!(err.major_status() != StatusCode::EXECUTED);
                 match test_info.expected_failure.as_ref() {
-                    Some(ExpectedFailure::Expected) => {
-                        if is_last_execution_of_test {
-                            output.pass(function_name);
-                        }
-                        stats.test_success(function_name.to_string(), test_run_info, test_plan)
-                    }
+                    Some(ExpectedFailure::Expected) => Ok(()),
                     Some(ExpectedFailure::ExpectedWithError(expected_err))
                         if expected_err == &actual_err =>
                     {
-                        if is_last_execution_of_test {
-                            output.pass(function_name);
-                        }
-                        stats.test_success(function_name.to_string(), test_run_info, test_plan)
+                        Ok(())
                     }
                     Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(code))
                         if actual_err.0 == StatusCode::ABORTED
                             && actual_err.1.is_some()
                             && actual_err.1.as_ref().unwrap() == code =>
                     {
-                        if is_last_execution_of_test {
-                            output.pass(function_name);
-                        }
-                        stats.test_success(function_name.to_string(), test_run_info, test_plan)
+                        Ok(())
                     }
                     // incorrect cases
-                    Some(ExpectedFailure::ExpectedWithError(expected_err)) => {
-                        output.fail(function_name);
-                        stats.test_failure(
-                            function_name.to_string(),
-                            TestFailure::new(
-                                FailureReason::wrong_error(expected_err.clone(), actual_err),
-                                test_run_info,
-                                Some(err),
-                                prng_seed,
-                            ),
-                            test_plan,
-                        )
-                    }
-                    Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(expected_code)) => {
-                        output.fail(function_name);
-                        stats.test_failure(
-                            function_name.to_string(),
-                            TestFailure::new(
-                                FailureReason::wrong_abort_deprecated(
-                                    expected_code.clone(),
-                                    actual_err,
-                                ),
-                                test_run_info,
-                                Some(err),
-                                prng_seed,
-                            ),
-                            test_plan,
-                        )
-                    }
+                    Some(ExpectedFailure::ExpectedWithError(expected_err)) => Err((
+                        FailureReason::wrong_error(expected_err.clone(), actual_err),
+                        Some(err),
+                    )),
+                    Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(expected_code)) => Err((
+                        FailureReason::wrong_abort_deprecated(expected_code.clone(), actual_err),
+                        Some(err),
+                    )),
+                    // Ran out of ticks, report a test timeout
                     None if err.major_status() == StatusCode::OUT_OF_GAS => {
-                        // Ran out of ticks, report a test timeout and log a test failure
-                        output.timeout(function_name);
-                        stats.test_failure(
-                            function_name.to_string(),
-                            TestFailure::new(
-                                FailureReason::timeout(),
-                                test_run_info,
-                                Some(err),
-                                prng_seed,
-                            ),
-                            test_plan,
-                        )
-                    }
-                    None => {
-                        output.fail(function_name);
-                        stats.test_failure(
-                            function_name.to_string(),
-                            TestFailure::new(
-                                FailureReason::unexpected_error(actual_err),
-                                test_run_info,
-                                Some(err),
-                                prng_seed,
-                            ),
-                            test_plan,
-                        )
+                        Err((FailureReason::timeout(), Some(err)))
                     }
+                    None => Err((FailureReason::unexpected_error(actual_err), Some(err))),
                 }
             }
-            Ok(_) => {
-                // Expected the test to fail, but it executed
-                if test_info.expected_failure.is_some() {
-                    output.fail(function_name);
-                    stats.test_failure(
-                        function_name.to_string(),
-                        TestFailure::new(FailureReason::no_error(), test_run_info, None, prng_seed),
+            // Expected the test to fail, but it executed
+            Ok(_) if test_info.expected_failure.is_some() => Err((FailureReason::no_error(), None)),
+            // Expected the test to execute fully and it did
+            Ok(_) => Ok(()),
+        };
+
+        (test_run_info, result)
+    }
+
+    /// Greedily shrink the generated arguments of a failing `#[random_test]`, keeping each smaller
+    /// argument that the test still fails with for the same reason, until no argument can be made
+    /// smaller or `MAX_SHRINK_RUNS` runs have been made. Returns the smallest arguments found, and
+    /// the run and failure for them if they differ from the original arguments.
+    fn shrink_arguments(
+        &self,
+        test_plan: &ModuleTestPlan,
+        global_test_context: &BTreeMap<ModuleId, NamedCompiledModule>,
+        function_name: &str,
+        test_info: &TestCase,
+        mut arguments: Vec<MoveValue>,
+        original_failure: &(FailureReason, Option<VMError>),
+    ) -> (
+        Vec<MoveValue>,
+        Option<TestRunInfo>,
+        Option<(FailureReason, Option<VMError>)>,
+    ) {
+        let mut minimal = None;
+        let mut runs = 0;
+
+        'shrink: loop {
+            for (i, arg) in test_info.arguments.iter().enumerate() {
+                if !matches!(arg, TestArgument::Generate { .. }) {
+                    continue;
+                }
+
+                // Candidates are generated lazily from a copy of the argument, as there can be far
+                // more of them than are ever run, and `arguments` is replaced when one is kept.
+                let argument = arguments[i].clone();
+                for candidate in shrink::candidates(&argument) {
+                    if runs == MAX_SHRINK_RUNS {
+                        break 'shrink;
+                    }
+                    runs += 1;
+
+                    let mut candidate_arguments = arguments.clone();
+                    candidate_arguments[i] = candidate;
+                    let (test_run_info, result) = self.run_test_once(
                         test_plan,
-                    )
-                } else {
-                    // Expected the test to execute fully and it did
-                    if is_last_execution_of_test {
-                        output.pass(function_name);
+                        global_test_context,
+                        function_name,
+                        test_info,
+                        candidate_arguments.clone(),
+                    );
+                    if let Err(failure) = result {
+                        if shrink::same_failure(&failure.0, &original_failure.0) {
+                            arguments = candidate_arguments;
+                            minimal = Some((test_run_info, failure));
+                            continue 'shrink;
+                        }
                     }
-                    stats.test_success(function_name.to_string(), test_run_info, test_plan)
                 }
             }
+            break;
+        }
+
+        match minimal {
+            Some((test_run_info, failure)) => (arguments, Some(test_run_info), Some(failure)),
+            None => (arguments, None, None),
         }
     }
 